@@ -17,6 +17,7 @@ use nymsphinx::{
     acknowledgements::AckKey,
     addressing::clients::Recipient,
     chunking::fragment::{Fragment, FragmentIdentifier},
+    params::{packet_sizes::MessagePacketSizes, PacketMode},
     preparer::MessagePreparer,
     Delay as SphinxDelay,
 };
@@ -119,6 +120,15 @@ pub(super) struct Config {
 
     /// Average delay a data packet is going to get delayed at a single mixnode.
     average_packet_delay: Duration,
+
+    /// Sizes of the packets the data can be sent in.
+    packet_sizes: MessagePacketSizes,
+
+    /// Mode in which the data packets are sent through the mix network.
+    packet_mode: PacketMode,
+
+    /// Maximum number of packets sent in the `Vpn` mode with the same keys and route.
+    vpn_key_reuse_limit: usize,
 }
 
 impl Config {
@@ -127,12 +137,18 @@ impl Config {
         ack_wait_multiplier: f64,
         average_ack_delay: Duration,
         average_packet_delay: Duration,
+        packet_sizes: MessagePacketSizes,
+        packet_mode: PacketMode,
+        vpn_key_reuse_limit: usize,
     ) -> Self {
         Config {
             ack_wait_addition,
             ack_wait_multiplier,
             average_ack_delay,
             average_packet_delay,
+            packet_sizes,
+            packet_mode,
+            vpn_key_reuse_limit,
         }
     }
 }
@@ -173,7 +189,10 @@ where
             ack_recipient,
             config.average_packet_delay,
             config.average_ack_delay,
-        );
+        )
+        .with_packet_sizes(config.packet_sizes)
+        .with_packet_mode(config.packet_mode)
+        .with_vpn_key_reuse_limit(config.vpn_key_reuse_limit);
 
         // will listen for any acks coming from the network
         let acknowledgement_listener = AcknowledgementListener::new(
@@ -19,6 +19,8 @@ use gateway_client::AcknowledgementReceiver;
 use log::*;
 use nymsphinx::acknowledgements::AckKey;
 use nymsphinx::addressing::clients::Recipient;
+use nymsphinx::params::packet_sizes::MessagePacketSizes;
+use nymsphinx::params::{PacketMode, DEFAULT_VPN_KEY_REUSE_LIMIT};
 use rand::{rngs::OsRng, CryptoRng, Rng};
 use std::sync::Arc;
 use std::time::Duration;
@@ -50,6 +52,15 @@ pub struct Config {
 
     /// Average delay an acknowledgement packet is going to get delayed at a single mixnode.
     average_ack_delay_duration: Duration,
+
+    /// Sizes of the packets the real messages can be sent in.
+    packet_sizes: MessagePacketSizes,
+
+    /// Mode in which the real messages are sent through the mix network.
+    packet_mode: PacketMode,
+
+    /// Maximum number of packets sent in the `Vpn` mode with the same keys and route.
+    vpn_key_reuse_limit: usize,
 }
 
 impl Config {
@@ -70,8 +81,26 @@ impl Config {
             average_message_sending_delay,
             average_packet_delay_duration,
             average_ack_delay_duration,
+            packet_sizes: Default::default(),
+            packet_mode: Default::default(),
+            vpn_key_reuse_limit: DEFAULT_VPN_KEY_REUSE_LIMIT,
         }
     }
+
+    /// Allows the real messages to be sent in packets of different sizes, choosing the smallest
+    /// one that fits each message.
+    pub fn with_packet_sizes(mut self, packet_sizes: MessagePacketSizes) -> Self {
+        self.packet_sizes = packet_sizes;
+        self
+    }
+
+    /// Makes the real messages get sent in the low-latency `Vpn` mode, where they are not delayed
+    /// by the mixnodes, at the cost of considerably weaker anonymity.
+    pub fn with_vpn_mode(mut self, vpn_key_reuse_limit: usize) -> Self {
+        self.packet_mode = PacketMode::Vpn;
+        self.vpn_key_reuse_limit = vpn_key_reuse_limit;
+        self
+    }
 }
 
 pub struct RealMessagesController<R>
@@ -110,6 +139,9 @@ impl RealMessagesController<OsRng> {
             config.ack_wait_multiplier,
             config.average_ack_delay_duration,
             config.average_packet_delay_duration,
+            config.packet_sizes,
+            config.packet_mode,
+            config.vpn_key_reuse_limit,
         );
 
         let ack_control = AcknowledgementController::new(
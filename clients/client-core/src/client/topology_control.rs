@@ -182,7 +182,18 @@ impl TopologyRefresher {
         // only refresh mixnodes on timer and refresh gateways only when
         // we have to send to a new, unknown, gateway
 
-        let mixnodes = match self.validator_client.get_cached_mixnodes().await {
+        let mixnodes = match self.validator_client.get_cached_active_mixnodes().await {
+            Ok(mixes) if !mixes.is_empty() => Ok(mixes),
+            // no active set has been selected yet, so the traffic is routed through all the mixnodes
+            Ok(_) => self.validator_client.get_cached_mixnodes().await,
+            Err(err) if err.is_not_found() => {
+                warn!("the validator API does not know about the active set of mixnodes - falling back to all of them");
+                self.validator_client.get_cached_mixnodes().await
+            }
+            Err(err) => Err(err),
+        };
+
+        let mixnodes = match mixnodes {
             Err(err) => {
                 error!("failed to get network mixnodes - {}", err);
                 return None;
@@ -3,6 +3,7 @@
 
 use config::defaults::*;
 use config::NymConfig;
+use nymsphinx::params::packet_sizes::MessagePacketSizes;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 use std::path::PathBuf;
@@ -210,6 +211,18 @@ impl<T: NymConfig> Config<T> {
         self.debug.topology_resolution_timeout
     }
 
+    pub fn get_packet_sizes(&self) -> MessagePacketSizes {
+        self.debug.packet_sizes.clone()
+    }
+
+    pub fn get_vpn_mode(&self) -> bool {
+        self.debug.vpn_mode
+    }
+
+    pub fn get_vpn_key_reuse_limit(&self) -> usize {
+        self.debug.vpn_key_reuse_limit
+    }
+
     pub fn get_version(&self) -> &str {
         &self.client.version
     }
@@ -393,6 +406,22 @@ pub struct Debug {
     /// did not reach its destination.
     #[serde(with = "humantime_serde")]
     topology_resolution_timeout: Duration,
+
+    /// Controls whether the real messages should be sent in the low-latency 'vpn' mode,
+    /// in which they are not delayed by the mixnodes and reuse the same keys and route
+    /// for multiple packets, so that the mixnodes could skip the expensive key derivation.
+    /// Note that it offers considerably weaker anonymity, as the packets can be correlated
+    /// by their timing and every mixnode on the route can link packets of the same session.
+    vpn_mode: bool,
+
+    /// Sizes of the sphinx packets the real messages can be sent in. Each message is put into
+    /// the smallest of them that can fit it, or split into the largest ones otherwise.
+    /// Note that the gateways and mixnodes on the route have to accept all of the chosen sizes
+    /// (they are listed on the `/packet-sizes` endpoint of their http api).
+    packet_sizes: MessagePacketSizes,
+
+    /// Number of packets sent in the 'vpn' mode after which new keys and route get chosen.
+    vpn_key_reuse_limit: usize,
 }
 
 impl Default for Debug {
@@ -407,6 +436,9 @@ impl Default for Debug {
             gateway_response_timeout: DEFAULT_GATEWAY_RESPONSE_TIMEOUT,
             topology_refresh_rate: DEFAULT_TOPOLOGY_REFRESH_RATE,
             topology_resolution_timeout: DEFAULT_TOPOLOGY_RESOLUTION_TIMEOUT,
+            vpn_mode: false,
+            packet_sizes: Default::default(),
+            vpn_key_reuse_limit: nymsphinx::params::DEFAULT_VPN_KEY_REUSE_LIMIT,
         }
     }
 }
@@ -35,7 +35,9 @@ use log::*;
 use nymsphinx::addressing::clients::Recipient;
 use nymsphinx::addressing::nodes::NodeIdentity;
 use nymsphinx::anonymous_replies::ReplySurb;
+use nymsphinx::params::PacketSize;
 use nymsphinx::receiver::ReconstructedMessage;
+use std::process;
 use tokio::runtime::Runtime;
 
 pub(crate) mod config;
@@ -121,7 +123,7 @@ impl NymClient {
         input_receiver: InputMessageReceiver,
         mix_sender: BatchMixMessageSender,
     ) {
-        let controller_config = real_messages_control::Config::new(
+        let mut controller_config = real_messages_control::Config::new(
             self.key_manager.ack_key(),
             self.config.get_base().get_ack_wait_multiplier(),
             self.config.get_base().get_ack_wait_addition(),
@@ -129,7 +131,14 @@ impl NymClient {
             self.config.get_base().get_message_sending_average_delay(),
             self.config.get_base().get_average_packet_delay(),
             self.as_mix_recipient(),
-        );
+        )
+        .with_packet_sizes(self.config.get_base().get_packet_sizes());
+
+        if self.config.get_base().get_vpn_mode() {
+            warn!("Sending messages in the 'vpn' mode - they will not be delayed by the mixnodes, which considerably weakens their anonymity!");
+            controller_config =
+                controller_config.with_vpn_mode(self.config.get_base().get_vpn_key_reuse_limit());
+        }
 
         info!("Starting real traffic stream...");
         // we need to explicitly enter runtime due to "next_delay: time::delay_for(Default::default())"
@@ -346,6 +355,13 @@ impl NymClient {
 
     pub fn start(&mut self) {
         info!("Starting nym client");
+
+        if let Err(err) = PacketSize::register_all(self.config.get_base().get_packet_sizes().iter())
+        {
+            error!("Failed to register the configured packet sizes - {}", err);
+            process::exit(1);
+        }
+
         // channels for inter-component communication
         // TODO: make the channels be internally created by the relevant components
         // rather than creating them here, so say for example the buffer controller would create the request channels
@@ -7,11 +7,16 @@ pub use client_core::config::MISSING_VALUE;
 use config::defaults::DEFAULT_SOCKS5_LISTENING_PORT;
 use config::NymConfig;
 use nymsphinx::addressing::clients::Recipient;
+use ordered_buffer::DEFAULT_MAX_BUFFERED_BYTES;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::time::Duration;
 
 mod template;
 
+const DEFAULT_PROVIDER_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_PROVIDER_PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
@@ -66,6 +71,16 @@ impl Config {
         self
     }
 
+    pub fn with_additional_provider_mix_addresses(mut self, addresses: Vec<String>) -> Self {
+        self.socks5.additional_provider_mix_addresses = addresses;
+        self
+    }
+
+    pub fn with_provider_selection(mut self, provider_selection: ProviderSelection) -> Self {
+        self.socks5.provider_selection = provider_selection;
+        self
+    }
+
     // getters
     pub fn get_config_file_save_location(&self) -> PathBuf {
         self.config_directory().join(Self::config_file_name())
@@ -76,6 +91,31 @@ impl Config {
             .expect("malformed provider address")
     }
 
+    /// Returns addresses of all configured providers, starting with the primary one.
+    pub fn get_provider_mix_addresses(&self) -> Vec<Recipient> {
+        let mut providers = vec![self.get_provider_mix_address()];
+        for address in &self.socks5.additional_provider_mix_addresses {
+            let provider = Recipient::try_from_base58_string(address)
+                .expect("malformed additional provider address");
+            if !providers.contains(&provider) {
+                providers.push(provider);
+            }
+        }
+        providers
+    }
+
+    pub fn get_provider_selection(&self) -> ProviderSelection {
+        self.socks5.provider_selection
+    }
+
+    pub fn get_provider_probe_interval(&self) -> Duration {
+        self.socks5.provider_probe_interval
+    }
+
+    pub fn get_provider_probe_timeout(&self) -> Duration {
+        self.socks5.provider_probe_timeout
+    }
+
     pub fn get_base(&self) -> &BaseConfig<Self> {
         &self.base
     }
@@ -87,6 +127,39 @@ impl Config {
     pub fn get_listening_port(&self) -> u16 {
         self.socks5.listening_port
     }
+
+    pub fn get_max_buffered_bytes_per_connection(&self) -> usize {
+        self.socks5.max_buffered_bytes_per_connection
+    }
+}
+
+fn default_max_buffered_bytes_per_connection() -> usize {
+    DEFAULT_MAX_BUFFERED_BYTES
+}
+
+fn default_provider_probe_interval() -> Duration {
+    DEFAULT_PROVIDER_PROBE_INTERVAL
+}
+
+fn default_provider_probe_timeout() -> Duration {
+    DEFAULT_PROVIDER_PROBE_TIMEOUT
+}
+
+/// Strategy used for choosing the provider for each new connection.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderSelection {
+    /// Healthy providers are used in turns.
+    RoundRobin,
+
+    /// The healthy provider with the lowest measured round trip time is used.
+    LowestRtt,
+}
+
+impl Default for ProviderSelection {
+    fn default() -> Self {
+        ProviderSelection::RoundRobin
+    }
 }
 
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
@@ -97,6 +170,28 @@ pub struct Socks5 {
 
     /// The mix address of the provider to which all requests are going to be sent.
     provider_mix_address: String,
+
+    /// Mix addresses of additional providers that can be used for new connections
+    /// if the primary one is unavailable (or not preferred).
+    #[serde(default)]
+    additional_provider_mix_addresses: Vec<String>,
+
+    /// Strategy used for choosing the provider for each new connection.
+    #[serde(default)]
+    provider_selection: ProviderSelection,
+
+    /// How often each provider is probed for its health and round trip time.
+    #[serde(with = "humantime_serde", default = "default_provider_probe_interval")]
+    provider_probe_interval: Duration,
+
+    /// How long to wait for the response to a probe before considering it as failed.
+    #[serde(with = "humantime_serde", default = "default_provider_probe_timeout")]
+    provider_probe_timeout: Duration,
+
+    /// Maximum number of bytes that can be buffered for a single connection while waiting
+    /// for out of order data to arrive. Connections going over the limit are closed.
+    #[serde(default = "default_max_buffered_bytes_per_connection")]
+    max_buffered_bytes_per_connection: usize,
 }
 
 impl Socks5 {
@@ -104,6 +199,11 @@ impl Socks5 {
         Socks5 {
             listening_port: DEFAULT_SOCKS5_LISTENING_PORT,
             provider_mix_address: provider_mix_address.into(),
+            additional_provider_mix_addresses: Vec::new(),
+            provider_selection: Default::default(),
+            provider_probe_interval: DEFAULT_PROVIDER_PROBE_INTERVAL,
+            provider_probe_timeout: DEFAULT_PROVIDER_PROBE_TIMEOUT,
+            max_buffered_bytes_per_connection: DEFAULT_MAX_BUFFERED_BYTES,
         }
     }
 }
@@ -113,6 +213,11 @@ impl Default for Socks5 {
         Socks5 {
             listening_port: DEFAULT_SOCKS5_LISTENING_PORT,
             provider_mix_address: "".into(),
+            additional_provider_mix_addresses: Vec::new(),
+            provider_selection: Default::default(),
+            provider_probe_interval: DEFAULT_PROVIDER_PROBE_INTERVAL,
+            provider_probe_timeout: DEFAULT_PROVIDER_PROBE_TIMEOUT,
+            max_buffered_bytes_per_connection: DEFAULT_MAX_BUFFERED_BYTES,
         }
     }
 }
@@ -71,9 +71,31 @@ nym_root_directory = '{{ client.nym_root_directory }}'
 # The mix address of the provider to which all requests are going to be sent.
 provider_mix_address = '{{ socks5.provider_mix_address }}'
 
+# Mix addresses of additional providers that can be used for new connections
+# if the primary one is unavailable (or not preferred).
+additional_provider_mix_addresses = [
+    {{#each socks5.additional_provider_mix_addresses }}
+        '{{this}}',
+    {{/each}}
+]
+
+# Strategy used for choosing the provider for each new connection.
+# Either 'round_robin' or 'lowest_rtt'.
+provider_selection = '{{ socks5.provider_selection }}'
+
+# How often each provider is probed for its health and round trip time.
+provider_probe_interval = '{{ socks5.provider_probe_interval }}'
+
+# How long to wait for the response to a probe before considering it as failed.
+provider_probe_timeout = '{{ socks5.provider_probe_timeout }}'
+
 # The port on which the client will be listening for incoming requests
 listening_port = {{ socks5.listening_port }}
 
+# Maximum number of bytes that can be buffered for a single connection while waiting
+# for out of order data to arrive. Connections going over the limit are closed.
+max_buffered_bytes_per_connection = {{ socks5.max_buffered_bytes_per_connection }}
+
 
 ##### logging configuration options #####
 
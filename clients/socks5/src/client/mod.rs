@@ -4,6 +4,7 @@
 use crate::client::config::Config;
 use crate::socks::{
     authentication::{AuthenticationMethods, Authenticator, User},
+    providers::ProviderPool,
     server::SphinxSocksServer,
 };
 use client_core::client::cover_traffic_stream::LoopCoverTrafficStream;
@@ -35,6 +36,8 @@ use gateway_client::{
 use log::*;
 use nymsphinx::addressing::clients::Recipient;
 use nymsphinx::addressing::nodes::NodeIdentity;
+use nymsphinx::params::PacketSize;
+use std::process;
 use tokio::runtime::Runtime;
 
 pub(crate) mod config;
@@ -109,7 +112,7 @@ impl NymClient {
         input_receiver: InputMessageReceiver,
         mix_sender: BatchMixMessageSender,
     ) {
-        let controller_config = client_core::client::real_messages_control::Config::new(
+        let mut controller_config = client_core::client::real_messages_control::Config::new(
             self.key_manager.ack_key(),
             self.config.get_base().get_ack_wait_multiplier(),
             self.config.get_base().get_ack_wait_addition(),
@@ -117,7 +120,14 @@ impl NymClient {
             self.config.get_base().get_message_sending_average_delay(),
             self.config.get_base().get_average_packet_delay(),
             self.as_mix_recipient(),
-        );
+        )
+        .with_packet_sizes(self.config.get_base().get_packet_sizes());
+
+        if self.config.get_base().get_vpn_mode() {
+            warn!("Sending messages in the 'vpn' mode - they will not be delayed by the mixnodes, which considerably weakens their anonymity!");
+            controller_config =
+                controller_config.with_vpn_mode(self.config.get_base().get_vpn_key_reuse_limit());
+        }
 
         info!("Starting real traffic stream...");
         // we need to explicitly enter runtime due to "next_delay: time::delay_for(Default::default())"
@@ -273,8 +283,14 @@ impl NymClient {
         let mut sphinx_socks = SphinxSocksServer::new(
             self.config.get_listening_port(),
             authenticator,
-            self.config.get_provider_mix_address(),
+            ProviderPool::new(
+                self.config.get_provider_mix_addresses(),
+                self.config.get_provider_selection(),
+                self.config.get_provider_probe_timeout(),
+            ),
+            self.config.get_provider_probe_interval(),
             self.as_mix_recipient(),
+            self.config.get_max_buffered_bytes_per_connection(),
         );
         self.runtime
             .spawn(async move { sphinx_socks.serve(msg_input, buffer_requester).await });
@@ -297,6 +313,13 @@ impl NymClient {
 
     pub fn start(&mut self) {
         info!("Starting nym client");
+
+        if let Err(err) = PacketSize::register_all(self.config.get_base().get_packet_sizes().iter())
+        {
+            error!("Failed to register the configured packet sizes - {}", err);
+            process::exit(1);
+        }
+
         // channels for inter-component communication
         // TODO: make the channels be internally created by the relevant components
         // rather than creating them here, so say for example the buffer controller would create the request channels
@@ -37,6 +37,17 @@ pub fn command_args<'a, 'b>() -> clap::App<'a, 'b> {
             .takes_value(true)
             .required(true)
         )
+        .arg(Arg::with_name("additional-providers")
+            .long("additional-providers")
+            .help("Comma separated list of addresses of additional socks5 providers to use for new connections.")
+            .takes_value(true)
+        )
+        .arg(Arg::with_name("provider-selection")
+            .long("provider-selection")
+            .help("Strategy for choosing provider for each new connection.")
+            .takes_value(true)
+            .possible_values(&["round_robin", "lowest_rtt"])
+        )
         .arg(Arg::with_name("gateway")
             .long("gateway")
             .help("Id of the gateway we are going to connect to.")
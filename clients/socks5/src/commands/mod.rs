@@ -1,7 +1,7 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::client::config::Config;
+use crate::client::config::{Config, ProviderSelection};
 use clap::ArgMatches;
 use url::Url;
 
@@ -20,6 +20,18 @@ fn parse_validators(raw: &str) -> Vec<Url> {
         .collect()
 }
 
+fn parse_providers(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|raw_provider| raw_provider.trim())
+        .filter(|raw_provider| !raw_provider.is_empty())
+        .map(|raw_provider| {
+            nymsphinx::addressing::clients::Recipient::try_from_base58_string(raw_provider)
+                .expect("one of the provided provider addresses is invalid");
+            raw_provider.to_owned()
+        })
+        .collect()
+}
+
 pub(crate) fn override_config(mut config: Config, matches: &ArgMatches) -> Config {
     if let Some(raw_validators) = matches.value_of("validators") {
         config
@@ -27,6 +39,22 @@ pub(crate) fn override_config(mut config: Config, matches: &ArgMatches) -> Confi
             .set_custom_validator_apis(parse_validators(raw_validators));
     }
 
+    if let Some(provider) = matches.value_of("provider") {
+        config = config.with_provider_mix_address(provider.to_owned());
+    }
+
+    if let Some(raw_providers) = matches.value_of("additional-providers") {
+        config = config.with_additional_provider_mix_addresses(parse_providers(raw_providers));
+    }
+
+    if let Some(provider_selection) = matches.value_of("provider-selection") {
+        let provider_selection = match provider_selection {
+            "lowest_rtt" => ProviderSelection::LowestRtt,
+            _ => ProviderSelection::RoundRobin,
+        };
+        config = config.with_provider_selection(provider_selection);
+    }
+
     if let Some(gateway_id) = matches.value_of("gateway") {
         config.get_base_mut().with_gateway_id(gateway_id);
     }
@@ -29,6 +29,17 @@ pub fn command_args<'a, 'b>() -> clap::App<'a, 'b> {
             .help("Address of the socks5 provider to send messages to.")
             .takes_value(true)
         )
+        .arg(Arg::with_name("additional-providers")
+            .long("additional-providers")
+            .help("Comma separated list of addresses of additional socks5 providers to use for new connections.")
+            .takes_value(true)
+        )
+        .arg(Arg::with_name("provider-selection")
+            .long("provider-selection")
+            .help("Strategy for choosing provider for each new connection.")
+            .takes_value(true)
+            .possible_values(&["round_robin", "lowest_rtt"])
+        )
         .arg(Arg::with_name("validators")
                 .long("validators")
                 .help("Comma separated list of rest endpoints of the validators")
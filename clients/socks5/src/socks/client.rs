@@ -16,7 +16,7 @@ use proxy_helpers::connection_controller::{
 };
 use proxy_helpers::proxy_runner::ProxyRunner;
 use rand::RngCore;
-use socks5_requests::{ConnectionId, RemoteAddress, Request};
+use socks5_requests::{ConnectionId, ProtocolVersion, RemoteAddress, Request};
 use std::io;
 use std::net::SocketAddr;
 use std::pin::Pin;
@@ -138,6 +138,7 @@ pub(crate) struct SocksClient {
     input_sender: InputMessageSender,
     connection_id: ConnectionId,
     service_provider: Recipient,
+    protocol_version: ProtocolVersion,
     self_address: Recipient,
     started_proxy: bool,
 }
@@ -161,6 +162,7 @@ impl SocksClient {
         authenticator: Authenticator,
         input_sender: InputMessageSender,
         service_provider: Recipient,
+        protocol_version: ProtocolVersion,
         controller_sender: ControllerSender,
         self_address: Recipient,
     ) -> Self {
@@ -174,6 +176,7 @@ impl SocksClient {
             authenticator,
             input_sender,
             service_provider,
+            protocol_version,
             self_address,
             started_proxy: false,
         }
@@ -223,7 +226,12 @@ impl SocksClient {
     }
 
     async fn send_connect_to_mixnet(&mut self, remote_address: RemoteAddress) {
-        let req = Request::new_connect(self.connection_id, remote_address, self.self_address);
+        let req = Request::new_connect(
+            self.connection_id,
+            remote_address,
+            self.self_address,
+            self.protocol_version,
+        );
 
         let input_message = InputMessage::new_fresh(self.service_provider, req.into_bytes(), false);
         self.input_sender.unbounded_send(input_message).unwrap();
@@ -249,9 +257,11 @@ impl SocksClient {
             conn_receiver,
             input_sender,
             connection_id,
+            self.protocol_version,
         )
-        .run(move |conn_id, read_data, socket_closed| {
-            let provider_request = Request::new_send(conn_id, read_data, socket_closed);
+        .run(move |conn_id, read_data, socket_closed, receive_window| {
+            let provider_request =
+                Request::new_send(conn_id, read_data, socket_closed, receive_window);
             InputMessage::new_fresh(recipient, provider_request.into_bytes(), false)
         })
         .await
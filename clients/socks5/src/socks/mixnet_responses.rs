@@ -1,3 +1,4 @@
+use crate::socks::providers::ProviderPool;
 use client_core::client::received_buffer::ReconstructedMessagesReceiver;
 use client_core::client::received_buffer::{ReceivedBufferMessage, ReceivedBufferRequestSender};
 use futures::channel::mpsc;
@@ -11,6 +12,7 @@ pub(crate) struct MixnetResponseListener {
     buffer_requester: ReceivedBufferRequestSender,
     mix_response_receiver: ReconstructedMessagesReceiver,
     controller_sender: ControllerSender,
+    provider_pool: ProviderPool,
 }
 
 impl Drop for MixnetResponseListener {
@@ -25,6 +27,7 @@ impl MixnetResponseListener {
     pub(crate) fn new(
         buffer_requester: ReceivedBufferRequestSender,
         controller_sender: ControllerSender,
+        provider_pool: ProviderPool,
     ) -> Self {
         let (mix_response_sender, mix_response_receiver) = mpsc::unbounded();
         buffer_requester
@@ -35,6 +38,7 @@ impl MixnetResponseListener {
             buffer_requester,
             mix_response_receiver,
             controller_sender,
+            provider_pool,
         }
     }
 
@@ -52,11 +56,30 @@ impl MixnetResponseListener {
             Ok(data) => data,
         };
 
+        if response.is_pong {
+            // for pongs, the connection id field holds the id of the probe
+            self.provider_pool
+                .record_pong(response.connection_id, response.protocol_version);
+            return;
+        }
+
+        if let Some(error) = response.error {
+            warn!(
+                "the service provider refused to handle connection {} - {}",
+                response.connection_id, error
+            );
+            self.controller_sender
+                .unbounded_send(ControllerCommand::Close(response.connection_id))
+                .unwrap();
+            return;
+        }
+
         self.controller_sender
             .unbounded_send(ControllerCommand::Send(
                 response.connection_id,
                 response.data,
                 response.is_closed,
+                response.receive_window,
             ))
             .unwrap();
     }
@@ -3,6 +3,7 @@
 pub mod authentication;
 mod client;
 pub(crate) mod mixnet_responses;
+pub(crate) mod providers;
 mod request;
 pub mod server;
 pub mod types;
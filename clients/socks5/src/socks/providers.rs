@@ -0,0 +1,357 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::client::config::ProviderSelection;
+use client_core::client::inbound_messages::{InputMessage, InputMessageSender};
+use log::*;
+use nymsphinx::addressing::clients::Recipient;
+use rand::RngCore;
+use socks5_requests::{ProtocolVersion, Request};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Number of consecutive unanswered probes after which the provider is no longer used
+/// for new connections.
+const MAX_CONSECUTIVE_FAILURES: usize = 2;
+
+struct ProviderState {
+    address: Recipient,
+    rtt: Option<Duration>,
+
+    /// Probes that haven't been answered yet alongside the time they were sent at.
+    outstanding_probes: HashMap<u64, Instant>,
+    consecutive_failures: usize,
+
+    /// Protocol version agreed on with the provider based on its pongs. Until we hear back
+    /// from it, we must assume it might not understand anything beyond the legacy protocol.
+    protocol_version: ProtocolVersion,
+}
+
+impl ProviderState {
+    fn new(address: Recipient) -> Self {
+        ProviderState {
+            address,
+            rtt: None,
+            outstanding_probes: HashMap::new(),
+            consecutive_failures: 0,
+            protocol_version: ProtocolVersion::Legacy,
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.consecutive_failures < MAX_CONSECUTIVE_FAILURES
+    }
+
+    /// Removes probes that haven't been answered within the timeout, counting each of them
+    /// as a failure.
+    fn expire_probes(&mut self, now: Instant, probe_timeout: Duration) {
+        let before = self.outstanding_probes.len();
+        self.outstanding_probes
+            .retain(|_, sent| now.duration_since(*sent) < probe_timeout);
+        let expired = before - self.outstanding_probes.len();
+        if expired == 0 {
+            return;
+        }
+
+        let was_healthy = self.is_healthy();
+        self.consecutive_failures += expired;
+        if was_healthy && !self.is_healthy() {
+            warn!(
+                "provider {} has not responded to {} consecutive probes - it will not be used for new connections",
+                self.address, self.consecutive_failures
+            );
+        }
+    }
+}
+
+struct ProviderPoolInner {
+    selection: ProviderSelection,
+    probe_timeout: Duration,
+    providers: Vec<ProviderState>,
+    next: usize,
+}
+
+impl ProviderPoolInner {
+    fn new(
+        providers: Vec<Recipient>,
+        selection: ProviderSelection,
+        probe_timeout: Duration,
+    ) -> Self {
+        assert!(
+            !providers.is_empty(),
+            "at least a single provider must be specified"
+        );
+        ProviderPoolInner {
+            selection,
+            probe_timeout,
+            providers: providers.into_iter().map(ProviderState::new).collect(),
+            next: 0,
+        }
+    }
+
+    fn choose_provider(&mut self) -> (Recipient, ProtocolVersion) {
+        let any_healthy = self.providers.iter().any(ProviderState::is_healthy);
+        // if everything seems to be down, there's no point in refusing the connection,
+        // perhaps it's our probes that are getting lost
+        let candidates = self
+            .providers
+            .iter()
+            .enumerate()
+            .filter(|(_, provider)| !any_healthy || provider.is_healthy());
+
+        match self.selection {
+            ProviderSelection::RoundRobin => {
+                let len = self.providers.len();
+                let next = self.next;
+                let (index, provider) = candidates
+                    .min_by_key(|(index, _)| (index + len - next) % len)
+                    .unwrap();
+                self.next = (index + 1) % len;
+                (provider.address, provider.protocol_version)
+            }
+            ProviderSelection::LowestRtt => {
+                let (_, provider) = candidates
+                    // providers we haven't yet heard from go last
+                    .min_by_key(|(_, provider)| (provider.rtt.is_none(), provider.rtt))
+                    .unwrap();
+                (provider.address, provider.protocol_version)
+            }
+        }
+    }
+
+    fn start_probe(&mut self, index: usize, probe_id: u64, now: Instant) {
+        let probe_timeout = self.probe_timeout;
+        let provider = &mut self.providers[index];
+        provider.expire_probes(now, probe_timeout);
+        provider.outstanding_probes.insert(probe_id, now);
+    }
+
+    fn record_pong(&mut self, probe_id: u64, protocol_version: ProtocolVersion, now: Instant) {
+        for provider in self.providers.iter_mut() {
+            if let Some(sent) = provider.outstanding_probes.remove(&probe_id) {
+                let rtt = now.duration_since(sent);
+                debug!("provider {} responded in {:?}", provider.address, rtt);
+                if !provider.is_healthy() {
+                    info!("provider {} is available again", provider.address);
+                }
+                provider.rtt = Some(rtt);
+                provider.protocol_version = ProtocolVersion::negotiate(protocol_version);
+                provider.consecutive_failures = 0;
+                return;
+            }
+        }
+        debug!(
+            "received pong for an unknown (or expired) probe {}",
+            probe_id
+        );
+    }
+}
+
+/// Set of service providers available to the client alongside their observed health.
+// Note: you should NEVER create more than a single instance of this using 'new()'.
+// You should always use .clone() to create additional instances
+#[derive(Clone)]
+pub(crate) struct ProviderPool {
+    inner: Arc<Mutex<ProviderPoolInner>>,
+}
+
+impl ProviderPool {
+    pub(crate) fn new(
+        providers: Vec<Recipient>,
+        selection: ProviderSelection,
+        probe_timeout: Duration,
+    ) -> Self {
+        ProviderPool {
+            inner: Arc::new(Mutex::new(ProviderPoolInner::new(
+                providers,
+                selection,
+                probe_timeout,
+            ))),
+        }
+    }
+
+    /// Chooses the provider that should be used for a new connection alongside
+    /// the protocol version that should be used with it.
+    pub(crate) fn choose_provider(&self) -> (Recipient, ProtocolVersion) {
+        self.inner.lock().unwrap().choose_provider()
+    }
+
+    /// Marks the probe as answered, updating the round trip time and protocol version
+    /// of its provider.
+    pub(crate) fn record_pong(&self, probe_id: u64, protocol_version: ProtocolVersion) {
+        self.inner
+            .lock()
+            .unwrap()
+            .record_pong(probe_id, protocol_version, Instant::now())
+    }
+
+    fn start_probes(&self, rng: &mut impl RngCore) -> Vec<(Recipient, u64)> {
+        let mut inner = self.inner.lock().unwrap();
+        let now = Instant::now();
+        (0..inner.providers.len())
+            .map(|index| {
+                let probe_id = rng.next_u64();
+                inner.start_probe(index, probe_id, now);
+                (inner.providers[index].address, probe_id)
+            })
+            .collect()
+    }
+}
+
+/// Periodically pings all the providers in the pool to determine their health and latency.
+pub(crate) struct ProviderProber {
+    pool: ProviderPool,
+    input_sender: InputMessageSender,
+    self_address: Recipient,
+    probe_interval: Duration,
+}
+
+impl ProviderProber {
+    pub(crate) fn new(
+        pool: ProviderPool,
+        input_sender: InputMessageSender,
+        self_address: Recipient,
+        probe_interval: Duration,
+    ) -> Self {
+        ProviderProber {
+            pool,
+            input_sender,
+            self_address,
+            probe_interval,
+        }
+    }
+
+    pub(crate) async fn run(&mut self) {
+        let mut rng = rand::rngs::OsRng;
+        let mut interval = tokio::time::interval(self.probe_interval);
+        loop {
+            interval.tick().await;
+            for (provider, probe_id) in self.pool.start_probes(&mut rng) {
+                trace!("probing provider {} with {}", provider, probe_id);
+                let ping = Request::new_ping(probe_id, self.self_address);
+                let input_message = InputMessage::new_fresh(provider, ping.into_bytes(), false);
+                if self.input_sender.unbounded_send(input_message).is_err() {
+                    error!("failed to send provider probe - the client is shutting down");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn test_provider(name: &str) -> Recipient {
+        let raw = match name {
+            "a" => "CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f",
+            _ => "2fBnkcxL8Trj3BTT3hGP7Y7rqzQWq7bpC9QhXQLeQjuh.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f",
+        };
+        Recipient::try_from_base58_string(raw).unwrap()
+    }
+
+    #[test]
+    fn round_robin_skips_unhealthy_providers() {
+        let (a, b) = (test_provider("a"), test_provider("b"));
+        let mut pool = ProviderPoolInner::new(vec![a, b], ProviderSelection::RoundRobin, TIMEOUT);
+        assert_eq!(a, pool.choose_provider().0);
+        assert_eq!(b, pool.choose_provider().0);
+        assert_eq!(a, pool.choose_provider().0);
+
+        let now = Instant::now();
+        for probe_id in 0..MAX_CONSECUTIVE_FAILURES as u64 + 1 {
+            pool.start_probe(0, probe_id, now + TIMEOUT * probe_id as u32);
+        }
+        assert_eq!(b, pool.choose_provider().0);
+        assert_eq!(b, pool.choose_provider().0);
+
+        let probe_id = MAX_CONSECUTIVE_FAILURES as u64;
+        let answered = now + TIMEOUT * probe_id as u32;
+        pool.record_pong(probe_id, ProtocolVersion::V1, answered);
+        assert_eq!(a, pool.choose_provider().0);
+    }
+
+    #[test]
+    fn lowest_rtt_prefers_fastest_provider() {
+        let (a, b) = (test_provider("a"), test_provider("b"));
+        let mut pool = ProviderPoolInner::new(vec![a, b], ProviderSelection::LowestRtt, TIMEOUT);
+
+        let now = Instant::now();
+        pool.start_probe(0, 1, now);
+        pool.start_probe(1, 2, now);
+        pool.record_pong(2, ProtocolVersion::V1, now + Duration::from_millis(100));
+        // provider we haven't heard back from yet is not preferred
+        assert_eq!(b, pool.choose_provider().0);
+
+        pool.record_pong(1, ProtocolVersion::V1, now + Duration::from_millis(50));
+        assert_eq!(a, pool.choose_provider().0);
+        assert_eq!(a, pool.choose_provider().0);
+    }
+
+    #[test]
+    fn uses_legacy_protocol_until_provider_announces_its_version() {
+        let a = test_provider("a");
+        let mut pool = ProviderPoolInner::new(vec![a], ProviderSelection::RoundRobin, TIMEOUT);
+        assert_eq!((a, ProtocolVersion::Legacy), pool.choose_provider());
+
+        let now = Instant::now();
+        pool.start_probe(0, 1, now);
+        pool.record_pong(1, ProtocolVersion::V1, now);
+        assert_eq!((a, ProtocolVersion::V1), pool.choose_provider());
+    }
+
+    #[test]
+    fn falls_back_to_all_providers_if_none_is_healthy() {
+        let (a, b) = (test_provider("a"), test_provider("b"));
+        let mut pool = ProviderPoolInner::new(vec![a, b], ProviderSelection::RoundRobin, TIMEOUT);
+
+        let now = Instant::now();
+        for probe_id in 0..MAX_CONSECUTIVE_FAILURES as u64 + 1 {
+            let sent = now + TIMEOUT * probe_id as u32;
+            pool.start_probe(0, probe_id * 2, sent);
+            pool.start_probe(1, probe_id * 2 + 1, sent);
+        }
+        assert_eq!(a, pool.choose_provider().0);
+        assert_eq!(b, pool.choose_provider().0);
+    }
+
+    #[test]
+    fn outstanding_probes_are_not_failures_until_they_time_out() {
+        let (a, b) = (test_provider("a"), test_provider("b"));
+        let mut pool = ProviderPoolInner::new(vec![a, b], ProviderSelection::RoundRobin, TIMEOUT);
+
+        // probes are sent more often than the provider manages to respond to them
+        let now = Instant::now();
+        let interval = TIMEOUT / 4;
+        for probe_id in 0..4 {
+            pool.start_probe(0, probe_id, now + interval * probe_id as u32);
+        }
+        assert!(pool.providers[0].is_healthy());
+
+        // so the pong to the first one arrives only after more probes got sent
+        pool.record_pong(0, ProtocolVersion::V1, now + interval * 3);
+        assert_eq!(Some(interval * 3), pool.providers[0].rtt);
+        pool.record_pong(1, ProtocolVersion::V1, now + interval * 4);
+        assert_eq!(Some(interval * 3), pool.providers[0].rtt);
+        assert!(pool.providers[0].is_healthy());
+    }
+
+    #[test]
+    fn pongs_to_expired_probes_are_ignored() {
+        let a = test_provider("a");
+        let mut pool = ProviderPoolInner::new(vec![a], ProviderSelection::RoundRobin, TIMEOUT);
+
+        let now = Instant::now();
+        pool.start_probe(0, 1, now);
+        pool.start_probe(0, 2, now + TIMEOUT);
+        assert_eq!(1, pool.providers[0].consecutive_failures);
+
+        pool.record_pong(1, ProtocolVersion::V1, now + TIMEOUT);
+        assert_eq!(1, pool.providers[0].consecutive_failures);
+        assert!(pool.providers[0].rtt.is_none());
+    }
+}
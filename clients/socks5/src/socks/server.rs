@@ -2,6 +2,7 @@ use super::authentication::Authenticator;
 use super::client::SocksClient;
 use super::{
     mixnet_responses::MixnetResponseListener,
+    providers::{ProviderPool, ProviderProber},
     types::{ResponseCode, SocksProxyError},
 };
 use client_core::client::{
@@ -11,14 +12,17 @@ use log::*;
 use nymsphinx::addressing::clients::Recipient;
 use proxy_helpers::connection_controller::Controller;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tokio::net::TcpListener;
 
 /// A Socks5 server that listens for connections.
 pub struct SphinxSocksServer {
     authenticator: Authenticator,
     listening_address: SocketAddr,
-    service_provider: Recipient,
+    provider_pool: ProviderPool,
+    provider_probe_interval: Duration,
     self_address: Recipient,
+    max_buffered_bytes_per_connection: usize,
 }
 
 impl SphinxSocksServer {
@@ -26,8 +30,10 @@ impl SphinxSocksServer {
     pub(crate) fn new(
         port: u16,
         authenticator: Authenticator,
-        service_provider: Recipient,
+        provider_pool: ProviderPool,
+        provider_probe_interval: Duration,
         self_address: Recipient,
+        max_buffered_bytes_per_connection: usize,
     ) -> Self {
         // hardcode ip as we (presumably) ONLY want to listen locally. If we change it, we can
         // just modify the config
@@ -36,8 +42,10 @@ impl SphinxSocksServer {
         SphinxSocksServer {
             authenticator,
             listening_address: format!("{}:{}", ip, port).parse().unwrap(),
-            service_provider,
+            provider_pool,
+            provider_probe_interval,
             self_address,
+            max_buffered_bytes_per_connection,
         }
     }
 
@@ -52,27 +60,45 @@ impl SphinxSocksServer {
         info!("Serving Connections...");
 
         // controller for managing all active connections
-        let (mut active_streams_controller, controller_sender) = Controller::new();
+        let (mut active_streams_controller, controller_sender) =
+            Controller::new(self.max_buffered_bytes_per_connection);
         tokio::spawn(async move {
             active_streams_controller.run().await;
         });
 
         // listener for mix messages
-        let mut mixnet_response_listener =
-            MixnetResponseListener::new(buffer_requester, controller_sender.clone());
+        let mut mixnet_response_listener = MixnetResponseListener::new(
+            buffer_requester,
+            controller_sender.clone(),
+            self.provider_pool.clone(),
+        );
 
         tokio::spawn(async move {
             mixnet_response_listener.run().await;
         });
 
+        // health and latency checks of all known providers
+        let mut provider_prober = ProviderProber::new(
+            self.provider_pool.clone(),
+            input_sender.clone(),
+            self.self_address,
+            self.provider_probe_interval,
+        );
+
+        tokio::spawn(async move {
+            provider_prober.run().await;
+        });
+
         loop {
             if let Ok((stream, _remote)) = listener.accept().await {
                 // TODO Optimize this
+                let (provider, protocol_version) = self.provider_pool.choose_provider();
                 let mut client = SocksClient::new(
                     stream,
                     self.authenticator.clone(),
                     input_sender.clone(),
-                    self.service_provider,
+                    provider,
+                    protocol_version,
                     controller_sender.clone(),
                     self.self_address,
                 );
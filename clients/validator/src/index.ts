@@ -281,9 +281,9 @@ export default class ValidatorClient {
     /**
      *  Announce a mixnode, paying a fee.
      */
-    async bondMixnode(mixNode: MixNode, bond: Coin): Promise<ExecuteResult> {
+    async bondMixnode(mixNode: MixNode, profitMarginPercent: number, bond: Coin): Promise<ExecuteResult> {
         if (this.client instanceof NetClient) {
-            const result = await this.client.executeContract(this.client.clientAddress, this.contractAddress, {bond_mixnode: {mix_node: mixNode}}, "adding mixnode", [bond]).catch((err) => this.handleRequestFailure(err));
+            const result = await this.client.executeContract(this.client.clientAddress, this.contractAddress, {bond_mixnode: {mix_node: mixNode, profit_margin_percent: profitMarginPercent}}, "adding mixnode", [bond]).catch((err) => this.handleRequestFailure(err));
             console.log(`account ${this.client.clientAddress} added mixnode with ${mixNode.host}`);
             return result;
         } else {
@@ -337,6 +337,33 @@ export default class ValidatorClient {
         }
     }
 
+    /**
+     * Claims the unbonded stake and removed delegations whose unbonding period is already over.
+     * At most `limit` of the oldest pending withdrawals are looked at.
+     */
+    async claimPendingWithdrawals(limit?: number): Promise<ExecuteResult> {
+        if (this.client instanceof NetClient) {
+            const result = await this.client.executeContract(this.client.clientAddress, this.contractAddress, {claim_pending_withdrawals: {limit}}).catch((err) => this.handleRequestFailure(err))
+            console.log(`account ${this.client.clientAddress} claimed its pending withdrawals`);
+            return result;
+        } else {
+            throw new Error("Tried to claim pending withdrawals with a query client")
+        }
+    }
+
+    /**
+     * Releases the mixnode owned by the client from jail, so that it could be selected for the active set again.
+     */
+    async unjailMixnode(): Promise<ExecuteResult> {
+        if (this.client instanceof NetClient) {
+            const result = await this.client.executeContract(this.client.clientAddress, this.contractAddress, {unjail_mixnode: {}}).catch((err) => this.handleRequestFailure(err))
+            console.log(`account ${this.client.clientAddress} unjailed its mixnode`);
+            return result;
+        } else {
+            throw new Error("Tried to unjail mixnode with a query client")
+        }
+    }
+
     /**
      * Delegates specified amount of stake to particular gateway.
      *
@@ -461,11 +488,25 @@ export default class ValidatorClient {
         }
     }
 
-    async updateStateParams(newParams: StateParams): Promise<ExecuteResult> {
+    /**
+     * Proposes new state params. They are applied once enough governance members approve them and the timelock expires.
+     */
+    async proposeStateParams(newParams: StateParams, description: string): Promise<ExecuteResult> {
         if (this.client instanceof NetClient) {
-            return await this.client.executeContract(this.client.clientAddress, this.contractAddress, {update_state_params: newParams}, "updating contract state").catch((err) => this.handleRequestFailure(err));
+            return await this.client.executeContract(this.client.clientAddress, this.contractAddress, {propose_state_params: {params: newParams, description}}, "proposing contract state").catch((err) => this.handleRequestFailure(err));
         } else {
-            throw new Error("Tried to update state params with a query client")
+            throw new Error("Tried to propose state params with a query client")
+        }
+    }
+
+    /**
+     * Proposes new governance members, threshold or periods. They are applied the same way as the state params proposals.
+     */
+    async proposeGovernanceConfig(newConfig: GovernanceConfig, description: string): Promise<ExecuteResult> {
+        if (this.client instanceof NetClient) {
+            return await this.client.executeContract(this.client.clientAddress, this.contractAddress, {propose_governance_config: {config: newConfig, description}}, "proposing governance config").catch((err) => this.handleRequestFailure(err));
+        } else {
+            throw new Error("Tried to propose governance config with a query client")
         }
     }
 
@@ -673,6 +714,20 @@ export type StateParams = {
     mixnode_delegation_reward_rate: string,
     gateway_delegation_reward_rate: string,
     mixnode_active_set_size: number,
+    unbonding_period: number,
+    minimum_mixnode_uptime: number,
+    mixnode_jail_epochs: number,
+    mixnode_slashing_fraction: string,
+}
+
+export type GovernanceConfig = {
+    members: string[],
+    // has to be a majority of the members
+    threshold: number,
+    // all periods are expressed in hours
+    voting_period: number,
+    timelock: number,
+    execution_period: number,
 }
 
 export type Delegation = {
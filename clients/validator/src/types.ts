@@ -11,6 +11,10 @@ export type MixNodeBond = { // TODO: change name to MixNodeBond
     owner: string,
     mix_node: MixNode,    // TODO: camelCase this later once everything else works
     layer: Layer,
+    profit_margin_percent: number,
+    family: string | null,
+    jailed: boolean,
+    jailed_until: number,
     bond_amount: Coin,
     total_delegation: Coin,
 }
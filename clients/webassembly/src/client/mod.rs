@@ -263,7 +263,17 @@ impl NymClient {
     pub(crate) async fn get_nym_topology(&self) -> NymTopology {
         let validator_client = validator_client::ApiClient::new(self.validator_server.clone());
 
-        let mixnodes = match validator_client.get_cached_mixnodes().await {
+        let mixnodes = match validator_client.get_cached_active_mixnodes().await {
+            Ok(mixes) if !mixes.is_empty() => Ok(mixes),
+            // no active set has been selected yet, so the traffic is routed through all the mixnodes
+            Ok(_) => validator_client.get_cached_mixnodes().await,
+            Err(err) if err.is_not_found() => {
+                console_warn!("the validator API does not know about the active set of mixnodes - falling back to all of them");
+                validator_client.get_cached_mixnodes().await
+            }
+            Err(err) => Err(err),
+        };
+        let mixnodes = match mixnodes {
             Err(err) => panic!("{}", err),
             Ok(mixes) => mixes,
         };
@@ -44,15 +44,13 @@ impl PacketRouter {
         for received_packet in unwrapped_packets {
             if received_packet.len() == PacketSize::AckPacket.plaintext_size() {
                 received_acks.push(received_packet);
-            } else if received_packet.len()
-                == PacketSize::RegularPacket.plaintext_size() - ack_overhead
+            } else if PacketSize::all()
+                .filter(|packet_size| packet_size.is_message_packet())
+                .any(|packet_size| {
+                    received_packet.len() == packet_size.plaintext_size() - ack_overhead
+                })
             {
                 received_messages.push(received_packet);
-            } else if received_packet.len()
-                == PacketSize::ExtendedPacket.plaintext_size() - ack_overhead
-            {
-                warn!("received extended packet? Did not expect this...");
-                received_messages.push(received_packet);
             } else {
                 // this can happen if other clients are not padding their messages
                 warn!("Received message of unexpected size. Probably from an outdated client... len: {}", received_packet.len());
@@ -1,28 +1,44 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::peers::TopologyPeers;
+use crate::stats::{ConnectionCounters, ConnectionPoolStats};
 use futures::channel::mpsc;
+use futures::future;
 use futures::StreamExt;
 use log::*;
-use nymsphinx::framing::codec::SphinxCodec;
+use nymnoise::{Negotiated, NoiseConfig, NoiseError, NoiseMode};
 use nymsphinx::framing::packet::FramedSphinxPacket;
 use nymsphinx::params::PacketMode;
 use nymsphinx::{addressing::nodes::NymNodeRoutingAddress, SphinxPacket};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::net::TcpStream;
-use tokio::time::sleep;
+use tokio::time::{sleep, Instant};
 use tokio_util::codec::Framed;
 
+// how often we check whether all connections got closed when shutting down
+const CLOSING_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the owner of the client is expected to call `maintain_connections`.
+pub const CONNECTION_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(30);
+
+const DEFAULT_IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const DEFAULT_MAXIMUM_CONNECTIONS: usize = 1024;
+
 pub struct Config {
     initial_reconnection_backoff: Duration,
     maximum_reconnection_backoff: Duration,
     initial_connection_timeout: Duration,
     maximum_connection_buffer_size: usize,
+    noise_config: NoiseConfig,
+    idle_connection_timeout: Duration,
+    maximum_connections: usize,
+    topology_peers: Option<TopologyPeers>,
 }
 
 impl Config {
@@ -31,24 +47,61 @@ impl Config {
         maximum_reconnection_backoff: Duration,
         initial_connection_timeout: Duration,
         maximum_connection_buffer_size: usize,
+        noise_config: NoiseConfig,
     ) -> Self {
         Config {
             initial_reconnection_backoff,
             maximum_reconnection_backoff,
             initial_connection_timeout,
             maximum_connection_buffer_size,
+            noise_config,
+            idle_connection_timeout: DEFAULT_IDLE_CONNECTION_TIMEOUT,
+            maximum_connections: DEFAULT_MAXIMUM_CONNECTIONS,
+            topology_peers: None,
         }
     }
+
+    /// Connections that were not used for this long get closed on the next maintenance.
+    pub fn with_idle_connection_timeout(mut self, idle_connection_timeout: Duration) -> Self {
+        self.idle_connection_timeout = idle_connection_timeout;
+        self
+    }
+
+    /// Maximum number of connections kept in the pool. If exceeded, the least recently used one
+    /// gets closed. If set to 0, the number of connections is not limited.
+    pub fn with_maximum_connections(mut self, maximum_connections: usize) -> Self {
+        self.maximum_connections = maximum_connections;
+        self
+    }
+
+    /// Topology used for closing connections to nodes that left the network and for
+    /// pre-connecting to the nodes in the next layer.
+    pub fn with_topology_peers(mut self, topology_peers: TopologyPeers) -> Self {
+        self.topology_peers = Some(topology_peers);
+        self
+    }
 }
 
 pub struct Client {
     conn_new: HashMap<NymNodeRoutingAddress, ConnectionSender>,
     config: Config,
+    stats: ConnectionPoolStats,
+    // number of spawned connection managers, regardless of whether they managed to connect
+    running_managers: Arc<AtomicUsize>,
+    // number of packets buffered on any of the connections that have not yet been sent
+    pending_packets: Arc<AtomicUsize>,
 }
 
 struct ConnectionSender {
     channel: mpsc::Sender<FramedSphinxPacket>,
     current_reconnection_attempt: Arc<AtomicU32>,
+    // set if the remote turned out to not understand the transport preamble
+    is_legacy_peer: Arc<AtomicBool>,
+    counters: Arc<ConnectionCounters>,
+    last_used: Instant,
+    // set if the remote belongs to the next layer of the topology, in which case the connection
+    // is never considered idle
+    persistent: bool,
 }
 
 impl ConnectionSender {
@@ -56,6 +109,10 @@ impl ConnectionSender {
         ConnectionSender {
             channel,
             current_reconnection_attempt: Arc::new(AtomicU32::new(0)),
+            is_legacy_peer: Arc::new(AtomicBool::new(false)),
+            counters: Arc::new(ConnectionCounters::default()),
+            last_used: Instant::now(),
+            persistent: false,
         }
     }
 }
@@ -65,31 +122,121 @@ impl Client {
         Client {
             conn_new: HashMap::new(),
             config,
+            stats: ConnectionPoolStats::new(),
+            running_managers: Arc::new(AtomicUsize::new(0)),
+            pending_packets: Arc::new(AtomicUsize::new(0)),
         }
     }
 
-    async fn manage_connection(
+    /// Returns the number of currently established outbound connections.
+    pub fn active_connections(&self) -> usize {
+        self.stats.active_connections()
+    }
+
+    /// Returns a handle to the statistics of all the pooled connections.
+    pub fn pool_stats(&self) -> ConnectionPoolStats {
+        self.stats.clone()
+    }
+
+    fn is_at_capacity(&self) -> bool {
+        self.config.maximum_connections != 0
+            && self.conn_new.len() >= self.config.maximum_connections
+    }
+
+    fn remove_connection(&mut self, address: &NymNodeRoutingAddress) {
+        // dropping the sender makes the connection manager finish once its channel is empty
+        self.conn_new.remove(address);
+        self.stats.remove(address);
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let least_recently_used = self
+            .conn_new
+            .iter()
+            .min_by_key(|(_, sender)| (sender.persistent, sender.last_used))
+            .map(|(address, _)| *address);
+
+        if let Some(address) = least_recently_used {
+            debug!(
+                "closing connection to {} as the connection limit got reached",
+                address
+            );
+            self.remove_connection(&address);
+        }
+    }
+
+    /// Closes connections that were idle for too long or whose remotes are no longer part
+    /// of the topology and establishes connections to all the nodes in the next layer.
+    /// It should be called periodically by the owner of the client.
+    pub fn maintain_connections(&mut self) {
+        let (known, next_layer) = match &self.config.topology_peers {
+            Some(topology_peers) => topology_peers.snapshot(),
+            None => (HashSet::new(), Vec::new()),
+        };
+
+        // if we don't know the topology (yet), leave the existing connections alone
+        if !next_layer.is_empty() {
+            for (address, sender) in self.conn_new.iter_mut() {
+                sender.persistent = next_layer.contains(address);
+            }
+        }
+
+        let now = Instant::now();
+        let idle_connection_timeout = self.config.idle_connection_timeout;
+        let stale = self
+            .conn_new
+            .iter()
+            .filter(|(address, sender)| {
+                let left_topology = !known.is_empty() && !known.contains(address);
+                let is_idle = !sender.persistent
+                    && now.duration_since(sender.last_used) >= idle_connection_timeout;
+                left_topology || is_idle
+            })
+            .map(|(address, _)| *address)
+            .collect::<Vec<_>>();
+
+        for address in stale {
+            debug!("closing connection to {} as it's no longer needed", address);
+            self.remove_connection(&address);
+        }
+
+        for address in next_layer {
+            let needs_connection = match self.conn_new.get(&address) {
+                Some(sender) => sender.channel.is_closed(),
+                None if self.is_at_capacity() => continue,
+                None => true,
+            };
+            if needs_connection {
+                debug!("pre-connecting to {}", address);
+                self.make_connection(address, None);
+                if let Some(sender) = self.conn_new.get_mut(&address) {
+                    sender.persistent = true;
+                }
+            }
+        }
+    }
+
+    async fn connect(
         address: SocketAddr,
-        receiver: mpsc::Receiver<FramedSphinxPacket>,
         connection_timeout: Duration,
         current_reconnection: &AtomicU32,
-    ) {
+    ) -> Option<TcpStream> {
         let connection_fut = TcpStream::connect(address);
 
-        let conn = match tokio::time::timeout(connection_timeout, connection_fut).await {
+        match tokio::time::timeout(connection_timeout, connection_fut).await {
             Ok(stream_res) => match stream_res {
                 Ok(stream) => {
                     debug!("Managed to establish connection to {}", address);
                     // if we managed to connect, reset the reconnection count (whatever it might have been)
                     current_reconnection.store(0, Ordering::Release);
-                    Framed::new(stream, SphinxCodec)
+                    Some(stream)
                 }
                 Err(err) => {
                     debug!(
                         "failed to establish connection to {} (err: {})",
                         address, err
                     );
-                    return;
+                    None
                 }
             },
             Err(_) => {
@@ -99,17 +246,129 @@ impl Client {
                 );
 
                 // we failed to connect - increase reconnection attempt
+                current_reconnection.fetch_add(1, Ordering::SeqCst);
+                None
+            }
+        }
+    }
+
+    /// Closes all the connections once the packets already buffered on them get sent.
+    /// Waits until that happens or until the deadline is reached and returns the number of packets
+    /// that did not get sent.
+    pub async fn close_connections(&mut self, deadline: Instant) -> usize {
+        // dropping the senders makes the connection managers finish once their channels are empty
+        let addresses = self.conn_new.keys().copied().collect::<Vec<_>>();
+        for address in addresses {
+            self.remove_connection(&address);
+        }
+
+        while self.running_managers.load(Ordering::Acquire) > 0 && Instant::now() < deadline {
+            sleep(CLOSING_CHECK_INTERVAL).await;
+        }
+
+        self.pending_packets.load(Ordering::Acquire)
+    }
+
+    fn discard_unsent(
+        receiver: &mut mpsc::Receiver<FramedSphinxPacket>,
+        pending_packets: &AtomicUsize,
+        counters: &ConnectionCounters,
+    ) {
+        receiver.close();
+        while let Ok(Some(_)) = receiver.try_next() {
+            pending_packets.fetch_sub(1, Ordering::AcqRel);
+            counters.decrement_queued();
+            counters.report_dropped();
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn manage_connection(
+        address: SocketAddr,
+        receiver: &mut mpsc::Receiver<FramedSphinxPacket>,
+        connection_timeout: Duration,
+        noise_config: &NoiseConfig,
+        current_reconnection: &AtomicU32,
+        is_legacy_peer: &AtomicBool,
+        active_connections: &AtomicUsize,
+        pending_packets: &AtomicUsize,
+        counters: &ConnectionCounters,
+    ) {
+        let mut stream =
+            match Self::connect(address, connection_timeout, current_reconnection).await {
+                Some(stream) => stream,
+                None => return,
+            };
+
+        let negotiated = if is_legacy_peer.load(Ordering::Acquire) {
+            Self::negotiate_legacy(address, noise_config)
+        } else {
+            match nymnoise::upgrade_outbound(&mut stream, noise_config, address).await {
+                Ok(negotiated) => Some(negotiated),
+                Err(NoiseError::LegacyPeer) if noise_config.mode() != NoiseMode::Required => {
+                    debug!("{} does not support the transport preamble", address);
+                    is_legacy_peer.store(true, Ordering::Release);
+                    // the previous connection got closed by the remote so we have to establish a new one
+                    stream = match Self::connect(address, connection_timeout, current_reconnection)
+                        .await
+                    {
+                        Some(stream) => stream,
+                        None => return,
+                    };
+                    Self::negotiate_legacy(address, noise_config)
+                }
+                Err(err) => {
+                    warn!(
+                        "failed to negotiate the transport with {} - {}",
+                        address, err
+                    );
+                    current_reconnection.fetch_add(1, Ordering::SeqCst);
+                    return;
+                }
+            }
+        };
+
+        let negotiated = match negotiated {
+            Some(negotiated) => negotiated,
+            None => {
                 current_reconnection.fetch_add(1, Ordering::SeqCst);
                 return;
             }
         };
+        let protocol = negotiated.protocol.clone();
+        let conn = Framed::new(stream, negotiated.into_codec());
+
+        active_connections.fetch_add(1, Ordering::Relaxed);
 
-        // Take whatever the receiver channel produces and put it on the connection.
+        // Take whatever the receiver channel produces and put it on the connection, apart from
+        // the packets the remote is not capable of handling.
         // We could have as well used conn.send_all(receiver.map(Ok)), but considering we don't care
         // about neither receiver nor the connection, it doesn't matter which one gets consumed
-        if let Err(err) = receiver.map(Ok).forward(conn).await {
+        let sent = receiver
+            .filter(|packet| {
+                let supported = protocol.supports_packet_size(packet.packet_size())
+                    && protocol.supports_packet_mode(packet.packet_mode());
+                if !supported {
+                    debug!(
+                        "{} does not support {:?} packets in {:?} mode - dropping the packet",
+                        address,
+                        packet.packet_size(),
+                        packet.packet_mode()
+                    );
+                    pending_packets.fetch_sub(1, Ordering::AcqRel);
+                    counters.decrement_queued();
+                    counters.report_dropped();
+                }
+                future::ready(supported)
+            })
+            .inspect(|_| {
+                pending_packets.fetch_sub(1, Ordering::AcqRel);
+                counters.report_sent();
+            });
+        if let Err(err) = sent.map(Ok).forward(conn).await {
             warn!("Failed to forward packets to {} - {:?}", address, err);
         }
+        active_connections.fetch_sub(1, Ordering::Relaxed);
 
         debug!(
             "connection manager to {} is finished. Either the connection failed or mixnet client got dropped",
@@ -117,6 +376,20 @@ impl Client {
         );
     }
 
+    /// Determines the framing protocol used with the remote that does not understand the preamble.
+    fn negotiate_legacy(address: SocketAddr, noise_config: &NoiseConfig) -> Option<Negotiated> {
+        match noise_config.legacy_protocol() {
+            Ok(protocol) => Some(Negotiated {
+                transport: None,
+                protocol,
+            }),
+            Err(err) => {
+                warn!("failed to agree on the protocol with {} - {}", address, err);
+                None
+            }
+        }
+    }
+
     /// If we're trying to reconnect, determine how long we should wait.
     fn determine_backoff(&self, current_attempt: u32) -> Option<Duration> {
         if current_attempt == 0 {
@@ -143,25 +416,39 @@ impl Client {
     fn make_connection(
         &mut self,
         address: NymNodeRoutingAddress,
-        pending_packet: FramedSphinxPacket,
+        pending_packet: Option<FramedSphinxPacket>,
     ) {
-        let (mut sender, receiver) = mpsc::channel(self.config.maximum_connection_buffer_size);
-
-        // this CAN'T fail because we just created the channel which has a non-zero capacity
-        if self.config.maximum_connection_buffer_size > 0 {
-            sender.try_send(pending_packet).unwrap();
-        }
+        let (sender, receiver) = mpsc::channel(self.config.maximum_connection_buffer_size);
 
-        // if we already tried to connect to `address` before, grab the current attempt count
-        let current_reconnection_attempt = if let Some(existing) = self.conn_new.get_mut(&address) {
+        // if we already tried to connect to `address` before, we keep its current attempt count
+        if let Some(existing) = self.conn_new.get_mut(&address) {
             existing.channel = sender;
-            Arc::clone(&existing.current_reconnection_attempt)
+            existing.counters.report_reconnect();
         } else {
+            if self.is_at_capacity() {
+                self.evict_least_recently_used();
+            }
             let new_entry = ConnectionSender::new(sender);
-            let current_attempt = Arc::clone(&new_entry.current_reconnection_attempt);
+            self.stats.insert(address, Arc::clone(&new_entry.counters));
             self.conn_new.insert(address, new_entry);
-            current_attempt
-        };
+        }
+
+        // the entry is guaranteed to exist as we have just inserted it if it didn't
+        let entry = self.conn_new.get_mut(&address).unwrap();
+        entry.last_used = Instant::now();
+
+        if let Some(pending_packet) = pending_packet {
+            // this CAN'T fail because we just created the channel which has a non-zero capacity
+            if self.config.maximum_connection_buffer_size > 0 {
+                self.pending_packets.fetch_add(1, Ordering::AcqRel);
+                entry.counters.increment_queued();
+                entry.channel.try_send(pending_packet).unwrap();
+            }
+        }
+
+        let current_reconnection_attempt = Arc::clone(&entry.current_reconnection_attempt);
+        let is_legacy_peer = Arc::clone(&entry.is_legacy_peer);
+        let counters = Arc::clone(&entry.counters);
 
         // load the actual value.
         let reconnection_attempt = current_reconnection_attempt.load(Ordering::Acquire);
@@ -169,8 +456,15 @@ impl Client {
 
         // copy the value before moving into another task
         let initial_connection_timeout = self.config.initial_connection_timeout;
+        let noise_config = self.config.noise_config.clone();
+        let active_connections = self.stats.active_connections_counter();
+        let running_managers = Arc::clone(&self.running_managers);
+        let pending_packets = Arc::clone(&self.pending_packets);
 
+        running_managers.fetch_add(1, Ordering::AcqRel);
         tokio::spawn(async move {
+            let mut receiver = receiver;
+
             // before executing the manager, wait for what was specified, if anything
             if let Some(backoff) = backoff {
                 trace!("waiting for {:?} before attempting connection", backoff);
@@ -179,11 +473,20 @@ impl Client {
 
             Self::manage_connection(
                 address.into(),
-                receiver,
+                &mut receiver,
                 initial_connection_timeout,
+                &noise_config,
                 &*current_reconnection_attempt,
+                &*is_legacy_peer,
+                &*active_connections,
+                &*pending_packets,
+                &*counters,
             )
-            .await
+            .await;
+
+            // whatever is left in the channel at this point is never going to get sent
+            Self::discard_unsent(&mut receiver, &*pending_packets, &*counters);
+            running_managers.fetch_sub(1, Ordering::AcqRel);
         });
     }
 
@@ -199,8 +502,16 @@ impl Client {
         let framed_packet = FramedSphinxPacket::new(packet, packet_mode);
 
         if let Some(sender) = self.conn_new.get_mut(&address) {
+            sender.last_used = Instant::now();
+            // increment it before sending so that the connection manager would never observe
+            // the counter before we did
+            self.pending_packets.fetch_add(1, Ordering::AcqRel);
+            sender.counters.increment_queued();
             if let Err(err) = sender.channel.try_send(framed_packet) {
+                self.pending_packets.fetch_sub(1, Ordering::AcqRel);
+                sender.counters.decrement_queued();
                 if err.is_full() {
+                    sender.counters.report_dropped();
                     debug!("Connection to {} seems to not be able to handle all the traffic - dropping the current packet", address);
                     // it's not a 'big' error, but we did not manage to send the packet
                     // if the queue is full, we can't really do anything but to drop the packet
@@ -215,7 +526,7 @@ impl Client {
                     );
                     // it's not a 'big' error, but we did not manage to send the packet, but queue
                     // it up to send it as soon as the connection is re-established
-                    self.make_connection(address, err.into_inner());
+                    self.make_connection(address, Some(err.into_inner()));
                     Err(io::Error::new(
                         io::ErrorKind::ConnectionAborted,
                         "reconnection attempt is in progress",
@@ -235,7 +546,7 @@ impl Client {
             debug!("establishing initial connection to {}", address);
             // it's not a 'big' error, but we did not manage to send the packet, but queue the packet
             // for sending for as soon as the connection is created
-            self.make_connection(address, framed_packet);
+            self.make_connection(address, Some(framed_packet));
             Err(io::Error::new(
                 io::ErrorKind::NotConnected,
                 "connection is in progress",
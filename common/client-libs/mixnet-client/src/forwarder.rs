@@ -1,12 +1,12 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::client::{Client, Config};
+use crate::client::{Client, Config, CONNECTION_MAINTENANCE_INTERVAL};
+use crate::stats::ConnectionPoolStats;
 use futures::channel::mpsc;
 use futures::StreamExt;
 use log::*;
 use nymsphinx::forwarding::packet::MixPacket;
-use std::time::Duration;
 
 pub type MixForwardingSender = mpsc::UnboundedSender<MixPacket>;
 type MixForwardingReceiver = mpsc::UnboundedReceiver<MixPacket>;
@@ -19,19 +19,7 @@ pub struct PacketForwarder {
 }
 
 impl PacketForwarder {
-    pub fn new(
-        initial_reconnection_backoff: Duration,
-        maximum_reconnection_backoff: Duration,
-        initial_connection_timeout: Duration,
-        maximum_connection_buffer_size: usize,
-    ) -> (PacketForwarder, MixForwardingSender) {
-        let client_config = Config::new(
-            initial_reconnection_backoff,
-            maximum_reconnection_backoff,
-            initial_connection_timeout,
-            maximum_connection_buffer_size,
-        );
-
+    pub fn new(client_config: Config) -> (PacketForwarder, MixForwardingSender) {
         let (packet_sender, packet_receiver) = mpsc::unbounded();
 
         (
@@ -43,21 +31,36 @@ impl PacketForwarder {
         )
     }
 
-    pub async fn run(&mut self) {
-        while let Some(mix_packet) = self.packet_receiver.next().await {
-            trace!("Going to forward packet to {:?}", mix_packet.next_hop());
+    pub fn pool_stats(&self) -> ConnectionPoolStats {
+        self.mixnet_client.pool_stats()
+    }
+
+    fn forward_packet(&mut self, mix_packet: MixPacket) {
+        trace!("Going to forward packet to {:?}", mix_packet.next_hop());
 
-            let next_hop = mix_packet.next_hop();
-            let packet_mode = mix_packet.packet_mode();
-            let sphinx_packet = mix_packet.into_sphinx_packet();
-            // we don't care about responses, we just want to fire packets
-            // as quickly as possible
+        let next_hop = mix_packet.next_hop();
+        let packet_mode = mix_packet.packet_mode();
+        let sphinx_packet = mix_packet.into_sphinx_packet();
+        // we don't care about responses, we just want to fire packets
+        // as quickly as possible
 
-            if let Err(err) =
-                self.mixnet_client
-                    .send_without_response(next_hop, sphinx_packet, packet_mode)
-            {
-                debug!("failed to forward the packet - {}", err)
+        if let Err(err) =
+            self.mixnet_client
+                .send_without_response(next_hop, sphinx_packet, packet_mode)
+        {
+            debug!("failed to forward the packet - {}", err)
+        }
+    }
+
+    pub async fn run(&mut self) {
+        let mut maintenance_interval = tokio::time::interval(CONNECTION_MAINTENANCE_INTERVAL);
+        loop {
+            tokio::select! {
+                mix_packet = self.packet_receiver.next() => match mix_packet {
+                    Some(mix_packet) => self.forward_packet(mix_packet),
+                    None => break,
+                },
+                _ = maintenance_interval.tick() => self.mixnet_client.maintain_connections(),
             }
         }
     }
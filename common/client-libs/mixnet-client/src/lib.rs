@@ -3,5 +3,9 @@
 
 pub mod client;
 pub mod forwarder;
+pub mod peers;
+pub mod stats;
 
 pub use client::{Client, Config};
+pub use peers::TopologyPeers;
+pub use stats::{ConnectionPoolSnapshot, ConnectionPoolStats, ConnectionStats};
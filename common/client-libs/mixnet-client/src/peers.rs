@@ -0,0 +1,45 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nymsphinx::addressing::nodes::NymNodeRoutingAddress;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+#[derive(Default)]
+struct TopologyPeersInner {
+    known: HashSet<NymNodeRoutingAddress>,
+    next_layer: Vec<NymNodeRoutingAddress>,
+}
+
+/// Nodes present in the current network topology, as seen by the owner of the mixnet client.
+/// Connections to nodes that are no longer part of it get closed, while connections to the nodes
+/// in the next layer are established in advance and kept open.
+// Note: you should NEVER create more than a single instance of this using 'new()'.
+// You should always use .clone() to create additional instances
+#[derive(Clone, Default)]
+pub struct TopologyPeers {
+    inner: Arc<RwLock<TopologyPeersInner>>,
+}
+
+impl TopologyPeers {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn update(
+        &self,
+        known: impl IntoIterator<Item = NymNodeRoutingAddress>,
+        next_layer: impl IntoIterator<Item = NymNodeRoutingAddress>,
+    ) {
+        let mut inner = self.inner.write().unwrap();
+        inner.known = known.into_iter().collect();
+        inner.next_layer = next_layer.into_iter().collect();
+    }
+
+    /// Returns the current sets of known and next-layer nodes. Both are empty if the topology
+    /// hasn't been obtained yet.
+    pub(crate) fn snapshot(&self) -> (HashSet<NymNodeRoutingAddress>, Vec<NymNodeRoutingAddress>) {
+        let inner = self.inner.read().unwrap();
+        (inner.known.clone(), inner.next_layer.clone())
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nymsphinx::addressing::nodes::NymNodeRoutingAddress;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+#[derive(Default)]
+pub(crate) struct ConnectionCounters {
+    queued: AtomicUsize,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    reconnects: AtomicU64,
+}
+
+impl ConnectionCounters {
+    pub(crate) fn increment_queued(&self) {
+        self.queued.fetch_add(1, Ordering::AcqRel);
+    }
+
+    pub(crate) fn decrement_queued(&self) {
+        self.queued.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    pub(crate) fn report_sent(&self) {
+        self.queued.fetch_sub(1, Ordering::AcqRel);
+        self.sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn report_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn report_reconnect(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> ConnectionStats {
+        ConnectionStats {
+            queued: self.queued.load(Ordering::Relaxed),
+            sent: self.sent.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Traffic statistics of a single pooled connection.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct ConnectionStats {
+    /// Number of packets waiting to be sent.
+    pub queued: usize,
+    /// Number of packets written to the connection.
+    pub sent: u64,
+    /// Number of packets dropped because the queue was full or the connection failed.
+    pub dropped: u64,
+    /// Number of times the connection had to be re-established.
+    pub reconnects: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ConnectionPoolSnapshot {
+    /// Number of connections currently kept in the pool, regardless of their state.
+    pub pooled_connections: usize,
+    /// Number of currently established connections.
+    pub active_connections: usize,
+    /// Statistics of all the pooled connections, keyed by the address of the remote.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub connections: HashMap<String, ConnectionStats>,
+}
+
+/// Read-only view into the statistics of the connection pool of a mixnet client.
+#[derive(Clone, Default)]
+pub struct ConnectionPoolStats {
+    connections: Arc<RwLock<HashMap<NymNodeRoutingAddress, Arc<ConnectionCounters>>>>,
+    active_connections: Arc<AtomicUsize>,
+}
+
+impl ConnectionPoolStats {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn active_connections_counter(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.active_connections)
+    }
+
+    pub(crate) fn insert(&self, address: NymNodeRoutingAddress, counters: Arc<ConnectionCounters>) {
+        self.connections.write().unwrap().insert(address, counters);
+    }
+
+    pub(crate) fn remove(&self, address: &NymNodeRoutingAddress) {
+        self.connections.write().unwrap().remove(address);
+    }
+
+    /// Returns the number of currently established outbound connections.
+    pub fn active_connections(&self) -> usize {
+        self.active_connections.load(Ordering::Relaxed)
+    }
+
+    pub fn snapshot(&self) -> ConnectionPoolSnapshot {
+        let connections = self.connections.read().unwrap();
+        ConnectionPoolSnapshot {
+            pooled_connections: connections.len(),
+            active_connections: self.active_connections(),
+            connections: connections
+                .iter()
+                .map(|(address, counters)| (address.to_string(), counters.snapshot()))
+                .collect(),
+        }
+    }
+}
@@ -19,3 +19,13 @@ pub enum ValidatorClientError {
     #[error("There was an issue with the Nymd client - {0}")]
     NymdError(#[from] crate::nymd::error::NymdError),
 }
+
+impl ValidatorClientError {
+    /// Checks whether the validator API did not know about the queried route.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            ValidatorClientError::ValidatorAPIError { source } => source.is_not_found(),
+            _ => false,
+        }
+    }
+}
@@ -3,7 +3,10 @@
 
 use crate::nymd::error::NymdError;
 use cosmos_sdk::tendermint::abci;
+use cosmwasm_std::{Attribute, Uint128};
 use itertools::Itertools;
+use mixnet_contract::events::*;
+use mixnet_contract::{IdentityKey, StateParams};
 use serde::Deserialize;
 
 // it seems that currently validators just emit stringified events (which are also returned as part of deliverTx response)
@@ -50,6 +53,241 @@ pub fn parse_raw_logs(raw: abci::Log) -> Result<Vec<Log>, NymdError> {
     parse_raw_str_logs(raw.as_ref())
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct BondingEvent {
+    pub identity: IdentityKey,
+    pub owner: String,
+    pub amount: Uint128,
+    pub epoch: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DelegationEvent {
+    pub identity: IdentityKey,
+    pub delegator: String,
+    pub amount: Uint128,
+    pub epoch: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewardingEvent {
+    pub identity: IdentityKey,
+    pub uptime: u32,
+    // one of `REWARDED_RESULT`, `ZERO_UPTIME_RESULT`, `BOND_NOT_FOUND_RESULT` or `NODE_JAILED_RESULT`
+    pub result: String,
+    pub operator_reward: Uint128,
+    pub delegators_reward: Uint128,
+    pub epoch: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamsUpdateEvent {
+    pub params: StateParams,
+    pub epoch: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MixnetContractEvent {
+    MixnodeBonding(BondingEvent),
+    MixnodeUnbonding(BondingEvent),
+    GatewayBonding(BondingEvent),
+    GatewayUnbonding(BondingEvent),
+    MixnodeDelegation(DelegationEvent),
+    MixnodeUndelegation(DelegationEvent),
+    GatewayDelegation(DelegationEvent),
+    GatewayUndelegation(DelegationEvent),
+    MixnodeRewarding(RewardingEvent),
+    GatewayRewarding(RewardingEvent),
+    ParamsUpdate(ParamsUpdateEvent),
+}
+
+struct EventAttributes<'a> {
+    event_type: &'a str,
+    attributes: &'a [Attribute],
+}
+
+impl<'a> EventAttributes<'a> {
+    fn malformed(&self, reason: String) -> NymdError {
+        NymdError::MalformedContractEvent {
+            event_type: self.event_type.to_string(),
+            reason,
+        }
+    }
+
+    fn value(&self, key: &str) -> Result<&'a str, NymdError> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.as_str())
+            .ok_or_else(|| self.malformed(format!("attribute {} is missing", key)))
+    }
+
+    fn u32_value(&self, key: &str) -> Result<u32, NymdError> {
+        self.value(key)?
+            .parse()
+            .map_err(|_| self.malformed(format!("attribute {} is not a valid u32", key)))
+    }
+
+    fn uint128_value(&self, key: &str) -> Result<Uint128, NymdError> {
+        self.value(key)?
+            .parse()
+            .map(Uint128)
+            .map_err(|_| self.malformed(format!("attribute {} is not a valid u128", key)))
+    }
+
+    fn bonding_event(&self) -> Result<BondingEvent, NymdError> {
+        Ok(BondingEvent {
+            identity: self.value(NODE_IDENTITY_KEY)?.to_string(),
+            owner: self.value(OWNER_KEY)?.to_string(),
+            amount: self.uint128_value(AMOUNT_KEY)?,
+            epoch: self.u32_value(EPOCH_KEY)?,
+        })
+    }
+
+    fn delegation_event(&self) -> Result<DelegationEvent, NymdError> {
+        Ok(DelegationEvent {
+            identity: self.value(NODE_IDENTITY_KEY)?.to_string(),
+            delegator: self.value(DELEGATOR_KEY)?.to_string(),
+            amount: self.uint128_value(AMOUNT_KEY)?,
+            epoch: self.u32_value(EPOCH_KEY)?,
+        })
+    }
+
+    fn rewarding_event(&self) -> Result<RewardingEvent, NymdError> {
+        Ok(RewardingEvent {
+            identity: self.value(NODE_IDENTITY_KEY)?.to_string(),
+            uptime: self.u32_value(UPTIME_KEY)?,
+            result: self.value(REWARDING_RESULT_KEY)?.to_string(),
+            operator_reward: self.uint128_value(OPERATOR_REWARD_KEY)?,
+            delegators_reward: self.uint128_value(DELEGATORS_REWARD_KEY)?,
+            epoch: self.u32_value(EPOCH_KEY)?,
+        })
+    }
+
+    fn params_update_event(&self) -> Result<ParamsUpdateEvent, NymdError> {
+        let params = serde_json::from_str(self.value(PARAMS_KEY)?)
+            .map_err(|err| self.malformed(format!("invalid params - {}", err)))?;
+        Ok(ParamsUpdateEvent {
+            params,
+            epoch: self.u32_value(EPOCH_KEY)?,
+        })
+    }
+
+    // returns `None` for the events of the contract whose details are not exposed by the client
+    fn into_event(self) -> Result<Option<MixnetContractEvent>, NymdError> {
+        let event = match self.event_type {
+            MIXNODE_BONDING_EVENT_TYPE => {
+                MixnetContractEvent::MixnodeBonding(self.bonding_event()?)
+            }
+            MIXNODE_UNBONDING_EVENT_TYPE => {
+                MixnetContractEvent::MixnodeUnbonding(self.bonding_event()?)
+            }
+            GATEWAY_BONDING_EVENT_TYPE => {
+                MixnetContractEvent::GatewayBonding(self.bonding_event()?)
+            }
+            GATEWAY_UNBONDING_EVENT_TYPE => {
+                MixnetContractEvent::GatewayUnbonding(self.bonding_event()?)
+            }
+            MIXNODE_DELEGATION_EVENT_TYPE => {
+                MixnetContractEvent::MixnodeDelegation(self.delegation_event()?)
+            }
+            MIXNODE_UNDELEGATION_EVENT_TYPE => {
+                MixnetContractEvent::MixnodeUndelegation(self.delegation_event()?)
+            }
+            GATEWAY_DELEGATION_EVENT_TYPE => {
+                MixnetContractEvent::GatewayDelegation(self.delegation_event()?)
+            }
+            GATEWAY_UNDELEGATION_EVENT_TYPE => {
+                MixnetContractEvent::GatewayUndelegation(self.delegation_event()?)
+            }
+            MIXNODE_REWARDING_EVENT_TYPE => {
+                MixnetContractEvent::MixnodeRewarding(self.rewarding_event()?)
+            }
+            GATEWAY_REWARDING_EVENT_TYPE => {
+                MixnetContractEvent::GatewayRewarding(self.rewarding_event()?)
+            }
+            PARAMS_UPDATE_EVENT_TYPE => {
+                MixnetContractEvent::ParamsUpdate(self.params_update_event()?)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(event))
+    }
+}
+
+// the `wasm` event contains attributes of all contracts executed as part of the message,
+// each set of them being preceded by the `contract_address` attribute. Each response of the
+// mixnet contract starts with the `action` attribute.
+fn split_wasm_event<'a>(
+    attributes: &'a [Attribute],
+    contract_address: &str,
+) -> Vec<EventAttributes<'a>> {
+    let mut events = Vec::new();
+    let mut in_contract = false;
+    let mut current: Option<(&'a str, usize)> = None;
+
+    for (i, attr) in attributes.iter().enumerate() {
+        let is_boundary =
+            attr.key == CONTRACT_ADDRESS_KEY || (in_contract && attr.key == EVENT_TYPE_KEY);
+        if is_boundary {
+            if let Some((event_type, start)) = current.take() {
+                events.push(EventAttributes {
+                    event_type,
+                    attributes: &attributes[start..i],
+                })
+            }
+        }
+        if attr.key == CONTRACT_ADDRESS_KEY {
+            in_contract = attr.value == contract_address;
+        } else if in_contract && attr.key == EVENT_TYPE_KEY {
+            current = Some((attr.value.as_str(), i + 1));
+        }
+    }
+    if let Some((event_type, start)) = current {
+        events.push(EventAttributes {
+            event_type,
+            attributes: &attributes[start..],
+        })
+    }
+
+    events
+}
+
+/// Extracts all events emitted by the mixnet contract at the provided address from the logs
+/// of a transaction. Both the events emitted as attributes of the `wasm` event
+/// and the custom `wasm-{event_type}` events are understood.
+pub fn parse_mixnet_contract_events(
+    logs: &[Log],
+    contract_address: &str,
+) -> Result<Vec<MixnetContractEvent>, NymdError> {
+    let mut parsed = Vec::new();
+    for event in logs.iter().flat_map(|log| log.events.iter()) {
+        let candidates = if event.kind == WASM_EVENT_TYPE {
+            split_wasm_event(&event.attributes, contract_address)
+        } else if let Some(event_type) = event.kind.strip_prefix(CUSTOM_EVENT_TYPE_PREFIX) {
+            let emitted_by_contract = event.attributes.iter().any(|attr| {
+                attr.key == CUSTOM_EVENT_CONTRACT_ADDRESS_KEY && attr.value == contract_address
+            });
+            if !emitted_by_contract {
+                continue;
+            }
+            vec![EventAttributes {
+                event_type,
+                attributes: &event.attributes,
+            }]
+        } else {
+            continue;
+        };
+
+        for candidate in candidates {
+            if let Some(event) = candidate.into_event()? {
+                parsed.push(event)
+            }
+        }
+    }
+    Ok(parsed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,4 +326,126 @@ mod tests {
             "punk1q9n5a3cgw3azegcddr82s0f5nxeel4pup8vxzt"
         );
     }
+
+    fn wasm_log(attributes: Vec<Attribute>) -> Log {
+        Log {
+            msg_index: 0,
+            events: vec![cosmwasm_std::Event {
+                kind: WASM_EVENT_TYPE.to_string(),
+                attributes,
+            }],
+        }
+    }
+
+    #[test]
+    fn mixnet_events_are_parsed_from_wasm_event_attributes() {
+        let owner = cosmwasm_std::Addr::unchecked("owner");
+        let mut attributes = vec![cosmwasm_std::attr(CONTRACT_ADDRESS_KEY, "mixnet")];
+        attributes.append(&mut new_mixnode_bonding_event(
+            &owner,
+            "foo",
+            Uint128(100),
+            3,
+        ));
+        attributes.push(cosmwasm_std::attr(CONTRACT_ADDRESS_KEY, "other-contract"));
+        attributes.push(cosmwasm_std::attr(
+            EVENT_TYPE_KEY,
+            MIXNODE_BONDING_EVENT_TYPE,
+        ));
+        attributes.push(cosmwasm_std::attr(CONTRACT_ADDRESS_KEY, "mixnet"));
+        attributes.append(&mut new_mixnode_rewarding_event(
+            "foo",
+            90,
+            Uint128(10),
+            Uint128(20),
+            3,
+        ));
+        // events whose details are not exposed are ignored
+        attributes.append(&mut new_epoch_advance_event(4, Some(100)));
+
+        let parsed = parse_mixnet_contract_events(&[wasm_log(attributes)], "mixnet").unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                MixnetContractEvent::MixnodeBonding(BondingEvent {
+                    identity: "foo".to_string(),
+                    owner: "owner".to_string(),
+                    amount: Uint128(100),
+                    epoch: 3,
+                }),
+                MixnetContractEvent::MixnodeRewarding(RewardingEvent {
+                    identity: "foo".to_string(),
+                    uptime: 90,
+                    result: REWARDED_RESULT.to_string(),
+                    operator_reward: Uint128(10),
+                    delegators_reward: Uint128(20),
+                    epoch: 3,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn mixnet_events_are_parsed_from_custom_events() {
+        let delegator = cosmwasm_std::Addr::unchecked("delegator");
+        let mut attributes = vec![cosmwasm_std::attr(
+            CUSTOM_EVENT_CONTRACT_ADDRESS_KEY,
+            "mixnet",
+        )];
+        attributes.append(&mut new_gateway_undelegation_event(
+            &delegator,
+            "bar",
+            Uint128(42),
+            1,
+        ));
+        let log = Log {
+            msg_index: 0,
+            events: vec![
+                cosmwasm_std::Event {
+                    kind: format!(
+                        "{}{}",
+                        CUSTOM_EVENT_TYPE_PREFIX, GATEWAY_UNDELEGATION_EVENT_TYPE
+                    ),
+                    attributes: attributes.clone(),
+                },
+                cosmwasm_std::Event {
+                    kind: format!(
+                        "{}{}",
+                        CUSTOM_EVENT_TYPE_PREFIX, GATEWAY_UNDELEGATION_EVENT_TYPE
+                    ),
+                    attributes: vec![cosmwasm_std::attr(
+                        CUSTOM_EVENT_CONTRACT_ADDRESS_KEY,
+                        "other-contract",
+                    )],
+                },
+            ],
+        };
+
+        let parsed = parse_mixnet_contract_events(&[log], "mixnet").unwrap();
+        assert_eq!(
+            parsed,
+            vec![MixnetContractEvent::GatewayUndelegation(DelegationEvent {
+                identity: "bar".to_string(),
+                delegator: "delegator".to_string(),
+                amount: Uint128(42),
+                epoch: 1,
+            })]
+        );
+    }
+
+    #[test]
+    fn malformed_mixnet_events_are_rejected() {
+        let attributes = vec![
+            cosmwasm_std::attr(CONTRACT_ADDRESS_KEY, "mixnet"),
+            cosmwasm_std::attr(EVENT_TYPE_KEY, MIXNODE_DELEGATION_EVENT_TYPE),
+            cosmwasm_std::attr(NODE_IDENTITY_KEY, "foo"),
+            cosmwasm_std::attr(DELEGATOR_KEY, "delegator"),
+            cosmwasm_std::attr(AMOUNT_KEY, "not-a-number"),
+            cosmwasm_std::attr(EPOCH_KEY, 1),
+        ];
+        assert!(matches!(
+            parse_mixnet_contract_events(&[wasm_log(attributes)], "mixnet"),
+            Err(NymdError::MalformedContractEvent { .. })
+        ));
+    }
 }
@@ -75,6 +75,9 @@ pub enum NymdError {
     #[error("Logs returned from the validator were malformed")]
     MalformedLogString,
 
+    #[error("Mixnet contract event of type {event_type} was malformed - {reason}")]
+    MalformedContractEvent { event_type: String, reason: String },
+
     #[error(
     "Error when broadcasting tx {hash} at height {height}. Error occurred during CheckTx phase. Code: {code}; Raw log: {raw_log}"
     )]
@@ -16,15 +16,33 @@ pub enum Operation {
 
     BondMixnode,
     UnbondMixnode,
+    UpdateMixnodeConfig,
+    UpdateMixnodeProfitMargin,
+    ClaimOperatorReward,
+    CreateFamily,
+    JoinFamily,
+    LeaveFamily,
+    UnjailMixnode,
     DelegateToMixnode,
     UndelegateFromMixnode,
+    ClaimDelegatorReward,
+    ClaimPendingWithdrawals,
 
     BondGateway,
     UnbondGateway,
+    UpdateGatewayConfig,
     DelegateToGateway,
     UndelegateFromGateway,
 
-    UpdateStateParams,
+    AdvanceEpoch,
+    ReportMisbehaviour,
+    WithdrawSlashedFunds,
+
+    ProposeStateParams,
+    ProposeGovernanceConfig,
+    VoteOnProposal,
+    ExecuteProposal,
+    CancelProposal,
 }
 
 pub(crate) fn calculate_fee(gas_price: &GasPrice, gas_limit: Gas) -> Coin {
@@ -49,15 +67,35 @@ impl Operation {
 
             Operation::BondMixnode => 175_000u64.into(),
             Operation::UnbondMixnode => 175_000u64.into(),
+            Operation::UpdateMixnodeConfig => 175_000u64.into(),
+            Operation::UpdateMixnodeProfitMargin => 175_000u64.into(),
+            Operation::ClaimOperatorReward => 175_000u64.into(),
+            Operation::CreateFamily => 175_000u64.into(),
+            Operation::JoinFamily => 175_000u64.into(),
+            Operation::LeaveFamily => 175_000u64.into(),
+            Operation::UnjailMixnode => 175_000u64.into(),
             Operation::DelegateToMixnode => 175_000u64.into(),
             Operation::UndelegateFromMixnode => 175_000u64.into(),
+            Operation::ClaimDelegatorReward => 175_000u64.into(),
+            Operation::ClaimPendingWithdrawals => 175_000u64.into(),
 
             Operation::BondGateway => 175_000u64.into(),
             Operation::UnbondGateway => 175_000u64.into(),
+            Operation::UpdateGatewayConfig => 175_000u64.into(),
             Operation::DelegateToGateway => 175_000u64.into(),
             Operation::UndelegateFromGateway => 175_000u64.into(),
 
-            Operation::UpdateStateParams => 175_000u64.into(),
+            // goes through a batch of bonded mixnodes
+            Operation::AdvanceEpoch => 1_000_000u64.into(),
+            Operation::ReportMisbehaviour => 175_000u64.into(),
+            Operation::WithdrawSlashedFunds => 175_000u64.into(),
+
+            Operation::ProposeStateParams => 175_000u64.into(),
+            Operation::ProposeGovernanceConfig => 175_000u64.into(),
+            Operation::VoteOnProposal => 175_000u64.into(),
+            // goes through all bonded mixnodes when recomputing the reward pool
+            Operation::ExecuteProposal => 1_000_000u64.into(),
+            Operation::CancelProposal => 175_000u64.into(),
         }
     }
 
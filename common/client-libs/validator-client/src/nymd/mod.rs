@@ -3,8 +3,8 @@
 
 use crate::nymd::cosmwasm_client::signing_client;
 use crate::nymd::cosmwasm_client::types::{
-    ChangeAdminResult, ContractCodeId, ExecuteResult, InstantiateOptions, InstantiateResult,
-    MigrateResult, UploadMeta, UploadResult,
+    ChangeAdminResult, Contract, ContractCodeId, ExecuteResult, InstantiateOptions,
+    InstantiateResult, MigrateResult, UploadMeta, UploadResult,
 };
 use crate::nymd::error::NymdError;
 use crate::nymd::fee_helpers::Operation;
@@ -16,9 +16,13 @@ use cosmos_sdk::Coin as CosmosCoin;
 use cosmos_sdk::{AccountId, Denom};
 use cosmwasm_std::Coin;
 use mixnet_contract::{
-    Addr, Delegation, ExecuteMsg, Gateway, GatewayOwnershipResponse, IdentityKey,
-    LayerDistribution, MixNode, MixOwnershipResponse, PagedGatewayDelegationsResponse,
-    PagedGatewayResponse, PagedMixDelegationsResponse, PagedMixnodeResponse, QueryMsg, StateParams,
+    Addr, ContractVersion, Delegation, EpochResponse, ExecuteMsg, Gateway,
+    GatewayOwnershipResponse, GovernanceConfig, IdentityKey, LayerDistribution, MigrateMsg,
+    MixNode, MixOwnershipResponse, PagedDelegatorGatewayDelegationsResponse,
+    PagedDelegatorMixDelegationsResponse, PagedGatewayDelegationsResponse, PagedGatewayResponse,
+    PagedMixDelegationsResponse, PagedMixnodeMisbehaviourResponse, PagedMixnodeResponse,
+    PagedPendingWithdrawalsResponse, PagedProposalVotesResponse, PagedProposalsResponse, Proposal,
+    ProposalId, QueryMsg, StateParams, VoteOption,
 };
 use serde::Serialize;
 use std::collections::HashMap;
@@ -164,6 +168,80 @@ impl<C> NymdClient<C> {
             .await
     }
 
+    pub async fn get_governance_config(&self) -> Result<GovernanceConfig, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetGovernanceConfig {};
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
+    pub async fn get_proposal(&self, proposal_id: ProposalId) -> Result<Proposal, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetProposal { proposal_id };
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
+    pub async fn get_proposals_paged(
+        &self,
+        start_after: Option<ProposalId>,
+        page_limit: Option<u32>,
+    ) -> Result<PagedProposalsResponse, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetProposals {
+            start_after,
+            limit: page_limit,
+        };
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
+    pub async fn get_proposal_votes_paged(
+        &self,
+        proposal_id: ProposalId,
+        start_after: Option<Addr>,
+        page_limit: Option<u32>,
+    ) -> Result<PagedProposalVotesResponse, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetProposalVotes {
+            proposal_id,
+            start_after,
+            limit: page_limit,
+        };
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
+    /// Obtains the chain information about the mixnet contract, such as its current code id and admin.
+    pub async fn get_mixnet_contract_info(&self) -> Result<Contract, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        self.client.get_contract(self.contract_address()?).await
+    }
+
+    pub async fn get_contract_version(&self) -> Result<ContractVersion, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetContractVersion {};
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
     pub async fn get_layer_distribution(&self) -> Result<LayerDistribution, NymdError>
     where
         C: CosmWasmClient + Sync,
@@ -174,6 +252,83 @@ impl<C> NymdClient<C> {
             .await
     }
 
+    /// Gets the number of the current epoch along with its start and end time.
+    pub async fn get_epoch(&self) -> Result<EpochResponse, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetEpoch {};
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
+    /// Gets the mixnodes selected to route traffic during the current epoch.
+    pub async fn get_active_set_paged(
+        &self,
+        start_after: Option<IdentityKey>,
+        page_limit: Option<u32>,
+    ) -> Result<PagedMixnodeResponse, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetActiveSet {
+            start_after,
+            limit: page_limit,
+        };
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
+    pub async fn get_jailed_mixnodes_paged(
+        &self,
+        start_after: Option<IdentityKey>,
+        page_limit: Option<u32>,
+    ) -> Result<PagedMixnodeResponse, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetJailedMixNodes {
+            start_after,
+            limit: page_limit,
+        };
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
+    /// Gets the history of misbehaviour reports of particular mixnode on particular page.
+    pub async fn get_mixnode_misbehaviour(
+        &self,
+        identity: IdentityKey,
+        start_after: Option<u32>,
+        page_limit: Option<u32>,
+    ) -> Result<PagedMixnodeMisbehaviourResponse, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetMixnodeMisbehaviour {
+            identity,
+            start_after,
+            limit: page_limit,
+        };
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
+    /// Gets the amount of stake slashed from jailed mixnodes that is yet to be withdrawn.
+    pub async fn get_slashed_funds(&self) -> Result<Coin, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetSlashedFunds {};
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
     /// Checks whether there is a bonded mixnode associated with the provided client's address
     pub async fn owns_mixnode(&self, address: &AccountId) -> Result<bool, NymdError>
     where
@@ -315,6 +470,66 @@ impl<C> NymdClient<C> {
             .await
     }
 
+    /// Gets list of all mixnode delegations made by particular address on particular page.
+    pub async fn get_delegator_mix_delegations(
+        &self,
+        delegator: &AccountId,
+        start_after: Option<IdentityKey>,
+        page_limit: Option<u32>,
+    ) -> Result<PagedDelegatorMixDelegationsResponse, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetDelegatorMixDelegations {
+            address: Addr::unchecked(delegator.as_ref()),
+            start_after,
+            limit: page_limit,
+        };
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
+    /// Gets list of all gateway delegations made by particular address on particular page.
+    pub async fn get_delegator_gateway_delegations(
+        &self,
+        delegator: &AccountId,
+        start_after: Option<IdentityKey>,
+        page_limit: Option<u32>,
+    ) -> Result<PagedDelegatorGatewayDelegationsResponse, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetDelegatorGatewayDelegations {
+            address: Addr::unchecked(delegator.as_ref()),
+            start_after,
+            limit: page_limit,
+        };
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
+    /// Gets list of withdrawals of the given client that are still locked or have not been claimed yet.
+    pub async fn get_pending_withdrawals(
+        &self,
+        address: &AccountId,
+        start_after: Option<u64>,
+        page_limit: Option<u32>,
+    ) -> Result<PagedPendingWithdrawalsResponse, NymdError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        let request = QueryMsg::GetPendingWithdrawals {
+            address: Addr::unchecked(address.as_ref()),
+            start_after,
+            limit: page_limit,
+        };
+        self.client
+            .query_contract_smart(self.contract_address()?, &request)
+            .await
+    }
+
     /// Send funds from one address to another
     pub async fn send(
         &self,
@@ -427,10 +642,25 @@ impl<C> NymdClient<C> {
             .await
     }
 
+    /// Migrates the mixnet contract to the code with the provided id. The contract itself
+    /// upgrades its stored state to the layout used by the new code.
+    pub async fn migrate_mixnet_contract(
+        &self,
+        code_id: ContractCodeId,
+        memo: impl Into<String> + Send + 'static,
+    ) -> Result<MigrateResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        self.migrate(self.contract_address()?, code_id, &MigrateMsg {}, memo)
+            .await
+    }
+
     /// Announce a mixnode, paying a fee.
     pub async fn bond_mixnode(
         &self,
         mixnode: MixNode,
+        profit_margin_percent: u8,
         bond: Coin,
     ) -> Result<ExecuteResult, NymdError>
     where
@@ -438,7 +668,10 @@ impl<C> NymdClient<C> {
     {
         let fee = self.get_fee(Operation::BondMixnode);
 
-        let req = ExecuteMsg::BondMixnode { mix_node: mixnode };
+        let req = ExecuteMsg::BondMixnode {
+            mix_node: mixnode,
+            profit_margin_percent,
+        };
         self.client
             .execute(
                 self.address(),
@@ -471,6 +704,174 @@ impl<C> NymdClient<C> {
             .await
     }
 
+    /// Update the networking information and version of the bonded mixnode, without affecting
+    /// its layer or delegations.
+    pub async fn update_mixnode_config(
+        &self,
+        host: String,
+        mix_port: u16,
+        verloc_port: u16,
+        http_api_port: u16,
+        version: String,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::UpdateMixnodeConfig);
+
+        let req = ExecuteMsg::UpdateMixnodeConfig {
+            host,
+            mix_port,
+            verloc_port,
+            http_api_port,
+            version,
+        };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Updating mixnode configuration from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Change the percentage of the delegators' rewards taken by the operator of the bonded mixnode.
+    pub async fn update_mixnode_profit_margin(
+        &self,
+        profit_margin_percent: u8,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::UpdateMixnodeProfitMargin);
+
+        let req = ExecuteMsg::UpdateMixnodeProfitMargin {
+            profit_margin_percent,
+        };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Updating mixnode profit margin from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Claims the rewards accumulated by the bonded mixnode, either paying them out
+    /// or adding them to the bond.
+    pub async fn claim_operator_reward(&self, compound: bool) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::ClaimOperatorReward);
+
+        let req = ExecuteMsg::ClaimOperatorReward { compound };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Claiming operator reward from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Makes the client's mixnode the head of a new family of nodes.
+    pub async fn create_family(&self) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::CreateFamily);
+
+        let req = ExecuteMsg::CreateFamily {};
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Creating mixnode family from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Adds the client's mixnode to the family of the specified head, which has to consent to it
+    /// by signing the `mixnet_contract::family_join_message` for the client's mixnode.
+    pub async fn join_family(
+        &self,
+        family_head: IdentityKey,
+        signature: String,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::JoinFamily);
+
+        let req = ExecuteMsg::JoinFamily {
+            family_head,
+            signature,
+        };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Joining mixnode family from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Removes the client's mixnode from its family.
+    pub async fn leave_family(&self) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::LeaveFamily);
+
+        let req = ExecuteMsg::LeaveFamily {};
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Leaving mixnode family from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Releases the mixnode of the client from jail, so that it could be selected for the active set again.
+    pub async fn unjail_mixnode(&self) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::UnjailMixnode);
+
+        let req = ExecuteMsg::UnjailMixnode {};
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Unjailing mixnode from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
     /// Delegates specified amount of stake to particular mixnode.
     pub async fn delegate_to_mixnode(
         &self,
@@ -518,6 +919,58 @@ impl<C> NymdClient<C> {
             .await
     }
 
+    /// Claims the rewards accumulated by the stake delegated to a particular mixnode, either paying
+    /// them out or adding them to the delegation.
+    pub async fn claim_delegator_reward(
+        &self,
+        mix_identity: IdentityKey,
+        compound: bool,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::ClaimDelegatorReward);
+
+        let req = ExecuteMsg::ClaimDelegatorReward {
+            mix_identity,
+            compound,
+        };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Claiming delegator reward from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Claims the unbonded stake whose unbonding period is already over, looking at most
+    /// at `limit` of the oldest pending withdrawals.
+    pub async fn claim_pending_withdrawals(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::ClaimPendingWithdrawals);
+
+        let req = ExecuteMsg::ClaimPendingWithdrawals { limit };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Claiming pending withdrawals from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
     /// Announce a gateway, paying a fee.
     pub async fn bond_gateway(
         &self,
@@ -562,6 +1015,40 @@ impl<C> NymdClient<C> {
             .await
     }
 
+    /// Update the networking information, location and version of the bonded gateway, without
+    /// affecting its delegations.
+    pub async fn update_gateway_config(
+        &self,
+        host: String,
+        mix_port: u16,
+        clients_port: u16,
+        location: String,
+        version: String,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::UpdateGatewayConfig);
+
+        let req = ExecuteMsg::UpdateGatewayConfig {
+            host,
+            mix_port,
+            clients_port,
+            location,
+            version,
+        };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Updating gateway configuration from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
     /// Delegates specified amount of stake to particular gateway.
     pub async fn delegate_to_gateway(
         &self,
@@ -609,23 +1096,190 @@ impl<C> NymdClient<C> {
             .await
     }
 
-    pub async fn update_state_params(
+    /// Finishes the current epoch and selects the active set of mixnodes for the next one.
+    pub async fn advance_epoch(&self) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::AdvanceEpoch);
+
+        let req = ExecuteMsg::AdvanceEpoch {};
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Advancing epoch from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Reports mixnode whose uptime during the current epoch was below the required minimum.
+    pub async fn report_misbehaviour(
+        &self,
+        identity: IdentityKey,
+        uptime: u32,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::ReportMisbehaviour);
+
+        let req = ExecuteMsg::ReportMisbehaviour { identity, uptime };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Reporting mixnode misbehaviour from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Sends all the stake slashed so far to the provided recipient. Can only be done by the contract owner.
+    pub async fn withdraw_slashed_funds(
+        &self,
+        recipient: &AccountId,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::WithdrawSlashedFunds);
+
+        let req = ExecuteMsg::WithdrawSlashedFunds {
+            recipient: recipient.to_string(),
+        };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Withdrawing slashed funds from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Submits a proposal to change the contract state parameters. Proposing counts as a yes vote.
+    pub async fn propose_state_params(
         &self,
-        new_params: StateParams,
+        params: StateParams,
+        description: String,
     ) -> Result<ExecuteResult, NymdError>
     where
         C: SigningCosmWasmClient + Sync,
     {
-        let fee = self.get_fee(Operation::UpdateStateParams);
+        let fee = self.get_fee(Operation::ProposeStateParams);
+
+        let req = ExecuteMsg::ProposeStateParams {
+            params,
+            description,
+        };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Proposing state params from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Submits a proposal to change the members, threshold or periods of the governance.
+    /// Proposing counts as a yes vote.
+    pub async fn propose_governance_config(
+        &self,
+        config: GovernanceConfig,
+        description: String,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::ProposeGovernanceConfig);
+
+        let req = ExecuteMsg::ProposeGovernanceConfig {
+            config,
+            description,
+        };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Proposing governance config from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    pub async fn vote_on_proposal(
+        &self,
+        proposal_id: ProposalId,
+        vote: VoteOption,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::VoteOnProposal);
+
+        let req = ExecuteMsg::VoteOnProposal { proposal_id, vote };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Voting on proposal from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    /// Applies a passed proposal once its timelock has expired.
+    pub async fn execute_proposal(
+        &self,
+        proposal_id: ProposalId,
+    ) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::ExecuteProposal);
+
+        let req = ExecuteMsg::ExecuteProposal { proposal_id };
+        self.client
+            .execute(
+                self.address(),
+                self.contract_address()?,
+                &req,
+                fee,
+                "Executing proposal from rust!",
+                Vec::new(),
+            )
+            .await
+    }
+
+    pub async fn cancel_proposal(&self, proposal_id: ProposalId) -> Result<ExecuteResult, NymdError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        let fee = self.get_fee(Operation::CancelProposal);
 
-        let req = ExecuteMsg::UpdateStateParams(new_params);
+        let req = ExecuteMsg::CancelProposal { proposal_id };
         self.client
             .execute(
                 self.address(),
                 self.contract_address()?,
                 &req,
                 fee,
-                "Updating contract state from rust!",
+                "Cancelling proposal from rust!",
                 Vec::new(),
             )
             .await
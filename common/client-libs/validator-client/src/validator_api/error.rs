@@ -8,3 +8,15 @@ pub enum ValidatorAPIError {
         source: reqwest::Error,
     },
 }
+
+impl ValidatorAPIError {
+    /// Checks whether the request failed because the queried route does not exist,
+    /// for example because the validator API is running an older version.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            ValidatorAPIError::ReqwestClientError { source } => {
+                source.status() == Some(reqwest::StatusCode::NOT_FOUND)
+            }
+        }
+    }
+}
@@ -35,7 +35,14 @@ impl Client {
         for<'a> T: Deserialize<'a>,
     {
         let url = create_api_url(&self.url, path);
-        Ok(self.reqwest_client.get(url).send().await?.json().await?)
+        Ok(self
+            .reqwest_client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
     }
 
     async fn post_validator_api<B, T>(
@@ -63,6 +70,11 @@ impl Client {
             .await
     }
 
+    pub async fn get_active_mixnodes(&self) -> Result<Vec<MixNodeBond>, ValidatorAPIError> {
+        self.query_validator_api(&[routes::API_VERSION, routes::MIXNODES, routes::ACTIVE])
+            .await
+    }
+
     pub async fn get_gateways(&self) -> Result<Vec<GatewayBond>, ValidatorAPIError> {
         self.query_validator_api(&[routes::API_VERSION, routes::GATEWAYS])
             .await
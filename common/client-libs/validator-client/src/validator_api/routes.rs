@@ -5,6 +5,7 @@ use network_defaults::VALIDATOR_API_VERSION;
 
 pub const API_VERSION: &str = VALIDATOR_API_VERSION;
 pub const MIXNODES: &str = "mixnodes";
+pub const ACTIVE: &str = "active";
 pub const GATEWAYS: &str = "gateways";
 
 pub const COCONUT_BLIND_SIGN: &str = "blind_sign";
@@ -78,3 +78,78 @@ impl PagedGatewayDelegationsResponse {
         }
     }
 }
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct NodeDelegation {
+    node_identity: IdentityKey,
+    amount: Coin,
+}
+
+impl NodeDelegation {
+    pub fn new(node_identity: IdentityKey, amount: Coin) -> Self {
+        NodeDelegation {
+            node_identity,
+            amount,
+        }
+    }
+
+    pub fn amount(&self) -> &Coin {
+        &self.amount
+    }
+
+    pub fn node_identity(&self) -> IdentityKey {
+        self.node_identity.clone()
+    }
+}
+
+impl Display for NodeDelegation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} delegated towards {}",
+            self.amount.amount, self.amount.denom, self.node_identity
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct PagedDelegatorMixDelegationsResponse {
+    pub delegation_owner: Addr,
+    pub delegations: Vec<NodeDelegation>,
+    pub start_next_after: Option<IdentityKey>,
+}
+
+impl PagedDelegatorMixDelegationsResponse {
+    pub fn new(
+        delegation_owner: Addr,
+        delegations: Vec<NodeDelegation>,
+        start_next_after: Option<IdentityKey>,
+    ) -> Self {
+        PagedDelegatorMixDelegationsResponse {
+            delegation_owner,
+            delegations,
+            start_next_after,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct PagedDelegatorGatewayDelegationsResponse {
+    pub delegation_owner: Addr,
+    pub delegations: Vec<NodeDelegation>,
+    pub start_next_after: Option<IdentityKey>,
+}
+
+impl PagedDelegatorGatewayDelegationsResponse {
+    pub fn new(
+        delegation_owner: Addr,
+        delegations: Vec<NodeDelegation>,
+        start_next_after: Option<IdentityKey>,
+    ) -> Self {
+        PagedDelegatorGatewayDelegationsResponse {
+            delegation_owner,
+            delegations,
+            start_next_after,
+        }
+    }
+}
@@ -0,0 +1,595 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    Addr, Gateway, GovernanceConfig, IdentityKeyRef, MixNode, ProposalId, StateParams, VoteOption,
+};
+use cosmwasm_std::{attr, to_vec, Attribute, StdError, StdResult, Uint128};
+use serde::Serialize;
+
+// cosmwasm 0.14 does not allow contracts to emit custom events just yet. Until it does,
+// each of our events is emitted as a set of attributes of the standard `wasm` event,
+// with the `action` attribute holding its type. Once it's possible, the very same attributes
+// are going to be emitted as `wasm-{event_type}` events instead.
+pub const WASM_EVENT_TYPE: &str = "wasm";
+pub const CUSTOM_EVENT_TYPE_PREFIX: &str = "wasm-";
+// attribute appended by wasmd to the contract attributes of the `wasm` event
+pub const CONTRACT_ADDRESS_KEY: &str = "contract_address";
+// attribute appended by wasmd to all custom events
+pub const CUSTOM_EVENT_CONTRACT_ADDRESS_KEY: &str = "_contract_address";
+
+// event types
+pub const MIXNODE_BONDING_EVENT_TYPE: &str = "mixnode_bonding";
+pub const MIXNODE_UNBONDING_EVENT_TYPE: &str = "mixnode_unbonding";
+pub const GATEWAY_BONDING_EVENT_TYPE: &str = "gateway_bonding";
+pub const GATEWAY_UNBONDING_EVENT_TYPE: &str = "gateway_unbonding";
+pub const MIXNODE_DELEGATION_EVENT_TYPE: &str = "mixnode_delegation";
+pub const MIXNODE_UNDELEGATION_EVENT_TYPE: &str = "mixnode_undelegation";
+pub const GATEWAY_DELEGATION_EVENT_TYPE: &str = "gateway_delegation";
+pub const GATEWAY_UNDELEGATION_EVENT_TYPE: &str = "gateway_undelegation";
+pub const MIXNODE_REWARDING_EVENT_TYPE: &str = "mixnode_rewarding";
+pub const GATEWAY_REWARDING_EVENT_TYPE: &str = "gateway_rewarding";
+pub const PARAMS_UPDATE_EVENT_TYPE: &str = "params_update";
+pub const MIXNODE_PROFIT_MARGIN_UPDATE_EVENT_TYPE: &str = "mixnode_profit_margin_update";
+pub const MIXNODE_CONFIG_UPDATE_EVENT_TYPE: &str = "mixnode_config_update";
+pub const GATEWAY_CONFIG_UPDATE_EVENT_TYPE: &str = "gateway_config_update";
+pub const FAMILY_CREATION_EVENT_TYPE: &str = "family_creation";
+pub const FAMILY_JOIN_EVENT_TYPE: &str = "family_join";
+pub const FAMILY_LEAVE_EVENT_TYPE: &str = "family_leave";
+pub const OPERATOR_REWARD_CLAIM_EVENT_TYPE: &str = "operator_reward_claim";
+pub const DELEGATOR_REWARD_CLAIM_EVENT_TYPE: &str = "delegator_reward_claim";
+pub const PENDING_WITHDRAWALS_CLAIM_EVENT_TYPE: &str = "pending_withdrawals_claim";
+pub const EPOCH_ADVANCE_EVENT_TYPE: &str = "epoch_advance";
+pub const MISBEHAVIOUR_REPORT_EVENT_TYPE: &str = "misbehaviour_report";
+pub const MIXNODE_UNJAILING_EVENT_TYPE: &str = "mixnode_unjailing";
+pub const SLASHED_FUNDS_WITHDRAWAL_EVENT_TYPE: &str = "slashed_funds_withdrawal";
+pub const GOVERNANCE_CONFIG_UPDATE_EVENT_TYPE: &str = "governance_config_update";
+pub const PROPOSAL_SUBMISSION_EVENT_TYPE: &str = "proposal_submission";
+pub const PROPOSAL_VOTE_EVENT_TYPE: &str = "proposal_vote";
+pub const PROPOSAL_EXECUTION_EVENT_TYPE: &str = "proposal_execution";
+pub const PROPOSAL_CANCELLATION_EVENT_TYPE: &str = "proposal_cancellation";
+pub const MIGRATION_EVENT_TYPE: &str = "migrate";
+
+// attribute keys
+pub const EVENT_TYPE_KEY: &str = "action";
+pub const NODE_IDENTITY_KEY: &str = "identity";
+pub const OWNER_KEY: &str = "owner";
+pub const DELEGATOR_KEY: &str = "delegator";
+// all amounts are expressed in the base denomination of the network
+pub const AMOUNT_KEY: &str = "amount";
+pub const EPOCH_KEY: &str = "epoch";
+pub const UPTIME_KEY: &str = "uptime";
+pub const OPERATOR_REWARD_KEY: &str = "operator_reward";
+pub const DELEGATORS_REWARD_KEY: &str = "delegators_reward";
+pub const REWARDING_RESULT_KEY: &str = "result";
+// json-encoded `StateParams`
+pub const PARAMS_KEY: &str = "params";
+pub const OLD_PROFIT_MARGIN_KEY: &str = "old_profit_margin_percent";
+pub const NEW_PROFIT_MARGIN_KEY: &str = "new_profit_margin_percent";
+pub const FAMILY_HEAD_KEY: &str = "family_head";
+pub const HOST_KEY: &str = "host";
+pub const MIX_PORT_KEY: &str = "mix_port";
+pub const VERLOC_PORT_KEY: &str = "verloc_port";
+pub const HTTP_API_PORT_KEY: &str = "http_api_port";
+pub const CLIENTS_PORT_KEY: &str = "clients_port";
+pub const LOCATION_KEY: &str = "location";
+// version of the software run by the node, as opposed to the version of the contract
+pub const NODE_VERSION_KEY: &str = "node_version";
+// whether the claimed reward was added to the stake instead of being paid out
+pub const COMPOUND_KEY: &str = "compound";
+pub const CLAIMED_WITHDRAWALS_KEY: &str = "claimed_withdrawals";
+// whether all the steps of the active set selection have been performed
+pub const SELECTION_FINISHED_KEY: &str = "selection_finished";
+pub const ACTIVE_SET_SIZE_KEY: &str = "active_set_size";
+pub const CONSECUTIVE_EPOCHS_KEY: &str = "consecutive_epochs";
+pub const JAILED_KEY: &str = "jailed";
+pub const SLASHED_KEY: &str = "slashed";
+pub const RECIPIENT_KEY: &str = "recipient";
+// json-encoded `GovernanceConfig`
+pub const GOVERNANCE_CONFIG_KEY: &str = "governance_config";
+pub const PROPOSAL_ID_KEY: &str = "proposal_id";
+pub const PROPOSER_KEY: &str = "proposer";
+pub const VOTER_KEY: &str = "voter";
+pub const VOTE_KEY: &str = "vote";
+pub const SENDER_KEY: &str = "sender";
+pub const PREVIOUS_VERSION_KEY: &str = "previous_version";
+pub const VERSION_KEY: &str = "version";
+
+// possible values of the rewarding result
+pub const REWARDED_RESULT: &str = "rewarded";
+pub const ZERO_UPTIME_RESULT: &str = "zero_uptime";
+pub const BOND_NOT_FOUND_RESULT: &str = "bond_not_found";
+pub const NODE_JAILED_RESULT: &str = "node_jailed";
+
+fn new_bonding_event(
+    event_type: &str,
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, event_type),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(OWNER_KEY, owner),
+        attr(AMOUNT_KEY, amount),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+pub fn new_mixnode_bonding_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_bonding_event(MIXNODE_BONDING_EVENT_TYPE, owner, identity, amount, epoch)
+}
+
+/// `amount` is the total value that is going to be withdrawn, i.e. the bond with all unclaimed rewards.
+pub fn new_mixnode_unbonding_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_bonding_event(MIXNODE_UNBONDING_EVENT_TYPE, owner, identity, amount, epoch)
+}
+
+pub fn new_gateway_bonding_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_bonding_event(GATEWAY_BONDING_EVENT_TYPE, owner, identity, amount, epoch)
+}
+
+pub fn new_gateway_unbonding_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_bonding_event(GATEWAY_UNBONDING_EVENT_TYPE, owner, identity, amount, epoch)
+}
+
+fn new_delegation_event(
+    event_type: &str,
+    delegator: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, event_type),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(DELEGATOR_KEY, delegator),
+        attr(AMOUNT_KEY, amount),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+/// `amount` is the amount added to the delegation by this particular transaction.
+pub fn new_mixnode_delegation_event(
+    delegator: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_delegation_event(
+        MIXNODE_DELEGATION_EVENT_TYPE,
+        delegator,
+        identity,
+        amount,
+        epoch,
+    )
+}
+
+/// `amount` is the total value of the removed delegation, including its rewards.
+pub fn new_mixnode_undelegation_event(
+    delegator: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_delegation_event(
+        MIXNODE_UNDELEGATION_EVENT_TYPE,
+        delegator,
+        identity,
+        amount,
+        epoch,
+    )
+}
+
+pub fn new_gateway_delegation_event(
+    delegator: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_delegation_event(
+        GATEWAY_DELEGATION_EVENT_TYPE,
+        delegator,
+        identity,
+        amount,
+        epoch,
+    )
+}
+
+pub fn new_gateway_undelegation_event(
+    delegator: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_delegation_event(
+        GATEWAY_UNDELEGATION_EVENT_TYPE,
+        delegator,
+        identity,
+        amount,
+        epoch,
+    )
+}
+
+fn new_rewarding_event(
+    event_type: &str,
+    identity: IdentityKeyRef,
+    uptime: u32,
+    result: &str,
+    operator_reward: Uint128,
+    delegators_reward: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, event_type),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(UPTIME_KEY, uptime),
+        attr(REWARDING_RESULT_KEY, result),
+        attr(OPERATOR_REWARD_KEY, operator_reward),
+        attr(DELEGATORS_REWARD_KEY, delegators_reward),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+pub fn new_mixnode_rewarding_event(
+    identity: IdentityKeyRef,
+    uptime: u32,
+    operator_reward: Uint128,
+    delegators_reward: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_rewarding_event(
+        MIXNODE_REWARDING_EVENT_TYPE,
+        identity,
+        uptime,
+        REWARDED_RESULT,
+        operator_reward,
+        delegators_reward,
+        epoch,
+    )
+}
+
+/// Rewarding of the mixnode that did not result in any tokens being distributed,
+/// with the reason being one of the non-`REWARDED_RESULT` results.
+pub fn new_mixnode_not_rewarded_event(
+    identity: IdentityKeyRef,
+    uptime: u32,
+    result: &str,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_rewarding_event(
+        MIXNODE_REWARDING_EVENT_TYPE,
+        identity,
+        uptime,
+        result,
+        Uint128(0),
+        Uint128(0),
+        epoch,
+    )
+}
+
+pub fn new_gateway_rewarding_event(
+    identity: IdentityKeyRef,
+    uptime: u32,
+    operator_reward: Uint128,
+    delegators_reward: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_rewarding_event(
+        GATEWAY_REWARDING_EVENT_TYPE,
+        identity,
+        uptime,
+        REWARDED_RESULT,
+        operator_reward,
+        delegators_reward,
+        epoch,
+    )
+}
+
+/// Rewarding of the gateway that did not result in any tokens being distributed,
+/// with the reason being one of the non-`REWARDED_RESULT` results.
+pub fn new_gateway_not_rewarded_event(
+    identity: IdentityKeyRef,
+    uptime: u32,
+    result: &str,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_rewarding_event(
+        GATEWAY_REWARDING_EVENT_TYPE,
+        identity,
+        uptime,
+        result,
+        Uint128(0),
+        Uint128(0),
+        epoch,
+    )
+}
+
+fn to_json_string<T: Serialize>(value: &T) -> StdResult<String> {
+    String::from_utf8(to_vec(value)?).map_err(StdError::invalid_utf8)
+}
+
+pub fn new_params_update_event(params: &StateParams, epoch: u32) -> StdResult<Vec<Attribute>> {
+    Ok(vec![
+        attr(EVENT_TYPE_KEY, PARAMS_UPDATE_EVENT_TYPE),
+        attr(PARAMS_KEY, to_json_string(params)?),
+        attr(EPOCH_KEY, epoch),
+    ])
+}
+
+pub fn new_mixnode_profit_margin_update_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    old_profit_margin_percent: u8,
+    new_profit_margin_percent: u8,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, MIXNODE_PROFIT_MARGIN_UPDATE_EVENT_TYPE),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(OWNER_KEY, owner),
+        attr(OLD_PROFIT_MARGIN_KEY, old_profit_margin_percent),
+        attr(NEW_PROFIT_MARGIN_KEY, new_profit_margin_percent),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+pub fn new_mixnode_config_update_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    mix_node: &MixNode,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, MIXNODE_CONFIG_UPDATE_EVENT_TYPE),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(OWNER_KEY, owner),
+        attr(HOST_KEY, &mix_node.host),
+        attr(MIX_PORT_KEY, mix_node.mix_port),
+        attr(VERLOC_PORT_KEY, mix_node.verloc_port),
+        attr(HTTP_API_PORT_KEY, mix_node.http_api_port),
+        attr(NODE_VERSION_KEY, &mix_node.version),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+pub fn new_gateway_config_update_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    gateway: &Gateway,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, GATEWAY_CONFIG_UPDATE_EVENT_TYPE),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(OWNER_KEY, owner),
+        attr(HOST_KEY, &gateway.host),
+        attr(MIX_PORT_KEY, gateway.mix_port),
+        attr(CLIENTS_PORT_KEY, gateway.clients_port),
+        attr(LOCATION_KEY, &gateway.location),
+        attr(NODE_VERSION_KEY, &gateway.version),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+fn new_family_event(
+    event_type: &str,
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    family_head: IdentityKeyRef,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, event_type),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(OWNER_KEY, owner),
+        attr(FAMILY_HEAD_KEY, family_head),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+/// Families are identified by the identity of their head, so it's both the `identity` and the `family_head`.
+pub fn new_family_creation_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_family_event(FAMILY_CREATION_EVENT_TYPE, owner, identity, identity, epoch)
+}
+
+pub fn new_family_join_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    family_head: IdentityKeyRef,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_family_event(FAMILY_JOIN_EVENT_TYPE, owner, identity, family_head, epoch)
+}
+
+pub fn new_family_leave_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    family_head: IdentityKeyRef,
+    epoch: u32,
+) -> Vec<Attribute> {
+    new_family_event(FAMILY_LEAVE_EVENT_TYPE, owner, identity, family_head, epoch)
+}
+
+pub fn new_operator_reward_claim_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    compound: bool,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, OPERATOR_REWARD_CLAIM_EVENT_TYPE),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(OWNER_KEY, owner),
+        attr(AMOUNT_KEY, amount),
+        attr(COMPOUND_KEY, compound),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+pub fn new_delegator_reward_claim_event(
+    delegator: &Addr,
+    identity: IdentityKeyRef,
+    amount: Uint128,
+    compound: bool,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, DELEGATOR_REWARD_CLAIM_EVENT_TYPE),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(DELEGATOR_KEY, delegator),
+        attr(AMOUNT_KEY, amount),
+        attr(COMPOUND_KEY, compound),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+/// `amount` is the total value of all the withdrawals paid out by this particular transaction.
+pub fn new_pending_withdrawals_claim_event(
+    owner: &Addr,
+    claimed_withdrawals: usize,
+    amount: Uint128,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, PENDING_WITHDRAWALS_CLAIM_EVENT_TYPE),
+        attr(OWNER_KEY, owner),
+        attr(CLAIMED_WITHDRAWALS_KEY, claimed_withdrawals),
+        attr(AMOUNT_KEY, amount),
+    ]
+}
+
+/// The selection of the active set might be spread over multiple transactions. Until the last
+/// of them, `epoch` is the one still in progress and `active_set_size` is not known yet.
+pub fn new_epoch_advance_event(epoch: u32, active_set_size: Option<usize>) -> Vec<Attribute> {
+    let mut attributes = vec![
+        attr(EVENT_TYPE_KEY, EPOCH_ADVANCE_EVENT_TYPE),
+        attr(EPOCH_KEY, epoch),
+        attr(SELECTION_FINISHED_KEY, active_set_size.is_some()),
+    ];
+    if let Some(active_set_size) = active_set_size {
+        attributes.push(attr(ACTIVE_SET_SIZE_KEY, active_set_size))
+    }
+    attributes
+}
+
+/// `slashed` is the part of the bond taken away if this report caused the node to get jailed.
+pub fn new_misbehaviour_report_event(
+    identity: IdentityKeyRef,
+    uptime: u32,
+    consecutive_epochs: u32,
+    jailed: bool,
+    slashed: Uint128,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, MISBEHAVIOUR_REPORT_EVENT_TYPE),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(UPTIME_KEY, uptime),
+        attr(CONSECUTIVE_EPOCHS_KEY, consecutive_epochs),
+        attr(JAILED_KEY, jailed),
+        attr(SLASHED_KEY, slashed),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+pub fn new_mixnode_unjailing_event(
+    owner: &Addr,
+    identity: IdentityKeyRef,
+    epoch: u32,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, MIXNODE_UNJAILING_EVENT_TYPE),
+        attr(NODE_IDENTITY_KEY, identity),
+        attr(OWNER_KEY, owner),
+        attr(EPOCH_KEY, epoch),
+    ]
+}
+
+pub fn new_slashed_funds_withdrawal_event(recipient: &Addr, amount: Uint128) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, SLASHED_FUNDS_WITHDRAWAL_EVENT_TYPE),
+        attr(RECIPIENT_KEY, recipient),
+        attr(AMOUNT_KEY, amount),
+    ]
+}
+
+pub fn new_governance_config_update_event(
+    governance_config: &GovernanceConfig,
+) -> StdResult<Vec<Attribute>> {
+    Ok(vec![
+        attr(EVENT_TYPE_KEY, GOVERNANCE_CONFIG_UPDATE_EVENT_TYPE),
+        attr(GOVERNANCE_CONFIG_KEY, to_json_string(governance_config)?),
+    ])
+}
+
+pub fn new_proposal_submission_event(proposal_id: ProposalId, proposer: &Addr) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, PROPOSAL_SUBMISSION_EVENT_TYPE),
+        attr(PROPOSAL_ID_KEY, proposal_id),
+        attr(PROPOSER_KEY, proposer),
+    ]
+}
+
+pub fn new_proposal_vote_event(
+    proposal_id: ProposalId,
+    voter: &Addr,
+    vote: VoteOption,
+) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, PROPOSAL_VOTE_EVENT_TYPE),
+        attr(PROPOSAL_ID_KEY, proposal_id),
+        attr(VOTER_KEY, voter),
+        attr(VOTE_KEY, vote),
+    ]
+}
+
+/// Emitted right after the event of the change applied by the proposal.
+pub fn new_proposal_execution_event(proposal_id: ProposalId) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, PROPOSAL_EXECUTION_EVENT_TYPE),
+        attr(PROPOSAL_ID_KEY, proposal_id),
+    ]
+}
+
+/// `sender` is either the proposer withdrawing its proposal or the contract owner vetoing it.
+pub fn new_proposal_cancellation_event(proposal_id: ProposalId, sender: &Addr) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, PROPOSAL_CANCELLATION_EVENT_TYPE),
+        attr(PROPOSAL_ID_KEY, proposal_id),
+        attr(SENDER_KEY, sender),
+    ]
+}
+
+pub fn new_migration_event(previous_version: u32, version: u32) -> Vec<Attribute> {
+    vec![
+        attr(EVENT_TYPE_KEY, MIGRATION_EVENT_TYPE),
+        attr(PREVIOUS_VERSION_KEY, previous_version),
+        attr(VERSION_KEY, version),
+    ]
+}
@@ -0,0 +1,175 @@
+// due to code generated by JsonSchema
+#![allow(clippy::field_reassign_with_default)]
+
+use crate::{Addr, StateParams};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+pub type ProposalId = u64;
+
+/// Set of addresses allowed to propose and vote on changes to the contract state parameters
+/// and to the governance itself.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct GovernanceConfig {
+    pub members: Vec<Addr>,
+    /// Number of members that have to approve a proposal for it to pass. It must be a majority of them.
+    pub threshold: u32,
+    /// Time, in hours, during which the members can vote on a proposal.
+    pub voting_period: u32,
+    /// Time, in hours, that has to pass after the proposal passed before it can be executed.
+    pub timelock: u32,
+    /// Time, in hours, after the timelock during which the passed proposal can be executed.
+    pub execution_period: u32,
+}
+
+impl GovernanceConfig {
+    pub fn is_member(&self, address: &Addr) -> bool {
+        self.members.contains(address)
+    }
+}
+
+impl Display for GovernanceConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} out of {} members; voting period: {}h; timelock: {}h; execution period: {}h",
+            self.threshold,
+            self.members.len(),
+            self.voting_period,
+            self.timelock,
+            self.execution_period
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteOption {
+    Yes,
+    No,
+}
+
+impl Display for VoteOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            VoteOption::Yes => write!(f, "yes"),
+            VoteOption::No => write!(f, "no"),
+        }
+    }
+}
+
+/// Change applied to the contract once the proposal is executed.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalAction {
+    UpdateStateParams(StateParams),
+    UpdateGovernanceConfig(GovernanceConfig),
+}
+
+impl Display for ProposalAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ProposalAction::UpdateStateParams(_) => write!(f, "state params update"),
+            ProposalAction::UpdateGovernanceConfig(config) => {
+                write!(f, "governance update ({})", config)
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    /// The proposal is still being voted on.
+    Open,
+    /// The proposal got enough approvals and can be executed once its timelock expires,
+    /// until its execution deadline.
+    Passed,
+    /// The proposal can no longer reach the required number of approvals.
+    Rejected,
+    /// The proposed change has been applied.
+    Executed,
+    /// The proposal was withdrawn by its proposer or vetoed by the contract owner.
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct Proposal {
+    pub id: ProposalId,
+    pub proposer: Addr,
+    pub description: String,
+    pub action: ProposalAction,
+    /// Governance in force when the proposal was submitted. Its members vote on the proposal and
+    /// its threshold and timelock apply, even if the governance has changed since.
+    pub governance: GovernanceConfig,
+    pub status: ProposalStatus,
+    pub yes_votes: u32,
+    pub no_votes: u32,
+    /// Unix timestamp (in seconds) of the proposal submission.
+    pub submitted_at: u64,
+    /// Unix timestamp (in seconds) after which no more votes are accepted.
+    pub voting_ends_at: u64,
+    /// Unix timestamp (in seconds) after which the passed proposal can be executed.
+    pub executable_at: Option<u64>,
+    /// Unix timestamp (in seconds) after which the passed proposal can no longer be executed.
+    pub expires_at: Option<u64>,
+}
+
+impl Display for Proposal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "proposal {} by {} ({:?}) of {}: {}; yes: {}, no: {}",
+            self.id,
+            self.proposer,
+            self.status,
+            self.action,
+            self.description,
+            self.yes_votes,
+            self.no_votes
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct ProposalVote {
+    pub voter: Addr,
+    pub vote: VoteOption,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct PagedProposalsResponse {
+    pub proposals: Vec<Proposal>,
+    pub start_next_after: Option<ProposalId>,
+}
+
+impl PagedProposalsResponse {
+    pub fn new(proposals: Vec<Proposal>, start_next_after: Option<ProposalId>) -> Self {
+        PagedProposalsResponse {
+            proposals,
+            start_next_after,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct PagedProposalVotesResponse {
+    pub proposal_id: ProposalId,
+    pub votes: Vec<ProposalVote>,
+    pub start_next_after: Option<Addr>,
+}
+
+impl PagedProposalVotesResponse {
+    pub fn new(
+        proposal_id: ProposalId,
+        votes: Vec<ProposalVote>,
+        start_next_after: Option<Addr>,
+    ) -> Self {
+        PagedProposalVotesResponse {
+            proposal_id,
+            votes,
+            start_next_after,
+        }
+    }
+}
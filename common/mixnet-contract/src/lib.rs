@@ -2,14 +2,33 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod delegation;
+pub mod events;
 mod gateway;
+mod governance;
+mod misbehaviour;
 mod mixnode;
 mod msg;
 mod types;
+mod withdrawal;
 
 pub use cosmwasm_std::{Addr, Coin};
-pub use delegation::{Delegation, PagedGatewayDelegationsResponse, PagedMixDelegationsResponse};
+pub use delegation::{
+    Delegation, NodeDelegation, PagedDelegatorGatewayDelegationsResponse,
+    PagedDelegatorMixDelegationsResponse, PagedGatewayDelegationsResponse,
+    PagedMixDelegationsResponse,
+};
 pub use gateway::{Gateway, GatewayBond, GatewayOwnershipResponse, PagedGatewayResponse};
-pub use mixnode::{Layer, MixNode, MixNodeBond, MixOwnershipResponse, PagedMixnodeResponse};
+pub use governance::{
+    GovernanceConfig, PagedProposalVotesResponse, PagedProposalsResponse, Proposal, ProposalAction,
+    ProposalId, ProposalStatus, ProposalVote, VoteOption,
+};
+pub use misbehaviour::{MixnodeMisbehaviour, PagedMixnodeMisbehaviourResponse};
+pub use mixnode::{
+    family_join_message, EpochResponse, Layer, MixNode, MixNodeBond, MixOwnershipResponse,
+    PagedMixnodeResponse,
+};
 pub use msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-pub use types::{IdentityKey, IdentityKeyRef, LayerDistribution, SphinxKey, StateParams};
+pub use types::{
+    ContractVersion, IdentityKey, IdentityKeyRef, LayerDistribution, SphinxKey, StateParams,
+};
+pub use withdrawal::{PagedPendingWithdrawalsResponse, PendingWithdrawal};
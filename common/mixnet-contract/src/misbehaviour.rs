@@ -0,0 +1,62 @@
+// due to code generated by JsonSchema
+#![allow(clippy::field_reassign_with_default)]
+
+use crate::IdentityKey;
+use cosmwasm_std::Coin;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Report of a mixnode whose uptime during an epoch was below the required threshold.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct MixnodeMisbehaviour {
+    pub epoch: u32,
+    /// Percentage value in range 0-100.
+    pub uptime: u32,
+    pub block_height: u64,
+    /// Number of consecutive epochs, including this one, the node has been below the threshold.
+    pub consecutive_epochs: u32,
+    /// Whether this report caused the node to get jailed.
+    pub jailed: bool,
+    /// Part of the bond taken away from the operator upon getting jailed.
+    pub slashed: Coin,
+}
+
+impl Display for MixnodeMisbehaviour {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "uptime of {}% in epoch {} ({} consecutive epochs)",
+            self.uptime, self.epoch, self.consecutive_epochs
+        )?;
+        if self.jailed {
+            write!(
+                f,
+                " resulted in jailing and slashing of {} {}",
+                self.slashed.amount, self.slashed.denom
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct PagedMixnodeMisbehaviourResponse {
+    pub identity: IdentityKey,
+    pub reports: Vec<MixnodeMisbehaviour>,
+    pub start_next_after: Option<u32>,
+}
+
+impl PagedMixnodeMisbehaviourResponse {
+    pub fn new(
+        identity: IdentityKey,
+        reports: Vec<MixnodeMisbehaviour>,
+        start_next_after: Option<u32>,
+    ) -> Self {
+        PagedMixnodeMisbehaviourResponse {
+            identity,
+            reports,
+            start_next_after,
+        }
+    }
+}
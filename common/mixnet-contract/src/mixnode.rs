@@ -35,16 +35,38 @@ pub struct MixNodeBond {
     pub total_delegation: Coin,
     pub owner: Addr,
     pub layer: Layer,
+    /// Percentage (0-100) of the delegators' rewards taken by the operator as its commission.
+    #[serde(default)]
+    pub profit_margin_percent: u8,
+    /// Identity of the head of the family of nodes run by the same operator, if the node belongs to one.
+    #[serde(default)]
+    pub family: Option<IdentityKey>,
+    /// Jailed nodes are excluded from the network until their owner unjails them.
+    #[serde(default)]
+    pub jailed: bool,
+    /// Unix timestamp (in seconds) before which a jailed node can't be unjailed.
+    #[serde(default)]
+    pub jailed_until: u64,
     pub mix_node: MixNode,
 }
 
 impl MixNodeBond {
-    pub fn new(bond_amount: Coin, owner: Addr, layer: Layer, mix_node: MixNode) -> Self {
+    pub fn new(
+        bond_amount: Coin,
+        owner: Addr,
+        layer: Layer,
+        profit_margin_percent: u8,
+        mix_node: MixNode,
+    ) -> Self {
         MixNodeBond {
             total_delegation: coin(0, &bond_amount.denom),
             bond_amount,
             owner,
             layer,
+            profit_margin_percent,
+            family: None,
+            jailed: false,
+            jailed_until: 0,
             mix_node,
         }
     }
@@ -64,6 +86,22 @@ impl MixNodeBond {
     pub fn mix_node(&self) -> &MixNode {
         &self.mix_node
     }
+
+    pub fn profit_margin_percent(&self) -> u8 {
+        self.profit_margin_percent
+    }
+
+    pub fn family(&self) -> Option<&IdentityKey> {
+        self.family.as_ref()
+    }
+
+    pub fn is_jailed(&self) -> bool {
+        self.jailed
+    }
+
+    pub fn jailed_until(&self) -> u64 {
+        self.jailed_until
+    }
 }
 
 impl Display for MixNodeBond {
@@ -76,6 +114,12 @@ impl Display for MixNodeBond {
     }
 }
 
+/// Message the head of a family has to sign with its identity key to let the member join it.
+/// It's bound to the particular contract, so that the consent can't be reused elsewhere.
+pub fn family_join_message(contract_address: &str, family_head: &str, member: &str) -> String {
+    format!("{}:{}:{}", contract_address, family_head, member)
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
 pub struct PagedMixnodeResponse {
     pub nodes: Vec<MixNodeBond>,
@@ -97,6 +141,26 @@ impl PagedMixnodeResponse {
     }
 }
 
+/// The current epoch, during which the mixnodes of its active set are routing traffic.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct EpochResponse {
+    pub epoch: u32,
+    /// Unix timestamp (in seconds) at which the epoch has started.
+    pub epoch_start: u64,
+    /// Unix timestamp (in seconds) after which the next active set can be selected.
+    pub epoch_end: u64,
+}
+
+impl EpochResponse {
+    pub fn new(epoch: u32, epoch_start: u64, epoch_end: u64) -> Self {
+        EpochResponse {
+            epoch,
+            epoch_start,
+            epoch_end,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
 pub struct MixOwnershipResponse {
     pub address: Addr,
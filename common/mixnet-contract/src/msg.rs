@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::StateParams;
-use crate::{Gateway, IdentityKey, MixNode};
+use crate::{Gateway, GovernanceConfig, IdentityKey, MixNode, ProposalId, VoteOption};
 use cosmwasm_std::Addr;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -15,13 +15,65 @@ pub struct InstantiateMsg {}
 pub enum ExecuteMsg {
     BondMixnode {
         mix_node: MixNode,
+        // percentage value in range 0-100
+        profit_margin_percent: u8,
     },
     UnbondMixnode {},
+    UpdateMixnodeConfig {
+        host: String,
+        mix_port: u16,
+        verloc_port: u16,
+        http_api_port: u16,
+        version: String,
+    },
+    UpdateMixnodeProfitMargin {
+        // percentage value in range 0-100
+        profit_margin_percent: u8,
+    },
+    ClaimOperatorReward {
+        // whether the reward should be added to the bond instead of being paid out
+        compound: bool,
+    },
+    // makes the sender's mixnode the head of a new family
+    CreateFamily {},
+    JoinFamily {
+        family_head: IdentityKey,
+        // base58 encoded signature of the `family_join_message`, made with the identity key of the family head
+        signature: String,
+    },
+    LeaveFamily {},
+    UnjailMixnode {},
     BondGateway {
         gateway: Gateway,
     },
     UnbondGateway {},
-    UpdateStateParams(StateParams),
+    UpdateGatewayConfig {
+        host: String,
+        mix_port: u16,
+        clients_port: u16,
+        location: String,
+        version: String,
+    },
+    ProposeStateParams {
+        params: StateParams,
+        description: String,
+    },
+    ProposeGovernanceConfig {
+        config: GovernanceConfig,
+        description: String,
+    },
+    VoteOnProposal {
+        proposal_id: ProposalId,
+        vote: VoteOption,
+    },
+    // applies the change of the passed proposal once its timelock has expired
+    ExecuteProposal {
+        proposal_id: ProposalId,
+    },
+    // the proposer can withdraw its proposal, while the owner can veto any that was not executed yet
+    CancelProposal {
+        proposal_id: ProposalId,
+    },
 
     DelegateToMixnode {
         mix_identity: IdentityKey,
@@ -31,6 +83,18 @@ pub enum ExecuteMsg {
         mix_identity: IdentityKey,
     },
 
+    ClaimDelegatorReward {
+        mix_identity: IdentityKey,
+        // whether the reward should be added to the delegation instead of being paid out
+        compound: bool,
+    },
+
+    // at most `limit` of the oldest withdrawals are looked at, so that a long queue can be
+    // claimed in several transactions
+    ClaimPendingWithdrawals {
+        limit: Option<u32>,
+    },
+
     DelegateToGateway {
         gateway_identity: IdentityKey,
     },
@@ -50,6 +114,21 @@ pub enum ExecuteMsg {
         // percentage value in range 0-100
         uptime: u32,
     },
+
+    // selects the active set of mixnodes for the next epoch
+    AdvanceEpoch {},
+
+    // reports mixnode whose uptime during the current epoch was below the required threshold
+    ReportMisbehaviour {
+        identity: IdentityKey,
+        // percentage value in range 0-100
+        uptime: u32,
+    },
+
+    // sends the stake slashed from jailed mixnodes to the given address
+    WithdrawSlashedFunds {
+        recipient: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -88,7 +167,51 @@ pub enum QueryMsg {
         gateway_identity: IdentityKey,
         address: Addr,
     },
+    GetDelegatorMixDelegations {
+        address: Addr,
+        start_after: Option<IdentityKey>,
+        limit: Option<u32>,
+    },
+    GetDelegatorGatewayDelegations {
+        address: Addr,
+        start_after: Option<IdentityKey>,
+        limit: Option<u32>,
+    },
     LayerDistribution {},
+    GetPendingWithdrawals {
+        address: Addr,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetEpoch {},
+    GetActiveSet {
+        start_after: Option<IdentityKey>,
+        limit: Option<u32>,
+    },
+    GetJailedMixNodes {
+        start_after: Option<IdentityKey>,
+        limit: Option<u32>,
+    },
+    GetMixnodeMisbehaviour {
+        identity: IdentityKey,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    GetSlashedFunds {},
+    GetContractVersion {},
+    GetGovernanceConfig {},
+    GetProposal {
+        proposal_id: ProposalId,
+    },
+    GetProposals {
+        start_after: Option<ProposalId>,
+        limit: Option<u32>,
+    },
+    GetProposalVotes {
+        proposal_id: ProposalId,
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
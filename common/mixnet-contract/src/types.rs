@@ -37,6 +37,10 @@ pub struct StateParams {
     pub mixnode_delegation_reward_rate: Decimal, // annual reward rate, expressed as a decimal like 1.25
     pub gateway_delegation_reward_rate: Decimal, // annual reward rate, expressed as a decimal like 1.25
     pub mixnode_active_set_size: u32,
+    pub unbonding_period: u32, // time after which unbonded stake can be withdrawn, expressed in hours
+    pub minimum_mixnode_uptime: u32, // uptime, in percents, below which mixnode is considered to be misbehaving
+    pub mixnode_jail_epochs: u32, // number of consecutive epochs of misbehaviour after which mixnode gets jailed
+    pub mixnode_slashing_fraction: Decimal, // fraction of the bond taken away upon jailing, expressed as a decimal like 0.05
 }
 
 impl Display for StateParams {
@@ -67,12 +71,39 @@ impl Display for StateParams {
         )?;
         write!(
             f,
-            "mixnode active set size: {} ]",
+            "mixnode active set size: {}; ",
             self.mixnode_active_set_size
+        )?;
+        write!(f, "unbonding period: {}; ", self.unbonding_period)?;
+        write!(
+            f,
+            "minimum mixnode uptime: {}; ",
+            self.minimum_mixnode_uptime
+        )?;
+        write!(f, "mixnode jail epochs: {}; ", self.mixnode_jail_epochs)?;
+        write!(
+            f,
+            "mixnode slashing fraction: {} ]",
+            self.mixnode_slashing_fraction
         )
     }
 }
 
+/// Metadata identifying the deployed contract, modelled after the cw2 specification,
+/// used to determine which migrations have to be applied to its state.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractVersion {
+    pub contract: String,
+    // version of the storage layout, bumped with every change requiring a migration
+    pub version: u32,
+}
+
+impl Display for ContractVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (storage version {})", self.contract, self.version)
+    }
+}
+
 // type aliases for better reasoning about available data
 pub type IdentityKey = String;
 pub type IdentityKeyRef<'a> = &'a str;
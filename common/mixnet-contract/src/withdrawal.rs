@@ -0,0 +1,65 @@
+// due to code generated by JsonSchema
+#![allow(clippy::field_reassign_with_default)]
+
+use crate::Addr;
+use cosmwasm_std::Coin;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Funds released by unbonding or undelegating, which can only be claimed
+/// after the unbonding period has passed.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct PendingWithdrawal {
+    pub id: u64,
+    pub owner: Addr,
+    pub amount: Coin,
+    /// Unix timestamp (in seconds) from which the funds can be claimed.
+    pub available_at: u64,
+}
+
+impl PendingWithdrawal {
+    pub fn new(id: u64, owner: Addr, amount: Coin, available_at: u64) -> Self {
+        PendingWithdrawal {
+            id,
+            owner,
+            amount,
+            available_at,
+        }
+    }
+
+    pub fn is_available(&self, current_time: u64) -> bool {
+        current_time >= self.available_at
+    }
+}
+
+impl Display for PendingWithdrawal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} withdrawable by {} from {}",
+            self.amount.amount, self.amount.denom, self.owner, self.available_at
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, JsonSchema)]
+pub struct PagedPendingWithdrawalsResponse {
+    pub owner: Addr,
+    pub withdrawals: Vec<PendingWithdrawal>,
+    pub start_next_after: Option<u64>,
+}
+
+impl PagedPendingWithdrawalsResponse {
+    pub fn new(
+        owner: Addr,
+        withdrawals: Vec<PendingWithdrawal>,
+        start_next_after: Option<u64>,
+    ) -> Self {
+        PagedPendingWithdrawalsResponse {
+            owner,
+            withdrawals,
+            start_next_after,
+        }
+    }
+}
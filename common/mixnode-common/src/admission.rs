@@ -0,0 +1,343 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Admission control of incoming mixnet connections, limiting the total number of connections,
+//! connections per source ip address and the rate at which packets are read from each connection.
+
+use log::*;
+use nymnoise::KnownNodes;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const DEFAULT_MAXIMUM_CONNECTIONS: usize = 2048;
+const DEFAULT_MAXIMUM_CONNECTIONS_PER_IP: usize = 16;
+const DEFAULT_MAXIMUM_PACKETS_PER_SECOND: u32 = 10_000;
+const DEFAULT_UNKNOWN_PEER_MAXIMUM_CONNECTIONS_PER_IP: usize = 2;
+const DEFAULT_UNKNOWN_PEER_MAXIMUM_PACKETS_PER_SECOND: u32 = 100;
+
+#[derive(Debug, PartialEq)]
+pub enum AdmissionError {
+    TooManyConnections,
+    TooManyConnectionsFromIp(IpAddr),
+    UnknownPeerRefused(IpAddr),
+}
+
+impl Display for AdmissionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AdmissionError::TooManyConnections => {
+                write!(f, "maximum number of connections has been reached")
+            }
+            AdmissionError::TooManyConnectionsFromIp(ip) => write!(
+                f,
+                "maximum number of connections from {} has been reached",
+                ip
+            ),
+            AdmissionError::UnknownPeerRefused(ip) => {
+                write!(f, "{} does not belong to any node in the topology", ip)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AdmissionError {}
+
+/// Limits applied to incoming connections. Setting any of the packets per second limits
+/// to 0 disables rate limiting, while setting the connection limit of unknown peers to 0
+/// refuses their connections altogether.
+#[derive(Clone, Debug)]
+pub struct AdmissionConfig {
+    maximum_connections: usize,
+    maximum_connections_per_ip: usize,
+    maximum_packets_per_second: u32,
+    unknown_peer_maximum_connections_per_ip: usize,
+    unknown_peer_maximum_packets_per_second: u32,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        AdmissionConfig {
+            maximum_connections: DEFAULT_MAXIMUM_CONNECTIONS,
+            maximum_connections_per_ip: DEFAULT_MAXIMUM_CONNECTIONS_PER_IP,
+            maximum_packets_per_second: DEFAULT_MAXIMUM_PACKETS_PER_SECOND,
+            unknown_peer_maximum_connections_per_ip:
+                DEFAULT_UNKNOWN_PEER_MAXIMUM_CONNECTIONS_PER_IP,
+            unknown_peer_maximum_packets_per_second:
+                DEFAULT_UNKNOWN_PEER_MAXIMUM_PACKETS_PER_SECOND,
+        }
+    }
+}
+
+impl AdmissionConfig {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn maximum_connections(mut self, maximum_connections: usize) -> Self {
+        self.maximum_connections = maximum_connections;
+        self
+    }
+
+    pub fn maximum_connections_per_ip(mut self, maximum_connections_per_ip: usize) -> Self {
+        self.maximum_connections_per_ip = maximum_connections_per_ip;
+        self
+    }
+
+    pub fn maximum_packets_per_second(mut self, maximum_packets_per_second: u32) -> Self {
+        self.maximum_packets_per_second = maximum_packets_per_second;
+        self
+    }
+
+    pub fn unknown_peer_maximum_connections_per_ip(mut self, maximum_connections: usize) -> Self {
+        self.unknown_peer_maximum_connections_per_ip = maximum_connections;
+        self
+    }
+
+    pub fn unknown_peer_maximum_packets_per_second(mut self, maximum_packets: u32) -> Self {
+        self.unknown_peer_maximum_packets_per_second = maximum_packets;
+        self
+    }
+}
+
+#[derive(Default)]
+struct AdmissionState {
+    total_connections: usize,
+    connections_per_ip: HashMap<IpAddr, usize>,
+}
+
+/// Decides whether incoming connections should be accepted.
+// Note: you should NEVER create more than a single instance of this using 'new()'.
+// You should always use .clone() to create additional instances
+#[derive(Clone)]
+pub struct ConnectionAdmission {
+    config: Arc<AdmissionConfig>,
+    known_nodes: KnownNodes,
+    state: Arc<Mutex<AdmissionState>>,
+}
+
+impl ConnectionAdmission {
+    pub fn new(config: AdmissionConfig, known_nodes: KnownNodes) -> Self {
+        ConnectionAdmission {
+            config: Arc::new(config),
+            known_nodes,
+            state: Arc::new(Mutex::new(AdmissionState::default())),
+        }
+    }
+
+    fn is_known_peer(&self, ip: IpAddr) -> bool {
+        // if we haven't obtained the topology yet, give everyone the benefit of the doubt
+        self.known_nodes.is_empty() || self.known_nodes.contains_ip(ip)
+    }
+
+    /// Attempts to admit a new connection from `remote`. The returned permit has to be kept
+    /// alive for the duration of the connection.
+    pub fn try_admit(&self, remote: SocketAddr) -> Result<AdmissionPermit, AdmissionError> {
+        let ip = remote.ip();
+        let (maximum_connections_per_ip, maximum_packets_per_second) = if self.is_known_peer(ip) {
+            (
+                self.config.maximum_connections_per_ip,
+                self.config.maximum_packets_per_second,
+            )
+        } else {
+            if self.config.unknown_peer_maximum_connections_per_ip == 0 {
+                return Err(AdmissionError::UnknownPeerRefused(ip));
+            }
+            (
+                self.config.unknown_peer_maximum_connections_per_ip,
+                self.config.unknown_peer_maximum_packets_per_second,
+            )
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if state.total_connections >= self.config.maximum_connections {
+            return Err(AdmissionError::TooManyConnections);
+        }
+        let ip_connections = state.connections_per_ip.entry(ip).or_insert(0);
+        if *ip_connections >= maximum_connections_per_ip {
+            return Err(AdmissionError::TooManyConnectionsFromIp(ip));
+        }
+        *ip_connections += 1;
+        state.total_connections += 1;
+
+        Ok(AdmissionPermit {
+            admission: self.clone(),
+            ip,
+            rate_limiter: RateLimiter::new(maximum_packets_per_second, Instant::now()),
+        })
+    }
+
+    fn release(&self, ip: IpAddr) {
+        let mut state = self.state.lock().unwrap();
+        state.total_connections -= 1;
+        if let Some(ip_connections) = state.connections_per_ip.get_mut(&ip) {
+            *ip_connections -= 1;
+            if *ip_connections == 0 {
+                state.connections_per_ip.remove(&ip);
+            }
+        }
+    }
+}
+
+/// Permit of an admitted connection. Once dropped, the connection is no longer counted
+/// towards the limits.
+pub struct AdmissionPermit {
+    admission: ConnectionAdmission,
+    ip: IpAddr,
+    rate_limiter: RateLimiter,
+}
+
+impl AdmissionPermit {
+    /// Should be called for every packet read from the connection. If the connection exceeds
+    /// its packets per second limit, waits until it's allowed to read another packet.
+    pub async fn throttle(&mut self) {
+        if let Some(delay) = self.rate_limiter.consume(Instant::now()) {
+            trace!("throttling connection from {} for {:?}", self.ip, delay);
+            tokio::time::sleep(delay).await
+        }
+    }
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        self.admission.release(self.ip)
+    }
+}
+
+/// Token bucket allowing bursts of up to a second worth of packets.
+struct RateLimiter {
+    // packets per second, 0 means unlimited
+    rate: u32,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    fn new(rate: u32, now: Instant) -> Self {
+        RateLimiter {
+            rate,
+            tokens: rate as f64,
+            last_refill: now,
+        }
+    }
+
+    /// Consumes a single token, returning for how long the caller should wait
+    /// if there were no tokens available.
+    fn consume(&mut self, now: Instant) -> Option<Duration> {
+        if self.rate == 0 {
+            return None;
+        }
+
+        let rate = self.rate as f64;
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(rate);
+        self.last_refill = now;
+
+        // note that the tokens can go negative, meaning subsequent packets will wait even longer
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            None
+        } else {
+            Some(Duration::from_secs_f64(-self.tokens / rate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::asymmetric::encryption;
+
+    fn address(ip: [u8; 4]) -> SocketAddr {
+        SocketAddr::from((ip, 1789))
+    }
+
+    fn known_nodes(addresses: &[SocketAddr]) -> KnownNodes {
+        let key = encryption::PublicKey::from_bytes(&[42; 32]).unwrap();
+        let known_nodes = KnownNodes::new();
+        known_nodes.replace(addresses.iter().map(|address| (*address, key)));
+        known_nodes
+    }
+
+    #[test]
+    fn connection_limits_are_enforced() {
+        let config = AdmissionConfig::new()
+            .maximum_connections(3)
+            .maximum_connections_per_ip(2);
+        let admission = ConnectionAdmission::new(config, KnownNodes::new());
+
+        let _first = admission.try_admit(address([1, 1, 1, 1])).unwrap();
+        let second = admission.try_admit(address([1, 1, 1, 1])).unwrap();
+        assert_eq!(
+            Err(AdmissionError::TooManyConnectionsFromIp(
+                [1, 1, 1, 1].into()
+            )),
+            admission.try_admit(address([1, 1, 1, 1])).map(|_| ())
+        );
+
+        let _third = admission.try_admit(address([2, 2, 2, 2])).unwrap();
+        assert_eq!(
+            Err(AdmissionError::TooManyConnections),
+            admission.try_admit(address([3, 3, 3, 3])).map(|_| ())
+        );
+
+        drop(second);
+        assert!(admission.try_admit(address([1, 1, 1, 1])).is_ok());
+    }
+
+    #[test]
+    fn unknown_peers_get_lower_limits() {
+        let known = address([1, 1, 1, 1]);
+        let unknown = address([2, 2, 2, 2]);
+
+        let config = AdmissionConfig::new()
+            .maximum_connections_per_ip(2)
+            .unknown_peer_maximum_connections_per_ip(1);
+        let admission = ConnectionAdmission::new(config, known_nodes(&[known]));
+        let _known_permits = (
+            admission.try_admit(known).unwrap(),
+            admission.try_admit(known).unwrap(),
+        );
+        let _unknown_permit = admission.try_admit(unknown).unwrap();
+        assert!(admission.try_admit(unknown).is_err());
+
+        let config = AdmissionConfig::new().unknown_peer_maximum_connections_per_ip(0);
+        let admission = ConnectionAdmission::new(config, known_nodes(&[known]));
+        assert!(admission.try_admit(known).is_ok());
+        assert_eq!(
+            Err(AdmissionError::UnknownPeerRefused(unknown.ip())),
+            admission.try_admit(unknown).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn rate_limiter_allows_bursts_and_then_throttles() {
+        let now = Instant::now();
+        let mut limiter = RateLimiter::new(10, now);
+        for _ in 0..10 {
+            assert!(limiter.consume(now).is_none());
+        }
+        assert_eq!(Some(Duration::from_millis(100)), limiter.consume(now));
+
+        // after a second the bucket is full again
+        let later = now + Duration::from_secs(2);
+        for _ in 0..10 {
+            assert!(limiter.consume(later).is_none());
+        }
+        assert!(limiter.consume(later).is_some());
+    }
+
+    #[test]
+    fn zero_rate_means_unlimited() {
+        let now = Instant::now();
+        let mut limiter = RateLimiter::new(0, now);
+        for _ in 0..1000 {
+            assert!(limiter.consume(now).is_none());
+        }
+    }
+}
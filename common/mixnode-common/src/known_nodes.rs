@@ -0,0 +1,200 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crypto::asymmetric::encryption;
+use futures::future::join_all;
+use log::*;
+use mixnet_client::TopologyPeers;
+use mixnet_contract::{Layer, MixNodeBond};
+use nymnoise::KnownNodes;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::lookup_host;
+use tokio::time::sleep;
+use url::Url;
+
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Role of the node running the refresher, which determines the layer it is sending packets to.
+pub enum NodeRole {
+    Mixnode { identity_key: String },
+    Gateway,
+}
+
+impl NodeRole {
+    fn next_layer(&self, mixnodes: &[MixNodeBond]) -> Option<Layer> {
+        match self {
+            // gateways only ever send acks into the first layer
+            NodeRole::Gateway => Some(Layer::One),
+            NodeRole::Mixnode { identity_key } => {
+                let own_bond = mixnodes
+                    .iter()
+                    .find(|bond| &bond.mix_node.identity_key == identity_key)?;
+                match own_bond.layer {
+                    Layer::One => Some(Layer::Two),
+                    Layer::Two => Some(Layer::Three),
+                    Layer::Three => Some(Layer::Gateway),
+                    Layer::Gateway => None,
+                }
+            }
+        }
+    }
+}
+
+/// Periodically obtains the sphinx keys of all bonded mixnodes and gateways from the validator API
+/// so that they could be used for authenticating noise handshakes with other nodes.
+/// Optionally it also keeps track of the topology for the purposes of managing outbound connections.
+pub struct KnownNodesRefresher {
+    validator_api_urls: Vec<Url>,
+    currently_used_api: usize,
+    validator_client: validator_client::ApiClient,
+    known_nodes: KnownNodes,
+    topology_peers: Option<(TopologyPeers, NodeRole)>,
+    refresh_interval: Duration,
+}
+
+impl KnownNodesRefresher {
+    pub fn new(mut validator_api_urls: Vec<Url>, known_nodes: KnownNodes) -> Self {
+        assert!(
+            !validator_api_urls.is_empty(),
+            "at least a single validator API must be specified"
+        );
+        validator_api_urls.shuffle(&mut thread_rng());
+
+        KnownNodesRefresher {
+            validator_client: validator_client::ApiClient::new(validator_api_urls[0].clone()),
+            validator_api_urls,
+            currently_used_api: 0,
+            known_nodes,
+            topology_peers: None,
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+        }
+    }
+
+    pub fn with_topology_peers(mut self, topology_peers: TopologyPeers, role: NodeRole) -> Self {
+        self.topology_peers = Some((topology_peers, role));
+        self
+    }
+
+    fn use_next_validator_api(&mut self) {
+        if self.validator_api_urls.len() == 1 {
+            return;
+        }
+
+        self.currently_used_api = (self.currently_used_api + 1) % self.validator_api_urls.len();
+        self.validator_client
+            .change_validator_api(self.validator_api_urls[self.currently_used_api].clone())
+    }
+
+    async fn parse_node(
+        host: &str,
+        mix_port: u16,
+        sphinx_key: &str,
+    ) -> Option<(SocketAddr, encryption::PublicKey)> {
+        let sphinx_key = encryption::PublicKey::from_base58_string(sphinx_key).ok()?;
+        let address = lookup_host((host, mix_port)).await.ok()?.next()?;
+        Some((address, sphinx_key))
+    }
+
+    fn update_topology_peers(
+        topology_peers: &TopologyPeers,
+        role: &NodeRole,
+        mixnodes: &[MixNodeBond],
+        parsed_mixnodes: &[(Layer, SocketAddr)],
+        parsed_gateways: &[SocketAddr],
+    ) {
+        let next_layer = role.next_layer(mixnodes);
+        let next_layer_nodes = match next_layer {
+            Some(Layer::Gateway) => parsed_gateways.to_vec(),
+            Some(layer) => parsed_mixnodes
+                .iter()
+                .filter(|(node_layer, _)| *node_layer == layer)
+                .map(|(_, address)| *address)
+                .collect(),
+            None => {
+                warn!("could not determine the next layer of the topology");
+                Vec::new()
+            }
+        };
+
+        let known = parsed_mixnodes
+            .iter()
+            .map(|(_, address)| *address)
+            .chain(parsed_gateways.iter().copied());
+        topology_peers.update(
+            known.map(Into::into),
+            next_layer_nodes.into_iter().map(Into::into),
+        );
+    }
+
+    async fn refresh(&mut self) -> Result<(), validator_client::ValidatorClientError> {
+        let mixnodes = self.validator_client.get_cached_mixnodes().await?;
+        let gateways = self.validator_client.get_cached_gateways().await?;
+
+        // hosts might have to be resolved, so do it concurrently
+        let parsed_mixnodes = join_all(mixnodes.iter().map(|bond| async move {
+            Self::parse_node(
+                &bond.mix_node.host,
+                bond.mix_node.mix_port,
+                &bond.mix_node.sphinx_key,
+            )
+            .await
+            .map(|node| (bond.layer, node))
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        let parsed_gateways = join_all(gateways.iter().map(|bond| {
+            Self::parse_node(
+                &bond.gateway.host,
+                bond.gateway.mix_port,
+                &bond.gateway.sphinx_key,
+            )
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        if let Some((topology_peers, role)) = &self.topology_peers {
+            Self::update_topology_peers(
+                topology_peers,
+                role,
+                &mixnodes,
+                &parsed_mixnodes
+                    .iter()
+                    .map(|(layer, (address, _))| (*layer, *address))
+                    .collect::<Vec<_>>(),
+                &parsed_gateways
+                    .iter()
+                    .map(|(address, _)| *address)
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        let nodes = parsed_mixnodes
+            .into_iter()
+            .map(|(_, node)| node)
+            .chain(parsed_gateways)
+            .collect::<Vec<_>>();
+        debug!("obtained sphinx keys of {} nodes", nodes.len());
+        self.known_nodes.replace(nodes);
+        Ok(())
+    }
+
+    pub async fn run(&mut self) {
+        loop {
+            if let Err(err) = self.refresh().await {
+                warn!(
+                    "failed to obtain the list of nodes from the validator - {}. Going to attempt to use another validator API in the next run",
+                    err
+                );
+                self.use_next_validator_api();
+            }
+            sleep(self.refresh_interval).await
+        }
+    }
+}
@@ -1,5 +1,8 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod admission;
+pub mod known_nodes;
+pub mod node_description;
 pub mod packet_processor;
 pub mod verloc;
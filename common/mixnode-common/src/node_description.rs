@@ -1,10 +1,15 @@
-use serde::Deserialize;
-use serde::Serialize;
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Write};
 use std::path::PathBuf;
-use std::{fs, io};
 
-pub(crate) const DESCRIPTION_FILE: &str = "description.toml";
+pub const DESCRIPTION_FILE: &str = "description.toml";
 
+/// Description of a node provided by its operator, served by the node's http api.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct NodeDescription {
     pub(crate) name: String,
@@ -25,7 +30,19 @@ impl Default for NodeDescription {
 }
 
 impl NodeDescription {
-    pub(crate) fn load_from_file(config_path: PathBuf) -> io::Result<NodeDescription> {
+    /// Interactively asks the operator for each part of the description.
+    pub fn read_from_stdin(example_link: impl Display) -> io::Result<NodeDescription> {
+        let example_location = "City: London, Country: UK";
+
+        Ok(NodeDescription {
+            name: prompt("name")?,
+            description: prompt("description")?,
+            link: prompt(format!("link, e.g. {}", example_link))?,
+            location: prompt(format!("location, e.g. {}", example_location))?,
+        })
+    }
+
+    pub fn load_from_file(config_path: PathBuf) -> io::Result<NodeDescription> {
         let description_file_path: PathBuf = [config_path.to_str().unwrap(), DESCRIPTION_FILE]
             .iter()
             .collect();
@@ -33,10 +50,7 @@ impl NodeDescription {
         toml::from_str(&toml).map_err(|toml_err| io::Error::new(io::ErrorKind::Other, toml_err))
     }
 
-    pub(crate) fn save_to_file(
-        description: &NodeDescription,
-        config_path: PathBuf,
-    ) -> io::Result<()> {
+    pub fn save_to_file(description: &NodeDescription, config_path: PathBuf) -> io::Result<()> {
         let description_file_path: PathBuf = [config_path.to_str().unwrap(), DESCRIPTION_FILE]
             .iter()
             .collect();
@@ -46,3 +60,11 @@ impl NodeDescription {
         Ok(())
     }
 }
+
+fn prompt(field: impl Display) -> io::Result<String> {
+    print!("{}: ", field);
+    io::stdout().flush()?;
+    let mut buf = String::new();
+    io::stdin().read_line(&mut buf)?;
+    Ok(buf.trim().to_string())
+}
@@ -12,8 +12,6 @@ pub enum MixProcessingError {
     InvalidHopAddress(NymNodeRoutingAddressError),
     NoSurbAckInFinalHop,
     MalformedSurbAck(SurbAckRecoveryError),
-
-    ReceivedOldTypeVpnPacket,
 }
 
 impl From<SphinxError> for MixProcessingError {
@@ -41,6 +39,18 @@ impl From<SurbAckRecoveryError> for MixProcessingError {
     }
 }
 
+impl MixProcessingError {
+    /// Short, static, description of the error variant, suitable for being used as a metrics label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MixProcessingError::SphinxProcessingError(_) => "sphinx_processing",
+            MixProcessingError::InvalidHopAddress(_) => "invalid_hop_address",
+            MixProcessingError::NoSurbAckInFinalHop => "no_surb_ack_in_final_hop",
+            MixProcessingError::MalformedSurbAck(_) => "malformed_surb_ack",
+        }
+    }
+}
+
 impl Display for MixProcessingError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -56,9 +66,6 @@ impl Display for MixProcessingError {
             MixProcessingError::MalformedSurbAck(surb_ack_err) => {
                 write!(f, "Malformed SURBAck - {:?}", surb_ack_err)
             }
-            MixProcessingError::ReceivedOldTypeVpnPacket => {
-                write!(f, "Received an old-type unsafe 'VPN' mode packet")
-            }
         }
     }
 }
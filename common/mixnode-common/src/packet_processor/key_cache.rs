@@ -0,0 +1,181 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use dashmap::DashMap;
+use nymsphinx_types::header::keys::RoutingKeys;
+use nymsphinx_types::SharedSecret;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Minimum time between subsequent purges of expired entries of a full cache, so that flooding
+/// the node with `Vpn` mode packets wouldn't make it iterate over the whole cache for each of them.
+const MIN_PURGE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Keys derived while processing the first packet of a `Vpn` mode session, i.e. the shared secret
+/// of the next hop (if any) and the routing keys of this hop.
+pub type CachedKeys = (Option<SharedSecret>, RoutingKeys);
+
+struct CacheEntry {
+    keys: Arc<CachedKeys>,
+    inserted_at: Instant,
+}
+
+impl CacheEntry {
+    fn is_expired(&self, ttl: Duration) -> bool {
+        self.inserted_at.elapsed() > ttl
+    }
+}
+
+/// Bounded cache of keys derived for `Vpn` mode packets, indexed by the initial shared secret
+/// of the packet. Entries are only valid for the specified time-to-live.
+#[derive(Clone)]
+pub struct KeyCache {
+    entries: Arc<DashMap<SharedSecret, CacheEntry>>,
+    capacity: usize,
+    entry_ttl: Duration,
+    last_purge: Arc<Mutex<Option<Instant>>>,
+}
+
+impl KeyCache {
+    /// Creates new instance of `KeyCache`. Capacity of 0 disables the caching altogether.
+    pub fn new(capacity: usize, entry_ttl: Duration) -> Self {
+        KeyCache {
+            entries: Arc::new(DashMap::new()),
+            capacity,
+            entry_ttl,
+            last_purge: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Retrieves keys cached for the specified initial secret, if they haven't expired yet.
+    pub fn get(&self, initial_secret: &SharedSecret) -> Option<Arc<CachedKeys>> {
+        {
+            let entry = self.entries.get(initial_secret)?;
+            if !entry.is_expired(self.entry_ttl) {
+                return Some(Arc::clone(&entry.keys));
+            }
+        }
+        // note: the read guard must be dropped before removing the expired entry,
+        // otherwise we'd deadlock
+        self.entries.remove(initial_secret);
+        None
+    }
+
+    /// Removes all expired entries, unless it has already been done recently (or is being done
+    /// right now by another thread). Returns whether the purge happened.
+    fn try_purge_expired(&self) -> bool {
+        let mut last_purge = match self.last_purge.try_lock() {
+            Ok(last_purge) => last_purge,
+            Err(_) => return false,
+        };
+        if matches!(*last_purge, Some(last) if last.elapsed() < MIN_PURGE_INTERVAL) {
+            return false;
+        }
+
+        let ttl = self.entry_ttl;
+        self.entries.retain(|_, entry| !entry.is_expired(ttl));
+        *last_purge = Some(Instant::now());
+        true
+    }
+
+    /// Caches keys derived for the specified initial secret. If the cache is full, the expired
+    /// entries are purged first (at most once per `MIN_PURGE_INTERVAL`) and if there's still
+    /// no space, the keys are not cached.
+    pub fn insert(&self, initial_secret: SharedSecret, keys: CachedKeys) {
+        if self.entries.len() >= self.capacity
+            && (!self.try_purge_expired() || self.entries.len() >= self.capacity)
+        {
+            return;
+        }
+
+        self.entries.insert(
+            initial_secret,
+            CacheEntry {
+                keys: Arc::new(keys),
+                inserted_at: Instant::now(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nymsphinx_types::crypto::keygen;
+    use nymsphinx_types::SphinxHeader;
+
+    fn fixture_keys() -> (SharedSecret, CachedKeys) {
+        let (private_key, public_key) = keygen();
+        let routing_keys = SphinxHeader::compute_routing_keys(&public_key, &private_key);
+        (public_key, (None, routing_keys))
+    }
+
+    #[test]
+    fn cached_keys_can_be_retrieved() {
+        let cache = KeyCache::new(10, Duration::from_secs(60));
+        let (secret, keys) = fixture_keys();
+        assert!(cache.get(&secret).is_none());
+
+        cache.insert(secret, keys);
+        assert!(cache.get(&secret).is_some());
+        assert_eq!(1, cache.len());
+    }
+
+    #[test]
+    fn expired_keys_are_removed() {
+        let cache = KeyCache::new(10, Duration::from_secs(0));
+        let (secret, keys) = fixture_keys();
+
+        cache.insert(secret, keys);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.get(&secret).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn cache_does_not_grow_above_capacity() {
+        let cache = KeyCache::new(2, Duration::from_secs(60));
+        let mut last_secret = None;
+        for _ in 0..3 {
+            let (secret, keys) = fixture_keys();
+            cache.insert(secret, keys);
+            last_secret = Some(secret);
+        }
+        assert_eq!(2, cache.len());
+        assert!(cache.get(&last_secret.unwrap()).is_none());
+
+        let disabled = KeyCache::new(0, Duration::from_secs(60));
+        let (secret, keys) = fixture_keys();
+        disabled.insert(secret, keys);
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn full_cache_is_purged_at_most_once_per_interval() {
+        let cache = KeyCache::new(1, Duration::from_secs(0));
+        let (secret, keys) = fixture_keys();
+        cache.insert(secret, keys);
+        std::thread::sleep(Duration::from_millis(5));
+
+        // the expired entry gets purged to make space for the new one
+        let (secret, keys) = fixture_keys();
+        cache.insert(secret, keys);
+        assert_eq!(1, cache.len());
+        std::thread::sleep(Duration::from_millis(5));
+
+        // but the purge is not repeated straight away, so the keys are not cached
+        let (new_secret, keys) = fixture_keys();
+        cache.insert(new_secret, keys);
+        assert_eq!(1, cache.len());
+        assert!(cache.entries.contains_key(&secret));
+        assert!(!cache.entries.contains_key(&new_secret));
+    }
+}
@@ -2,4 +2,5 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod error;
+pub mod key_cache;
 pub mod processor;
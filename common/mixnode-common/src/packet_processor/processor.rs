@@ -2,15 +2,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::packet_processor::error::MixProcessingError;
+use crate::packet_processor::key_cache::KeyCache;
 use log::*;
 use nymsphinx_acknowledgements::surb_ack::SurbAck;
 use nymsphinx_addressing::nodes::NymNodeRoutingAddress;
 use nymsphinx_forwarding::packet::MixPacket;
 use nymsphinx_framing::packet::FramedSphinxPacket;
 use nymsphinx_params::{PacketMode, PacketSize};
+use nymsphinx_types::header::keys::RoutingKeys;
 use nymsphinx_types::{
     Delay as SphinxDelay, DestinationAddressBytes, NodeAddressBytes, Payload, PrivateKey,
-    ProcessedPacket, SphinxPacket,
+    ProcessedPacket, SharedSecret, SphinxHeader, SphinxPacket,
 };
 use std::convert::TryFrom;
 use std::sync::Arc;
@@ -25,6 +27,7 @@ pub struct ProcessedFinalHop {
 
 pub enum MixProcessingResult {
     /// Contains unwrapped data that should first get delayed before being sent to next hop.
+    /// Packets sent in the `Vpn` mode do not get any delay.
     ForwardHop(MixPacket, Option<SphinxDelay>),
 
     /// Contains all data extracted out of the final hop packet that could be forwarded to the destination.
@@ -35,14 +38,59 @@ pub enum MixProcessingResult {
 pub struct SphinxPacketProcessor {
     /// Private sphinx key of this node required to unwrap received sphinx packet.
     sphinx_key: Arc<PrivateKey>,
+
+    /// Keys derived for the `Vpn` mode packets, so that the expensive key derivation could be
+    /// skipped for subsequent packets of the same session.
+    vpn_key_cache: KeyCache,
 }
 
 impl SphinxPacketProcessor {
-    /// Creates new instance of `CachedPacketProcessor`
-    pub fn new(sphinx_key: PrivateKey) -> Self {
+    /// Creates new instance of `SphinxPacketProcessor`
+    pub fn new(sphinx_key: PrivateKey, vpn_key_cache: KeyCache) -> Self {
         SphinxPacketProcessor {
             sphinx_key: Arc::new(sphinx_key),
+            vpn_key_cache,
+        }
+    }
+
+    fn recompute_routing_keys(&self, initial_secret: &SharedSecret) -> RoutingKeys {
+        SphinxHeader::compute_routing_keys(initial_secret, &self.sphinx_key)
+    }
+
+    /// Caches keys used for unwrapping the `Vpn` mode packet, so that they could be reused
+    /// for all subsequent packets created with the same initial secret.
+    fn cache_keys(&self, initial_secret: SharedSecret, processed_packet: &ProcessedPacket) {
+        let next_hop_secret = match processed_packet {
+            ProcessedPacket::ForwardHop(packet, ..) => Some(packet.shared_secret()),
+            ProcessedPacket::FinalHop(..) => None,
+        };
+        let routing_keys = self.recompute_routing_keys(&initial_secret);
+        self.vpn_key_cache
+            .insert(initial_secret, (next_hop_secret, routing_keys));
+    }
+
+    /// Tries to unwrap the `Vpn` mode packet using the keys derived for the previous packets
+    /// of the same session. If there are none, performs a fresh unwrapping and caches the keys.
+    fn perform_vpn_packet_processing(
+        &self,
+        packet: SphinxPacket,
+    ) -> Result<ProcessedPacket, MixProcessingError> {
+        let initial_secret = packet.shared_secret();
+        if let Some(cached_keys) = self.vpn_key_cache.get(&initial_secret) {
+            return packet
+                .process_with_derived_keys(&cached_keys.0, &cached_keys.1)
+                .map_err(|err| {
+                    debug!(
+                        "Failed to unwrap Sphinx packet using cached keys: {:?}",
+                        err
+                    );
+                    MixProcessingError::SphinxProcessingError(err)
+                });
         }
+
+        let processed_packet = self.perform_initial_sphinx_packet_processing(packet)?;
+        self.cache_keys(initial_secret, &processed_packet);
+        Ok(processed_packet)
     }
 
     /// Performs a fresh sphinx unwrapping using no cache.
@@ -64,11 +112,11 @@ impl SphinxPacketProcessor {
         let packet_mode = received.packet_mode();
         let sphinx_packet = received.into_inner();
 
-        if packet_mode.is_old_vpn() {
-            return Err(MixProcessingError::ReceivedOldTypeVpnPacket);
+        if packet_mode.is_vpn() {
+            self.perform_vpn_packet_processing(sphinx_packet)
+        } else {
+            self.perform_initial_sphinx_packet_processing(sphinx_packet)
         }
-
-        self.perform_initial_sphinx_packet_processing(sphinx_packet)
     }
 
     /// Processed received forward hop packet - tries to extract next hop address, sets delay
@@ -82,8 +130,15 @@ impl SphinxPacketProcessor {
     ) -> Result<MixProcessingResult, MixProcessingError> {
         let next_hop_address = NymNodeRoutingAddress::try_from(forward_address)?;
 
+        // packets sent in the vpn mode trade anonymity for latency and are forwarded immediately
+        let delay = if packet_mode.is_vpn() {
+            None
+        } else {
+            Some(delay)
+        };
+
         let mix_packet = MixPacket::new(next_hop_address, packet, packet_mode);
-        Ok(MixProcessingResult::ForwardHop(mix_packet, Some(delay)))
+        Ok(MixProcessingResult::ForwardHop(mix_packet, delay))
     }
 
     /// Split data extracted from the final hop sphinx packet into a SURBAck and message
@@ -111,18 +166,15 @@ impl SphinxPacketProcessor {
         packet_size: PacketSize,
         packet_mode: PacketMode,
     ) -> Result<(Option<MixPacket>, Vec<u8>), MixProcessingError> {
-        match packet_size {
-            PacketSize::AckPacket => {
-                trace!("received an ack packet!");
-                Ok((None, data))
-            }
-            PacketSize::RegularPacket | PacketSize::ExtendedPacket => {
-                trace!("received a normal packet!");
-                let (ack_data, message) = self.split_hop_data_into_ack_and_message(data)?;
-                let (ack_first_hop, ack_packet) = SurbAck::try_recover_first_hop_packet(&ack_data)?;
-                let forward_ack = MixPacket::new(ack_first_hop, ack_packet, packet_mode);
-                Ok((Some(forward_ack), message))
-            }
+        if !packet_size.is_message_packet() {
+            trace!("received an ack packet!");
+            Ok((None, data))
+        } else {
+            trace!("received a normal packet!");
+            let (ack_data, message) = self.split_hop_data_into_ack_and_message(data)?;
+            let (ack_first_hop, ack_packet) = SurbAck::try_recover_first_hop_packet(&ack_data)?;
+            let forward_ack = MixPacket::new(ack_first_hop, ack_packet, packet_mode);
+            Ok((Some(forward_ack), message))
         }
     }
 
@@ -189,11 +241,114 @@ impl SphinxPacketProcessor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use nymsphinx_types::builder::SphinxPacketBuilder;
     use nymsphinx_types::crypto::keygen;
+    use nymsphinx_types::{
+        Delay as SphinxDelay, Destination, Node, PublicKey, DESTINATION_ADDRESS_LENGTH,
+        IDENTIFIER_LENGTH,
+    };
+    use std::convert::TryInto;
+    use std::net::SocketAddr;
+    use std::time::Duration;
 
     fn fixture() -> SphinxPacketProcessor {
+        fixture_with_public_key().0
+    }
+
+    fn fixture_with_public_key() -> (SphinxPacketProcessor, PublicKey) {
         let local_keys = keygen();
-        SphinxPacketProcessor::new(local_keys.0)
+        let processor =
+            SphinxPacketProcessor::new(local_keys.0, KeyCache::new(10, Duration::from_secs(60)));
+        (processor, local_keys.1)
+    }
+
+    fn node_address(address: &str) -> NodeAddressBytes {
+        NymNodeRoutingAddress::from(address.parse::<SocketAddr>().unwrap())
+            .try_into()
+            .unwrap()
+    }
+
+    // creates a packet for which the processor is the first of two hops
+    fn make_forward_packet(local_key: PublicKey) -> SphinxPacket {
+        let route = [
+            Node::new(node_address("1.2.3.4:1789"), local_key),
+            Node::new(node_address("5.6.7.8:1789"), keygen().1),
+        ];
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([3u8; DESTINATION_ADDRESS_LENGTH]),
+            [4u8; IDENTIFIER_LENGTH],
+        );
+        let delays = vec![
+            SphinxDelay::new_from_nanos(42),
+            SphinxDelay::new_from_nanos(42),
+        ];
+        SphinxPacketBuilder::new()
+            .with_payload_size(PacketSize::default().payload_size())
+            .build_packet(b"foomp".to_vec(), &route, &destination, &delays)
+            .unwrap()
+    }
+
+    fn copy_packet(packet: &SphinxPacket) -> SphinxPacket {
+        SphinxPacket::from_bytes(&packet.to_bytes()).unwrap()
+    }
+
+    fn unwrap_forward_hop(processed: ProcessedPacket) -> (Vec<u8>, NodeAddressBytes) {
+        match processed {
+            ProcessedPacket::ForwardHop(packet, address, _) => (packet.to_bytes(), address),
+            ProcessedPacket::FinalHop(..) => panic!("expected a forward hop"),
+        }
+    }
+
+    #[test]
+    fn vpn_processing_with_cached_keys_is_equivalent_to_fresh_processing() {
+        let (processor, local_key) = fixture_with_public_key();
+        let packet = make_forward_packet(local_key);
+        let initial_secret = packet.shared_secret();
+
+        let fresh = processor
+            .perform_initial_sphinx_packet_processing(copy_packet(&packet))
+            .unwrap();
+
+        // first packet of the session gets its keys cached...
+        let first = processor
+            .perform_vpn_packet_processing(copy_packet(&packet))
+            .unwrap();
+        assert!(processor.vpn_key_cache.get(&initial_secret).is_some());
+
+        // ...and they're used for the subsequent one
+        let cached = processor.perform_vpn_packet_processing(packet).unwrap();
+
+        let (fresh_bytes, fresh_address) = unwrap_forward_hop(fresh);
+        let (first_bytes, first_address) = unwrap_forward_hop(first);
+        let (cached_bytes, cached_address) = unwrap_forward_hop(cached);
+        assert_eq!(fresh_bytes, first_bytes);
+        assert_eq!(fresh_bytes, cached_bytes);
+        assert_eq!(fresh_address.as_bytes_ref(), first_address.as_bytes_ref());
+        assert_eq!(fresh_address.as_bytes_ref(), cached_address.as_bytes_ref());
+    }
+
+    #[test]
+    fn only_mix_packets_are_delayed() {
+        let (processor, local_key) = fixture_with_public_key();
+        let packet = make_forward_packet(local_key);
+
+        let mix_packet = FramedSphinxPacket::new(copy_packet(&packet), PacketMode::Mix);
+        match processor.process_received(mix_packet).unwrap() {
+            MixProcessingResult::ForwardHop(forwarded, delay) => {
+                assert_eq!(PacketMode::Mix, forwarded.packet_mode());
+                assert!(delay.is_some());
+            }
+            MixProcessingResult::FinalHop(..) => panic!("expected a forward hop"),
+        }
+
+        let vpn_packet = FramedSphinxPacket::new(packet, PacketMode::Vpn);
+        match processor.process_received(vpn_packet).unwrap() {
+            MixProcessingResult::ForwardHop(forwarded, delay) => {
+                assert_eq!(PacketMode::Vpn, forwarded.packet_mode());
+                assert!(delay.is_none());
+            }
+            MixProcessingResult::FinalHop(..) => panic!("expected a forward hop"),
+        }
     }
 
     #[tokio::test]
@@ -70,6 +70,20 @@ impl AtomicVerlocResult {
     }
 }
 
+impl VerlocResult {
+    pub fn total_tested(&self) -> usize {
+        self.total_tested
+    }
+
+    pub fn run_finished(&self) -> Option<std::time::SystemTime> {
+        self.run_finished
+    }
+
+    pub fn results(&self) -> &[Verloc] {
+        &self.results
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
 pub struct Verloc {
     #[serde(serialize_with = "serialize_identity_as_string")]
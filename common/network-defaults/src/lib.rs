@@ -65,6 +65,8 @@ pub const DEFAULT_MIX_LISTENING_PORT: u16 = 1789;
 
 // 'GATEWAY'
 pub const DEFAULT_CLIENT_LISTENING_PORT: u16 = 9000;
+// different from the mixnode's so that both could be run on the same machine
+pub const DEFAULT_GATEWAY_HTTP_API_LISTENING_PORT: u16 = 8001;
 
 // 'MIXNODE'
 pub const DEFAULT_VERLOC_LISTENING_PORT: u16 = 1790;
@@ -0,0 +1,116 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::FutureExt;
+use nonexhaustive_delayqueue::{NonExhaustiveDelayQueue, TimingWheelDelayQueue};
+use rand::SeedableRng;
+use rand_distr::{Distribution, Exp};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio_stream::{Stream, StreamExt};
+
+// average delay of a packet on a single mix hop
+const AVERAGE_DELAY: Duration = Duration::from_millis(50);
+const SIMULATED_TIME: Duration = Duration::from_secs(1);
+const TICK: Duration = Duration::from_millis(1);
+
+trait BenchQueue: Stream + Unpin {
+    fn new() -> Self;
+    fn push(&mut self, value: u64, delay: Duration);
+}
+
+impl BenchQueue for NonExhaustiveDelayQueue<u64> {
+    fn new() -> Self {
+        NonExhaustiveDelayQueue::new()
+    }
+
+    fn push(&mut self, value: u64, delay: Duration) {
+        self.insert(value, delay);
+    }
+}
+
+impl BenchQueue for TimingWheelDelayQueue<u64> {
+    fn new() -> Self {
+        TimingWheelDelayQueue::new()
+    }
+
+    fn push(&mut self, value: u64, delay: Duration) {
+        self.insert(value, delay).unwrap();
+    }
+}
+
+fn sample_delays(count: usize) -> Vec<Duration> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let distribution = Exp::new(1.0 / AVERAGE_DELAY.as_secs_f64()).unwrap();
+    (0..count)
+        .map(|_| Duration::from_secs_f64(distribution.sample(&mut rng)))
+        .collect()
+}
+
+// inserts packets at the given rate for the simulated period, while collecting all the expired ones
+async fn simulate_traffic<Q: BenchQueue>(packets_per_second: u64, delays: &[Duration]) -> u64 {
+    let mut queue = Q::new();
+    let mut delays = delays.iter().cycle();
+    let packets_per_tick = packets_per_second * TICK.as_millis() as u64 / 1000;
+    let ticks = SIMULATED_TIME.as_millis() / TICK.as_millis();
+
+    let mut expired = 0;
+    for tick in 0..ticks as u64 {
+        for packet in 0..packets_per_tick {
+            queue.push(tick * packets_per_tick + packet, *delays.next().unwrap());
+        }
+        tokio::time::advance(TICK).await;
+        while let Some(Some(_)) = queue.next().now_or_never() {
+            expired += 1;
+        }
+    }
+    expired
+}
+
+fn paused_runtime() -> Runtime {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+    runtime.block_on(async { tokio::time::pause() });
+    runtime
+}
+
+fn delay_queues(c: &mut Criterion) {
+    let delays = sample_delays(10_000);
+    let mut group = c.benchmark_group("delay_queue");
+    group.sample_size(10);
+
+    for packets_per_second in [10_000u64, 50_000, 100_000].iter() {
+        group.throughput(Throughput::Elements(*packets_per_second));
+        group.bench_with_input(
+            BenchmarkId::new("tokio_delay_queue", packets_per_second),
+            packets_per_second,
+            |b, &rate| {
+                let runtime = paused_runtime();
+                b.iter(|| {
+                    runtime.block_on(simulate_traffic::<NonExhaustiveDelayQueue<u64>>(
+                        rate, &delays,
+                    ))
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("timing_wheel", packets_per_second),
+            packets_per_second,
+            |b, &rate| {
+                let runtime = paused_runtime();
+                b.iter(|| {
+                    runtime.block_on(simulate_traffic::<TimingWheelDelayQueue<u64>>(
+                        rate, &delays,
+                    ))
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, delay_queues);
+criterion_main!(benches);
@@ -8,6 +8,10 @@ use tokio::time::Instant;
 use tokio_stream::Stream;
 use tokio_util::time::{delay_queue, DelayQueue};
 
+mod timing_wheel;
+
+pub use timing_wheel::TimingWheelDelayQueue;
+
 pub use tokio::time::error::Error as TimerError;
 pub use tokio_util::time::delay_queue::Expired;
 pub type QueueKey = delay_queue::Key;
@@ -50,6 +54,15 @@ impl<T> NonExhaustiveDelayQueue<T> {
     pub fn remove(&mut self, key: &QueueKey) -> Expired<T> {
         self.inner.remove(key)
     }
+
+    /// Returns the number of items currently in the queue.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
 }
 
 impl<T> Default for NonExhaustiveDelayQueue<T> {
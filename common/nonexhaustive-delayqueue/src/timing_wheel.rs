@@ -0,0 +1,425 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant, Sleep};
+use tokio_stream::Stream;
+
+// each level of the wheel has 64 slots, which lets us use a single u64 as the occupancy bitmap
+const SLOT_BITS: u32 = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+// with millisecond granularity, 4 levels cover a bit over 4.5 hours. Anything delayed for longer
+// is put in the furthest slot and cascaded down once it's reached.
+const LEVELS: usize = 4;
+const MAXIMUM_DELAY_TICKS: u64 = (1 << (SLOT_BITS * LEVELS as u32)) - 1;
+
+// after a slot (or the ready buffer) gets emptied, its allocation is kept for reuse unless it grew
+// beyond this many entries, in which case it's freed so that a burst of traffic wouldn't
+// permanently inflate the memory usage
+const MAXIMUM_RETAINED_CAPACITY: usize = 1024;
+
+struct Expiration {
+    level: usize,
+    slot: usize,
+    deadline: u64,
+}
+
+struct Level<T> {
+    occupied: u64,
+    slots: Vec<Vec<(u64, T)>>,
+}
+
+impl<T> Level<T> {
+    fn new() -> Self {
+        Level {
+            occupied: 0,
+            slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+        }
+    }
+}
+
+fn slot_range(level: usize) -> u64 {
+    1 << (SLOT_BITS * level as u32)
+}
+
+fn level_range(level: usize) -> u64 {
+    slot_range(level + 1)
+}
+
+/// Hierarchical timing wheel operating on abstract millisecond ticks.
+struct TimingWheel<T> {
+    // all entries with deadlines up to and including this tick have been moved to `ready`
+    elapsed: u64,
+    levels: Vec<Level<T>>,
+    ready: VecDeque<T>,
+    len: usize,
+}
+
+impl<T> TimingWheel<T> {
+    fn new() -> Self {
+        TimingWheel {
+            elapsed: 0,
+            levels: (0..LEVELS).map(|_| Level::new()).collect(),
+            ready: VecDeque::new(),
+            len: 0,
+        }
+    }
+
+    fn insert(&mut self, value: T, deadline: u64) {
+        self.len += 1;
+        self.insert_entry(value, deadline)
+    }
+
+    fn insert_entry(&mut self, value: T, deadline: u64) {
+        if deadline <= self.elapsed {
+            self.ready.push_back(value);
+            return;
+        }
+
+        // the slot is determined by the most significant bit in which the deadline differs from
+        // the current time. Deadlines that are too far in the future are placed as far as possible.
+        let placement = std::cmp::min(deadline, self.elapsed + MAXIMUM_DELAY_TICKS);
+        let significant = 63 - ((self.elapsed ^ placement) | (SLOTS as u64 - 1)).leading_zeros();
+        let level = std::cmp::min(significant as usize / SLOT_BITS as usize, LEVELS - 1);
+        let slot = ((placement >> (SLOT_BITS * level as u32)) as usize) & (SLOTS - 1);
+
+        let level = &mut self.levels[level];
+        level.slots[slot].push((deadline, value));
+        level.occupied |= 1 << slot;
+    }
+
+    fn next_expiration(&self) -> Option<Expiration> {
+        // the entries at lower levels always expire before the ones at higher levels
+        self.levels.iter().enumerate().find_map(|(index, level)| {
+            if level.occupied == 0 {
+                return None;
+            }
+            let now_slot = (self.elapsed / slot_range(index)) as u32 % SLOTS as u32;
+            let distance = level.occupied.rotate_right(now_slot).trailing_zeros();
+            let slot = (now_slot + distance) as usize % SLOTS;
+
+            let level_start = self.elapsed & !(level_range(index) - 1);
+            let mut deadline = level_start + slot as u64 * slot_range(index);
+            if deadline <= self.elapsed {
+                // this can only happen for the entries that were placed in the last level
+                // because their deadlines were beyond the range of the wheel
+                deadline += level_range(index);
+            }
+
+            Some(Expiration {
+                level: index,
+                slot,
+                deadline,
+            })
+        })
+    }
+
+    fn process_expiration(&mut self, expiration: Expiration) {
+        let level = &mut self.levels[expiration.level];
+        let mut entries = std::mem::take(&mut level.slots[expiration.slot]);
+        level.occupied &= !(1 << expiration.slot);
+
+        self.elapsed = std::cmp::max(self.elapsed, expiration.deadline);
+        // the entries either expire now or get cascaded to a lower level
+        for (deadline, value) in entries.drain(..) {
+            self.insert_entry(value, deadline);
+        }
+
+        // cascading never puts anything back into the same slot
+        if entries.capacity() <= MAXIMUM_RETAINED_CAPACITY {
+            self.levels[expiration.level].slots[expiration.slot] = entries;
+        }
+    }
+
+    /// Moves all the entries with deadlines up to and including `now` to the ready buffer.
+    fn advance(&mut self, now: u64) {
+        while let Some(expiration) = self.next_expiration() {
+            if expiration.deadline > now {
+                break;
+            }
+            self.process_expiration(expiration);
+        }
+        self.elapsed = std::cmp::max(self.elapsed, now);
+    }
+
+    fn pop_ready(&mut self) -> Option<T> {
+        let value = self.ready.pop_front()?;
+        self.len -= 1;
+        if self.ready.is_empty() && self.ready.capacity() > MAXIMUM_RETAINED_CAPACITY {
+            self.ready = VecDeque::new();
+        }
+        Some(value)
+    }
+}
+
+/// A delay queue backed by a hierarchical timing wheel with millisecond granularity.
+/// Unlike `NonExhaustiveDelayQueue`, it does not allocate per inserted item and expires all
+/// items scheduled for the same millisecond in a single batch. Items never expire early,
+/// but they might expire up to a millisecond late.
+///
+/// Similarly to `NonExhaustiveDelayQueue`, its `Stream` implementation will never return a 'None'.
+pub struct TimingWheelDelayQueue<T> {
+    wheel: TimingWheel<T>,
+    start: Instant,
+    maximum_len: Option<usize>,
+    // the sleep is created lazily, so that the queue could be constructed outside the runtime
+    sleep: Option<Pin<Box<Sleep>>>,
+    // tick at which the sleep is going to fire, if it's armed
+    armed_deadline: Option<u64>,
+    waker: Option<Waker>,
+}
+
+impl<T> TimingWheelDelayQueue<T> {
+    pub fn new() -> Self {
+        TimingWheelDelayQueue {
+            wheel: TimingWheel::new(),
+            start: Instant::now(),
+            maximum_len: None,
+            sleep: None,
+            armed_deadline: None,
+            waker: None,
+        }
+    }
+
+    /// Limits the number of items the queue can hold at once.
+    pub fn with_maximum_len(mut self, maximum_len: usize) -> Self {
+        self.maximum_len = Some(maximum_len);
+        self
+    }
+
+    fn elapsed_ticks(&self, when: Instant) -> u64 {
+        when.saturating_duration_since(self.start).as_millis() as u64
+    }
+
+    fn deadline_ticks(&self, when: Instant) -> u64 {
+        // round up so that the items would never expire early
+        let elapsed = when.saturating_duration_since(self.start);
+        let ticks = elapsed.as_millis() as u64;
+        if elapsed > Duration::from_millis(ticks) {
+            ticks + 1
+        } else {
+            ticks
+        }
+    }
+
+    /// Inserts the value into the queue such that it's going to be yielded after the specified
+    /// timeout. If the queue is full, the value is returned back.
+    pub fn insert(&mut self, value: T, timeout: Duration) -> Result<(), T> {
+        self.insert_at(value, Instant::now() + timeout)
+    }
+
+    /// Inserts the value into the queue such that it's going to be yielded at the specified
+    /// instant. If the queue is full, the value is returned back.
+    pub fn insert_at(&mut self, value: T, when: Instant) -> Result<(), T> {
+        if let Some(maximum_len) = self.maximum_len {
+            if self.wheel.len >= maximum_len {
+                return Err(value);
+            }
+        }
+
+        let deadline = self.deadline_ticks(when);
+        self.wheel.insert(value, deadline);
+
+        // we only need to wake the executor if the new item expires before we were planning to wake up
+        let expires_sooner = match self.armed_deadline {
+            Some(armed_deadline) => deadline < armed_deadline,
+            None => true,
+        };
+        if expires_sooner {
+            if let Some(waker) = self.waker.take() {
+                waker.wake()
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of items currently in the queue.
+    pub fn len(&self) -> usize {
+        self.wheel.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wheel.len == 0
+    }
+}
+
+impl<T> Default for TimingWheelDelayQueue<T> {
+    fn default() -> Self {
+        TimingWheelDelayQueue::new()
+    }
+}
+
+// none of the fields are structurally pinned
+impl<T> Unpin for TimingWheelDelayQueue<T> {}
+
+impl<T> Stream for TimingWheelDelayQueue<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(value) = self.wheel.pop_ready() {
+                return Poll::Ready(Some(value));
+            }
+
+            let now = self.elapsed_ticks(Instant::now());
+            self.wheel.advance(now);
+            if let Some(value) = self.wheel.pop_ready() {
+                return Poll::Ready(Some(value));
+            }
+
+            // we'll need to keep the waker to notify the executor if an item that should expire
+            // sooner than anything we currently have gets inserted
+            self.waker = Some(cx.waker().clone());
+
+            let next_deadline = match self.wheel.next_expiration() {
+                Some(expiration) => expiration.deadline,
+                None => {
+                    self.armed_deadline = None;
+                    return Poll::Pending;
+                }
+            };
+
+            let wake_at = self.start + Duration::from_millis(next_deadline);
+            self.armed_deadline = Some(next_deadline);
+            if let Some(sleep) = self.sleep.as_mut() {
+                sleep.as_mut().reset(wake_at);
+            } else {
+                self.sleep = Some(Box::pin(sleep_until(wake_at)));
+            }
+
+            let sleep = self.sleep.as_mut().unwrap();
+            if sleep.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    #[test]
+    fn wheel_expires_entries_in_order() {
+        let mut wheel = TimingWheel::new();
+        let deadlines = [5, 1, 70, 64, 4096, 63, 300_000, 4095, 1];
+        for deadline in deadlines.iter() {
+            wheel.insert(*deadline, *deadline);
+        }
+
+        let mut expired = Vec::new();
+        for now in 0..=300_000 {
+            wheel.advance(now);
+            while let Some(deadline) = wheel.pop_ready() {
+                assert_eq!(deadline, now);
+                expired.push(deadline);
+            }
+        }
+
+        let mut expected = deadlines.to_vec();
+        expected.sort_unstable();
+        assert_eq!(expected, expired);
+        assert_eq!(0, wheel.len);
+    }
+
+    #[test]
+    fn wheel_handles_jumps_in_time() {
+        let mut wheel = TimingWheel::new();
+        for deadline in (0..10_000).step_by(7) {
+            wheel.insert(deadline, deadline);
+        }
+
+        wheel.advance(5000);
+        let mut expired = Vec::new();
+        while let Some(deadline) = wheel.pop_ready() {
+            expired.push(deadline);
+        }
+        assert_eq!((0..=5000).step_by(7).collect::<Vec<_>>(), expired);
+
+        wheel.advance(20_000);
+        assert_eq!(wheel.len, wheel.ready.len());
+        assert!(wheel.next_expiration().is_none());
+    }
+
+    #[test]
+    fn wheel_never_expires_entries_early_or_late() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let mut wheel = TimingWheel::new();
+        let mut pending = Vec::new();
+        let mut now = 0;
+        for _ in 0..1000 {
+            for _ in 0..rng.gen_range(0..20) {
+                let deadline = now + rng.gen_range(0..100_000);
+                wheel.insert(deadline, deadline);
+                pending.push(deadline);
+            }
+            now += rng.gen_range(0..500);
+            wheel.advance(now);
+            while let Some(deadline) = wheel.pop_ready() {
+                assert!(deadline <= now);
+                let index = pending.iter().position(|d| *d == deadline).unwrap();
+                pending.swap_remove(index);
+            }
+            assert!(pending.iter().all(|deadline| *deadline > now));
+        }
+        assert_eq!(pending.len(), wheel.len);
+    }
+
+    #[test]
+    fn wheel_handles_deadlines_beyond_its_range() {
+        let mut wheel = TimingWheel::new();
+        let far_deadline = MAXIMUM_DELAY_TICKS * 3;
+        wheel.insert((), far_deadline);
+
+        let mut now = 0;
+        while let Some(expiration) = wheel.next_expiration() {
+            assert!(expiration.deadline <= far_deadline);
+            now = expiration.deadline;
+            wheel.advance(now);
+        }
+        assert_eq!(far_deadline, now);
+        assert_eq!(Some(()), wheel.pop_ready());
+    }
+
+    #[tokio::test]
+    async fn queue_yields_items_after_their_delays() {
+        tokio::time::pause();
+        let start = Instant::now();
+        let mut queue = TimingWheelDelayQueue::new();
+        queue.insert(2, Duration::from_millis(200)).unwrap();
+        queue.insert(1, Duration::from_millis(100)).unwrap();
+        queue.insert(3, Duration::from_secs(10)).unwrap();
+
+        assert_eq!(Some(1), queue.next().await);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(Some(2), queue.next().await);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert_eq!(Some(3), queue.next().await);
+        assert!(start.elapsed() >= Duration::from_secs(10));
+        assert!(queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn queue_never_terminates() {
+        tokio::time::pause();
+        let mut queue: TimingWheelDelayQueue<()> = TimingWheelDelayQueue::new();
+        assert!(tokio::time::timeout(Duration::from_secs(1), queue.next())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn queue_respects_maximum_len() {
+        let mut queue = TimingWheelDelayQueue::new().with_maximum_len(1);
+        assert!(queue.insert(1, Duration::from_millis(100)).is_ok());
+        assert_eq!(Err(2), queue.insert(2, Duration::from_millis(100)));
+        assert_eq!(1, queue.len());
+    }
+}
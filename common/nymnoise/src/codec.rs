@@ -0,0 +1,191 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::handshake::MAX_NOISE_MESSAGE_LEN;
+use bytes::{Buf, BufMut, BytesMut};
+use snow::TransportState;
+use std::io;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Size of the authentication tag appended to every noise transport message.
+const TAG_LEN: usize = 16;
+const MAX_PLAINTEXT_LEN: usize = MAX_NOISE_MESSAGE_LEN - TAG_LEN;
+const LENGTH_PREFIX_LEN: usize = 2;
+
+fn noise_io_error(err: snow::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+/// Wraps the inner codec so that, if the noise handshake has been completed, everything it encodes
+/// is sent as length-prefixed noise transport messages. Otherwise the inner codec is used as is.
+pub struct NoiseCodec<C> {
+    inner: C,
+    transport: Option<Box<TransportState>>,
+    decrypted: BytesMut,
+}
+
+impl<C> NoiseCodec<C> {
+    pub fn new(inner: C, transport: Option<TransportState>) -> Self {
+        NoiseCodec {
+            inner,
+            transport: transport.map(Box::new),
+            decrypted: BytesMut::new(),
+        }
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.transport.is_some()
+    }
+}
+
+impl<C, T> Encoder<T> for NoiseCodec<C>
+where
+    C: Encoder<T>,
+    C::Error: Into<io::Error>,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let transport = match self.transport.as_mut() {
+            Some(transport) => transport,
+            None => return self.inner.encode(item, dst).map_err(Into::into),
+        };
+
+        let mut plaintext = BytesMut::new();
+        self.inner
+            .encode(item, &mut plaintext)
+            .map_err(Into::into)?;
+
+        let mut ciphertext = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+        for chunk in plaintext.chunks(MAX_PLAINTEXT_LEN) {
+            let len = transport
+                .write_message(chunk, &mut ciphertext)
+                .map_err(noise_io_error)?;
+            dst.reserve(LENGTH_PREFIX_LEN + len);
+            dst.put_u16(len as u16);
+            dst.put_slice(&ciphertext[..len]);
+        }
+        Ok(())
+    }
+}
+
+impl<C> Decoder for NoiseCodec<C>
+where
+    C: Decoder,
+    C::Error: Into<io::Error>,
+{
+    type Item = C::Item;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let transport = match self.transport.as_mut() {
+            Some(transport) => transport,
+            None => return self.inner.decode(src).map_err(Into::into),
+        };
+
+        loop {
+            if let Some(item) = self.inner.decode(&mut self.decrypted).map_err(Into::into)? {
+                return Ok(Some(item));
+            }
+
+            if src.len() < LENGTH_PREFIX_LEN {
+                return Ok(None);
+            }
+            let len = u16::from_be_bytes([src[0], src[1]]) as usize;
+            if src.len() < LENGTH_PREFIX_LEN + len {
+                src.reserve(LENGTH_PREFIX_LEN + len - src.len());
+                return Ok(None);
+            }
+
+            src.advance(LENGTH_PREFIX_LEN);
+            let message = src.split_to(len);
+            let mut plaintext = vec![0u8; len];
+            let plaintext_len = transport
+                .read_message(&message, &mut plaintext)
+                .map_err(noise_io_error)?;
+            self.decrypted
+                .extend_from_slice(&plaintext[..plaintext_len]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::codec::BytesCodec;
+
+    fn transport_pair() -> (TransportState, TransportState) {
+        let params: snow::params::NoiseParams =
+            "Noise_NN_25519_ChaChaPoly_BLAKE2s".parse().unwrap();
+        let mut initiator = snow::Builder::new(params.clone())
+            .build_initiator()
+            .unwrap();
+        let mut responder = snow::Builder::new(params).build_responder().unwrap();
+
+        let mut message = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+        let mut payload = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+        let len = initiator.write_message(&[], &mut message).unwrap();
+        responder
+            .read_message(&message[..len], &mut payload)
+            .unwrap();
+        let len = responder.write_message(&[], &mut message).unwrap();
+        initiator
+            .read_message(&message[..len], &mut payload)
+            .unwrap();
+
+        (
+            initiator.into_transport_mode().unwrap(),
+            responder.into_transport_mode().unwrap(),
+        )
+    }
+
+    #[test]
+    fn plaintext_codec_is_passed_through() {
+        let mut codec = NoiseCodec::new(BytesCodec::new(), None);
+        let mut buf = BytesMut::new();
+        codec
+            .encode(BytesMut::from(&[1, 2, 3][..]), &mut buf)
+            .unwrap();
+        assert_eq!(&[1, 2, 3][..], &buf[..]);
+    }
+
+    #[test]
+    fn encrypted_items_can_be_decoded() {
+        let (initiator, responder) = transport_pair();
+        let mut encoder = NoiseCodec::new(BytesCodec::new(), Some(initiator));
+        let mut decoder = NoiseCodec::new(BytesCodec::new(), Some(responder));
+
+        // make sure the item has to get split into multiple noise messages
+        let item = vec![42u8; MAX_PLAINTEXT_LEN * 2 + 123];
+        let mut buf = BytesMut::new();
+        encoder
+            .encode(BytesMut::from(item.as_slice()), &mut buf)
+            .unwrap();
+        assert_ne!(&item[..], &buf[..item.len()]);
+
+        // partial message can't be decoded yet
+        let mut partial = buf.split_to(100);
+        assert!(decoder.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(buf);
+
+        let mut decoded = Vec::new();
+        while let Some(chunk) = decoder.decode(&mut partial).unwrap() {
+            decoded.extend_from_slice(&chunk);
+        }
+        assert_eq!(item, decoded);
+    }
+
+    #[test]
+    fn tampered_messages_are_rejected() {
+        let (initiator, responder) = transport_pair();
+        let mut encoder = NoiseCodec::new(BytesCodec::new(), Some(initiator));
+        let mut decoder = NoiseCodec::new(BytesCodec::new(), Some(responder));
+
+        let mut buf = BytesMut::new();
+        encoder
+            .encode(BytesMut::from(&[1, 2, 3][..]), &mut buf)
+            .unwrap();
+        buf[LENGTH_PREFIX_LEN] ^= 1;
+        assert!(decoder.decode(&mut buf).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::NoiseError;
+use crypto::asymmetric::encryption;
+use log::*;
+use nymsphinx_framing::hello::{Capabilities, Protocol};
+use nymsphinx_framing::preamble::{PreambleError, TransportMode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+const NOISE_PATTERN: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+
+const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type StaticKey = [u8; encryption::PUBLIC_KEY_SIZE];
+
+/// Determines whether the node attempts and requires encryption of its connections with other nodes.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseMode {
+    /// Never attempt the noise handshake and only ever use plaintext framing.
+    Disabled,
+
+    /// Attempt the noise handshake, but fallback to plaintext framing with legacy nodes.
+    Optional,
+
+    /// Only ever accept and establish connections with a completed noise handshake
+    /// with a node whose key is present in the topology.
+    Required,
+}
+
+impl NoiseMode {
+    /// Transports announced to other nodes as part of our capabilities.
+    fn transports(self) -> Vec<TransportMode> {
+        match self {
+            NoiseMode::Disabled => vec![TransportMode::Plaintext],
+            NoiseMode::Optional => vec![TransportMode::Noise, TransportMode::Plaintext],
+            NoiseMode::Required => vec![TransportMode::Noise],
+        }
+    }
+}
+
+impl Default for NoiseMode {
+    fn default() -> Self {
+        NoiseMode::Optional
+    }
+}
+
+#[derive(Default)]
+struct KnownNodesInner {
+    by_address: HashMap<SocketAddr, StaticKey>,
+    ips: HashSet<IpAddr>,
+    keys: HashSet<StaticKey>,
+}
+
+/// Static keys of all the nodes in the network, as announced in the topology, used to authenticate
+/// the remote side of the noise handshake.
+// Note: you should NEVER create more than a single instance of this using 'new()'.
+// You should always use .clone() to create additional instances
+#[derive(Clone, Default)]
+pub struct KnownNodes {
+    inner: Arc<RwLock<KnownNodesInner>>,
+}
+
+impl KnownNodes {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Replaces all currently known nodes with the provided ones.
+    pub fn replace<I>(&self, nodes: I)
+    where
+        I: IntoIterator<Item = (SocketAddr, encryption::PublicKey)>,
+    {
+        let mut new_inner = KnownNodesInner::default();
+        for (address, key) in nodes {
+            let key = key.to_bytes();
+            new_inner.by_address.insert(address, key);
+            new_inner.ips.insert(address.ip());
+            new_inner.keys.insert(key);
+        }
+        *self.inner.write().unwrap() = new_inner;
+    }
+
+    /// Checks whether any known node is running on the provided ip address.
+    pub fn contains_ip(&self, ip: IpAddr) -> bool {
+        self.inner.read().unwrap().ips.contains(&ip)
+    }
+
+    /// Checks whether any nodes are known at all, i.e. whether the topology has been obtained yet.
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().unwrap().by_address.is_empty()
+    }
+
+    fn expected_key(&self, address: SocketAddr) -> Option<StaticKey> {
+        self.inner.read().unwrap().by_address.get(&address).copied()
+    }
+
+    fn is_known(&self, key: &[u8]) -> bool {
+        match StaticKey::try_from(key) {
+            Ok(key) => self.inner.read().unwrap().keys.contains(&key),
+            Err(_) => false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct NoiseConfig {
+    local_keys: Arc<encryption::KeyPair>,
+    mode: NoiseMode,
+    known_nodes: KnownNodes,
+    handshake_timeout: Duration,
+    capabilities: Arc<Capabilities>,
+}
+
+impl NoiseConfig {
+    pub fn new(
+        local_keys: Arc<encryption::KeyPair>,
+        mode: NoiseMode,
+        known_nodes: KnownNodes,
+    ) -> Self {
+        NoiseConfig {
+            local_keys,
+            mode,
+            known_nodes,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            capabilities: Arc::new(Capabilities::current().with_transports(mode.transports())),
+        }
+    }
+
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+
+    /// Protocol versions, packet sizes and packet modes announced to other nodes.
+    /// The announced transports are always determined by the noise mode.
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = Arc::new(capabilities.with_transports(self.mode.transports()));
+        self
+    }
+
+    pub fn mode(&self) -> NoiseMode {
+        self.mode
+    }
+
+    pub(crate) fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Determines the framing protocol used with the remote that has not sent its capabilities.
+    pub fn legacy_protocol(&self) -> Result<Protocol, PreambleError> {
+        self.capabilities.negotiate(&Capabilities::legacy())
+    }
+
+    pub(crate) fn handshake_timeout(&self) -> Duration {
+        self.handshake_timeout
+    }
+
+    pub(crate) fn build_handshake(
+        &self,
+        initiator: bool,
+    ) -> Result<snow::HandshakeState, NoiseError> {
+        let local_private_key = self.local_keys.private_key().to_bytes();
+        // the pattern is a valid constant, so parsing it can't fail
+        let builder = snow::Builder::new(NOISE_PATTERN.parse().unwrap())
+            .local_private_key(&local_private_key);
+
+        let handshake = if initiator {
+            builder.build_initiator()?
+        } else {
+            builder.build_responder()?
+        };
+        Ok(handshake)
+    }
+
+    /// Decides whether the connection with the remote can continue without encryption.
+    pub(crate) fn accept_plaintext(&self, remote: SocketAddr) -> Result<(), NoiseError> {
+        if self.mode == NoiseMode::Required {
+            warn!("{} does not support encrypted transport", remote);
+            Err(NoiseError::PlaintextRefused)
+        } else {
+            debug!("using plaintext transport with {}", remote);
+            Ok(())
+        }
+    }
+
+    /// Verifies the static key presented by the remote we have connected to.
+    pub(crate) fn verify_outbound_remote(
+        &self,
+        remote: SocketAddr,
+        remote_key: Option<&[u8]>,
+    ) -> Result<(), NoiseError> {
+        let remote_key = remote_key.ok_or(NoiseError::UnknownRemoteKey)?;
+        match self.known_nodes.expected_key(remote) {
+            Some(expected) if expected[..] == *remote_key => Ok(()),
+            Some(_) => {
+                warn!(
+                    "{} presented a static key different from the one in the topology",
+                    remote
+                );
+                Err(NoiseError::RemoteKeyMismatch)
+            }
+            None => self.handle_unknown_remote(remote),
+        }
+    }
+
+    /// Verifies the static key presented by the remote that has connected to us.
+    pub(crate) fn verify_inbound_remote(
+        &self,
+        remote: SocketAddr,
+        remote_key: Option<&[u8]>,
+    ) -> Result<(), NoiseError> {
+        let remote_key = remote_key.ok_or(NoiseError::UnknownRemoteKey)?;
+        // the source port of inbound connections is ephemeral so we can only check
+        // whether the key belongs to any node at all
+        if self.known_nodes.is_known(remote_key) {
+            Ok(())
+        } else {
+            self.handle_unknown_remote(remote)
+        }
+    }
+
+    fn handle_unknown_remote(&self, remote: SocketAddr) -> Result<(), NoiseError> {
+        if self.mode == NoiseMode::Required {
+            warn!(
+                "{} presented a static key not present in the topology",
+                remote
+            );
+            Err(NoiseError::UnknownRemoteKey)
+        } else {
+            debug!(
+                "{} presented a static key not (yet) present in the topology",
+                remote
+            );
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nymsphinx_framing::preamble::{HelloError, PreambleError};
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+#[derive(Debug)]
+pub enum NoiseError {
+    /// The remote did not understand our preamble and closed the connection.
+    LegacyPeer,
+
+    /// The remote did not complete the handshake in time.
+    HandshakeTimeout,
+
+    /// The connection would have been established without encryption, but it is required.
+    PlaintextRefused,
+
+    /// The static key presented by the remote does not belong to any known node.
+    UnknownRemoteKey,
+
+    /// The static key presented by the remote is different from the one announced for its address.
+    RemoteKeyMismatch,
+
+    PreambleError(PreambleError),
+    HandshakeError(snow::Error),
+    IoError(io::Error),
+}
+
+impl From<PreambleError> for NoiseError {
+    fn from(err: PreambleError) -> Self {
+        NoiseError::PreambleError(err)
+    }
+}
+
+impl From<HelloError> for NoiseError {
+    fn from(err: HelloError) -> Self {
+        match err {
+            HelloError::LegacyPeer => NoiseError::LegacyPeer,
+            HelloError::PreambleError(err) => NoiseError::PreambleError(err),
+            HelloError::IoError(err) => NoiseError::IoError(err),
+        }
+    }
+}
+
+impl From<snow::Error> for NoiseError {
+    fn from(err: snow::Error) -> Self {
+        NoiseError::HandshakeError(err)
+    }
+}
+
+impl From<io::Error> for NoiseError {
+    fn from(err: io::Error) -> Self {
+        NoiseError::IoError(err)
+    }
+}
+
+impl Display for NoiseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NoiseError::LegacyPeer => write!(f, "remote does not support the transport preamble"),
+            NoiseError::HandshakeTimeout => write!(f, "noise handshake has timed out"),
+            NoiseError::PlaintextRefused => {
+                write!(f, "refused to establish a plaintext connection")
+            }
+            NoiseError::UnknownRemoteKey => write!(f, "remote presented an unknown static key"),
+            NoiseError::RemoteKeyMismatch => write!(
+                f,
+                "remote presented a static key different from the one announced in the topology"
+            ),
+            NoiseError::PreambleError(err) => write!(f, "invalid preamble - {}", err),
+            NoiseError::HandshakeError(err) => write!(f, "noise handshake failure - {}", err),
+            NoiseError::IoError(err) => write!(f, "io error - {}", err),
+        }
+    }
+}
+
+impl std::error::Error for NoiseError {}
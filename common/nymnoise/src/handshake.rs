@@ -0,0 +1,362 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::codec::NoiseCodec;
+use crate::config::NoiseConfig;
+use crate::error::NoiseError;
+use log::*;
+use nymsphinx_framing::codec::SphinxCodec;
+use nymsphinx_framing::hello::Protocol;
+use nymsphinx_framing::preamble::{self, TransportMode};
+use snow::{HandshakeState, TransportState};
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Maximum size of a single noise message, as defined by the specification.
+pub(crate) const MAX_NOISE_MESSAGE_LEN: usize = 65535;
+
+/// Outcome of the negotiation performed at the beginning of a node-to-node connection.
+pub struct Negotiated {
+    /// Noise transport state if the connection is going to be encrypted,
+    /// or `None` if plaintext framing should be used instead.
+    pub transport: Option<TransportState>,
+
+    /// Framing protocol agreed on with the remote.
+    pub protocol: Protocol,
+}
+
+impl Negotiated {
+    /// Creates the codec used for exchanging framed sphinx packets over the negotiated connection.
+    pub fn into_codec(self) -> NoiseCodec<SphinxCodec> {
+        NoiseCodec::new(SphinxCodec::new(self.protocol), self.transport)
+    }
+}
+
+/// Negotiates the transport and the framing protocol of a connection we have just established
+/// with `remote`.
+///
+/// If the remote turns out to be a legacy node, `NoiseError::LegacyPeer` is returned, in which
+/// case the connection is no longer usable and should be re-established without calling this.
+pub async fn upgrade_outbound(
+    stream: &mut TcpStream,
+    config: &NoiseConfig,
+    remote: SocketAddr,
+) -> Result<Negotiated, NoiseError> {
+    tokio::time::timeout(
+        config.handshake_timeout(),
+        outbound_handshake(stream, config, remote),
+    )
+    .await
+    .map_err(|_| NoiseError::HandshakeTimeout)?
+}
+
+/// Negotiates the transport and the framing protocol of a connection `remote` has established with us.
+pub async fn upgrade_inbound(
+    stream: &mut TcpStream,
+    config: &NoiseConfig,
+    remote: SocketAddr,
+) -> Result<Negotiated, NoiseError> {
+    tokio::time::timeout(
+        config.handshake_timeout(),
+        inbound_handshake(stream, config, remote),
+    )
+    .await
+    .map_err(|_| NoiseError::HandshakeTimeout)?
+}
+
+async fn outbound_handshake(
+    stream: &mut TcpStream,
+    config: &NoiseConfig,
+    remote: SocketAddr,
+) -> Result<Negotiated, NoiseError> {
+    let hello = preamble::outbound_hello(stream, config.capabilities()).await?;
+    if hello.transport == TransportMode::Plaintext {
+        config.accept_plaintext(remote)?;
+        return Ok(Negotiated {
+            transport: None,
+            protocol: hello.protocol,
+        });
+    }
+
+    let mut handshake = config.build_handshake(true)?;
+    // -> e
+    write_handshake_message(stream, &mut handshake).await?;
+    // <- e, ee, s, es
+    read_handshake_message(stream, &mut handshake).await?;
+    // -> s, se
+    write_handshake_message(stream, &mut handshake).await?;
+
+    let transport = handshake.into_transport_mode()?;
+    config.verify_outbound_remote(remote, transport.get_remote_static())?;
+    debug!("established encrypted transport with {}", remote);
+    Ok(Negotiated {
+        transport: Some(transport),
+        protocol: hello.protocol,
+    })
+}
+
+async fn inbound_handshake(
+    stream: &mut TcpStream,
+    config: &NoiseConfig,
+    remote: SocketAddr,
+) -> Result<Negotiated, NoiseError> {
+    let hello = preamble::inbound_hello(stream, config.capabilities()).await?;
+    if hello.transport == TransportMode::Plaintext {
+        config.accept_plaintext(remote)?;
+        return Ok(Negotiated {
+            transport: None,
+            protocol: hello.protocol,
+        });
+    }
+
+    let mut handshake = config.build_handshake(false)?;
+    // <- e
+    read_handshake_message(stream, &mut handshake).await?;
+    // -> e, ee, s, es
+    write_handshake_message(stream, &mut handshake).await?;
+    // <- s, se
+    read_handshake_message(stream, &mut handshake).await?;
+
+    let transport = handshake.into_transport_mode()?;
+    config.verify_inbound_remote(remote, transport.get_remote_static())?;
+    debug!("established encrypted transport with {}", remote);
+    Ok(Negotiated {
+        transport: Some(transport),
+        protocol: hello.protocol,
+    })
+}
+
+async fn write_handshake_message(
+    stream: &mut TcpStream,
+    handshake: &mut HandshakeState,
+) -> Result<(), NoiseError> {
+    let mut message = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+    let len = handshake.write_message(&[], &mut message)?;
+
+    stream.write_all(&(len as u16).to_be_bytes()).await?;
+    stream.write_all(&message[..len]).await?;
+    Ok(())
+}
+
+async fn read_handshake_message(
+    stream: &mut TcpStream,
+    handshake: &mut HandshakeState,
+) -> Result<(), NoiseError> {
+    let mut len = [0u8; 2];
+    stream.read_exact(&mut len).await?;
+    let mut message = vec![0u8; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut message).await?;
+
+    let mut payload = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+    handshake.read_message(&message, &mut payload)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{KnownNodes, NoiseMode};
+    use crypto::asymmetric::encryption;
+    use nymsphinx_framing::hello::{Capabilities, ProtocolVersion};
+    use nymsphinx_framing::preamble::{Preamble, PreambleError, PREAMBLE_MAGIC};
+    use nymsphinx_params::{PacketMode, PacketSize};
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    fn test_config(mode: NoiseMode) -> (NoiseConfig, KnownNodes, encryption::PublicKey) {
+        let mut rng = rand::rngs::OsRng;
+        let keys = encryption::KeyPair::new(&mut rng);
+        let public_key = *keys.public_key();
+        let known_nodes = KnownNodes::new();
+        (
+            NoiseConfig::new(Arc::new(keys), mode, known_nodes.clone()),
+            known_nodes,
+            public_key,
+        )
+    }
+
+    type UpgradeResult = Result<Negotiated, NoiseError>;
+
+    async fn connect(
+        initiator: &NoiseConfig,
+        responder: &NoiseConfig,
+    ) -> (UpgradeResult, UpgradeResult) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let responder = responder.clone();
+        let responder_fut = tokio::spawn(async move {
+            let (mut socket, remote) = listener.accept().await.unwrap();
+            upgrade_inbound(&mut socket, &responder, remote).await
+        });
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let outbound = upgrade_outbound(&mut stream, initiator, address).await;
+        // make sure the responder doesn't wait for more handshake messages if we failed
+        drop(stream);
+        (outbound, responder_fut.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn handshake_succeeds_between_known_nodes() {
+        let (initiator, initiator_nodes, initiator_key) = test_config(NoiseMode::Required);
+        let (responder, responder_nodes, responder_key) = test_config(NoiseMode::Required);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        initiator_nodes.replace(vec![(address, responder_key)]);
+        // the address is irrelevant for inbound connections
+        responder_nodes.replace(vec![(address, initiator_key)]);
+
+        let responder_fut = tokio::spawn(async move {
+            let (mut socket, remote) = listener.accept().await.unwrap();
+            upgrade_inbound(&mut socket, &responder, remote).await
+        });
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let outbound = upgrade_outbound(&mut stream, &initiator, address)
+            .await
+            .unwrap()
+            .transport
+            .unwrap();
+        let inbound = responder_fut.await.unwrap().unwrap().transport.unwrap();
+        assert_eq!(
+            &responder_key.to_bytes()[..],
+            outbound.get_remote_static().unwrap()
+        );
+        assert_eq!(
+            &initiator_key.to_bytes()[..],
+            inbound.get_remote_static().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_keys_are_only_accepted_in_optional_mode() {
+        let (initiator, ..) = test_config(NoiseMode::Optional);
+        let (responder, ..) = test_config(NoiseMode::Optional);
+        let (outbound, inbound) = connect(&initiator, &responder).await;
+        assert!(outbound.unwrap().transport.is_some());
+        assert!(inbound.unwrap().transport.is_some());
+
+        let (initiator, ..) = test_config(NoiseMode::Required);
+        let (outbound, _) = connect(&initiator, &responder).await;
+        assert!(matches!(outbound, Err(NoiseError::UnknownRemoteKey)));
+    }
+
+    #[tokio::test]
+    async fn disabled_responder_negotiates_plaintext() {
+        let (initiator, ..) = test_config(NoiseMode::Optional);
+        let (responder, ..) = test_config(NoiseMode::Disabled);
+        let (outbound, inbound) = connect(&initiator, &responder).await;
+        assert!(outbound.unwrap().transport.is_none());
+        assert!(inbound.unwrap().transport.is_none());
+
+        let (initiator, ..) = test_config(NoiseMode::Required);
+        let (outbound, _) = connect(&initiator, &responder).await;
+        assert!(matches!(outbound, Err(NoiseError::PlaintextRefused)));
+    }
+
+    #[tokio::test]
+    async fn common_protocol_is_negotiated() {
+        let (initiator, ..) = test_config(NoiseMode::Optional);
+        let (responder, ..) = test_config(NoiseMode::Optional);
+        let responder = responder.with_capabilities(Capabilities::new(
+            vec![ProtocolVersion::V1],
+            vec![PacketSize::RegularPacket, PacketSize::AckPacket],
+            vec![PacketMode::Mix],
+        ));
+
+        let (outbound, inbound) = connect(&initiator, &responder).await;
+        let (outbound, inbound) = (outbound.unwrap(), inbound.unwrap());
+        assert_eq!(outbound.protocol, inbound.protocol);
+        assert_eq!(ProtocolVersion::V1, outbound.protocol.version());
+        assert!(outbound
+            .protocol
+            .supports_packet_size(PacketSize::AckPacket));
+        assert!(!outbound
+            .protocol
+            .supports_packet_size(PacketSize::ExtendedPacket));
+    }
+
+    #[tokio::test]
+    async fn disabled_initiator_still_negotiates_the_protocol() {
+        let (initiator, ..) = test_config(NoiseMode::Disabled);
+        let (responder, ..) = test_config(NoiseMode::Optional);
+        let (outbound, inbound) = connect(&initiator, &responder).await;
+        let (outbound, inbound) = (outbound.unwrap(), inbound.unwrap());
+        assert!(outbound.transport.is_none());
+        assert!(inbound.transport.is_none());
+        assert_eq!(ProtocolVersion::V1, outbound.protocol.version());
+        assert_eq!(outbound.protocol, inbound.protocol);
+    }
+
+    #[tokio::test]
+    async fn incompatible_protocols_are_rejected() {
+        let (initiator, ..) = test_config(NoiseMode::Optional);
+        let (responder, ..) = test_config(NoiseMode::Optional);
+        let initiator = initiator.with_capabilities(Capabilities::new(
+            vec![ProtocolVersion::V1],
+            vec![PacketSize::RegularPacket],
+            vec![PacketMode::Mix],
+        ));
+        let responder = responder.with_capabilities(Capabilities::legacy());
+
+        let (outbound, inbound) = connect(&initiator, &responder).await;
+        assert!(matches!(
+            outbound,
+            Err(NoiseError::PreambleError(
+                PreambleError::NoCommonProtocolVersion
+            ))
+        ));
+        assert!(matches!(
+            inbound,
+            Err(NoiseError::PreambleError(
+                PreambleError::NoCommonProtocolVersion
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn legacy_framing_is_used_with_older_initiators() {
+        let (responder, ..) = test_config(NoiseMode::Optional);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let responder_fut = tokio::spawn(async move {
+            let (mut socket, remote) = listener.accept().await.unwrap();
+            upgrade_inbound(&mut socket, &responder, remote).await
+        });
+
+        // initiator understanding only the first version of the preamble
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(&[PREAMBLE_MAGIC, 1, TransportMode::Plaintext as u8])
+            .await
+            .unwrap();
+        let mut response = [0u8; Preamble::SIZE];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(1, response[1]);
+
+        let inbound = responder_fut.await.unwrap().unwrap();
+        assert!(inbound.transport.is_none());
+        assert_eq!(ProtocolVersion::Legacy, inbound.protocol.version());
+    }
+
+    #[tokio::test]
+    async fn legacy_peers_are_detected() {
+        let (initiator, ..) = test_config(NoiseMode::Optional);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            // legacy node would fail to decode the preamble and close the connection
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut header = [0u8; 2];
+            socket.read_exact(&mut header).await.unwrap();
+        });
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let outbound = upgrade_outbound(&mut stream, &initiator, address).await;
+        assert!(matches!(outbound, Err(NoiseError::LegacyPeer)));
+    }
+}
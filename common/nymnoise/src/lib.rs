@@ -0,0 +1,22 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optional encrypted and authenticated transport for node-to-node connections.
+//!
+//! Before sending any packets, both sides of the connection exchange the framing preambles
+//! alongside their capabilities, which include the transports they support. If both of them
+//! support the noise transport, they perform a `Noise_XX` handshake, using the x25519 sphinx keys
+//! of the nodes as their static keys, and all framed packets are afterwards sent as noise
+//! transport messages. Legacy nodes, that do not understand the preamble, keep using
+//! the plaintext legacy framing.
+
+pub mod codec;
+pub mod config;
+pub mod error;
+pub mod handshake;
+
+pub use codec::NoiseCodec;
+pub use config::{KnownNodes, NoiseConfig, NoiseMode};
+pub use error::NoiseError;
+pub use handshake::{upgrade_inbound, upgrade_outbound, Negotiated};
+pub use snow::TransportState;
@@ -22,14 +22,14 @@ impl Display for MixPacketFormattingError {
         match self {
             TooFewBytesProvided => write!(f, "Too few bytes provided to recover from bytes"),
             InvalidAddress => write!(f, "address field was incorrectly encoded"),
-            InvalidPacketSize(actual) =>
-                write!(
-                    f,
-                    "received request had invalid size. (actual: {}, but expected one of: {} (ACK), {} (REGULAR), {} (EXTENDED))",
-                    actual, PacketSize::AckPacket.size(), PacketSize::RegularPacket.size(), PacketSize::ExtendedPacket.size()
-                ),
+            InvalidPacketSize(actual) => write!(
+                f,
+                "received request had invalid size. (actual: {}, but expected one of: {:?})",
+                actual,
+                PacketSize::all().map(PacketSize::size).collect::<Vec<_>>()
+            ),
             MalformedSphinxPacket => write!(f, "received sphinx packet was malformed"),
-            InvalidPacketMode => write!(f, "provided packet mode is invalid")
+            InvalidPacketMode => write!(f, "provided packet mode is invalid"),
         }
     }
 }
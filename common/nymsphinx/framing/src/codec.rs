@@ -1,6 +1,7 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::hello::Protocol;
 use crate::packet::{FramedSphinxPacket, Header};
 use bytes::{Buf, BufMut, BytesMut};
 use nymsphinx_params::packet_modes::InvalidPacketMode;
@@ -14,6 +15,9 @@ use tokio_util::codec::{Decoder, Encoder};
 pub enum SphinxCodecError {
     InvalidPacketSize,
     InvalidPacketMode,
+    /// The header specifies a framing protocol version different from the one
+    /// negotiated for the connection.
+    UnsupportedProtocolVersion(u8),
     MalformedSphinxPacket,
     IoError(io::Error),
 }
@@ -33,6 +37,10 @@ impl From<SphinxCodecError> for io::Error {
             SphinxCodecError::InvalidPacketMode => {
                 io::Error::new(io::ErrorKind::InvalidInput, "invalid packet mode")
             }
+            SphinxCodecError::UnsupportedProtocolVersion(version) => io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported framing protocol version {}", version),
+            ),
             SphinxCodecError::MalformedSphinxPacket => {
                 io::Error::new(io::ErrorKind::InvalidData, "malformed packet")
             }
@@ -53,15 +61,31 @@ impl From<InvalidPacketMode> for SphinxCodecError {
     }
 }
 
-// TODO: in the future it could be extended to have state containing symmetric encryption key
-// so that all data could be encrypted easily (alternatively we could just slap TLS)
-pub struct SphinxCodec;
+/// Frames sphinx packets according to the protocol negotiated for the connection.
+#[derive(Default)]
+pub struct SphinxCodec {
+    protocol: Protocol,
+}
+
+impl SphinxCodec {
+    pub fn new(protocol: Protocol) -> Self {
+        SphinxCodec { protocol }
+    }
+
+    fn header_size(&self) -> usize {
+        Header::size(self.protocol.version())
+    }
+}
 
 impl Encoder<FramedSphinxPacket> for SphinxCodec {
     type Error = SphinxCodecError;
 
     fn encode(&mut self, item: FramedSphinxPacket, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        item.header.encode(dst);
+        let header = Header {
+            protocol_version: self.protocol.version(),
+            ..item.header
+        };
+        header.encode(dst);
         dst.put(item.packet.to_bytes().as_ref());
         Ok(())
     }
@@ -75,19 +99,27 @@ impl Decoder for SphinxCodec {
         if src.is_empty() {
             // can't do anything if we have no bytes, but let's reserve enough for the most
             // conservative case, i.e. receiving an ack packet
-            src.reserve(Header::SIZE + PacketSize::AckPacket.size());
+            src.reserve(self.header_size() + PacketSize::AckPacket.size());
             return Ok(None);
         }
 
         // because header is so small and simple it makes no point in trying to cache
         // this result. It will be just simpler to re-decode it
-        let header = match Header::decode(src)? {
+        let header = match Header::decode(src, self.protocol.version())? {
             Some(header) => header,
             None => return Ok(None), // we have some data but not enough to get header back
         };
 
+        // the remote should have never sent us packets we haven't agreed on
+        if !self.protocol.supports_packet_size(header.packet_size) {
+            return Err(SphinxCodecError::InvalidPacketSize);
+        }
+        if !self.protocol.supports_packet_mode(header.packet_mode) {
+            return Err(SphinxCodecError::InvalidPacketMode);
+        }
+
         let sphinx_packet_size = header.packet_size.size();
-        let frame_len = Header::SIZE + sphinx_packet_size;
+        let frame_len = self.header_size() + sphinx_packet_size;
 
         if src.len() < frame_len {
             // we don't have enough bytes to read the rest of frame
@@ -96,7 +128,7 @@ impl Decoder for SphinxCodec {
         }
 
         // advance buffer past the header - at this point we have enough bytes
-        src.advance(Header::SIZE);
+        src.advance(self.header_size());
         let sphinx_packet_bytes = src.split_to(sphinx_packet_size);
         let sphinx_packet = match SphinxPacket::from_bytes(&sphinx_packet_bytes) {
             Ok(sphinx_packet) => sphinx_packet,
@@ -115,20 +147,21 @@ impl Decoder for SphinxCodec {
         // has appropriate capacity in anticipation of future calls to decode.
         // Failing to do so leads to inefficiency.
 
-        // if we have at least one more byte available, we can reserve enough bytes for
-        // the entire next frame, if not, we assume the next frame is an ack packet and
-        // reserve for that.
-        if !src.is_empty() {
-            let next_packet_len = match PacketSize::try_from(src[0]) {
+        // if we have enough bytes available to read the size of the next packet, we can reserve
+        // enough bytes for the entire next frame, if not, we assume the next frame is an ack packet
+        // and reserve for that.
+        let packet_size_offset = Header::packet_size_offset(self.protocol.version());
+        if src.len() > packet_size_offset {
+            let next_packet_len = match PacketSize::try_from(src[packet_size_offset]) {
                 Ok(next_packet_len) => next_packet_len,
                 // the next frame will be malformed but let's leave handling the error to the next
                 // call to 'decode', as presumably, the current sphinx packet is still valid
                 Err(_) => return Ok(Some(nymsphinx_packet)),
             };
-            let next_frame_len = next_packet_len.size() + Header::SIZE;
-            src.reserve(next_frame_len - 1);
+            let next_frame_len = next_packet_len.size() + self.header_size();
+            src.reserve(next_frame_len.saturating_sub(src.len()));
         } else {
-            src.reserve(Header::SIZE + PacketSize::AckPacket.size());
+            src.reserve(self.header_size() + PacketSize::AckPacket.size());
         }
 
         Ok(Some(nymsphinx_packet))
@@ -138,6 +171,8 @@ impl Decoder for SphinxCodec {
 #[cfg(test)]
 mod packet_encoding {
     use super::*;
+    use crate::hello::{Capabilities, ProtocolVersion};
+    use nymsphinx_params::PacketMode;
     use nymsphinx_types::builder::SphinxPacketBuilder;
     use nymsphinx_types::{
         crypto, Delay as SphinxDelay, Destination, DestinationAddressBytes, Node, NodeAddressBytes,
@@ -189,13 +224,73 @@ mod packet_encoding {
         };
 
         let mut bytes = BytesMut::new();
-        SphinxCodec.encode(packet, &mut bytes).unwrap();
-        let decoded = SphinxCodec.decode(&mut bytes).unwrap().unwrap();
+        SphinxCodec::default().encode(packet, &mut bytes).unwrap();
+        let decoded = SphinxCodec::default().decode(&mut bytes).unwrap().unwrap();
 
         assert_eq!(decoded.header, header);
         assert_eq!(decoded.packet.to_bytes(), sphinx_bytes)
     }
 
+    #[test]
+    fn legacy_packets_can_be_decoded() {
+        let protocol = Capabilities::current()
+            .negotiate(&Capabilities::legacy())
+            .unwrap();
+        let mut codec = SphinxCodec::new(protocol);
+        let packet = FramedSphinxPacket::new(
+            make_valid_sphinx_packet(Default::default()),
+            PacketMode::Mix,
+        );
+
+        let mut bytes = BytesMut::new();
+        codec.encode(packet, &mut bytes).unwrap();
+        assert_eq!(
+            bytes.len(),
+            Header::size(ProtocolVersion::Legacy) + PacketSize::default().size()
+        );
+        assert_eq!(bytes[0], PacketSize::default().id());
+
+        let decoded = codec.decode(&mut bytes).unwrap().unwrap();
+        assert_eq!(decoded.protocol_version(), ProtocolVersion::Legacy);
+    }
+
+    #[test]
+    fn packets_with_unknown_protocol_version_are_rejected() {
+        let packet = FramedSphinxPacket::new(
+            make_valid_sphinx_packet(Default::default()),
+            PacketMode::Mix,
+        );
+
+        let mut bytes = BytesMut::new();
+        SphinxCodec::default().encode(packet, &mut bytes).unwrap();
+        bytes[0] = 42;
+        assert!(matches!(
+            SphinxCodec::default().decode(&mut bytes),
+            Err(SphinxCodecError::UnsupportedProtocolVersion(42))
+        ));
+    }
+
+    #[test]
+    fn packets_not_agreed_on_are_rejected() {
+        let remote = Capabilities::new(
+            vec![ProtocolVersion::V1],
+            vec![PacketSize::AckPacket],
+            vec![PacketMode::Mix],
+        );
+        let mut codec = SphinxCodec::new(Capabilities::current().negotiate(&remote).unwrap());
+        let packet = FramedSphinxPacket::new(
+            make_valid_sphinx_packet(PacketSize::RegularPacket),
+            PacketMode::Mix,
+        );
+
+        let mut bytes = BytesMut::new();
+        codec.encode(packet, &mut bytes).unwrap();
+        assert!(matches!(
+            codec.decode(&mut bytes),
+            Err(SphinxCodecError::InvalidPacketSize)
+        ));
+    }
+
     #[cfg(test)]
     mod decode_will_allocate_enough_bytes_for_next_call {
         use super::*;
@@ -204,10 +299,13 @@ mod packet_encoding {
         fn for_empty_bytes() {
             // empty bytes should allocate for header + ack packet
             let mut empty_bytes = BytesMut::new();
-            assert!(SphinxCodec.decode(&mut empty_bytes).unwrap().is_none());
+            assert!(SphinxCodec::default()
+                .decode(&mut empty_bytes)
+                .unwrap()
+                .is_none());
             assert_eq!(
                 empty_bytes.capacity(),
-                Header::SIZE + PacketSize::AckPacket.size()
+                Header::size(Default::default()) + PacketSize::AckPacket.size()
             );
         }
 
@@ -221,14 +319,18 @@ mod packet_encoding {
             ];
             for packet_size in packet_sizes {
                 let header = Header {
+                    protocol_version: Default::default(),
                     packet_size,
                     packet_mode: Default::default(),
                 };
                 let mut bytes = BytesMut::new();
                 header.encode(&mut bytes);
-                assert!(SphinxCodec.decode(&mut bytes).unwrap().is_none());
+                assert!(SphinxCodec::default().decode(&mut bytes).unwrap().is_none());
 
-                assert_eq!(bytes.capacity(), Header::SIZE + packet_size.size())
+                assert_eq!(
+                    bytes.capacity(),
+                    Header::size(Default::default()) + packet_size.size()
+                )
             }
         }
 
@@ -241,17 +343,18 @@ mod packet_encoding {
             };
 
             let mut bytes = BytesMut::new();
-            SphinxCodec.encode(packet, &mut bytes).unwrap();
-            assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
+            SphinxCodec::default().encode(packet, &mut bytes).unwrap();
+            assert!(SphinxCodec::default().decode(&mut bytes).unwrap().is_some());
             assert_eq!(
                 bytes.capacity(),
-                Header::SIZE + PacketSize::AckPacket.size()
+                Header::size(Default::default()) + PacketSize::AckPacket.size()
             );
         }
 
         #[test]
         fn for_full_frame_with_extra_byte() {
-            // if there was at least 1 byte left, there should be enough space for entire next frame
+            // if there was enough bytes left to read the packet size,
+            // there should be enough space for entire next frame
             let packet_sizes = vec![
                 PacketSize::AckPacket,
                 PacketSize::RegularPacket,
@@ -265,11 +368,14 @@ mod packet_encoding {
                 };
 
                 let mut bytes = BytesMut::new();
-                SphinxCodec.encode(first_packet, &mut bytes).unwrap();
-                bytes.put_u8(packet_size as u8);
-                assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
-
-                assert!(bytes.capacity() >= Header::SIZE + packet_size.size())
+                SphinxCodec::default()
+                    .encode(first_packet, &mut bytes)
+                    .unwrap();
+                bytes.put_u8(ProtocolVersion::default() as u8);
+                bytes.put_u8(packet_size.id());
+                assert!(SphinxCodec::default().decode(&mut bytes).unwrap().is_some());
+
+                assert!(bytes.capacity() >= Header::size(Default::default()) + packet_size.size())
             }
         }
     }
@@ -288,12 +394,12 @@ mod packet_encoding {
 
         let mut bytes = BytesMut::new();
 
-        SphinxCodec.encode(packet1, &mut bytes).unwrap();
-        SphinxCodec.encode(packet2, &mut bytes).unwrap();
+        SphinxCodec::default().encode(packet1, &mut bytes).unwrap();
+        SphinxCodec::default().encode(packet2, &mut bytes).unwrap();
 
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_none());
+        assert!(SphinxCodec::default().decode(&mut bytes).unwrap().is_some());
+        assert!(SphinxCodec::default().decode(&mut bytes).unwrap().is_some());
+        assert!(SphinxCodec::default().decode(&mut bytes).unwrap().is_none());
     }
 
     #[test]
@@ -311,18 +417,20 @@ mod packet_encoding {
         let mut bytes = BytesMut::new();
         let mut bytes_tmp = BytesMut::new();
 
-        SphinxCodec.encode(packet1, &mut bytes).unwrap();
-        SphinxCodec.encode(packet2, &mut bytes_tmp).unwrap();
+        SphinxCodec::default().encode(packet1, &mut bytes).unwrap();
+        SphinxCodec::default()
+            .encode(packet2, &mut bytes_tmp)
+            .unwrap();
 
         let tmp = bytes_tmp.split_off(100);
         bytes.put(bytes_tmp);
 
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_none());
+        assert!(SphinxCodec::default().decode(&mut bytes).unwrap().is_some());
+        assert!(SphinxCodec::default().decode(&mut bytes).unwrap().is_none());
 
         bytes.put(tmp);
 
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_none());
+        assert!(SphinxCodec::default().decode(&mut bytes).unwrap().is_some());
+        assert!(SphinxCodec::default().decode(&mut bytes).unwrap().is_none());
     }
 }
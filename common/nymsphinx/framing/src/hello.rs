@@ -0,0 +1,422 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Capabilities exchanged by both sides of a node-to-node connection as part of the preamble,
+//! used to agree on the framing protocol version, on the packets that can be sent over it
+//! and on the transport, such as noise, used for the rest of the connection.
+//!
+//! Values announced by the remote that are unknown to us are simply ignored, so that new
+//! protocol versions, packet sizes or modes can be introduced without breaking existing nodes.
+//! Custom (i.e. not built-in) packet sizes are announced alongside their definitions and are
+//! only used if both sides have registered them identically.
+
+use crate::preamble::{PreambleError, TransportMode};
+use nymsphinx_params::packet_sizes::PacketSize;
+use nymsphinx_params::PacketMode;
+use std::convert::TryFrom;
+
+/// Length of the definition of a custom packet size, i.e. its id and its plaintext size.
+const CUSTOM_PACKET_SIZE_LEN: usize = 5;
+
+/// Version of the framing protocol used for sending sphinx packets over a connection.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProtocolVersion {
+    /// Original framing, where the header consists only of the packet size and packet mode.
+    /// Used with nodes that do not send the capabilities.
+    Legacy = 0,
+
+    /// Framing where every header starts with the protocol version byte.
+    V1 = 1,
+}
+
+/// The newest framing protocol version this node understands.
+pub const CURRENT_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::V1;
+
+impl TryFrom<u8> for ProtocolVersion {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            _ if value == (ProtocolVersion::Legacy as u8) => Ok(Self::Legacy),
+            _ if value == (ProtocolVersion::V1 as u8) => Ok(Self::V1),
+            _ => Err(value),
+        }
+    }
+}
+
+impl Default for ProtocolVersion {
+    fn default() -> Self {
+        CURRENT_PROTOCOL_VERSION
+    }
+}
+
+/// Protocol versions, packet sizes, packet modes and transports supported by a node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    versions: Vec<ProtocolVersion>,
+    packet_sizes: Vec<PacketSize>,
+    packet_modes: Vec<PacketMode>,
+    transports: Vec<TransportMode>,
+}
+
+impl Capabilities {
+    pub fn new(
+        versions: Vec<ProtocolVersion>,
+        packet_sizes: Vec<PacketSize>,
+        packet_modes: Vec<PacketMode>,
+    ) -> Self {
+        Capabilities {
+            versions,
+            packet_sizes,
+            packet_modes,
+            transports: vec![TransportMode::Plaintext],
+        }
+    }
+
+    /// Everything this node is capable of handling.
+    pub fn current() -> Self {
+        Capabilities {
+            versions: vec![ProtocolVersion::V1, ProtocolVersion::Legacy],
+            packet_sizes: PacketSize::all().collect(),
+            packet_modes: vec![PacketMode::Mix, PacketMode::Vpn],
+            transports: vec![TransportMode::Noise, TransportMode::Plaintext],
+        }
+    }
+
+    /// Sets the transports this node is willing to use, for example
+    /// when it's not configured to use noise.
+    pub fn with_transports(mut self, transports: Vec<TransportMode>) -> Self {
+        self.transports = transports;
+        self
+    }
+
+    pub fn supports_transport(&self, transport: TransportMode) -> bool {
+        self.transports.contains(&transport)
+    }
+
+    /// Transport requested when initiating a connection.
+    pub fn preferred_transport(&self) -> TransportMode {
+        if self.supports_transport(TransportMode::Noise) {
+            TransportMode::Noise
+        } else {
+            TransportMode::Plaintext
+        }
+    }
+
+    /// Restricts the supported packet sizes to the specified ones, for example
+    /// when the node operator does not wish to handle some of them.
+    pub fn with_packet_sizes(mut self, packet_sizes: &[PacketSize]) -> Self {
+        self.packet_sizes.retain(|size| packet_sizes.contains(size));
+        self
+    }
+
+    pub fn packet_sizes(&self) -> &[PacketSize] {
+        &self.packet_sizes
+    }
+
+    /// Capabilities implicitly assumed for nodes that do not send them.
+    // note: legacy nodes reject packets sent in the `Vpn` mode
+    pub fn legacy() -> Self {
+        Capabilities {
+            versions: vec![ProtocolVersion::Legacy],
+            packet_sizes: vec![
+                PacketSize::RegularPacket,
+                PacketSize::AckPacket,
+                PacketSize::ExtendedPacket,
+            ],
+            packet_modes: vec![PacketMode::Mix],
+            transports: vec![TransportMode::Plaintext],
+        }
+    }
+
+    /// Chooses the newest protocol version supported by both sides alongside
+    /// the packet sizes and modes both of them can handle.
+    pub fn negotiate(&self, remote: &Capabilities) -> Result<Protocol, PreambleError> {
+        let version = self
+            .versions
+            .iter()
+            .copied()
+            .filter(|version| remote.versions.contains(version))
+            .max_by_key(|version| *version as u8)
+            .ok_or(PreambleError::NoCommonProtocolVersion)?;
+
+        Ok(Protocol {
+            version,
+            packet_sizes: self
+                .packet_sizes
+                .iter()
+                .filter(|size| remote.packet_sizes.contains(size))
+                .copied()
+                .collect(),
+            packet_modes: self
+                .packet_modes
+                .iter()
+                .filter(|mode| remote.packet_modes.contains(mode))
+                .copied()
+                .collect(),
+        })
+    }
+
+    /// Encodes the capabilities, prefixed with their length, as
+    /// `len || n || versions || n || packet sizes || n || packet modes || n || transports
+    /// || n || custom packet sizes`, where each custom packet size is defined as
+    /// `id || plaintext size (u32 BE)`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let versions = self.versions.iter().map(|version| *version as u8);
+        let packet_sizes = self.packet_sizes.iter().map(|size| size.id());
+        let packet_modes = self.packet_modes.iter().map(|mode| *mode as u8);
+        let transports = self.transports.iter().map(|transport| *transport as u8);
+        let custom_packet_sizes = self
+            .packet_sizes
+            .iter()
+            .filter(|size| !size.is_built_in())
+            .flat_map(|size| {
+                let mut definition = vec![size.id()];
+                definition.extend_from_slice(&(size.plaintext_size() as u32).to_be_bytes());
+                definition
+            })
+            .collect::<Vec<_>>();
+
+        let mut bytes = vec![0];
+        bytes.push(self.versions.len() as u8);
+        bytes.extend(versions);
+        bytes.push(self.packet_sizes.len() as u8);
+        bytes.extend(packet_sizes);
+        bytes.push(self.packet_modes.len() as u8);
+        bytes.extend(packet_modes);
+        bytes.push(self.transports.len() as u8);
+        bytes.extend(transports);
+        bytes.push(custom_packet_sizes.len() as u8);
+        bytes.extend(custom_packet_sizes);
+        bytes[0] = (bytes.len() - 1) as u8;
+        bytes
+    }
+
+    /// Recovers the capabilities from the bytes following the length prefix.
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, PreambleError> {
+        let mut bytes = bytes;
+        let versions = next_section(&mut bytes)?
+            .iter()
+            .filter_map(|version| ProtocolVersion::try_from(*version).ok())
+            .collect();
+        let mut packet_sizes: Vec<_> = next_section(&mut bytes)?
+            .iter()
+            .filter_map(|size| PacketSize::try_from(*size).ok())
+            .collect();
+        let packet_modes = next_section(&mut bytes)?
+            .iter()
+            .filter_map(|mode| PacketMode::try_from(*mode).ok())
+            .collect();
+        // the transports were not announced by the first nodes sending the capabilities,
+        // which only ever requested the transport in the preamble itself
+        let transports = optional_section(&mut bytes)?
+            .iter()
+            .filter_map(|transport| TransportMode::try_from(*transport).ok())
+            .collect();
+        let custom_packet_sizes = optional_section(&mut bytes)?;
+        if custom_packet_sizes.len() % CUSTOM_PACKET_SIZE_LEN != 0 {
+            return Err(PreambleError::MalformedCapabilities);
+        }
+        let custom_packet_sizes = custom_packet_sizes
+            .chunks_exact(CUSTOM_PACKET_SIZE_LEN)
+            .filter_map(|definition| {
+                let mut plaintext_size = [0u8; 4];
+                plaintext_size.copy_from_slice(&definition[1..]);
+                PacketSize::new(definition[0], u32::from_be_bytes(plaintext_size) as usize).ok()
+            })
+            .collect::<Vec<_>>();
+        // the same id might denote a different size on the remote
+        packet_sizes.retain(|size| size.is_built_in() || custom_packet_sizes.contains(size));
+
+        Ok(Capabilities {
+            versions,
+            packet_sizes,
+            packet_modes,
+            transports,
+        })
+    }
+}
+
+/// Splits off the next length-prefixed section of the encoded capabilities, if it was sent.
+fn optional_section<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], PreambleError> {
+    if bytes.is_empty() {
+        Ok(&[])
+    } else {
+        next_section(bytes)
+    }
+}
+
+/// Splits off the next length-prefixed section of the encoded capabilities.
+fn next_section<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], PreambleError> {
+    let (len, rest) = bytes
+        .split_first()
+        .ok_or(PreambleError::MalformedCapabilities)?;
+    if rest.len() < *len as usize {
+        return Err(PreambleError::MalformedCapabilities);
+    }
+    let (section, rest) = rest.split_at(*len as usize);
+    *bytes = rest;
+    Ok(section)
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities::current()
+    }
+}
+
+/// Framing protocol agreed on by both sides of the connection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Protocol {
+    version: ProtocolVersion,
+    packet_sizes: Vec<PacketSize>,
+    packet_modes: Vec<PacketMode>,
+}
+
+impl Protocol {
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    pub fn supports_packet_size(&self, packet_size: PacketSize) -> bool {
+        self.packet_sizes.contains(&packet_size)
+    }
+
+    pub fn supports_packet_mode(&self, packet_mode: PacketMode) -> bool {
+        self.packet_modes.contains(&packet_mode)
+    }
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        let current = Capabilities::current();
+        // we can always agree with ourselves
+        current.negotiate(&current).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities_can_be_recovered_from_bytes() {
+        for capabilities in &[Capabilities::current(), Capabilities::legacy()] {
+            let bytes = capabilities.to_bytes();
+            assert_eq!(bytes[0] as usize, bytes.len() - 1);
+            assert_eq!(
+                capabilities,
+                &Capabilities::try_from_bytes(&bytes[1..]).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_values_are_ignored() {
+        let bytes = [2, 1, 42, 2, 1, 42, 1, 42, 2, 0, 42];
+        let capabilities = Capabilities::try_from_bytes(&bytes).unwrap();
+        assert_eq!(
+            Capabilities::new(
+                vec![ProtocolVersion::V1],
+                vec![PacketSize::RegularPacket],
+                vec![]
+            ),
+            capabilities
+        );
+    }
+
+    #[test]
+    fn transports_are_optional() {
+        let bytes = [1, 1, 1, 1, 1, 0];
+        let capabilities = Capabilities::try_from_bytes(&bytes).unwrap();
+        assert!(!capabilities.supports_transport(TransportMode::Noise));
+        assert!(!capabilities.supports_transport(TransportMode::Plaintext));
+
+        let plaintext_only =
+            Capabilities::current().with_transports(vec![TransportMode::Plaintext]);
+        assert_eq!(
+            TransportMode::Plaintext,
+            plaintext_only.preferred_transport()
+        );
+        assert_eq!(
+            TransportMode::Noise,
+            Capabilities::current().preferred_transport()
+        );
+    }
+
+    #[test]
+    fn custom_packet_sizes_must_be_defined_identically() {
+        let custom = PacketSize::new(210, 3 * 1024).unwrap();
+        PacketSize::register(custom).unwrap();
+
+        let capabilities = Capabilities::new(
+            vec![ProtocolVersion::V1],
+            vec![PacketSize::AckPacket, custom],
+            vec![PacketMode::Mix],
+        );
+        assert_eq!(
+            capabilities,
+            Capabilities::try_from_bytes(&capabilities.to_bytes()[1..]).unwrap()
+        );
+
+        // the remote uses the same id for a different size
+        let bytes = [1, 1, 2, 2, 210, 1, 0, 0, 5, 210, 0, 0, 4, 0];
+        let capabilities = Capabilities::try_from_bytes(&bytes).unwrap();
+        assert_eq!(&[PacketSize::AckPacket], capabilities.packet_sizes());
+
+        // the remote does not define the size at all
+        let bytes = [1, 1, 2, 2, 210, 1, 0, 0];
+        let capabilities = Capabilities::try_from_bytes(&bytes).unwrap();
+        assert_eq!(&[PacketSize::AckPacket], capabilities.packet_sizes());
+    }
+
+    #[test]
+    fn truncated_capabilities_are_rejected() {
+        assert_eq!(
+            Err(PreambleError::MalformedCapabilities),
+            Capabilities::try_from_bytes(&[1, 1, 3, 1])
+        );
+        assert_eq!(
+            Err(PreambleError::MalformedCapabilities),
+            Capabilities::try_from_bytes(&[1, 1, 0])
+        );
+    }
+
+    #[test]
+    fn negotiation_chooses_newest_common_version() {
+        let current = Capabilities::current();
+        assert_eq!(
+            ProtocolVersion::V1,
+            current.negotiate(&current).unwrap().version()
+        );
+        assert_eq!(
+            ProtocolVersion::Legacy,
+            current
+                .negotiate(&Capabilities::legacy())
+                .unwrap()
+                .version()
+        );
+
+        let v1_only = Capabilities::new(vec![ProtocolVersion::V1], vec![], vec![]);
+        assert_eq!(
+            Err(PreambleError::NoCommonProtocolVersion),
+            v1_only.negotiate(&Capabilities::legacy())
+        );
+    }
+
+    #[test]
+    fn negotiation_only_keeps_commonly_supported_packets() {
+        let remote = Capabilities::new(
+            vec![ProtocolVersion::V1],
+            vec![PacketSize::AckPacket, PacketSize::RegularPacket],
+            vec![PacketMode::Mix],
+        );
+        let protocol = Capabilities::current().negotiate(&remote).unwrap();
+        assert!(protocol.supports_packet_size(PacketSize::RegularPacket));
+        assert!(protocol.supports_packet_size(PacketSize::AckPacket));
+        assert!(!protocol.supports_packet_size(PacketSize::ExtendedPacket));
+        assert!(protocol.supports_packet_mode(PacketMode::Mix));
+        assert!(!protocol.supports_packet_mode(PacketMode::Vpn));
+    }
+}
@@ -2,4 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod codec;
+pub mod hello;
 pub mod packet;
+pub mod preamble;
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::codec::SphinxCodecError;
+use crate::hello::ProtocolVersion;
 use bytes::{BufMut, BytesMut};
 use nymsphinx_params::packet_sizes::PacketSize;
 use nymsphinx_params::PacketMode;
@@ -23,6 +24,7 @@ impl FramedSphinxPacket {
         let packet_size = PacketSize::get_type(packet.len()).unwrap();
         FramedSphinxPacket {
             header: Header {
+                protocol_version: Default::default(),
                 packet_size,
                 packet_mode,
             },
@@ -30,6 +32,10 @@ impl FramedSphinxPacket {
         }
     }
 
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.header.protocol_version
+    }
+
     pub fn packet_size(&self) -> PacketSize {
         self.header.packet_size
     }
@@ -48,6 +54,10 @@ impl FramedSphinxPacket {
 // but would that really be worth it?
 #[derive(Debug, Default, PartialEq, Copy, Clone)]
 pub struct Header {
+    /// Version of the framing protocol negotiated for the connection. It is not encoded
+    /// in the legacy headers.
+    pub(crate) protocol_version: ProtocolVersion,
+
     /// Represents type and consequently size of the included SphinxPacket.
     pub(crate) packet_size: PacketSize,
 
@@ -64,27 +74,53 @@ pub struct Header {
 }
 
 impl Header {
-    pub(crate) const SIZE: usize = 2;
+    /// Size of the encoded header for the specified protocol version.
+    pub(crate) fn size(protocol_version: ProtocolVersion) -> usize {
+        Self::packet_size_offset(protocol_version) + 2
+    }
+
+    /// Position of the `packet_size` byte within the encoded header.
+    pub(crate) fn packet_size_offset(protocol_version: ProtocolVersion) -> usize {
+        match protocol_version {
+            ProtocolVersion::Legacy => 0,
+            ProtocolVersion::V1 => 1,
+        }
+    }
 
     pub(crate) fn encode(&self, dst: &mut BytesMut) {
-        // we reserve one byte for `packet_size` and the other for `mode`
-        dst.reserve(Self::SIZE);
-        dst.put_u8(self.packet_size as u8);
+        // we reserve one byte for the `version` (unless it's a legacy header),
+        // one for `packet_size` and the other for `mode`
+        dst.reserve(Self::size(self.protocol_version));
+        if self.protocol_version != ProtocolVersion::Legacy {
+            dst.put_u8(self.protocol_version as u8);
+        }
+        dst.put_u8(self.packet_size.id());
         dst.put_u8(self.packet_mode as u8);
         // reserve bytes for the actual packet
         dst.reserve(self.packet_size.size());
     }
 
-    pub(crate) fn decode(src: &mut BytesMut) -> Result<Option<Self>, SphinxCodecError> {
-        if src.len() < Self::SIZE {
+    /// Attempts to decode the header using the protocol version negotiated for the connection.
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+        protocol_version: ProtocolVersion,
+    ) -> Result<Option<Self>, SphinxCodecError> {
+        let size = Self::size(protocol_version);
+        if src.len() < size {
             // can't do anything if we don't have enough bytes - but reserve enough for the next call
-            src.reserve(Self::SIZE);
+            src.reserve(size);
             return Ok(None);
         }
 
+        let offset = Self::packet_size_offset(protocol_version);
+        if offset > 0 && src[0] != protocol_version as u8 {
+            return Err(SphinxCodecError::UnsupportedProtocolVersion(src[0]));
+        }
+
         Ok(Some(Header {
-            packet_size: PacketSize::try_from(src[0])?,
-            packet_mode: PacketMode::try_from(src[1])?,
+            protocol_version,
+            packet_size: PacketSize::try_from(src[offset])?,
+            packet_mode: PacketMode::try_from(src[offset + 1])?,
         }))
     }
 }
@@ -95,11 +131,39 @@ mod header_encoding {
 
     #[test]
     fn header_can_be_decoded_from_a_valid_encoded_instance() {
-        let header = Header::default();
-        let mut bytes = BytesMut::new();
-        header.encode(&mut bytes);
-        let decoded = Header::decode(&mut bytes).unwrap().unwrap();
-        assert_eq!(decoded, header);
+        for protocol_version in &[ProtocolVersion::Legacy, ProtocolVersion::V1] {
+            let header = Header {
+                protocol_version: *protocol_version,
+                ..Default::default()
+            };
+            let mut bytes = BytesMut::new();
+            header.encode(&mut bytes);
+            assert_eq!(bytes.len(), Header::size(*protocol_version));
+            let decoded = Header::decode(&mut bytes, *protocol_version)
+                .unwrap()
+                .unwrap();
+            assert_eq!(decoded, header);
+        }
+    }
+
+    #[test]
+    fn decoding_will_fail_for_unexpected_protocol_version() {
+        let unknown_version: u8 = 255;
+        // make sure this is still 'unknown' for if we make changes in the future
+        assert!(ProtocolVersion::try_from(unknown_version).is_err());
+
+        let mut bytes = BytesMut::from(
+            [
+                unknown_version,
+                PacketSize::default().id(),
+                PacketMode::default() as u8,
+            ]
+            .as_ref(),
+        );
+        assert!(matches!(
+            Header::decode(&mut bytes, ProtocolVersion::V1),
+            Err(SphinxCodecError::UnsupportedProtocolVersion(255))
+        ))
     }
 
     #[test]
@@ -108,8 +172,15 @@ mod header_encoding {
         // make sure this is still 'unknown' for if we make changes in the future
         assert!(PacketSize::try_from(unknown_packet_size).is_err());
 
-        let mut bytes = BytesMut::from([unknown_packet_size, PacketMode::default() as u8].as_ref());
-        assert!(Header::decode(&mut bytes).is_err())
+        let mut bytes = BytesMut::from(
+            [
+                ProtocolVersion::V1 as u8,
+                unknown_packet_size,
+                PacketMode::default() as u8,
+            ]
+            .as_ref(),
+        );
+        assert!(Header::decode(&mut bytes, ProtocolVersion::V1).is_err())
     }
 
     #[test]
@@ -118,21 +189,28 @@ mod header_encoding {
         // make sure this is still 'unknown' for if we make changes in the future
         assert!(PacketMode::try_from(unknown_packet_mode).is_err());
 
-        let mut bytes = BytesMut::from([PacketSize::default() as u8, unknown_packet_mode].as_ref());
-        assert!(Header::decode(&mut bytes).is_err())
+        let mut bytes = BytesMut::from(
+            [
+                ProtocolVersion::V1 as u8,
+                PacketSize::default().id(),
+                unknown_packet_mode,
+            ]
+            .as_ref(),
+        );
+        assert!(Header::decode(&mut bytes, ProtocolVersion::V1).is_err())
     }
 
     #[test]
     fn decode_will_allocate_enough_bytes_for_next_call() {
         let mut empty_bytes = BytesMut::new();
-        let decode_attempt_1 = Header::decode(&mut empty_bytes).unwrap();
+        let decode_attempt_1 = Header::decode(&mut empty_bytes, ProtocolVersion::V1).unwrap();
         assert!(decode_attempt_1.is_none());
-        assert!(empty_bytes.capacity() > Header::SIZE);
+        assert!(empty_bytes.capacity() > Header::size(ProtocolVersion::V1));
 
         let mut empty_bytes = BytesMut::with_capacity(1);
-        let decode_attempt_2 = Header::decode(&mut empty_bytes).unwrap();
+        let decode_attempt_2 = Header::decode(&mut empty_bytes, ProtocolVersion::V1).unwrap();
         assert!(decode_attempt_2.is_none());
-        assert!(empty_bytes.capacity() > Header::SIZE);
+        assert!(empty_bytes.capacity() > Header::size(ProtocolVersion::V1));
     }
 
     #[test]
@@ -144,6 +222,7 @@ mod header_encoding {
         ];
         for packet_size in packet_sizes {
             let header = Header {
+                protocol_version: Default::default(),
                 packet_size,
                 packet_mode: Default::default(),
             };
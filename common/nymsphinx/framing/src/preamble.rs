@@ -0,0 +1,396 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Preamble sent by the initiator of a node-to-node connection before any framed
+//! sphinx packets, used to negotiate the transport used for the rest of the connection.
+//! Since version 2, the preamble of both sides is immediately followed by their `Capabilities`,
+//! used to agree on the framing protocol of the connection. The preambles are exchanged
+//! regardless of whether the connection ends up being encrypted.
+//!
+//! The first byte of the preamble is never a valid `PacketSize` byte, so legacy nodes that do
+//! not understand it will simply fail to decode the first "packet" and close the connection,
+//! while upgraded nodes can tell apart legacy peers that start sending packets straight away.
+
+use crate::hello::{Capabilities, Protocol};
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Marker starting every preamble.
+pub const PREAMBLE_MAGIC: u8 = 0xF0;
+
+/// The newest version of the preamble this node understands.
+pub const CURRENT_PREAMBLE_VERSION: u8 = 2;
+
+/// The oldest version of the preamble this node understands. It is not followed by the capabilities.
+pub const MINIMUM_PREAMBLE_VERSION: u8 = 1;
+
+/// The first version of the preamble that is followed by the capabilities.
+const CAPABILITIES_PREAMBLE_VERSION: u8 = 2;
+
+#[derive(Debug, PartialEq)]
+pub enum PreambleError {
+    InvalidMagic(u8),
+    UnsupportedVersion(u8),
+    InvalidTransportMode(u8),
+    MalformedCapabilities,
+    NoCommonProtocolVersion,
+    TooShort,
+}
+
+impl Display for PreambleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PreambleError::InvalidMagic(byte) => write!(f, "invalid preamble magic byte {}", byte),
+            PreambleError::UnsupportedVersion(version) => {
+                write!(f, "unsupported preamble version {}", version)
+            }
+            PreambleError::InvalidTransportMode(mode) => {
+                write!(f, "invalid transport mode {}", mode)
+            }
+            PreambleError::MalformedCapabilities => write!(f, "malformed capabilities"),
+            PreambleError::NoCommonProtocolVersion => {
+                write!(f, "no framing protocol version is supported by both sides")
+            }
+            PreambleError::TooShort => write!(f, "preamble is too short"),
+        }
+    }
+}
+
+impl std::error::Error for PreambleError {}
+
+#[derive(Debug)]
+pub enum HelloError {
+    /// The remote did not understand our preamble and closed the connection.
+    LegacyPeer,
+
+    PreambleError(PreambleError),
+    IoError(io::Error),
+}
+
+impl From<PreambleError> for HelloError {
+    fn from(err: PreambleError) -> Self {
+        HelloError::PreambleError(err)
+    }
+}
+
+impl From<io::Error> for HelloError {
+    fn from(err: io::Error) -> Self {
+        HelloError::IoError(err)
+    }
+}
+
+impl Display for HelloError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HelloError::LegacyPeer => write!(f, "remote does not support the preamble"),
+            HelloError::PreambleError(err) => write!(f, "invalid preamble - {}", err),
+            HelloError::IoError(err) => write!(f, "io error - {}", err),
+        }
+    }
+}
+
+impl std::error::Error for HelloError {}
+
+/// Transport requested by the initiator or chosen by the responder of the connection.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransportMode {
+    /// Framed sphinx packets are sent directly over the connection.
+    Plaintext = 0,
+
+    /// Noise handshake is performed and framed sphinx packets are sent as noise transport messages.
+    Noise = 1,
+}
+
+impl TryFrom<u8> for TransportMode {
+    type Error = PreambleError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            _ if value == (TransportMode::Plaintext as u8) => Ok(Self::Plaintext),
+            _ if value == (TransportMode::Noise as u8) => Ok(Self::Noise),
+            _ => Err(PreambleError::InvalidTransportMode(value)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Preamble {
+    pub version: u8,
+    pub transport: TransportMode,
+}
+
+impl Preamble {
+    pub const SIZE: usize = 3;
+
+    pub fn new(transport: TransportMode) -> Self {
+        Preamble {
+            version: CURRENT_PREAMBLE_VERSION,
+            transport,
+        }
+    }
+
+    /// Creates a preamble responding to the one received from the initiator of the connection,
+    /// making sure it uses a version the initiator understands.
+    pub fn response_to(received: Preamble, transport: TransportMode) -> Self {
+        Preamble {
+            version: received.version.min(CURRENT_PREAMBLE_VERSION),
+            transport,
+        }
+    }
+
+    /// Checks whether this preamble is followed by the capabilities of its sender.
+    pub fn has_capabilities(&self) -> bool {
+        self.version >= CAPABILITIES_PREAMBLE_VERSION
+    }
+
+    /// Checks whether the provided first byte of a stream indicates it starts with a preamble
+    /// rather than with a framed sphinx packet.
+    pub fn is_preamble_start(first_byte: u8) -> bool {
+        first_byte == PREAMBLE_MAGIC
+    }
+
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        [PREAMBLE_MAGIC, self.version, self.transport as u8]
+    }
+
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, PreambleError> {
+        if bytes.len() < Self::SIZE {
+            return Err(PreambleError::TooShort);
+        }
+        if bytes[0] != PREAMBLE_MAGIC {
+            return Err(PreambleError::InvalidMagic(bytes[0]));
+        }
+        if bytes[1] < MINIMUM_PREAMBLE_VERSION || bytes[1] > CURRENT_PREAMBLE_VERSION {
+            return Err(PreambleError::UnsupportedVersion(bytes[1]));
+        }
+
+        Ok(Preamble {
+            version: bytes[1],
+            transport: TransportMode::try_from(bytes[2])?,
+        })
+    }
+}
+
+/// Outcome of exchanging the preambles at the beginning of a node-to-node connection.
+#[derive(Debug)]
+pub struct Hello {
+    /// Framing protocol agreed on with the remote.
+    pub protocol: Protocol,
+
+    /// Transport to be used for the rest of the connection.
+    pub transport: TransportMode,
+}
+
+/// Sends our preamble alongside our capabilities to the remote we have just connected to and
+/// agrees on the framing protocol and the transport based on its response.
+///
+/// If the remote turns out to be a legacy node, `HelloError::LegacyPeer` is returned, in which
+/// case the connection is no longer usable and should be re-established with the legacy framing.
+pub async fn outbound_hello(
+    stream: &mut TcpStream,
+    capabilities: &Capabilities,
+) -> Result<Hello, HelloError> {
+    let requested_transport = capabilities.preferred_transport();
+    let mut request = Preamble::new(requested_transport).to_bytes().to_vec();
+    request.extend(capabilities.to_bytes());
+    stream.write_all(&request).await?;
+
+    let mut response = [0u8; Preamble::SIZE];
+    if let Err(err) = stream.read_exact(&mut response).await {
+        // legacy nodes fail to decode our preamble as a packet header and close the connection
+        return match err.kind() {
+            io::ErrorKind::UnexpectedEof | io::ErrorKind::ConnectionReset => {
+                Err(HelloError::LegacyPeer)
+            }
+            _ => Err(err.into()),
+        };
+    }
+
+    let response = Preamble::try_from_bytes(&response)?;
+    let protocol = if response.has_capabilities() {
+        let remote_capabilities = read_capabilities(stream).await?;
+        capabilities.negotiate(&remote_capabilities)?
+    } else {
+        capabilities.negotiate(&Capabilities::legacy())?
+    };
+
+    // the remote can only ever fall back to plaintext rather than choose a transport
+    // we haven't asked for
+    if response.transport != requested_transport && response.transport != TransportMode::Plaintext {
+        return Err(PreambleError::InvalidTransportMode(response.transport as u8).into());
+    }
+
+    Ok(Hello {
+        protocol,
+        transport: response.transport,
+    })
+}
+
+/// Receives the preamble of the remote that has connected to us, if it sent any, and responds
+/// with our own alongside our capabilities.
+///
+/// Legacy nodes start sending framed packets straight away, in which case the legacy framing
+/// over the plaintext transport is used.
+pub async fn inbound_hello(
+    stream: &mut TcpStream,
+    capabilities: &Capabilities,
+) -> Result<Hello, HelloError> {
+    let mut first_byte = [0u8; 1];
+    let peeked = stream.peek(&mut first_byte).await?;
+    if peeked == 0 || !Preamble::is_preamble_start(first_byte[0]) {
+        return Ok(Hello {
+            protocol: capabilities.negotiate(&Capabilities::legacy())?,
+            transport: TransportMode::Plaintext,
+        });
+    }
+
+    let mut preamble = [0u8; Preamble::SIZE];
+    stream.read_exact(&mut preamble).await?;
+    let preamble = Preamble::try_from_bytes(&preamble)?;
+
+    // if we fail to agree on the protocol, we still respond with our capabilities,
+    // so that the remote could find out why the connection is getting closed
+    let protocol = if preamble.has_capabilities() {
+        let remote_capabilities = read_capabilities(stream).await?;
+        capabilities.negotiate(&remote_capabilities)
+    } else {
+        capabilities.negotiate(&Capabilities::legacy())
+    };
+
+    let transport = if preamble.transport == TransportMode::Noise
+        && capabilities.supports_transport(TransportMode::Noise)
+    {
+        TransportMode::Noise
+    } else {
+        TransportMode::Plaintext
+    };
+    let response = Preamble::response_to(preamble, transport);
+    let mut response_bytes = response.to_bytes().to_vec();
+    if response.has_capabilities() {
+        response_bytes.extend(capabilities.to_bytes());
+    }
+    stream.write_all(&response_bytes).await?;
+
+    Ok(Hello {
+        protocol: protocol?,
+        transport,
+    })
+}
+
+async fn read_capabilities(stream: &mut TcpStream) -> Result<Capabilities, HelloError> {
+    let mut len = [0u8; 1];
+    stream.read_exact(&mut len).await?;
+    let mut capabilities = vec![0u8; len[0] as usize];
+    stream.read_exact(&mut capabilities).await?;
+    Ok(Capabilities::try_from_bytes(&capabilities)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hello::ProtocolVersion;
+    use nymsphinx_params::PacketSize;
+    use tokio::net::TcpListener;
+
+    async fn exchange_hellos(
+        initiator: Capabilities,
+        responder: Capabilities,
+    ) -> (Result<Hello, HelloError>, Result<Hello, HelloError>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let responder_fut = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            inbound_hello(&mut socket, &responder).await
+        });
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let outbound = outbound_hello(&mut stream, &initiator).await;
+        (outbound, responder_fut.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn protocol_is_negotiated_without_noise() {
+        let plaintext_only =
+            Capabilities::current().with_transports(vec![TransportMode::Plaintext]);
+        let (outbound, inbound) =
+            exchange_hellos(plaintext_only.clone(), Capabilities::current()).await;
+        let (outbound, inbound) = (outbound.unwrap(), inbound.unwrap());
+        assert_eq!(TransportMode::Plaintext, outbound.transport);
+        assert_eq!(TransportMode::Plaintext, inbound.transport);
+        assert_eq!(ProtocolVersion::V1, outbound.protocol.version());
+        assert_eq!(outbound.protocol, inbound.protocol);
+
+        let (outbound, inbound) = exchange_hellos(Capabilities::current(), plaintext_only).await;
+        assert_eq!(TransportMode::Plaintext, outbound.unwrap().transport);
+        assert_eq!(TransportMode::Plaintext, inbound.unwrap().transport);
+    }
+
+    #[tokio::test]
+    async fn noise_is_chosen_if_supported_by_both_sides() {
+        let (outbound, inbound) =
+            exchange_hellos(Capabilities::current(), Capabilities::current()).await;
+        assert_eq!(TransportMode::Noise, outbound.unwrap().transport);
+        assert_eq!(TransportMode::Noise, inbound.unwrap().transport);
+    }
+
+    #[test]
+    fn preamble_magic_is_not_a_valid_packet_size() {
+        assert!(PacketSize::try_from(PREAMBLE_MAGIC).is_err());
+        assert!(PacketSize::new(PREAMBLE_MAGIC, 1024).is_err())
+    }
+
+    #[test]
+    fn preamble_can_be_recovered_from_bytes() {
+        for transport in &[TransportMode::Plaintext, TransportMode::Noise] {
+            let preamble = Preamble::new(*transport);
+            assert_eq!(
+                preamble,
+                Preamble::try_from_bytes(&preamble.to_bytes()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn responses_use_version_understood_by_initiator() {
+        let legacy = Preamble {
+            version: MINIMUM_PREAMBLE_VERSION,
+            transport: TransportMode::Noise,
+        };
+        let response = Preamble::response_to(legacy, TransportMode::Plaintext);
+        assert_eq!(MINIMUM_PREAMBLE_VERSION, response.version);
+        assert!(!response.has_capabilities());
+
+        let response =
+            Preamble::response_to(Preamble::new(TransportMode::Noise), TransportMode::Noise);
+        assert_eq!(CURRENT_PREAMBLE_VERSION, response.version);
+        assert!(response.has_capabilities());
+    }
+
+    #[test]
+    fn invalid_preambles_are_rejected() {
+        assert_eq!(
+            Err(PreambleError::TooShort),
+            Preamble::try_from_bytes(&[PREAMBLE_MAGIC, CURRENT_PREAMBLE_VERSION])
+        );
+        assert_eq!(
+            Err(PreambleError::InvalidMagic(1)),
+            Preamble::try_from_bytes(&[1, CURRENT_PREAMBLE_VERSION, 0])
+        );
+        assert_eq!(
+            Err(PreambleError::UnsupportedVersion(42)),
+            Preamble::try_from_bytes(&[PREAMBLE_MAGIC, 42, 0])
+        );
+        assert_eq!(
+            Err(PreambleError::UnsupportedVersion(0)),
+            Preamble::try_from_bytes(&[PREAMBLE_MAGIC, 0, 0])
+        );
+        assert_eq!(
+            Err(PreambleError::InvalidTransportMode(42)),
+            Preamble::try_from_bytes(&[PREAMBLE_MAGIC, CURRENT_PREAMBLE_VERSION, 42])
+        );
+    }
+}
@@ -15,6 +15,9 @@ pub mod packet_sizes;
 // I will change this to [`usize`]
 pub const DEFAULT_NUM_MIX_HOPS: u8 = 3;
 
+/// Number of packets sent in the `Vpn` mode after which a new initial secret and route are chosen.
+pub const DEFAULT_VPN_KEY_REUSE_LIMIT: usize = 1000;
+
 // TODO: not entirely sure how to feel about those being defined here, ideally it'd be where [`Fragment`]
 // is defined, but that'd introduce circular dependencies as the acknowledgements crate also needs
 // access to that
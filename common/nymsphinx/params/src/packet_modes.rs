@@ -13,8 +13,14 @@ pub enum PacketMode {
     /// value at each hop.
     Mix = 0,
 
-    /// Represents a VPN packet that should not be delayed and ideally cached pre-computed keys
-    /// should be used for unwrapping data. Note that it does not offer the same level of anonymity.
+    /// Represents a low-latency packet that should not be delayed at any hop. All packets sent
+    /// within the same session are created with the same initial secret and through the same route,
+    /// so that every hop sees the same shared secret and could reuse the keys it has derived
+    /// for the first packet, skipping the expensive sphinx key derivation for the remaining ones.
+    ///
+    /// Note that it offers considerably weaker anonymity than the `Mix` mode: without the delays
+    /// the packets can be correlated by timing analysis, and since all packets within a session
+    /// share the same keys, every hop can trivially link them together.
     Vpn = 1,
 }
 
@@ -23,7 +29,7 @@ impl PacketMode {
         self == PacketMode::Mix
     }
 
-    pub fn is_old_vpn(self) -> bool {
+    pub fn is_vpn(self) -> bool {
         self == PacketMode::Vpn
     }
 }
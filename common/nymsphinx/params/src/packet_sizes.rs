@@ -1,13 +1,26 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+//! Sizes of sphinx packets, identified on the wire by single byte ids.
+//!
+//! All the sizes known to this process are kept in a registry, which initially contains only
+//! the built-in sizes. Additional sizes can be registered (for example from the configuration)
+//! without any code changes, as long as their ids do not clash with the already registered ones.
+
 use crate::FRAG_ID_LEN;
 use nymsphinx_types::header::HEADER_SIZE;
 use nymsphinx_types::PAYLOAD_OVERHEAD_SIZE;
+use once_cell::sync::Lazy;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::sync::RwLock;
 
 // it's up to the smart people to figure those values out : )
+const SMALL_PACKET_SIZE: usize = HEADER_SIZE + PAYLOAD_OVERHEAD_SIZE + 1024;
 const REGULAR_PACKET_SIZE: usize = HEADER_SIZE + PAYLOAD_OVERHEAD_SIZE + 2 * 1024;
+const LARGE_PACKET_SIZE: usize = HEADER_SIZE + PAYLOAD_OVERHEAD_SIZE + 8 * 1024;
 // TODO: even though we have 16B IV, is having just 5B (FRAG_ID_LEN) of the ID possibly insecure?
 
 // TODO: I'm not entirely sure if we can easily extract `<AckEncryptionAlgorithm as NewStreamCipher>::NonceSize`
@@ -17,63 +30,215 @@ const ACK_IV_SIZE: usize = 16;
 const ACK_PACKET_SIZE: usize = HEADER_SIZE + PAYLOAD_OVERHEAD_SIZE + ACK_IV_SIZE + FRAG_ID_LEN;
 const EXTENDED_PACKET_SIZE: usize = HEADER_SIZE + PAYLOAD_OVERHEAD_SIZE + 32 * 1024;
 
-#[derive(Debug)]
-pub struct InvalidPacketSize;
+/// Maximum plaintext size of any registered packet.
+pub const MAX_PLAINTEXT_SIZE: usize = 1024 * 1024;
+
+/// Ids that can't be used by any packet size as they have a special meaning on the wire,
+/// i.e. an unset value and the start of the connection preamble (`PREAMBLE_MAGIC`).
+const RESERVED_IDS: [u8; 2] = [0, 0xF0];
+
+/// Sizes known to the process, ordered from the smallest one.
+static REGISTRY: Lazy<RwLock<Vec<PacketSize>>> =
+    Lazy::new(|| RwLock::new(PacketSize::BUILT_IN.to_vec()));
+
+#[derive(Debug, PartialEq)]
+pub enum InvalidPacketSize {
+    /// There is no registered packet size with the specified id or size.
+    Unknown,
+
+    /// The id can't be used for any packet size.
+    ReservedId(u8),
+
+    /// The plaintext size is either zero or larger than `MAX_PLAINTEXT_SIZE`.
+    InvalidPlaintextSize(usize),
+
+    /// A different packet size has already been registered with the same id or size.
+    Conflict(PacketSize),
+}
+
+impl Display for InvalidPacketSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidPacketSize::Unknown => write!(f, "unknown packet size"),
+            InvalidPacketSize::ReservedId(id) => write!(f, "packet size id {} is reserved", id),
+            InvalidPacketSize::InvalidPlaintextSize(size) => write!(
+                f,
+                "plaintext size {} is not within the allowed range of 1 - {} bytes",
+                size, MAX_PLAINTEXT_SIZE
+            ),
+            InvalidPacketSize::Conflict(existing) => write!(
+                f,
+                "conflicts with the already registered packet size {} ({} bytes)",
+                existing.id(),
+                existing.size()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvalidPacketSize {}
 
-#[repr(u8)]
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum PacketSize {
+/// Size of a sphinx packet alongside the id used to announce it on the wire.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PacketSize {
+    id: u8,
+
+    /// Size of the entire sphinx packet, i.e. including its header and the payload overhead.
+    size: usize,
+}
+
+#[allow(non_upper_case_globals)]
+impl PacketSize {
     // for example instant messaging use case
-    RegularPacket = 1,
+    pub const RegularPacket: PacketSize = PacketSize {
+        id: 1,
+        size: REGULAR_PACKET_SIZE,
+    };
 
     // for sending SURB-ACKs
-    AckPacket = 2,
+    pub const AckPacket: PacketSize = PacketSize {
+        id: 2,
+        size: ACK_PACKET_SIZE,
+    };
 
     // for example for streaming fast and furious in uncompressed 10bit 4K HDR quality
-    ExtendedPacket = 3,
+    pub const ExtendedPacket: PacketSize = PacketSize {
+        id: 3,
+        size: EXTENDED_PACKET_SIZE,
+    };
+
+    // for example for short chat messages or interactive sessions
+    pub const SmallPacket: PacketSize = PacketSize {
+        id: 4,
+        size: SMALL_PACKET_SIZE,
+    };
+
+    // for example for transferring files without paying for the full extended packets
+    pub const LargePacket: PacketSize = PacketSize {
+        id: 5,
+        size: LARGE_PACKET_SIZE,
+    };
+
+    /// Sizes known to every node, ordered from the smallest one.
+    const BUILT_IN: [PacketSize; 5] = [
+        PacketSize::AckPacket,
+        PacketSize::SmallPacket,
+        PacketSize::RegularPacket,
+        PacketSize::LargePacket,
+        PacketSize::ExtendedPacket,
+    ];
 }
 
-impl TryFrom<u8> for PacketSize {
-    type Error = InvalidPacketSize;
+impl PacketSize {
+    /// Defines a packet size carrying `plaintext_size` bytes of plaintext. Note that it still has
+    /// to be registered before it could be recognised on the wire.
+    pub fn new(id: u8, plaintext_size: usize) -> Result<Self, InvalidPacketSize> {
+        if RESERVED_IDS.contains(&id) {
+            return Err(InvalidPacketSize::ReservedId(id));
+        }
+        if plaintext_size == 0 || plaintext_size > MAX_PLAINTEXT_SIZE {
+            return Err(InvalidPacketSize::InvalidPlaintextSize(plaintext_size));
+        }
+        Ok(PacketSize {
+            id,
+            size: HEADER_SIZE + PAYLOAD_OVERHEAD_SIZE + plaintext_size,
+        })
+    }
 
-    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
-        match value {
-            _ if value == (PacketSize::RegularPacket as u8) => Ok(Self::RegularPacket),
-            _ if value == (PacketSize::AckPacket as u8) => Ok(Self::AckPacket),
-            _ if value == (PacketSize::ExtendedPacket as u8) => Ok(Self::ExtendedPacket),
-            _ => Err(InvalidPacketSize),
+    /// Adds the packet size to the registry, so that it could be recognised on the wire.
+    /// Registering an already registered size is a no-op.
+    pub fn register(packet_size: PacketSize) -> Result<(), InvalidPacketSize> {
+        let mut registry = REGISTRY.write().unwrap();
+        if let Some(existing) = registry.iter().find(|registered| {
+            registered.id == packet_size.id || registered.size == packet_size.size
+        }) {
+            return if *existing == packet_size {
+                Ok(())
+            } else {
+                Err(InvalidPacketSize::Conflict(*existing))
+            };
         }
+
+        let position = registry
+            .iter()
+            .position(|registered| registered.size > packet_size.size)
+            .unwrap_or_else(|| registry.len());
+        registry.insert(position, packet_size);
+        Ok(())
+    }
+
+    /// Registers all of the provided packet sizes, for example the ones specified in the config.
+    pub fn register_all<I>(packet_sizes: I) -> Result<(), InvalidPacketSize>
+    where
+        I: IntoIterator<Item = PacketSize>,
+    {
+        packet_sizes.into_iter().try_for_each(PacketSize::register)
+    }
+
+    /// All registered packet sizes, starting from the smallest one.
+    pub fn all() -> impl Iterator<Item = PacketSize> {
+        REGISTRY.read().unwrap().clone().into_iter()
+    }
+
+    pub fn id(self) -> u8 {
+        self.id
     }
-}
 
-impl PacketSize {
     pub fn size(self) -> usize {
-        match self {
-            PacketSize::RegularPacket => REGULAR_PACKET_SIZE,
-            PacketSize::AckPacket => ACK_PACKET_SIZE,
-            PacketSize::ExtendedPacket => EXTENDED_PACKET_SIZE,
-        }
+        self.size
     }
 
     pub fn plaintext_size(self) -> usize {
-        self.size() - HEADER_SIZE - PAYLOAD_OVERHEAD_SIZE
+        self.size - HEADER_SIZE - PAYLOAD_OVERHEAD_SIZE
     }
 
     pub fn payload_size(self) -> usize {
-        self.size() - HEADER_SIZE
+        self.size - HEADER_SIZE
     }
 
     pub fn get_type(size: usize) -> std::result::Result<Self, InvalidPacketSize> {
-        if PacketSize::RegularPacket.size() == size {
-            Ok(PacketSize::RegularPacket)
-        } else if PacketSize::AckPacket.size() == size {
-            Ok(PacketSize::AckPacket)
-        } else if PacketSize::ExtendedPacket.size() == size {
-            Ok(PacketSize::ExtendedPacket)
-        } else {
-            Err(InvalidPacketSize)
+        PacketSize::all()
+            .find(|packet_size| packet_size.size() == size)
+            .ok_or(InvalidPacketSize::Unknown)
+    }
+
+    /// Whether the packet is used for carrying the actual messages, as opposed to SURB-ACKs.
+    pub fn is_message_packet(self) -> bool {
+        self != PacketSize::AckPacket
+    }
+
+    /// Whether the size is known to every node, as opposed to being registered at runtime.
+    pub fn is_built_in(self) -> bool {
+        self.built_in_name().is_some()
+    }
+
+    fn built_in_name(self) -> Option<&'static str> {
+        match self {
+            PacketSize::RegularPacket => Some("regular_packet"),
+            PacketSize::AckPacket => Some("ack_packet"),
+            PacketSize::ExtendedPacket => Some("extended_packet"),
+            PacketSize::SmallPacket => Some("small_packet"),
+            PacketSize::LargePacket => Some("large_packet"),
+            _ => None,
         }
     }
+
+    fn from_built_in_name(name: &str) -> Option<Self> {
+        PacketSize::BUILT_IN
+            .iter()
+            .copied()
+            .find(|packet_size| packet_size.built_in_name() == Some(name))
+    }
+}
+
+impl TryFrom<u8> for PacketSize {
+    type Error = InvalidPacketSize;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        PacketSize::all()
+            .find(|packet_size| packet_size.id == value)
+            .ok_or(InvalidPacketSize::Unknown)
+    }
 }
 
 impl Default for PacketSize {
@@ -81,3 +246,201 @@ impl Default for PacketSize {
         PacketSize::RegularPacket
     }
 }
+
+/// Serialized form of a packet size. The built-in sizes can also be referred to by their names.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum PacketSizeDefinition {
+    BuiltIn(String),
+    Custom { id: u8, plaintext_size: usize },
+}
+
+impl Serialize for PacketSize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // keep the built-in sizes readable by the nodes that don't know about the custom ones
+        match self.built_in_name() {
+            Some(name) => PacketSizeDefinition::BuiltIn(name.to_owned()),
+            None => PacketSizeDefinition::Custom {
+                id: self.id,
+                plaintext_size: self.plaintext_size(),
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PacketSize {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let packet_size = match PacketSizeDefinition::deserialize(deserializer)? {
+            PacketSizeDefinition::BuiltIn(name) => PacketSize::from_built_in_name(&name)
+                .ok_or_else(|| D::Error::custom(format!("unknown packet size '{}'", name)))?,
+            PacketSizeDefinition::Custom { id, plaintext_size } => {
+                PacketSize::new(id, plaintext_size).map_err(D::Error::custom)?
+            }
+        };
+
+        // custom sizes can't redefine the built-in ones
+        if let Some(built_in) = PacketSize::BUILT_IN.iter().find(|built_in| {
+            (built_in.id == packet_size.id || built_in.size == packet_size.size)
+                && **built_in != packet_size
+        }) {
+            return Err(D::Error::custom(InvalidPacketSize::Conflict(*built_in)));
+        }
+        Ok(packet_size)
+    }
+}
+
+/// Non-empty set of sizes of packets carrying the actual messages, ordered from the smallest one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessagePacketSizes {
+    smaller: Vec<PacketSize>,
+    largest: PacketSize,
+}
+
+impl MessagePacketSizes {
+    /// Creates the set out of the provided sizes, ignoring the ack packet size.
+    /// Returns `None` if no message packet size was provided.
+    pub fn new(mut packet_sizes: Vec<PacketSize>) -> Option<Self> {
+        packet_sizes.retain(|packet_size| packet_size.is_message_packet());
+        packet_sizes.sort_by_key(|packet_size| packet_size.size());
+        packet_sizes.dedup();
+
+        let largest = packet_sizes.pop()?;
+        Some(MessagePacketSizes {
+            smaller: packet_sizes,
+            largest,
+        })
+    }
+
+    /// All of the sizes, starting from the smallest one.
+    pub fn iter(&self) -> impl Iterator<Item = PacketSize> + '_ {
+        self.smaller
+            .iter()
+            .copied()
+            .chain(std::iter::once(self.largest))
+    }
+
+    pub fn largest(&self) -> PacketSize {
+        self.largest
+    }
+}
+
+impl Default for MessagePacketSizes {
+    fn default() -> Self {
+        MessagePacketSizes {
+            smaller: Vec::new(),
+            largest: PacketSize::default(),
+        }
+    }
+}
+
+impl Serialize for MessagePacketSizes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for MessagePacketSizes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let packet_sizes = Vec::<PacketSize>::deserialize(deserializer)?;
+        MessagePacketSizes::new(packet_sizes).ok_or_else(|| {
+            D::Error::custom(
+                "at least a single packet size other than the ack packet size must be specified",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_sizes_are_unique_and_ordered() {
+        let sizes = PacketSize::all().map(PacketSize::size).collect::<Vec<_>>();
+        assert!(sizes.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn all_registered_sizes_can_be_recovered() {
+        for packet_size in PacketSize::all() {
+            assert_eq!(packet_size, PacketSize::try_from(packet_size.id()).unwrap());
+            assert_eq!(
+                packet_size,
+                PacketSize::get_type(packet_size.size()).unwrap()
+            );
+        }
+        assert!(PacketSize::try_from(0).is_err());
+        assert!(PacketSize::get_type(42).is_err());
+    }
+
+    #[test]
+    fn custom_sizes_can_be_registered() {
+        let custom = PacketSize::new(200, 4 * 1024).unwrap();
+        assert!(PacketSize::try_from(200).is_err());
+
+        PacketSize::register(custom).unwrap();
+        assert_eq!(custom, PacketSize::try_from(200).unwrap());
+        assert_eq!(custom, PacketSize::get_type(custom.size()).unwrap());
+        // registering the same size again is fine
+        PacketSize::register(custom).unwrap();
+
+        let sizes = PacketSize::all().map(PacketSize::size).collect::<Vec<_>>();
+        assert!(sizes.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn conflicting_sizes_are_rejected() {
+        let same_id = PacketSize::new(PacketSize::RegularPacket.id(), 42).unwrap();
+        assert_eq!(
+            Err(InvalidPacketSize::Conflict(PacketSize::RegularPacket)),
+            PacketSize::register(same_id)
+        );
+
+        let same_size = PacketSize::new(201, PacketSize::RegularPacket.plaintext_size()).unwrap();
+        assert_eq!(
+            Err(InvalidPacketSize::Conflict(PacketSize::RegularPacket)),
+            PacketSize::register(same_size)
+        );
+
+        assert_eq!(
+            Err(InvalidPacketSize::ReservedId(0)),
+            PacketSize::new(0, 1024)
+        );
+        assert_eq!(
+            Err(InvalidPacketSize::InvalidPlaintextSize(0)),
+            PacketSize::new(202, 0)
+        );
+    }
+
+    #[test]
+    fn message_packet_sizes_are_never_empty() {
+        assert!(MessagePacketSizes::new(vec![]).is_none());
+        assert!(MessagePacketSizes::new(vec![PacketSize::AckPacket]).is_none());
+
+        let sizes = MessagePacketSizes::new(vec![
+            PacketSize::ExtendedPacket,
+            PacketSize::AckPacket,
+            PacketSize::SmallPacket,
+            PacketSize::SmallPacket,
+        ])
+        .unwrap();
+        assert_eq!(
+            vec![PacketSize::SmallPacket, PacketSize::ExtendedPacket],
+            sizes.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(PacketSize::ExtendedPacket, sizes.largest());
+    }
+}
@@ -2,10 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chunking;
+use crate::preparer::vpn_session::VpnSession;
 use crypto::asymmetric::encryption;
 use crypto::shared_key::new_ephemeral_shared_key;
 use crypto::symmetric::stream_cipher;
-use crypto::Digest;
 use nymsphinx_acknowledgements::surb_ack::SurbAck;
 use nymsphinx_acknowledgements::AckKey;
 use nymsphinx_addressing::clients::Recipient;
@@ -14,18 +14,20 @@ use nymsphinx_anonymous_replies::encryption_key::SurbEncryptionKey;
 use nymsphinx_anonymous_replies::reply_surb::ReplySurb;
 use nymsphinx_chunking::fragment::{Fragment, FragmentIdentifier};
 use nymsphinx_forwarding::packet::MixPacket;
-use nymsphinx_params::packet_sizes::PacketSize;
+use nymsphinx_params::packet_sizes::{MessagePacketSizes, PacketSize};
 use nymsphinx_params::{
-    PacketEncryptionAlgorithm, PacketHkdfAlgorithm, ReplySurbEncryptionAlgorithm,
-    ReplySurbKeyDigestAlgorithm, DEFAULT_NUM_MIX_HOPS,
+    PacketEncryptionAlgorithm, PacketHkdfAlgorithm, PacketMode, ReplySurbEncryptionAlgorithm,
+    DEFAULT_NUM_MIX_HOPS, DEFAULT_VPN_KEY_REUSE_LIMIT,
 };
 use nymsphinx_types::builder::SphinxPacketBuilder;
-use nymsphinx_types::{delays, Delay};
+use nymsphinx_types::{delays, Delay, EphemeralSecret, Node};
 use rand::{CryptoRng, Rng};
 use std::convert::TryFrom;
 use std::time::Duration;
 use topology::{NymTopology, NymTopologyError};
 
+mod vpn_session;
+
 /// Represents fully packed and prepared [`Fragment`] that can be sent through the mix network.
 pub struct PreparedFragment {
     /// Indicates the total expected round-trip time, i.e. delay from the sending of this message
@@ -58,8 +60,9 @@ pub struct MessagePreparer<R: CryptoRng + Rng> {
     /// Instance of a cryptographically secure random number generator.
     rng: R,
 
-    /// Size of the target [`SphinxPacket`] into which the underlying is going to get split.
-    packet_size: PacketSize,
+    /// Sizes of the [`SphinxPacket`]s that can be sent out, ordered from the smallest one.
+    /// Each message is put into the smallest one able to fit it.
+    packet_sizes: MessagePacketSizes,
 
     /// Address of this client which also represent an address to which all acknowledgements
     /// and surb-based are going to be sent.
@@ -74,6 +77,15 @@ pub struct MessagePreparer<R: CryptoRng + Rng> {
     /// Number of mix hops each packet ('real' message, ack, reply) is expected to take.
     /// Note that it does not include gateway hops.
     num_mix_hops: u8,
+
+    /// Mode in which the sphinx packets are sent out.
+    packet_mode: PacketMode,
+
+    /// Maximum number of packets sent in the `Vpn` mode with the same initial secret.
+    vpn_key_reuse_limit: usize,
+
+    /// Currently used session for the packets sent in the `Vpn` mode.
+    vpn_session: Option<VpnSession>,
 }
 
 impl<R> MessagePreparer<R>
@@ -88,11 +100,14 @@ where
     ) -> Self {
         MessagePreparer {
             rng,
-            packet_size: Default::default(),
+            packet_sizes: Default::default(),
             sender_address,
             average_packet_delay,
             average_ack_delay,
             num_mix_hops: DEFAULT_NUM_MIX_HOPS,
+            packet_mode: Default::default(),
+            vpn_key_reuse_limit: DEFAULT_VPN_KEY_REUSE_LIMIT,
+            vpn_session: None,
         }
     }
 
@@ -102,9 +117,23 @@ where
         self
     }
 
-    /// Allows setting non-default size of the sphinx packets sent out.
-    pub fn with_packet_size(mut self, packet_size: PacketSize) -> Self {
-        self.packet_size = packet_size;
+    /// Allows setting non-default sizes of the sphinx packets sent out.
+    pub fn with_packet_sizes(mut self, packet_sizes: MessagePacketSizes) -> Self {
+        self.packet_sizes = packet_sizes;
+        self
+    }
+
+    /// Allows setting non-default mode of the sphinx packets sent out.
+    /// Note that the `Vpn` mode provides considerably weaker anonymity guarantees.
+    pub fn with_packet_mode(mut self, packet_mode: PacketMode) -> Self {
+        self.packet_mode = packet_mode;
+        self
+    }
+
+    /// Allows setting non-default number of packets sent in the `Vpn` mode before
+    /// their initial secret and route get rotated.
+    pub fn with_vpn_key_reuse_limit(mut self, vpn_key_reuse_limit: usize) -> Self {
+        self.vpn_key_reuse_limit = vpn_key_reuse_limit;
         self
     }
 
@@ -113,9 +142,51 @@ where
         self.sender_address = sender_address;
     }
 
+    /// Chooses the smallest of the available packet sizes satisfying the predicate.
+    fn smallest_packet_size_where<F>(&self, predicate: F) -> Option<PacketSize>
+    where
+        F: Fn(PacketSize) -> bool,
+    {
+        self.packet_sizes
+            .iter()
+            .find(|packet_size| predicate(*packet_size))
+    }
+
+    /// Chooses the smallest of the available packet sizes able to fit the entire message
+    /// in a single fragment. If there's none, the message is going to get split
+    /// into the largest available packets.
+    fn packet_size_for_message(&self, message_len: usize) -> PacketSize {
+        self.smallest_packet_size_where(|packet_size| {
+            let (fragments, _) = chunking::number_of_required_fragments(
+                message_len,
+                Self::available_plaintext_per_packet(packet_size),
+            );
+            fragments == 1
+        })
+        .unwrap_or_else(|| self.packet_sizes.largest())
+    }
+
+    /// Chooses the smallest of the available packet sizes able to carry the fragment.
+    fn packet_size_for_fragment(&self, fragment_len: usize) -> PacketSize {
+        self.smallest_packet_size_where(|packet_size| {
+            Self::available_plaintext_per_packet(packet_size) >= fragment_len
+        })
+        // fragments are always created using one of the available packet sizes,
+        // so one of them must be able to fit it
+        .unwrap_or_else(|| self.packet_sizes.largest())
+    }
+
+    /// Chooses the smallest of the available packet sizes able to fit the entire reply message.
+    /// Replies can't be split into multiple packets, so if there's none, the reply can't be sent.
+    fn packet_size_for_reply(&self, message_len: usize) -> Option<PacketSize> {
+        self.smallest_packet_size_where(|packet_size| {
+            message_len <= ReplySurb::max_msg_len(packet_size)
+        })
+    }
+
     /// Length of plaintext (from the sphinx point of view) data that is available per sphinx
-    /// packet.
-    fn available_plaintext_per_packet(&self) -> usize {
+    /// packet of the specified size.
+    fn available_plaintext_per_packet(packet_size: PacketSize) -> usize {
         // we need to put first hop's destination alongside the actual ack data
         // TODO: a possible optimization way down the line: currently we're always assuming that
         // the addresses will have `MAX_NODE_ADDRESS_UNPADDED_LEN`, i.e. be ipv6. In most cases
@@ -127,17 +198,17 @@ where
         let ack_overhead = MAX_NODE_ADDRESS_UNPADDED_LEN + PacketSize::AckPacket.size();
         let ephemeral_public_key_overhead = encryption::PUBLIC_KEY_SIZE;
 
-        self.packet_size.plaintext_size() - ack_overhead - ephemeral_public_key_overhead
+        packet_size.plaintext_size() - ack_overhead - ephemeral_public_key_overhead
     }
 
     /// Pads the message so that after it gets chunked, it will occupy exactly N sphinx packets.
     /// Produces new_message = message || 1 || 0000....
-    fn pad_message(&self, message: Vec<u8>) -> Vec<u8> {
+    fn pad_message(&self, message: Vec<u8>, packet_size: PacketSize) -> Vec<u8> {
         // 1 is added as there will always have to be at least a single byte of padding (1) added
         // to be able to later distinguish the actual padding from the underlying message
         let (_, space_left) = chunking::number_of_required_fragments(
             message.len() + 1,
-            self.available_plaintext_per_packet(),
+            Self::available_plaintext_per_packet(packet_size),
         );
 
         message
@@ -187,8 +258,8 @@ where
     }
 
     /// Splits the message into [`Fragment`] that are going to be put later put into sphinx packets.
-    fn split_message(&mut self, message: Vec<u8>) -> Vec<Fragment> {
-        let plaintext_per_packet = self.available_plaintext_per_packet();
+    fn split_message(&mut self, message: Vec<u8>, packet_size: PacketSize) -> Vec<Fragment> {
+        let plaintext_per_packet = Self::available_plaintext_per_packet(packet_size);
         chunking::split_into_sets(&mut self.rng, &message, plaintext_per_packet)
             .into_iter()
             .flat_map(|fragment_set| fragment_set.into_iter())
@@ -225,17 +296,9 @@ where
             .await?
             .prepare_for_sending();
 
-        // TODO:
-        // TODO:
-        // TODO:
-        // TODO:
-        // TODO: ASK @AP AND @DH WHETHER THOSE KEYS CAN/SHOULD ALSO BE REUSED IN VPN MODE!!
-        // TODO:
-        // TODO:
-        // TODO:
-        // TODO:
-
         // create keys for 'payload' encryption
+        // note: those are always fresh, even in the vpn mode, as they are only ever used by
+        // the recipient and hence reusing them would not save any work on the mix nodes
         let (ephemeral_keypair, shared_key) =
             new_ephemeral_shared_key::<PacketEncryptionAlgorithm, PacketHkdfAlgorithm, _>(
                 &mut self.rng,
@@ -244,6 +307,7 @@ where
 
         // serialize fragment and encrypt its content
         let mut chunk_data = fragment.into_bytes();
+        let packet_size = self.packet_size_for_fragment(chunk_data.len());
 
         let zero_iv = stream_cipher::zero_iv::<PacketEncryptionAlgorithm>();
         stream_cipher::encrypt_in_place::<PacketEncryptionAlgorithm>(
@@ -261,37 +325,81 @@ where
             .chain(chunk_data.into_iter())
             .collect();
 
-        // generate pseudorandom route for the packet
-        let route = topology.random_route_to_gateway(
-            &mut self.rng,
-            self.num_mix_hops,
-            packet_recipient.gateway(),
-        )?;
         let destination = packet_recipient.as_sphinx_destination();
+        let payload_size = packet_size.payload_size();
+
+        let (sphinx_packet, first_hop, delays) = if self.packet_mode.is_vpn() {
+            // in the vpn mode the packets are not delayed and share the route and the initial
+            // secret, so that the mix nodes could reuse the keys derived for the previous packets
+            let (initial_secret, route) =
+                self.next_vpn_session_packet(topology, packet_recipient)?;
+            let delays = vec![Delay::new_from_nanos(0); route.len()];
+
+            // create the actual sphinx packet here. With valid route and correct payload size,
+            // there's absolutely no reason for this call to fail.
+            let sphinx_packet = SphinxPacketBuilder::new()
+                .with_payload_size(payload_size)
+                .with_initial_secret(initial_secret)
+                .build_packet(packet_payload, route, &destination, &delays)
+                .unwrap();
+            (sphinx_packet, route.first().unwrap().address, delays)
+        } else {
+            // generate pseudorandom route for the packet
+            let route = topology.random_route_to_gateway(
+                &mut self.rng,
+                self.num_mix_hops,
+                packet_recipient.gateway(),
+            )?;
 
-        // including set of delays
-        let delays = delays::generate_from_average_duration(route.len(), self.average_packet_delay);
+            // including set of delays
+            let delays =
+                delays::generate_from_average_duration(route.len(), self.average_packet_delay);
 
-        // create the actual sphinx packet here. With valid route and correct payload size,
-        // there's absolutely no reason for this call to fail.
-        let sphinx_packet = SphinxPacketBuilder::new()
-            .with_payload_size(self.packet_size.payload_size())
-            .build_packet(packet_payload, &route, &destination, &delays)
-            .unwrap();
+            // create the actual sphinx packet here. With valid route and correct payload size,
+            // there's absolutely no reason for this call to fail.
+            let sphinx_packet = SphinxPacketBuilder::new()
+                .with_payload_size(payload_size)
+                .build_packet(packet_payload, &route, &destination, &delays)
+                .unwrap();
+            (sphinx_packet, route.first().unwrap().address, delays)
+        };
 
         // from the previously constructed route extract the first hop
-        let first_hop_address =
-            NymNodeRoutingAddress::try_from(route.first().unwrap().address).unwrap();
+        let first_hop_address = NymNodeRoutingAddress::try_from(first_hop).unwrap();
 
         Ok(PreparedFragment {
             // the round-trip delay is the sum of delays of all hops on the forward route as
             // well as the total delay of the ack packet.
             // note that the last hop of the packet is a gateway that does not do any delays
             total_delay: delays.iter().take(delays.len() - 1).sum::<Delay>() + ack_delay,
-            mix_packet: MixPacket::new(first_hop_address, sphinx_packet, Default::default()),
+            mix_packet: MixPacket::new(first_hop_address, sphinx_packet, self.packet_mode),
         })
     }
 
+    /// Returns the initial secret and the route of the current vpn session, starting a new one
+    /// if it has been used too many times already or the packet is meant for a different gateway.
+    fn next_vpn_session_packet(
+        &mut self,
+        topology: &NymTopology,
+        packet_recipient: &Recipient,
+    ) -> Result<(&EphemeralSecret, &[Node]), NymTopologyError> {
+        let gateway = packet_recipient.gateway();
+        let is_usable = self
+            .vpn_session
+            .as_ref()
+            .map(|session| session.is_usable(gateway, self.vpn_key_reuse_limit))
+            .unwrap_or_default();
+
+        if !is_usable {
+            let route =
+                topology.random_route_to_gateway(&mut self.rng, self.num_mix_hops, gateway)?;
+            self.vpn_session = Some(VpnSession::new(&mut self.rng, route, *gateway));
+        }
+
+        // the session was set above if it didn't exist
+        Ok(self.vpn_session.as_mut().unwrap().next_packet())
+    }
+
     /// Construct an acknowledgement SURB for the given [`FragmentIdentifier`]
     async fn generate_surb_ack(
         &mut self,
@@ -321,9 +429,11 @@ where
         let (message, reply_key) =
             self.optionally_attach_reply_surb(message, with_reply_surb, topology)?;
 
-        let message = self.pad_message(message);
+        // 1 is added to account for the padding
+        let packet_size = self.packet_size_for_message(message.len() + 1);
+        let message = self.pad_message(message, packet_size);
 
-        Ok((self.split_message(message), reply_key))
+        Ok((self.split_message(message, packet_size), reply_key))
     }
 
     // TODO: perhaps the return type could somehow be combined with [`PreparedFragment`] ?
@@ -339,15 +449,9 @@ where
         // and need 1 byte to indicate padding length (this is not the case for 'normal' messages
         // as there the padding is added for the whole message)
         // so before doing any processing, let's see if we have enough space for it all
-        let ack_overhead = MAX_NODE_ADDRESS_UNPADDED_LEN + PacketSize::AckPacket.size();
-        if message.len()
-            > self.packet_size.plaintext_size()
-                - ack_overhead
-                - ReplySurbKeyDigestAlgorithm::output_size()
-                - 1
-        {
-            return Err(PreparationError::TooLongReplyMessageError);
-        }
+        let packet_size = self
+            .packet_size_for_reply(message.len())
+            .ok_or(PreparationError::TooLongReplyMessageError)?;
 
         let reply_id = FragmentIdentifier::new_reply(&mut self.rng);
 
@@ -360,11 +464,7 @@ where
             .await?
             .prepare_for_sending();
 
-        let zero_pad_len = self.packet_size.plaintext_size()
-            - message.len()
-            - ack_overhead
-            - ReplySurbKeyDigestAlgorithm::output_size()
-            - 1;
+        let zero_pad_len = ReplySurb::max_msg_len(packet_size) - message.len();
 
         // create reply message that will reach the recipient:
         let mut reply_content: Vec<_> = message
@@ -400,7 +500,7 @@ where
         // this can only fail if packet payload has incorrect size, but if it does, it means
         // there's a bug in the above code
         let (packet, first_hop) = reply_surb
-            .apply_surb(&packet_payload, Some(self.packet_size))
+            .apply_surb(&packet_payload, Some(packet_size))
             .unwrap();
 
         Ok((
@@ -417,13 +517,278 @@ where
 
         MessagePreparer {
             rng,
-            packet_size: Default::default(),
+            packet_sizes: vec![Default::default()],
             sender_address: dummy_address,
             average_packet_delay: Default::default(),
             average_ack_delay: Default::default(),
             num_mix_hops: DEFAULT_NUM_MIX_HOPS,
+            packet_mode: Default::default(),
+            vpn_key_reuse_limit: DEFAULT_VPN_KEY_REUSE_LIMIT,
+            vpn_session: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::asymmetric::identity;
+    use mixnet_contract::Layer;
+    use nymsphinx_types::SharedSecret;
+    use std::collections::HashMap;
+    use topology::{gateway, mix};
+
+    type TestPreparer = MessagePreparer<rand::rngs::OsRng>;
+
+    const RECIPIENT: &str = "CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@FioFa8nMmPpQnYi7JyojoTuwGLeyNS8BF4ChPr29zUML";
+    const OTHER_GATEWAY_RECIPIENT: &str = "CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@3ebjp1Fb9hdcS1AR6AZihgeJiMHkB5jjJUsvqNnfQwU7";
+
+    fn mix_node(layer: Layer, host: &str, identity_key: &str, sphinx_key: &str) -> mix::Node {
+        mix::Node {
+            owner: "foomp".to_string(),
+            stake: 123,
+            delegation: 456,
+            host: host.parse().unwrap(),
+            mix_host: format!("{}:1789", host).parse().unwrap(),
+            identity_key: identity::PublicKey::from_base58_string(identity_key).unwrap(),
+            sphinx_key: encryption::PublicKey::from_base58_string(sphinx_key).unwrap(),
+            layer,
+            family: None,
+            version: "0.8.0-dev".to_string(),
+        }
+    }
+
+    fn gateway_node(host: &str, identity_key: &str, sphinx_key: &str) -> gateway::Node {
+        gateway::Node {
+            owner: "foomp".to_string(),
+            stake: 123,
+            delegation: 456,
+            location: "unknown".to_string(),
+            host: host.parse().unwrap(),
+            mix_host: format!("{}:1789", host).parse().unwrap(),
+            clients_port: 9000,
+            identity_key: identity::PublicKey::from_base58_string(identity_key).unwrap(),
+            sphinx_key: encryption::PublicKey::from_base58_string(sphinx_key).unwrap(),
+            version: "0.8.0-dev".to_string(),
         }
     }
+
+    fn topology_fixture() -> NymTopology {
+        let mut mixes = HashMap::new();
+        mixes.insert(
+            1,
+            vec![mix_node(
+                Layer::One,
+                "10.20.30.40",
+                "3ebjp1Fb9hdcS1AR6AZihgeJiMHkB5jjJUsvqNnfQwU7",
+                "B3GzG62aXAZNg14RoMCp3BhELNBrySLr2JqrwyfYFzRc",
+            )],
+        );
+        mixes.insert(
+            2,
+            vec![mix_node(
+                Layer::Two,
+                "11.21.31.41",
+                "D6YaMzLSY7mANtSQRKXsmMZpqgqiVkeiagKM4V4oFPFr",
+                "5Z1VqYwM2xeKxd8H7fJpGWasNiDFijYBAee7MErkZ5QT",
+            )],
+        );
+        mixes.insert(
+            3,
+            vec![mix_node(
+                Layer::Three,
+                "12.22.32.42",
+                "GkWDysw4AjESv1KiAiVn7JzzCMJeksxNSXVfr1PpX8wD",
+                "9EyjhCggr2QEA2nakR88YHmXgpy92DWxoe2draDRkYof",
+            )],
+        );
+
+        NymTopology::new(
+            mixes,
+            vec![
+                gateway_node(
+                    "1.2.3.4",
+                    "FioFa8nMmPpQnYi7JyojoTuwGLeyNS8BF4ChPr29zUML",
+                    "EB42xvMFMD5rUCstE2CDazgQQJ22zLv8SPm1Luxni44c",
+                ),
+                gateway_node(
+                    "5.6.7.8",
+                    "3ebjp1Fb9hdcS1AR6AZihgeJiMHkB5jjJUsvqNnfQwU7",
+                    "B3GzG62aXAZNg14RoMCp3BhELNBrySLr2JqrwyfYFzRc",
+                ),
+            ],
+        )
+    }
+
+    /// Returns the secret shared with the first hop by a packet created within the current
+    /// vpn session, i.e. the one the first hop would be using for its key cache.
+    fn next_session_secret(
+        preparer: &mut TestPreparer,
+        topology: &NymTopology,
+        recipient: &Recipient,
+    ) -> SharedSecret {
+        let (initial_secret, route) = preparer
+            .next_vpn_session_packet(topology, recipient)
+            .unwrap();
+        let delays = vec![Delay::new_from_nanos(0); route.len()];
+        SphinxPacketBuilder::new()
+            .with_initial_secret(initial_secret)
+            .build_packet(
+                b"foomp".to_vec(),
+                route,
+                &recipient.as_sphinx_destination(),
+                &delays,
+            )
+            .unwrap()
+            .shared_secret()
+    }
+
+    fn preparer_with_sizes() -> TestPreparer {
+        // deliberately unordered and including the ack size, which must be ignored
+        MessagePreparer::test_fixture().with_packet_sizes(
+            MessagePacketSizes::new(vec![
+                PacketSize::LargePacket,
+                PacketSize::AckPacket,
+                PacketSize::SmallPacket,
+                PacketSize::RegularPacket,
+            ])
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn smallest_packet_fitting_whole_message_is_chosen() {
+        let preparer = preparer_with_sizes();
+        let small = TestPreparer::available_plaintext_per_packet(PacketSize::SmallPacket);
+        let regular = TestPreparer::available_plaintext_per_packet(PacketSize::RegularPacket);
+        let large = TestPreparer::available_plaintext_per_packet(PacketSize::LargePacket);
+
+        assert_eq!(PacketSize::SmallPacket, preparer.packet_size_for_message(1));
+        assert_eq!(
+            PacketSize::SmallPacket,
+            preparer.packet_size_for_message(small)
+        );
+        assert_eq!(
+            PacketSize::RegularPacket,
+            preparer.packet_size_for_message(small + 1)
+        );
+        assert_eq!(
+            PacketSize::LargePacket,
+            preparer.packet_size_for_message(regular + 1)
+        );
+        // messages not fitting in any packet get split into the largest ones
+        assert_eq!(
+            PacketSize::LargePacket,
+            preparer.packet_size_for_message(large * 3)
+        );
+    }
+
+    #[test]
+    fn single_packet_size_is_used_for_all_messages() {
+        let preparer = TestPreparer::test_fixture();
+        assert_eq!(
+            PacketSize::RegularPacket,
+            preparer.packet_size_for_message(1)
+        );
+        assert_eq!(
+            PacketSize::RegularPacket,
+            preparer.packet_size_for_message(100_000)
+        );
+    }
+
+    #[test]
+    fn smallest_packet_fitting_fragment_is_chosen() {
+        let preparer = preparer_with_sizes();
+        let small = TestPreparer::available_plaintext_per_packet(PacketSize::SmallPacket);
+        let regular = TestPreparer::available_plaintext_per_packet(PacketSize::RegularPacket);
+
+        assert_eq!(
+            PacketSize::SmallPacket,
+            preparer.packet_size_for_fragment(small)
+        );
+        assert_eq!(
+            PacketSize::RegularPacket,
+            preparer.packet_size_for_fragment(small + 1)
+        );
+        assert_eq!(
+            PacketSize::RegularPacket,
+            preparer.packet_size_for_fragment(regular)
+        );
+        assert_eq!(
+            PacketSize::LargePacket,
+            preparer.packet_size_for_fragment(regular + 1)
+        );
+    }
+
+    #[test]
+    fn smallest_packet_fitting_reply_is_chosen() {
+        let preparer = preparer_with_sizes();
+        let small = ReplySurb::max_msg_len(PacketSize::SmallPacket);
+        let large = ReplySurb::max_msg_len(PacketSize::LargePacket);
+
+        assert_eq!(
+            Some(PacketSize::SmallPacket),
+            preparer.packet_size_for_reply(0)
+        );
+        assert_eq!(
+            Some(PacketSize::SmallPacket),
+            preparer.packet_size_for_reply(small)
+        );
+        assert_eq!(
+            Some(PacketSize::RegularPacket),
+            preparer.packet_size_for_reply(small + 1)
+        );
+        assert_eq!(
+            Some(PacketSize::LargePacket),
+            preparer.packet_size_for_reply(large)
+        );
+        // replies can't be split
+        assert!(preparer.packet_size_for_reply(large + 1).is_none());
+    }
+
+    #[test]
+    fn vpn_session_is_reused_until_the_reuse_limit() {
+        let topology = topology_fixture();
+        let recipient = Recipient::try_from_base58_string(RECIPIENT).unwrap();
+        let mut preparer = MessagePreparer::test_fixture().with_vpn_key_reuse_limit(3);
+
+        let first = next_session_secret(&mut preparer, &topology, &recipient);
+        assert_eq!(
+            first,
+            next_session_secret(&mut preparer, &topology, &recipient)
+        );
+        assert_eq!(
+            first,
+            next_session_secret(&mut preparer, &topology, &recipient)
+        );
+
+        // the limit has been reached, so a new session must have been started
+        let rotated = next_session_secret(&mut preparer, &topology, &recipient);
+        assert_ne!(first, rotated);
+        assert_eq!(
+            rotated,
+            next_session_secret(&mut preparer, &topology, &recipient)
+        );
+    }
+
+    #[test]
+    fn vpn_session_is_rotated_when_gateway_changes() {
+        let topology = topology_fixture();
+        let recipient = Recipient::try_from_base58_string(RECIPIENT).unwrap();
+        let other_recipient = Recipient::try_from_base58_string(OTHER_GATEWAY_RECIPIENT).unwrap();
+        let mut preparer = MessagePreparer::test_fixture();
+
+        let first = next_session_secret(&mut preparer, &topology, &recipient);
+        let other = next_session_secret(&mut preparer, &topology, &other_recipient);
+        assert_ne!(first, other);
+
+        // and the new session goes through the other gateway
+        let (_, route) = preparer
+            .next_vpn_session_packet(&topology, &other_recipient)
+            .unwrap();
+        let last_hop = NymNodeRoutingAddress::try_from(route.last().unwrap().address).unwrap();
+        assert_eq!("5.6.7.8:1789", last_hop.to_string());
+    }
 }
 
 /*
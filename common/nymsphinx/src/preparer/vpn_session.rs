@@ -0,0 +1,46 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nymsphinx_addressing::nodes::NodeIdentity;
+use nymsphinx_types::{EphemeralSecret, Node};
+use rand::{CryptoRng, Rng};
+use std::sync::Arc;
+
+/// State shared by all packets sent in the `Vpn` mode until the session gets rotated.
+///
+/// All of them are created with the same initial secret and sent through the same route,
+/// so that each hop could reuse the keys derived for the first packet.
+#[derive(Clone)]
+pub(super) struct VpnSession {
+    initial_secret: Arc<EphemeralSecret>,
+    route: Vec<Node>,
+    gateway: NodeIdentity,
+    packets_sent: usize,
+}
+
+impl VpnSession {
+    pub(super) fn new<R: CryptoRng + Rng>(
+        rng: &mut R,
+        route: Vec<Node>,
+        gateway: NodeIdentity,
+    ) -> Self {
+        VpnSession {
+            initial_secret: Arc::new(EphemeralSecret::new_with_rng(rng)),
+            route,
+            gateway,
+            packets_sent: 0,
+        }
+    }
+
+    /// Checks whether the session can be used for sending another packet to the specified gateway
+    /// without exceeding the allowed number of key reuses.
+    pub(super) fn is_usable(&self, gateway: &NodeIdentity, reuse_limit: usize) -> bool {
+        &self.gateway == gateway && self.packets_sent < reuse_limit
+    }
+
+    /// Marks the session as used for one more packet and returns the data required to create it.
+    pub(super) fn next_packet(&mut self) -> (&EphemeralSecret, &[Node]) {
+        self.packets_sent += 1;
+        (&self.initial_secret, &self.route)
+    }
+}
@@ -218,6 +218,7 @@ mod message_receiver {
                 )
                 .unwrap(),
                 layer: Layer::One,
+                family: None,
                 version: "0.8.0-dev".to_string(),
             }],
         );
@@ -239,6 +240,7 @@ mod message_receiver {
                 )
                 .unwrap(),
                 layer: Layer::Two,
+                family: None,
                 version: "0.8.0-dev".to_string(),
             }],
         );
@@ -260,6 +262,7 @@ mod message_receiver {
                 )
                 .unwrap(),
                 layer: Layer::Three,
+                family: None,
                 version: "0.8.0-dev".to_string(),
             }],
         );
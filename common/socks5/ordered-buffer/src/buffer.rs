@@ -1,37 +1,108 @@
 use crate::message::OrderedMessage;
 use log::*;
 use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+/// Default maximum number of bytes a single buffer is allowed to hold while waiting for
+/// missing messages to arrive. It's well above the flow control window, so that it's only ever
+/// reached by remotes that do not respect it (or do not know about it).
+pub const DEFAULT_MAX_BUFFERED_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub enum OrderedBufferError {
+    /// Storing the message would have made the buffer go over its memory limit.
+    MemoryLimitExceeded { limit: usize },
+}
+
+impl Display for OrderedBufferError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderedBufferError::MemoryLimitExceeded { limit } => write!(
+                f,
+                "buffered data would have exceeded the limit of {} bytes",
+                limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OrderedBufferError {}
 
 /// Stores messages and emits them in order.
 ///
 /// Only contiguous messages with an index less than or equal to `next_index`
 /// will be returned - this avoids returning gaps while we wait for the buffer
 /// to fill up with the full sequence.
+///
+/// The total size of stored messages is bounded by `max_buffered_bytes` so that
+/// a misbehaving (or simply too fast) sender can't make us use arbitrary amount of memory.
 #[derive(Debug)]
 pub struct OrderedMessageBuffer {
     next_index: u64,
     messages: HashMap<u64, OrderedMessage>,
+    buffered_bytes: usize,
+    max_buffered_bytes: usize,
 }
 
 impl OrderedMessageBuffer {
     pub fn new() -> OrderedMessageBuffer {
+        OrderedMessageBuffer::with_max_buffered_bytes(DEFAULT_MAX_BUFFERED_BYTES)
+    }
+
+    pub fn with_max_buffered_bytes(max_buffered_bytes: usize) -> OrderedMessageBuffer {
         OrderedMessageBuffer {
             next_index: 0,
             messages: HashMap::new(),
+            buffered_bytes: 0,
+            max_buffered_bytes,
         }
     }
 
+    /// Index of the next message that is going to be returned by `read`.
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Total number of data bytes currently stored in the buffer.
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+
     /// Writes a message to the buffer. messages are sort on insertion, so
     /// that later on multiple reads for incomplete sequences don't result in
     /// useless sort work.
-    pub fn write(&mut self, message: OrderedMessage) {
+    ///
+    /// Messages with indices that were already read are silently discarded.
+    pub fn write(&mut self, message: OrderedMessage) -> Result<(), OrderedBufferError> {
         trace!(
             "Writing message index: {} length {:?} to OrderedMessageBuffer.",
             message.index,
             message.data.len()
         );
 
+        if message.index < self.next_index {
+            debug!(
+                "Received message with index {} that was already read (next index is {})",
+                message.index, self.next_index
+            );
+            return Ok(());
+        }
+
+        let replaced_bytes = self
+            .messages
+            .get(&message.index)
+            .map(|existing| existing.data.len())
+            .unwrap_or_default();
+        let new_buffered_bytes = self.buffered_bytes - replaced_bytes + message.data.len();
+        if new_buffered_bytes > self.max_buffered_bytes {
+            return Err(OrderedBufferError::MemoryLimitExceeded {
+                limit: self.max_buffered_bytes,
+            });
+        }
+
+        self.buffered_bytes = new_buffered_bytes;
         self.messages.insert(message.index, message);
+        Ok(())
     }
 
     /// Returns `Option<Vec<u8>>` where it's `Some(bytes)` if there is gapless
@@ -57,6 +128,10 @@ impl OrderedMessageBuffer {
 
         let high_water = index;
         self.next_index = high_water;
+        self.buffered_bytes -= contiguous_messages
+            .iter()
+            .map(|message| message.data.len())
+            .sum::<usize>();
         trace!("Next high water mark is: {}", high_water);
 
         // dig out the bytes from inside the struct
@@ -101,11 +176,11 @@ mod test_chunking_and_reassembling {
                     index: 1,
                 };
 
-                buffer.write(first_message);
+                buffer.write(first_message).unwrap();
                 let first_read = buffer.read().unwrap();
                 assert_eq!(vec![1, 2, 3, 4], first_read);
 
-                buffer.write(second_message);
+                buffer.write(second_message).unwrap();
                 let second_read = buffer.read().unwrap();
                 assert_eq!(vec![5, 6, 7, 8], second_read);
 
@@ -125,8 +200,8 @@ mod test_chunking_and_reassembling {
                     index: 1,
                 };
 
-                buffer.write(first_message);
-                buffer.write(second_message);
+                buffer.write(first_message).unwrap();
+                buffer.write(second_message).unwrap();
                 let second_read = buffer.read();
                 assert_eq!(vec![1, 2, 3, 4, 5, 6, 7, 8], second_read.unwrap());
                 assert_eq!(None, buffer.read()); // second read on fully ordered result set is empty
@@ -145,8 +220,8 @@ mod test_chunking_and_reassembling {
                     index: 1,
                 };
 
-                buffer.write(second_message);
-                buffer.write(first_message);
+                buffer.write(second_message).unwrap();
+                buffer.write(first_message).unwrap();
                 let read = buffer.read();
                 assert_eq!(vec![1, 2, 3, 4, 5, 6, 7, 8], read.unwrap());
                 assert_eq!(None, buffer.read()); // second read on fully ordered result set is empty
@@ -174,9 +249,9 @@ mod test_chunking_and_reassembling {
                     index: 3,
                 };
 
-                buffer.write(zero_message);
-                buffer.write(one_message);
-                buffer.write(three_message);
+                buffer.write(zero_message).unwrap();
+                buffer.write(one_message).unwrap();
+                buffer.write(three_message).unwrap();
                 buffer
             }
             #[test]
@@ -193,7 +268,7 @@ mod test_chunking_and_reassembling {
                     data: vec![5, 5, 5, 5],
                     index: 5,
                 };
-                buffer.write(five_message);
+                buffer.write(five_message).unwrap();
                 assert_eq!(None, buffer.read());
             }
 
@@ -206,7 +281,7 @@ mod test_chunking_and_reassembling {
                     data: vec![2, 2, 2, 2],
                     index: 2,
                 };
-                buffer.write(two_message);
+                buffer.write(two_message).unwrap();
 
                 let more_ordered_bytes = buffer.read().unwrap();
                 assert_eq!([2, 2, 2, 2, 3, 3, 3, 3].to_vec(), more_ordered_bytes);
@@ -216,7 +291,7 @@ mod test_chunking_and_reassembling {
                     data: vec![5, 5, 5, 5],
                     index: 5,
                 };
-                buffer.write(five_message);
+                buffer.write(five_message).unwrap();
 
                 assert_eq!(None, buffer.read());
 
@@ -225,7 +300,7 @@ mod test_chunking_and_reassembling {
                     data: vec![4, 4, 4, 4],
                     index: 4,
                 };
-                buffer.write(four_message);
+                buffer.write(four_message).unwrap();
 
                 assert_eq!([4, 4, 4, 4, 5, 5, 5, 5].to_vec(), buffer.read().unwrap());
 
@@ -249,11 +324,11 @@ mod test_chunking_and_reassembling {
                     index: 2,
                 };
 
-                buffer.write(zero_message);
+                buffer.write(zero_message).unwrap();
                 assert!(buffer.read().is_some()); // burn the buffer
 
-                buffer.write(two_message);
-                buffer.write(one_message);
+                buffer.write(two_message).unwrap();
+                buffer.write(one_message).unwrap();
                 assert!(buffer.read().is_some());
                 assert_eq!(buffer.next_index, 3);
             }
@@ -281,26 +356,84 @@ mod test_chunking_and_reassembling {
                     data: vec![2, 2, 2, 2],
                     index: 4,
                 };
-                buffer.write(zero_message);
+                buffer.write(zero_message).unwrap();
                 assert!(buffer.read().is_some());
                 assert_eq!(buffer.next_index, 1);
 
-                buffer.write(four_message);
+                buffer.write(four_message).unwrap();
                 assert!(buffer.read().is_none());
                 assert_eq!(buffer.next_index, 1);
 
-                buffer.write(three_message);
+                buffer.write(three_message).unwrap();
                 assert!(buffer.read().is_none());
                 assert_eq!(buffer.next_index, 1);
 
-                buffer.write(two_message);
+                buffer.write(two_message).unwrap();
                 assert!(buffer.read().is_none());
                 assert_eq!(buffer.next_index, 1);
 
-                buffer.write(one_message);
+                buffer.write(one_message).unwrap();
                 assert!(buffer.read().is_some());
                 assert_eq!(buffer.next_index, 5)
             }
         }
     }
+
+    mod enforcing_memory_limit {
+        use super::*;
+
+        #[test]
+        fn write_fails_when_limit_would_be_exceeded() {
+            let mut buffer = OrderedMessageBuffer::with_max_buffered_bytes(6);
+            let one_message = OrderedMessage {
+                data: vec![1, 1, 1, 1],
+                index: 1,
+            };
+            let two_message = OrderedMessage {
+                data: vec![2, 2, 2, 2],
+                index: 2,
+            };
+
+            buffer.write(one_message).unwrap();
+            assert_eq!(
+                Err(OrderedBufferError::MemoryLimitExceeded { limit: 6 }),
+                buffer.write(two_message)
+            );
+            assert_eq!(4, buffer.buffered_bytes());
+        }
+
+        #[test]
+        fn reading_frees_up_space() {
+            let mut buffer = OrderedMessageBuffer::with_max_buffered_bytes(6);
+            let zero_message = OrderedMessage {
+                data: vec![0, 0, 0, 0],
+                index: 0,
+            };
+            let one_message = OrderedMessage {
+                data: vec![1, 1, 1, 1],
+                index: 1,
+            };
+
+            buffer.write(zero_message).unwrap();
+            assert!(buffer.read().is_some());
+            assert_eq!(0, buffer.buffered_bytes());
+            assert!(buffer.write(one_message).is_ok());
+        }
+
+        #[test]
+        fn already_read_messages_are_discarded() {
+            let mut buffer = OrderedMessageBuffer::new();
+            let zero_message = OrderedMessage {
+                data: vec![0, 0, 0, 0],
+                index: 0,
+            };
+
+            buffer.write(zero_message.clone()).unwrap();
+            assert!(buffer.read().is_some());
+
+            buffer.write(zero_message).unwrap();
+            assert_eq!(0, buffer.buffered_bytes());
+            assert_eq!(None, buffer.read());
+        }
+    }
 }
@@ -2,7 +2,7 @@ mod buffer;
 mod message;
 mod sender;
 
-pub use buffer::OrderedMessageBuffer;
+pub use buffer::{OrderedBufferError, OrderedMessageBuffer, DEFAULT_MAX_BUFFERED_BYTES};
 pub use message::MessageError;
 pub use message::OrderedMessage;
-pub use sender::OrderedMessageSender;
+pub use sender::{OrderedMessageSender, DEFAULT_WINDOW_SIZE};
@@ -1,22 +1,65 @@
 use crate::message::OrderedMessage;
 
+/// Number of bytes the sender is allowed to send beyond the data the receiver has confirmed
+/// to have delivered. It's also the initial window, before the receiver managed to
+/// advertise anything.
+pub const DEFAULT_WINDOW_SIZE: u64 = 2 * 1024 * 1024;
+
 /// Assigns sequence numbers to outbound byte vectors. These messages can then
-/// be reassembled into an ordered sequence by the `OrderedMessageSender`.
+/// be reassembled into an ordered sequence by the `OrderedMessageBuffer`.
+///
+/// If the receiver does flow control, the sender also keeps track of the window it advertised,
+/// i.e. the total number of bytes it is willing to accept on this connection.
 #[derive(Debug)]
 pub struct OrderedMessageSender {
     next_index: u64,
+    sent_bytes: u64,
+    window_end: Option<u64>,
 }
 
 impl OrderedMessageSender {
     pub fn new() -> OrderedMessageSender {
-        OrderedMessageSender { next_index: 0 }
+        OrderedMessageSender {
+            next_index: 0,
+            sent_bytes: 0,
+            window_end: Some(DEFAULT_WINDOW_SIZE),
+        }
+    }
+
+    /// Creates a sender for a receiver that does not advertise any window,
+    /// in which case the window never closes.
+    pub fn without_flow_control() -> OrderedMessageSender {
+        OrderedMessageSender {
+            next_index: 0,
+            sent_bytes: 0,
+            window_end: None,
+        }
+    }
+
+    /// Checks whether the receiver is willing to accept more data.
+    pub fn is_window_open(&self) -> bool {
+        match self.window_end {
+            Some(window_end) => self.sent_bytes < window_end,
+            None => true,
+        }
+    }
+
+    /// Updates the window based on the receiver's advertisement. Since advertisements
+    /// might arrive out of order, the window is never shrunk.
+    pub fn update_window(&mut self, window_end: u64) {
+        if let Some(current_end) = self.window_end.as_mut() {
+            if window_end > *current_end {
+                *current_end = window_end
+            }
+        }
     }
 
     /// Turns raw bytes into an OrderedMessage containing the original bytes
     /// and a sequence number;
     pub fn wrap_message(&mut self, input: Vec<u8>) -> OrderedMessage {
+        self.sent_bytes += input.len() as u64;
         let message = OrderedMessage {
-            data: input.to_vec(),
+            data: input,
             index: self.next_index,
         };
         self.next_index += 1;
@@ -54,4 +97,56 @@ mod ordered_message_sender {
             assert_eq!(second_message.index, 1);
         }
     }
+
+    #[cfg(test)]
+    mod flow_control_window {
+        use super::*;
+
+        const CHUNK: usize = 1024;
+
+        #[test]
+        fn closes_after_default_window_is_used() {
+            let mut sender = OrderedMessageSender::new();
+            for _ in 0..DEFAULT_WINDOW_SIZE as usize / CHUNK {
+                assert!(sender.is_window_open());
+                sender.wrap_message(vec![42; CHUNK]);
+            }
+            assert!(!sender.is_window_open());
+        }
+
+        #[test]
+        fn is_counted_in_bytes_rather_than_messages() {
+            let mut sender = OrderedMessageSender::new();
+            sender.wrap_message(vec![42; DEFAULT_WINDOW_SIZE as usize]);
+            assert!(!sender.is_window_open());
+        }
+
+        #[test]
+        fn reopens_after_receiver_advertises_bigger_window() {
+            let mut sender = OrderedMessageSender::new();
+            sender.wrap_message(vec![42; DEFAULT_WINDOW_SIZE as usize]);
+            sender.update_window(DEFAULT_WINDOW_SIZE + CHUNK as u64);
+            assert!(sender.is_window_open());
+            sender.wrap_message(vec![42; CHUNK]);
+            assert!(!sender.is_window_open());
+        }
+
+        #[test]
+        fn is_never_shrunk() {
+            let mut sender = OrderedMessageSender::new();
+            sender.update_window(1);
+            for _ in 0..DEFAULT_WINDOW_SIZE as usize / CHUNK {
+                assert!(sender.is_window_open());
+                sender.wrap_message(vec![42; CHUNK]);
+            }
+        }
+
+        #[test]
+        fn never_closes_without_flow_control() {
+            let mut sender = OrderedMessageSender::without_flow_control();
+            sender.wrap_message(vec![42; DEFAULT_WINDOW_SIZE as usize]);
+            sender.update_window(1);
+            assert!(sender.is_window_open());
+        }
+    }
 }
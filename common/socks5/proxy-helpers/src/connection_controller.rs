@@ -4,9 +4,20 @@
 use futures::channel::mpsc;
 use futures::StreamExt;
 use log::*;
-use ordered_buffer::{OrderedMessage, OrderedMessageBuffer};
+use ordered_buffer::{OrderedBufferError, OrderedMessage, OrderedMessageBuffer};
 use socks5_requests::ConnectionId;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+/// Maximum number of connections that can have data buffered before being established.
+const MAX_PENDING_CONNECTIONS: usize = 256;
+
+/// Maximum number of bytes buffered, across all connections, before the connections got established.
+const MAX_TOTAL_PENDING_BYTES: usize = 64 * 1024 * 1024;
+
+/// Amount of time after which data buffered for a connection that never got established is dropped.
+const PENDING_MESSAGES_TIMEOUT: Duration = Duration::from_secs(60);
 
 /// A generic message produced after reading from a socket/connection. It includes data that was
 /// actually read alongside boolean indicating whether the connection got closed so that
@@ -15,6 +26,9 @@ use std::collections::{HashMap, HashSet};
 pub struct ConnectionMessage {
     pub payload: Vec<u8>,
     pub socket_closed: bool,
+
+    /// The most recent receive window advertised by the remote, if any.
+    pub remote_window: Option<u64>,
 }
 
 /// Channel responsible for sending data that was received from mix network into particular connection.
@@ -26,13 +40,26 @@ pub type ConnectionSender = mpsc::UnboundedSender<ConnectionMessage>;
 /// Receiver part of the [`ConnectionSender`]
 pub type ConnectionReceiver = mpsc::UnboundedReceiver<ConnectionMessage>;
 
+/// Data received before the connection was established alongside the flags that came with it.
+type PendingMessage = (Vec<u8>, bool, Option<u64>);
+
+struct PendingConnection {
+    messages: Vec<PendingMessage>,
+    bytes: usize,
+    first_received: Instant,
+}
+
 pub type ControllerSender = mpsc::UnboundedSender<ControllerCommand>;
 pub type ControllerReceiver = mpsc::UnboundedReceiver<ControllerCommand>;
 
 pub enum ControllerCommand {
     Insert(ConnectionId, ConnectionSender),
     Remove(ConnectionId),
-    Send(ConnectionId, Vec<u8>, bool),
+    Send(ConnectionId, Vec<u8>, bool, Option<u64>),
+
+    /// Forcefully closes the connection without waiting for any outstanding ordered data,
+    /// for example because the remote refused to handle it any further.
+    Close(ConnectionId),
 }
 
 struct ActiveConnection {
@@ -42,15 +69,15 @@ struct ActiveConnection {
 }
 
 impl ActiveConnection {
-    fn write_to_buf(&mut self, payload: Vec<u8>) {
+    fn write_to_buf(&mut self, payload: Vec<u8>) -> Result<(), OrderedBufferError> {
         let ordered_message = match OrderedMessage::try_from_bytes(payload) {
             Ok(msg) => msg,
             Err(err) => {
                 error!("Malformed ordered message - {:?}", err);
-                return;
+                return Ok(());
             }
         };
-        self.ordered_buffer.write(ordered_message);
+        self.ordered_buffer.write(ordered_message)
     }
 
     fn read_from_buf(&mut self) -> Option<Vec<u8>> {
@@ -68,16 +95,23 @@ pub struct Controller {
     // to avoid memory issues
     recently_closed: HashSet<ConnectionId>,
 
-    // TODO: this can potentially be abused to ddos and kill provider. Not sure at this point
-    // how to handle it more gracefully
-
     // buffer for messages received before connection was established due to mixnet being able to
     // un-order messages. Note we don't ever expect to have more than 1-2 messages per connection here
-    pending_messages: HashMap<ConnectionId, Vec<(Vec<u8>, bool)>>,
+    // (data for each connection is bounded by `max_buffered_bytes` though, while all of the
+    // data is bounded by `MAX_PENDING_CONNECTIONS` and `MAX_TOTAL_PENDING_BYTES`)
+    pending_messages: HashMap<ConnectionId, PendingConnection>,
+
+    // connections with pending messages alongside the time they were first received at,
+    // in the order they were received in. Used for evicting and expiring the pending messages.
+    pending_order: VecDeque<(ConnectionId, Instant)>,
+    pending_bytes: usize,
+
+    /// Maximum amount of data that is allowed to be buffered for each connection.
+    max_buffered_bytes: usize,
 }
 
 impl Controller {
-    pub fn new() -> (Self, ControllerSender) {
+    pub fn new(max_buffered_bytes: usize) -> (Self, ControllerSender) {
         let (sender, receiver) = mpsc::unbounded();
         (
             Controller {
@@ -85,6 +119,9 @@ impl Controller {
                 receiver,
                 recently_closed: HashSet::new(),
                 pending_messages: HashMap::new(),
+                pending_order: VecDeque::new(),
+                pending_bytes: 0,
+                max_buffered_bytes,
             },
             sender,
         )
@@ -94,16 +131,16 @@ impl Controller {
         let active_connection = ActiveConnection {
             is_closed: false,
             connection_sender: Some(connection_sender),
-            ordered_buffer: OrderedMessageBuffer::new(),
+            ordered_buffer: OrderedMessageBuffer::with_max_buffered_bytes(self.max_buffered_bytes),
         };
         if let Some(_active_conn) = self.active_connections.insert(conn_id, active_connection) {
             error!("Received a duplicate 'Connect'!")
         } else {
             // check if there were any pending messages
-            if let Some(pending) = self.pending_messages.remove(&conn_id) {
+            if let Some(pending) = self.take_pending(conn_id) {
                 debug!("There were some pending messages for {}", conn_id);
-                for (payload, is_closed) in pending {
-                    self.send_to_connection(conn_id, payload, is_closed)
+                for (payload, is_closed, remote_window) in pending.messages {
+                    self.send_to_connection(conn_id, payload, is_closed, remote_window)
                 }
             }
         }
@@ -120,45 +157,85 @@ impl Controller {
         self.recently_closed.insert(conn_id);
     }
 
-    fn send_to_connection(&mut self, conn_id: ConnectionId, payload: Vec<u8>, is_closed: bool) {
+    fn close_connection(&mut self, conn_id: ConnectionId) {
+        if let Some(active_connection) = self.active_connections.get_mut(&conn_id) {
+            debug!("Forcefully closing connection {}", conn_id);
+            active_connection.is_closed = true;
+            if let Some(connection_sender) = active_connection.connection_sender.as_ref() {
+                if connection_sender
+                    .unbounded_send(ConnectionMessage {
+                        payload: Vec::new(),
+                        socket_closed: true,
+                        remote_window: None,
+                    })
+                    .is_err()
+                {
+                    debug!("Connection {} has already been shut down", conn_id);
+                }
+            }
+        } else if self.take_pending(conn_id).is_some() {
+            debug!(
+                "Dropped pending messages of connection {} that was closed before being established",
+                conn_id
+            );
+        }
+    }
+
+    fn send_to_connection(
+        &mut self,
+        conn_id: ConnectionId,
+        payload: Vec<u8>,
+        is_closed: bool,
+        remote_window: Option<u64>,
+    ) {
         if let Some(active_connection) = self.active_connections.get_mut(&conn_id) {
             if !payload.is_empty() {
-                active_connection.write_to_buf(payload);
-            } else if !is_closed {
+                if let Err(err) = active_connection.write_to_buf(payload) {
+                    error!(
+                        "Failed to buffer data for connection {} - {}. Closing the connection",
+                        conn_id, err
+                    );
+                    self.close_connection(conn_id);
+                    return;
+                }
+            } else if !is_closed && remote_window.is_none() {
                 error!("Tried to write an empty message to a not-closing connection. Please let us know if you see this message");
             }
             // if messages get unordered, make sure we don't lose information about
             // remote socket getting closed!
             active_connection.is_closed |= is_closed;
 
-            if let Some(payload) = active_connection.read_from_buf() {
-                if let Err(err) = active_connection
-                    .connection_sender
-                    .as_mut()
-                    .unwrap()
-                    .unbounded_send(ConnectionMessage {
-                        payload,
-                        socket_closed: active_connection.is_closed,
-                    })
-                {
-                    error!("WTF IS THIS: {:?}", err);
-                }
+            // window updates are not ordered, so make sure to forward them even if there's
+            // no new data to write
+            let payload = match active_connection.read_from_buf() {
+                Some(payload) => payload,
+                None if remote_window.is_some() => Vec::new(),
+                None => return,
+            };
 
-                // TODO: ABOVE UNWRAP CAUSED A CRASH IN A NORMAL USE!!!!
-                // TODO:
-                // TODO: surprisingly it only happened on socks client, never on nSP
-                // TODO:
-                // TODO:
-                // TODO:
-                // TODO:
+            if let Err(err) = active_connection
+                .connection_sender
+                .as_mut()
+                .unwrap()
+                .unbounded_send(ConnectionMessage {
+                    payload,
+                    socket_closed: active_connection.is_closed,
+                    remote_window,
+                })
+            {
+                error!("WTF IS THIS: {:?}", err);
             }
+
+            // TODO: ABOVE UNWRAP CAUSED A CRASH IN A NORMAL USE!!!!
+            // TODO:
+            // TODO: surprisingly it only happened on socks client, never on nSP
+            // TODO:
+            // TODO:
+            // TODO:
+            // TODO:
         } else if !self.recently_closed.contains(&conn_id) {
             debug!("Received a 'Send' before 'Connect' - going to buffer the data");
-            let pending = self
-                .pending_messages
-                .entry(conn_id)
-                .or_insert_with(Vec::new);
-            pending.push((payload, is_closed));
+            self.buffer_pending(conn_id, (payload, is_closed, remote_window));
         } else if !is_closed {
             error!(
                 "Tried to write to closed connection ({} bytes were 'lost)",
@@ -169,17 +246,153 @@ impl Controller {
         }
     }
 
+    fn take_pending(&mut self, conn_id: ConnectionId) -> Option<PendingConnection> {
+        let pending = self.pending_messages.remove(&conn_id)?;
+        self.pending_bytes -= pending.bytes;
+        Some(pending)
+    }
+
+    /// Drops data of the connection that has been waiting to get established for the longest.
+    fn evict_oldest_pending(&mut self) -> bool {
+        while let Some((conn_id, first_received)) = self.pending_order.pop_front() {
+            // the entry might have already been removed (and possibly re-created) in the meantime
+            match self.pending_messages.get(&conn_id) {
+                Some(pending) if pending.first_received == first_received => {
+                    if let Some(pending) = self.take_pending(conn_id) {
+                        warn!(
+                            "Dropping {} bytes of pending data of connection {} to make space for other connections",
+                            pending.bytes, conn_id
+                        );
+                    }
+                    return true;
+                }
+                _ => continue,
+            }
+        }
+        false
+    }
+
+    fn drop_expired_pending(&mut self, now: Instant) {
+        while let Some((conn_id, first_received)) = self.pending_order.front().copied() {
+            if now.duration_since(first_received) < PENDING_MESSAGES_TIMEOUT {
+                break;
+            }
+            self.pending_order.pop_front();
+            if let Some(pending) = self.pending_messages.get(&conn_id) {
+                if pending.first_received == first_received {
+                    debug!(
+                        "Connection {} was never established - dropping its pending data",
+                        conn_id
+                    );
+                    self.take_pending(conn_id);
+                }
+            }
+        }
+    }
+
+    fn buffer_pending(&mut self, conn_id: ConnectionId, message: PendingMessage) {
+        let message_len = message.0.len();
+        let connection_bytes = self
+            .pending_messages
+            .get(&conn_id)
+            .map(|pending| pending.bytes)
+            .unwrap_or_default();
+        if connection_bytes + message_len > self.max_buffered_bytes
+            || message_len > MAX_TOTAL_PENDING_BYTES
+        {
+            error!(
+                "Too much data was sent to connection {} before it got established ({} bytes were 'lost')",
+                conn_id,
+                message_len
+            );
+            return;
+        }
+
+        // make space for the new data by dropping the data of the oldest connections
+        let is_new_connection = !self.pending_messages.contains_key(&conn_id);
+        while (is_new_connection && self.pending_messages.len() >= MAX_PENDING_CONNECTIONS)
+            || self.pending_bytes + message_len > MAX_TOTAL_PENDING_BYTES
+        {
+            if !self.evict_oldest_pending() {
+                break;
+            }
+        }
+
+        // we might have just evicted our own connection
+        let now = Instant::now();
+        let pending_order = &mut self.pending_order;
+        let pending = self.pending_messages.entry(conn_id).or_insert_with(|| {
+            pending_order.push_back((conn_id, now));
+            PendingConnection {
+                messages: Vec::new(),
+                bytes: 0,
+                first_received: now,
+            }
+        });
+        pending.bytes += message_len;
+        pending.messages.push(message);
+        self.pending_bytes += message_len;
+    }
+
     pub async fn run(&mut self) {
-        while let Some(command) = self.receiver.next().await {
+        let mut expiry_interval = time::interval(PENDING_MESSAGES_TIMEOUT / 2);
+        loop {
+            let command = tokio::select! {
+                command = self.receiver.next() => match command {
+                    Some(command) => command,
+                    None => break,
+                },
+                _ = expiry_interval.tick() => {
+                    self.drop_expired_pending(Instant::now());
+                    continue;
+                }
+            };
             match command {
-                ControllerCommand::Send(conn_id, data, is_closed) => {
-                    self.send_to_connection(conn_id, data, is_closed)
+                ControllerCommand::Send(conn_id, data, is_closed, remote_window) => {
+                    self.send_to_connection(conn_id, data, is_closed, remote_window)
                 }
                 ControllerCommand::Insert(conn_id, sender) => {
                     self.insert_connection(conn_id, sender)
                 }
                 ControllerCommand::Remove(conn_id) => self.remove_connection(conn_id),
+                ControllerCommand::Close(conn_id) => self.close_connection(conn_id),
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oldest_pending_connections_are_evicted() {
+        let (mut controller, _) = Controller::new(100);
+        for conn_id in 0..=MAX_PENDING_CONNECTIONS as ConnectionId {
+            controller.send_to_connection(conn_id, vec![42], false, None);
+        }
+
+        assert_eq!(MAX_PENDING_CONNECTIONS, controller.pending_messages.len());
+        assert_eq!(MAX_PENDING_CONNECTIONS, controller.pending_bytes);
+        assert!(!controller.pending_messages.contains_key(&0));
+        assert!(controller
+            .pending_messages
+            .contains_key(&(MAX_PENDING_CONNECTIONS as ConnectionId)));
+    }
+
+    #[test]
+    fn pending_messages_expire() {
+        let (mut controller, _) = Controller::new(100);
+        controller.send_to_connection(1, vec![42; 10], false, None);
+        controller.send_to_connection(2, vec![42; 10], false, None);
+        let last_received = controller.pending_messages[&2].first_received;
+
+        controller.drop_expired_pending(last_received);
+        assert_eq!(2, controller.pending_messages.len());
+
+        controller.drop_expired_pending(last_received + PENDING_MESSAGES_TIMEOUT);
+        assert!(controller.pending_messages.is_empty());
+        assert!(controller.pending_order.is_empty());
+        assert_eq!(0, controller.pending_bytes);
+    }
+}
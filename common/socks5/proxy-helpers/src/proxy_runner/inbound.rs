@@ -8,37 +8,65 @@ use bytes::Bytes;
 use futures::FutureExt;
 use futures::StreamExt;
 use log::*;
-use ordered_buffer::OrderedMessageSender;
+use ordered_buffer::{OrderedMessageSender, DEFAULT_WINDOW_SIZE};
 use socks5_requests::ConnectionId;
 use std::{io, sync::Arc};
 use tokio::select;
+use tokio::sync::watch;
 use tokio::{net::tcp::OwnedReadHalf, sync::Notify, time::sleep};
 
 fn send_empty_close<F, S>(
     connection_id: ConnectionId,
     message_sender: &mut OrderedMessageSender,
+    receive_window: Option<u64>,
     mix_sender: &MixProxySender<S>,
     adapter_fn: F,
 ) where
-    F: Fn(ConnectionId, Vec<u8>, bool) -> S,
+    F: Fn(ConnectionId, Vec<u8>, bool, Option<u64>) -> S,
 {
     let ordered_msg = message_sender.wrap_message(Vec::new()).into_bytes();
     mix_sender
-        .unbounded_send(adapter_fn(connection_id, ordered_msg, true))
+        .unbounded_send(adapter_fn(connection_id, ordered_msg, true, receive_window))
         .unwrap();
 }
 
+// window updates are not part of the ordered stream, so they're sent with no data attached
+fn send_window_update<F, S>(
+    connection_id: ConnectionId,
+    receive_window: u64,
+    mix_sender: &MixProxySender<S>,
+    adapter_fn: F,
+) where
+    F: Fn(ConnectionId, Vec<u8>, bool, Option<u64>) -> S,
+{
+    trace!(
+        target: &*format!("({}) socks5 inbound", connection_id),
+        "advertising receive window of {}",
+        receive_window
+    );
+    mix_sender
+        .unbounded_send(adapter_fn(
+            connection_id,
+            Vec::new(),
+            false,
+            Some(receive_window),
+        ))
+        .unwrap();
+}
+
+#[allow(clippy::too_many_arguments)]
 fn deal_with_data<F, S>(
     read_data: Option<io::Result<Bytes>>,
     local_destination_address: &str,
     remote_source_address: &str,
     connection_id: ConnectionId,
     message_sender: &mut OrderedMessageSender,
+    receive_window: Option<u64>,
     mix_sender: &MixProxySender<S>,
     adapter_fn: F,
 ) -> bool
 where
-    F: Fn(ConnectionId, Vec<u8>, bool) -> S,
+    F: Fn(ConnectionId, Vec<u8>, bool, Option<u64>) -> S,
 {
     let (read_data, is_finished) = match read_data {
         Some(data) => match data {
@@ -63,7 +91,12 @@ where
     // if we're sending through the mixnet increase the sequence number...
     let ordered_msg = message_sender.wrap_message(read_data.to_vec()).into_bytes();
     mix_sender
-        .unbounded_send(adapter_fn(connection_id, ordered_msg, is_finished))
+        .unbounded_send(adapter_fn(
+            connection_id,
+            ordered_msg,
+            is_finished,
+            receive_window,
+        ))
         .unwrap();
 
     if is_finished {
@@ -74,6 +107,7 @@ where
     is_finished
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) async fn run_inbound<F, S>(
     mut reader: OwnedReadHalf,
     local_destination_address: String, // addresses are provided for better logging
@@ -82,28 +116,60 @@ pub(super) async fn run_inbound<F, S>(
     mix_sender: MixProxySender<S>,
     adapter_fn: F,
     shutdown_notify: Arc<Notify>,
+    flow_control: bool,
+    mut remote_window_receiver: watch::Receiver<u64>,
+    mut delivered_receiver: watch::Receiver<u64>,
 ) -> OwnedReadHalf
 where
-    F: Fn(ConnectionId, Vec<u8>, bool) -> S + Send + 'static,
+    F: Fn(ConnectionId, Vec<u8>, bool, Option<u64>) -> S + Send + 'static,
 {
     let mut available_reader = AvailableReader::new(&mut reader);
-    let mut message_sender = OrderedMessageSender::new();
+    // remotes that do not understand flow control would never advertise any window
+    let mut message_sender = if flow_control {
+        OrderedMessageSender::new()
+    } else {
+        OrderedMessageSender::without_flow_control()
+    };
     let shutdown_future = shutdown_notify.notified().then(|_| sleep(SHUTDOWN_TIMEOUT));
 
+    // the remote assumes the default window until told otherwise
+    let mut advertised_window = DEFAULT_WINDOW_SIZE;
+
     tokio::pin!(shutdown_future);
 
     loop {
+        // the window is expressed as the total number of bytes we're willing to accept
+        let receive_window = *delivered_receiver.borrow() + DEFAULT_WINDOW_SIZE;
+        let announced_window = if flow_control {
+            Some(receive_window)
+        } else {
+            None
+        };
         select! {
-            read_data = &mut available_reader.next() => {
-                if deal_with_data(read_data, &local_destination_address, &remote_source_address, connection_id, &mut message_sender, &mix_sender, &adapter_fn) {
+            // stop reading from the socket if the remote is not willing to accept any more data
+            // (which in turn should make the local side stop sending)
+            read_data = &mut available_reader.next(), if message_sender.is_window_open() => {
+                advertised_window = receive_window;
+                if deal_with_data(read_data, &local_destination_address, &remote_source_address, connection_id, &mut message_sender, announced_window, &mix_sender, &adapter_fn) {
                     break
                 }
             }
+            Ok(_) = remote_window_receiver.changed() => {
+                message_sender.update_window(*remote_window_receiver.borrow());
+            }
+            Ok(_) = delivered_receiver.changed(), if flow_control => {
+                let receive_window = *delivered_receiver.borrow() + DEFAULT_WINDOW_SIZE;
+                // don't bother the remote with every single message we've delivered
+                if receive_window - advertised_window >= DEFAULT_WINDOW_SIZE / 2 {
+                    send_window_update(connection_id, receive_window, &mix_sender, &adapter_fn);
+                    advertised_window = receive_window;
+                }
+            }
             _ = &mut shutdown_future => {
                 debug!("closing inbound proxy after outbound was closed {:?} ago", SHUTDOWN_TIMEOUT);
                 // inform remote just in case it was closed because of lack of heartbeat.
                 // worst case the remote will just have couple of false negatives
-                send_empty_close(connection_id, &mut message_sender, &mix_sender, &adapter_fn);
+                send_empty_close(connection_id, &mut message_sender, announced_window, &mix_sender, &adapter_fn);
                 break;
             }
         }
@@ -3,9 +3,10 @@
 
 use crate::connection_controller::ConnectionReceiver;
 use futures::channel::mpsc;
-use socks5_requests::ConnectionId;
+use ordered_buffer::DEFAULT_WINDOW_SIZE;
+use socks5_requests::{ConnectionId, ProtocolVersion};
 use std::{sync::Arc, time::Duration};
-use tokio::{net::TcpStream, sync::Notify};
+use tokio::{net::TcpStream, sync::watch, sync::Notify};
 
 mod inbound;
 mod outbound;
@@ -44,6 +45,10 @@ pub struct ProxyRunner<S> {
     local_destination_address: String,
     remote_source_address: String,
     connection_id: ConnectionId,
+
+    /// Protocol version agreed on with the remote, which determines whether
+    /// the flow control windows are used.
+    protocol_version: ProtocolVersion,
 }
 
 impl<S> ProxyRunner<S>
@@ -57,6 +62,7 @@ where
         mix_receiver: ConnectionReceiver,
         mix_sender: MixProxySender<S>,
         connection_id: ConnectionId,
+        protocol_version: ProtocolVersion,
     ) -> Self {
         ProxyRunner {
             mix_receiver: Some(mix_receiver),
@@ -65,18 +71,26 @@ where
             local_destination_address,
             remote_source_address,
             connection_id,
+            protocol_version,
         }
     }
 
     // The `adapter_fn` is used to transform whatever was read into appropriate
     // request/response as required by entity running particular side of the proxy.
+    // Apart from the data itself, it is also given our current receive window that
+    // should be advertised to the remote, if it understands flow control.
     pub async fn run<F>(mut self, adapter_fn: F) -> Self
     where
-        F: Fn(ConnectionId, Vec<u8>, bool) -> S + Send + 'static,
+        F: Fn(ConnectionId, Vec<u8>, bool, Option<u64>) -> S + Send + 'static,
     {
         let (read_half, write_half) = self.socket.take().unwrap().into_split();
         let shutdown_notify = Arc::new(Notify::new());
 
+        // outbound learns about remote window and data we've delivered, but it's inbound that
+        // has to act on it
+        let (remote_window_sender, remote_window_receiver) = watch::channel(DEFAULT_WINDOW_SIZE);
+        let (delivered_sender, delivered_receiver) = watch::channel(0);
+
         // should run until either inbound closes or is notified from outbound
         let inbound_future = inbound::run_inbound(
             read_half,
@@ -86,6 +100,9 @@ where
             self.mix_sender.clone(),
             adapter_fn,
             Arc::clone(&shutdown_notify),
+            self.protocol_version.supports_flow_control(),
+            remote_window_receiver,
+            delivered_receiver,
         );
 
         let outbound_future = outbound::run_outbound(
@@ -95,6 +112,8 @@ where
             self.mix_receiver.take().unwrap(),
             self.connection_id,
             shutdown_notify,
+            remote_window_sender,
+            delivered_sender,
         );
 
         // TODO: this shouldn't really have to spawn tasks inside "library" code, but
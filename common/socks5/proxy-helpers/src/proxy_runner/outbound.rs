@@ -10,6 +10,7 @@ use socks5_requests::ConnectionId;
 use std::{sync::Arc, time::Duration};
 use tokio::io::AsyncWriteExt;
 use tokio::select;
+use tokio::sync::watch;
 use tokio::{net::tcp::OwnedWriteHalf, sync::Notify, time::sleep, time::Instant};
 
 const MIX_TTL: Duration = Duration::from_secs(5 * 60);
@@ -20,7 +21,16 @@ async fn deal_with_message(
     local_destination_address: &str,
     remote_source_address: &str,
     connection_id: ConnectionId,
+    remote_window_sender: &watch::Sender<u64>,
+    delivered_sender: &watch::Sender<u64>,
+    delivered_bytes: &mut u64,
 ) -> bool {
+    // the window update is independent of the data so make sure to handle it first
+    if let Some(remote_window) = connection_message.remote_window {
+        // if the inbound is already gone, there's nothing to unblock anyway
+        let _ = remote_window_sender.send(remote_window);
+    }
+
     debug!(
         target: &*format!("({}) socks5 outbound", connection_id),
         "[{} bytes]\t{} → remote → mixnet → local → {} Remote closed: {}",
@@ -35,6 +45,12 @@ async fn deal_with_message(
         error!(target: &*format!("({}) socks5 outbound", connection_id), "failed to write response back to the socket - {}", err);
         return true;
     }
+    // the data is now out of our hands, so the remote can be told it's free to send more
+    if !connection_message.payload.is_empty() {
+        *delivered_bytes += connection_message.payload.len() as u64;
+        let _ = delivered_sender.send(*delivered_bytes);
+    }
+
     if connection_message.socket_closed {
         debug!(target: &*format!("({}) socks5 outbound", connection_id),
                "Remote socket got closed - closing the local socket too");
@@ -43,6 +59,7 @@ async fn deal_with_message(
     false
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) async fn run_outbound(
     mut writer: OwnedWriteHalf,
     local_destination_address: String, // addresses are provided for better logging
@@ -50,17 +67,20 @@ pub(super) async fn run_outbound(
     mut mix_receiver: ConnectionReceiver,
     connection_id: ConnectionId,
     shutdown_notify: Arc<Notify>,
+    remote_window_sender: watch::Sender<u64>,
+    delivered_sender: watch::Sender<u64>,
 ) -> (OwnedWriteHalf, ConnectionReceiver) {
     let shutdown_future = shutdown_notify.notified().then(|_| sleep(SHUTDOWN_TIMEOUT));
     tokio::pin!(shutdown_future);
 
     let mut mix_timeout = Box::pin(sleep(MIX_TTL));
+    let mut delivered_bytes = 0;
 
     loop {
         select! {
             connection_message = &mut mix_receiver.next() => {
                 if let Some(connection_message) = connection_message {
-                    if deal_with_message(connection_message, &mut writer, &local_destination_address, &remote_source_address, connection_id).await {
+                    if deal_with_message(connection_message, &mut writer, &local_destination_address, &remote_source_address, connection_id, &remote_window_sender, &delivered_sender, &mut delivered_bytes).await {
                         break;
                     }
                     mix_timeout.as_mut().reset(Instant::now() + MIX_TTL);
@@ -1,5 +1,72 @@
+use std::convert::TryFrom;
+
 pub mod request;
 pub mod response;
 
 pub use request::*;
 pub use response::*;
+
+/// Bit of the flags byte indicating that the socket on the sending side got closed.
+pub(crate) const CLOSED_FLAG: u8 = 0b01;
+
+/// Bit of the flags byte indicating that the message carries a flow control window.
+pub(crate) const RECEIVE_WINDOW_FLAG: u8 = 0b10;
+
+/// Version of the protocol spoken between the socks5 client and the service provider.
+///
+/// Service providers announce the newest version they understand in their pongs, while clients
+/// announce theirs when opening a connection. Both sides then use the older of the two for
+/// everything sent on that connection, so that peers that never announced anything
+/// are only ever sent frames they can understand.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProtocolVersion {
+    /// Original protocol, used with peers that do not announce their version.
+    Legacy = 0,
+
+    /// Adds error responses explaining why the service provider refused to handle a connection
+    /// and flow control windows advertised alongside the data.
+    V1 = 1,
+}
+
+/// The newest protocol version this side understands.
+pub const CURRENT_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::V1;
+
+impl ProtocolVersion {
+    /// Recovers the version announced by the remote. Versions we don't know about must be
+    /// newer than ours, in which case the best we can do is the newest one we understand.
+    pub(crate) fn from_announced(value: u8) -> ProtocolVersion {
+        ProtocolVersion::try_from(value).unwrap_or(CURRENT_PROTOCOL_VERSION)
+    }
+
+    /// Chooses the version to use with a remote announcing the specified version.
+    pub fn negotiate(remote: ProtocolVersion) -> ProtocolVersion {
+        remote.min(CURRENT_PROTOCOL_VERSION)
+    }
+
+    pub fn supports_error_responses(self) -> bool {
+        self >= ProtocolVersion::V1
+    }
+
+    pub fn supports_flow_control(self) -> bool {
+        self >= ProtocolVersion::V1
+    }
+}
+
+impl TryFrom<u8> for ProtocolVersion {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            _ if value == (ProtocolVersion::Legacy as u8) => Ok(Self::Legacy),
+            _ if value == (ProtocolVersion::V1 as u8) => Ok(Self::V1),
+            _ => Err(value),
+        }
+    }
+}
+
+impl Default for ProtocolVersion {
+    fn default() -> Self {
+        ProtocolVersion::Legacy
+    }
+}
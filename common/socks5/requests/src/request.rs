@@ -1,3 +1,4 @@
+use crate::{ProtocolVersion, CLOSED_FLAG, RECEIVE_WINDOW_FLAG};
 use nymsphinx_addressing::clients::{Recipient, RecipientFormattingError};
 use std::convert::TryFrom;
 use std::fmt::{self};
@@ -10,6 +11,8 @@ pub type RemoteAddress = String;
 pub enum RequestFlag {
     Connect = 0,
     Send = 1,
+    Ping = 2,
+    VersionedConnect = 3,
 }
 
 #[derive(Debug)]
@@ -21,6 +24,8 @@ pub enum RequestError {
     UnknownRequestFlag,
     ReturnAddressTooShort,
     MalformedReturnAddress(RecipientFormattingError),
+    ReceiveWindowTooShort,
+    ProtocolVersionMissing,
 }
 
 impl fmt::Display for RequestError {
@@ -39,6 +44,12 @@ impl fmt::Display for RequestError {
             RequestError::MalformedReturnAddress(recipient_err) => {
                 write!(f, "malformed return address - {}", recipient_err)
             }
+            RequestError::ReceiveWindowTooShort => {
+                write!(f, "not enough bytes to recover the receive window")
+            }
+            RequestError::ProtocolVersionMissing => {
+                write!(f, "not enough bytes to recover the protocol version")
+            }
         }
     }
 }
@@ -58,6 +69,8 @@ impl TryFrom<u8> for RequestFlag {
         match value {
             _ if value == (RequestFlag::Connect as u8) => Ok(Self::Connect),
             _ if value == (RequestFlag::Send as u8) => Ok(Self::Send),
+            _ if value == (RequestFlag::Ping as u8) => Ok(Self::Ping),
+            _ if value == (RequestFlag::VersionedConnect as u8) => Ok(Self::VersionedConnect),
             _ => Err(RequestError::UnknownRequestFlag),
         }
     }
@@ -70,14 +83,25 @@ pub enum Request {
     /// Start a new TCP connection to the specified `RemoteAddress` and send
     /// the request data up the connection.
     /// All responses produced on this `ConnectionId` should come back to the specified `Recipient`
+    /// using the protocol version agreed on with the client.
     Connect {
         conn_id: ConnectionId,
         remote_addr: RemoteAddress,
         return_address: Recipient,
+        protocol_version: ProtocolVersion,
     },
 
     /// Re-use an existing TCP connection, sending more request data up it.
-    Send(ConnectionId, Vec<u8>, bool),
+    /// Optionally it also includes the flow control window of the client, i.e. index of the
+    /// first ordered message on this connection it is not yet willing to accept.
+    Send(ConnectionId, Vec<u8>, bool, Option<u64>),
+
+    /// Health probe of the service provider. It should be answered with a pong response
+    /// carrying the same `ping_id`, sent to the specified `Recipient`.
+    Ping {
+        ping_id: u64,
+        return_address: Recipient,
+    },
 }
 
 impl Request {
@@ -86,17 +110,32 @@ impl Request {
         conn_id: ConnectionId,
         remote_addr: RemoteAddress,
         return_address: Recipient,
+        protocol_version: ProtocolVersion,
     ) -> Request {
         Request::Connect {
             conn_id,
             remote_addr,
             return_address,
+            protocol_version,
         }
     }
 
     /// Construct a new Request::Send instance
-    pub fn new_send(conn_id: ConnectionId, data: Vec<u8>, local_closed: bool) -> Request {
-        Request::Send(conn_id, data, local_closed)
+    pub fn new_send(
+        conn_id: ConnectionId,
+        data: Vec<u8>,
+        local_closed: bool,
+        receive_window: Option<u64>,
+    ) -> Request {
+        Request::Send(conn_id, data, local_closed, receive_window)
+    }
+
+    /// Construct a new Request::Ping instance
+    pub fn new_ping(ping_id: u64, return_address: Recipient) -> Request {
+        Request::Ping {
+            ping_id,
+            return_address,
+        }
     }
 
     /// Deserialize the request type, connection id, destination address and port,
@@ -112,6 +151,13 @@ impl Request {
     /// The request_flag tells us whether this is a new connection request (`new_connect`),
     /// an already-established connection we should send up (`new_send`), or
     /// a request to close an established connection (`new_close`).
+    ///
+    /// Connection requests of clients announcing any protocol version other than `Legacy` use
+    /// a separate flag and carry a single byte of the version right after the connection id,
+    /// as otherwise they'd be misinterpreted by older service providers.
+    ///
+    /// For `new_send`, instead of the address, there's a single byte of flags, indicating whether
+    /// the local socket got closed and whether the following 8 bytes contain the receive window.
     pub fn try_from_bytes(b: &[u8]) -> Result<Request, RequestError> {
         // each request needs to at least contain flag and ConnectionId
         if b.is_empty() {
@@ -122,9 +168,18 @@ impl Request {
             return Err(RequestError::ConnectionIdTooShort);
         }
         let connection_id = u64::from_be_bytes([b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8]]);
-        match RequestFlag::try_from(b[0])? {
-            RequestFlag::Connect => {
-                let connect_request_bytes = &b[9..];
+        let request_flag = RequestFlag::try_from(b[0])?;
+        match request_flag {
+            RequestFlag::Connect | RequestFlag::VersionedConnect => {
+                let (protocol_version, connect_request_bytes) =
+                    if let RequestFlag::VersionedConnect = request_flag {
+                        if b.len() < 10 {
+                            return Err(RequestError::ProtocolVersionMissing);
+                        }
+                        (ProtocolVersion::from_announced(b[9]), &b[10..])
+                    } else {
+                        (ProtocolVersion::Legacy, &b[9..])
+                    };
 
                 // we need to be able to read at least 2 bytes that specify address length
                 if connect_request_bytes.len() < 2 {
@@ -144,29 +199,47 @@ impl Request {
                 let address_bytes = &connect_request_bytes[address_start..address_end];
                 let remote_address = String::from_utf8_lossy(address_bytes).to_string();
 
-                // just a temporary reference to mid-slice for ease of use
-                let recipient_data_bytes = &connect_request_bytes[address_end..];
-
-                if recipient_data_bytes.len() != Recipient::LEN {
-                    return Err(RequestError::ReturnAddressTooShort);
-                }
-
-                let mut return_bytes = [0u8; Recipient::LEN];
-                return_bytes.copy_from_slice(&recipient_data_bytes[..Recipient::LEN]);
-                let return_address = Recipient::try_from_bytes(return_bytes)
-                    .map_err(RequestError::MalformedReturnAddress)?;
+                let return_address = parse_return_address(&connect_request_bytes[address_end..])?;
 
                 Ok(Request::Connect {
                     conn_id: connection_id,
                     remote_addr: remote_address,
                     return_address,
+                    protocol_version,
                 })
             }
             RequestFlag::Send => {
-                let local_closed = b[9] != 0;
-                let data = b[10..].as_ref().to_vec();
-
-                Ok(Request::Send(connection_id, data, local_closed))
+                if b.len() < 10 {
+                    return Err(RequestError::NoData);
+                }
+                let flags = b[9];
+                let local_closed = flags & CLOSED_FLAG != 0;
+                let (receive_window, data_start) = if flags & RECEIVE_WINDOW_FLAG != 0 {
+                    if b.len() < 18 {
+                        return Err(RequestError::ReceiveWindowTooShort);
+                    }
+                    let window = u64::from_be_bytes([
+                        b[10], b[11], b[12], b[13], b[14], b[15], b[16], b[17],
+                    ]);
+                    (Some(window), 18)
+                } else {
+                    (None, 10)
+                };
+                let data = b[data_start..].as_ref().to_vec();
+
+                Ok(Request::Send(
+                    connection_id,
+                    data,
+                    local_closed,
+                    receive_window,
+                ))
+            }
+            RequestFlag::Ping => {
+                let return_address = parse_return_address(&b[9..])?;
+                Ok(Request::Ping {
+                    ping_id: connection_id,
+                    return_address,
+                })
             }
         }
     }
@@ -177,30 +250,73 @@ impl Request {
     pub fn into_bytes(self) -> Vec<u8> {
         match self {
             // connect is: CONN_FLAG || CONN_ID || REMOTE_LEN || REMOTE || RETURN
+            // or, if any version is announced,
+            // VERSIONED_CONN_FLAG || CONN_ID || VERSION || REMOTE_LEN || REMOTE || RETURN
             Request::Connect {
                 conn_id,
                 remote_addr,
                 return_address,
+                protocol_version,
             } => {
                 let remote_address_bytes = remote_addr.into_bytes();
                 let remote_address_bytes_len = remote_address_bytes.len() as u16;
 
-                std::iter::once(RequestFlag::Connect as u8)
+                let (request_flag, announced_version) = match protocol_version {
+                    ProtocolVersion::Legacy => (RequestFlag::Connect, None),
+                    version => (RequestFlag::VersionedConnect, Some(version as u8)),
+                };
+
+                std::iter::once(request_flag as u8)
                     .chain(conn_id.to_be_bytes().iter().cloned())
+                    .chain(announced_version.into_iter())
                     .chain(remote_address_bytes_len.to_be_bytes().iter().cloned())
                     .chain(remote_address_bytes.into_iter())
                     .chain(return_address.to_bytes().iter().cloned())
                     .collect()
             }
-            Request::Send(conn_id, data, local_closed) => std::iter::once(RequestFlag::Send as u8)
-                .chain(conn_id.to_be_bytes().iter().cloned())
-                .chain(std::iter::once(local_closed as u8))
-                .chain(data.into_iter())
+            // send is: SEND_FLAG || CONN_ID || FLAGS || [RECEIVE_WINDOW] || DATA
+            Request::Send(conn_id, data, local_closed, receive_window) => {
+                let mut flags = 0;
+                if local_closed {
+                    flags |= CLOSED_FLAG
+                }
+                if receive_window.is_some() {
+                    flags |= RECEIVE_WINDOW_FLAG
+                }
+
+                std::iter::once(RequestFlag::Send as u8)
+                    .chain(conn_id.to_be_bytes().iter().cloned())
+                    .chain(std::iter::once(flags))
+                    .chain(
+                        receive_window
+                            .into_iter()
+                            .flat_map(|window| window.to_be_bytes().to_vec()),
+                    )
+                    .chain(data.into_iter())
+                    .collect()
+            }
+            // ping is: PING_FLAG || PING_ID || RETURN
+            Request::Ping {
+                ping_id,
+                return_address,
+            } => std::iter::once(RequestFlag::Ping as u8)
+                .chain(ping_id.to_be_bytes().iter().cloned())
+                .chain(return_address.to_bytes().iter().cloned())
                 .collect(),
         }
     }
 }
 
+fn parse_return_address(recipient_data_bytes: &[u8]) -> Result<Recipient, RequestError> {
+    if recipient_data_bytes.len() != Recipient::LEN {
+        return Err(RequestError::ReturnAddressTooShort);
+    }
+
+    let mut return_bytes = [0u8; Recipient::LEN];
+    return_bytes.copy_from_slice(&recipient_data_bytes[..Recipient::LEN]);
+    Recipient::try_from_bytes(return_bytes).map_err(RequestError::MalformedReturnAddress)
+}
+
 #[cfg(test)]
 mod request_deserialization_tests {
     use super::*;
@@ -377,6 +493,7 @@ mod request_deserialization_tests {
                     conn_id,
                     remote_addr,
                     return_address,
+                    protocol_version,
                 } => {
                     assert_eq!("foo.com".to_string(), remote_addr);
                     assert_eq!(u64::from_be_bytes([1, 2, 3, 4, 5, 6, 7, 8]), conn_id);
@@ -384,6 +501,7 @@ mod request_deserialization_tests {
                         return_address.to_bytes().to_vec(),
                         recipient.to_bytes().to_vec()
                     );
+                    assert_eq!(ProtocolVersion::Legacy, protocol_version);
                 }
                 _ => unreachable!(),
             }
@@ -428,6 +546,7 @@ mod request_deserialization_tests {
                     conn_id,
                     remote_addr,
                     return_address,
+                    protocol_version,
                 } => {
                     assert_eq!("foo.com".to_string(), remote_addr);
                     assert_eq!(u64::from_be_bytes([1, 2, 3, 4, 5, 6, 7, 8]), conn_id);
@@ -435,10 +554,65 @@ mod request_deserialization_tests {
                         return_address.to_bytes().to_vec(),
                         recipient.to_bytes().to_vec()
                     );
+                    assert_eq!(ProtocolVersion::Legacy, protocol_version);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn works_with_announced_protocol_version() {
+            let recipient = Recipient::try_from_base58_string("CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f").unwrap();
+            let request =
+                Request::new_connect(42, "foo.com".to_string(), recipient, ProtocolVersion::V1);
+            let request_bytes = request.into_bytes();
+            assert_eq!(RequestFlag::VersionedConnect as u8, request_bytes[0]);
+
+            match Request::try_from_bytes(&request_bytes).unwrap() {
+                Request::Connect {
+                    conn_id,
+                    remote_addr,
+                    return_address,
+                    protocol_version,
+                } => {
+                    assert_eq!(42, conn_id);
+                    assert_eq!("foo.com".to_string(), remote_addr);
+                    assert_eq!(
+                        return_address.to_bytes().to_vec(),
+                        recipient.to_bytes().to_vec()
+                    );
+                    assert_eq!(ProtocolVersion::V1, protocol_version);
                 }
                 _ => unreachable!(),
             }
         }
+
+        #[test]
+        fn legacy_requests_are_understood_by_older_providers() {
+            let recipient = Recipient::try_from_base58_string("CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f").unwrap();
+            let request = Request::new_connect(
+                42,
+                "foo.com".to_string(),
+                recipient,
+                ProtocolVersion::Legacy,
+            );
+            let request_bytes = request.into_bytes();
+            assert_eq!(RequestFlag::Connect as u8, request_bytes[0]);
+            assert_eq!(
+                9 + 2 + "foo.com".len() + Recipient::LEN,
+                request_bytes.len()
+            );
+        }
+
+        #[test]
+        fn returns_error_when_protocol_version_is_missing() {
+            let request_bytes =
+                [RequestFlag::VersionedConnect as u8, 1, 2, 3, 4, 5, 6, 7, 8].to_vec();
+            match Request::try_from_bytes(&request_bytes).unwrap_err() {
+                RequestError::ProtocolVersionMissing => {}
+                _ => unreachable!(),
+            }
+        }
     }
 
     #[cfg(test)]
@@ -451,10 +625,11 @@ mod request_deserialization_tests {
             let request_bytes = [RequestFlag::Send as u8, 1, 2, 3, 4, 5, 6, 7, 8, 0].to_vec();
             let request = Request::try_from_bytes(&request_bytes).unwrap();
             match request {
-                Request::Send(conn_id, data, local_closed) => {
+                Request::Send(conn_id, data, local_closed, receive_window) => {
                     assert_eq!(u64::from_be_bytes([1, 2, 3, 4, 5, 6, 7, 8]), conn_id);
                     assert_eq!(Vec::<u8>::new(), data);
-                    assert!(!local_closed)
+                    assert!(!local_closed);
+                    assert!(receive_window.is_none())
                 }
                 _ => unreachable!(),
             }
@@ -482,13 +657,85 @@ mod request_deserialization_tests {
 
             let request = Request::try_from_bytes(&request_bytes).unwrap();
             match request {
-                Request::Send(conn_id, data, local_closed) => {
+                Request::Send(conn_id, data, local_closed, receive_window) => {
                     assert_eq!(u64::from_be_bytes([1, 2, 3, 4, 5, 6, 7, 8]), conn_id);
                     assert_eq!(vec![255, 255, 255], data);
-                    assert!(!local_closed)
+                    assert!(!local_closed);
+                    assert!(receive_window.is_none())
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn works_with_receive_window() {
+            let request = Request::new_send(42, vec![1, 2, 3], true, Some(123));
+            match Request::try_from_bytes(&request.into_bytes()).unwrap() {
+                Request::Send(conn_id, data, local_closed, receive_window) => {
+                    assert_eq!(42, conn_id);
+                    assert_eq!(vec![1, 2, 3], data);
+                    assert!(local_closed);
+                    assert_eq!(Some(123), receive_window)
                 }
                 _ => unreachable!(),
             }
         }
+
+        #[test]
+        fn returns_error_when_receive_window_is_too_short() {
+            let request_bytes = [
+                RequestFlag::Send as u8,
+                1,
+                2,
+                3,
+                4,
+                5,
+                6,
+                7,
+                8,
+                RECEIVE_WINDOW_FLAG,
+                1,
+                2,
+            ]
+            .to_vec();
+            match Request::try_from_bytes(&request_bytes).unwrap_err() {
+                RequestError::ReceiveWindowTooShort => {}
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod probing_the_service_provider {
+        use super::*;
+
+        #[test]
+        fn works_when_request_is_sized_properly() {
+            let recipient = Recipient::try_from_base58_string("CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f").unwrap();
+            let request = Request::new_ping(42, recipient);
+
+            match Request::try_from_bytes(&request.into_bytes()).unwrap() {
+                Request::Ping {
+                    ping_id,
+                    return_address,
+                } => {
+                    assert_eq!(42, ping_id);
+                    assert_eq!(
+                        return_address.to_bytes().to_vec(),
+                        recipient.to_bytes().to_vec()
+                    );
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn returns_error_when_return_address_is_missing() {
+            let request_bytes = [RequestFlag::Ping as u8, 1, 2, 3, 4, 5, 6, 7, 8].to_vec();
+            match Request::try_from_bytes(&request_bytes).unwrap_err() {
+                RequestError::ReturnAddressTooShort => {}
+                _ => unreachable!(),
+            }
+        }
     }
 }
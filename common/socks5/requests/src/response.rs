@@ -1,10 +1,24 @@
-use crate::ConnectionId;
+use crate::{ConnectionId, ProtocolVersion, CLOSED_FLAG, RECEIVE_WINDOW_FLAG};
+
+/// Value of the first byte of a serialized response indicating that the service provider
+/// refused to (further) handle the connection.
+/// Older clients would treat it as the connection being closed and write the error message
+/// into the application's socket, so it must only be sent to clients that announced
+/// support for error responses.
+const ERROR_RESPONSE_FLAG: u8 = 0xFF;
+
+/// Value of the first byte of a serialized response indicating it's an answer to a ping request.
+/// In that case the connection id field holds the id of the ping and it's followed by
+/// the protocol version of the service provider.
+const PONG_RESPONSE_FLAG: u8 = 0xFE;
 
 #[derive(Debug, PartialEq)]
 pub enum ResponseError {
     ConnectionIdTooShort,
     NoData,
+    ReceiveWindowTooShort,
 }
+
 /// A remote network response retrieved by the Socks5 service provider. This
 /// can be serialized and sent back through the mixnet to the requesting
 /// application.
@@ -13,6 +27,21 @@ pub struct Response {
     pub data: Vec<u8>,
     pub connection_id: ConnectionId,
     pub is_closed: bool,
+
+    /// Explanation of why the service provider refused to handle the connection.
+    /// If set, `data` is always empty and the connection should be treated as closed.
+    pub error: Option<String>,
+
+    /// Flow control window advertised by the service provider, i.e. index of the first
+    /// ordered message on this connection it is not yet willing to accept.
+    pub receive_window: Option<u64>,
+
+    /// Indicates this is a response to a health probe, in which case `connection_id`
+    /// holds the id of the ping that is being answered.
+    pub is_pong: bool,
+
+    /// Newest protocol version the service provider understands. Only announced in pongs.
+    pub protocol_version: ProtocolVersion,
 }
 
 impl Response {
@@ -22,43 +51,147 @@ impl Response {
             data,
             connection_id,
             is_closed,
+            error: None,
+            receive_window: None,
+            is_pong: false,
+            protocol_version: ProtocolVersion::Legacy,
+        }
+    }
+
+    /// Attaches the flow control window of the service provider to the response.
+    pub fn with_receive_window(mut self, receive_window: u64) -> Self {
+        self.receive_window = Some(receive_window);
+        self
+    }
+
+    /// Constructor for responses informing the remote that the connection was refused
+    /// or forcefully terminated by the service provider.
+    pub fn new_error(connection_id: ConnectionId, message: String) -> Self {
+        Response {
+            data: Vec::new(),
+            connection_id,
+            is_closed: true,
+            error: Some(message),
+            receive_window: None,
+            is_pong: false,
+            protocol_version: ProtocolVersion::Legacy,
+        }
+    }
+
+    /// Constructor for responses informing the remote, using the specified protocol version,
+    /// that the connection was refused or forcefully terminated by the service provider.
+    /// Clients that do not understand error responses are only told the connection got closed.
+    pub fn new_refusal(
+        connection_id: ConnectionId,
+        message: String,
+        protocol_version: ProtocolVersion,
+    ) -> Self {
+        if protocol_version.supports_error_responses() {
+            Response::new_error(connection_id, message)
+        } else {
+            Response::new(connection_id, Vec::new(), true)
         }
     }
 
+    /// Constructor for responses to health probes of the service provider.
+    pub fn new_pong(ping_id: u64, protocol_version: ProtocolVersion) -> Self {
+        Response {
+            data: Vec::new(),
+            connection_id: ping_id,
+            is_closed: false,
+            error: None,
+            receive_window: None,
+            is_pong: true,
+            protocol_version,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.error.is_some()
+    }
+
     pub fn try_from_bytes(b: &[u8]) -> Result<Response, ResponseError> {
         if b.is_empty() {
             return Err(ResponseError::NoData);
         }
 
-        let is_closed = b[0] != 0;
+        let flags = b[0];
 
         if b.len() < 9 {
             return Err(ResponseError::ConnectionIdTooShort);
         }
 
-        let mut connection_id_bytes = b.to_vec();
-        let data = connection_id_bytes.split_off(9);
-
-        let connection_id = u64::from_be_bytes([
-            connection_id_bytes[1],
-            connection_id_bytes[2],
-            connection_id_bytes[3],
-            connection_id_bytes[4],
-            connection_id_bytes[5],
-            connection_id_bytes[6],
-            connection_id_bytes[7],
-            connection_id_bytes[8],
-        ]);
-
-        let response = Response::new(connection_id, data, is_closed);
+        let connection_id = u64::from_be_bytes([b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8]]);
+
+        if flags == ERROR_RESPONSE_FLAG {
+            let message = String::from_utf8_lossy(&b[9..]).to_string();
+            return Ok(Response::new_error(connection_id, message));
+        }
+
+        if flags == PONG_RESPONSE_FLAG {
+            let protocol_version = b
+                .get(9)
+                .map(|version| ProtocolVersion::from_announced(*version))
+                .unwrap_or_default();
+            return Ok(Response::new_pong(connection_id, protocol_version));
+        }
+
+        let is_closed = flags & CLOSED_FLAG != 0;
+        let (receive_window, data_start) = if flags & RECEIVE_WINDOW_FLAG != 0 {
+            if b.len() < 17 {
+                return Err(ResponseError::ReceiveWindowTooShort);
+            }
+            let window =
+                u64::from_be_bytes([b[9], b[10], b[11], b[12], b[13], b[14], b[15], b[16]]);
+            (Some(window), 17)
+        } else {
+            (None, 9)
+        };
+
+        let mut response = Response::new(connection_id, b[data_start..].to_vec(), is_closed);
+        response.receive_window = receive_window;
         Ok(response)
     }
 
     /// Serializes the response into bytes so that it can be sent back through
     /// the mixnet to the requesting application.
+    ///
+    /// Serialized bytes look like this:
+    ///
+    /// ----------------------------------------------------------------
+    ///  flags | connection_id | receive_window (optional) | data      |
+    ///    1   |       8       |            8              |    ...    |
+    /// ----------------------------------------------------------------
     pub fn into_bytes(self) -> Vec<u8> {
-        std::iter::once(self.is_closed as u8)
+        if let Some(error) = self.error {
+            return std::iter::once(ERROR_RESPONSE_FLAG)
+                .chain(self.connection_id.to_be_bytes().iter().cloned())
+                .chain(error.into_bytes().into_iter())
+                .collect();
+        }
+
+        if self.is_pong {
+            return std::iter::once(PONG_RESPONSE_FLAG)
+                .chain(self.connection_id.to_be_bytes().iter().cloned())
+                .chain(std::iter::once(self.protocol_version as u8))
+                .collect();
+        }
+
+        let mut flags = 0;
+        if self.is_closed {
+            flags |= CLOSED_FLAG
+        }
+        if self.receive_window.is_some() {
+            flags |= RECEIVE_WINDOW_FLAG
+        }
+
+        std::iter::once(flags)
             .chain(self.connection_id.to_be_bytes().iter().cloned())
+            .chain(
+                self.receive_window
+                    .into_iter()
+                    .flat_map(|window| window.to_be_bytes().to_vec()),
+            )
             .chain(self.data.into_iter())
             .collect()
     }
@@ -114,4 +247,77 @@ mod constructing_socks5_responses_from_bytes {
         assert_eq!(expected.data, actual.data);
         assert_eq!(expected.is_closed, actual.is_closed);
     }
+
+    #[test]
+    fn works_with_receive_window() {
+        let response = Response::new(42, vec![1, 2, 3], false).with_receive_window(123);
+        let actual = Response::try_from_bytes(&response.into_bytes()).unwrap();
+        assert_eq!(42, actual.connection_id);
+        assert_eq!(vec![1, 2, 3], actual.data);
+        assert!(!actual.is_closed);
+        assert_eq!(Some(123), actual.receive_window);
+    }
+
+    #[test]
+    fn fails_when_receive_window_is_too_short() {
+        let response_bytes = vec![RECEIVE_WINDOW_FLAG, 0, 1, 2, 3, 4, 5, 6, 7, 1, 2, 3];
+        assert_eq!(
+            ResponseError::ReceiveWindowTooShort,
+            Response::try_from_bytes(&response_bytes).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn works_for_error_responses() {
+        let response = Response::new_error(42, "too many connections".to_string());
+        let actual = Response::try_from_bytes(&response.into_bytes()).unwrap();
+        assert_eq!(42, actual.connection_id);
+        assert!(actual.data.is_empty());
+        assert!(actual.is_closed);
+        assert_eq!(Some("too many connections".to_string()), actual.error);
+    }
+
+    #[test]
+    fn refusals_are_plain_closes_for_legacy_clients() {
+        let response = Response::new_refusal(
+            42,
+            "too many connections".to_string(),
+            ProtocolVersion::Legacy,
+        );
+        // exactly what older clients expect when the remote closes the connection
+        assert_eq!(
+            vec![CLOSED_FLAG, 0, 0, 0, 0, 0, 0, 0, 42],
+            response.into_bytes()
+        );
+
+        let response =
+            Response::new_refusal(42, "too many connections".to_string(), ProtocolVersion::V1);
+        assert!(response.is_error());
+    }
+
+    #[test]
+    fn works_for_pong_responses() {
+        let pong = Response::new_pong(42, ProtocolVersion::V1);
+        let actual = Response::try_from_bytes(&pong.into_bytes()).unwrap();
+        assert_eq!(42, actual.connection_id);
+        assert!(actual.is_pong);
+        assert!(!actual.is_closed);
+        assert!(!actual.is_error());
+        assert_eq!(ProtocolVersion::V1, actual.protocol_version);
+    }
+
+    #[test]
+    fn pongs_of_unknown_versions_are_treated_as_current() {
+        let response_bytes = vec![PONG_RESPONSE_FLAG, 0, 0, 0, 0, 0, 0, 0, 42, 255];
+        let actual = Response::try_from_bytes(&response_bytes).unwrap();
+        assert_eq!(crate::CURRENT_PROTOCOL_VERSION, actual.protocol_version);
+    }
+
+    #[test]
+    fn regular_responses_are_not_errors() {
+        let response_bytes = vec![1, 0, 1, 2, 3, 4, 5, 6, 7, 255, 255, 255];
+        let actual = Response::try_from_bytes(&response_bytes).unwrap();
+        assert!(actual.is_closed);
+        assert!(!actual.is_error());
+    }
 }
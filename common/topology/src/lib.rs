@@ -26,6 +26,7 @@ pub enum NymTopologyError {
 
     InvalidNumberOfHopsError,
     NoMixesOnLayerAvailable(MixLayer),
+    NoUnrelatedMixesOnLayerAvailable(MixLayer),
 }
 
 #[derive(Debug, Clone)]
@@ -115,15 +116,14 @@ impl NymTopology {
         self.gateways = gateways
     }
 
-    /// Returns a vec of size of `num_mix_hops` of mixnodes, such that each subsequent node is on
-    /// next layer, starting from layer 1
-    pub fn random_mix_route<R>(
+    // no two chosen nodes belong to the same family or are located in the same subnet,
+    // as a single operator controlling multiple hops could link the sender and the receiver
+    fn random_unrelated_mixes<R>(
         &self,
         rng: &mut R,
         num_mix_hops: u8,
-    ) -> Result<Vec<SphinxNode>, NymTopologyError>
+    ) -> Result<Vec<&mix::Node>, NymTopologyError>
     where
-        // I don't think there's a need for this RNG to be crypto-secure
         R: Rng + ?Sized,
     {
         use rand::seq::SliceRandom;
@@ -131,7 +131,7 @@ impl NymTopology {
         if self.mixes.len() < num_mix_hops as usize {
             return Err(NymTopologyError::InvalidNumberOfHopsError);
         }
-        let mut route = Vec::with_capacity(num_mix_hops as usize);
+        let mut route: Vec<&mix::Node> = Vec::with_capacity(num_mix_hops as usize);
 
         // there is no "layer 0"
         for layer in 1..=num_mix_hops {
@@ -140,18 +140,45 @@ impl NymTopology {
                 .mixes
                 .get(&layer)
                 .ok_or(NymTopologyError::NoMixesOnLayerAvailable(layer))?;
+            if layer_mixes.is_empty() {
+                return Err(NymTopologyError::NoMixesOnLayerAvailable(layer));
+            }
+
+            let unrelated_mixes = layer_mixes
+                .iter()
+                .filter(|mix| !route.iter().any(|chosen| chosen.is_related_to(mix)))
+                .collect::<Vec<_>>();
 
             // choose a random mix from the above list
-            // this can return a 'None' only if slice is empty
-            let random_mix = layer_mixes
+            // this can return a 'None' only if there are no mixes unrelated to the already chosen ones
+            let random_mix = unrelated_mixes
                 .choose(rng)
-                .ok_or(NymTopologyError::NoMixesOnLayerAvailable(layer))?;
-            route.push(random_mix.into());
+                .ok_or(NymTopologyError::NoUnrelatedMixesOnLayerAvailable(layer))?;
+            route.push(*random_mix);
         }
 
         Ok(route)
     }
 
+    /// Returns a vec of size of `num_mix_hops` of mixnodes, such that each subsequent node is on
+    /// next layer, starting from layer 1. No two nodes on the route belong to the same family
+    /// or are located in the same subnet.
+    pub fn random_mix_route<R>(
+        &self,
+        rng: &mut R,
+        num_mix_hops: u8,
+    ) -> Result<Vec<SphinxNode>, NymTopologyError>
+    where
+        // I don't think there's a need for this RNG to be crypto-secure
+        R: Rng + ?Sized,
+    {
+        Ok(self
+            .random_unrelated_mixes(rng, num_mix_hops)?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
     /// Tries to create a route to the specified gateway, such that it goes through mixnode on layer 1,
     /// mixnode on layer2, .... mixnode on layer n and finally the target gateway
     pub fn random_route_to_gateway<R>(
@@ -291,6 +318,7 @@ mod converting_mixes_to_vec {
                 )
                 .unwrap(),
                 layer: Layer::One,
+                family: None,
                 version: "0.x.0".to_string(),
             };
 
@@ -326,3 +354,87 @@ mod converting_mixes_to_vec {
         }
     }
 }
+
+#[cfg(test)]
+mod choosing_random_mix_route {
+    use super::*;
+    use crypto::asymmetric::{encryption, identity};
+    use mixnet_contract::Layer;
+
+    fn mix_fixture(layer: Layer, ip: &str, family: Option<&str>) -> mix::Node {
+        mix::Node {
+            owner: "N/A".to_string(),
+            stake: 0,
+            delegation: 0,
+            host: ip.parse().unwrap(),
+            mix_host: format!("{}:1789", ip).parse().unwrap(),
+            identity_key: identity::PublicKey::from_base58_string(
+                "3ebjp1Fb9hdcS1AR6AZihgeJiMHkB5jjJUsvqNnfQwU7",
+            )
+            .unwrap(),
+            sphinx_key: encryption::PublicKey::from_base58_string(
+                "C7cown6dYCLZpLiMFC1PaBmhvLvmJmLDJGeRTbPD45bX",
+            )
+            .unwrap(),
+            layer,
+            family: family.map(ToString::to_string),
+            version: "0.x.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn never_picks_nodes_from_same_family_or_subnet() {
+        let mut mixes = HashMap::new();
+        mixes.insert(1, vec![mix_fixture(Layer::One, "1.1.1.1", Some("family"))]);
+        mixes.insert(
+            2,
+            vec![
+                mix_fixture(Layer::Two, "2.2.2.2", Some("family")),
+                mix_fixture(Layer::Two, "3.3.3.3", Some("other-family")),
+            ],
+        );
+        mixes.insert(
+            3,
+            vec![
+                mix_fixture(Layer::Three, "1.1.4.4", None),
+                mix_fixture(Layer::Three, "5.5.5.5", None),
+            ],
+        );
+        let topology = NymTopology::new(mixes, vec![]);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let route = topology.random_unrelated_mixes(&mut rng, 3).unwrap();
+            let hosts = route
+                .iter()
+                .map(|mix| mix.mix_host.to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(vec!["1.1.1.1:1789", "3.3.3.3:1789", "5.5.5.5:1789"], hosts);
+        }
+    }
+
+    #[test]
+    fn fails_if_only_related_nodes_are_available() {
+        let mut mixes = HashMap::new();
+        mixes.insert(1, vec![mix_fixture(Layer::One, "1.1.1.1", None)]);
+        mixes.insert(2, vec![mix_fixture(Layer::Two, "1.1.2.2", None)]);
+        let topology = NymTopology::new(mixes, vec![]);
+
+        assert!(matches!(
+            topology.random_mix_route(&mut rand::thread_rng(), 2),
+            Err(NymTopologyError::NoUnrelatedMixesOnLayerAvailable(2))
+        ));
+    }
+
+    #[test]
+    fn local_addresses_are_not_considered_related() {
+        let mut mixes = HashMap::new();
+        mixes.insert(1, vec![mix_fixture(Layer::One, "127.0.0.1", None)]);
+        mixes.insert(2, vec![mix_fixture(Layer::Two, "127.0.0.1", None)]);
+        let topology = NymTopology::new(mixes, vec![]);
+
+        assert!(topology
+            .random_mix_route(&mut rand::thread_rng(), 2)
+            .is_ok());
+    }
+}
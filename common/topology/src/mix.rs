@@ -9,7 +9,7 @@ use nymsphinx_types::Node as SphinxNode;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::{self, Display, Formatter};
 use std::io;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 #[derive(Debug)]
 pub enum MixnodeConversionError {
@@ -80,9 +80,37 @@ pub struct Node {
     pub identity_key: identity::PublicKey,
     pub sphinx_key: encryption::PublicKey, // TODO: or nymsphinx::PublicKey? both are x25519
     pub layer: Layer,
+    // identity of the head of the family of nodes run by the same operator
+    pub family: Option<String>,
     pub version: String,
 }
 
+impl Node {
+    // nodes in the same /16 network (or /32 for IPv6) are likely to be run by the same entity.
+    // Loopback and private addresses are ignored, as those are only used in local deployments.
+    fn network_prefix(&self) -> Option<Vec<u8>> {
+        match self.mix_host.ip() {
+            IpAddr::V4(ip) if ip.is_loopback() || ip.is_private() => None,
+            IpAddr::V4(ip) => Some(ip.octets()[..2].to_vec()),
+            IpAddr::V6(ip) if ip.is_loopback() => None,
+            IpAddr::V6(ip) => Some(ip.octets()[..4].to_vec()),
+        }
+    }
+
+    /// Checks whether both nodes are likely to be run by the same operator, i.e. they either
+    /// belong to the same family or are located in the same subnet.
+    pub fn is_related_to(&self, other: &Node) -> bool {
+        if self.family.is_some() && self.family == other.family {
+            return true;
+        }
+
+        match (self.network_prefix(), other.network_prefix()) {
+            (Some(prefix), Some(other_prefix)) => prefix == other_prefix,
+            _ => false,
+        }
+    }
+}
+
 impl filter::Versioned for Node {
     fn version(&self) -> String {
         self.version.clone()
@@ -124,6 +152,7 @@ impl<'a> TryFrom<&'a MixNodeBond> for Node {
             identity_key: identity::PublicKey::from_base58_string(&bond.mix_node.identity_key)?,
             sphinx_key: encryption::PublicKey::from_base58_string(&bond.mix_node.sphinx_key)?,
             layer: bond.layer,
+            family: bond.family.clone(),
             version: bond.mix_node.version.clone(),
         })
     }
@@ -3,14 +3,17 @@
 
 use crate::helpers::calculate_epoch_reward_rate;
 use crate::state::State;
-use crate::storage::{config, layer_distribution};
-use crate::{error::ContractError, queries, transactions};
+use crate::storage::{config, governance, layer_distribution};
+use crate::{error::ContractError, migrations, queries, transactions};
 use config::defaults::NETWORK_MONITOR_ADDRESS;
 use cosmwasm_std::{
     entry_point, to_binary, Addr, Decimal, Deps, DepsMut, Env, MessageInfo, QueryResponse,
     Response, Uint128,
 };
-use mixnet_contract::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, StateParams};
+use mixnet_contract::events;
+use mixnet_contract::{
+    ExecuteMsg, GovernanceConfig, InstantiateMsg, MigrateMsg, QueryMsg, StateParams,
+};
 
 pub const INITIAL_DEFAULT_EPOCH_LENGTH: u32 = 2;
 
@@ -28,6 +31,34 @@ pub const INITIAL_GATEWAY_DELEGATION_REWARD_RATE: u64 = 110;
 
 pub const INITIAL_MIXNODE_ACTIVE_SET_SIZE: u32 = 100;
 
+/// Constant specifying, in hours, how long the unbonded stake is locked before it can be withdrawn
+pub const INITIAL_UNBONDING_PERIOD: u32 = 7 * 24;
+
+/// Constant specifying, in percents, the uptime below which a mixnode is reported as misbehaving
+pub const INITIAL_MINIMUM_MIXNODE_UPTIME: u32 = 50;
+/// Constant specifying number of consecutive epochs of misbehaviour after which a mixnode gets jailed
+pub const INITIAL_MIXNODE_JAIL_EPOCHS: u32 = 3;
+// by default the jailed mixnodes do not lose any of their bond
+pub const INITIAL_MIXNODE_SLASHING_PERCENT: u64 = 0;
+
+/// Constant specifying, in hours, for how long the governance members can vote on a proposal
+pub const INITIAL_PROPOSAL_VOTING_PERIOD: u32 = 7 * 24;
+/// Constant specifying, in hours, how long a passed proposal has to wait before it can be executed
+pub const INITIAL_PROPOSAL_TIMELOCK: u32 = 24;
+/// Constant specifying, in hours, for how long after its timelock a passed proposal can be executed
+pub const INITIAL_PROPOSAL_EXECUTION_PERIOD: u32 = 7 * 24;
+
+// initially the owner is the only member of the governance, until it decides otherwise
+pub(crate) fn default_governance_config(owner: Addr) -> GovernanceConfig {
+    GovernanceConfig {
+        members: vec![owner],
+        threshold: 1,
+        voting_period: INITIAL_PROPOSAL_VOTING_PERIOD,
+        timelock: INITIAL_PROPOSAL_TIMELOCK,
+        execution_period: INITIAL_PROPOSAL_EXECUTION_PERIOD,
+    }
+}
+
 fn default_initial_state(owner: Addr) -> State {
     let mixnode_bond_reward_rate = Decimal::percent(INITIAL_MIXNODE_BOND_REWARD_RATE);
     let gateway_bond_reward_rate = Decimal::percent(INITIAL_GATEWAY_BOND_REWARD_RATE);
@@ -46,6 +77,10 @@ fn default_initial_state(owner: Addr) -> State {
             mixnode_delegation_reward_rate,
             gateway_delegation_reward_rate,
             mixnode_active_set_size: INITIAL_MIXNODE_ACTIVE_SET_SIZE,
+            unbonding_period: INITIAL_UNBONDING_PERIOD,
+            minimum_mixnode_uptime: INITIAL_MINIMUM_MIXNODE_UPTIME,
+            mixnode_jail_epochs: INITIAL_MIXNODE_JAIL_EPOCHS,
+            mixnode_slashing_fraction: Decimal::percent(INITIAL_MIXNODE_SLASHING_PERCENT),
         },
         mixnode_epoch_bond_reward: calculate_epoch_reward_rate(
             INITIAL_DEFAULT_EPOCH_LENGTH,
@@ -78,10 +113,12 @@ pub fn instantiate(
     info: MessageInfo,
     _msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    let state = default_initial_state(info.sender);
+    let state = default_initial_state(info.sender.clone());
 
     config(deps.storage).save(&state)?;
+    governance(deps.storage).save(&default_governance_config(info.sender))?;
     layer_distribution(deps.storage).save(&Default::default())?;
+    migrations::set_contract_version(deps.storage)?;
     Ok(Response::default())
 }
 
@@ -89,17 +126,77 @@ pub fn instantiate(
 #[entry_point]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::BondMixnode { mix_node } => transactions::try_add_mixnode(deps, info, mix_node),
-        ExecuteMsg::UnbondMixnode {} => transactions::try_remove_mixnode(deps, info),
+        ExecuteMsg::BondMixnode {
+            mix_node,
+            profit_margin_percent,
+        } => transactions::try_add_mixnode(deps, info, mix_node, profit_margin_percent),
+        ExecuteMsg::UnbondMixnode {} => transactions::try_remove_mixnode(deps, env, info),
+        ExecuteMsg::UpdateMixnodeConfig {
+            host,
+            mix_port,
+            verloc_port,
+            http_api_port,
+            version,
+        } => transactions::try_update_mixnode_config(
+            deps,
+            info,
+            host,
+            mix_port,
+            verloc_port,
+            http_api_port,
+            version,
+        ),
+        ExecuteMsg::UpdateMixnodeProfitMargin {
+            profit_margin_percent,
+        } => transactions::try_update_mixnode_profit_margin(deps, info, profit_margin_percent),
+        ExecuteMsg::ClaimOperatorReward { compound } => {
+            transactions::try_claim_operator_reward(deps, info, compound)
+        }
+        ExecuteMsg::CreateFamily {} => transactions::try_create_family(deps, info),
+        ExecuteMsg::JoinFamily {
+            family_head,
+            signature,
+        } => transactions::try_join_family(deps, env, info, family_head, signature),
+        ExecuteMsg::LeaveFamily {} => transactions::try_leave_family(deps, info),
+        ExecuteMsg::UnjailMixnode {} => transactions::try_unjail_mixnode(deps, env, info),
         ExecuteMsg::BondGateway { gateway } => transactions::try_add_gateway(deps, info, gateway),
-        ExecuteMsg::UnbondGateway {} => transactions::try_remove_gateway(deps, info),
-        ExecuteMsg::UpdateStateParams(params) => {
-            transactions::try_update_state_params(deps, info, params)
+        ExecuteMsg::UnbondGateway {} => transactions::try_remove_gateway(deps, env, info),
+        ExecuteMsg::UpdateGatewayConfig {
+            host,
+            mix_port,
+            clients_port,
+            location,
+            version,
+        } => transactions::try_update_gateway_config(
+            deps,
+            info,
+            host,
+            mix_port,
+            clients_port,
+            location,
+            version,
+        ),
+        ExecuteMsg::ProposeStateParams {
+            params,
+            description,
+        } => transactions::try_propose_state_params(deps, env, info, params, description),
+        ExecuteMsg::ProposeGovernanceConfig {
+            config,
+            description,
+        } => transactions::try_propose_governance_config(deps, env, info, config, description),
+        ExecuteMsg::VoteOnProposal { proposal_id, vote } => {
+            transactions::try_vote_on_proposal(deps, env, info, proposal_id, vote)
+        }
+        ExecuteMsg::ExecuteProposal { proposal_id } => {
+            transactions::try_execute_proposal(deps, env, proposal_id)
+        }
+        ExecuteMsg::CancelProposal { proposal_id } => {
+            transactions::try_cancel_proposal(deps, info, proposal_id)
         }
         ExecuteMsg::RewardMixnode { identity, uptime } => {
             transactions::try_reward_mixnode(deps, info, identity, uptime)
@@ -107,17 +204,31 @@ pub fn execute(
         ExecuteMsg::RewardGateway { identity, uptime } => {
             transactions::try_reward_gateway(deps, info, identity, uptime)
         }
+        ExecuteMsg::AdvanceEpoch {} => transactions::try_advance_epoch(deps, env, info),
+        ExecuteMsg::ReportMisbehaviour { identity, uptime } => {
+            transactions::try_report_misbehaviour(deps, env, info, identity, uptime)
+        }
+        ExecuteMsg::WithdrawSlashedFunds { recipient } => {
+            transactions::try_withdraw_slashed_funds(deps, info, recipient)
+        }
         ExecuteMsg::DelegateToMixnode { mix_identity } => {
             transactions::try_delegate_to_mixnode(deps, info, mix_identity)
         }
         ExecuteMsg::UndelegateFromMixnode { mix_identity } => {
-            transactions::try_remove_delegation_from_mixnode(deps, info, mix_identity)
+            transactions::try_remove_delegation_from_mixnode(deps, env, info, mix_identity)
+        }
+        ExecuteMsg::ClaimDelegatorReward {
+            mix_identity,
+            compound,
+        } => transactions::try_claim_delegator_reward(deps, info, mix_identity, compound),
+        ExecuteMsg::ClaimPendingWithdrawals { limit } => {
+            transactions::try_claim_pending_withdrawals(deps, env, info, limit)
         }
         ExecuteMsg::DelegateToGateway { gateway_identity } => {
             transactions::try_delegate_to_gateway(deps, info, gateway_identity)
         }
         ExecuteMsg::UndelegateFromGateway { gateway_identity } => {
-            transactions::try_remove_delegation_from_gateway(deps, info, gateway_identity)
+            transactions::try_remove_delegation_from_gateway(deps, env, info, gateway_identity)
         }
     }
 }
@@ -175,14 +286,87 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, Cont
             gateway_identity,
             address,
         )?),
+        QueryMsg::GetDelegatorMixDelegations {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&queries::query_delegator_mix_delegations_paged(
+            deps,
+            address,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetDelegatorGatewayDelegations {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&queries::query_delegator_gateway_delegations_paged(
+            deps,
+            address,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetPendingWithdrawals {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&queries::query_pending_withdrawals_paged(
+            deps,
+            address,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetEpoch {} => to_binary(&queries::query_epoch(deps)?),
+        QueryMsg::GetActiveSet { start_after, limit } => {
+            to_binary(&queries::query_active_set_paged(deps, start_after, limit)?)
+        }
+        QueryMsg::GetJailedMixNodes { start_after, limit } => to_binary(
+            &queries::query_jailed_mixnodes_paged(deps, start_after, limit)?,
+        ),
+        QueryMsg::GetMixnodeMisbehaviour {
+            identity,
+            start_after,
+            limit,
+        } => to_binary(&queries::query_mixnode_misbehaviour_paged(
+            deps,
+            identity,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetSlashedFunds {} => to_binary(&queries::query_slashed_funds(deps)?),
+        QueryMsg::GetContractVersion {} => to_binary(&queries::query_contract_version(deps)?),
+        QueryMsg::GetGovernanceConfig {} => to_binary(&queries::query_governance_config(deps)?),
+        QueryMsg::GetProposal { proposal_id } => {
+            to_binary(&queries::query_proposal(deps, proposal_id)?)
+        }
+        QueryMsg::GetProposals { start_after, limit } => {
+            to_binary(&queries::query_proposals_paged(deps, start_after, limit)?)
+        }
+        QueryMsg::GetProposalVotes {
+            proposal_id,
+            start_after,
+            limit,
+        } => to_binary(&queries::query_proposal_votes_paged(
+            deps,
+            proposal_id,
+            start_after,
+            limit,
+        )?),
     };
 
     Ok(query_res?)
 }
 
 #[entry_point]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Default::default())
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous_version = migrations::migrate_contract_state(deps.storage)?;
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes: events::new_migration_event(previous_version, migrations::CONTRACT_VERSION),
+        data: None,
+    })
 }
 
 #[cfg(test)]
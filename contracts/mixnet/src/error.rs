@@ -57,6 +57,20 @@ pub enum ContractError {
     #[error("The delegation reward rate for gateway was set to be lower than 1")]
     DecreasingGatewayDelegationReward,
 
+    #[error("Minimum mixnode uptime must be a percentage value in range 0-100. Got {value}")]
+    InvalidUptimeThreshold { value: u32 },
+
+    #[error(
+        "Mixnodes must be allowed to misbehave for at least a single epoch before getting jailed"
+    )]
+    ZeroJailEpochs,
+
+    #[error("The slashing fraction was set to be greater than 1")]
+    InvalidSlashingFraction,
+
+    #[error("Mixnode active set size must be in range 1-{max}. Got {value}")]
+    InvalidActiveSetSize { value: u32, max: u32 },
+
     #[error("The node had uptime larger than 100%")]
     UnexpectedUptime,
 
@@ -75,9 +89,143 @@ pub enum ContractError {
     #[error("No funds were provided for the delegation")]
     EmptyDelegation,
 
+    #[error("No funds should be sent alongside the node configuration update")]
+    UnexpectedConfigUpdateFunds,
+
     #[error("Request did not come from the node owner ({owner})")]
     InvalidSender { owner: Addr },
 
+    #[error("Node host must be a non-empty string of at most {max} characters without whitespace. Got {host:?}")]
+    InvalidNodeHost { host: String, max: usize },
+
+    #[error("Node version must be a non-empty string of at most {max} alphanumeric, '.', '-' or '+' characters. Got {version:?}")]
+    InvalidNodeVersion { version: String, max: usize },
+
+    #[error("Gateway location can't be longer than {max} characters. Got {location:?}")]
+    InvalidGatewayLocation { location: String, max: usize },
+
+    #[error(
+        "Node {key_name} key must be a non-empty string of at most {max} characters. Got {key:?}"
+    )]
+    InvalidNodeKey {
+        key_name: String,
+        key: String,
+        max: usize,
+    },
+
+    #[error("The {port_name} port of the node must not be 0")]
+    ZeroNodePort { port_name: String },
+
+    #[error("Profit margin must be a percentage value in range 0-100. Got {value}")]
+    InvalidProfitMargin { value: u8 },
+
+    #[error("Profit margin can't change by more than {max} percentage points at once. Attempted to change it from {old} to {new}")]
+    ProfitMarginChangeTooLarge { old: u8, new: u8, max: u8 },
+
+    #[error("Profit margin has already been updated in epoch {epoch}. It can be updated again from epoch {next_epoch}")]
+    ProfitMarginUpdateTooSoon { epoch: u32, next_epoch: u32 },
+
+    #[error("No funds should be sent alongside the claim")]
+    UnexpectedClaimFunds,
+
+    #[error("There are no rewards to claim")]
+    NoRewardToClaim,
+
+    #[error("There are no pending withdrawals whose unbonding period is over")]
+    NoAvailableWithdrawals,
+
+    #[error("The current epoch has not finished yet. It will end at {epoch_end}")]
+    EpochInProgress { epoch_end: u64 },
+
+    #[error("This mixnode already belongs to the family headed by {family_head}")]
+    AlreadyInFamily { family_head: IdentityKey },
+
+    #[error("Mixnode {family_head} does not head any family")]
+    FamilyNotFound { family_head: IdentityKey },
+
+    #[error("The provided signature of the family head is invalid")]
+    InvalidFamilySignature,
+
+    #[error("This mixnode does not belong to any family")]
+    NotInFamily,
+
+    #[error("Family of {family_head} already has the maximum of {max} members")]
+    FamilyFull {
+        family_head: IdentityKey,
+        max: usize,
+    },
+
+    #[error("Uptime of {uptime}% is not below the required minimum of {minimum}%")]
+    UptimeAboveThreshold { uptime: u32, minimum: u32 },
+
+    #[error("Misbehaviour of mixnode {identity} has already been reported in epoch {epoch}")]
+    MisbehaviourAlreadyReported { identity: IdentityKey, epoch: u32 },
+
+    #[error("Mixnode {identity} is jailed")]
+    MixnodeJailed { identity: IdentityKey },
+
+    #[error("This mixnode is not jailed")]
+    MixnodeNotJailed,
+
+    #[error("This mixnode can't be unjailed before {jailed_until}")]
+    MixnodeStillJailed { jailed_until: u64 },
+
+    #[error("There are no slashed funds to withdraw")]
+    NoSlashedFunds,
+
+    #[error("Only members of the governance can propose and vote on state parameters")]
+    NotGovernanceMember,
+
+    #[error("Governance must have at least one member")]
+    NoGovernanceMembers,
+
+    #[error("{member} is listed more than once as a governance member")]
+    DuplicateGovernanceMember { member: Addr },
+
+    #[error(
+        "Threshold of {threshold} approvals is not a majority of {members} governance members"
+    )]
+    InvalidGovernanceThreshold { threshold: u32, members: u32 },
+
+    #[error("Passed proposals must be timelocked for at least an hour")]
+    ZeroTimelock,
+
+    #[error("Proposal {proposal_id} does not exist")]
+    ProposalNotFound { proposal_id: u64 },
+
+    #[error("Proposal {proposal_id} is not open for voting")]
+    ProposalNotOpen { proposal_id: u64 },
+
+    #[error("Voting period of proposal {proposal_id} has ended")]
+    ProposalVotingEnded { proposal_id: u64 },
+
+    #[error("{voter} has already voted on proposal {proposal_id}")]
+    AlreadyVoted { proposal_id: u64, voter: Addr },
+
+    #[error("Proposal {proposal_id} has not passed")]
+    ProposalNotPassed { proposal_id: u64 },
+
+    #[error("Proposal {proposal_id} cannot be executed before {executable_at}")]
+    ProposalTimelocked {
+        proposal_id: u64,
+        executable_at: u64,
+    },
+
+    #[error("Proposal {proposal_id} could only be executed before {expired_at}")]
+    ProposalExpired { proposal_id: u64, expired_at: u64 },
+
+    #[error("Proposal {proposal_id} has already been finalised")]
+    ProposalFinalised { proposal_id: u64 },
+
+    #[error("Passed proposals must be executable for at least an hour")]
+    ZeroExecutionPeriod,
+
+    #[error("Cannot migrate state of a different contract ({contract})")]
+    UnexpectedContract { contract: String },
+
+    #[error("Cannot migrate the contract state from version {stored} down to version {current}")]
+    ContractDowngrade { stored: u32, current: u32 },
+
     #[error("Could not find any delegation information associated with mixnode {identity} for {address}")]
     NoMixnodeDelegationFound {
         identity: IdentityKey,
@@ -45,6 +45,44 @@ pub(crate) fn calculate_epoch_reward_rate(
     uint128_to_decimal(epoch_reward_uint128)
 }
 
+// increases the cumulative reward index by the provided (already scaled) reward,
+// i.e. returns `index` * (1 + `reward`)
+// epoch length is expressed in hours while the block time is measured in seconds
+pub(crate) fn calculate_epoch_end(epoch_start: u64, epoch_length: u32) -> u64 {
+    epoch_start + epoch_length as u64 * 60 * 60
+}
+
+pub(crate) fn compound_reward_index(index: Decimal, reward: Decimal) -> Decimal {
+    // same as in `scale_reward_by_uptime`, Mul<Decimal> for Decimal is not implemented, so we
+    // have to go through the more precise intermediate representation
+    let index_uint128 = decimal_to_uint128(index);
+    uint128_to_decimal(index_uint128 + index_uint128 * reward)
+}
+
+// scales the amount by the growth of the cumulative reward index since the `starting_index`
+pub(crate) fn apply_reward_index(
+    amount: Uint128,
+    starting_index: Decimal,
+    current_index: Decimal,
+) -> Uint128 {
+    amount.multiply_ratio(
+        decimal_to_uint128(current_index),
+        decimal_to_uint128(starting_index),
+    )
+}
+
+// reduces the reward by the profit margin of the node operator,
+// i.e. returns `reward` * (100 - `profit_margin_percent`) / 100
+pub(crate) fn deduct_profit_margin(reward: Decimal, profit_margin_percent: u8) -> Decimal {
+    // this is more of a sanity check as the contract does not allow setting profit margins
+    // outside the 0-100 range
+    debug_assert!(profit_margin_percent <= 100);
+
+    let reward_uint128 = decimal_to_uint128(reward);
+    let deducted = reward_uint128.multiply_ratio(100 - profit_margin_percent as u128, 100u128);
+    uint128_to_decimal(deducted)
+}
+
 pub(crate) fn scale_reward_by_uptime(
     reward: Decimal,
     uptime: u32,
@@ -124,4 +162,65 @@ mod tests {
         // anything larger than 100 returns an error
         assert!(scale_reward_by_uptime(epoch_reward, 101).is_err())
     }
+
+    #[test]
+    fn deducting_profit_margin() {
+        // 0.05
+        let epoch_reward = Decimal::from_ratio(5u128, 100u128);
+
+        // no margin leaves the whole reward
+        assert_eq!(epoch_reward, deduct_profit_margin(epoch_reward, 0));
+
+        // 10% margin leaves 0.045
+        let expected = Decimal::from_ratio(45u128, 1000u128);
+        assert_eq!(expected, deduct_profit_margin(epoch_reward, 10));
+
+        // 100% margin leaves nothing
+        assert_eq!(Decimal::zero(), deduct_profit_margin(epoch_reward, 100));
+    }
+
+    #[test]
+    fn compounding_reward_index() {
+        // 0.05
+        let epoch_reward = Decimal::from_ratio(5u128, 100u128);
+
+        // no reward leaves the index unchanged
+        assert_eq!(
+            Decimal::one(),
+            compound_reward_index(Decimal::one(), Decimal::zero())
+        );
+
+        // 1 * 1.05
+        let index = compound_reward_index(Decimal::one(), epoch_reward);
+        assert_eq!(Decimal::from_ratio(105u128, 100u128), index);
+
+        // 1.05 * 1.05
+        let index = compound_reward_index(index, epoch_reward);
+        assert_eq!(Decimal::from_ratio(11025u128, 10000u128), index);
+    }
+
+    #[test]
+    fn applying_reward_index() {
+        let amount = Uint128(1000);
+        let starting_index = Decimal::from_ratio(105u128, 100u128);
+        let current_index = Decimal::from_ratio(11025u128, 10000u128);
+
+        // index did not change since the starting point
+        assert_eq!(
+            amount,
+            apply_reward_index(amount, starting_index, starting_index)
+        );
+
+        // only the growth since the starting point is applied, i.e. 1000 * 1.05
+        assert_eq!(
+            Uint128(1050),
+            apply_reward_index(amount, starting_index, current_index)
+        );
+
+        // 1000 * 1.1025
+        assert_eq!(
+            Uint128(1102),
+            apply_reward_index(amount, Decimal::one(), current_index)
+        );
+    }
 }
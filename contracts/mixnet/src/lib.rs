@@ -4,6 +4,7 @@
 pub mod contract;
 pub mod error;
 pub(crate) mod helpers;
+pub(crate) mod migrations;
 pub mod queries;
 pub mod state;
 pub(crate) mod storage;
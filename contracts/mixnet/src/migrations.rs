@@ -0,0 +1,485 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::contract::{
+    default_governance_config, INITIAL_MINIMUM_MIXNODE_UPTIME, INITIAL_MIXNODE_JAIL_EPOCHS,
+    INITIAL_MIXNODE_SLASHING_PERCENT, INITIAL_UNBONDING_PERIOD,
+};
+use crate::error::ContractError;
+use crate::state::State;
+use crate::storage::{
+    config, config_read, contract_version, contract_version_read, governance, jailed_mixnodes,
+    mix_family_members, mixnodes, mixnodes_read, reverse_gateway_delegations,
+    reverse_mix_delegations, CONFIG_KEY, PREFIX_GATEWAY_DELEGATION, PREFIX_MIX_DELEGATION,
+};
+use cosmwasm_std::{Decimal, Order, StdError, StdResult, Storage};
+use cosmwasm_storage::{prefixed_read, singleton_read};
+use mixnet_contract::{ContractVersion, StateParams};
+
+pub const CONTRACT_NAME: &str = "crate:mixnet-contracts";
+
+type MigrationStep = fn(&mut dyn Storage) -> Result<(), ContractError>;
+
+// `MIGRATIONS[n]` upgrades the contract state from version `n` to version `n + 1`.
+// Contracts deployed before the version started being tracked are at version 0.
+// Any change to the storage layout that is not backwards compatible must come with a new step.
+const MIGRATIONS: &[MigrationStep] = &[migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
+
+/// Version of the storage layout used by this code.
+pub const CONTRACT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+pub(crate) fn set_contract_version(storage: &mut dyn Storage) -> StdResult<()> {
+    contract_version(storage).save(&ContractVersion {
+        contract: CONTRACT_NAME.to_string(),
+        version: CONTRACT_VERSION,
+    })
+}
+
+/// Applies, in order, all the migration steps required to bring the stored state
+/// to the current version. Returns the version the state was migrated from.
+pub(crate) fn migrate_contract_state(storage: &mut dyn Storage) -> Result<u32, ContractError> {
+    let stored_version = match contract_version_read(storage).may_load()? {
+        Some(stored) if stored.contract != CONTRACT_NAME => {
+            return Err(ContractError::UnexpectedContract {
+                contract: stored.contract,
+            })
+        }
+        Some(stored) => stored.version,
+        None => 0,
+    };
+
+    if stored_version > CONTRACT_VERSION {
+        return Err(ContractError::ContractDowngrade {
+            stored: stored_version,
+            current: CONTRACT_VERSION,
+        });
+    }
+
+    for step in &MIGRATIONS[stored_version as usize..] {
+        step(storage)?;
+    }
+    set_contract_version(storage)?;
+
+    Ok(stored_version)
+}
+
+// layout of the state from before its version started being tracked
+mod v0 {
+    use cosmwasm_std::{Addr, Decimal, Uint128};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    pub(super) struct StateParams {
+        pub(super) epoch_length: u32,
+        pub(super) minimum_mixnode_bond: Uint128,
+        pub(super) minimum_gateway_bond: Uint128,
+        pub(super) mixnode_bond_reward_rate: Decimal,
+        pub(super) gateway_bond_reward_rate: Decimal,
+        pub(super) mixnode_delegation_reward_rate: Decimal,
+        pub(super) gateway_delegation_reward_rate: Decimal,
+        pub(super) mixnode_active_set_size: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub(super) struct State {
+        pub(super) owner: Addr,
+        pub(super) network_monitor_address: Addr,
+        pub(super) params: StateParams,
+        pub(super) mixnode_epoch_bond_reward: Decimal,
+        pub(super) gateway_epoch_bond_reward: Decimal,
+        pub(super) mixnode_epoch_delegation_reward: Decimal,
+        pub(super) gateway_epoch_delegation_reward: Decimal,
+    }
+}
+
+// version 1 introduced the unbonding period and the mixnode misbehaviour parameters
+// and started indexing delegations by their delegators
+fn migrate_v0_to_v1(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let legacy: v0::State = singleton_read(storage, CONFIG_KEY).load()?;
+    let state = State {
+        owner: legacy.owner,
+        network_monitor_address: legacy.network_monitor_address,
+        params: StateParams {
+            epoch_length: legacy.params.epoch_length,
+            minimum_mixnode_bond: legacy.params.minimum_mixnode_bond,
+            minimum_gateway_bond: legacy.params.minimum_gateway_bond,
+            mixnode_bond_reward_rate: legacy.params.mixnode_bond_reward_rate,
+            gateway_bond_reward_rate: legacy.params.gateway_bond_reward_rate,
+            mixnode_delegation_reward_rate: legacy.params.mixnode_delegation_reward_rate,
+            gateway_delegation_reward_rate: legacy.params.gateway_delegation_reward_rate,
+            mixnode_active_set_size: legacy.params.mixnode_active_set_size,
+            unbonding_period: INITIAL_UNBONDING_PERIOD,
+            minimum_mixnode_uptime: INITIAL_MINIMUM_MIXNODE_UPTIME,
+            mixnode_jail_epochs: INITIAL_MIXNODE_JAIL_EPOCHS,
+            mixnode_slashing_fraction: Decimal::percent(INITIAL_MIXNODE_SLASHING_PERCENT),
+        },
+        mixnode_epoch_bond_reward: legacy.mixnode_epoch_bond_reward,
+        gateway_epoch_bond_reward: legacy.gateway_epoch_bond_reward,
+        mixnode_epoch_delegation_reward: legacy.mixnode_epoch_delegation_reward,
+        gateway_epoch_delegation_reward: legacy.gateway_epoch_delegation_reward,
+    };
+    config(storage).save(&state)?;
+
+    rebuild_delegator_indices(storage)?;
+    Ok(())
+}
+
+// version 2 introduced the governance of the state parameters
+fn migrate_v1_to_v2(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let owner = config_read(storage).load()?.owner;
+    governance(storage).save(&default_governance_config(owner))?;
+    Ok(())
+}
+
+// version 3 started indexing the jailed mixnodes and the members of the families.
+// Nodes jailed before that have no jail period recorded, so their owners are free to unjail
+// them straight away, while the members of the families whose heads are gone are removed from them
+fn migrate_v2_to_v3(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let bonds = mixnodes_read(storage)
+        .range(None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (identity, mut bond) in bonds {
+        if bond.jailed {
+            jailed_mixnodes(storage).save(&identity, &())?;
+        }
+
+        let family_head = match bond.family.clone() {
+            Some(family_head) if family_head.as_bytes() != identity.as_slice() => family_head,
+            _ => continue,
+        };
+        let head_bond = mixnodes_read(storage).may_load(family_head.as_bytes())?;
+        if head_bond.and_then(|head| head.family) == Some(family_head.clone()) {
+            mix_family_members(storage, &family_head).save(&identity, &())?;
+        } else {
+            bond.family = None;
+            mixnodes(storage).save(&identity, &bond)?;
+        }
+    }
+    Ok(())
+}
+
+// splits the key of a delegation, relative to the top-level delegation prefix, into the node
+// identity and the delegator address. Such key consists of
+// [2 bytes big-endian length of the identity][identity][delegator address]
+fn split_delegation_key(key: &[u8]) -> StdResult<(Vec<u8>, Vec<u8>)> {
+    if key.len() < 2 {
+        return Err(StdError::generic_err("malformed delegation key"));
+    }
+    let identity_len = u16::from_be_bytes([key[0], key[1]]) as usize;
+    if key.len() < 2 + identity_len {
+        return Err(StdError::generic_err("malformed delegation key"));
+    }
+    let (identity, delegator) = key[2..].split_at(identity_len);
+    Ok((identity.to_vec(), delegator.to_vec()))
+}
+
+fn delegation_keys(storage: &dyn Storage, prefix: &[u8]) -> StdResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    prefixed_read(storage, prefix)
+        .range(None, None, Order::Ascending)
+        .map(|(key, _)| split_delegation_key(&key))
+        .collect()
+}
+
+// delegations made before the per-delegator index was introduced are not present in it,
+// so it has to be rebuilt from the existing per-node delegations
+fn rebuild_delegator_indices(storage: &mut dyn Storage) -> StdResult<()> {
+    for (identity, delegator) in delegation_keys(storage, PREFIX_MIX_DELEGATION)? {
+        reverse_mix_delegations(storage, &delegator).save(&identity, &())?;
+    }
+
+    for (identity, delegator) in delegation_keys(storage, PREFIX_GATEWAY_DELEGATION)? {
+        reverse_gateway_delegations(storage, &delegator).save(&identity, &())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{migrate, query, INITIAL_MIXNODE_BOND};
+    use crate::queries::{
+        query_delegator_gateway_delegations_paged, query_delegator_mix_delegations_paged,
+        query_jailed_mixnodes_paged,
+    };
+    use crate::storage::{gateway_delegations, governance_read, mix_delegations, mixnodes};
+    use crate::support::tests::helpers;
+    use cosmwasm_std::testing::{mock_env, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{attr, from_binary, Addr, OwnedDeps, Uint128};
+    use cosmwasm_storage::singleton;
+    use mixnet_contract::{MigrateMsg, QueryMsg};
+
+    // contract in the state it would have been in before its version started being tracked
+    fn legacy_contract() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = helpers::init_contract();
+        let current = config_read(&deps.storage).load().unwrap();
+
+        let legacy = v0::State {
+            owner: current.owner,
+            network_monitor_address: current.network_monitor_address,
+            params: v0::StateParams {
+                epoch_length: 42,
+                minimum_mixnode_bond: INITIAL_MIXNODE_BOND,
+                minimum_gateway_bond: Uint128(123),
+                mixnode_bond_reward_rate: current.params.mixnode_bond_reward_rate,
+                gateway_bond_reward_rate: current.params.gateway_bond_reward_rate,
+                mixnode_delegation_reward_rate: current.params.mixnode_delegation_reward_rate,
+                gateway_delegation_reward_rate: current.params.gateway_delegation_reward_rate,
+                mixnode_active_set_size: 7,
+            },
+            mixnode_epoch_bond_reward: current.mixnode_epoch_bond_reward,
+            gateway_epoch_bond_reward: current.gateway_epoch_bond_reward,
+            mixnode_epoch_delegation_reward: current.mixnode_epoch_delegation_reward,
+            gateway_epoch_delegation_reward: current.gateway_epoch_delegation_reward,
+        };
+        singleton(&mut deps.storage, CONFIG_KEY)
+            .save(&legacy)
+            .unwrap();
+        contract_version(&mut deps.storage).remove();
+
+        deps
+    }
+
+    #[test]
+    fn instantiation_sets_current_contract_version() {
+        let deps = helpers::init_contract();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetContractVersion {}).unwrap();
+        let version: ContractVersion = from_binary(&res).unwrap();
+        assert_eq!(
+            ContractVersion {
+                contract: CONTRACT_NAME.to_string(),
+                version: CONTRACT_VERSION,
+            },
+            version
+        );
+    }
+
+    #[test]
+    fn legacy_state_is_migrated_to_the_current_layout() {
+        let mut deps = legacy_contract();
+
+        // the old state cannot be read by the current code
+        assert!(config_read(&deps.storage).load().is_err());
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(
+            vec![
+                attr("action", "migrate"),
+                attr("previous_version", 0),
+                attr("version", CONTRACT_VERSION),
+            ],
+            res.attributes
+        );
+
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(Addr::unchecked("creator"), state.owner);
+        assert_eq!(42, state.params.epoch_length);
+        assert_eq!(Uint128(123), state.params.minimum_gateway_bond);
+        assert_eq!(7, state.params.mixnode_active_set_size);
+        assert_eq!(INITIAL_UNBONDING_PERIOD, state.params.unbonding_period);
+        assert_eq!(
+            INITIAL_MINIMUM_MIXNODE_UPTIME,
+            state.params.minimum_mixnode_uptime
+        );
+        assert_eq!(
+            INITIAL_MIXNODE_JAIL_EPOCHS,
+            state.params.mixnode_jail_epochs
+        );
+        assert_eq!(
+            Decimal::percent(INITIAL_MIXNODE_SLASHING_PERCENT),
+            state.params.mixnode_slashing_fraction
+        );
+
+        assert_eq!(
+            default_governance_config(Addr::unchecked("creator")),
+            governance_read(&deps.storage).load().unwrap()
+        );
+
+        assert_eq!(
+            CONTRACT_VERSION,
+            contract_version_read(&deps.storage).load().unwrap().version
+        );
+    }
+
+    #[test]
+    fn governance_is_introduced_when_migrating_from_version_1() {
+        let mut deps = helpers::init_contract();
+        governance(&mut deps.storage).remove();
+        contract_version(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: CONTRACT_NAME.to_string(),
+                version: 1,
+            })
+            .unwrap();
+        let state = config_read(&deps.storage).load().unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(attr("previous_version", 1), res.attributes[1]);
+
+        // the owner is the only member of the governance
+        assert_eq!(
+            default_governance_config(state.owner.clone()),
+            governance_read(&deps.storage).load().unwrap()
+        );
+        // and the rest of the state is untouched
+        assert_eq!(state, config_read(&deps.storage).load().unwrap());
+    }
+
+    #[test]
+    fn jailed_mixnodes_are_indexed_when_migrating_from_version_2() {
+        let mut deps = helpers::init_contract();
+        let alice_identity = helpers::add_mixnode("alice", helpers::good_mixnode_bond(), &mut deps);
+        helpers::add_mixnode("bob", helpers::good_mixnode_bond(), &mut deps);
+
+        // node jailed directly, as it would have been before the index existed
+        let mut bond = mixnodes_read(&deps.storage)
+            .load(alice_identity.as_bytes())
+            .unwrap();
+        bond.jailed = true;
+        mixnodes(&mut deps.storage)
+            .save(alice_identity.as_bytes(), &bond)
+            .unwrap();
+        contract_version(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: CONTRACT_NAME.to_string(),
+                version: 2,
+            })
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let jailed = query_jailed_mixnodes_paged(deps.as_ref(), None, None).unwrap();
+        assert_eq!(1, jailed.nodes.len());
+        assert_eq!(&alice_identity, jailed.nodes[0].identity());
+    }
+
+    #[test]
+    fn migrating_current_state_does_not_change_it() {
+        let mut deps = helpers::init_contract();
+        let state = config_read(&deps.storage).load().unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(
+            attr("previous_version", CONTRACT_VERSION),
+            res.attributes[1]
+        );
+        assert_eq!(state, config_read(&deps.storage).load().unwrap());
+    }
+
+    #[test]
+    fn migration_of_different_contract_is_rejected() {
+        let mut deps = helpers::init_contract();
+        contract_version(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: "crate:some-other-contract".to_string(),
+                version: 0,
+            })
+            .unwrap();
+
+        assert_eq!(
+            Err(ContractError::UnexpectedContract {
+                contract: "crate:some-other-contract".to_string()
+            }),
+            migrate(deps.as_mut(), mock_env(), MigrateMsg {})
+        );
+    }
+
+    #[test]
+    fn migration_to_older_version_is_rejected() {
+        let mut deps = helpers::init_contract();
+        contract_version(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: CONTRACT_NAME.to_string(),
+                version: CONTRACT_VERSION + 1,
+            })
+            .unwrap();
+
+        assert_eq!(
+            Err(ContractError::ContractDowngrade {
+                stored: CONTRACT_VERSION + 1,
+                current: CONTRACT_VERSION,
+            }),
+            migrate(deps.as_mut(), mock_env(), MigrateMsg {})
+        );
+    }
+
+    #[test]
+    fn rebuilding_delegator_indices_includes_all_existing_delegations() {
+        let mut deps = legacy_contract();
+
+        // delegations written directly, as they would have been before the index existed
+        mix_delegations(&mut deps.storage, "mix1")
+            .save(b"alice", &Uint128(100))
+            .unwrap();
+        mix_delegations(&mut deps.storage, "mix2")
+            .save(b"alice", &Uint128(200))
+            .unwrap();
+        mix_delegations(&mut deps.storage, "mix2")
+            .save(b"bob", &Uint128(300))
+            .unwrap();
+        gateway_delegations(&mut deps.storage, "gateway1")
+            .save(b"bob", &Uint128(400))
+            .unwrap();
+
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+
+        let before =
+            query_delegator_mix_delegations_paged(deps.as_ref(), alice.clone(), None, None)
+                .unwrap();
+        assert!(before.delegations.is_empty());
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let alice_mix =
+            query_delegator_mix_delegations_paged(deps.as_ref(), alice.clone(), None, None)
+                .unwrap();
+        let alice_identities: Vec<_> = alice_mix
+            .delegations
+            .iter()
+            .map(|delegation| delegation.node_identity())
+            .collect();
+        assert_eq!(
+            vec!["mix1".to_string(), "mix2".to_string()],
+            alice_identities
+        );
+        assert_eq!(Uint128(200), alice_mix.delegations[1].amount().amount);
+
+        let bob_mix =
+            query_delegator_mix_delegations_paged(deps.as_ref(), bob.clone(), None, None).unwrap();
+        assert_eq!(1, bob_mix.delegations.len());
+        assert_eq!("mix2", bob_mix.delegations[0].node_identity());
+        assert_eq!(Uint128(300), bob_mix.delegations[0].amount().amount);
+
+        let bob_gateway =
+            query_delegator_gateway_delegations_paged(deps.as_ref(), bob, None, None).unwrap();
+        assert_eq!(1, bob_gateway.delegations.len());
+        assert_eq!("gateway1", bob_gateway.delegations[0].node_identity());
+        assert_eq!(Uint128(400), bob_gateway.delegations[0].amount().amount);
+
+        let alice_gateway =
+            query_delegator_gateway_delegations_paged(deps.as_ref(), alice, None, None).unwrap();
+        assert!(alice_gateway.delegations.is_empty());
+    }
+
+    #[test]
+    fn rebuilding_delegator_indices_is_idempotent() {
+        let mut deps = helpers::init_contract();
+        mix_delegations(&mut deps.storage, "mix1")
+            .save(b"alice", &Uint128(100))
+            .unwrap();
+
+        rebuild_delegator_indices(&mut deps.storage).unwrap();
+        rebuild_delegator_indices(&mut deps.storage).unwrap();
+
+        let res = query_delegator_mix_delegations_paged(
+            deps.as_ref(),
+            Addr::unchecked("alice"),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(1, res.delegations.len());
+    }
+}
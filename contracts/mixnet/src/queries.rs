@@ -2,28 +2,55 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error::ContractError;
+use crate::helpers::{apply_reward_index, calculate_epoch_end};
 use crate::storage::{
-    gateway_delegations_read, gateways_owners_read, gateways_read, mix_delegations_read,
-    mixnodes_owners_read, mixnodes_read, read_layer_distribution, read_state_params,
+    contract_version_read, gateway_delegation_reward_indices_read, gateway_delegations_read,
+    gateways_owners_read, gateways_read, governance_read, jailed_mixnodes_read,
+    mix_delegation_reward_indices_read, mix_delegations_read, mix_misbehaviour_read,
+    mixnodes_owners_read, mixnodes_read, pending_withdrawals_read, proposal_votes_read,
+    proposals_read, read_active_set, read_gateway_delegation_value, read_gateway_reward_index,
+    read_layer_distribution, read_mix_delegation_value, read_mix_reward_index, read_slashed_funds,
+    read_state_params, reverse_gateway_delegations_read, reverse_mix_delegations_read,
 };
 use config::defaults::DENOM;
+use cosmwasm_std::Decimal;
 use cosmwasm_std::Deps;
 use cosmwasm_std::Order;
 use cosmwasm_std::StdResult;
-use cosmwasm_std::{coin, Addr};
+use cosmwasm_std::{coin, Addr, Coin};
 use mixnet_contract::{
-    Delegation, GatewayBond, GatewayOwnershipResponse, IdentityKey, LayerDistribution, MixNodeBond,
-    MixOwnershipResponse, PagedGatewayDelegationsResponse, PagedGatewayResponse,
-    PagedMixDelegationsResponse, PagedMixnodeResponse, StateParams,
+    ContractVersion, Delegation, EpochResponse, GatewayBond, GatewayOwnershipResponse,
+    GovernanceConfig, IdentityKey, LayerDistribution, MixNodeBond, MixOwnershipResponse,
+    MixnodeMisbehaviour, NodeDelegation, PagedDelegatorGatewayDelegationsResponse,
+    PagedDelegatorMixDelegationsResponse, PagedGatewayDelegationsResponse, PagedGatewayResponse,
+    PagedMixDelegationsResponse, PagedMixnodeMisbehaviourResponse, PagedMixnodeResponse,
+    PagedPendingWithdrawalsResponse, PagedProposalVotesResponse, PagedProposalsResponse,
+    PendingWithdrawal, Proposal, ProposalId, ProposalVote, StateParams,
 };
 
 const BOND_PAGE_MAX_LIMIT: u32 = 100;
 const BOND_PAGE_DEFAULT_LIMIT: u32 = 50;
 
 // currently the maximum limit before running into memory issue is somewhere between 1150 and 1200
-pub(crate) const DELEGATION_PAGE_MAX_LIMIT: u32 = 750;
+const DELEGATION_PAGE_MAX_LIMIT: u32 = 750;
 const DELEGATION_PAGE_DEFAULT_LIMIT: u32 = 500;
 
+const WITHDRAWAL_PAGE_MAX_LIMIT: u32 = 100;
+const WITHDRAWAL_PAGE_DEFAULT_LIMIT: u32 = 50;
+
+const MISBEHAVIOUR_PAGE_MAX_LIMIT: u32 = 100;
+const MISBEHAVIOUR_PAGE_DEFAULT_LIMIT: u32 = 50;
+
+const PROPOSAL_PAGE_MAX_LIMIT: u32 = 50;
+const PROPOSAL_PAGE_DEFAULT_LIMIT: u32 = 20;
+
+const VOTE_PAGE_MAX_LIMIT: u32 = 100;
+const VOTE_PAGE_DEFAULT_LIMIT: u32 = 50;
+
+// The limit applies to the number of visited bonds rather than the returned ones, so that
+// the query costs the same regardless of how many nodes are jailed. Since jailed nodes are
+// left out, a page might contain fewer nodes than the limit, or even none, while there are
+// still more pages to go. Iteration is only finished once `start_next_after` is `None`.
 pub fn query_mixnodes_paged(
     deps: Deps,
     start_after: Option<IdentityKey>,
@@ -34,12 +61,38 @@ pub fn query_mixnodes_paged(
         .min(BOND_PAGE_MAX_LIMIT) as usize;
     let start = calculate_start_value(start_after);
 
-    let nodes = mixnodes_read(deps.storage)
+    let bonds = mixnodes_read(deps.storage)
         .range(start.as_deref(), None, Order::Ascending)
         .take(limit)
         .map(|res| res.map(|item| item.1))
         .collect::<StdResult<Vec<MixNodeBond>>>()?;
 
+    // the next page starts after the last visited node, even if it's not included in this one
+    let start_next_after = bonds.last().map(|node| node.identity().clone());
+
+    // jailed nodes are excluded from the network
+    let nodes = bonds.into_iter().filter(|bond| !bond.jailed).collect();
+
+    Ok(PagedMixnodeResponse::new(nodes, limit, start_next_after))
+}
+
+pub(crate) fn query_jailed_mixnodes_paged(
+    deps: Deps,
+    start_after: Option<IdentityKey>,
+    limit: Option<u32>,
+) -> StdResult<PagedMixnodeResponse> {
+    let limit = limit
+        .unwrap_or(BOND_PAGE_DEFAULT_LIMIT)
+        .min(BOND_PAGE_MAX_LIMIT) as usize;
+    let start = calculate_start_value(start_after);
+
+    let bonds = mixnodes_read(deps.storage);
+    let nodes = jailed_mixnodes_read(deps.storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|res| res.and_then(|(identity, _)| bonds.load(&identity)))
+        .collect::<StdResult<Vec<MixNodeBond>>>()?;
+
     let start_next_after = nodes.last().map(|node| node.identity().clone());
 
     Ok(PagedMixnodeResponse::new(nodes, limit, start_next_after))
@@ -87,6 +140,10 @@ pub(crate) fn query_state_params(deps: Deps) -> StateParams {
     read_state_params(deps.storage)
 }
 
+pub(crate) fn query_contract_version(deps: Deps) -> StdResult<ContractVersion> {
+    contract_version_read(deps.storage).load()
+}
+
 pub(crate) fn query_layer_distribution(deps: Deps) -> LayerDistribution {
     read_layer_distribution(deps.storage)
 }
@@ -94,7 +151,7 @@ pub(crate) fn query_layer_distribution(deps: Deps) -> LayerDistribution {
 /// Adds a 0 byte to terminate the `start_after` value given. This allows CosmWasm
 /// to get the succeeding key as the start of the next page.
 // S works for both `String` and `Addr` and that's what we wanted
-fn calculate_start_value<S: AsRef<str>>(start_after: Option<S>) -> Option<Vec<u8>> {
+pub(crate) fn calculate_start_value<S: AsRef<str>>(start_after: Option<S>) -> Option<Vec<u8>> {
     start_after.as_ref().map(|identity| {
         identity
             .as_ref()
@@ -117,15 +174,23 @@ pub(crate) fn query_mixnode_delegations_paged(
         .min(DELEGATION_PAGE_MAX_LIMIT) as usize;
     let start = calculate_start_value(start_after);
 
+    // the stored amounts do not include rewards given since the delegations were last updated
+    let current_index = read_mix_reward_index(deps.storage, &mix_identity)?;
+    let delegation_indices = mix_delegation_reward_indices_read(deps.storage, &mix_identity);
+
     let delegations = mix_delegations_read(deps.storage, &mix_identity)
         .range(start.as_deref(), None, Order::Ascending)
         .take(limit)
         .map(|res| {
-            res.map(|entry| {
-                Delegation::new(
+            res.and_then(|entry| {
+                let starting_index = delegation_indices
+                    .may_load(&entry.0)?
+                    .unwrap_or_else(Decimal::one);
+                let amount = apply_reward_index(entry.1, starting_index, current_index);
+                Ok(Delegation::new(
                     Addr::unchecked(String::from_utf8(entry.0).unwrap()),
-                    coin(entry.1.u128(), DENOM),
-                )
+                    coin(amount.u128(), DENOM),
+                ))
             })
         })
         .collect::<StdResult<Vec<Delegation>>>()?;
@@ -145,7 +210,7 @@ pub(crate) fn query_mixnode_delegation(
     mix_identity: IdentityKey,
     address: Addr,
 ) -> Result<Delegation, ContractError> {
-    match mix_delegations_read(deps.storage, &mix_identity).may_load(address.as_bytes())? {
+    match read_mix_delegation_value(deps.storage, &mix_identity, address.as_bytes())? {
         Some(delegation_value) => Ok(Delegation::new(
             address,
             coin(delegation_value.u128(), DENOM),
@@ -168,15 +233,24 @@ pub(crate) fn query_gateway_delegations_paged(
         .min(DELEGATION_PAGE_MAX_LIMIT) as usize;
     let start = calculate_start_value(start_after);
 
+    // the stored amounts do not include rewards given since the delegations were last updated
+    let current_index = read_gateway_reward_index(deps.storage, &gateway_identity)?;
+    let delegation_indices =
+        gateway_delegation_reward_indices_read(deps.storage, &gateway_identity);
+
     let delegations = gateway_delegations_read(deps.storage, &gateway_identity)
         .range(start.as_deref(), None, Order::Ascending)
         .take(limit)
         .map(|res| {
-            res.map(|entry| {
-                Delegation::new(
+            res.and_then(|entry| {
+                let starting_index = delegation_indices
+                    .may_load(&entry.0)?
+                    .unwrap_or_else(Decimal::one);
+                let amount = apply_reward_index(entry.1, starting_index, current_index);
+                Ok(Delegation::new(
                     Addr::unchecked(String::from_utf8(entry.0).unwrap()),
-                    coin(entry.1.u128(), DENOM),
-                )
+                    coin(amount.u128(), DENOM),
+                ))
             })
         })
         .collect::<StdResult<Vec<Delegation>>>()?;
@@ -196,7 +270,7 @@ pub(crate) fn query_gateway_delegation(
     gateway_identity: IdentityKey,
     address: Addr,
 ) -> Result<Delegation, ContractError> {
-    match gateway_delegations_read(deps.storage, &gateway_identity).may_load(address.as_bytes())? {
+    match read_gateway_delegation_value(deps.storage, &gateway_identity, address.as_bytes())? {
         Some(delegation_value) => Ok(Delegation::new(
             address,
             coin(delegation_value.u128(), DENOM),
@@ -208,15 +282,272 @@ pub(crate) fn query_gateway_delegation(
     }
 }
 
+pub(crate) fn query_delegator_mix_delegations_paged(
+    deps: Deps,
+    address: Addr,
+    start_after: Option<IdentityKey>,
+    limit: Option<u32>,
+) -> StdResult<PagedDelegatorMixDelegationsResponse> {
+    let limit = limit
+        .unwrap_or(DELEGATION_PAGE_DEFAULT_LIMIT)
+        .min(DELEGATION_PAGE_MAX_LIMIT) as usize;
+    let start = calculate_start_value(start_after);
+
+    let delegations = reverse_mix_delegations_read(deps.storage, address.as_bytes())
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|res| {
+            res.and_then(|(identity, _)| {
+                let mix_identity = String::from_utf8(identity).unwrap();
+                // the index is always updated alongside the delegation itself
+                let amount =
+                    read_mix_delegation_value(deps.storage, &mix_identity, address.as_bytes())?
+                        .unwrap_or_default();
+                Ok(NodeDelegation::new(
+                    mix_identity,
+                    coin(amount.u128(), DENOM),
+                ))
+            })
+        })
+        .collect::<StdResult<Vec<NodeDelegation>>>()?;
+
+    let start_next_after = delegations
+        .last()
+        .map(|delegation| delegation.node_identity());
+
+    Ok(PagedDelegatorMixDelegationsResponse::new(
+        address,
+        delegations,
+        start_next_after,
+    ))
+}
+
+pub(crate) fn query_delegator_gateway_delegations_paged(
+    deps: Deps,
+    address: Addr,
+    start_after: Option<IdentityKey>,
+    limit: Option<u32>,
+) -> StdResult<PagedDelegatorGatewayDelegationsResponse> {
+    let limit = limit
+        .unwrap_or(DELEGATION_PAGE_DEFAULT_LIMIT)
+        .min(DELEGATION_PAGE_MAX_LIMIT) as usize;
+    let start = calculate_start_value(start_after);
+
+    let delegations = reverse_gateway_delegations_read(deps.storage, address.as_bytes())
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|res| {
+            res.and_then(|(identity, _)| {
+                let gateway_identity = String::from_utf8(identity).unwrap();
+                // the index is always updated alongside the delegation itself
+                let amount = read_gateway_delegation_value(
+                    deps.storage,
+                    &gateway_identity,
+                    address.as_bytes(),
+                )?
+                .unwrap_or_default();
+                Ok(NodeDelegation::new(
+                    gateway_identity,
+                    coin(amount.u128(), DENOM),
+                ))
+            })
+        })
+        .collect::<StdResult<Vec<NodeDelegation>>>()?;
+
+    let start_next_after = delegations
+        .last()
+        .map(|delegation| delegation.node_identity());
+
+    Ok(PagedDelegatorGatewayDelegationsResponse::new(
+        address,
+        delegations,
+        start_next_after,
+    ))
+}
+
+pub(crate) fn query_epoch(deps: Deps) -> StdResult<EpochResponse> {
+    let active_set = read_active_set(deps.storage)?;
+    let epoch_end = calculate_epoch_end(
+        active_set.epoch_start,
+        read_state_params(deps.storage).epoch_length,
+    );
+
+    Ok(EpochResponse::new(
+        active_set.epoch,
+        active_set.epoch_start,
+        epoch_end,
+    ))
+}
+
+pub(crate) fn query_active_set_paged(
+    deps: Deps,
+    start_after: Option<IdentityKey>,
+    limit: Option<u32>,
+) -> StdResult<PagedMixnodeResponse> {
+    let limit = limit
+        .unwrap_or(BOND_PAGE_DEFAULT_LIMIT)
+        .min(BOND_PAGE_MAX_LIMIT) as usize;
+
+    // the identities of the active set are stored sorted
+    let active_set = read_active_set(deps.storage)?;
+    let identities = active_set
+        .nodes
+        .iter()
+        .filter(|identity| start_after.as_ref().map_or(true, |start| *identity > start))
+        .take(limit)
+        .collect::<Vec<_>>();
+
+    // the next page starts after the last visited node, even if it's not included in this one
+    let start_next_after = identities.last().map(|identity| (*identity).clone());
+
+    // nodes that got unbonded or jailed since the set was selected are no longer part of it
+    let mixnodes = mixnodes_read(deps.storage);
+    let nodes = identities
+        .into_iter()
+        .filter_map(|identity| mixnodes.may_load(identity.as_bytes()).transpose())
+        .filter(|res| res.as_ref().map_or(true, |bond| !bond.jailed))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PagedMixnodeResponse::new(nodes, limit, start_next_after))
+}
+
+pub(crate) fn query_mixnode_misbehaviour_paged(
+    deps: Deps,
+    mix_identity: IdentityKey,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> StdResult<PagedMixnodeMisbehaviourResponse> {
+    let limit = limit
+        .unwrap_or(MISBEHAVIOUR_PAGE_DEFAULT_LIMIT)
+        .min(MISBEHAVIOUR_PAGE_MAX_LIMIT) as usize;
+    // reports are keyed by big-endian epochs, so the next epoch is the first key after `start_after`
+    let start = start_after.map(|epoch| (epoch + 1).to_be_bytes());
+
+    let reports = mix_misbehaviour_read(deps.storage, &mix_identity)
+        .range(start.as_ref().map(|s| &s[..]), None, Order::Ascending)
+        .take(limit)
+        .map(|res| res.map(|(_, report)| report))
+        .collect::<StdResult<Vec<MixnodeMisbehaviour>>>()?;
+
+    let start_next_after = reports.last().map(|report| report.epoch);
+
+    Ok(PagedMixnodeMisbehaviourResponse::new(
+        mix_identity,
+        reports,
+        start_next_after,
+    ))
+}
+
+pub(crate) fn query_slashed_funds(deps: Deps) -> StdResult<Coin> {
+    Ok(coin(read_slashed_funds(deps.storage)?.u128(), DENOM))
+}
+
+pub(crate) fn query_governance_config(deps: Deps) -> StdResult<GovernanceConfig> {
+    governance_read(deps.storage).load()
+}
+
+pub(crate) fn query_proposal(
+    deps: Deps,
+    proposal_id: ProposalId,
+) -> Result<Proposal, ContractError> {
+    proposals_read(deps.storage)
+        .may_load(&proposal_id.to_be_bytes())?
+        .ok_or(ContractError::ProposalNotFound { proposal_id })
+}
+
+pub(crate) fn query_proposals_paged(
+    deps: Deps,
+    start_after: Option<ProposalId>,
+    limit: Option<u32>,
+) -> StdResult<PagedProposalsResponse> {
+    let limit = limit
+        .unwrap_or(PROPOSAL_PAGE_DEFAULT_LIMIT)
+        .min(PROPOSAL_PAGE_MAX_LIMIT) as usize;
+    // proposals are keyed by big-endian ids, so the next id is the first key after `start_after`
+    let start = start_after.map(|id| (id + 1).to_be_bytes());
+
+    let proposals = proposals_read(deps.storage)
+        .range(start.as_ref().map(|s| &s[..]), None, Order::Ascending)
+        .take(limit)
+        .map(|res| res.map(|(_, proposal)| proposal))
+        .collect::<StdResult<Vec<Proposal>>>()?;
+
+    let start_next_after = proposals.last().map(|proposal| proposal.id);
+
+    Ok(PagedProposalsResponse::new(proposals, start_next_after))
+}
+
+pub(crate) fn query_proposal_votes_paged(
+    deps: Deps,
+    proposal_id: ProposalId,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<PagedProposalVotesResponse> {
+    let limit = limit
+        .unwrap_or(VOTE_PAGE_DEFAULT_LIMIT)
+        .min(VOTE_PAGE_MAX_LIMIT) as usize;
+    let start = calculate_start_value(start_after);
+
+    let votes = proposal_votes_read(deps.storage, proposal_id)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|res| {
+            res.map(|(voter, vote)| ProposalVote {
+                voter: Addr::unchecked(String::from_utf8(voter).unwrap()),
+                vote,
+            })
+        })
+        .collect::<StdResult<Vec<ProposalVote>>>()?;
+
+    let start_next_after = votes.last().map(|vote| vote.voter.clone());
+
+    Ok(PagedProposalVotesResponse::new(
+        proposal_id,
+        votes,
+        start_next_after,
+    ))
+}
+
+pub(crate) fn query_pending_withdrawals_paged(
+    deps: Deps,
+    address: Addr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PagedPendingWithdrawalsResponse> {
+    let limit = limit
+        .unwrap_or(WITHDRAWAL_PAGE_DEFAULT_LIMIT)
+        .min(WITHDRAWAL_PAGE_MAX_LIMIT) as usize;
+    // withdrawals are keyed by big-endian ids, so the next id is the first key after `start_after`
+    let start = start_after.map(|id| (id + 1).to_be_bytes());
+
+    let withdrawals = pending_withdrawals_read(deps.storage, &address)
+        .range(start.as_ref().map(|s| &s[..]), None, Order::Ascending)
+        .take(limit)
+        .map(|res| res.map(|(_, withdrawal)| withdrawal))
+        .collect::<StdResult<Vec<PendingWithdrawal>>>()?;
+
+    let start_next_after = withdrawals.last().map(|withdrawal| withdrawal.id);
+
+    Ok(PagedPendingWithdrawalsResponse::new(
+        address,
+        withdrawals,
+        start_next_after,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::state::State;
-    use crate::storage::{config, gateway_delegations, gateways, mix_delegations, mixnodes};
+    use crate::storage::{
+        config, gateway_delegations, gateways, increase_mix_reward_index, mix_delegations,
+        mixnodes, pending_withdrawals, remove_gateway_delegation, remove_mix_delegation,
+        save_gateway_delegation, save_mix_delegation,
+    };
     use crate::support::tests::helpers;
     use crate::support::tests::helpers::{good_gateway_bond, good_mixnode_bond};
     use crate::transactions;
-    use cosmwasm_std::testing::mock_info;
+    use cosmwasm_std::testing::{mock_env, mock_info};
     use cosmwasm_std::{Addr, Storage, Uint128};
     use mixnet_contract::{Gateway, MixNode};
 
@@ -472,8 +803,13 @@ mod tests {
             identity_key: "bobsnode".into(),
             ..helpers::mix_node_fixture()
         };
-        transactions::try_add_mixnode(deps.as_mut(), mock_info("bob", &good_mixnode_bond()), node)
-            .unwrap();
+        transactions::try_add_mixnode(
+            deps.as_mut(),
+            mock_info("bob", &good_mixnode_bond()),
+            node,
+            10,
+        )
+        .unwrap();
 
         let res = query_owns_mixnode(deps.as_ref(), Addr::unchecked("fred")).unwrap();
         assert!(!res.has_node);
@@ -483,14 +819,20 @@ mod tests {
             identity_key: "fredsnode".into(),
             ..helpers::mix_node_fixture()
         };
-        transactions::try_add_mixnode(deps.as_mut(), mock_info("fred", &good_mixnode_bond()), node)
-            .unwrap();
+        transactions::try_add_mixnode(
+            deps.as_mut(),
+            mock_info("fred", &good_mixnode_bond()),
+            node,
+            10,
+        )
+        .unwrap();
 
         let res = query_owns_mixnode(deps.as_ref(), Addr::unchecked("fred")).unwrap();
         assert!(res.has_node);
 
         // but after unbonding it, he doesn't own one anymore
-        transactions::try_remove_mixnode(deps.as_mut(), mock_info("fred", &[])).unwrap();
+        transactions::try_remove_mixnode(deps.as_mut(), mock_env(), mock_info("fred", &[]))
+            .unwrap();
 
         let res = query_owns_mixnode(deps.as_ref(), Addr::unchecked("fred")).unwrap();
         assert!(!res.has_node);
@@ -527,7 +869,8 @@ mod tests {
         assert!(res.has_gateway);
 
         // but after unbonding it, he doesn't own one anymore
-        transactions::try_remove_gateway(deps.as_mut(), mock_info("fred", &[])).unwrap();
+        transactions::try_remove_gateway(deps.as_mut(), mock_env(), mock_info("fred", &[]))
+            .unwrap();
 
         let res = query_owns_gateway(deps.as_ref(), Addr::unchecked("fred")).unwrap();
         assert!(!res.has_gateway);
@@ -549,6 +892,10 @@ mod tests {
                 mixnode_delegation_reward_rate: "7.89".parse().unwrap(),
                 gateway_delegation_reward_rate: "0.12".parse().unwrap(),
                 mixnode_active_set_size: 1000,
+                unbonding_period: 24,
+                minimum_mixnode_uptime: 50,
+                mixnode_jail_epochs: 3,
+                mixnode_slashing_fraction: "0.05".parse().unwrap(),
             },
             mixnode_epoch_bond_reward: "1.23".parse().unwrap(),
             gateway_epoch_bond_reward: "4.56".parse().unwrap(),
@@ -1010,4 +1357,160 @@ mod tests {
             query_gateway_delegation(deps.as_ref(), node_identity1, delegation_owner1)
         )
     }
+
+    #[test]
+    fn pending_withdrawals_paged_retrieval_works() {
+        let mut deps = helpers::init_contract();
+        let owner = Addr::unchecked("alice");
+
+        let response =
+            query_pending_withdrawals_paged(deps.as_ref(), owner.clone(), None, None).unwrap();
+        assert!(response.withdrawals.is_empty());
+        assert_eq!(None, response.start_next_after);
+
+        for id in 1..=5u64 {
+            let withdrawal = PendingWithdrawal::new(id, owner.clone(), coin(100, DENOM), id);
+            pending_withdrawals(&mut deps.storage, &owner)
+                .save(&id.to_be_bytes(), &withdrawal)
+                .unwrap();
+        }
+
+        // withdrawals of other addresses are not included
+        let other = Addr::unchecked("bob");
+        pending_withdrawals(&mut deps.storage, &other)
+            .save(
+                &6u64.to_be_bytes(),
+                &PendingWithdrawal::new(6, other.clone(), coin(100, DENOM), 6),
+            )
+            .unwrap();
+
+        let page1 =
+            query_pending_withdrawals_paged(deps.as_ref(), owner.clone(), None, Some(2)).unwrap();
+        assert_eq!(
+            vec![1, 2],
+            page1.withdrawals.iter().map(|w| w.id).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(2), page1.start_next_after);
+
+        let page2 = query_pending_withdrawals_paged(
+            deps.as_ref(),
+            owner.clone(),
+            page1.start_next_after,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![3, 4, 5],
+            page2.withdrawals.iter().map(|w| w.id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn delegator_mix_delegations_paged_retrieval_works() {
+        let mut deps = helpers::init_contract();
+        let delegator = Addr::unchecked("alice");
+
+        let response =
+            query_delegator_mix_delegations_paged(deps.as_ref(), delegator.clone(), None, None)
+                .unwrap();
+        assert!(response.delegations.is_empty());
+        assert_eq!(None, response.start_next_after);
+
+        for i in 1..=5 {
+            save_mix_delegation(
+                &mut deps.storage,
+                &format!("mix{}", i),
+                delegator.as_bytes(),
+                Uint128(i * 100),
+            )
+            .unwrap();
+        }
+        // delegations of other addresses are not included
+        save_mix_delegation(&mut deps.storage, "mix1", b"bob", Uint128(1000)).unwrap();
+        // and neither are the removed ones
+        remove_mix_delegation(&mut deps.storage, "mix5", delegator.as_bytes());
+
+        let page1 =
+            query_delegator_mix_delegations_paged(deps.as_ref(), delegator.clone(), None, Some(2))
+                .unwrap();
+        assert_eq!(
+            vec![
+                NodeDelegation::new("mix1".into(), coin(100, DENOM)),
+                NodeDelegation::new("mix2".into(), coin(200, DENOM)),
+            ],
+            page1.delegations
+        );
+        assert_eq!(Some("mix2".to_string()), page1.start_next_after);
+
+        let page2 = query_delegator_mix_delegations_paged(
+            deps.as_ref(),
+            delegator.clone(),
+            page1.start_next_after,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["mix3".to_string(), "mix4".to_string()],
+            page2
+                .delegations
+                .iter()
+                .map(|delegation| delegation.node_identity())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn delegator_mix_delegations_include_accumulated_rewards() {
+        let mut deps = helpers::init_contract();
+        let delegator = Addr::unchecked("alice");
+        save_mix_delegation(
+            &mut deps.storage,
+            "mix1",
+            delegator.as_bytes(),
+            Uint128(1000),
+        )
+        .unwrap();
+        increase_mix_reward_index(&mut deps.storage, "mix1", Decimal::percent(10)).unwrap();
+
+        let response =
+            query_delegator_mix_delegations_paged(deps.as_ref(), delegator.clone(), None, None)
+                .unwrap();
+        assert_eq!(
+            query_mixnode_delegation(deps.as_ref(), "mix1".into(), delegator)
+                .unwrap()
+                .amount(),
+            response.delegations[0].amount()
+        );
+    }
+
+    #[test]
+    fn delegator_gateway_delegations_paged_retrieval_works() {
+        let mut deps = helpers::init_contract();
+        let delegator = Addr::unchecked("alice");
+
+        for i in 1..=3 {
+            save_gateway_delegation(
+                &mut deps.storage,
+                &format!("gateway{}", i),
+                delegator.as_bytes(),
+                Uint128(i * 100),
+            )
+            .unwrap();
+        }
+        save_gateway_delegation(&mut deps.storage, "gateway1", b"bob", Uint128(1000)).unwrap();
+        remove_gateway_delegation(&mut deps.storage, "gateway2", delegator.as_bytes());
+
+        let response =
+            query_delegator_gateway_delegations_paged(deps.as_ref(), delegator.clone(), None, None)
+                .unwrap();
+        assert_eq!(delegator, response.delegation_owner);
+        assert_eq!(
+            vec![
+                NodeDelegation::new("gateway1".into(), coin(100, DENOM)),
+                NodeDelegation::new("gateway3".into(), coin(300, DENOM)),
+            ],
+            response.delegations
+        );
+        assert_eq!(Some("gateway3".to_string()), response.start_next_after);
+    }
 }
@@ -1,8 +1,8 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use cosmwasm_std::{Addr, Decimal};
-use mixnet_contract::StateParams;
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use mixnet_contract::{IdentityKey, StateParams};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -18,3 +18,30 @@ pub struct State {
     pub mixnode_epoch_delegation_reward: Decimal, // reward per epoch expressed as a decimal like 0.05
     pub gateway_epoch_delegation_reward: Decimal, // reward per epoch expressed as a decimal like 0.05
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ActiveSet {
+    pub epoch: u32,
+    pub epoch_start: u64, // unix timestamp (in seconds) at which the set got selected
+    pub nodes: Vec<IdentityKey>, // sorted by identity, so that the set can be queried in pages
+}
+
+// progress of the selection of the next active set. The selection is spread over multiple
+// transactions, so that none of them has to go through all of the bonded mixnodes at once
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ActiveSetSelection {
+    pub started_at: u64, // unix timestamp (in seconds) at which the selection has begun
+    pub last_scanned: Option<IdentityKey>, // the last mixnode whose stake has been looked at
+    pub scan_finished: bool,
+    // nodes with the highest stake seen so far, ordered by decreasing stake
+    pub candidates: Vec<(IdentityKey, Uint128)>,
+    pub assigned_candidates: u32, // number of candidates that were already given their new layers
+    pub selected: Vec<IdentityKey>, // candidates that were still eligible when given their layers
+}
+
+// tracks for how many consecutive epochs the mixnode has been reported as misbehaving
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct MisbehaviourStreak {
+    pub last_epoch: u32,
+    pub consecutive_epochs: u32,
+}
@@ -1,15 +1,17 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::queries;
-use crate::state::State;
-use cosmwasm_std::{Decimal, Order, StdResult, Storage, Uint128};
+use crate::helpers::{apply_reward_index, compound_reward_index};
+use crate::state::{ActiveSet, ActiveSetSelection, MisbehaviourStreak, State};
+use cosmwasm_std::{Decimal, StdResult, Storage, Uint128};
 use cosmwasm_storage::{
     bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton,
     Singleton,
 };
 use mixnet_contract::{
-    GatewayBond, IdentityKey, IdentityKeyRef, Layer, LayerDistribution, MixNodeBond, StateParams,
+    Addr, ContractVersion, GatewayBond, GovernanceConfig, IdentityKey, IdentityKeyRef, Layer,
+    LayerDistribution, MixNodeBond, MixnodeMisbehaviour, PendingWithdrawal, Proposal, ProposalId,
+    StateParams, VoteOption,
 };
 
 // storage prefixes
@@ -18,17 +20,45 @@ use mixnet_contract::{
 // it's not as important for singletons, but is a nice optimisation for buckets
 
 // singletons
-const CONFIG_KEY: &[u8] = b"config";
+pub(crate) const CONFIG_KEY: &[u8] = b"config";
+// the same key as used by cw2, so that any tooling understanding it could find our version
+const CONTRACT_VERSION_KEY: &[u8] = b"contract_info";
 const LAYER_DISTRIBUTION_KEY: &[u8] = b"layers";
+const PENDING_WITHDRAWAL_ID_KEY: &[u8] = b"withdrawal_id";
+const ACTIVE_SET_KEY: &[u8] = b"active_set";
+const ACTIVE_SET_SELECTION_KEY: &[u8] = b"active_set_selection";
+const GOVERNANCE_KEY: &[u8] = b"governance";
+const PROPOSAL_ID_KEY: &[u8] = b"proposal_id";
+const SLASHED_FUNDS_KEY: &[u8] = b"slashed";
 
 // buckets
 const PREFIX_MIXNODES: &[u8] = b"mn";
 const PREFIX_MIXNODES_OWNERS: &[u8] = b"mo";
+const PREFIX_JAILED_MIXNODES: &[u8] = b"mj";
+const PREFIX_MIX_FAMILY_MEMBERS: &[u8] = b"mf";
+const PREFIX_MIX_OPERATOR_REWARDS: &[u8] = b"mw";
+const PREFIX_MIX_PROFIT_MARGIN_UPDATES: &[u8] = b"mp";
 const PREFIX_GATEWAYS: &[u8] = b"gt";
 const PREFIX_GATEWAYS_OWNERS: &[u8] = b"go";
 
-const PREFIX_MIX_DELEGATION: &[u8] = b"md";
-const PREFIX_GATEWAY_DELEGATION: &[u8] = b"gd";
+pub(crate) const PREFIX_MIX_DELEGATION: &[u8] = b"md";
+pub(crate) const PREFIX_GATEWAY_DELEGATION: &[u8] = b"gd";
+const PREFIX_REVERSE_MIX_DELEGATION: &[u8] = b"dm";
+const PREFIX_REVERSE_GATEWAY_DELEGATION: &[u8] = b"dg";
+
+const PREFIX_MIX_REWARD_INDEX: &[u8] = b"mr";
+const PREFIX_GATEWAY_REWARD_INDEX: &[u8] = b"gr";
+const PREFIX_MIX_DELEGATION_INDEX: &[u8] = b"mi";
+const PREFIX_GATEWAY_DELEGATION_INDEX: &[u8] = b"gi";
+const PREFIX_MIX_DELEGATION_UNCLAIMED_REWARD: &[u8] = b"mu";
+
+const PREFIX_PENDING_WITHDRAWALS: &[u8] = b"pw";
+
+const PREFIX_MIX_MISBEHAVIOUR: &[u8] = b"mb";
+const PREFIX_MIX_MISBEHAVIOUR_STREAKS: &[u8] = b"ms";
+
+const PREFIX_PROPOSALS: &[u8] = b"pp";
+const PREFIX_PROPOSAL_VOTES: &[u8] = b"pv";
 
 // Contract-level stuff
 
@@ -40,6 +70,14 @@ pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<State> {
     singleton_read(storage, CONFIG_KEY)
 }
 
+pub fn contract_version(storage: &mut dyn Storage) -> Singleton<ContractVersion> {
+    singleton(storage, CONTRACT_VERSION_KEY)
+}
+
+pub fn contract_version_read(storage: &dyn Storage) -> ReadonlySingleton<ContractVersion> {
+    singleton_read(storage, CONTRACT_VERSION_KEY)
+}
+
 pub(crate) fn read_state_params(storage: &dyn Storage) -> StateParams {
     // note: In any other case, I wouldn't have attempted to unwrap this result, but in here
     // if we fail to load the stored state we would already be in the undefined behaviour land,
@@ -94,6 +132,32 @@ pub(crate) fn read_layer_distribution(storage: &dyn Storage) -> LayerDistributio
     layer_distribution_read(storage).load().unwrap()
 }
 
+pub fn active_set(storage: &mut dyn Storage) -> Singleton<ActiveSet> {
+    singleton(storage, ACTIVE_SET_KEY)
+}
+
+pub fn active_set_read(storage: &dyn Storage) -> ReadonlySingleton<ActiveSet> {
+    singleton_read(storage, ACTIVE_SET_KEY)
+}
+
+// before the first epoch gets started, the active set is empty
+pub(crate) fn read_active_set(storage: &dyn Storage) -> StdResult<ActiveSet> {
+    Ok(active_set_read(storage).may_load()?.unwrap_or_default())
+}
+
+pub fn active_set_selection(storage: &mut dyn Storage) -> Singleton<ActiveSetSelection> {
+    singleton(storage, ACTIVE_SET_SELECTION_KEY)
+}
+
+pub fn active_set_selection_read(storage: &dyn Storage) -> ReadonlySingleton<ActiveSetSelection> {
+    singleton_read(storage, ACTIVE_SET_SELECTION_KEY)
+}
+
+// number of the epoch the currently used active set was selected in
+pub(crate) fn read_current_epoch(storage: &dyn Storage) -> StdResult<u32> {
+    Ok(read_active_set(storage)?.epoch)
+}
+
 pub fn increment_layer_count(storage: &mut dyn Storage, layer: Layer) -> StdResult<()> {
     let mut distribution = layer_distribution(storage).load()?;
     match layer {
@@ -156,93 +220,143 @@ pub fn mixnodes_owners_read(storage: &dyn Storage) -> ReadonlyBucket<IdentityKey
     bucket_read(storage, PREFIX_MIXNODES_OWNERS)
 }
 
-// helpers
-pub(crate) fn increase_mix_delegated_stakes(
-    storage: &mut dyn Storage,
-    mix_identity: IdentityKeyRef,
-    scaled_reward_rate: Decimal,
-) -> StdResult<Uint128> {
-    let chunk_size = queries::DELEGATION_PAGE_MAX_LIMIT as usize;
-
-    let mut total_rewarded = Uint128::zero();
-    let mut chunk_start: Option<Vec<_>> = None;
-    loop {
-        // get `chunk_size` of delegations
-        let delegations_chunk = mix_delegations_read(storage, mix_identity)
-            .range(chunk_start.as_deref(), None, Order::Ascending)
-            .take(chunk_size)
-            .collect::<StdResult<Vec<_>>>()?;
-
-        if delegations_chunk.is_empty() {
-            break;
-        }
+// identities of the jailed nodes, so that they can be listed without going through all the bonds
+pub fn jailed_mixnodes(storage: &mut dyn Storage) -> Bucket<()> {
+    bucket(storage, PREFIX_JAILED_MIXNODES)
+}
 
-        // append 0 byte to the last value to start with whatever is the next succeeding key
-        chunk_start = Some(
-            delegations_chunk
-                .last()
-                .unwrap()
-                .0
-                .iter()
-                .cloned()
-                .chain(std::iter::once(0u8))
-                .collect(),
-        );
+pub fn jailed_mixnodes_read(storage: &dyn Storage) -> ReadonlyBucket<()> {
+    bucket_read(storage, PREFIX_JAILED_MIXNODES)
+}
 
-        // and for each of them increase the stake proportionally to the reward
-        for (delegator_address, amount) in delegations_chunk.into_iter() {
-            let reward = amount * scaled_reward_rate;
-            let new_amount = amount + reward;
-            total_rewarded += reward;
-            mix_delegations(storage, mix_identity).save(&delegator_address, &new_amount)?;
-        }
-    }
+// identities of the members of the family headed by the given node, apart from the head itself
+pub fn mix_family_members<'a>(
+    storage: &'a mut dyn Storage,
+    family_head: IdentityKeyRef,
+) -> Bucket<'a, ()> {
+    Bucket::multilevel(
+        storage,
+        &[PREFIX_MIX_FAMILY_MEMBERS, family_head.as_bytes()],
+    )
+}
 
-    Ok(total_rewarded)
+pub fn mix_family_members_read<'a>(
+    storage: &'a dyn Storage,
+    family_head: IdentityKeyRef,
+) -> ReadonlyBucket<'a, ()> {
+    ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_MIX_FAMILY_MEMBERS, family_head.as_bytes()],
+    )
+}
+
+// node identity -> rewards of the node operator that were not claimed yet
+pub fn mix_operator_rewards(storage: &mut dyn Storage) -> Bucket<Uint128> {
+    bucket(storage, PREFIX_MIX_OPERATOR_REWARDS)
+}
+
+pub fn mix_operator_rewards_read(storage: &dyn Storage) -> ReadonlyBucket<Uint128> {
+    bucket_read(storage, PREFIX_MIX_OPERATOR_REWARDS)
+}
+
+// node identity -> epoch in which the profit margin of the node was last updated
+pub fn mix_profit_margin_updates(storage: &mut dyn Storage) -> Bucket<u32> {
+    bucket(storage, PREFIX_MIX_PROFIT_MARGIN_UPDATES)
+}
+
+pub fn mix_profit_margin_updates_read(storage: &dyn Storage) -> ReadonlyBucket<u32> {
+    bucket_read(storage, PREFIX_MIX_PROFIT_MARGIN_UPDATES)
+}
+
+// cumulative reward index of the node, i.e. the product of (1 + delegation reward) of all epochs
+// the node was rewarded in. Nodes that were never rewarded have the index of 1.
+pub fn mix_reward_indices(storage: &mut dyn Storage) -> Bucket<Decimal> {
+    bucket(storage, PREFIX_MIX_REWARD_INDEX)
+}
+
+pub fn mix_reward_indices_read(storage: &dyn Storage) -> ReadonlyBucket<Decimal> {
+    bucket_read(storage, PREFIX_MIX_REWARD_INDEX)
+}
+
+// helpers
+pub(crate) fn read_mix_reward_index(
+    storage: &dyn Storage,
+    mix_identity: IdentityKeyRef,
+) -> StdResult<Decimal> {
+    Ok(mix_reward_indices_read(storage)
+        .may_load(mix_identity.as_bytes())?
+        .unwrap_or_else(Decimal::one))
 }
 
-pub(crate) fn increase_gateway_delegated_stakes(
+// rewards all delegators of the node at once by only increasing its cumulative reward index.
+// the actual delegation values are calculated lazily whenever they are accessed,
+// so the cost does not depend on the number of delegators
+pub(crate) fn increase_mix_reward_index(
     storage: &mut dyn Storage,
-    gateway_identity: IdentityKeyRef,
+    mix_identity: IdentityKeyRef,
     scaled_reward_rate: Decimal,
-) -> StdResult<Uint128> {
-    let chunk_size = queries::DELEGATION_PAGE_MAX_LIMIT as usize;
-
-    let mut total_rewarded = Uint128::zero();
-    let mut chunk_start: Option<Vec<_>> = None;
-    loop {
-        // get `chunk_size` of delegations
-        let delegations_chunk = gateway_delegations_read(storage, gateway_identity)
-            .range(chunk_start.as_deref(), None, Order::Ascending)
-            .take(chunk_size)
-            .collect::<StdResult<Vec<_>>>()?;
-
-        if delegations_chunk.is_empty() {
-            break;
-        }
+) -> StdResult<()> {
+    let current_index = read_mix_reward_index(storage, mix_identity)?;
+    let new_index = compound_reward_index(current_index, scaled_reward_rate);
+    mix_reward_indices(storage).save(mix_identity.as_bytes(), &new_index)
+}
 
-        // append 0 byte to the last value to start with whatever is the next suceeding key
-        chunk_start = Some(
-            delegations_chunk
-                .last()
-                .unwrap()
-                .0
-                .iter()
-                .cloned()
-                .chain(std::iter::once(0u8))
-                .collect(),
-        );
+// reads the current value of the delegation, including all the rewards it has accumulated
+// since it was last updated
+pub(crate) fn read_mix_delegation_value(
+    storage: &dyn Storage,
+    mix_identity: IdentityKeyRef,
+    delegator_address: &[u8],
+) -> StdResult<Option<Uint128>> {
+    let amount = match mix_delegations_read(storage, mix_identity).may_load(delegator_address)? {
+        Some(amount) => amount,
+        None => return Ok(None),
+    };
+    let starting_index = mix_delegation_reward_indices_read(storage, mix_identity)
+        .may_load(delegator_address)?
+        .unwrap_or_else(Decimal::one);
+    let current_index = read_mix_reward_index(storage, mix_identity)?;
+
+    Ok(Some(apply_reward_index(
+        amount,
+        starting_index,
+        current_index,
+    )))
+}
 
-        // and for each of them increase the stake proportionally to the reward
-        for (delegator_address, amount) in delegations_chunk.into_iter() {
-            let reward = amount * scaled_reward_rate;
-            let new_amount = amount + reward;
-            total_rewarded += reward;
-            gateway_delegations(storage, gateway_identity).save(&delegator_address, &new_amount)?;
-        }
-    }
+// saves the (already settled) delegation value alongside the current reward index of the node,
+// so that only the future rewards are going to be applied to it
+pub(crate) fn save_mix_delegation(
+    storage: &mut dyn Storage,
+    mix_identity: IdentityKeyRef,
+    delegator_address: &[u8],
+    amount: Uint128,
+) -> StdResult<()> {
+    let current_index = read_mix_reward_index(storage, mix_identity)?;
+    mix_delegations(storage, mix_identity).save(delegator_address, &amount)?;
+    reverse_mix_delegations(storage, delegator_address).save(mix_identity.as_bytes(), &())?;
+    mix_delegation_reward_indices(storage, mix_identity).save(delegator_address, &current_index)
+}
+
+pub(crate) fn remove_mix_delegation(
+    storage: &mut dyn Storage,
+    mix_identity: IdentityKeyRef,
+    delegator_address: &[u8],
+) {
+    mix_delegations(storage, mix_identity).remove(delegator_address);
+    reverse_mix_delegations(storage, delegator_address).remove(mix_identity.as_bytes());
+    mix_delegation_reward_indices(storage, mix_identity).remove(delegator_address);
+    mix_delegation_unclaimed_rewards(storage, mix_identity).remove(delegator_address);
+}
 
-    Ok(total_rewarded)
+pub(crate) fn read_mix_delegation_unclaimed_reward(
+    storage: &dyn Storage,
+    mix_identity: IdentityKeyRef,
+    delegator_address: &[u8],
+) -> StdResult<Uint128> {
+    Ok(mix_delegation_unclaimed_rewards_read(storage, mix_identity)
+        .may_load(delegator_address)?
+        .unwrap_or_default())
 }
 
 // currently not used outside tests
@@ -286,6 +400,89 @@ pub fn gateways_owners_read(storage: &dyn Storage) -> ReadonlyBucket<IdentityKey
     bucket_read(storage, PREFIX_GATEWAYS_OWNERS)
 }
 
+// cumulative reward index of the node, i.e. the product of (1 + delegation reward) of all epochs
+// the node was rewarded in. Nodes that were never rewarded have the index of 1.
+pub fn gateway_reward_indices(storage: &mut dyn Storage) -> Bucket<Decimal> {
+    bucket(storage, PREFIX_GATEWAY_REWARD_INDEX)
+}
+
+pub fn gateway_reward_indices_read(storage: &dyn Storage) -> ReadonlyBucket<Decimal> {
+    bucket_read(storage, PREFIX_GATEWAY_REWARD_INDEX)
+}
+
+// helpers
+pub(crate) fn read_gateway_reward_index(
+    storage: &dyn Storage,
+    gateway_identity: IdentityKeyRef,
+) -> StdResult<Decimal> {
+    Ok(gateway_reward_indices_read(storage)
+        .may_load(gateway_identity.as_bytes())?
+        .unwrap_or_else(Decimal::one))
+}
+
+// rewards all delegators of the node at once by only increasing its cumulative reward index.
+// the actual delegation values are calculated lazily whenever they are accessed,
+// so the cost does not depend on the number of delegators
+pub(crate) fn increase_gateway_reward_index(
+    storage: &mut dyn Storage,
+    gateway_identity: IdentityKeyRef,
+    scaled_reward_rate: Decimal,
+) -> StdResult<()> {
+    let current_index = read_gateway_reward_index(storage, gateway_identity)?;
+    let new_index = compound_reward_index(current_index, scaled_reward_rate);
+    gateway_reward_indices(storage).save(gateway_identity.as_bytes(), &new_index)
+}
+
+// reads the current value of the delegation, including all the rewards it has accumulated
+// since it was last updated
+pub(crate) fn read_gateway_delegation_value(
+    storage: &dyn Storage,
+    gateway_identity: IdentityKeyRef,
+    delegator_address: &[u8],
+) -> StdResult<Option<Uint128>> {
+    let amount =
+        match gateway_delegations_read(storage, gateway_identity).may_load(delegator_address)? {
+            Some(amount) => amount,
+            None => return Ok(None),
+        };
+    let starting_index = gateway_delegation_reward_indices_read(storage, gateway_identity)
+        .may_load(delegator_address)?
+        .unwrap_or_else(Decimal::one);
+    let current_index = read_gateway_reward_index(storage, gateway_identity)?;
+
+    Ok(Some(apply_reward_index(
+        amount,
+        starting_index,
+        current_index,
+    )))
+}
+
+// saves the (already settled) delegation value alongside the current reward index of the node,
+// so that only the future rewards are going to be applied to it
+pub(crate) fn save_gateway_delegation(
+    storage: &mut dyn Storage,
+    gateway_identity: IdentityKeyRef,
+    delegator_address: &[u8],
+    amount: Uint128,
+) -> StdResult<()> {
+    let current_index = read_gateway_reward_index(storage, gateway_identity)?;
+    gateway_delegations(storage, gateway_identity).save(delegator_address, &amount)?;
+    reverse_gateway_delegations(storage, delegator_address)
+        .save(gateway_identity.as_bytes(), &())?;
+    gateway_delegation_reward_indices(storage, gateway_identity)
+        .save(delegator_address, &current_index)
+}
+
+pub(crate) fn remove_gateway_delegation(
+    storage: &mut dyn Storage,
+    gateway_identity: IdentityKeyRef,
+    delegator_address: &[u8],
+) {
+    gateway_delegations(storage, gateway_identity).remove(delegator_address);
+    reverse_gateway_delegations(storage, delegator_address).remove(gateway_identity.as_bytes());
+    gateway_delegation_reward_indices(storage, gateway_identity).remove(delegator_address);
+}
+
 // delegation related
 pub fn mix_delegations<'a>(
     storage: &'a mut dyn Storage,
@@ -321,6 +518,229 @@ pub fn gateway_delegations_read<'a>(
     )
 }
 
+// secondary index of all nodes the particular address delegated to, keyed by node identity.
+// the delegated amounts themselves are only stored in the primary, per-node, buckets
+pub fn reverse_mix_delegations<'a>(
+    storage: &'a mut dyn Storage,
+    delegator_address: &[u8],
+) -> Bucket<'a, ()> {
+    Bucket::multilevel(storage, &[PREFIX_REVERSE_MIX_DELEGATION, delegator_address])
+}
+
+pub fn reverse_mix_delegations_read<'a>(
+    storage: &'a dyn Storage,
+    delegator_address: &[u8],
+) -> ReadonlyBucket<'a, ()> {
+    ReadonlyBucket::multilevel(storage, &[PREFIX_REVERSE_MIX_DELEGATION, delegator_address])
+}
+
+pub fn reverse_gateway_delegations<'a>(
+    storage: &'a mut dyn Storage,
+    delegator_address: &[u8],
+) -> Bucket<'a, ()> {
+    Bucket::multilevel(
+        storage,
+        &[PREFIX_REVERSE_GATEWAY_DELEGATION, delegator_address],
+    )
+}
+
+pub fn reverse_gateway_delegations_read<'a>(
+    storage: &'a dyn Storage,
+    delegator_address: &[u8],
+) -> ReadonlyBucket<'a, ()> {
+    ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_REVERSE_GATEWAY_DELEGATION, delegator_address],
+    )
+}
+
+// reward index of the node at the time the delegation was last updated.
+// delegations without the stored index were made when the node had the initial index of 1
+pub fn mix_delegation_reward_indices<'a>(
+    storage: &'a mut dyn Storage,
+    mix_identity: IdentityKeyRef,
+) -> Bucket<'a, Decimal> {
+    Bucket::multilevel(
+        storage,
+        &[PREFIX_MIX_DELEGATION_INDEX, mix_identity.as_bytes()],
+    )
+}
+
+pub fn mix_delegation_reward_indices_read<'a>(
+    storage: &'a dyn Storage,
+    mix_identity: IdentityKeyRef,
+) -> ReadonlyBucket<'a, Decimal> {
+    ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_MIX_DELEGATION_INDEX, mix_identity.as_bytes()],
+    )
+}
+
+// rewards accumulated by the delegation before it was increased, which no longer earn any further
+// rewards and are waiting to be claimed
+pub fn mix_delegation_unclaimed_rewards<'a>(
+    storage: &'a mut dyn Storage,
+    mix_identity: IdentityKeyRef,
+) -> Bucket<'a, Uint128> {
+    Bucket::multilevel(
+        storage,
+        &[
+            PREFIX_MIX_DELEGATION_UNCLAIMED_REWARD,
+            mix_identity.as_bytes(),
+        ],
+    )
+}
+
+pub fn mix_delegation_unclaimed_rewards_read<'a>(
+    storage: &'a dyn Storage,
+    mix_identity: IdentityKeyRef,
+) -> ReadonlyBucket<'a, Uint128> {
+    ReadonlyBucket::multilevel(
+        storage,
+        &[
+            PREFIX_MIX_DELEGATION_UNCLAIMED_REWARD,
+            mix_identity.as_bytes(),
+        ],
+    )
+}
+
+pub fn gateway_delegation_reward_indices<'a>(
+    storage: &'a mut dyn Storage,
+    gateway_identity: IdentityKeyRef,
+) -> Bucket<'a, Decimal> {
+    Bucket::multilevel(
+        storage,
+        &[PREFIX_GATEWAY_DELEGATION_INDEX, gateway_identity.as_bytes()],
+    )
+}
+
+pub fn gateway_delegation_reward_indices_read<'a>(
+    storage: &'a dyn Storage,
+    gateway_identity: IdentityKeyRef,
+) -> ReadonlyBucket<'a, Decimal> {
+    ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_GATEWAY_DELEGATION_INDEX, gateway_identity.as_bytes()],
+    )
+}
+
+// Pending withdrawals related
+
+fn pending_withdrawal_id(storage: &mut dyn Storage) -> Singleton<u64> {
+    singleton(storage, PENDING_WITHDRAWAL_ID_KEY)
+}
+
+// returns a new, unique, identifier for a pending withdrawal
+pub(crate) fn next_pending_withdrawal_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = pending_withdrawal_id(storage)
+        .may_load()?
+        .unwrap_or_default()
+        + 1;
+    pending_withdrawal_id(storage).save(&id)?;
+    Ok(id)
+}
+
+// withdrawals are keyed by their big-endian ids, so that they are iterated in creation order
+pub fn pending_withdrawals<'a>(
+    storage: &'a mut dyn Storage,
+    owner: &Addr,
+) -> Bucket<'a, PendingWithdrawal> {
+    Bucket::multilevel(storage, &[PREFIX_PENDING_WITHDRAWALS, owner.as_bytes()])
+}
+
+pub fn pending_withdrawals_read<'a>(
+    storage: &'a dyn Storage,
+    owner: &Addr,
+) -> ReadonlyBucket<'a, PendingWithdrawal> {
+    ReadonlyBucket::multilevel(storage, &[PREFIX_PENDING_WITHDRAWALS, owner.as_bytes()])
+}
+
+// Misbehaviour related
+
+// total amount of the slashed stake that has not yet been withdrawn from the contract
+pub fn slashed_funds(storage: &mut dyn Storage) -> Singleton<Uint128> {
+    singleton(storage, SLASHED_FUNDS_KEY)
+}
+
+pub fn slashed_funds_read(storage: &dyn Storage) -> ReadonlySingleton<Uint128> {
+    singleton_read(storage, SLASHED_FUNDS_KEY)
+}
+
+// nothing is stored until the first node gets slashed
+pub(crate) fn read_slashed_funds(storage: &dyn Storage) -> StdResult<Uint128> {
+    Ok(slashed_funds_read(storage).may_load()?.unwrap_or_default())
+}
+
+pub fn mix_misbehaviour_streaks(storage: &mut dyn Storage) -> Bucket<MisbehaviourStreak> {
+    bucket(storage, PREFIX_MIX_MISBEHAVIOUR_STREAKS)
+}
+
+pub fn mix_misbehaviour_streaks_read(storage: &dyn Storage) -> ReadonlyBucket<MisbehaviourStreak> {
+    bucket_read(storage, PREFIX_MIX_MISBEHAVIOUR_STREAKS)
+}
+
+// history of the reports is keyed by the big-endian epoch numbers, so that it's iterated in order
+pub fn mix_misbehaviour<'a>(
+    storage: &'a mut dyn Storage,
+    mix_identity: IdentityKeyRef,
+) -> Bucket<'a, MixnodeMisbehaviour> {
+    Bucket::multilevel(storage, &[PREFIX_MIX_MISBEHAVIOUR, mix_identity.as_bytes()])
+}
+
+pub fn mix_misbehaviour_read<'a>(
+    storage: &'a dyn Storage,
+    mix_identity: IdentityKeyRef,
+) -> ReadonlyBucket<'a, MixnodeMisbehaviour> {
+    ReadonlyBucket::multilevel(storage, &[PREFIX_MIX_MISBEHAVIOUR, mix_identity.as_bytes()])
+}
+
+// Governance related
+
+pub fn governance(storage: &mut dyn Storage) -> Singleton<GovernanceConfig> {
+    singleton(storage, GOVERNANCE_KEY)
+}
+
+pub fn governance_read(storage: &dyn Storage) -> ReadonlySingleton<GovernanceConfig> {
+    singleton_read(storage, GOVERNANCE_KEY)
+}
+
+fn proposal_id(storage: &mut dyn Storage) -> Singleton<ProposalId> {
+    singleton(storage, PROPOSAL_ID_KEY)
+}
+
+// returns a new, unique, identifier for a proposal
+pub(crate) fn next_proposal_id(storage: &mut dyn Storage) -> StdResult<ProposalId> {
+    let id = proposal_id(storage).may_load()?.unwrap_or_default() + 1;
+    proposal_id(storage).save(&id)?;
+    Ok(id)
+}
+
+// proposals are keyed by their big-endian ids, so that they are iterated in submission order
+pub fn proposals(storage: &mut dyn Storage) -> Bucket<Proposal> {
+    bucket(storage, PREFIX_PROPOSALS)
+}
+
+pub fn proposals_read(storage: &dyn Storage) -> ReadonlyBucket<Proposal> {
+    bucket_read(storage, PREFIX_PROPOSALS)
+}
+
+pub fn proposal_votes(storage: &mut dyn Storage, proposal_id: ProposalId) -> Bucket<VoteOption> {
+    Bucket::multilevel(
+        storage,
+        &[PREFIX_PROPOSAL_VOTES, &proposal_id.to_be_bytes()],
+    )
+}
+
+pub fn proposal_votes_read(
+    storage: &dyn Storage,
+    proposal_id: ProposalId,
+) -> ReadonlyBucket<VoteOption> {
+    ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_PROPOSAL_VOTES, &proposal_id.to_be_bytes()],
+    )
+}
+
 // currently not used outside tests
 #[cfg(test)]
 pub(crate) fn read_gateway_bond(
@@ -400,6 +820,10 @@ mod tests {
             total_delegation: coin(0, DENOM),
             owner: node_owner.clone(),
             layer: Layer::One,
+            profit_margin_percent: 0,
+            family: None,
+            jailed: false,
+            jailed_until: 0,
             mix_node: MixNode {
                 identity_key: node_identity.clone(),
                 ..mix_node_fixture()
@@ -450,65 +874,46 @@ mod tests {
     }
 
     #[cfg(test)]
-    mod increasing_mix_delegated_stakes {
+    mod mix_reward_index {
         use super::*;
-        use crate::queries::query_mixnode_delegations_paged;
-        use cosmwasm_std::testing::mock_dependencies;
 
         #[test]
-        fn when_there_are_no_delegations() {
-            let mut deps = mock_dependencies(&[]);
+        fn is_initially_one() {
+            let storage = MockStorage::new();
             let node_identity: IdentityKey = "nodeidentity".into();
 
-            // 0.001
-            let reward = Decimal::from_ratio(1u128, 1000u128);
-
-            let total_increase =
-                increase_mix_delegated_stakes(&mut deps.storage, node_identity.as_ref(), reward)
-                    .unwrap();
-
-            // there was no increase
-            assert!(total_increase.is_zero());
-
-            // there are no 'new' delegations magically added
-            assert!(
-                query_mixnode_delegations_paged(deps.as_ref(), node_identity, None, None)
-                    .unwrap()
-                    .delegations
-                    .is_empty()
-            )
+            assert_eq!(
+                Decimal::one(),
+                read_mix_reward_index(&storage, &node_identity).unwrap()
+            );
+            assert!(read_mix_delegation_value(&storage, &node_identity, b"bob")
+                .unwrap()
+                .is_none());
         }
 
         #[test]
-        fn when_there_is_a_single_delegation() {
-            let mut deps = mock_dependencies(&[]);
+        fn applies_to_delegations_without_stored_index() {
+            let mut storage = MockStorage::new();
             let node_identity: IdentityKey = "nodeidentity".into();
 
             // 0.001
             let reward = Decimal::from_ratio(1u128, 1000u128);
 
-            let delegator_address = Addr::unchecked("bob");
-            mix_delegations(&mut deps.storage, &node_identity)
-                .save(delegator_address.as_bytes(), &Uint128(1000))
+            mix_delegations(&mut storage, &node_identity)
+                .save(b"bob", &Uint128(1000))
                 .unwrap();
 
-            let total_increase =
-                increase_mix_delegated_stakes(&mut deps.storage, node_identity.as_ref(), reward)
-                    .unwrap();
-
-            assert_eq!(Uint128(1), total_increase);
+            increase_mix_reward_index(&mut storage, &node_identity, reward).unwrap();
 
             assert_eq!(
-                Uint128(1001),
-                mix_delegations_read(&mut deps.storage, &node_identity)
-                    .load(delegator_address.as_bytes())
-                    .unwrap()
-            )
+                Some(Uint128(1001)),
+                read_mix_delegation_value(&storage, &node_identity, b"bob").unwrap()
+            );
         }
 
         #[test]
-        fn when_there_are_multiple_delegations() {
-            let mut deps = mock_dependencies(&[]);
+        fn does_not_modify_stored_delegations() {
+            let mut storage = MockStorage::new();
             let node_identity: IdentityKey = "nodeidentity".into();
 
             // 0.001
@@ -516,130 +921,116 @@ mod tests {
 
             for i in 0..100 {
                 let delegator_address = Addr::unchecked(format!("address{}", i));
-                mix_delegations(&mut deps.storage, &node_identity)
-                    .save(delegator_address.as_bytes(), &Uint128(1000))
-                    .unwrap();
+                save_mix_delegation(
+                    &mut storage,
+                    &node_identity,
+                    delegator_address.as_bytes(),
+                    Uint128(1000),
+                )
+                .unwrap();
             }
 
-            let total_increase =
-                increase_mix_delegated_stakes(&mut deps.storage, node_identity.as_ref(), reward)
-                    .unwrap();
-
-            assert_eq!(Uint128(100), total_increase);
+            increase_mix_reward_index(&mut storage, &node_identity, reward).unwrap();
 
             for i in 0..100 {
                 let delegator_address = Addr::unchecked(format!("address{}", i));
                 assert_eq!(
-                    Uint128(1001),
-                    mix_delegations_read(&mut deps.storage, &node_identity)
+                    Uint128(1000),
+                    mix_delegations_read(&storage, &node_identity)
                         .load(delegator_address.as_bytes())
                         .unwrap()
-                )
+                );
+                assert_eq!(
+                    Some(Uint128(1001)),
+                    read_mix_delegation_value(
+                        &storage,
+                        &node_identity,
+                        delegator_address.as_bytes()
+                    )
+                    .unwrap()
+                );
             }
         }
 
         #[test]
-        fn when_there_are_more_delegations_than_page_size() {
-            let mut deps = mock_dependencies(&[]);
+        fn only_applies_rewards_given_after_delegating() {
+            let mut storage = MockStorage::new();
             let node_identity: IdentityKey = "nodeidentity".into();
 
             // 0.001
             let reward = Decimal::from_ratio(1u128, 1000u128);
 
-            for i in 0..queries::DELEGATION_PAGE_MAX_LIMIT * 10 {
-                let delegator_address = Addr::unchecked(format!("address{}", i));
-                mix_delegations(&mut deps.storage, &node_identity)
-                    .save(delegator_address.as_bytes(), &Uint128(1000))
-                    .unwrap();
-            }
+            save_mix_delegation(&mut storage, &node_identity, b"bob", Uint128(1000)).unwrap();
+            increase_mix_reward_index(&mut storage, &node_identity, reward).unwrap();
 
-            let total_increase =
-                increase_mix_delegated_stakes(&mut deps.storage, node_identity.as_ref(), reward)
-                    .unwrap();
+            save_mix_delegation(&mut storage, &node_identity, b"alice", Uint128(1000)).unwrap();
+            increase_mix_reward_index(&mut storage, &node_identity, reward).unwrap();
 
+            // 1000 * 1.001 * 1.001
+            assert_eq!(
+                Some(Uint128(1002)),
+                read_mix_delegation_value(&storage, &node_identity, b"bob").unwrap()
+            );
+            // 1000 * 1.001
             assert_eq!(
-                Uint128(queries::DELEGATION_PAGE_MAX_LIMIT as u128 * 10),
-                total_increase
+                Some(Uint128(1001)),
+                read_mix_delegation_value(&storage, &node_identity, b"alice").unwrap()
             );
 
-            for i in 0..queries::DELEGATION_PAGE_MAX_LIMIT * 10 {
-                let delegator_address = Addr::unchecked(format!("address{}", i));
-                assert_eq!(
-                    Uint128(1001),
-                    mix_delegations_read(&mut deps.storage, &node_identity)
-                        .load(delegator_address.as_bytes())
-                        .unwrap()
-                )
-            }
+            remove_mix_delegation(&mut storage, &node_identity, b"bob");
+            assert!(read_mix_delegation_value(&storage, &node_identity, b"bob")
+                .unwrap()
+                .is_none());
+            assert!(mix_delegation_reward_indices_read(&storage, &node_identity)
+                .may_load(b"bob")
+                .unwrap()
+                .is_none());
         }
     }
 
     #[cfg(test)]
-    mod increasing_gateway_delegated_stakes {
+    mod gateway_reward_index {
         use super::*;
-        use crate::queries::query_gateway_delegations_paged;
-        use cosmwasm_std::testing::mock_dependencies;
 
         #[test]
-        fn when_there_are_no_delegations() {
-            let mut deps = mock_dependencies(&[]);
+        fn is_initially_one() {
+            let storage = MockStorage::new();
             let node_identity: IdentityKey = "nodeidentity".into();
 
-            // 0.001
-            let reward = Decimal::from_ratio(1u128, 1000u128);
-
-            let total_increase = increase_gateway_delegated_stakes(
-                &mut deps.storage,
-                node_identity.as_ref(),
-                reward,
-            )
-            .unwrap();
-
-            // there was no increase
-            assert!(total_increase.is_zero());
-
-            // there are no 'new' delegations magically added
+            assert_eq!(
+                Decimal::one(),
+                read_gateway_reward_index(&storage, &node_identity).unwrap()
+            );
             assert!(
-                query_gateway_delegations_paged(deps.as_ref(), node_identity, None, None)
+                read_gateway_delegation_value(&storage, &node_identity, b"bob")
                     .unwrap()
-                    .delegations
-                    .is_empty()
-            )
+                    .is_none()
+            );
         }
 
         #[test]
-        fn when_there_is_a_single_delegation() {
-            let mut deps = mock_dependencies(&[]);
+        fn applies_to_delegations_without_stored_index() {
+            let mut storage = MockStorage::new();
             let node_identity: IdentityKey = "nodeidentity".into();
 
             // 0.001
             let reward = Decimal::from_ratio(1u128, 1000u128);
 
-            let delegator_address = Addr::unchecked("bob");
-            gateway_delegations(&mut deps.storage, &node_identity)
-                .save(delegator_address.as_bytes(), &Uint128(1000))
+            gateway_delegations(&mut storage, &node_identity)
+                .save(b"bob", &Uint128(1000))
                 .unwrap();
 
-            let total_increase = increase_gateway_delegated_stakes(
-                &mut deps.storage,
-                node_identity.as_ref(),
-                reward,
-            )
-            .unwrap();
-
-            assert_eq!(Uint128(1), total_increase);
+            increase_gateway_reward_index(&mut storage, &node_identity, reward).unwrap();
 
             assert_eq!(
-                Uint128(1001),
-                gateway_delegations_read(&mut deps.storage, &node_identity)
-                    .load(delegator_address.as_bytes())
-                    .unwrap()
-            )
+                Some(Uint128(1001)),
+                read_gateway_delegation_value(&storage, &node_identity, b"bob").unwrap()
+            );
         }
 
         #[test]
-        fn when_there_are_multiple_delegations() {
-            let mut deps = mock_dependencies(&[]);
+        fn does_not_modify_stored_delegations() {
+            let mut storage = MockStorage::new();
             let node_identity: IdentityKey = "nodeidentity".into();
 
             // 0.001
@@ -647,67 +1038,74 @@ mod tests {
 
             for i in 0..100 {
                 let delegator_address = Addr::unchecked(format!("address{}", i));
-                gateway_delegations(&mut deps.storage, &node_identity)
-                    .save(delegator_address.as_bytes(), &Uint128(1000))
-                    .unwrap();
+                save_gateway_delegation(
+                    &mut storage,
+                    &node_identity,
+                    delegator_address.as_bytes(),
+                    Uint128(1000),
+                )
+                .unwrap();
             }
 
-            let total_increase = increase_gateway_delegated_stakes(
-                &mut deps.storage,
-                node_identity.as_ref(),
-                reward,
-            )
-            .unwrap();
-
-            assert_eq!(Uint128(100), total_increase);
+            increase_gateway_reward_index(&mut storage, &node_identity, reward).unwrap();
 
             for i in 0..100 {
                 let delegator_address = Addr::unchecked(format!("address{}", i));
                 assert_eq!(
-                    Uint128(1001),
-                    gateway_delegations_read(&mut deps.storage, &node_identity)
+                    Uint128(1000),
+                    gateway_delegations_read(&storage, &node_identity)
                         .load(delegator_address.as_bytes())
                         .unwrap()
-                )
+                );
+                assert_eq!(
+                    Some(Uint128(1001)),
+                    read_gateway_delegation_value(
+                        &storage,
+                        &node_identity,
+                        delegator_address.as_bytes()
+                    )
+                    .unwrap()
+                );
             }
         }
 
         #[test]
-        fn when_there_are_more_delegations_than_page_size() {
-            let mut deps = mock_dependencies(&[]);
+        fn only_applies_rewards_given_after_delegating() {
+            let mut storage = MockStorage::new();
             let node_identity: IdentityKey = "nodeidentity".into();
 
             // 0.001
             let reward = Decimal::from_ratio(1u128, 1000u128);
 
-            for i in 0..queries::DELEGATION_PAGE_MAX_LIMIT * 10 {
-                let delegator_address = Addr::unchecked(format!("address{}", i));
-                gateway_delegations(&mut deps.storage, &node_identity)
-                    .save(delegator_address.as_bytes(), &Uint128(1000))
-                    .unwrap();
-            }
+            save_gateway_delegation(&mut storage, &node_identity, b"bob", Uint128(1000)).unwrap();
+            increase_gateway_reward_index(&mut storage, &node_identity, reward).unwrap();
 
-            let total_increase = increase_gateway_delegated_stakes(
-                &mut deps.storage,
-                node_identity.as_ref(),
-                reward,
-            )
-            .unwrap();
+            save_gateway_delegation(&mut storage, &node_identity, b"alice", Uint128(1000)).unwrap();
+            increase_gateway_reward_index(&mut storage, &node_identity, reward).unwrap();
 
+            // 1000 * 1.001 * 1.001
             assert_eq!(
-                Uint128(queries::DELEGATION_PAGE_MAX_LIMIT as u128 * 10),
-                total_increase
+                Some(Uint128(1002)),
+                read_gateway_delegation_value(&storage, &node_identity, b"bob").unwrap()
+            );
+            // 1000 * 1.001
+            assert_eq!(
+                Some(Uint128(1001)),
+                read_gateway_delegation_value(&storage, &node_identity, b"alice").unwrap()
             );
 
-            for i in 0..queries::DELEGATION_PAGE_MAX_LIMIT * 10 {
-                let delegator_address = Addr::unchecked(format!("address{}", i));
-                assert_eq!(
-                    Uint128(1001),
-                    gateway_delegations_read(&mut deps.storage, &node_identity)
-                        .load(delegator_address.as_bytes())
-                        .unwrap()
-                )
-            }
+            remove_gateway_delegation(&mut storage, &node_identity, b"bob");
+            assert!(
+                read_gateway_delegation_value(&storage, &node_identity, b"bob")
+                    .unwrap()
+                    .is_none()
+            );
+            assert!(
+                gateway_delegation_reward_indices_read(&storage, &node_identity)
+                    .may_load(b"bob")
+                    .unwrap()
+                    .is_none()
+            );
         }
     }
 }
@@ -3,8 +3,10 @@ pub mod helpers {
     use super::*;
     use crate::contract::query;
     use crate::contract::{instantiate, INITIAL_MIXNODE_BOND};
-    use crate::transactions::{try_add_gateway, try_add_mixnode};
+    use crate::storage::config_read;
+    use crate::transactions::{try_add_gateway, try_add_mixnode, try_advance_epoch};
     use config::defaults::DENOM;
+    use cosmwasm_std::attr;
     use cosmwasm_std::coin;
     use cosmwasm_std::from_binary;
     use cosmwasm_std::testing::mock_dependencies;
@@ -15,6 +17,7 @@ pub mod helpers {
     use cosmwasm_std::testing::MockStorage;
     use cosmwasm_std::Addr;
     use cosmwasm_std::Coin;
+    use cosmwasm_std::Env;
     use cosmwasm_std::OwnedDeps;
     use cosmwasm_std::{Empty, MemoryStorage};
     use mixnet_contract::{
@@ -36,11 +39,30 @@ pub mod helpers {
                 identity_key: key.clone(),
                 ..helpers::mix_node_fixture()
             },
+            0,
         )
         .unwrap();
         key
     }
 
+    // keeps advancing the epoch until the next active set is selected,
+    // returning the number of transactions it took
+    pub fn advance_epoch(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        env: Env,
+    ) -> usize {
+        let state = config_read(&deps.storage).load().unwrap();
+        let info = mock_info(state.network_monitor_address.as_ref(), &[]);
+        let mut transactions = 0;
+        loop {
+            transactions += 1;
+            let res = try_advance_epoch(deps.as_mut(), env.clone(), info.clone()).unwrap();
+            if res.attributes.contains(&attr("selection_finished", true)) {
+                return transactions;
+            }
+        }
+    }
+
     pub fn get_mix_nodes(
         deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
     ) -> Vec<MixNodeBond> {
@@ -129,6 +151,7 @@ pub mod helpers {
             coin(50, DENOM),
             Addr::unchecked("foo"),
             Layer::One,
+            10,
             mix_node,
         )
     }
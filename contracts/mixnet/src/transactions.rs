@@ -2,20 +2,45 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error::ContractError;
-use crate::helpers::{calculate_epoch_reward_rate, scale_reward_by_uptime};
+use crate::helpers::{
+    apply_reward_index, calculate_epoch_end, calculate_epoch_reward_rate, deduct_profit_margin,
+    scale_reward_by_uptime,
+};
 use crate::queries;
+use crate::state::{ActiveSet, ActiveSetSelection, MisbehaviourStreak};
 use crate::storage::*;
 use config::defaults::DENOM;
 use cosmwasm_std::{
-    attr, coins, BankMsg, Coin, Decimal, DepsMut, MessageInfo, Order, Response, StdResult, Uint128,
+    coin, coins, Addr, Api, BankMsg, Coin, Decimal, DepsMut, Env, MessageInfo, Order, Response,
+    StdResult, Storage, Uint128,
 };
 use cosmwasm_storage::ReadonlyBucket;
+use mixnet_contract::events;
 use mixnet_contract::{
-    Gateway, GatewayBond, IdentityKey, Layer, MixNode, MixNodeBond, StateParams,
+    family_join_message, Gateway, GatewayBond, GovernanceConfig, IdentityKey, Layer,
+    LayerDistribution, MixNode, MixNodeBond, MixnodeMisbehaviour, PendingWithdrawal, Proposal,
+    ProposalAction, ProposalId, ProposalStatus, StateParams, VoteOption,
 };
 
 const OLD_DELEGATIONS_CHUNK_SIZE: usize = 500;
 
+// upper bound on the number of mixnodes in the active set, as the layers of all of them have to
+// be assigned, and the whole set is stored, at the end of every epoch
+pub(crate) const MAX_MIXNODE_ACTIVE_SET_SIZE: u32 = 1000;
+
+// bounds on how quickly operators can change the commission taken from their delegators,
+// so that the delegators have a chance to react before the margin gets much higher
+pub(crate) const MAX_PROFIT_MARGIN_CHANGE: u8 = 10;
+pub(crate) const PROFIT_MARGIN_UPDATE_COOLDOWN_EPOCHS: u32 = 1;
+
+// families can't grow without bounds, as all of their members have to be updated
+// once the family gets dissolved
+pub(crate) const MAX_FAMILY_MEMBERS: usize = 50;
+
+// bounds the number of withdrawals looked at, and paid out, in a single claim
+const WITHDRAWAL_CLAIM_DEFAULT_LIMIT: u32 = 50;
+const WITHDRAWAL_CLAIM_MAX_LIMIT: u32 = 100;
+
 // Looks for the total amount of delegations towards a particular node.
 // This function is used only in very specific circumstances:
 // 1. The mixnode/gateway bonds
@@ -23,7 +48,14 @@ const OLD_DELEGATIONS_CHUNK_SIZE: usize = 500;
 // 3. The node unbonds
 // 4. Some of the addresses that delegated in the past have not removed the delegation yet
 // 5. The node rebonds with the same identity
-fn find_old_delegations(delegations_bucket: ReadonlyBucket<Uint128>) -> StdResult<Coin> {
+//
+// The stored delegation values are scaled by the growth of the node's reward index since each
+// of them was made, so that the rewards received before the node unbonded are included.
+fn find_old_delegations(
+    delegations_bucket: ReadonlyBucket<Uint128>,
+    delegation_indices_bucket: ReadonlyBucket<Decimal>,
+    current_index: Decimal,
+) -> StdResult<Coin> {
     // I think it's incredibly unlikely to ever read more than that
     // but in case we do, we should guard ourselves against possible
     // out of memory errors (wasm contracts can only allocate at most 2MB
@@ -45,8 +77,11 @@ fn find_old_delegations(delegations_bucket: ReadonlyBucket<Uint128>) -> StdResul
                 continue;
             }
 
-            let value = delegation?.1;
-            total_delegation.amount += value;
+            let (delegator_address, amount) = delegation?;
+            let starting_index = delegation_indices_bucket
+                .may_load(&delegator_address)?
+                .unwrap_or_else(Decimal::one);
+            total_delegation.amount += apply_reward_index(amount, starting_index, current_index);
         }
 
         if iterated <= OLD_DELEGATIONS_CHUNK_SIZE {
@@ -84,10 +119,110 @@ fn validate_mixnode_bond(bond: &[Coin], minimum_bond: Uint128) -> Result<(), Con
     Ok(())
 }
 
+// limits on the node information stored alongside each bond, so that it stays usable
+// by the clients building the topology and can't be used to bloat the contract storage
+const MAX_NODE_HOST_LENGTH: usize = 255;
+const MAX_NODE_VERSION_LENGTH: usize = 32;
+const MAX_NODE_KEY_LENGTH: usize = 64;
+const MAX_GATEWAY_LOCATION_LENGTH: usize = 64;
+
+fn validate_node_host(host: &str) -> Result<(), ContractError> {
+    if host.is_empty()
+        || host.len() > MAX_NODE_HOST_LENGTH
+        || host.chars().any(|c| c.is_whitespace() || c.is_control())
+    {
+        return Err(ContractError::InvalidNodeHost {
+            host: host.to_string(),
+            max: MAX_NODE_HOST_LENGTH,
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_node_version(version: &str) -> Result<(), ContractError> {
+    if version.is_empty()
+        || version.len() > MAX_NODE_VERSION_LENGTH
+        || !version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+')
+    {
+        return Err(ContractError::InvalidNodeVersion {
+            version: version.to_string(),
+            max: MAX_NODE_VERSION_LENGTH,
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_node_key(key_name: &str, key: &str) -> Result<(), ContractError> {
+    if key.is_empty() || key.len() > MAX_NODE_KEY_LENGTH {
+        return Err(ContractError::InvalidNodeKey {
+            key_name: key_name.to_string(),
+            key: key.to_string(),
+            max: MAX_NODE_KEY_LENGTH,
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_node_port(port_name: &str, port: u16) -> Result<(), ContractError> {
+    if port == 0 {
+        return Err(ContractError::ZeroNodePort {
+            port_name: port_name.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+// used both when bonding and when updating the configuration of an already bonded node
+fn validate_mixnode_config(mix_node: &MixNode) -> Result<(), ContractError> {
+    validate_node_host(&mix_node.host)?;
+    validate_node_port("mix", mix_node.mix_port)?;
+    validate_node_port("verloc", mix_node.verloc_port)?;
+    validate_node_port("http api", mix_node.http_api_port)?;
+    validate_node_key("identity", &mix_node.identity_key)?;
+    validate_node_key("sphinx", &mix_node.sphinx_key)?;
+    validate_node_version(&mix_node.version)
+}
+
+// used both when bonding and when updating the configuration of an already bonded gateway
+fn validate_gateway_config(gateway: &Gateway) -> Result<(), ContractError> {
+    validate_node_host(&gateway.host)?;
+    validate_node_port("mix", gateway.mix_port)?;
+    validate_node_port("clients", gateway.clients_port)?;
+    validate_node_key("identity", &gateway.identity_key)?;
+    validate_node_key("sphinx", &gateway.sphinx_key)?;
+    validate_node_version(&gateway.version)?;
+
+    if gateway.location.len() > MAX_GATEWAY_LOCATION_LENGTH {
+        return Err(ContractError::InvalidGatewayLocation {
+            location: gateway.location.clone(),
+            max: MAX_GATEWAY_LOCATION_LENGTH,
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_profit_margin(profit_margin_percent: u8) -> Result<(), ContractError> {
+    if profit_margin_percent > 100 {
+        return Err(ContractError::InvalidProfitMargin {
+            value: profit_margin_percent,
+        });
+    }
+
+    Ok(())
+}
+
 pub(crate) fn try_add_mixnode(
     deps: DepsMut,
     info: MessageInfo,
     mix_node: MixNode,
+    profit_margin_percent: u8,
 ) -> Result<Response, ContractError> {
     let sender_bytes = info.sender.as_bytes();
 
@@ -99,13 +234,11 @@ pub(crate) fn try_add_mixnode(
         return Err(ContractError::AlreadyOwnsGateway);
     }
 
-    let mut was_present = false;
     // if the client has an active mixnode with a different identity, don't allow bonding
     if let Some(existing_node) = mixnodes_owners_read(deps.storage).may_load(sender_bytes)? {
         if existing_node != mix_node.identity_key {
             return Err(ContractError::AlreadyOwnsMixnode);
         }
-        was_present = true
     }
 
     // check if somebody else has already bonded a mixnode with this identity
@@ -121,15 +254,27 @@ pub(crate) fn try_add_mixnode(
 
     let minimum_bond = read_state_params(deps.storage).minimum_mixnode_bond;
     validate_mixnode_bond(&info.funds, minimum_bond)?;
+    validate_profit_margin(profit_margin_percent)?;
+    validate_mixnode_config(&mix_node)?;
 
     let layer_distribution = queries::query_layer_distribution(deps.as_ref());
     let layer = layer_distribution.choose_with_fewest();
 
-    let mut bond = MixNodeBond::new(info.funds[0].clone(), info.sender.clone(), layer, mix_node);
+    let mut bond = MixNodeBond::new(
+        info.funds[0].clone(),
+        info.sender.clone(),
+        layer,
+        profit_margin_percent,
+        mix_node,
+    );
 
     // this might potentially require more gas if a significant number of delegations was there
     let delegations_bucket = mix_delegations_read(deps.storage, &bond.mix_node.identity_key);
-    let existing_delegation = find_old_delegations(delegations_bucket)?;
+    let delegation_indices_bucket =
+        mix_delegation_reward_indices_read(deps.storage, &bond.mix_node.identity_key);
+    let current_index = read_mix_reward_index(deps.storage, &bond.mix_node.identity_key)?;
+    let existing_delegation =
+        find_old_delegations(delegations_bucket, delegation_indices_bucket, current_index)?;
     bond.total_delegation = existing_delegation;
 
     let identity = bond.identity();
@@ -138,7 +283,12 @@ pub(crate) fn try_add_mixnode(
     mixnodes_owners(deps.storage).save(sender_bytes, identity)?;
     increment_layer_count(deps.storage, bond.layer)?;
 
-    let attributes = vec![attr("overwritten", was_present)];
+    let attributes = events::new_mixnode_bonding_event(
+        &info.sender,
+        identity,
+        bond.bond_amount.amount,
+        read_current_epoch(deps.storage)?,
+    );
     Ok(Response {
         submessages: Vec::new(),
         messages: Vec::new(),
@@ -147,8 +297,27 @@ pub(crate) fn try_add_mixnode(
     })
 }
 
+// locks the funds released by unbonding or undelegating until the unbonding period passes,
+// so that the stake could not be moved around just before rewarding or topology snapshots
+fn create_pending_withdrawal(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: Addr,
+    amount: Uint128,
+) -> StdResult<PendingWithdrawal> {
+    let unbonding_period = read_state_params(storage).unbonding_period;
+    let available_at = env.block.time.seconds() + unbonding_period as u64 * 60 * 60;
+
+    let id = next_pending_withdrawal_id(storage)?;
+    let withdrawal = PendingWithdrawal::new(id, owner, coin(amount.u128(), DENOM), available_at);
+    pending_withdrawals(storage, &withdrawal.owner).save(&id.to_be_bytes(), &withdrawal)?;
+
+    Ok(withdrawal)
+}
+
 pub(crate) fn try_remove_mixnode(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
     let sender_bytes = info.sender.as_bytes();
@@ -162,22 +331,403 @@ pub(crate) fn try_remove_mixnode(
     // get the bond, since we found associated identity, the node MUST exist
     let mixnode_bond = mixnodes_read(deps.storage).load(mix_identity.as_bytes())?;
 
-    // send bonded funds back to the bond owner
-    let messages = vec![BankMsg::Send {
-        to_address: info.sender.as_str().to_owned(),
-        amount: vec![mixnode_bond.bond_amount()],
+    // otherwise the jail could be escaped by rebonding the same identity with a clean bond
+    if mixnode_bond.jailed {
+        return Err(ContractError::MixnodeJailed {
+            identity: mix_identity,
+        });
     }
-    .into()];
 
-    // remove the bond from the list of bonded mixnodes
+    // the operator rewards that were not claimed yet are returned alongside the bond
+    let unclaimed_reward = mix_operator_rewards_read(deps.storage)
+        .may_load(mix_identity.as_bytes())?
+        .unwrap_or_default();
+
+    // the bonded funds can be withdrawn by the bond owner once the unbonding period is over
+    let withdrawal = create_pending_withdrawal(
+        deps.storage,
+        &env,
+        info.sender.clone(),
+        mixnode_bond.bond_amount.amount + unclaimed_reward,
+    )?;
+
+    // remove the bond from the list of bonded mixnodes,
+    // so that it immediately stops being taken into consideration for the topology
     mixnodes(deps.storage).remove(mix_identity.as_bytes());
+    // and any rewards it had accumulated
+    mix_operator_rewards(deps.storage).remove(mix_identity.as_bytes());
+    // and its misbehaviour streak, the history of reports is kept
+    mix_misbehaviour_streaks(deps.storage).remove(mix_identity.as_bytes());
+    // a rebonded node can choose any profit margin anyway
+    mix_profit_margin_updates(deps.storage).remove(mix_identity.as_bytes());
+    // and it doesn't inherit the family membership, nor the family it used to head
+    match mixnode_bond.family {
+        Some(family_head) if family_head == mix_identity => {
+            dissolve_family(deps.storage, &mix_identity)?
+        }
+        Some(family_head) => {
+            mix_family_members(deps.storage, &family_head).remove(mix_identity.as_bytes())
+        }
+        None => (),
+    }
     // remove the node ownership
     mixnodes_owners(deps.storage).remove(sender_bytes);
     // decrement layer count
     decrement_layer_count(deps.storage, mixnode_bond.layer)?;
 
-    // log our actions
-    let attributes = vec![attr("action", "unbond"), attr("mixnode_bond", mixnode_bond)];
+    let attributes = events::new_mixnode_unbonding_event(
+        &info.sender,
+        &mix_identity,
+        withdrawal.amount.amount,
+        read_current_epoch(deps.storage)?,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+// changes the networking information and version of the sender's mixnode in place,
+// so that its identity, layer and delegations are preserved
+pub(crate) fn try_update_mixnode_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    host: String,
+    mix_port: u16,
+    verloc_port: u16,
+    http_api_port: u16,
+    version: String,
+) -> Result<Response, ContractError> {
+    // the update does not touch the bond, so any sent funds would have been lost
+    if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedConfigUpdateFunds);
+    }
+
+    // try to find the identity of the sender's node
+    let mix_identity = match mixnodes_owners_read(deps.storage).may_load(info.sender.as_bytes())? {
+        Some(identity) => identity,
+        None => return Err(ContractError::NoAssociatedMixNodeBond { owner: info.sender }),
+    };
+
+    // get the bond, since we found associated identity, the node MUST exist
+    let mut mixnode_bond = mixnodes_read(deps.storage).load(mix_identity.as_bytes())?;
+    mixnode_bond.mix_node.host = host;
+    mixnode_bond.mix_node.mix_port = mix_port;
+    mixnode_bond.mix_node.verloc_port = verloc_port;
+    mixnode_bond.mix_node.http_api_port = http_api_port;
+    mixnode_bond.mix_node.version = version;
+    validate_mixnode_config(&mixnode_bond.mix_node)?;
+
+    mixnodes(deps.storage).save(mix_identity.as_bytes(), &mixnode_bond)?;
+
+    let attributes = events::new_mixnode_config_update_event(
+        &info.sender,
+        &mix_identity,
+        &mixnode_bond.mix_node,
+        read_current_epoch(deps.storage)?,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+pub(crate) fn try_update_mixnode_profit_margin(
+    deps: DepsMut,
+    info: MessageInfo,
+    profit_margin_percent: u8,
+) -> Result<Response, ContractError> {
+    // the update does not touch the bond, so any sent funds would have been lost
+    if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedConfigUpdateFunds);
+    }
+
+    validate_profit_margin(profit_margin_percent)?;
+
+    // try to find the identity of the sender's node
+    let mix_identity = match mixnodes_owners_read(deps.storage).may_load(info.sender.as_bytes())? {
+        Some(identity) => identity,
+        None => return Err(ContractError::NoAssociatedMixNodeBond { owner: info.sender }),
+    };
+
+    // get the bond, since we found associated identity, the node MUST exist
+    let mut mixnode_bond = mixnodes_read(deps.storage).load(mix_identity.as_bytes())?;
+    let old_profit_margin = mixnode_bond.profit_margin_percent;
+
+    let change = if profit_margin_percent > old_profit_margin {
+        profit_margin_percent - old_profit_margin
+    } else {
+        old_profit_margin - profit_margin_percent
+    };
+    if change > MAX_PROFIT_MARGIN_CHANGE {
+        return Err(ContractError::ProfitMarginChangeTooLarge {
+            old: old_profit_margin,
+            new: profit_margin_percent,
+            max: MAX_PROFIT_MARGIN_CHANGE,
+        });
+    }
+
+    let epoch = read_current_epoch(deps.storage)?;
+    if let Some(last_update) =
+        mix_profit_margin_updates_read(deps.storage).may_load(mix_identity.as_bytes())?
+    {
+        let next_epoch = last_update + PROFIT_MARGIN_UPDATE_COOLDOWN_EPOCHS;
+        if epoch < next_epoch {
+            return Err(ContractError::ProfitMarginUpdateTooSoon {
+                epoch: last_update,
+                next_epoch,
+            });
+        }
+    }
+
+    mixnode_bond.profit_margin_percent = profit_margin_percent;
+    mixnodes(deps.storage).save(mix_identity.as_bytes(), &mixnode_bond)?;
+    mix_profit_margin_updates(deps.storage).save(mix_identity.as_bytes(), &epoch)?;
+
+    let attributes = events::new_mixnode_profit_margin_update_event(
+        &info.sender,
+        &mix_identity,
+        old_profit_margin,
+        profit_margin_percent,
+        epoch,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+pub(crate) fn try_create_family(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // try to find the identity of the sender's node
+    let mix_identity = match mixnodes_owners_read(deps.storage).may_load(info.sender.as_bytes())? {
+        Some(identity) => identity,
+        None => return Err(ContractError::NoAssociatedMixNodeBond { owner: info.sender }),
+    };
+
+    // get the bond, since we found associated identity, the node MUST exist
+    let mut mixnode_bond = mixnodes_read(deps.storage).load(mix_identity.as_bytes())?;
+    if let Some(family_head) = mixnode_bond.family {
+        return Err(ContractError::AlreadyInFamily { family_head });
+    }
+
+    // families are identified by the identity of their head
+    mixnode_bond.family = Some(mix_identity.clone());
+    mixnodes(deps.storage).save(mix_identity.as_bytes(), &mixnode_bond)?;
+
+    let attributes = events::new_family_creation_event(
+        &info.sender,
+        &mix_identity,
+        read_current_epoch(deps.storage)?,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+// removes all the members from the family, so that whoever bonds the identity of its head
+// later on can't take it over
+fn dissolve_family(storage: &mut dyn Storage, family_head: &str) -> StdResult<()> {
+    let members = mix_family_members_read(storage, family_head)
+        .range(None, None, Order::Ascending)
+        .map(|res| res.map(|(identity, _)| identity))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for member in members {
+        if let Some(mut bond) = mixnodes_read(storage).may_load(&member)? {
+            bond.family = None;
+            mixnodes(storage).save(&member, &bond)?;
+        }
+        mix_family_members(storage, family_head).remove(&member);
+    }
+    Ok(())
+}
+
+// the family head has to consent to the new member by signing the message binding together
+// the contract, its own identity and the identity of the member with its identity key
+fn verify_family_signature(
+    api: &dyn Api,
+    contract_address: &Addr,
+    family_head: &str,
+    member: &str,
+    signature: &str,
+) -> Result<(), ContractError> {
+    let public_key = bs58::decode(family_head)
+        .into_vec()
+        .map_err(|_| ContractError::InvalidFamilySignature)?;
+    let signature = bs58::decode(signature)
+        .into_vec()
+        .map_err(|_| ContractError::InvalidFamilySignature)?;
+
+    let message = family_join_message(contract_address.as_str(), family_head, member);
+    match api.ed25519_verify(message.as_bytes(), &signature, &public_key) {
+        Ok(true) => Ok(()),
+        _ => Err(ContractError::InvalidFamilySignature),
+    }
+}
+
+pub(crate) fn try_join_family(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    family_head: IdentityKey,
+    signature: String,
+) -> Result<Response, ContractError> {
+    // try to find the identity of the sender's node
+    let mix_identity = match mixnodes_owners_read(deps.storage).may_load(info.sender.as_bytes())? {
+        Some(identity) => identity,
+        None => return Err(ContractError::NoAssociatedMixNodeBond { owner: info.sender }),
+    };
+
+    // get the bond, since we found associated identity, the node MUST exist
+    let mut mixnode_bond = mixnodes_read(deps.storage).load(mix_identity.as_bytes())?;
+    if let Some(family_head) = mixnode_bond.family {
+        return Err(ContractError::AlreadyInFamily { family_head });
+    }
+
+    // the family exists only as long as its head is bonded and has not left it
+    let head_bond = mixnodes_read(deps.storage).may_load(family_head.as_bytes())?;
+    if head_bond.and_then(|bond| bond.family) != Some(family_head.clone()) {
+        return Err(ContractError::FamilyNotFound { family_head });
+    }
+
+    let members = mix_family_members_read(deps.storage, &family_head)
+        .range(None, None, Order::Ascending)
+        .count();
+    if members >= MAX_FAMILY_MEMBERS {
+        return Err(ContractError::FamilyFull {
+            family_head,
+            max: MAX_FAMILY_MEMBERS,
+        });
+    }
+
+    verify_family_signature(
+        deps.api,
+        &env.contract.address,
+        &family_head,
+        &mix_identity,
+        &signature,
+    )?;
+
+    mixnode_bond.family = Some(family_head.clone());
+    mixnodes(deps.storage).save(mix_identity.as_bytes(), &mixnode_bond)?;
+    mix_family_members(deps.storage, &family_head).save(mix_identity.as_bytes(), &())?;
+
+    let attributes = events::new_family_join_event(
+        &info.sender,
+        &mix_identity,
+        &family_head,
+        read_current_epoch(deps.storage)?,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+pub(crate) fn try_leave_family(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // try to find the identity of the sender's node
+    let mix_identity = match mixnodes_owners_read(deps.storage).may_load(info.sender.as_bytes())? {
+        Some(identity) => identity,
+        None => return Err(ContractError::NoAssociatedMixNodeBond { owner: info.sender }),
+    };
+
+    // get the bond, since we found associated identity, the node MUST exist
+    let mut mixnode_bond = mixnodes_read(deps.storage).load(mix_identity.as_bytes())?;
+    let family_head = match mixnode_bond.family.take() {
+        Some(family_head) => family_head,
+        None => return Err(ContractError::NotInFamily),
+    };
+    mixnodes(deps.storage).save(mix_identity.as_bytes(), &mixnode_bond)?;
+
+    // the family doesn't outlive its head
+    if family_head == mix_identity {
+        dissolve_family(deps.storage, &mix_identity)?;
+    } else {
+        mix_family_members(deps.storage, &family_head).remove(mix_identity.as_bytes());
+    }
+
+    let attributes = events::new_family_leave_event(
+        &info.sender,
+        &mix_identity,
+        &family_head,
+        read_current_epoch(deps.storage)?,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+// either pays out the rewards accumulated by the sender's mixnode or adds them to its bond
+pub(crate) fn try_claim_operator_reward(
+    deps: DepsMut,
+    info: MessageInfo,
+    compound: bool,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedClaimFunds);
+    }
+
+    // try to find the identity of the sender's node
+    let mix_identity = match mixnodes_owners_read(deps.storage).may_load(info.sender.as_bytes())? {
+        Some(identity) => identity,
+        None => return Err(ContractError::NoAssociatedMixNodeBond { owner: info.sender }),
+    };
+
+    let reward = mix_operator_rewards_read(deps.storage)
+        .may_load(mix_identity.as_bytes())?
+        .unwrap_or_default();
+    if reward.is_zero() {
+        return Err(ContractError::NoRewardToClaim);
+    }
+    mix_operator_rewards(deps.storage).remove(mix_identity.as_bytes());
+
+    let messages = if compound {
+        // get the bond, since we found associated identity, the node MUST exist
+        let mut mixnode_bond = mixnodes_read(deps.storage).load(mix_identity.as_bytes())?;
+        mixnode_bond.bond_amount.amount += reward;
+        mixnodes(deps.storage).save(mix_identity.as_bytes(), &mixnode_bond)?;
+        Vec::new()
+    } else {
+        vec![BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(reward.u128(), DENOM),
+        }
+        .into()]
+    };
+
+    let attributes = events::new_operator_reward_claim_event(
+        &info.sender,
+        &mix_identity,
+        reward,
+        compound,
+        read_current_epoch(deps.storage)?,
+    );
 
     Ok(Response {
         submessages: Vec::new(),
@@ -228,13 +778,11 @@ pub(crate) fn try_add_gateway(
         return Err(ContractError::AlreadyOwnsMixnode);
     }
 
-    let mut was_present = false;
     // if the client has an active gateway with a different identity, don't allow bonding
     if let Some(existing_node) = gateways_owners_read(deps.storage).may_load(sender_bytes)? {
         if existing_node != gateway.identity_key {
             return Err(ContractError::AlreadyOwnsGateway);
         }
-        was_present = true
     }
 
     // check if somebody else has already bonded a gateway with this identity
@@ -250,12 +798,17 @@ pub(crate) fn try_add_gateway(
 
     let minimum_bond = read_state_params(deps.storage).minimum_gateway_bond;
     validate_gateway_bond(&info.funds, minimum_bond)?;
+    validate_gateway_config(&gateway)?;
 
     let mut bond = GatewayBond::new(info.funds[0].clone(), info.sender.clone(), gateway);
 
     // this might potentially require more gas if a significant number of delegations was there
     let delegations_bucket = gateway_delegations_read(deps.storage, &bond.gateway.identity_key);
-    let existing_delegation = find_old_delegations(delegations_bucket)?;
+    let delegation_indices_bucket =
+        gateway_delegation_reward_indices_read(deps.storage, &bond.gateway.identity_key);
+    let current_index = read_gateway_reward_index(deps.storage, &bond.gateway.identity_key)?;
+    let existing_delegation =
+        find_old_delegations(delegations_bucket, delegation_indices_bucket, current_index)?;
     bond.total_delegation = existing_delegation;
 
     let identity = bond.identity();
@@ -263,7 +816,12 @@ pub(crate) fn try_add_gateway(
     gateways_owners(deps.storage).save(sender_bytes, identity)?;
     increment_layer_count(deps.storage, Layer::Gateway)?;
 
-    let attributes = vec![attr("overwritten", was_present)];
+    let attributes = events::new_gateway_bonding_event(
+        &info.sender,
+        identity,
+        bond.bond_amount.amount,
+        read_current_epoch(deps.storage)?,
+    );
     Ok(Response {
         submessages: Vec::new(),
         messages: Vec::new(),
@@ -274,6 +832,7 @@ pub(crate) fn try_add_gateway(
 
 pub(crate) fn try_remove_gateway(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
     let sender_bytes = info.sender.as_str().as_bytes();
@@ -287,50 +846,87 @@ pub(crate) fn try_remove_gateway(
     // get the bond, since we found associated identity, the node MUST exist
     let gateway_bond = gateways_read(deps.storage).load(gateway_identity.as_bytes())?;
 
-    // send bonded funds back to the bond owner
-    let messages = vec![BankMsg::Send {
-        to_address: info.sender.as_str().to_owned(),
-        amount: vec![gateway_bond.bond_amount()],
-    }
-    .into()];
+    // the bonded funds can be withdrawn by the bond owner once the unbonding period is over
+    create_pending_withdrawal(
+        deps.storage,
+        &env,
+        info.sender.clone(),
+        gateway_bond.bond_amount.amount,
+    )?;
 
-    // remove the bond from the list of bonded gateways
+    // remove the bond from the list of bonded gateways,
+    // so that it immediately stops being taken into consideration for the topology
     gateways(deps.storage).remove(gateway_identity.as_bytes());
     // remove the node ownership
     gateways_owners(deps.storage).remove(sender_bytes);
     // decrement layer count
     decrement_layer_count(deps.storage, Layer::Gateway)?;
 
-    // log our actions
-    let attributes = vec![
-        attr("action", "unbond"),
-        attr("address", info.sender),
-        attr("gateway_bond", gateway_bond),
-    ];
+    let attributes = events::new_gateway_unbonding_event(
+        &info.sender,
+        &gateway_identity,
+        gateway_bond.bond_amount.amount,
+        read_current_epoch(deps.storage)?,
+    );
 
     Ok(Response {
         submessages: Vec::new(),
-        messages,
+        messages: Vec::new(),
         attributes,
         data: None,
     })
 }
 
-pub(crate) fn try_update_state_params(
+// changes the networking information, location and version of the sender's gateway in place,
+// so that its identity and delegations are preserved
+pub(crate) fn try_update_gateway_config(
     deps: DepsMut,
     info: MessageInfo,
-    params: StateParams,
+    host: String,
+    mix_port: u16,
+    clients_port: u16,
+    location: String,
+    version: String,
 ) -> Result<Response, ContractError> {
-    // note: In any other case, I wouldn't have attempted to unwrap this result, but in here
-    // if we fail to load the stored state we would already be in the undefined behaviour land,
-    // so we better just blow up immediately.
-    let mut state = config_read(deps.storage).load().unwrap();
-
-    // check if this is executed by the owner, if not reject the transaction
-    if info.sender != state.owner {
-        return Err(ContractError::Unauthorized);
+    // the update does not touch the bond, so any sent funds would have been lost
+    if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedConfigUpdateFunds);
     }
 
+    // try to find the identity of the sender's node
+    let gateway_identity =
+        match gateways_owners_read(deps.storage).may_load(info.sender.as_bytes())? {
+            Some(identity) => identity,
+            None => return Err(ContractError::NoAssociatedGatewayBond { owner: info.sender }),
+        };
+
+    // get the bond, since we found associated identity, the node MUST exist
+    let mut gateway_bond = gateways_read(deps.storage).load(gateway_identity.as_bytes())?;
+    gateway_bond.gateway.host = host;
+    gateway_bond.gateway.mix_port = mix_port;
+    gateway_bond.gateway.clients_port = clients_port;
+    gateway_bond.gateway.location = location;
+    gateway_bond.gateway.version = version;
+    validate_gateway_config(&gateway_bond.gateway)?;
+
+    gateways(deps.storage).save(gateway_identity.as_bytes(), &gateway_bond)?;
+
+    let attributes = events::new_gateway_config_update_event(
+        &info.sender,
+        &gateway_identity,
+        &gateway_bond.gateway,
+        read_current_epoch(deps.storage)?,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+fn validate_state_params(params: &StateParams) -> Result<(), ContractError> {
     if params.mixnode_bond_reward_rate < Decimal::one() {
         return Err(ContractError::DecreasingMixnodeBondReward);
     }
@@ -347,17 +943,55 @@ pub(crate) fn try_update_state_params(
         return Err(ContractError::DecreasingGatewayDelegationReward);
     }
 
-    // if we're updating epoch length, recalculate rewards for both mixnodes and gateways
-    if state.params.epoch_length != params.epoch_length {
-        state.mixnode_epoch_bond_reward =
-            calculate_epoch_reward_rate(params.epoch_length, params.mixnode_bond_reward_rate);
-        state.gateway_epoch_bond_reward =
-            calculate_epoch_reward_rate(params.epoch_length, params.gateway_bond_reward_rate);
-        state.mixnode_epoch_delegation_reward =
-            calculate_epoch_reward_rate(params.epoch_length, params.mixnode_delegation_reward_rate);
-        state.gateway_epoch_delegation_reward =
-            calculate_epoch_reward_rate(params.epoch_length, params.gateway_delegation_reward_rate);
-    } else {
+    if params.minimum_mixnode_uptime > 100 {
+        return Err(ContractError::InvalidUptimeThreshold {
+            value: params.minimum_mixnode_uptime,
+        });
+    }
+
+    if params.mixnode_jail_epochs == 0 {
+        return Err(ContractError::ZeroJailEpochs);
+    }
+
+    if params.mixnode_slashing_fraction > Decimal::one() {
+        return Err(ContractError::InvalidSlashingFraction);
+    }
+
+    if params.mixnode_active_set_size == 0
+        || params.mixnode_active_set_size > MAX_MIXNODE_ACTIVE_SET_SIZE
+    {
+        return Err(ContractError::InvalidActiveSetSize {
+            value: params.mixnode_active_set_size,
+            max: MAX_MIXNODE_ACTIVE_SET_SIZE,
+        });
+    }
+
+    Ok(())
+}
+
+// replaces the stored parameters, recalculating the derived per-epoch reward rates if needed
+fn apply_state_params(
+    storage: &mut dyn Storage,
+    params: StateParams,
+) -> Result<Response, ContractError> {
+    validate_state_params(&params)?;
+
+    // note: In any other case, I wouldn't have attempted to unwrap this result, but in here
+    // if we fail to load the stored state we would already be in the undefined behaviour land,
+    // so we better just blow up immediately.
+    let mut state = config_read(storage).load().unwrap();
+
+    // if we're updating epoch length, recalculate rewards for both mixnodes and gateways
+    if state.params.epoch_length != params.epoch_length {
+        state.mixnode_epoch_bond_reward =
+            calculate_epoch_reward_rate(params.epoch_length, params.mixnode_bond_reward_rate);
+        state.gateway_epoch_bond_reward =
+            calculate_epoch_reward_rate(params.epoch_length, params.gateway_bond_reward_rate);
+        state.mixnode_epoch_delegation_reward =
+            calculate_epoch_reward_rate(params.epoch_length, params.mixnode_delegation_reward_rate);
+        state.gateway_epoch_delegation_reward =
+            calculate_epoch_reward_rate(params.epoch_length, params.gateway_delegation_reward_rate);
+    } else {
         // if mixnode or gateway rewards changed, recalculate respective values
         if state.params.mixnode_bond_reward_rate != params.mixnode_bond_reward_rate {
             state.mixnode_epoch_bond_reward =
@@ -381,11 +1015,324 @@ pub(crate) fn try_update_state_params(
         }
     }
 
+    let attributes = events::new_params_update_event(&params, read_current_epoch(storage)?)?;
+
     state.params = params;
 
-    config(deps.storage).save(&state)?;
+    config(storage).save(&state)?;
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+fn validate_governance_config(config: &GovernanceConfig) -> Result<(), ContractError> {
+    if config.members.is_empty() {
+        return Err(ContractError::NoGovernanceMembers);
+    }
+
+    for (i, member) in config.members.iter().enumerate() {
+        if config.members[..i].contains(member) {
+            return Err(ContractError::DuplicateGovernanceMember {
+                member: member.clone(),
+            });
+        }
+    }
+
+    // no single member should be able to pass proposals on its own, unless it's the only one
+    let members = config.members.len();
+    if config.threshold as usize * 2 <= members || config.threshold as usize > members {
+        return Err(ContractError::InvalidGovernanceThreshold {
+            threshold: config.threshold,
+            members: members as u32,
+        });
+    }
+
+    // the owner must always have the time to veto the passed proposal before it gets executed
+    if config.timelock == 0 {
+        return Err(ContractError::ZeroTimelock);
+    }
+
+    if config.execution_period == 0 {
+        return Err(ContractError::ZeroExecutionPeriod);
+    }
+
+    Ok(())
+}
+
+fn apply_governance_config(
+    storage: &mut dyn Storage,
+    governance_config: GovernanceConfig,
+) -> Result<Response, ContractError> {
+    validate_governance_config(&governance_config)?;
+    let attributes = events::new_governance_config_update_event(&governance_config)?;
+    governance(storage).save(&governance_config)?;
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+fn load_proposal(
+    storage: &dyn Storage,
+    proposal_id: ProposalId,
+) -> Result<Proposal, ContractError> {
+    proposals_read(storage)
+        .may_load(&proposal_id.to_be_bytes())?
+        .ok_or(ContractError::ProposalNotFound { proposal_id })
+}
+
+// records the vote and, if it was decisive, either marks the proposal as passed,
+// starting its timelock and setting its execution deadline, or as rejected
+fn record_vote(
+    storage: &mut dyn Storage,
+    proposal: &mut Proposal,
+    voter: &Addr,
+    vote: VoteOption,
+    now: u64,
+) -> Result<(), ContractError> {
+    let mut votes = proposal_votes(storage, proposal.id);
+    if votes.may_load(voter.as_bytes())?.is_some() {
+        return Err(ContractError::AlreadyVoted {
+            proposal_id: proposal.id,
+            voter: voter.clone(),
+        });
+    }
+    votes.save(voter.as_bytes(), &vote)?;
+
+    match vote {
+        VoteOption::Yes => proposal.yes_votes += 1,
+        VoteOption::No => proposal.no_votes += 1,
+    }
+
+    let governance_config = &proposal.governance;
+    let max_no_votes =
+        (governance_config.members.len() as u32).saturating_sub(governance_config.threshold);
+    if proposal.yes_votes >= governance_config.threshold {
+        let executable_at = now + governance_config.timelock as u64 * 60 * 60;
+        proposal.status = ProposalStatus::Passed;
+        proposal.executable_at = Some(executable_at);
+        proposal.expires_at =
+            Some(executable_at + governance_config.execution_period as u64 * 60 * 60);
+    } else if proposal.no_votes > max_no_votes {
+        proposal.status = ProposalStatus::Rejected;
+    }
+
+    Ok(())
+}
+
+// the current governance is stored alongside the proposal, so that any later changes to it
+// do not affect how the proposal is voted on and executed
+fn submit_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    action: ProposalAction,
+    description: String,
+) -> Result<Response, ContractError> {
+    let governance_config = governance_read(deps.storage).load()?;
+    if !governance_config.is_member(&info.sender) {
+        return Err(ContractError::NotGovernanceMember);
+    }
+
+    // make sure we won't end up with a proposal that could never be applied
+    match &action {
+        ProposalAction::UpdateStateParams(params) => validate_state_params(params)?,
+        ProposalAction::UpdateGovernanceConfig(config) => validate_governance_config(config)?,
+    }
+
+    let now = env.block.time.seconds();
+    let mut proposal = Proposal {
+        id: next_proposal_id(deps.storage)?,
+        proposer: info.sender.clone(),
+        description,
+        action,
+        governance: governance_config.clone(),
+        status: ProposalStatus::Open,
+        yes_votes: 0,
+        no_votes: 0,
+        submitted_at: now,
+        voting_ends_at: now + governance_config.voting_period as u64 * 60 * 60,
+        executable_at: None,
+        expires_at: None,
+    };
+
+    // submitting a proposal counts as approving it
+    record_vote(
+        deps.storage,
+        &mut proposal,
+        &info.sender,
+        VoteOption::Yes,
+        now,
+    )?;
+    proposals(deps.storage).save(&proposal.id.to_be_bytes(), &proposal)?;
+
+    let attributes = events::new_proposal_submission_event(proposal.id, &info.sender);
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+pub(crate) fn try_propose_state_params(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    params: StateParams,
+    description: String,
+) -> Result<Response, ContractError> {
+    submit_proposal(
+        deps,
+        env,
+        info,
+        ProposalAction::UpdateStateParams(params),
+        description,
+    )
+}
+
+// the governance can only be changed by itself, there's no way for the owner to bypass it
+pub(crate) fn try_propose_governance_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: GovernanceConfig,
+    description: String,
+) -> Result<Response, ContractError> {
+    submit_proposal(
+        deps,
+        env,
+        info,
+        ProposalAction::UpdateGovernanceConfig(config),
+        description,
+    )
+}
+
+pub(crate) fn try_vote_on_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: ProposalId,
+    vote: VoteOption,
+) -> Result<Response, ContractError> {
+    let mut proposal = load_proposal(deps.storage, proposal_id)?;
+    // only the members at the time of the submission can vote on the proposal
+    if !proposal.governance.is_member(&info.sender) {
+        return Err(ContractError::NotGovernanceMember);
+    }
+
+    if proposal.status != ProposalStatus::Open {
+        return Err(ContractError::ProposalNotOpen { proposal_id });
+    }
+
+    let now = env.block.time.seconds();
+    if now >= proposal.voting_ends_at {
+        return Err(ContractError::ProposalVotingEnded { proposal_id });
+    }
+
+    record_vote(deps.storage, &mut proposal, &info.sender, vote, now)?;
+    proposals(deps.storage).save(&proposal_id.to_be_bytes(), &proposal)?;
+
+    let attributes = events::new_proposal_vote_event(proposal_id, &info.sender, vote);
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+// anyone can trigger the execution of the passed proposal once its timelock has expired,
+// as long as it's done before the deadline, so that stale parameters can't be applied long after
+pub(crate) fn try_execute_proposal(
+    deps: DepsMut,
+    env: Env,
+    proposal_id: ProposalId,
+) -> Result<Response, ContractError> {
+    let mut proposal = load_proposal(deps.storage, proposal_id)?;
+    match proposal.status {
+        ProposalStatus::Passed => (),
+        ProposalStatus::Open | ProposalStatus::Rejected => {
+            return Err(ContractError::ProposalNotPassed { proposal_id })
+        }
+        ProposalStatus::Executed | ProposalStatus::Cancelled => {
+            return Err(ContractError::ProposalFinalised { proposal_id })
+        }
+    }
+
+    // passed proposals always have both of them set
+    let now = env.block.time.seconds();
+    let executable_at = proposal.executable_at.unwrap_or_default();
+    if now < executable_at {
+        return Err(ContractError::ProposalTimelocked {
+            proposal_id,
+            executable_at,
+        });
+    }
+    let expired_at = proposal.expires_at.unwrap_or_default();
+    if now >= expired_at {
+        return Err(ContractError::ProposalExpired {
+            proposal_id,
+            expired_at,
+        });
+    }
+
+    let mut response = match proposal.action.clone() {
+        ProposalAction::UpdateStateParams(params) => apply_state_params(deps.storage, params)?,
+        ProposalAction::UpdateGovernanceConfig(config) => {
+            apply_governance_config(deps.storage, config)?
+        }
+    };
+    response
+        .attributes
+        .extend(events::new_proposal_execution_event(proposal_id));
+
+    proposal.status = ProposalStatus::Executed;
+    proposals(deps.storage).save(&proposal_id.to_be_bytes(), &proposal)?;
+
+    Ok(response)
+}
+
+pub(crate) fn try_cancel_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+    proposal_id: ProposalId,
+) -> Result<Response, ContractError> {
+    let mut proposal = load_proposal(deps.storage, proposal_id)?;
+
+    // the owner can veto any proposal as an emergency measure
+    let state = config_read(deps.storage).load().unwrap();
+    if info.sender != proposal.proposer && info.sender != state.owner {
+        return Err(ContractError::Unauthorized);
+    }
+
+    match proposal.status {
+        ProposalStatus::Open | ProposalStatus::Passed => (),
+        ProposalStatus::Rejected | ProposalStatus::Executed | ProposalStatus::Cancelled => {
+            return Err(ContractError::ProposalFinalised { proposal_id })
+        }
+    }
+
+    proposal.status = ProposalStatus::Cancelled;
+    proposals(deps.storage).save(&proposal_id.to_be_bytes(), &proposal)?;
+
+    let attributes = events::new_proposal_cancellation_event(proposal_id, &info.sender);
 
-    Ok(Response::default())
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
 }
 
 pub(crate) fn try_reward_mixnode(
@@ -401,16 +1348,18 @@ pub(crate) fn try_reward_mixnode(
         return Err(ContractError::Unauthorized);
     }
 
+    let epoch = read_current_epoch(deps.storage)?;
+
     // optimisation for uptime being 0. No rewards will be given so just terminate here
     if uptime == 0 {
         return Ok(Response {
-            submessages: vec![],
-            messages: vec![],
-            attributes: vec![
-                attr("bond increase", Uint128(0)),
-                attr("total delegation increase", Uint128(0)),
-            ],
-            data: None,
+            attributes: events::new_mixnode_not_rewarded_event(
+                &mix_identity,
+                uptime,
+                events::ZERO_UPTIME_RESULT,
+                epoch,
+            ),
+            ..Default::default()
         });
     }
 
@@ -419,33 +1368,78 @@ pub(crate) fn try_reward_mixnode(
         Ok(bond) => bond,
         Err(_) => {
             return Ok(Response {
-                attributes: vec![attr("result", "bond not found")],
+                attributes: events::new_mixnode_not_rewarded_event(
+                    &mix_identity,
+                    uptime,
+                    events::BOND_NOT_FOUND_RESULT,
+                    epoch,
+                ),
                 ..Default::default()
             });
         }
     };
 
+    // jailed nodes are not getting any rewards
+    if current_bond.jailed {
+        return Ok(Response {
+            attributes: events::new_mixnode_not_rewarded_event(
+                &mix_identity,
+                uptime,
+                events::NODE_JAILED_RESULT,
+                epoch,
+            ),
+            ..Default::default()
+        });
+    }
+
     let bond_reward_rate = read_mixnode_epoch_bond_reward_rate(deps.storage);
     let delegation_reward_rate = read_mixnode_epoch_delegation_reward_rate(deps.storage);
     let bond_scaled_reward_rate = scale_reward_by_uptime(bond_reward_rate, uptime)?;
     let delegation_scaled_reward_rate = scale_reward_by_uptime(delegation_reward_rate, uptime)?;
 
+    // the operator takes its profit margin out of the delegators' reward before they get paid
+    let delegators_reward_rate = deduct_profit_margin(
+        delegation_scaled_reward_rate,
+        current_bond.profit_margin_percent,
+    );
+
     let node_reward = current_bond.bond_amount.amount * bond_scaled_reward_rate;
     let total_delegation_reward =
-        increase_mix_delegated_stakes(deps.storage, &mix_identity, delegation_scaled_reward_rate)?;
+        current_bond.total_delegation.amount * delegation_scaled_reward_rate;
+    let delegators_reward = current_bond.total_delegation.amount * delegators_reward_rate;
+    // the delegators' reward rate can't be larger than the full one, so this can't underflow
+    let operator_commission = total_delegation_reward
+        .checked_sub(delegators_reward)
+        .unwrap_or_default();
+    let operator_reward = node_reward + operator_commission;
+
+    // the individual delegations are going to be updated lazily when accessed
+    increase_mix_reward_index(deps.storage, &mix_identity, delegators_reward_rate)?;
+
+    // the operator reward is kept aside until it's claimed
+    let mut operator_rewards_bucket = mix_operator_rewards(deps.storage);
+    let unclaimed_reward = operator_rewards_bucket
+        .may_load(mix_identity.as_bytes())?
+        .unwrap_or_default();
+    operator_rewards_bucket.save(
+        mix_identity.as_bytes(),
+        &(unclaimed_reward + operator_reward),
+    )?;
 
-    // update current bond with the reward given to the node and the delegators
-    current_bond.bond_amount.amount += node_reward;
-    current_bond.total_delegation.amount += total_delegation_reward;
+    // update current bond with the reward given to the delegators
+    current_bond.total_delegation.amount += delegators_reward;
     mixnodes(deps.storage).save(mix_identity.as_bytes(), &current_bond)?;
 
     Ok(Response {
         submessages: vec![],
         messages: vec![],
-        attributes: vec![
-            attr("bond increase", node_reward),
-            attr("total delegation increase", total_delegation_reward),
-        ],
+        attributes: events::new_mixnode_rewarding_event(
+            &mix_identity,
+            uptime,
+            operator_reward,
+            delegators_reward,
+            epoch,
+        ),
         data: None,
     })
 }
@@ -463,16 +1457,18 @@ pub(crate) fn try_reward_gateway(
         return Err(ContractError::Unauthorized);
     }
 
+    let epoch = read_current_epoch(deps.storage)?;
+
     // optimisation for uptime being 0. No rewards will be given so just terminate here
     if uptime == 0 {
         return Ok(Response {
-            submessages: vec![],
-            messages: vec![],
-            attributes: vec![
-                attr("bond increase", Uint128(0)),
-                attr("total delegation increase", Uint128(0)),
-            ],
-            data: None,
+            attributes: events::new_gateway_not_rewarded_event(
+                &gateway_identity,
+                uptime,
+                events::ZERO_UPTIME_RESULT,
+                epoch,
+            ),
+            ..Default::default()
         });
     }
 
@@ -481,7 +1477,12 @@ pub(crate) fn try_reward_gateway(
         Ok(bond) => bond,
         Err(_) => {
             return Ok(Response {
-                attributes: vec![attr("result", "bond not found")],
+                attributes: events::new_gateway_not_rewarded_event(
+                    &gateway_identity,
+                    uptime,
+                    events::BOND_NOT_FOUND_RESULT,
+                    epoch,
+                ),
                 ..Default::default()
             });
         }
@@ -493,7 +1494,10 @@ pub(crate) fn try_reward_gateway(
     let scaled_delegation_reward_rate = scale_reward_by_uptime(delegation_reward_rate, uptime)?;
 
     let node_reward = current_bond.bond_amount.amount * scaled_bond_reward_rate;
-    let total_delegation_reward = increase_gateway_delegated_stakes(
+    let total_delegation_reward =
+        current_bond.total_delegation.amount * scaled_delegation_reward_rate;
+    // the individual delegations are going to be updated lazily when accessed
+    increase_gateway_reward_index(
         deps.storage,
         &gateway_identity,
         scaled_delegation_reward_rate,
@@ -507,265 +1511,851 @@ pub(crate) fn try_reward_gateway(
     Ok(Response {
         submessages: vec![],
         messages: vec![],
-        attributes: vec![
-            attr("bond increase", node_reward),
-            attr("total delegation increase", total_delegation_reward),
-        ],
+        attributes: events::new_gateway_rewarding_event(
+            &gateway_identity,
+            uptime,
+            node_reward,
+            total_delegation_reward,
+            epoch,
+        ),
         data: None,
     })
 }
 
-fn validate_delegation_stake(delegation: &[Coin]) -> Result<(), ContractError> {
-    // check if anything was put as delegation
-    if delegation.is_empty() {
-        return Err(ContractError::EmptyDelegation);
-    }
+// maximum number of mixnodes looked at, or given their new layers, by a single `AdvanceEpoch`
+// transaction, so that its cost doesn't grow with the number of bonded mixnodes
+const ACTIVE_SET_SELECTION_BATCH_SIZE: usize = 250;
 
-    if delegation.len() > 1 {
-        return Err(ContractError::MultipleDenoms);
-    }
+fn mixnode_total_stake(bond: &MixNodeBond) -> Uint128 {
+    bond.bond_amount.amount + bond.total_delegation.amount
+}
 
-    // check that the denomination is correct
-    if delegation[0].denom != DENOM {
-        return Err(ContractError::WrongDenom {});
+// merges the provided nodes into the candidates, keeping up to `active_set_size` of them with
+// the highest total stake. Nodes with identical stake are ordered by their identities so that
+// the selection is deterministic.
+fn select_candidates(
+    candidates: &mut Vec<(IdentityKey, Uint128)>,
+    bonds: Vec<MixNodeBond>,
+    active_set_size: u32,
+) {
+    candidates.extend(
+        bonds
+            .into_iter()
+            // jailed nodes can't be part of the active set
+            .filter(|bond| !bond.jailed)
+            .map(|bond| {
+                let stake = mixnode_total_stake(&bond);
+                (bond.mix_node.identity_key, stake)
+            }),
+    );
+    candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    candidates.truncate(active_set_size as usize);
+}
+
+// the layers are assigned in round-robin fashion in the order of stake, so that
+// each of them receives roughly the same number of nodes and the same amount of stake
+fn active_set_layer(position: usize) -> Layer {
+    let layers = [Layer::One, Layer::Two, Layer::Three];
+    layers[position % layers.len()]
+}
+
+fn layer_count_mut(layers: &mut LayerDistribution, layer: Layer) -> &mut u64 {
+    match layer {
+        Layer::Gateway => &mut layers.gateways,
+        Layer::One => &mut layers.layer1,
+        Layer::Two => &mut layers.layer2,
+        Layer::Three => &mut layers.layer3,
     }
+}
 
-    // check that we have provided a non-zero amount in the delegation
-    if delegation[0].amount.is_zero() {
-        return Err(ContractError::EmptyDelegation);
+// looks at the stake of the next batch of bonded mixnodes
+fn scan_mixnodes(
+    storage: &dyn Storage,
+    selection: &mut ActiveSetSelection,
+    active_set_size: u32,
+) -> StdResult<()> {
+    let start = queries::calculate_start_value(selection.last_scanned.as_ref());
+    let bonds = mixnodes_read(storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(ACTIVE_SET_SELECTION_BATCH_SIZE)
+        .map(|res| res.map(|(_, bond)| bond))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if bonds.len() < ACTIVE_SET_SELECTION_BATCH_SIZE {
+        selection.scan_finished = true;
+    }
+    if let Some(last) = bonds.last() {
+        selection.last_scanned = Some(last.identity().clone());
     }
 
+    select_candidates(&mut selection.candidates, bonds, active_set_size);
     Ok(())
 }
 
-pub(crate) fn try_delegate_to_mixnode(
-    deps: DepsMut,
-    info: MessageInfo,
-    mix_identity: IdentityKey,
-) -> Result<Response, ContractError> {
-    // check if the delegation contains any funds of the appropriate denomination
-    validate_delegation_stake(&info.funds)?;
-
-    // check if the target node actually exists
-    let mut mixnodes_bucket = mixnodes(deps.storage);
-    let mut current_bond = match mixnodes_bucket.load(mix_identity.as_bytes()) {
-        Ok(bond) => bond,
-        Err(_) => {
-            return Err(ContractError::MixNodeBondNotFound {
-                identity: mix_identity,
-            });
-        }
-    };
-
-    // update total_delegation of this node
-    current_bond.total_delegation.amount += info.funds[0].amount;
-    mixnodes_bucket.save(mix_identity.as_bytes(), &current_bond)?;
-
-    let mut delegation_bucket = mix_delegations(deps.storage, &mix_identity);
-    let sender_bytes = info.sender.as_bytes();
+// moves the next batch of candidates to their new layers. Note that the candidates' stake was
+// checked when they were scanned, so the ones that got unbonded or jailed since are skipped
+fn assign_layers(storage: &mut dyn Storage, selection: &mut ActiveSetSelection) -> StdResult<()> {
+    let mut layers = read_layer_distribution(storage);
+    let mut mixnodes_bucket = mixnodes(storage);
+
+    let start = selection.assigned_candidates as usize;
+    for (identity, _) in selection
+        .candidates
+        .iter()
+        .skip(start)
+        .take(ACTIVE_SET_SELECTION_BATCH_SIZE)
+    {
+        selection.assigned_candidates += 1;
+        let mut bond = match mixnodes_bucket.may_load(identity.as_bytes())? {
+            Some(bond) if !bond.jailed => bond,
+            _ => continue,
+        };
 
-    // write the delegation
-    match delegation_bucket.may_load(sender_bytes)? {
-        Some(existing_delegation) => {
-            delegation_bucket.save(sender_bytes, &(existing_delegation + info.funds[0].amount))?
+        let new_layer = active_set_layer(selection.selected.len());
+        if bond.layer != new_layer {
+            *layer_count_mut(&mut layers, bond.layer) -= 1;
+            *layer_count_mut(&mut layers, new_layer) += 1;
+            bond.layer = new_layer;
+            mixnodes_bucket.save(identity.as_bytes(), &bond)?;
         }
-        None => delegation_bucket.save(sender_bytes, &info.funds[0].amount)?,
+        selection.selected.push(identity.clone());
     }
 
-    Ok(Response::default())
+    layer_distribution(storage).save(&layers)
 }
 
-pub(crate) fn try_remove_delegation_from_mixnode(
+pub(crate) fn try_advance_epoch(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    mix_identity: IdentityKey,
 ) -> Result<Response, ContractError> {
-    let mut delegation_bucket = mix_delegations(deps.storage, &mix_identity);
-    let sender_bytes = info.sender.as_bytes();
-    match delegation_bucket.may_load(sender_bytes)? {
-        Some(delegation) => {
-            // remove delegation from the bucket
-            delegation_bucket.remove(sender_bytes);
+    let state = config_read(deps.storage).load()?;
 
-            // send delegated funds back to the delegation owner
-            let messages = vec![BankMsg::Send {
-                to_address: info.sender.to_string(),
-                amount: coins(delegation.u128(), DENOM),
-            }
-            .into()];
+    // check if this is executed by the monitor, if not reject the transaction
+    if info.sender != state.network_monitor_address {
+        return Err(ContractError::Unauthorized);
+    }
 
-            // update total_delegation of this node
-            let mut mixnodes_bucket = mixnodes(deps.storage);
-            // in some rare cases the mixnode bond might no longer exist as the node unbonded
-            // before delegation was removed. that is fine
-            if let Some(mut existing_bond) = mixnodes_bucket.may_load(mix_identity.as_bytes())? {
-                // we should NEVER underflow here, if we do, it means we have some serious error in our logic
-                existing_bond.total_delegation.amount = existing_bond
-                    .total_delegation
-                    .amount
-                    .checked_sub(delegation)
-                    .unwrap();
-                mixnodes_bucket.save(mix_identity.as_bytes(), &existing_bond)?;
+    let current_set = read_active_set(deps.storage)?;
+    let mut selection = match active_set_selection_read(deps.storage).may_load()? {
+        // the selection of the next set has already begun in one of the previous transactions
+        Some(selection) => selection,
+        None => {
+            let current_time = env.block.time.seconds();
+            // the very first epoch can be started at any point
+            if current_set.epoch != 0 {
+                let epoch_end =
+                    calculate_epoch_end(current_set.epoch_start, state.params.epoch_length);
+                if current_time < epoch_end {
+                    return Err(ContractError::EpochInProgress { epoch_end });
+                }
             }
-
-            Ok(Response {
-                submessages: Vec::new(),
-                messages,
-                attributes: Vec::new(),
-                data: None,
+            ActiveSetSelection {
+                started_at: current_time,
+                ..Default::default()
+            }
+        }
+    };
+
+    // each transaction either goes through the next batch of bonded mixnodes or, once all of them
+    // have been looked at, assigns the layers to the next batch of the selected ones
+    if !selection.scan_finished {
+        // the parameter might have been set before it got capped
+        let active_set_size = state
+            .params
+            .mixnode_active_set_size
+            .min(MAX_MIXNODE_ACTIVE_SET_SIZE);
+        scan_mixnodes(deps.storage, &mut selection, active_set_size)?;
+    } else {
+        assign_layers(deps.storage, &mut selection)?;
+    }
+
+    if !selection.scan_finished
+        || (selection.assigned_candidates as usize) < selection.candidates.len()
+    {
+        active_set_selection(deps.storage).save(&selection)?;
+        return Ok(Response {
+            submessages: vec![],
+            messages: vec![],
+            attributes: events::new_epoch_advance_event(current_set.epoch, None),
+            data: None,
+        });
+    }
+
+    active_set_selection(deps.storage).remove();
+
+    let mut nodes = selection.selected;
+    nodes.sort();
+    let new_set = ActiveSet {
+        epoch: current_set.epoch + 1,
+        epoch_start: selection.started_at,
+        nodes,
+    };
+    active_set(deps.storage).save(&new_set)?;
+
+    Ok(Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: events::new_epoch_advance_event(new_set.epoch, Some(new_set.nodes.len())),
+        data: None,
+    })
+}
+
+// records the mixnode's uptime being below the threshold in the current epoch.
+// once it happens for enough consecutive epochs, the node gets jailed and, optionally, slashed
+pub(crate) fn try_report_misbehaviour(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mix_identity: IdentityKey,
+    uptime: u32,
+) -> Result<Response, ContractError> {
+    let state = config_read(deps.storage).load()?;
+
+    // check if this is executed by the monitor, if not reject the transaction
+    if info.sender != state.network_monitor_address {
+        return Err(ContractError::Unauthorized);
+    }
+
+    if uptime >= state.params.minimum_mixnode_uptime {
+        return Err(ContractError::UptimeAboveThreshold {
+            uptime,
+            minimum: state.params.minimum_mixnode_uptime,
+        });
+    }
+
+    let mut mixnode_bond = match mixnodes_read(deps.storage).may_load(mix_identity.as_bytes())? {
+        Some(bond) => bond,
+        None => {
+            return Err(ContractError::MixNodeBondNotFound {
+                identity: mix_identity,
             })
         }
-        None => Err(ContractError::NoMixnodeDelegationFound {
+    };
+    if mixnode_bond.jailed {
+        return Err(ContractError::MixnodeJailed {
             identity: mix_identity,
-            address: info.sender,
-        }),
+        });
+    }
+
+    let epoch = read_active_set(deps.storage)?.epoch;
+    let streak = mix_misbehaviour_streaks_read(deps.storage)
+        .may_load(mix_identity.as_bytes())?
+        .unwrap_or_default();
+
+    let consecutive_epochs = if streak.consecutive_epochs == 0 {
+        1
+    } else if streak.last_epoch == epoch {
+        return Err(ContractError::MisbehaviourAlreadyReported {
+            identity: mix_identity,
+            epoch,
+        });
+    } else if streak.last_epoch + 1 == epoch {
+        streak.consecutive_epochs + 1
+    } else {
+        // the node behaved correctly in between, so the streak starts anew
+        1
+    };
+
+    let jailed = consecutive_epochs >= state.params.mixnode_jail_epochs;
+    let mut slashed = coin(0, DENOM);
+    if jailed {
+        slashed.amount = mixnode_bond.bond_amount.amount * state.params.mixnode_slashing_fraction;
+        mixnode_bond.bond_amount.amount = mixnode_bond
+            .bond_amount
+            .amount
+            .checked_sub(slashed.amount)
+            .unwrap_or_default();
+        // the slashed tokens are kept aside until the owner withdraws them
+        if !slashed.amount.is_zero() {
+            let total_slashed = read_slashed_funds(deps.storage)? + slashed.amount;
+            slashed_funds(deps.storage).save(&total_slashed)?;
+        }
+        // the node has to sit out at least as many epochs as it took to get it jailed
+        let jail_period =
+            state.params.mixnode_jail_epochs as u64 * state.params.epoch_length as u64 * 60 * 60;
+        mixnode_bond.jailed = true;
+        mixnode_bond.jailed_until = env.block.time.seconds() + jail_period;
+        mixnodes(deps.storage).save(mix_identity.as_bytes(), &mixnode_bond)?;
+        jailed_mixnodes(deps.storage).save(mix_identity.as_bytes(), &())?;
+
+        // after being unjailed, the node starts with a clean slate
+        mix_misbehaviour_streaks(deps.storage).remove(mix_identity.as_bytes());
+    } else {
+        mix_misbehaviour_streaks(deps.storage).save(
+            mix_identity.as_bytes(),
+            &MisbehaviourStreak {
+                last_epoch: epoch,
+                consecutive_epochs,
+            },
+        )?;
+    }
+
+    let report = MixnodeMisbehaviour {
+        epoch,
+        uptime,
+        block_height: env.block.height,
+        consecutive_epochs,
+        jailed,
+        slashed,
+    };
+    mix_misbehaviour(deps.storage, &mix_identity).save(&epoch.to_be_bytes(), &report)?;
+
+    let attributes = events::new_misbehaviour_report_event(
+        &mix_identity,
+        uptime,
+        consecutive_epochs,
+        jailed,
+        report.slashed.amount,
+        epoch,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+// sends all the stake slashed so far to the recipient chosen by the owner,
+// e.g. the community pool or an address without a known key to burn it
+pub(crate) fn try_withdraw_slashed_funds(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let state = config_read(deps.storage).load()?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized);
+    }
+
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let amount = read_slashed_funds(deps.storage)?;
+    if amount.is_zero() {
+        return Err(ContractError::NoSlashedFunds);
+    }
+    slashed_funds(deps.storage).save(&Uint128::zero())?;
+
+    let messages = vec![BankMsg::Send {
+        to_address: recipient.to_string(),
+        amount: coins(amount.u128(), DENOM),
     }
+    .into()];
+
+    let attributes = events::new_slashed_funds_withdrawal_event(&recipient, amount);
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages,
+        attributes,
+        data: None,
+    })
 }
 
-pub(crate) fn try_delegate_to_gateway(
+pub(crate) fn try_unjail_mixnode(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    gateway_identity: IdentityKey,
+) -> Result<Response, ContractError> {
+    // try to find the identity of the sender's node
+    let mix_identity = match mixnodes_owners_read(deps.storage).may_load(info.sender.as_bytes())? {
+        Some(identity) => identity,
+        None => return Err(ContractError::NoAssociatedMixNodeBond { owner: info.sender }),
+    };
+
+    // get the bond, since we found associated identity, the node MUST exist
+    let mut mixnode_bond = mixnodes_read(deps.storage).load(mix_identity.as_bytes())?;
+    if !mixnode_bond.jailed {
+        return Err(ContractError::MixnodeNotJailed);
+    }
+    if env.block.time.seconds() < mixnode_bond.jailed_until {
+        return Err(ContractError::MixnodeStillJailed {
+            jailed_until: mixnode_bond.jailed_until,
+        });
+    }
+
+    // the node is going to be considered for the active set again from the next epoch
+    mixnode_bond.jailed = false;
+    mixnode_bond.jailed_until = 0;
+    mixnodes(deps.storage).save(mix_identity.as_bytes(), &mixnode_bond)?;
+    jailed_mixnodes(deps.storage).remove(mix_identity.as_bytes());
+
+    let attributes = events::new_mixnode_unjailing_event(
+        &info.sender,
+        &mix_identity,
+        read_current_epoch(deps.storage)?,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+fn validate_delegation_stake(delegation: &[Coin]) -> Result<(), ContractError> {
+    // check if anything was put as delegation
+    if delegation.is_empty() {
+        return Err(ContractError::EmptyDelegation);
+    }
+
+    if delegation.len() > 1 {
+        return Err(ContractError::MultipleDenoms);
+    }
+
+    // check that the denomination is correct
+    if delegation[0].denom != DENOM {
+        return Err(ContractError::WrongDenom {});
+    }
+
+    // check that we have provided a non-zero amount in the delegation
+    if delegation[0].amount.is_zero() {
+        return Err(ContractError::EmptyDelegation);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn try_delegate_to_mixnode(
+    deps: DepsMut,
+    info: MessageInfo,
+    mix_identity: IdentityKey,
 ) -> Result<Response, ContractError> {
     // check if the delegation contains any funds of the appropriate denomination
     validate_delegation_stake(&info.funds)?;
 
     // check if the target node actually exists
-    let mut gateways_bucket = gateways(deps.storage);
-    let mut current_bond = match gateways_bucket.load(gateway_identity.as_bytes()) {
+    let mut current_bond = match mixnodes_read(deps.storage).load(mix_identity.as_bytes()) {
         Ok(bond) => bond,
         Err(_) => {
-            return Err(ContractError::GatewayBondNotFound {
-                identity: gateway_identity,
+            return Err(ContractError::MixNodeBondNotFound {
+                identity: mix_identity,
             });
         }
     };
 
-    // update total_delegation of this node
-    current_bond.total_delegation.amount += info.funds[0].amount;
-    gateways_bucket.save(gateway_identity.as_bytes(), &current_bond)?;
-
-    let mut delegation_bucket = gateway_delegations(deps.storage, &gateway_identity);
     let sender_bytes = info.sender.as_bytes();
 
-    // write the delegation
-    match delegation_bucket.may_load(sender_bytes)? {
-        Some(existing_delegation) => {
-            delegation_bucket.save(sender_bytes, &(existing_delegation + info.funds[0].amount))?
-        }
-        None => delegation_bucket.save(sender_bytes, &info.funds[0].amount)?,
+    // rewards accumulated by the existing delegation are not added to the delegated stake,
+    // instead they're moved to its unclaimed balance, so that they could still be claimed
+    let settled_delegation = mix_delegations_read(deps.storage, &mix_identity)
+        .may_load(sender_bytes)?
+        .unwrap_or_default();
+    let accrued_reward = read_mix_delegation_value(deps.storage, &mix_identity, sender_bytes)?
+        .unwrap_or_default()
+        .checked_sub(settled_delegation)
+        .unwrap_or_default();
+    if !accrued_reward.is_zero() {
+        let unclaimed_reward =
+            read_mix_delegation_unclaimed_reward(deps.storage, &mix_identity, sender_bytes)?;
+        mix_delegation_unclaimed_rewards(deps.storage, &mix_identity)
+            .save(sender_bytes, &(unclaimed_reward + accrued_reward))?;
     }
 
-    Ok(Response::default())
+    // update total_delegation of this node. the moved rewards no longer earn anything, so they
+    // stop counting towards it (the same rounding consideration as when removing the delegation)
+    current_bond.total_delegation.amount = (current_bond.total_delegation.amount
+        + info.funds[0].amount)
+        .checked_sub(accrued_reward)
+        .unwrap_or_default();
+    mixnodes(deps.storage).save(mix_identity.as_bytes(), &current_bond)?;
+
+    save_mix_delegation(
+        deps.storage,
+        &mix_identity,
+        sender_bytes,
+        settled_delegation + info.funds[0].amount,
+    )?;
+
+    let attributes = events::new_mixnode_delegation_event(
+        &info.sender,
+        &mix_identity,
+        info.funds[0].amount,
+        read_current_epoch(deps.storage)?,
+    );
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
 }
 
-pub(crate) fn try_remove_delegation_from_gateway(
+pub(crate) fn try_remove_delegation_from_mixnode(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    gateway_identity: IdentityKey,
+    mix_identity: IdentityKey,
 ) -> Result<Response, ContractError> {
-    let mut delegation_bucket = gateway_delegations(deps.storage, &gateway_identity);
     let sender_bytes = info.sender.as_bytes();
-    match delegation_bucket.may_load(sender_bytes)? {
+    match read_mix_delegation_value(deps.storage, &mix_identity, sender_bytes)? {
         Some(delegation) => {
-            // remove delegation from the bucket
-            delegation_bucket.remove(sender_bytes);
+            let unclaimed_reward =
+                read_mix_delegation_unclaimed_reward(deps.storage, &mix_identity, sender_bytes)?;
 
-            // send delegated funds back to the delegation owner
-            let messages = vec![BankMsg::Send {
-                to_address: info.sender.to_string(),
-                amount: coins(delegation.u128(), DENOM),
-            }
-            .into()];
-
-            // update total_delegation of this node
-            let mut gateways_bucket = gateways(deps.storage);
-            // in some rare cases the gateway bond might no longer exist as the node unbonded
+            // remove delegation from the bucket
+            remove_mix_delegation(deps.storage, &mix_identity, sender_bytes);
+
+            // the delegated funds, alongside any unclaimed rewards, can be withdrawn by
+            // the delegation owner once the unbonding period is over
+            create_pending_withdrawal(
+                deps.storage,
+                &env,
+                info.sender.clone(),
+                delegation + unclaimed_reward,
+            )?;
+
+            // update total_delegation of this node, so that the removed stake immediately stops
+            // being taken into consideration for the topology
+            let mut mixnodes_bucket = mixnodes(deps.storage);
+            // in some rare cases the mixnode bond might no longer exist as the node unbonded
             // before delegation was removed. that is fine
-            if let Some(mut existing_bond) =
-                gateways_bucket.may_load(gateway_identity.as_bytes())?
-            {
-                // we should NEVER underflow here, if we do, it means we have some serious error in our logic
+            if let Some(mut existing_bond) = mixnodes_bucket.may_load(mix_identity.as_bytes())? {
+                // the total is rewarded as a whole while the individual delegations are scaled
+                // by the reward index, so due to rounding, the sum of the individual delegations
+                // might very slightly exceed it. in that case just bring the total to zero
                 existing_bond.total_delegation.amount = existing_bond
                     .total_delegation
                     .amount
                     .checked_sub(delegation)
-                    .unwrap();
-                gateways_bucket.save(gateway_identity.as_bytes(), &existing_bond)?;
+                    .unwrap_or_default();
+                mixnodes_bucket.save(mix_identity.as_bytes(), &existing_bond)?;
             }
 
+            let attributes = events::new_mixnode_undelegation_event(
+                &info.sender,
+                &mix_identity,
+                delegation,
+                read_current_epoch(deps.storage)?,
+            );
             Ok(Response {
                 submessages: Vec::new(),
-                messages,
-                attributes: Vec::new(),
+                messages: Vec::new(),
+                attributes,
                 data: None,
             })
         }
-        None => Err(ContractError::NoGatewayDelegationFound {
-            identity: gateway_identity,
+        None => Err(ContractError::NoMixnodeDelegationFound {
+            identity: mix_identity,
             address: info.sender,
         }),
     }
 }
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
-    use crate::contract::{
-        execute, query, INITIAL_DEFAULT_EPOCH_LENGTH, INITIAL_GATEWAY_BOND,
-        INITIAL_GATEWAY_BOND_REWARD_RATE, INITIAL_GATEWAY_DELEGATION_REWARD_RATE,
-        INITIAL_MIXNODE_BOND, INITIAL_MIXNODE_BOND_REWARD_RATE,
-        INITIAL_MIXNODE_DELEGATION_REWARD_RATE,
-    };
-    use crate::helpers::calculate_epoch_reward_rate;
-    use crate::storage::{
-        gateway_delegations, gateway_delegations_read, layer_distribution_read,
-        mix_delegations_read, read_gateway_bond, read_gateway_epoch_bond_reward_rate,
-        read_mixnode_bond,
-    };
-    use crate::support::tests::helpers;
-    use crate::support::tests::helpers::{
-        add_gateway, add_mixnode, gateway_fixture, good_gateway_bond, good_mixnode_bond,
-        mix_node_fixture,
-    };
-    use cosmwasm_std::testing::{mock_env, mock_info};
-    use cosmwasm_std::{coin, coins, from_binary, Addr, Uint128};
-    use mixnet_contract::{
-        ExecuteMsg, LayerDistribution, PagedGatewayResponse, PagedMixnodeResponse, QueryMsg,
-    };
-
-    #[test]
-    fn validating_mixnode_bond() {
-        // you must send SOME funds
-        let result = validate_mixnode_bond(&[], INITIAL_MIXNODE_BOND);
-        assert_eq!(result, Err(ContractError::NoBondFound));
+// either pays out the rewards accumulated by the sender's delegation (including the unclaimed ones
+// set aside when it was increased) or adds them to it, without touching the delegated stake
+pub(crate) fn try_claim_delegator_reward(
+    deps: DepsMut,
+    info: MessageInfo,
+    mix_identity: IdentityKey,
+    compound: bool,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedClaimFunds);
+    }
 
-        // you must send at least 100 coins...
-        let mut bond = good_mixnode_bond();
-        bond[0].amount = INITIAL_MIXNODE_BOND.checked_sub(Uint128(1)).unwrap();
-        let result = validate_mixnode_bond(&bond, INITIAL_MIXNODE_BOND);
-        assert_eq!(
-            result,
-            Err(ContractError::InsufficientMixNodeBond {
-                received: Into::<u128>::into(INITIAL_MIXNODE_BOND) - 1,
-                minimum: INITIAL_MIXNODE_BOND.into(),
+    let sender_bytes = info.sender.as_bytes();
+    let delegation = match read_mix_delegation_value(deps.storage, &mix_identity, sender_bytes)? {
+        Some(delegation) => delegation,
+        None => {
+            return Err(ContractError::NoMixnodeDelegationFound {
+                identity: mix_identity,
+                address: info.sender,
             })
-        );
+        }
+    };
 
-        // more than that is still fine
-        let mut bond = good_mixnode_bond();
-        bond[0].amount = INITIAL_MIXNODE_BOND + Uint128(1);
-        let result = validate_mixnode_bond(&bond, INITIAL_MIXNODE_BOND);
-        assert!(result.is_ok());
+    // the stored amount is the value of the delegation at the time it was last settled
+    let settled_amount = mix_delegations_read(deps.storage, &mix_identity).load(sender_bytes)?;
+    let accrued_reward = delegation.checked_sub(settled_amount).unwrap_or_default();
+    let unclaimed_reward =
+        read_mix_delegation_unclaimed_reward(deps.storage, &mix_identity, sender_bytes)?;
+    let reward = accrued_reward + unclaimed_reward;
+    if reward.is_zero() {
+        return Err(ContractError::NoRewardToClaim);
+    }
+    mix_delegation_unclaimed_rewards(deps.storage, &mix_identity).remove(sender_bytes);
+
+    let messages = if compound {
+        save_mix_delegation(
+            deps.storage,
+            &mix_identity,
+            sender_bytes,
+            delegation + unclaimed_reward,
+        )?;
+
+        // the node's total delegation already includes the accrued rewards,
+        // but not the ones that have been set aside
+        let mut mixnodes_bucket = mixnodes(deps.storage);
+        if let Some(mut existing_bond) = mixnodes_bucket.may_load(mix_identity.as_bytes())? {
+            existing_bond.total_delegation.amount += unclaimed_reward;
+            mixnodes_bucket.save(mix_identity.as_bytes(), &existing_bond)?;
+        }
+        Vec::new()
+    } else {
+        // reset the delegation back to its settled value
+        save_mix_delegation(deps.storage, &mix_identity, sender_bytes, settled_amount)?;
+
+        let mut mixnodes_bucket = mixnodes(deps.storage);
+        // the node might have unbonded in the meantime, that is fine
+        if let Some(mut existing_bond) = mixnodes_bucket.may_load(mix_identity.as_bytes())? {
+            // same rounding consideration as when removing the delegation
+            existing_bond.total_delegation.amount = existing_bond
+                .total_delegation
+                .amount
+                .checked_sub(accrued_reward)
+                .unwrap_or_default();
+            mixnodes_bucket.save(mix_identity.as_bytes(), &existing_bond)?;
+        }
 
-        // it must be sent in the defined denom!
-        let mut bond = good_mixnode_bond();
-        bond[0].denom = "baddenom".to_string();
-        let result = validate_mixnode_bond(&bond, INITIAL_MIXNODE_BOND);
-        assert_eq!(result, Err(ContractError::WrongDenom {}));
+        vec![BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(reward.u128(), DENOM),
+        }
+        .into()]
+    };
 
-        let mut bond = good_mixnode_bond();
-        bond[0].denom = "foomp".to_string();
+    let attributes = events::new_delegator_reward_claim_event(
+        &info.sender,
+        &mix_identity,
+        reward,
+        compound,
+        read_current_epoch(deps.storage)?,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages,
+        attributes,
+        data: None,
+    })
+}
+
+// pays out all the pending withdrawals of the sender whose unbonding period is over
+pub(crate) fn try_claim_pending_withdrawals(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedClaimFunds);
+    }
+
+    let current_time = env.block.time.seconds();
+    let limit = limit
+        .unwrap_or(WITHDRAWAL_CLAIM_DEFAULT_LIMIT)
+        .min(WITHDRAWAL_CLAIM_MAX_LIMIT) as usize;
+
+    // the withdrawals are ordered by their creation, so unless the unbonding period got shortened
+    // in the meantime, the oldest ones are the first to become available. The limit applies to
+    // the scanned ones so that the cost of the claim doesn't depend on the length of the queue
+    let available_withdrawals = pending_withdrawals_read(deps.storage, &info.sender)
+        .range(None, None, Order::Ascending)
+        .take(limit)
+        .filter(|res| match res {
+            Ok((_, withdrawal)) => withdrawal.is_available(current_time),
+            Err(_) => true,
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if available_withdrawals.is_empty() {
+        return Err(ContractError::NoAvailableWithdrawals);
+    }
+
+    let mut total = Uint128::zero();
+    let mut withdrawals_bucket = pending_withdrawals(deps.storage, &info.sender);
+    for (key, withdrawal) in available_withdrawals.iter() {
+        total += withdrawal.amount.amount;
+        withdrawals_bucket.remove(key);
+    }
+
+    let messages = vec![BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: coins(total.u128(), DENOM),
+    }
+    .into()];
+
+    let attributes = events::new_pending_withdrawals_claim_event(
+        &info.sender,
+        available_withdrawals.len(),
+        total,
+    );
+
+    Ok(Response {
+        submessages: Vec::new(),
+        messages,
+        attributes,
+        data: None,
+    })
+}
+
+pub(crate) fn try_delegate_to_gateway(
+    deps: DepsMut,
+    info: MessageInfo,
+    gateway_identity: IdentityKey,
+) -> Result<Response, ContractError> {
+    // check if the delegation contains any funds of the appropriate denomination
+    validate_delegation_stake(&info.funds)?;
+
+    // check if the target node actually exists
+    let mut gateways_bucket = gateways(deps.storage);
+    let mut current_bond = match gateways_bucket.load(gateway_identity.as_bytes()) {
+        Ok(bond) => bond,
+        Err(_) => {
+            return Err(ContractError::GatewayBondNotFound {
+                identity: gateway_identity,
+            });
+        }
+    };
+
+    // update total_delegation of this node
+    current_bond.total_delegation.amount += info.funds[0].amount;
+    gateways_bucket.save(gateway_identity.as_bytes(), &current_bond)?;
+
+    let sender_bytes = info.sender.as_bytes();
+
+    // write the delegation, settling any rewards the existing one has accumulated so far
+    let existing_delegation =
+        read_gateway_delegation_value(deps.storage, &gateway_identity, sender_bytes)?
+            .unwrap_or_default();
+    save_gateway_delegation(
+        deps.storage,
+        &gateway_identity,
+        sender_bytes,
+        existing_delegation + info.funds[0].amount,
+    )?;
+
+    let attributes = events::new_gateway_delegation_event(
+        &info.sender,
+        &gateway_identity,
+        info.funds[0].amount,
+        read_current_epoch(deps.storage)?,
+    );
+    Ok(Response {
+        submessages: Vec::new(),
+        messages: Vec::new(),
+        attributes,
+        data: None,
+    })
+}
+
+pub(crate) fn try_remove_delegation_from_gateway(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    gateway_identity: IdentityKey,
+) -> Result<Response, ContractError> {
+    let sender_bytes = info.sender.as_bytes();
+    match read_gateway_delegation_value(deps.storage, &gateway_identity, sender_bytes)? {
+        Some(delegation) => {
+            // remove delegation from the bucket
+            remove_gateway_delegation(deps.storage, &gateway_identity, sender_bytes);
+
+            // the delegated funds can be withdrawn by the delegation owner once the unbonding
+            // period is over
+            create_pending_withdrawal(deps.storage, &env, info.sender.clone(), delegation)?;
+
+            // update total_delegation of this node, so that the removed stake immediately stops
+            // being taken into consideration
+            let mut gateways_bucket = gateways(deps.storage);
+            // in some rare cases the gateway bond might no longer exist as the node unbonded
+            // before delegation was removed. that is fine
+            if let Some(mut existing_bond) =
+                gateways_bucket.may_load(gateway_identity.as_bytes())?
+            {
+                // the total is rewarded as a whole while the individual delegations are scaled
+                // by the reward index, so due to rounding, the sum of the individual delegations
+                // might very slightly exceed it. in that case just bring the total to zero
+                existing_bond.total_delegation.amount = existing_bond
+                    .total_delegation
+                    .amount
+                    .checked_sub(delegation)
+                    .unwrap_or_default();
+                gateways_bucket.save(gateway_identity.as_bytes(), &existing_bond)?;
+            }
+
+            let attributes = events::new_gateway_undelegation_event(
+                &info.sender,
+                &gateway_identity,
+                delegation,
+                read_current_epoch(deps.storage)?,
+            );
+            Ok(Response {
+                submessages: Vec::new(),
+                messages: Vec::new(),
+                attributes,
+                data: None,
+            })
+        }
+        None => Err(ContractError::NoGatewayDelegationFound {
+            identity: gateway_identity,
+            address: info.sender,
+        }),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::contract::{
+        execute, query, INITIAL_DEFAULT_EPOCH_LENGTH, INITIAL_GATEWAY_BOND,
+        INITIAL_GATEWAY_BOND_REWARD_RATE, INITIAL_GATEWAY_DELEGATION_REWARD_RATE,
+        INITIAL_MINIMUM_MIXNODE_UPTIME, INITIAL_MIXNODE_BOND, INITIAL_MIXNODE_BOND_REWARD_RATE,
+        INITIAL_MIXNODE_DELEGATION_REWARD_RATE, INITIAL_MIXNODE_JAIL_EPOCHS,
+        INITIAL_MIXNODE_SLASHING_PERCENT, INITIAL_UNBONDING_PERIOD,
+    };
+    use crate::helpers::{calculate_epoch_reward_rate, compound_reward_index};
+    use crate::storage::{
+        gateway_delegations, gateway_delegations_read, layer_distribution_read,
+        mix_delegations_read, read_gateway_bond, read_gateway_epoch_bond_reward_rate,
+        read_mixnode_bond,
+    };
+    use crate::support::tests::helpers;
+    use crate::support::tests::helpers::{
+        add_gateway, add_mixnode, gateway_fixture, good_gateway_bond, good_mixnode_bond,
+        mix_node_fixture,
+    };
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{attr, coin, coins, from_binary, Addr, Storage, Uint128};
+    use mixnet_contract::{
+        ExecuteMsg, LayerDistribution, PagedGatewayResponse, PagedMixnodeResponse, QueryMsg,
+    };
+
+    #[test]
+    fn validating_mixnode_bond() {
+        // you must send SOME funds
+        let result = validate_mixnode_bond(&[], INITIAL_MIXNODE_BOND);
+        assert_eq!(result, Err(ContractError::NoBondFound));
+
+        // you must send at least 100 coins...
+        let mut bond = good_mixnode_bond();
+        bond[0].amount = INITIAL_MIXNODE_BOND.checked_sub(Uint128(1)).unwrap();
+        let result = validate_mixnode_bond(&bond, INITIAL_MIXNODE_BOND);
+        assert_eq!(
+            result,
+            Err(ContractError::InsufficientMixNodeBond {
+                received: Into::<u128>::into(INITIAL_MIXNODE_BOND) - 1,
+                minimum: INITIAL_MIXNODE_BOND.into(),
+            })
+        );
+
+        // more than that is still fine
+        let mut bond = good_mixnode_bond();
+        bond[0].amount = INITIAL_MIXNODE_BOND + Uint128(1);
+        let result = validate_mixnode_bond(&bond, INITIAL_MIXNODE_BOND);
+        assert!(result.is_ok());
+
+        // it must be sent in the defined denom!
+        let mut bond = good_mixnode_bond();
+        bond[0].denom = "baddenom".to_string();
+        let result = validate_mixnode_bond(&bond, INITIAL_MIXNODE_BOND);
+        assert_eq!(result, Err(ContractError::WrongDenom {}));
+
+        let mut bond = good_mixnode_bond();
+        bond[0].denom = "foomp".to_string();
         let result = validate_mixnode_bond(&bond, INITIAL_MIXNODE_BOND);
         assert_eq!(result, Err(ContractError::WrongDenom {}));
     }
@@ -782,6 +2372,7 @@ pub mod tests {
                 identity_key: "anyonesmixnode".into(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         // we are informed that we didn't send enough funds
@@ -814,6 +2405,7 @@ pub mod tests {
                 identity_key: "anyonesmixnode".into(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         // we get back a message telling us everything was OK
@@ -847,10 +2439,19 @@ pub mod tests {
                 identity_key: "foompermixnode".into(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         let execute_response = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(execute_response.attributes[0], attr("overwritten", false));
+        assert_eq!(
+            execute_response.attributes,
+            events::new_mixnode_bonding_event(
+                &Addr::unchecked("foomper"),
+                "foompermixnode",
+                INITIAL_MIXNODE_BOND,
+                0
+            )
+        );
 
         let info = mock_info("foomper", &good_mixnode_bond());
         let msg = ExecuteMsg::BondMixnode {
@@ -858,11 +2459,20 @@ pub mod tests {
                 identity_key: "foompermixnode".into(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
-        // we get a log message about it (TODO: does it get back to the user?)
+        // re-bonding emits a bonding event just like the initial bond
         let execute_response = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(execute_response.attributes[0], attr("overwritten", true));
+        assert_eq!(
+            execute_response.attributes,
+            events::new_mixnode_bonding_event(
+                &Addr::unchecked("foomper"),
+                "foompermixnode",
+                INITIAL_MIXNODE_BOND,
+                0
+            )
+        );
 
         // bonding fails if the user already owns a gateway
         let info = mock_info("gateway-owner", &good_gateway_bond());
@@ -880,6 +2490,7 @@ pub mod tests {
                 identity_key: "ownersmixnode".into(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
         let execute_response = execute(deps.as_mut(), mock_env(), info, msg);
         assert_eq!(execute_response, Err(ContractError::AlreadyOwnsGateway));
@@ -895,6 +2506,7 @@ pub mod tests {
                 identity_key: "ownersmixnode".into(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
         let execute_response = execute(deps.as_mut(), mock_env(), info, msg);
         assert!(execute_response.is_ok());
@@ -913,6 +2525,7 @@ pub mod tests {
                 identity_key: "myAwesomeMixnode".to_string(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         // before the execution the node had no associated owner
@@ -943,6 +2556,7 @@ pub mod tests {
                 identity_key: "myAwesomeMixnode".to_string(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -954,6 +2568,7 @@ pub mod tests {
                 identity_key: "myAwesomeMixnode".to_string(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         let execute_response = execute(deps.as_mut(), mock_env(), info, msg);
@@ -976,6 +2591,7 @@ pub mod tests {
                 host: "1.1.1.1:1789".into(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -987,6 +2603,7 @@ pub mod tests {
                 host: "2.2.2.2:1789".into(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         assert!(execute(deps.as_mut(), mock_env(), info, msg).is_ok());
@@ -1017,6 +2634,7 @@ pub mod tests {
                 identity_key: "mix1".to_string(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -1074,6 +2692,7 @@ pub mod tests {
                 identity_key: "fredsmixnode".to_string(),
                 ..helpers::mix_node_fixture()
             },
+            10,
         )
         .unwrap();
 
@@ -1083,31 +2702,35 @@ pub mod tests {
         // un-register fred's node
         let info = mock_info("fred", &[]);
         let msg = ExecuteMsg::UnbondMixnode {};
-        let remove_fred = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-
-        // we should see log messages come back showing an unbond message
-        let expected_attributes = vec![
-            attr("action", "unbond"),
-            attr(
-                "mixnode_bond",
-                format!(
-                    "amount: {} {}, owner: fred, identity: fredsmixnode",
-                    INITIAL_MIXNODE_BOND, DENOM
-                ),
-            ),
-        ];
+        let env = mock_env();
+        let remove_fred = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // rather than being sent back immediately, fred's funds are locked until the unbonding period is over
+        let expected_withdrawal = PendingWithdrawal::new(
+            1,
+            info.sender.clone(),
+            good_mixnode_bond()[0].clone(),
+            env.block.time.seconds() + INITIAL_UNBONDING_PERIOD as u64 * 60 * 60,
+        );
+        assert_eq!(
+            expected_withdrawal,
+            pending_withdrawals_read(&deps.storage, &info.sender)
+                .load(&1u64.to_be_bytes())
+                .unwrap()
+        );
 
-        // we should see a funds transfer from the contract back to fred
-        let expected_messages = vec![BankMsg::Send {
-            to_address: String::from(info.sender),
-            amount: good_mixnode_bond(),
-        }
-        .into()];
+        // we should see an unbonding event with the withdrawn amount
+        let expected_attributes = events::new_mixnode_unbonding_event(
+            &info.sender,
+            "fredsmixnode",
+            expected_withdrawal.amount.amount,
+            0,
+        );
 
         // run the executer and check that we got back the correct results
         let expected = Response {
             submessages: Vec::new(),
-            messages: expected_messages,
+            messages: Vec::new(),
             attributes: expected_attributes,
             data: None,
         };
@@ -1129,6 +2752,7 @@ pub mod tests {
                 identity_key: "myAwesomeMixnode".to_string(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -1156,6 +2780,7 @@ pub mod tests {
                 identity_key: "myAwesomeMixnode".to_string(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
 
         assert!(execute(deps.as_mut(), mock_env(), info, msg).is_ok());
@@ -1168,67 +2793,277 @@ pub mod tests {
     }
 
     #[test]
-    fn validating_gateway_bond() {
-        // you must send SOME funds
-        let result = validate_gateway_bond(&[], INITIAL_GATEWAY_BOND);
-        assert_eq!(result, Err(ContractError::NoBondFound));
+    fn updating_mixnode_config() {
+        let mut deps = helpers::init_contract();
 
-        // you must send at least 100 coins...
-        let mut bond = good_gateway_bond();
-        bond[0].amount = INITIAL_GATEWAY_BOND.checked_sub(Uint128(1)).unwrap();
-        let result = validate_gateway_bond(&bond, INITIAL_GATEWAY_BOND);
+        fn update(sender: &str, funds: &[Coin], deps: DepsMut) -> Result<Response, ContractError> {
+            try_update_mixnode_config(
+                deps,
+                mock_info(sender, funds),
+                "1.1.1.1".to_string(),
+                1111,
+                2222,
+                3333,
+                "0.42.0".to_string(),
+            )
+        }
+
+        // it's impossible to update a non-existent node
         assert_eq!(
-            result,
-            Err(ContractError::InsufficientGatewayBond {
-                received: Into::<u128>::into(INITIAL_GATEWAY_BOND) - 1,
-                minimum: INITIAL_GATEWAY_BOND.into(),
-            })
+            Err(ContractError::NoAssociatedMixNodeBond {
+                owner: Addr::unchecked("mix-owner")
+            }),
+            update("mix-owner", &[], deps.as_mut())
         );
 
-        // more than that is still fine
-        let mut bond = good_gateway_bond();
-        bond[0].amount = INITIAL_GATEWAY_BOND + Uint128(1);
-        let result = validate_gateway_bond(&bond, INITIAL_GATEWAY_BOND);
-        assert!(result.is_ok());
+        let identity = add_mixnode("mix-owner", good_mixnode_bond(), &mut deps);
+        try_delegate_to_mixnode(
+            deps.as_mut(),
+            mock_info("delegator", &coins(123, DENOM)),
+            identity.clone(),
+        )
+        .unwrap();
+        let original_bond = mixnodes_read(&deps.storage)
+            .load(identity.as_bytes())
+            .unwrap();
 
-        // it must be sent in the defined denom!
-        let mut bond = good_gateway_bond();
-        bond[0].denom = "baddenom".to_string();
-        let result = validate_gateway_bond(&bond, INITIAL_GATEWAY_BOND);
-        assert_eq!(result, Err(ContractError::WrongDenom {}));
+        // only the owner can update the node
+        assert_eq!(
+            Err(ContractError::NoAssociatedMixNodeBond {
+                owner: Addr::unchecked("not-owner")
+            }),
+            update("not-owner", &[], deps.as_mut())
+        );
 
-        let mut bond = good_gateway_bond();
-        bond[0].denom = "foomp".to_string();
-        let result = validate_gateway_bond(&bond, INITIAL_GATEWAY_BOND);
-        assert_eq!(result, Err(ContractError::WrongDenom {}));
-    }
+        // and it must not send any funds that would get lost
+        assert_eq!(
+            Err(ContractError::UnexpectedConfigUpdateFunds),
+            update("mix-owner", &good_mixnode_bond(), deps.as_mut())
+        );
 
-    #[test]
-    fn gateway_add() {
-        let mut deps = helpers::init_contract();
+        // nor provide invalid node information
+        let invalid_update = try_update_mixnode_config(
+            deps.as_mut(),
+            mock_info("mix-owner", &[]),
+            "1.1.1.1".to_string(),
+            1111,
+            0,
+            3333,
+            "0.42.0".to_string(),
+        );
+        assert_eq!(
+            Err(ContractError::ZeroNodePort {
+                port_name: "verloc".to_string()
+            }),
+            invalid_update
+        );
 
-        // if we fail validation (by say not sending enough funds
-        let insufficient_bond = Into::<u128>::into(INITIAL_GATEWAY_BOND) - 1;
-        let info = mock_info("anyone", &coins(insufficient_bond, DENOM));
-        let msg = ExecuteMsg::BondGateway {
-            gateway: helpers::gateway_fixture(),
-        };
+        let res = update("mix-owner", &[], deps.as_mut()).unwrap();
 
-        // we are informed that we didn't send enough funds
-        let result = execute(deps.as_mut(), mock_env(), info, msg);
+        let updated_bond = mixnodes_read(&deps.storage)
+            .load(identity.as_bytes())
+            .unwrap();
         assert_eq!(
-            result,
-            Err(ContractError::InsufficientGatewayBond {
-                received: insufficient_bond,
-                minimum: INITIAL_GATEWAY_BOND.into(),
-            })
+            events::new_mixnode_config_update_event(
+                &Addr::unchecked("mix-owner"),
+                &identity,
+                &updated_bond.mix_node,
+                0
+            ),
+            res.attributes
+        );
+        assert_eq!("1.1.1.1", updated_bond.mix_node.host);
+        assert_eq!(1111, updated_bond.mix_node.mix_port);
+        assert_eq!(2222, updated_bond.mix_node.verloc_port);
+        assert_eq!(3333, updated_bond.mix_node.http_api_port);
+        assert_eq!("0.42.0", updated_bond.mix_node.version);
+
+        // while everything else remained the same
+        assert_eq!(original_bond.identity(), updated_bond.identity());
+        assert_eq!(
+            original_bond.mix_node.sphinx_key,
+            updated_bond.mix_node.sphinx_key
+        );
+        assert_eq!(original_bond.layer, updated_bond.layer);
+        assert_eq!(original_bond.bond_amount, updated_bond.bond_amount);
+        assert_eq!(
+            original_bond.total_delegation,
+            updated_bond.total_delegation
+        );
+        assert_eq!(
+            Uint128(123),
+            mix_delegations_read(&deps.storage, &identity)
+                .load(b"delegator")
+                .unwrap()
         );
+    }
 
-        // make sure no gateway was inserted into the topology
-        let res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetGateways {
+    #[test]
+    fn validating_node_config() {
+        assert!(validate_mixnode_config(&mix_node_fixture()).is_ok());
+        assert!(validate_gateway_config(&gateway_fixture()).is_ok());
+
+        for host in &["", "1.1.1.1 ", "mix\nnode.org"] {
+            assert_eq!(
+                Err(ContractError::InvalidNodeHost {
+                    host: host.to_string(),
+                    max: MAX_NODE_HOST_LENGTH,
+                }),
+                validate_mixnode_config(&MixNode {
+                    host: host.to_string(),
+                    ..mix_node_fixture()
+                })
+            );
+        }
+        let long_host = "a".repeat(MAX_NODE_HOST_LENGTH + 1);
+        assert!(validate_node_host(&long_host).is_err());
+
+        let long_version = "1".repeat(MAX_NODE_VERSION_LENGTH + 1);
+        for version in &["", "0.10.0; rm -rf", long_version.as_str()] {
+            assert_eq!(
+                Err(ContractError::InvalidNodeVersion {
+                    version: version.to_string(),
+                    max: MAX_NODE_VERSION_LENGTH,
+                }),
+                validate_gateway_config(&Gateway {
+                    version: version.to_string(),
+                    ..gateway_fixture()
+                })
+            );
+        }
+        assert!(validate_node_version("1.0.0-rc.1+build.2").is_ok());
+
+        assert_eq!(
+            Err(ContractError::ZeroNodePort {
+                port_name: "http api".to_string()
+            }),
+            validate_mixnode_config(&MixNode {
+                http_api_port: 0,
+                ..mix_node_fixture()
+            })
+        );
+        assert_eq!(
+            Err(ContractError::InvalidNodeKey {
+                key_name: "sphinx".to_string(),
+                key: "".to_string(),
+                max: MAX_NODE_KEY_LENGTH,
+            }),
+            validate_gateway_config(&Gateway {
+                sphinx_key: "".to_string(),
+                ..gateway_fixture()
+            })
+        );
+
+        let long_location = "a".repeat(MAX_GATEWAY_LOCATION_LENGTH + 1);
+        assert_eq!(
+            Err(ContractError::InvalidGatewayLocation {
+                location: long_location.clone(),
+                max: MAX_GATEWAY_LOCATION_LENGTH,
+            }),
+            validate_gateway_config(&Gateway {
+                location: long_location,
+                ..gateway_fixture()
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_node_config_is_rejected_at_bonding_time() {
+        let mut deps = helpers::init_contract();
+
+        assert_eq!(
+            Err(ContractError::InvalidNodeHost {
+                host: "".to_string(),
+                max: MAX_NODE_HOST_LENGTH,
+            }),
+            try_add_mixnode(
+                deps.as_mut(),
+                mock_info("mix-owner", &good_mixnode_bond()),
+                MixNode {
+                    host: "".to_string(),
+                    ..mix_node_fixture()
+                },
+                0,
+            )
+        );
+
+        assert_eq!(
+            Err(ContractError::ZeroNodePort {
+                port_name: "clients".to_string()
+            }),
+            try_add_gateway(
+                deps.as_mut(),
+                mock_info("gateway-owner", &good_gateway_bond()),
+                Gateway {
+                    clients_port: 0,
+                    ..gateway_fixture()
+                },
+            )
+        );
+    }
+
+    #[test]
+    fn validating_gateway_bond() {
+        // you must send SOME funds
+        let result = validate_gateway_bond(&[], INITIAL_GATEWAY_BOND);
+        assert_eq!(result, Err(ContractError::NoBondFound));
+
+        // you must send at least 100 coins...
+        let mut bond = good_gateway_bond();
+        bond[0].amount = INITIAL_GATEWAY_BOND.checked_sub(Uint128(1)).unwrap();
+        let result = validate_gateway_bond(&bond, INITIAL_GATEWAY_BOND);
+        assert_eq!(
+            result,
+            Err(ContractError::InsufficientGatewayBond {
+                received: Into::<u128>::into(INITIAL_GATEWAY_BOND) - 1,
+                minimum: INITIAL_GATEWAY_BOND.into(),
+            })
+        );
+
+        // more than that is still fine
+        let mut bond = good_gateway_bond();
+        bond[0].amount = INITIAL_GATEWAY_BOND + Uint128(1);
+        let result = validate_gateway_bond(&bond, INITIAL_GATEWAY_BOND);
+        assert!(result.is_ok());
+
+        // it must be sent in the defined denom!
+        let mut bond = good_gateway_bond();
+        bond[0].denom = "baddenom".to_string();
+        let result = validate_gateway_bond(&bond, INITIAL_GATEWAY_BOND);
+        assert_eq!(result, Err(ContractError::WrongDenom {}));
+
+        let mut bond = good_gateway_bond();
+        bond[0].denom = "foomp".to_string();
+        let result = validate_gateway_bond(&bond, INITIAL_GATEWAY_BOND);
+        assert_eq!(result, Err(ContractError::WrongDenom {}));
+    }
+
+    #[test]
+    fn gateway_add() {
+        let mut deps = helpers::init_contract();
+
+        // if we fail validation (by say not sending enough funds
+        let insufficient_bond = Into::<u128>::into(INITIAL_GATEWAY_BOND) - 1;
+        let info = mock_info("anyone", &coins(insufficient_bond, DENOM));
+        let msg = ExecuteMsg::BondGateway {
+            gateway: helpers::gateway_fixture(),
+        };
+
+        // we are informed that we didn't send enough funds
+        let result = execute(deps.as_mut(), mock_env(), info, msg);
+        assert_eq!(
+            result,
+            Err(ContractError::InsufficientGatewayBond {
+                received: insufficient_bond,
+                minimum: INITIAL_GATEWAY_BOND.into(),
+            })
+        );
+
+        // make sure no gateway was inserted into the topology
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetGateways {
                 start_after: None,
                 limit: Option::from(2),
             },
@@ -1280,7 +3115,15 @@ pub mod tests {
         };
 
         let execute_response = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(execute_response.attributes[0], attr("overwritten", false));
+        assert_eq!(
+            execute_response.attributes,
+            events::new_gateway_bonding_event(
+                &Addr::unchecked("foomper"),
+                "foompersgateway",
+                good_gateway_bond()[0].amount,
+                0
+            )
+        );
 
         let info = mock_info("foomper", &good_gateway_bond());
         let msg = ExecuteMsg::BondGateway {
@@ -1290,9 +3133,17 @@ pub mod tests {
             },
         };
 
-        // we get a log message about it (TODO: does it get back to the user?)
+        // re-bonding emits a bonding event just like the initial bond
         let execute_response = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(execute_response.attributes[0], attr("overwritten", true));
+        assert_eq!(
+            execute_response.attributes,
+            events::new_gateway_bonding_event(
+                &Addr::unchecked("foomper"),
+                "foompersgateway",
+                good_gateway_bond()[0].amount,
+                0
+            )
+        );
 
         // bonding fails if the user already owns a mixnode
         let info = mock_info("mixnode-owner", &good_mixnode_bond());
@@ -1301,6 +3152,7 @@ pub mod tests {
                 identity_key: "ownersmix".into(),
                 ..helpers::mix_node_fixture()
             },
+            profit_margin_percent: 10,
         };
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -1482,32 +3334,35 @@ pub mod tests {
         // unbond fred's node
         let info = mock_info("fred", &[]);
         let msg = ExecuteMsg::UnbondGateway {};
-        let remove_fred = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-
-        // we should see log messages come back showing an unbond message
-        let expected_attributes = vec![
-            attr("action", "unbond"),
-            attr("address", "fred"),
-            attr(
-                "gateway_bond",
-                format!(
-                    "amount: {} {}, owner: fred, identity: fredsgateway",
-                    INITIAL_GATEWAY_BOND, DENOM
-                ),
-            ),
-        ];
+        let env = mock_env();
+        let remove_fred = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // rather than being sent back immediately, fred's funds are locked until the unbonding period is over
+        let expected_withdrawal = PendingWithdrawal::new(
+            1,
+            info.sender.clone(),
+            good_gateway_bond()[0].clone(),
+            env.block.time.seconds() + INITIAL_UNBONDING_PERIOD as u64 * 60 * 60,
+        );
+        assert_eq!(
+            expected_withdrawal,
+            pending_withdrawals_read(&deps.storage, &info.sender)
+                .load(&1u64.to_be_bytes())
+                .unwrap()
+        );
 
-        // we should see a funds transfer from the contract back to fred
-        let expected_messages = vec![BankMsg::Send {
-            to_address: String::from(info.sender),
-            amount: good_gateway_bond(),
-        }
-        .into()];
+        // we should see an unbonding event with the withdrawn bond
+        let expected_attributes = events::new_gateway_unbonding_event(
+            &info.sender,
+            "fredsgateway",
+            good_gateway_bond()[0].amount,
+            0,
+        );
 
         // run the executer and check that we got back the correct results
         let expected = Response {
             submessages: Vec::new(),
-            messages: expected_messages,
+            messages: Vec::new(),
             attributes: expected_attributes,
             data: None,
         };
@@ -1568,7 +3423,101 @@ pub mod tests {
     }
 
     #[test]
-    fn updating_state_params() {
+    fn updating_gateway_config() {
+        let mut deps = helpers::init_contract();
+
+        fn update(sender: &str, funds: &[Coin], deps: DepsMut) -> Result<Response, ContractError> {
+            try_update_gateway_config(
+                deps,
+                mock_info(sender, funds),
+                "1.1.1.1".to_string(),
+                1111,
+                2222,
+                "Neverland".to_string(),
+                "0.42.0".to_string(),
+            )
+        }
+
+        // it's impossible to update a non-existent node
+        assert_eq!(
+            Err(ContractError::NoAssociatedGatewayBond {
+                owner: Addr::unchecked("gateway-owner")
+            }),
+            update("gateway-owner", &[], deps.as_mut())
+        );
+
+        let identity = add_gateway("gateway-owner", good_gateway_bond(), &mut deps);
+        let original_bond = gateways_read(&deps.storage)
+            .load(identity.as_bytes())
+            .unwrap();
+
+        // only the owner can update the node
+        assert_eq!(
+            Err(ContractError::NoAssociatedGatewayBond {
+                owner: Addr::unchecked("not-owner")
+            }),
+            update("not-owner", &[], deps.as_mut())
+        );
+
+        // and it must not send any funds that would get lost
+        assert_eq!(
+            Err(ContractError::UnexpectedConfigUpdateFunds),
+            update("gateway-owner", &good_gateway_bond(), deps.as_mut())
+        );
+
+        // nor provide invalid node information
+        let invalid_update = try_update_gateway_config(
+            deps.as_mut(),
+            mock_info("gateway-owner", &[]),
+            "1.1.1.1".to_string(),
+            1111,
+            2222,
+            "Neverland".to_string(),
+            "".to_string(),
+        );
+        assert_eq!(
+            Err(ContractError::InvalidNodeVersion {
+                version: "".to_string(),
+                max: MAX_NODE_VERSION_LENGTH,
+            }),
+            invalid_update
+        );
+
+        let res = update("gateway-owner", &[], deps.as_mut()).unwrap();
+
+        let updated_bond = gateways_read(&deps.storage)
+            .load(identity.as_bytes())
+            .unwrap();
+        assert_eq!(
+            events::new_gateway_config_update_event(
+                &Addr::unchecked("gateway-owner"),
+                &identity,
+                &updated_bond.gateway,
+                0
+            ),
+            res.attributes
+        );
+        assert_eq!("1.1.1.1", updated_bond.gateway.host);
+        assert_eq!(1111, updated_bond.gateway.mix_port);
+        assert_eq!(2222, updated_bond.gateway.clients_port);
+        assert_eq!("Neverland", updated_bond.gateway.location);
+        assert_eq!("0.42.0", updated_bond.gateway.version);
+
+        // while everything else remained the same
+        assert_eq!(original_bond.identity(), updated_bond.identity());
+        assert_eq!(
+            original_bond.gateway.sphinx_key,
+            updated_bond.gateway.sphinx_key
+        );
+        assert_eq!(original_bond.bond_amount, updated_bond.bond_amount);
+        assert_eq!(
+            original_bond.total_delegation,
+            updated_bond.total_delegation
+        );
+    }
+
+    #[test]
+    fn applying_state_params() {
         let mut deps = helpers::init_contract();
 
         let new_params = StateParams {
@@ -1584,17 +3533,18 @@ pub mod tests {
                 INITIAL_GATEWAY_DELEGATION_REWARD_RATE,
             ),
             mixnode_active_set_size: 42, // change something
+            unbonding_period: INITIAL_UNBONDING_PERIOD,
+            minimum_mixnode_uptime: INITIAL_MINIMUM_MIXNODE_UPTIME,
+            mixnode_jail_epochs: INITIAL_MIXNODE_JAIL_EPOCHS,
+            mixnode_slashing_fraction: Decimal::percent(INITIAL_MIXNODE_SLASHING_PERCENT),
         };
 
-        // cannot be updated from non-owner account
-        let info = mock_info("not-the-creator", &[]);
-        let res = try_update_state_params(deps.as_mut(), info, new_params.clone());
-        assert_eq!(res, Err(ContractError::Unauthorized));
-
-        // but works fine from the creator account
-        let info = mock_info("creator", &[]);
-        let res = try_update_state_params(deps.as_mut(), info, new_params.clone());
-        assert_eq!(res, Ok(Response::default()));
+        let res = apply_state_params(deps.as_mut().storage, new_params.clone());
+        let expected = Response {
+            attributes: events::new_params_update_event(&new_params, 0).unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(res, Ok(expected));
 
         // and the state is actually updated
         let current_state = config_read(deps.as_ref().storage).load().unwrap();
@@ -1619,8 +3569,7 @@ pub mod tests {
         new_params.mixnode_bond_reward_rate = new_mixnode_bond_reward_rate;
         new_params.mixnode_delegation_reward_rate = new_mixnode_delegation_reward_rate;
 
-        let info = mock_info("creator", &[]);
-        try_update_state_params(deps.as_mut(), info, new_params.clone()).unwrap();
+        apply_state_params(deps.as_mut().storage, new_params.clone()).unwrap();
 
         let new_state = config_read(deps.as_ref().storage).load().unwrap();
         let expected_bond =
@@ -1657,8 +3606,7 @@ pub mod tests {
         new_params.gateway_bond_reward_rate = new_gateway_bond_reward_rate;
         new_params.gateway_delegation_reward_rate = new_gateway_delegation_reward_rate;
 
-        let info = mock_info("creator", &[]);
-        try_update_state_params(deps.as_mut(), info, new_params.clone()).unwrap();
+        apply_state_params(deps.as_mut().storage, new_params.clone()).unwrap();
 
         let new_state = config_read(deps.as_ref().storage).load().unwrap();
         let expected_bond =
@@ -1693,8 +3641,7 @@ pub mod tests {
         new_params.gateway_bond_reward_rate = new_gateway_bond_reward_rate;
         new_params.gateway_delegation_reward_rate = new_gateway_delegation_reward_rate;
 
-        let info = mock_info("creator", &[]);
-        try_update_state_params(deps.as_mut(), info, new_params.clone()).unwrap();
+        apply_state_params(deps.as_mut().storage, new_params.clone()).unwrap();
 
         let new_state = config_read(deps.as_ref().storage).load().unwrap();
         let expected_mixnode_bond =
@@ -1728,299 +3675,1789 @@ pub mod tests {
         let mut new_params = current_state.params.clone();
         new_params.epoch_length = new_epoch_length;
 
-        let info = mock_info("creator", &[]);
-        try_update_state_params(deps.as_mut(), info, new_params.clone()).unwrap();
+        apply_state_params(deps.as_mut().storage, new_params.clone()).unwrap();
+
+        let new_state = config_read(deps.as_ref().storage).load().unwrap();
+        let expected_mixnode_bond =
+            calculate_epoch_reward_rate(new_epoch_length, new_params.mixnode_bond_reward_rate);
+        let expected_mixnode_delegation = calculate_epoch_reward_rate(
+            new_epoch_length,
+            new_params.mixnode_delegation_reward_rate,
+        );
+        assert_eq!(expected_mixnode_bond, new_state.mixnode_epoch_bond_reward);
+        assert_eq!(
+            expected_mixnode_delegation,
+            new_state.mixnode_epoch_delegation_reward
+        );
+
+        let expected_gateway_bond =
+            calculate_epoch_reward_rate(new_epoch_length, new_params.gateway_bond_reward_rate);
+        let expected_gateway_delegation = calculate_epoch_reward_rate(
+            new_epoch_length,
+            new_params.gateway_delegation_reward_rate,
+        );
+        assert_eq!(expected_gateway_bond, new_state.gateway_epoch_bond_reward);
+        assert_eq!(
+            expected_gateway_delegation,
+            new_state.gateway_epoch_delegation_reward
+        );
+    }
+
+    #[test]
+    fn proposing_state_params_validates_misbehaviour_settings() {
+        let mut deps = helpers::init_contract();
+        let info = mock_info("creator", &[]);
+        let current_params = config_read(deps.as_ref().storage).load().unwrap().params;
+
+        let mut new_params = current_params.clone();
+        new_params.minimum_mixnode_uptime = 101;
+        assert_eq!(
+            Err(ContractError::InvalidUptimeThreshold { value: 101 }),
+            try_propose_state_params(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                new_params,
+                String::new()
+            )
+        );
+
+        let mut new_params = current_params.clone();
+        new_params.mixnode_jail_epochs = 0;
+        assert_eq!(
+            Err(ContractError::ZeroJailEpochs),
+            try_propose_state_params(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                new_params,
+                String::new()
+            )
+        );
+
+        let mut new_params = current_params.clone();
+        new_params.mixnode_slashing_fraction = Decimal::percent(101);
+        assert_eq!(
+            Err(ContractError::InvalidSlashingFraction),
+            try_propose_state_params(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                new_params,
+                String::new()
+            )
+        );
+
+        let mut new_params = current_params;
+        new_params.mixnode_slashing_fraction = Decimal::one();
+        assert!(try_propose_state_params(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            new_params,
+            String::new()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn proposing_state_params_validates_active_set_size() {
+        let mut deps = helpers::init_contract();
+        let info = mock_info("creator", &[]);
+        let current_params = config_read(deps.as_ref().storage).load().unwrap().params;
+
+        for invalid_size in &[0, MAX_MIXNODE_ACTIVE_SET_SIZE + 1] {
+            let mut new_params = current_params.clone();
+            new_params.mixnode_active_set_size = *invalid_size;
+            assert_eq!(
+                Err(ContractError::InvalidActiveSetSize {
+                    value: *invalid_size,
+                    max: MAX_MIXNODE_ACTIVE_SET_SIZE
+                }),
+                try_propose_state_params(
+                    deps.as_mut(),
+                    mock_env(),
+                    info.clone(),
+                    new_params,
+                    String::new()
+                )
+            );
+        }
+
+        let mut new_params = current_params;
+        new_params.mixnode_active_set_size = MAX_MIXNODE_ACTIVE_SET_SIZE;
+        assert!(try_propose_state_params(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            new_params,
+            String::new()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn rewarding_mixnode() {
+        let mut deps = helpers::init_contract();
+        let current_state = config(deps.as_mut().storage).load().unwrap();
+        let network_monitor_address = current_state.network_monitor_address;
+
+        let node_owner: Addr = Addr::unchecked("node-owner");
+        let node_identity: IdentityKey = "nodeidentity".into();
+
+        // errors out if executed by somebody else than network monitor
+        let info = mock_info("not-the-monitor", &[]);
+        let res = try_reward_mixnode(deps.as_mut(), info, node_identity.clone(), 100);
+        assert_eq!(res, Err(ContractError::Unauthorized));
+
+        // returns bond not found attribute if the target owner hasn't bonded any mixnodes
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        let res = try_reward_mixnode(deps.as_mut(), info, node_identity.clone(), 100).unwrap();
+        assert_eq!(
+            events::new_mixnode_not_rewarded_event(
+                &node_identity,
+                100,
+                events::BOND_NOT_FOUND_RESULT,
+                0
+            ),
+            res.attributes
+        );
+
+        let initial_bond = 100_000000;
+        let initial_delegation = 200_000000;
+        let mixnode_bond = MixNodeBond {
+            bond_amount: coin(initial_bond, DENOM),
+            total_delegation: coin(initial_delegation, DENOM),
+            owner: node_owner.clone(),
+            layer: Layer::One,
+            profit_margin_percent: 0,
+            family: None,
+            jailed: false,
+            jailed_until: 0,
+            mix_node: MixNode {
+                identity_key: node_identity.clone(),
+                ..mix_node_fixture()
+            },
+        };
+
+        mixnodes(deps.as_mut().storage)
+            .save(node_identity.as_bytes(), &mixnode_bond)
+            .unwrap();
+
+        mix_delegations(&mut deps.storage, &node_identity)
+            .save(b"delegator", &Uint128(initial_delegation))
+            .unwrap();
+
+        let bond_reward_rate = read_mixnode_epoch_bond_reward_rate(deps.as_ref().storage);
+        let delegation_reward_rate =
+            read_mixnode_epoch_delegation_reward_rate(deps.as_ref().storage);
+        let expected_operator_reward = Uint128(initial_bond) * bond_reward_rate;
+        let expected_delegation_reward = Uint128(initial_delegation) * delegation_reward_rate;
+
+        // the node's delegations are correctly increased and scaled by uptime, while the operator
+        // reward is kept aside until claimed
+        // if node was 100% up, it will get full epoch reward
+        let expected_unclaimed = expected_operator_reward;
+        let expected_delegation = expected_delegation_reward + Uint128(initial_delegation);
+
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        let res = try_reward_mixnode(deps.as_mut(), info, node_identity.clone(), 100).unwrap();
+
+        assert_eq!(
+            Uint128(initial_bond),
+            read_mixnode_bond(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+        );
+        assert_eq!(
+            expected_unclaimed,
+            mix_operator_rewards_read(deps.as_ref().storage)
+                .load(node_identity.as_bytes())
+                .unwrap()
+        );
+        assert_eq!(
+            expected_delegation,
+            read_mixnode_delegation(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+        );
+
+        assert_eq!(
+            events::new_mixnode_rewarding_event(
+                &node_identity,
+                100,
+                expected_operator_reward,
+                expected_delegation_reward,
+                0
+            ),
+            res.attributes
+        );
+
+        // if node was 20% up, it will get 1/5th of epoch reward
+        // note that the unclaimed reward is not part of the stake
+        let scaled_bond_reward = scale_reward_by_uptime(bond_reward_rate, 20).unwrap();
+        let scaled_delegation_reward = scale_reward_by_uptime(delegation_reward_rate, 20).unwrap();
+        let expected_operator_reward = Uint128(initial_bond) * scaled_bond_reward;
+        let expected_delegation_reward = expected_delegation * scaled_delegation_reward;
+        let expected_unclaimed = expected_operator_reward + expected_unclaimed;
+        let expected_delegation = expected_delegation_reward + expected_delegation;
+
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        let res = try_reward_mixnode(deps.as_mut(), info, node_identity.clone(), 20).unwrap();
+
+        assert_eq!(
+            Uint128(initial_bond),
+            read_mixnode_bond(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+        );
+        assert_eq!(
+            expected_unclaimed,
+            mix_operator_rewards_read(deps.as_ref().storage)
+                .load(node_identity.as_bytes())
+                .unwrap()
+        );
+        assert_eq!(
+            expected_delegation,
+            read_mixnode_delegation(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+        );
+
+        assert_eq!(
+            events::new_mixnode_rewarding_event(
+                &node_identity,
+                20,
+                expected_operator_reward,
+                expected_delegation_reward,
+                0
+            ),
+            res.attributes
+        );
+    }
+
+    #[test]
+    fn rewarding_mixnode_with_profit_margin() {
+        let mut deps = helpers::init_contract();
+        let current_state = config(deps.as_mut().storage).load().unwrap();
+        let network_monitor_address = current_state.network_monitor_address;
+
+        let identity = add_mixnode("node-owner", good_mixnode_bond(), &mut deps);
+        try_update_mixnode_profit_margin(deps.as_mut(), mock_info("node-owner", &[]), 10).unwrap();
+
+        let delegation = 100000_000000;
+        try_delegate_to_mixnode(
+            deps.as_mut(),
+            mock_info("delegator", &coins(delegation, DENOM)),
+            identity.clone(),
+        )
+        .unwrap();
+
+        let bond_reward_rate = read_mixnode_epoch_bond_reward_rate(deps.as_ref().storage);
+        let delegation_reward_rate =
+            read_mixnode_epoch_delegation_reward_rate(deps.as_ref().storage);
+
+        let full_delegation_reward = Uint128(delegation) * delegation_reward_rate;
+        let expected_delegation_reward =
+            Uint128(delegation) * deduct_profit_margin(delegation_reward_rate, 10);
+        let expected_commission = full_delegation_reward
+            .checked_sub(expected_delegation_reward)
+            .unwrap();
+        let expected_operator_reward =
+            good_mixnode_bond()[0].amount * bond_reward_rate + expected_commission;
+
+        // sanity check to make sure the commission is actually taken
+        assert!(!expected_commission.is_zero());
+
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        let res = try_reward_mixnode(deps.as_mut(), info, identity.clone(), 100).unwrap();
+
+        assert_eq!(
+            events::new_mixnode_rewarding_event(
+                &identity,
+                100,
+                expected_operator_reward,
+                expected_delegation_reward,
+                0
+            ),
+            res.attributes
+        );
+        assert_eq!(
+            expected_operator_reward,
+            mix_operator_rewards_read(deps.as_ref().storage)
+                .load(identity.as_bytes())
+                .unwrap()
+        );
+        assert_eq!(
+            Uint128(delegation) + expected_delegation_reward,
+            read_mix_delegation_value(deps.as_ref().storage, &identity, b"delegator")
+                .unwrap()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn updating_mixnode_profit_margin() {
+        let mut deps = helpers::init_contract();
+        let identity = add_mixnode("mix-owner", good_mixnode_bond(), &mut deps);
+
+        // only the node owner can do it
+        assert_eq!(
+            Err(ContractError::NoAssociatedMixNodeBond {
+                owner: Addr::unchecked("not-owner")
+            }),
+            try_update_mixnode_profit_margin(deps.as_mut(), mock_info("not-owner", &[]), 20)
+        );
+
+        // it must be a valid percentage
+        assert_eq!(
+            Err(ContractError::InvalidProfitMargin { value: 101 }),
+            try_update_mixnode_profit_margin(deps.as_mut(), mock_info("mix-owner", &[]), 101)
+        );
+
+        // and no funds can be sent alongside it
+        assert_eq!(
+            Err(ContractError::UnexpectedConfigUpdateFunds),
+            try_update_mixnode_profit_margin(
+                deps.as_mut(),
+                mock_info("mix-owner", &good_mixnode_bond()),
+                20
+            )
+        );
+
+        assert!(
+            try_update_mixnode_profit_margin(deps.as_mut(), mock_info("mix-owner", &[]), 10)
+                .is_ok()
+        );
+        assert_eq!(
+            10,
+            mixnodes_read(&deps.storage)
+                .load(identity.as_bytes())
+                .unwrap()
+                .profit_margin_percent
+        );
+
+        // invalid margin is also rejected at bonding time
+        let msg = ExecuteMsg::BondMixnode {
+            mix_node: MixNode {
+                identity_key: "anothermixnode".into(),
+                ..helpers::mix_node_fixture()
+            },
+            profit_margin_percent: 150,
+        };
+        assert_eq!(
+            Err(ContractError::InvalidProfitMargin { value: 150 }),
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("another-owner", &good_mixnode_bond()),
+                msg
+            )
+        );
+    }
+
+    #[test]
+    fn profit_margin_changes_are_bounded() {
+        let mut deps = helpers::init_contract();
+        add_mixnode("mix-owner", good_mixnode_bond(), &mut deps);
+
+        assert_eq!(
+            Err(ContractError::ProfitMarginChangeTooLarge {
+                old: 0,
+                new: MAX_PROFIT_MARGIN_CHANGE + 1,
+                max: MAX_PROFIT_MARGIN_CHANGE,
+            }),
+            try_update_mixnode_profit_margin(
+                deps.as_mut(),
+                mock_info("mix-owner", &[]),
+                MAX_PROFIT_MARGIN_CHANGE + 1
+            )
+        );
+
+        try_update_mixnode_profit_margin(
+            deps.as_mut(),
+            mock_info("mix-owner", &[]),
+            MAX_PROFIT_MARGIN_CHANGE,
+        )
+        .unwrap();
+        helpers::advance_epoch(&mut deps, mock_env());
+
+        // the bound applies in both directions
+        try_update_mixnode_profit_margin(deps.as_mut(), mock_info("mix-owner", &[]), 0).unwrap();
+    }
+
+    #[test]
+    fn profit_margin_cannot_be_updated_again_before_the_cooldown_is_over() {
+        let mut deps = helpers::init_contract();
+        let identity = add_mixnode("mix-owner", good_mixnode_bond(), &mut deps);
+        let epoch = read_current_epoch(&deps.storage).unwrap();
+
+        try_update_mixnode_profit_margin(deps.as_mut(), mock_info("mix-owner", &[]), 5).unwrap();
+        assert_eq!(
+            Err(ContractError::ProfitMarginUpdateTooSoon {
+                epoch,
+                next_epoch: epoch + PROFIT_MARGIN_UPDATE_COOLDOWN_EPOCHS,
+            }),
+            try_update_mixnode_profit_margin(deps.as_mut(), mock_info("mix-owner", &[]), 10)
+        );
+
+        helpers::advance_epoch(&mut deps, mock_env());
+        assert_eq!(
+            epoch + PROFIT_MARGIN_UPDATE_COOLDOWN_EPOCHS,
+            read_current_epoch(&deps.storage).unwrap()
+        );
+        try_update_mixnode_profit_margin(deps.as_mut(), mock_info("mix-owner", &[]), 10).unwrap();
+        assert_eq!(
+            10,
+            mixnodes_read(&deps.storage)
+                .load(identity.as_bytes())
+                .unwrap()
+                .profit_margin_percent
+        );
+    }
+
+    #[test]
+    fn claiming_operator_reward() {
+        let mut deps = helpers::init_contract();
+        let current_state = config(deps.as_mut().storage).load().unwrap();
+        let network_monitor_address = current_state.network_monitor_address;
+
+        let identity = add_mixnode("mix-owner", good_mixnode_bond(), &mut deps);
+
+        // there's nothing to claim before the node got rewarded
+        assert_eq!(
+            Err(ContractError::NoRewardToClaim),
+            try_claim_operator_reward(deps.as_mut(), mock_info("mix-owner", &[]), false)
+        );
+
+        // and only the owner of a node can claim anything
+        assert_eq!(
+            Err(ContractError::NoAssociatedMixNodeBond {
+                owner: Addr::unchecked("not-owner")
+            }),
+            try_claim_operator_reward(deps.as_mut(), mock_info("not-owner", &[]), false)
+        );
+
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        try_reward_mixnode(deps.as_mut(), info, identity.clone(), 100).unwrap();
+        let reward = mix_operator_rewards_read(&deps.storage)
+            .load(identity.as_bytes())
+            .unwrap();
+
+        // the reward gets paid out
+        let res =
+            try_claim_operator_reward(deps.as_mut(), mock_info("mix-owner", &[]), false).unwrap();
+        assert_eq!(
+            vec![BankMsg::Send {
+                to_address: "mix-owner".to_string(),
+                amount: coins(reward.u128(), DENOM),
+            }
+            .into()],
+            res.messages
+        );
+
+        // and can't be claimed again
+        assert_eq!(
+            Err(ContractError::NoRewardToClaim),
+            try_claim_operator_reward(deps.as_mut(), mock_info("mix-owner", &[]), false)
+        );
+
+        // alternatively it can be added to the bond instead
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        try_reward_mixnode(deps.as_mut(), info, identity.clone(), 100).unwrap();
+        let reward = mix_operator_rewards_read(&deps.storage)
+            .load(identity.as_bytes())
+            .unwrap();
+
+        let res =
+            try_claim_operator_reward(deps.as_mut(), mock_info("mix-owner", &[]), true).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            good_mixnode_bond()[0].amount + reward,
+            read_mixnode_bond(&deps.storage, identity.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn claiming_delegator_reward() {
+        let mut deps = helpers::init_contract();
+        let current_state = config(deps.as_mut().storage).load().unwrap();
+        let network_monitor_address = current_state.network_monitor_address;
+
+        let identity = add_mixnode("mix-owner", good_mixnode_bond(), &mut deps);
+
+        // the delegation must exist
+        assert_eq!(
+            Err(ContractError::NoMixnodeDelegationFound {
+                identity: identity.clone(),
+                address: Addr::unchecked("delegator"),
+            }),
+            try_claim_delegator_reward(
+                deps.as_mut(),
+                mock_info("delegator", &[]),
+                identity.clone(),
+                false
+            )
+        );
+
+        let delegation = Uint128(100000_000000);
+        try_delegate_to_mixnode(
+            deps.as_mut(),
+            mock_info("delegator", &coins(delegation.u128(), DENOM)),
+            identity.clone(),
+        )
+        .unwrap();
+
+        // and have accumulated some rewards
+        assert_eq!(
+            Err(ContractError::NoRewardToClaim),
+            try_claim_delegator_reward(
+                deps.as_mut(),
+                mock_info("delegator", &[]),
+                identity.clone(),
+                false
+            )
+        );
+
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        try_reward_mixnode(deps.as_mut(), info, identity.clone(), 100).unwrap();
+        let total_delegation = read_mixnode_delegation(&deps.storage, identity.as_bytes()).unwrap();
+        let reward = read_mix_delegation_value(&deps.storage, &identity, b"delegator")
+            .unwrap()
+            .unwrap()
+            .checked_sub(delegation)
+            .unwrap();
+
+        // the reward is paid out while the delegated stake stays the same
+        let res = try_claim_delegator_reward(
+            deps.as_mut(),
+            mock_info("delegator", &[]),
+            identity.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![BankMsg::Send {
+                to_address: "delegator".to_string(),
+                amount: coins(reward.u128(), DENOM),
+            }
+            .into()],
+            res.messages
+        );
+        assert_eq!(
+            Some(delegation),
+            read_mix_delegation_value(&deps.storage, &identity, b"delegator").unwrap()
+        );
+        assert_eq!(
+            total_delegation.checked_sub(reward).unwrap(),
+            read_mixnode_delegation(&deps.storage, identity.as_bytes()).unwrap()
+        );
+
+        // alternatively, it can be added to the delegation
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        try_reward_mixnode(deps.as_mut(), info, identity.clone(), 100).unwrap();
+        let total_delegation = read_mixnode_delegation(&deps.storage, identity.as_bytes()).unwrap();
+        let delegation_value = read_mix_delegation_value(&deps.storage, &identity, b"delegator")
+            .unwrap()
+            .unwrap();
+
+        let res = try_claim_delegator_reward(
+            deps.as_mut(),
+            mock_info("delegator", &[]),
+            identity.clone(),
+            true,
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            delegation_value,
+            mix_delegations_read(&deps.storage, &identity)
+                .load(b"delegator")
+                .unwrap()
+        );
+        assert_eq!(
+            total_delegation,
+            read_mixnode_delegation(&deps.storage, identity.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn increasing_delegation_keeps_accrued_reward_claimable() {
+        let mut deps = helpers::init_contract();
+        let current_state = config(deps.as_mut().storage).load().unwrap();
+        let network_monitor_address = current_state.network_monitor_address;
+
+        let identity = add_mixnode("mix-owner", good_mixnode_bond(), &mut deps);
+
+        let delegation = Uint128(100000_000000);
+        try_delegate_to_mixnode(
+            deps.as_mut(),
+            mock_info("delegator", &coins(delegation.u128(), DENOM)),
+            identity.clone(),
+        )
+        .unwrap();
+
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        try_reward_mixnode(deps.as_mut(), info, identity.clone(), 100).unwrap();
+        let reward = read_mix_delegation_value(&deps.storage, &identity, b"delegator")
+            .unwrap()
+            .unwrap()
+            .checked_sub(delegation)
+            .unwrap();
+        assert!(!reward.is_zero());
+        let total_delegation = read_mixnode_delegation(&deps.storage, identity.as_bytes()).unwrap();
+
+        // the reward is not added to the delegated stake...
+        let top_up = Uint128(50000_000000);
+        try_delegate_to_mixnode(
+            deps.as_mut(),
+            mock_info("delegator", &coins(top_up.u128(), DENOM)),
+            identity.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            Some(delegation + top_up),
+            read_mix_delegation_value(&deps.storage, &identity, b"delegator").unwrap()
+        );
+        assert_eq!(
+            reward,
+            read_mix_delegation_unclaimed_reward(&deps.storage, &identity, b"delegator").unwrap()
+        );
+        assert_eq!(
+            (total_delegation + top_up).checked_sub(reward).unwrap(),
+            read_mixnode_delegation(&deps.storage, identity.as_bytes()).unwrap()
+        );
+
+        // ...and can still be claimed separately
+        let res = try_claim_delegator_reward(
+            deps.as_mut(),
+            mock_info("delegator", &[]),
+            identity.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![BankMsg::Send {
+                to_address: "delegator".to_string(),
+                amount: coins(reward.u128(), DENOM),
+            }
+            .into()],
+            res.messages
+        );
+        assert_eq!(
+            Some(delegation + top_up),
+            read_mix_delegation_value(&deps.storage, &identity, b"delegator").unwrap()
+        );
+        assert!(
+            read_mix_delegation_unclaimed_reward(&deps.storage, &identity, b"delegator")
+                .unwrap()
+                .is_zero()
+        );
+        assert_eq!(
+            Err(ContractError::NoRewardToClaim),
+            try_claim_delegator_reward(
+                deps.as_mut(),
+                mock_info("delegator", &[]),
+                identity.clone(),
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn unclaimed_reward_is_withdrawn_alongside_removed_delegation() {
+        let mut deps = helpers::init_contract();
+        let current_state = config(deps.as_mut().storage).load().unwrap();
+        let network_monitor_address = current_state.network_monitor_address;
+
+        let identity = add_mixnode("mix-owner", good_mixnode_bond(), &mut deps);
+
+        let delegation = Uint128(100000_000000);
+        for _ in 0..2 {
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("delegator", &coins(delegation.u128(), DENOM)),
+                identity.clone(),
+            )
+            .unwrap();
+            let info = mock_info(network_monitor_address.as_ref(), &[]);
+            try_reward_mixnode(deps.as_mut(), info, identity.clone(), 100).unwrap();
+        }
+
+        let value = read_mix_delegation_value(&deps.storage, &identity, b"delegator")
+            .unwrap()
+            .unwrap();
+        let unclaimed_reward =
+            read_mix_delegation_unclaimed_reward(&deps.storage, &identity, b"delegator").unwrap();
+        assert!(!unclaimed_reward.is_zero());
+
+        try_remove_delegation_from_mixnode(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("delegator", &[]),
+            identity.clone(),
+        )
+        .unwrap();
+
+        let withdrawals = pending_withdrawals_read(&deps.storage, &Addr::unchecked("delegator"))
+            .range(None, None, Order::Ascending)
+            .map(|res| res.unwrap().1.amount.amount)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![value + unclaimed_reward], withdrawals);
+        assert!(
+            read_mix_delegation_unclaimed_reward(&deps.storage, &identity, b"delegator")
+                .unwrap()
+                .is_zero()
+        );
+    }
+
+    #[test]
+    fn rewarding_gateway() {
+        let mut deps = helpers::init_contract();
+        let current_state = config(deps.as_mut().storage).load().unwrap();
+        let network_monitor_address = current_state.network_monitor_address;
+
+        let node_owner: Addr = Addr::unchecked("node-owner");
+        let node_identity: IdentityKey = "nodeidentity".into();
+
+        // errors out if executed by somebody else than network monitor
+        let info = mock_info("not-the-monitor", &[]);
+        let res = try_reward_gateway(deps.as_mut(), info, node_identity.clone(), 100);
+        assert_eq!(res, Err(ContractError::Unauthorized));
+
+        // returns bond not found attribute if the target owner hasn't bonded any gateways
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        let res = try_reward_gateway(deps.as_mut(), info, node_identity.clone(), 100).unwrap();
+        assert_eq!(
+            events::new_gateway_not_rewarded_event(
+                &node_identity,
+                100,
+                events::BOND_NOT_FOUND_RESULT,
+                0
+            ),
+            res.attributes
+        );
+
+        let initial_bond = 100_000000;
+        let initial_delegation = 200_000000;
+        let gateway_bond = GatewayBond {
+            bond_amount: coin(initial_bond, DENOM),
+            total_delegation: coin(initial_delegation, DENOM),
+            owner: node_owner.clone(),
+            gateway: Gateway {
+                identity_key: node_identity.clone(),
+                ..gateway_fixture()
+            },
+        };
+
+        gateways(deps.as_mut().storage)
+            .save(node_identity.as_bytes(), &gateway_bond)
+            .unwrap();
+
+        gateway_delegations(&mut deps.storage, &node_identity)
+            .save(b"delegator", &Uint128(initial_delegation))
+            .unwrap();
+
+        let bond_reward_rate = read_gateway_epoch_bond_reward_rate(deps.as_ref().storage);
+        let delegation_reward_rate =
+            read_gateway_epoch_delegation_reward_rate(deps.as_ref().storage);
+        let expected_bond_reward = Uint128(initial_bond) * bond_reward_rate;
+        let expected_delegation_reward = Uint128(initial_delegation) * delegation_reward_rate;
+
+        // the node's bond and delegations are correctly increased and scaled by uptime
+        // if node was 100% up, it will get full epoch reward
+        let expected_bond = expected_bond_reward + Uint128(initial_bond);
+        let expected_delegation = expected_delegation_reward + Uint128(initial_delegation);
+
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        let res = try_reward_gateway(deps.as_mut(), info, node_identity.clone(), 100).unwrap();
+
+        assert_eq!(
+            expected_bond,
+            read_gateway_bond(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+        );
+
+        assert_eq!(
+            expected_delegation,
+            read_gateway_delegation(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+        );
+
+        assert_eq!(
+            events::new_gateway_rewarding_event(
+                &node_identity,
+                100,
+                expected_bond_reward,
+                expected_delegation_reward,
+                0
+            ),
+            res.attributes
+        );
+
+        // if node was 20% up, it will get 1/5th of epoch reward
+        let scaled_bond_reward = scale_reward_by_uptime(bond_reward_rate, 20).unwrap();
+        let scaled_delegation_reward = scale_reward_by_uptime(delegation_reward_rate, 20).unwrap();
+        let expected_bond_reward = expected_bond * scaled_bond_reward;
+        let expected_delegation_reward = expected_delegation * scaled_delegation_reward;
+        let expected_bond = expected_bond_reward + expected_bond;
+        let expected_delegation = expected_delegation_reward + expected_delegation;
+
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        let res = try_reward_gateway(deps.as_mut(), info, node_identity.clone(), 20).unwrap();
+
+        assert_eq!(
+            expected_bond,
+            read_gateway_bond(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+        );
+
+        assert_eq!(
+            expected_delegation,
+            read_gateway_delegation(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+        );
+
+        assert_eq!(
+            events::new_gateway_rewarding_event(
+                &node_identity,
+                20,
+                expected_bond_reward,
+                expected_delegation_reward,
+                0
+            ),
+            res.attributes
+        );
+    }
+
+    #[cfg(test)]
+    mod delegation_stake_validation {
+        use super::*;
+        use cosmwasm_std::coin;
+
+        #[test]
+        fn stake_cant_be_empty() {
+            assert_eq!(
+                Err(ContractError::EmptyDelegation),
+                validate_delegation_stake(&[])
+            )
+        }
+
+        #[test]
+        fn stake_must_have_single_coin_type() {
+            assert_eq!(
+                Err(ContractError::MultipleDenoms),
+                validate_delegation_stake(&[coin(123, DENOM), coin(123, "BTC"), coin(123, "DOGE")])
+            )
+        }
+
+        #[test]
+        fn stake_coin_must_be_of_correct_type() {
+            assert_eq!(
+                Err(ContractError::WrongDenom {}),
+                validate_delegation_stake(&[coin(123, "DOGE")])
+            )
+        }
+
+        #[test]
+        fn stake_coin_must_have_value_greater_than_zero() {
+            assert_eq!(
+                Err(ContractError::EmptyDelegation),
+                validate_delegation_stake(&[coin(0, DENOM)])
+            )
+        }
+
+        #[test]
+        fn stake_can_have_any_positive_value() {
+            // this might change in the future, but right now an arbitrary (positive) value can be delegated
+            assert!(validate_delegation_stake(&[coin(1, DENOM)]).is_ok());
+            assert!(validate_delegation_stake(&[coin(123, DENOM)]).is_ok());
+            assert!(validate_delegation_stake(&[coin(10000000000, DENOM)]).is_ok());
+        }
+    }
+
+    #[cfg(test)]
+    mod mix_stake_delegation {
+        use super::*;
+        use crate::storage::mix_delegations_read;
+        use crate::support::tests::helpers::add_mixnode;
+
+        #[test]
+        fn fails_if_node_doesnt_exist() {
+            let mut deps = helpers::init_contract();
+            assert_eq!(
+                Err(ContractError::MixNodeBondNotFound {
+                    identity: "non-existent-mix-identity".into()
+                }),
+                try_delegate_to_mixnode(
+                    deps.as_mut(),
+                    mock_info("sender", &coins(123, DENOM)),
+                    "non-existent-mix-identity".into()
+                )
+            );
+        }
+
+        #[test]
+        fn succeeds_for_existing_node() {
+            let mut deps = helpers::init_contract();
+            let mixnode_owner = "bob";
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+
+            let delegation = coin(123, DENOM);
+            assert!(try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender", &vec![delegation.clone()]),
+                identity.clone()
+            )
+            .is_ok());
+
+            assert_eq!(
+                delegation.amount,
+                mix_delegations_read(&deps.storage, &identity)
+                    .load(b"sender")
+                    .unwrap()
+            );
+
+            // node's "total_delegation" is increased
+            assert_eq!(
+                delegation,
+                mixnodes_read(&deps.storage)
+                    .load(identity.as_bytes())
+                    .unwrap()
+                    .total_delegation
+            )
+        }
+
+        #[test]
+        fn fails_if_node_unbonded() {
+            let mut deps = helpers::init_contract();
+
+            let mixnode_owner = "bob";
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info(mixnode_owner, &[])).unwrap();
+
+            assert_eq!(
+                Err(ContractError::MixNodeBondNotFound {
+                    identity: identity.clone()
+                }),
+                try_delegate_to_mixnode(
+                    deps.as_mut(),
+                    mock_info("sender", &coins(123, DENOM)),
+                    identity
+                )
+            );
+        }
+
+        #[test]
+        fn succeeds_if_node_rebonded() {
+            let mut deps = helpers::init_contract();
+
+            let mixnode_owner = "bob";
+            add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info(mixnode_owner, &[])).unwrap();
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            let delegation = coin(123, DENOM);
+
+            assert!(try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender", &vec![delegation.clone()]),
+                identity.clone()
+            )
+            .is_ok());
+
+            assert_eq!(
+                delegation.amount,
+                mix_delegations_read(&deps.storage, &identity)
+                    .load(b"sender")
+                    .unwrap()
+            );
+
+            // node's "total_delegation" is increased
+            assert_eq!(
+                delegation,
+                mixnodes_read(&deps.storage)
+                    .load(identity.as_bytes())
+                    .unwrap()
+                    .total_delegation
+            )
+        }
+
+        #[test]
+        fn is_possible_for_an_already_delegated_node() {
+            let mut deps = helpers::init_contract();
+            let mixnode_owner = "bob";
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+
+            let delegation1 = coin(100, DENOM);
+            let delegation2 = coin(50, DENOM);
+
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender", &vec![delegation1.clone()]),
+                identity.clone(),
+            )
+            .unwrap();
+
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender", &vec![delegation2.clone()]),
+                identity.clone(),
+            )
+            .unwrap();
+
+            assert_eq!(
+                delegation1.amount + delegation2.amount,
+                mix_delegations_read(&deps.storage, &identity)
+                    .load(b"sender")
+                    .unwrap()
+            );
+
+            // node's "total_delegation" is sum of both
+            assert_eq!(
+                delegation1.amount + delegation2.amount,
+                mixnodes_read(&deps.storage)
+                    .load(identity.as_bytes())
+                    .unwrap()
+                    .total_delegation
+                    .amount
+            )
+        }
+
+        #[test]
+        fn is_disallowed_for_already_delegated_node_if_it_unbonded() {
+            let mut deps = helpers::init_contract();
+
+            let mixnode_owner = "bob";
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender", &coins(100, DENOM)),
+                identity.clone(),
+            )
+            .unwrap();
+
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info(mixnode_owner, &[])).unwrap();
+
+            assert_eq!(
+                Err(ContractError::MixNodeBondNotFound {
+                    identity: identity.clone()
+                }),
+                try_delegate_to_mixnode(
+                    deps.as_mut(),
+                    mock_info("sender", &coins(50, DENOM)),
+                    identity
+                )
+            );
+        }
+
+        #[test]
+        fn is_allowed_for_multiple_nodes() {
+            let mut deps = helpers::init_contract();
+            let mixnode_owner1 = "bob";
+            let mixnode_owner2 = "fred";
+            let identity1 = add_mixnode(mixnode_owner1, good_mixnode_bond(), &mut deps);
+            let identity2 = add_mixnode(mixnode_owner2, good_mixnode_bond(), &mut deps);
+
+            assert!(try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender", &coins(123, DENOM)),
+                identity1.clone()
+            )
+            .is_ok());
+
+            assert!(try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender", &coins(42, DENOM)),
+                identity2.clone()
+            )
+            .is_ok());
+
+            assert_eq!(
+                123,
+                mix_delegations_read(&deps.storage, &identity1)
+                    .load(b"sender")
+                    .unwrap()
+                    .u128()
+            );
+
+            assert_eq!(
+                42,
+                mix_delegations_read(&deps.storage, &identity2)
+                    .load(b"sender")
+                    .unwrap()
+                    .u128()
+            );
+        }
+
+        #[test]
+        fn is_allowed_by_multiple_users() {
+            let mut deps = helpers::init_contract();
+            let mixnode_owner = "bob";
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+
+            let delegation1 = coin(123, DENOM);
+            let delegation2 = coin(234, DENOM);
+
+            assert!(try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender1", &vec![delegation1.clone()]),
+                identity.clone()
+            )
+            .is_ok());
+
+            assert!(try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender2", &vec![delegation2.clone()]),
+                identity.clone()
+            )
+            .is_ok());
+
+            // node's "total_delegation" is sum of both
+            assert_eq!(
+                delegation1.amount + delegation2.amount,
+                mixnodes_read(&deps.storage)
+                    .load(identity.as_bytes())
+                    .unwrap()
+                    .total_delegation
+                    .amount
+            )
+        }
+
+        #[test]
+        fn delegation_is_not_removed_if_node_unbonded() {
+            let mut deps = helpers::init_contract();
+
+            let mixnode_owner = "bob";
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender", &coins(100, DENOM)),
+                identity.clone(),
+            )
+            .unwrap();
+
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info(mixnode_owner, &[])).unwrap();
+
+            assert_eq!(
+                100,
+                mix_delegations_read(&deps.storage, &identity)
+                    .load(b"sender")
+                    .unwrap()
+                    .u128()
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod removing_mix_stake_delegation {
+        use super::*;
+        use crate::storage::mix_delegations_read;
+        use crate::support::tests::helpers::add_mixnode;
+
+        #[test]
+        fn fails_if_delegation_never_existed() {
+            let mut deps = helpers::init_contract();
+
+            let mixnode_owner = "bob";
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+
+            assert_eq!(
+                Err(ContractError::NoMixnodeDelegationFound {
+                    identity: identity.clone(),
+                    address: Addr::unchecked("sender"),
+                }),
+                try_remove_delegation_from_mixnode(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info("sender", &[]),
+                    identity,
+                )
+            );
+        }
+
+        #[test]
+        fn succeeds_if_delegation_existed() {
+            let mut deps = helpers::init_contract();
+
+            let mixnode_owner = "bob";
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender", &coins(100, DENOM)),
+                identity.clone(),
+            )
+            .unwrap();
+
+            let env = mock_env();
+            let expected_withdrawal = PendingWithdrawal::new(
+                1,
+                Addr::unchecked("sender"),
+                coin(100, DENOM),
+                env.block.time.seconds() + INITIAL_UNBONDING_PERIOD as u64 * 60 * 60,
+            );
+            assert_eq!(
+                Ok(Response {
+                    submessages: vec![],
+                    messages: vec![],
+                    attributes: events::new_mixnode_undelegation_event(
+                        &Addr::unchecked("sender"),
+                        &identity,
+                        Uint128(100),
+                        0
+                    ),
+                    data: None,
+                }),
+                try_remove_delegation_from_mixnode(
+                    deps.as_mut(),
+                    env,
+                    mock_info("sender", &[]),
+                    identity.clone(),
+                )
+            );
+            assert_eq!(
+                expected_withdrawal,
+                pending_withdrawals_read(&deps.storage, &Addr::unchecked("sender"))
+                    .load(&1u64.to_be_bytes())
+                    .unwrap()
+            );
+
+            assert!(mix_delegations_read(&deps.storage, &identity)
+                .may_load(b"sender")
+                .unwrap()
+                .is_none());
+
+            // and total delegation is cleared
+            assert_eq!(
+                Uint128::zero(),
+                mixnodes_read(&deps.storage)
+                    .load(identity.as_bytes())
+                    .unwrap()
+                    .total_delegation
+                    .amount
+            )
+        }
+
+        #[test]
+        fn succeeds_if_delegation_existed_even_if_node_unbonded() {
+            let mut deps = helpers::init_contract();
+
+            let mixnode_owner = "bob";
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender", &coins(100, DENOM)),
+                identity.clone(),
+            )
+            .unwrap();
+
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info(mixnode_owner, &[])).unwrap();
+
+            let env = mock_env();
+            let expected_withdrawal = PendingWithdrawal::new(
+                2,
+                Addr::unchecked("sender"),
+                coin(100, DENOM),
+                env.block.time.seconds() + INITIAL_UNBONDING_PERIOD as u64 * 60 * 60,
+            );
+            assert_eq!(
+                Ok(Response {
+                    submessages: vec![],
+                    messages: vec![],
+                    attributes: events::new_mixnode_undelegation_event(
+                        &Addr::unchecked("sender"),
+                        &identity,
+                        Uint128(100),
+                        0
+                    ),
+                    data: None,
+                }),
+                try_remove_delegation_from_mixnode(
+                    deps.as_mut(),
+                    env,
+                    mock_info("sender", &[]),
+                    identity.clone(),
+                )
+            );
+            assert_eq!(
+                expected_withdrawal,
+                pending_withdrawals_read(&deps.storage, &Addr::unchecked("sender"))
+                    .load(&2u64.to_be_bytes())
+                    .unwrap()
+            );
+
+            assert!(mix_delegations_read(&deps.storage, &identity)
+                .may_load(b"sender")
+                .unwrap()
+                .is_none());
+        }
+
+        #[test]
+        fn total_delegation_is_preserved_if_only_some_undelegate() {
+            let mut deps = helpers::init_contract();
+            let mixnode_owner = "bob";
+            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+
+            let delegation1 = coin(123, DENOM);
+            let delegation2 = coin(234, DENOM);
+
+            assert!(try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender1", &vec![delegation1.clone()]),
+                identity.clone()
+            )
+            .is_ok());
+
+            assert!(try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("sender2", &vec![delegation2.clone()]),
+                identity.clone()
+            )
+            .is_ok());
 
-        let new_state = config_read(deps.as_ref().storage).load().unwrap();
-        let expected_mixnode_bond =
-            calculate_epoch_reward_rate(new_epoch_length, new_params.mixnode_bond_reward_rate);
-        let expected_mixnode_delegation = calculate_epoch_reward_rate(
-            new_epoch_length,
-            new_params.mixnode_delegation_reward_rate,
-        );
-        assert_eq!(expected_mixnode_bond, new_state.mixnode_epoch_bond_reward);
-        assert_eq!(
-            expected_mixnode_delegation,
-            new_state.mixnode_epoch_delegation_reward
-        );
+            // sender1 undelegates
+            try_remove_delegation_from_mixnode(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("sender1", &[]),
+                identity.clone(),
+            )
+            .unwrap();
 
-        let expected_gateway_bond =
-            calculate_epoch_reward_rate(new_epoch_length, new_params.gateway_bond_reward_rate);
-        let expected_gateway_delegation = calculate_epoch_reward_rate(
-            new_epoch_length,
-            new_params.gateway_delegation_reward_rate,
-        );
-        assert_eq!(expected_gateway_bond, new_state.gateway_epoch_bond_reward);
-        assert_eq!(
-            expected_gateway_delegation,
-            new_state.gateway_epoch_delegation_reward
-        );
+            // but total delegation should still equal to what sender2 sent
+            // node's "total_delegation" is sum of both
+            assert_eq!(
+                delegation2,
+                mixnodes_read(&deps.storage)
+                    .load(identity.as_bytes())
+                    .unwrap()
+                    .total_delegation
+            )
+        }
     }
 
     #[test]
-    fn rewarding_mixnode() {
+    fn delegators_on_mix_node_reward_rate() {
         let mut deps = helpers::init_contract();
         let current_state = config(deps.as_mut().storage).load().unwrap();
         let network_monitor_address = current_state.network_monitor_address;
 
-        let node_owner: Addr = Addr::unchecked("node-owner");
-        let node_identity: IdentityKey = "nodeidentity".into();
-
-        // errors out if executed by somebody else than network monitor
-        let info = mock_info("not-the-monitor", &[]);
-        let res = try_reward_mixnode(deps.as_mut(), info, node_identity.clone(), 100);
-        assert_eq!(res, Err(ContractError::Unauthorized));
-
-        // returns bond not found attribute if the target owner hasn't bonded any mixnodes
-        let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_mixnode(deps.as_mut(), info, node_identity.clone(), 100).unwrap();
-        assert_eq!(vec![attr("result", "bond not found")], res.attributes);
+        let initial_mix_bond = 100_000000;
+        let initial_delegation1 = 50000; // will see single digits rewards
+        let initial_delegation2 = 100; // won't see any rewards due to such a small delegation
+        let initial_delegation3 = 100000_000000; // will see big proper rewards
+        let initial_total_delegation =
+            Uint128(initial_delegation1 + initial_delegation2 + initial_delegation3);
 
-        let initial_bond = 100_000000;
-        let initial_delegation = 200_000000;
-        let mixnode_bond = MixNodeBond {
-            bond_amount: coin(initial_bond, DENOM),
-            total_delegation: coin(initial_delegation, DENOM),
-            owner: node_owner.clone(),
-            layer: Layer::One,
-            mix_node: MixNode {
-                identity_key: node_identity.clone(),
-                ..mix_node_fixture()
-            },
-        };
+        let node_owner = "node-owner";
+        let identity = add_mixnode(node_owner, good_mixnode_bond(), &mut deps);
 
-        mixnodes(deps.as_mut().storage)
-            .save(node_identity.as_bytes(), &mixnode_bond)
+        for (delegator, amount) in [
+            ("delegator1", initial_delegation1),
+            ("delegator2", initial_delegation2),
+            ("delegator3", initial_delegation3),
+        ] {
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info(delegator, &coins(amount, DENOM)),
+                identity.clone(),
+            )
             .unwrap();
+        }
 
-        mix_delegations(&mut deps.storage, &node_identity)
-            .save(b"delegator", &Uint128(initial_delegation))
-            .unwrap();
+        fn delegation_value(storage: &dyn Storage, identity: &str, delegator: &str) -> Uint128 {
+            read_mix_delegation_value(storage, identity, delegator.as_bytes())
+                .unwrap()
+                .unwrap()
+        }
 
-        let bond_reward_rate = read_mixnode_epoch_bond_reward_rate(deps.as_ref().storage);
-        let delegation_reward_rate =
-            read_mixnode_epoch_delegation_reward_rate(deps.as_ref().storage);
-        let expected_bond_reward = Uint128(initial_bond) * bond_reward_rate;
-        let expected_delegation_reward = Uint128(initial_delegation) * delegation_reward_rate;
+        let bond_reward = read_mixnode_epoch_bond_reward_rate(deps.as_ref().storage);
+        let delegation_reward = read_mixnode_epoch_delegation_reward_rate(deps.as_ref().storage);
 
         // the node's bond and delegations are correctly increased and scaled by uptime
         // if node was 100% up, it will get full epoch reward
-        let expected_bond = expected_bond_reward + Uint128(initial_bond);
-        let expected_delegation = expected_delegation_reward + Uint128(initial_delegation);
+        let expected_mix_reward = Uint128(initial_mix_bond) * bond_reward;
+        let expected_delegation1_reward = Uint128(initial_delegation1) * delegation_reward;
+        let expected_delegation2_reward = Uint128(initial_delegation2) * delegation_reward;
+        let expected_delegation3_reward = Uint128(initial_delegation3) * delegation_reward;
+        let expected_total_delegation_reward = initial_total_delegation * delegation_reward;
+
+        // operator rewards are kept aside until claimed, so the bond itself does not change
+        let expected_bond = Uint128(initial_mix_bond);
+        let expected_delegation1 = expected_delegation1_reward + Uint128(initial_delegation1);
+        let expected_delegation2 = expected_delegation2_reward + Uint128(initial_delegation2);
+        let expected_delegation3 = expected_delegation3_reward + Uint128(initial_delegation3);
+        let expected_total_delegation = expected_total_delegation_reward + initial_total_delegation;
 
         let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_mixnode(deps.as_mut(), info, node_identity.clone(), 100).unwrap();
+        let res = try_reward_mixnode(deps.as_mut(), info, identity.clone(), 100).unwrap();
 
         assert_eq!(
             expected_bond,
-            read_mixnode_bond(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+            read_mixnode_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
         );
         assert_eq!(
-            expected_delegation,
-            read_mixnode_delegation(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+            expected_total_delegation,
+            read_mixnode_delegation(deps.as_ref().storage, identity.as_bytes()).unwrap()
         );
 
         assert_eq!(
-            vec![
-                attr("bond increase", expected_bond_reward),
-                attr("total delegation increase", expected_delegation_reward),
-            ],
-            res.attributes
+            expected_delegation1,
+            delegation_value(&deps.storage, &identity, "delegator1")
         );
-
-        // if node was 20% up, it will get 1/5th of epoch reward
-        let scaled_bond_reward = scale_reward_by_uptime(bond_reward_rate, 20).unwrap();
-        let scaled_delegation_reward = scale_reward_by_uptime(delegation_reward_rate, 20).unwrap();
-        let expected_bond_reward = expected_bond * scaled_bond_reward;
-        let expected_delegation_reward = expected_delegation * scaled_delegation_reward;
-        let expected_bond = expected_bond_reward + expected_bond;
-        let expected_delegation = expected_delegation_reward + expected_delegation;
-
-        let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_mixnode(deps.as_mut(), info, node_identity.clone(), 20).unwrap();
-
         assert_eq!(
-            expected_bond,
-            read_mixnode_bond(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+            expected_delegation2,
+            delegation_value(&deps.storage, &identity, "delegator2")
         );
         assert_eq!(
-            expected_delegation,
-            read_mixnode_delegation(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+            expected_delegation3,
+            delegation_value(&deps.storage, &identity, "delegator3")
         );
 
         assert_eq!(
-            vec![
-                attr("bond increase", expected_bond_reward),
-                attr("total delegation increase", expected_delegation_reward),
-            ],
+            events::new_mixnode_rewarding_event(
+                &identity,
+                100,
+                expected_mix_reward,
+                expected_total_delegation_reward,
+                0
+            ),
             res.attributes
         );
-    }
-
-    #[test]
-    fn rewarding_gateway() {
-        let mut deps = helpers::init_contract();
-        let current_state = config(deps.as_mut().storage).load().unwrap();
-        let network_monitor_address = current_state.network_monitor_address;
 
-        let node_owner: Addr = Addr::unchecked("node-owner");
-        let node_identity: IdentityKey = "nodeidentity".into();
+        // if node was 20% up, it will get 1/5th of epoch reward
+        let scaled_bond_reward = scale_reward_by_uptime(bond_reward, 20).unwrap();
+        let scaled_delegation_reward = scale_reward_by_uptime(delegation_reward, 20).unwrap();
 
-        // errors out if executed by somebody else than network monitor
-        let info = mock_info("not-the-monitor", &[]);
-        let res = try_reward_gateway(deps.as_mut(), info, node_identity.clone(), 100);
-        assert_eq!(res, Err(ContractError::Unauthorized));
+        // delegations are compounded through the reward index of the node
+        let expected_index = compound_reward_index(
+            compound_reward_index(Decimal::one(), delegation_reward),
+            scaled_delegation_reward,
+        );
 
-        // returns bond not found attribute if the target owner hasn't bonded any gateways
-        let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_gateway(deps.as_mut(), info, node_identity.clone(), 100).unwrap();
-        assert_eq!(vec![attr("result", "bond not found")], res.attributes);
+        let expected_mix_reward = expected_bond * scaled_bond_reward;
+        let expected_total_delegation_reward = expected_total_delegation * scaled_delegation_reward;
 
-        let initial_bond = 100_000000;
-        let initial_delegation = 200_000000;
-        let gateway_bond = GatewayBond {
-            bond_amount: coin(initial_bond, DENOM),
-            total_delegation: coin(initial_delegation, DENOM),
-            owner: node_owner.clone(),
-            gateway: Gateway {
-                identity_key: node_identity.clone(),
-                ..gateway_fixture()
-            },
-        };
+        let expected_delegation1 =
+            apply_reward_index(Uint128(initial_delegation1), Decimal::one(), expected_index);
+        let expected_delegation2 =
+            apply_reward_index(Uint128(initial_delegation2), Decimal::one(), expected_index);
+        let expected_delegation3 =
+            apply_reward_index(Uint128(initial_delegation3), Decimal::one(), expected_index);
+        let expected_total_delegation =
+            expected_total_delegation_reward + expected_total_delegation;
 
-        gateways(deps.as_mut().storage)
-            .save(node_identity.as_bytes(), &gateway_bond)
-            .unwrap();
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        let res = try_reward_mixnode(deps.as_mut(), info, identity.clone(), 20).unwrap();
 
-        gateway_delegations(&mut deps.storage, &node_identity)
-            .save(b"delegator", &Uint128(initial_delegation))
-            .unwrap();
+        assert_eq!(
+            expected_bond,
+            read_mixnode_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
+        );
+        assert_eq!(
+            expected_total_delegation,
+            read_mixnode_delegation(deps.as_ref().storage, identity.as_bytes()).unwrap()
+        );
 
-        let bond_reward_rate = read_gateway_epoch_bond_reward_rate(deps.as_ref().storage);
-        let delegation_reward_rate =
-            read_gateway_epoch_delegation_reward_rate(deps.as_ref().storage);
-        let expected_bond_reward = Uint128(initial_bond) * bond_reward_rate;
-        let expected_delegation_reward = Uint128(initial_delegation) * delegation_reward_rate;
+        assert_eq!(
+            expected_delegation1,
+            delegation_value(&deps.storage, &identity, "delegator1")
+        );
+        assert_eq!(
+            expected_delegation2,
+            delegation_value(&deps.storage, &identity, "delegator2")
+        );
+        assert_eq!(
+            expected_delegation3,
+            delegation_value(&deps.storage, &identity, "delegator3")
+        );
 
-        // the node's bond and delegations are correctly increased and scaled by uptime
-        // if node was 100% up, it will get full epoch reward
-        let expected_bond = expected_bond_reward + Uint128(initial_bond);
-        let expected_delegation = expected_delegation_reward + Uint128(initial_delegation);
+        assert_eq!(
+            events::new_mixnode_rewarding_event(
+                &identity,
+                20,
+                expected_mix_reward,
+                expected_total_delegation_reward,
+                0
+            ),
+            res.attributes
+        );
 
+        // if the node was 0% up, nobody will get any rewards
         let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_gateway(deps.as_mut(), info, node_identity.clone(), 100).unwrap();
+        let res = try_reward_mixnode(deps.as_mut(), info, identity.clone(), 0).unwrap();
 
         assert_eq!(
             expected_bond,
-            read_gateway_bond(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+            read_mixnode_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
+        );
+        assert_eq!(
+            expected_total_delegation,
+            read_mixnode_delegation(deps.as_ref().storage, identity.as_bytes()).unwrap()
         );
 
         assert_eq!(
-            expected_delegation,
-            read_gateway_delegation(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
+            expected_delegation1,
+            delegation_value(&deps.storage, &identity, "delegator1")
+        );
+        assert_eq!(
+            expected_delegation2,
+            delegation_value(&deps.storage, &identity, "delegator2")
+        );
+        assert_eq!(
+            expected_delegation3,
+            delegation_value(&deps.storage, &identity, "delegator3")
         );
 
         assert_eq!(
-            vec![
-                attr("bond increase", expected_bond_reward),
-                attr("total delegation increase", expected_delegation_reward),
-            ],
+            events::new_mixnode_not_rewarded_event(&identity, 0, events::ZERO_UPTIME_RESULT, 0),
             res.attributes
         );
 
-        // if node was 20% up, it will get 1/5th of epoch reward
-        let scaled_bond_reward = scale_reward_by_uptime(bond_reward_rate, 20).unwrap();
-        let scaled_delegation_reward = scale_reward_by_uptime(delegation_reward_rate, 20).unwrap();
-        let expected_bond_reward = expected_bond * scaled_bond_reward;
-        let expected_delegation_reward = expected_delegation * scaled_delegation_reward;
-        let expected_bond = expected_bond_reward + expected_bond;
-        let expected_delegation = expected_delegation_reward + expected_delegation;
+        // and once the delegation is removed, the delegator can withdraw its rewards as well
+        try_remove_delegation_from_mixnode(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("delegator3", &[]),
+            identity.clone(),
+        )
+        .unwrap();
+        let withdrawals = pending_withdrawals_read(&deps.storage, &Addr::unchecked("delegator3"))
+            .range(None, None, Order::Ascending)
+            .map(|res| res.unwrap().1)
+            .collect::<Vec<_>>();
+        assert_eq!(1, withdrawals.len());
+        assert_eq!(expected_delegation3, withdrawals[0].amount.amount);
+    }
 
-        let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_gateway(deps.as_mut(), info, node_identity.clone(), 20).unwrap();
+    #[cfg(test)]
+    mod pending_withdrawals {
+        use super::*;
 
-        assert_eq!(
-            expected_bond,
-            read_gateway_bond(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
-        );
+        fn unbonding_period_seconds() -> u64 {
+            INITIAL_UNBONDING_PERIOD as u64 * 60 * 60
+        }
+
+        #[test]
+        fn unbonded_stake_stops_counting_immediately() {
+            let mut deps = helpers::init_contract();
+            let identity = add_mixnode("bob", good_mixnode_bond(), &mut deps);
+
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("alice", &coins(100, DENOM)),
+                identity.clone(),
+            )
+            .unwrap();
+            try_remove_delegation_from_mixnode(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                identity.clone(),
+            )
+            .unwrap();
 
-        assert_eq!(
-            expected_delegation,
-            read_gateway_delegation(deps.as_ref().storage, node_identity.as_bytes()).unwrap()
-        );
+            // the node no longer includes the delegation even though it hasn't been withdrawn yet
+            assert_eq!(
+                Uint128::zero(),
+                mixnodes_read(&deps.storage)
+                    .load(identity.as_bytes())
+                    .unwrap()
+                    .total_delegation
+                    .amount
+            );
+        }
 
-        assert_eq!(
-            vec![
-                attr("bond increase", expected_bond_reward),
-                attr("total delegation increase", expected_delegation_reward),
-            ],
-            res.attributes
-        );
-    }
+        #[test]
+        fn cannot_be_claimed_before_unbonding_period_is_over() {
+            let mut deps = helpers::init_contract();
+            add_mixnode("bob", good_mixnode_bond(), &mut deps);
 
-    #[cfg(test)]
-    mod delegation_stake_validation {
-        use super::*;
-        use cosmwasm_std::coin;
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info("bob", &[])).unwrap();
 
-        #[test]
-        fn stake_cant_be_empty() {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(unbonding_period_seconds() - 1);
             assert_eq!(
-                Err(ContractError::EmptyDelegation),
-                validate_delegation_stake(&[])
-            )
+                Err(ContractError::NoAvailableWithdrawals),
+                try_claim_pending_withdrawals(deps.as_mut(), env, mock_info("bob", &[]), None)
+            );
         }
 
         #[test]
-        fn stake_must_have_single_coin_type() {
+        fn cannot_be_claimed_by_someone_else() {
+            let mut deps = helpers::init_contract();
+            add_mixnode("bob", good_mixnode_bond(), &mut deps);
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info("bob", &[])).unwrap();
+
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(unbonding_period_seconds());
             assert_eq!(
-                Err(ContractError::MultipleDenoms),
-                validate_delegation_stake(&[coin(123, DENOM), coin(123, "BTC"), coin(123, "DOGE")])
-            )
+                Err(ContractError::NoAvailableWithdrawals),
+                try_claim_pending_withdrawals(deps.as_mut(), env, mock_info("alice", &[]), None)
+            );
         }
 
         #[test]
-        fn stake_coin_must_be_of_correct_type() {
+        fn fails_if_funds_are_sent() {
+            let mut deps = helpers::init_contract();
             assert_eq!(
-                Err(ContractError::WrongDenom {}),
-                validate_delegation_stake(&[coin(123, "DOGE")])
-            )
+                Err(ContractError::UnexpectedClaimFunds),
+                try_claim_pending_withdrawals(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info("bob", &coins(100, DENOM)),
+                    None
+                )
+            );
         }
 
         #[test]
-        fn stake_coin_must_have_value_greater_than_zero() {
+        fn only_available_withdrawals_are_claimed() {
+            let mut deps = helpers::init_contract();
+            let identity = add_mixnode("bob", good_mixnode_bond(), &mut deps);
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("alice", &coins(200, DENOM)),
+                identity.clone(),
+            )
+            .unwrap();
+
+            // first unbond the node itself and then undelegate an hour later
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info("bob", &[])).unwrap();
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(60 * 60);
+            try_remove_delegation_from_mixnode(
+                deps.as_mut(),
+                env,
+                mock_info("alice", &[]),
+                identity,
+            )
+            .unwrap();
+
+            // funds from both unbonds are available to their respective owners
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(unbonding_period_seconds());
+            let res = try_claim_pending_withdrawals(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("bob", &[]),
+                None,
+            )
+            .unwrap();
             assert_eq!(
-                Err(ContractError::EmptyDelegation),
-                validate_delegation_stake(&[coin(0, DENOM)])
+                vec![BankMsg::Send {
+                    to_address: "bob".to_string(),
+                    amount: good_mixnode_bond(),
+                }
+                .into()],
+                res.messages
+            );
+
+            // but alice needs to wait for a bit longer
+            assert_eq!(
+                Err(ContractError::NoAvailableWithdrawals),
+                try_claim_pending_withdrawals(
+                    deps.as_mut(),
+                    env.clone(),
+                    mock_info("alice", &[]),
+                    None
+                )
+            );
+
+            env.block.time = env.block.time.plus_seconds(60 * 60);
+            let res = try_claim_pending_withdrawals(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("alice", &[]),
+                None,
             )
+            .unwrap();
+            assert_eq!(
+                vec![BankMsg::Send {
+                    to_address: "alice".to_string(),
+                    amount: coins(200, DENOM),
+                }
+                .into()],
+                res.messages
+            );
+
+            // and the withdrawals can't be claimed twice
+            assert_eq!(
+                Err(ContractError::NoAvailableWithdrawals),
+                try_claim_pending_withdrawals(deps.as_mut(), env, mock_info("bob", &[]), None)
+            );
         }
 
         #[test]
-        fn stake_can_have_any_positive_value() {
-            // this might change in the future, but right now an arbitrary (positive) value can be delegated
-            assert!(validate_delegation_stake(&[coin(1, DENOM)]).is_ok());
-            assert!(validate_delegation_stake(&[coin(123, DENOM)]).is_ok());
-            assert!(validate_delegation_stake(&[coin(10000000000, DENOM)]).is_ok());
+        fn claims_are_split_according_to_the_limit() {
+            let mut deps = helpers::init_contract();
+            for owner in &["bob", "carol", "dave"] {
+                let identity = add_mixnode(owner, good_mixnode_bond(), &mut deps);
+                try_delegate_to_mixnode(
+                    deps.as_mut(),
+                    mock_info("alice", &coins(100, DENOM)),
+                    identity.clone(),
+                )
+                .unwrap();
+                try_remove_delegation_from_mixnode(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info("alice", &[]),
+                    identity,
+                )
+                .unwrap();
+            }
+
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(unbonding_period_seconds());
+            let res = try_claim_pending_withdrawals(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("alice", &[]),
+                Some(2),
+            )
+            .unwrap();
+            assert_eq!(
+                events::new_pending_withdrawals_claim_event(
+                    &Addr::unchecked("alice"),
+                    2,
+                    Uint128(200)
+                ),
+                res.attributes
+            );
+
+            // the rest is left for the next claim
+            let res = try_claim_pending_withdrawals(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("alice", &[]),
+                None,
+            )
+            .unwrap();
+            assert_eq!(
+                vec![BankMsg::Send {
+                    to_address: "alice".to_string(),
+                    amount: coins(100, DENOM),
+                }
+                .into()],
+                res.messages
+            );
+            assert_eq!(
+                Err(ContractError::NoAvailableWithdrawals),
+                try_claim_pending_withdrawals(deps.as_mut(), env, mock_info("alice", &[]), None)
+            );
         }
     }
 
     #[cfg(test)]
-    mod mix_stake_delegation {
+    mod gateway_stake_delegation {
         use super::*;
-        use crate::storage::mix_delegations_read;
-        use crate::support::tests::helpers::add_mixnode;
+        use crate::storage::gateway_delegations_read;
+        use crate::support::tests::helpers::add_gateway;
 
         #[test]
         fn fails_if_node_doesnt_exist() {
             let mut deps = helpers::init_contract();
             assert_eq!(
-                Err(ContractError::MixNodeBondNotFound {
-                    identity: "non-existent-mix-identity".into()
+                Err(ContractError::GatewayBondNotFound {
+                    identity: "non-existent-gateway-identity".into()
                 }),
-                try_delegate_to_mixnode(
+                try_delegate_to_gateway(
                     deps.as_mut(),
                     mock_info("sender", &coins(123, DENOM)),
-                    "non-existent-mix-identity".into()
+                    "non-existent-gateway-identity".into()
                 )
             );
         }
@@ -2028,11 +5465,11 @@ pub mod tests {
         #[test]
         fn succeeds_for_existing_node() {
             let mut deps = helpers::init_contract();
-            let mixnode_owner = "bob";
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            let gateway_owner = "bob";
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
 
             let delegation = coin(123, DENOM);
-            assert!(try_delegate_to_mixnode(
+            assert!(try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender", &vec![delegation.clone()]),
                 identity.clone()
@@ -2041,7 +5478,7 @@ pub mod tests {
 
             assert_eq!(
                 delegation.amount,
-                mix_delegations_read(&deps.storage, &identity)
+                gateway_delegations_read(&deps.storage, &identity)
                     .load(b"sender")
                     .unwrap()
             );
@@ -2049,7 +5486,7 @@ pub mod tests {
             // node's "total_delegation" is increased
             assert_eq!(
                 delegation,
-                mixnodes_read(&deps.storage)
+                gateways_read(&deps.storage)
                     .load(identity.as_bytes())
                     .unwrap()
                     .total_delegation
@@ -2060,16 +5497,15 @@ pub mod tests {
         fn fails_if_node_unbonded() {
             let mut deps = helpers::init_contract();
 
-            let mixnode_owner = "bob";
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
-
-            try_remove_mixnode(deps.as_mut(), mock_info(mixnode_owner, &[])).unwrap();
+            let gateway_owner = "bob";
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
+            try_remove_gateway(deps.as_mut(), mock_env(), mock_info(gateway_owner, &[])).unwrap();
 
             assert_eq!(
-                Err(ContractError::MixNodeBondNotFound {
+                Err(ContractError::GatewayBondNotFound {
                     identity: identity.clone()
                 }),
-                try_delegate_to_mixnode(
+                try_delegate_to_gateway(
                     deps.as_mut(),
                     mock_info("sender", &coins(123, DENOM)),
                     identity
@@ -2081,13 +5517,13 @@ pub mod tests {
         fn succeeds_if_node_rebonded() {
             let mut deps = helpers::init_contract();
 
-            let mixnode_owner = "bob";
-            add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
-            try_remove_mixnode(deps.as_mut(), mock_info(mixnode_owner, &[])).unwrap();
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            let gateway_owner = "bob";
+            add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
+            try_remove_gateway(deps.as_mut(), mock_env(), mock_info(gateway_owner, &[])).unwrap();
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
             let delegation = coin(123, DENOM);
 
-            assert!(try_delegate_to_mixnode(
+            assert!(try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender", &vec![delegation.clone()]),
                 identity.clone()
@@ -2096,7 +5532,7 @@ pub mod tests {
 
             assert_eq!(
                 delegation.amount,
-                mix_delegations_read(&deps.storage, &identity)
+                gateway_delegations_read(&deps.storage, &identity)
                     .load(b"sender")
                     .unwrap()
             );
@@ -2104,7 +5540,7 @@ pub mod tests {
             // node's "total_delegation" is increased
             assert_eq!(
                 delegation,
-                mixnodes_read(&deps.storage)
+                gateways_read(&deps.storage)
                     .load(identity.as_bytes())
                     .unwrap()
                     .total_delegation
@@ -2114,20 +5550,20 @@ pub mod tests {
         #[test]
         fn is_possible_for_an_already_delegated_node() {
             let mut deps = helpers::init_contract();
-            let mixnode_owner = "bob";
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            let gateway_owner = "bob";
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
 
             let delegation1 = coin(100, DENOM);
             let delegation2 = coin(50, DENOM);
 
-            try_delegate_to_mixnode(
+            try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender", &vec![delegation1.clone()]),
                 identity.clone(),
             )
             .unwrap();
 
-            try_delegate_to_mixnode(
+            try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender", &vec![delegation2.clone()]),
                 identity.clone(),
@@ -2136,7 +5572,7 @@ pub mod tests {
 
             assert_eq!(
                 delegation1.amount + delegation2.amount,
-                mix_delegations_read(&deps.storage, &identity)
+                gateway_delegations_read(&deps.storage, &identity)
                     .load(b"sender")
                     .unwrap()
             );
@@ -2144,7 +5580,7 @@ pub mod tests {
             // node's "total_delegation" is sum of both
             assert_eq!(
                 delegation1.amount + delegation2.amount,
-                mixnodes_read(&deps.storage)
+                gateways_read(&deps.storage)
                     .load(identity.as_bytes())
                     .unwrap()
                     .total_delegation
@@ -2156,26 +5592,26 @@ pub mod tests {
         fn is_disallowed_for_already_delegated_node_if_it_unbonded() {
             let mut deps = helpers::init_contract();
 
-            let mixnode_owner = "bob";
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            let gateway_owner = "bob";
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
 
-            try_delegate_to_mixnode(
+            try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender", &coins(100, DENOM)),
                 identity.clone(),
             )
             .unwrap();
 
-            try_remove_mixnode(deps.as_mut(), mock_info(mixnode_owner, &[])).unwrap();
+            try_remove_gateway(deps.as_mut(), mock_env(), mock_info(gateway_owner, &[])).unwrap();
 
             assert_eq!(
-                Err(ContractError::MixNodeBondNotFound {
+                Err(ContractError::GatewayBondNotFound {
                     identity: identity.clone()
                 }),
-                try_delegate_to_mixnode(
+                try_delegate_to_gateway(
                     deps.as_mut(),
                     mock_info("sender", &coins(50, DENOM)),
-                    identity
+                    identity.clone()
                 )
             );
         }
@@ -2183,19 +5619,19 @@ pub mod tests {
         #[test]
         fn is_allowed_for_multiple_nodes() {
             let mut deps = helpers::init_contract();
-            let mixnode_owner1 = "bob";
-            let mixnode_owner2 = "fred";
-            let identity1 = add_mixnode(mixnode_owner1, good_mixnode_bond(), &mut deps);
-            let identity2 = add_mixnode(mixnode_owner2, good_mixnode_bond(), &mut deps);
+            let gateway_owner1 = "bob";
+            let gateway_owner2 = "fred";
+            let identity1 = add_gateway(gateway_owner1, good_gateway_bond(), &mut deps);
+            let identity2 = add_gateway(gateway_owner2, good_gateway_bond(), &mut deps);
 
-            assert!(try_delegate_to_mixnode(
+            assert!(try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender", &coins(123, DENOM)),
                 identity1.clone()
             )
             .is_ok());
 
-            assert!(try_delegate_to_mixnode(
+            assert!(try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender", &coins(42, DENOM)),
                 identity2.clone()
@@ -2204,7 +5640,7 @@ pub mod tests {
 
             assert_eq!(
                 123,
-                mix_delegations_read(&deps.storage, &identity1)
+                gateway_delegations_read(&deps.storage, &identity1)
                     .load(b"sender")
                     .unwrap()
                     .u128()
@@ -2212,7 +5648,7 @@ pub mod tests {
 
             assert_eq!(
                 42,
-                mix_delegations_read(&deps.storage, &identity2)
+                gateway_delegations_read(&deps.storage, &identity2)
                     .load(b"sender")
                     .unwrap()
                     .u128()
@@ -2222,20 +5658,20 @@ pub mod tests {
         #[test]
         fn is_allowed_by_multiple_users() {
             let mut deps = helpers::init_contract();
-            let mixnode_owner = "bob";
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            let gateway_owner = "bob";
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
 
             let delegation1 = coin(123, DENOM);
             let delegation2 = coin(234, DENOM);
 
-            assert!(try_delegate_to_mixnode(
+            assert!(try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender1", &vec![delegation1.clone()]),
                 identity.clone()
             )
             .is_ok());
 
-            assert!(try_delegate_to_mixnode(
+            assert!(try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender2", &vec![delegation2.clone()]),
                 identity.clone()
@@ -2245,7 +5681,7 @@ pub mod tests {
             // node's "total_delegation" is sum of both
             assert_eq!(
                 delegation1.amount + delegation2.amount,
-                mixnodes_read(&deps.storage)
+                gateways_read(&deps.storage)
                     .load(identity.as_bytes())
                     .unwrap()
                     .total_delegation
@@ -2257,21 +5693,21 @@ pub mod tests {
         fn delegation_is_not_removed_if_node_unbonded() {
             let mut deps = helpers::init_contract();
 
-            let mixnode_owner = "bob";
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            let gateway_owner = "bob";
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
 
-            try_delegate_to_mixnode(
+            try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender", &coins(100, DENOM)),
                 identity.clone(),
             )
             .unwrap();
 
-            try_remove_mixnode(deps.as_mut(), mock_info(mixnode_owner, &[])).unwrap();
+            try_remove_gateway(deps.as_mut(), mock_env(), mock_info(gateway_owner, &[])).unwrap();
 
             assert_eq!(
                 100,
-                mix_delegations_read(&deps.storage, &identity)
+                gateway_delegations_read(&deps.storage, &identity)
                     .load(b"sender")
                     .unwrap()
                     .u128()
@@ -2280,25 +5716,25 @@ pub mod tests {
     }
 
     #[cfg(test)]
-    mod removing_mix_stake_delegation {
+    mod removing_gateway_stake_delegation {
         use super::*;
-        use crate::storage::mix_delegations_read;
-        use crate::support::tests::helpers::add_mixnode;
+        use crate::storage::gateway_delegations_read;
+        use crate::support::tests::helpers::add_gateway;
 
         #[test]
         fn fails_if_delegation_never_existed() {
             let mut deps = helpers::init_contract();
 
-            let mixnode_owner = "bob";
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
-
+            let gateway_owner = "bob";
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
             assert_eq!(
-                Err(ContractError::NoMixnodeDelegationFound {
+                Err(ContractError::NoGatewayDelegationFound {
                     identity: identity.clone(),
                     address: Addr::unchecked("sender"),
                 }),
-                try_remove_delegation_from_mixnode(
+                try_remove_delegation_from_gateway(
                     deps.as_mut(),
+                    mock_env(),
                     mock_info("sender", &[]),
                     identity,
                 )
@@ -2309,10 +5745,10 @@ pub mod tests {
         fn succeeds_if_delegation_existed() {
             let mut deps = helpers::init_contract();
 
-            let mixnode_owner = "bob";
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            let gateway_owner = "bob";
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
 
-            try_delegate_to_mixnode(
+            try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender", &coins(100, DENOM)),
                 identity.clone(),
@@ -2322,22 +5758,31 @@ pub mod tests {
             assert_eq!(
                 Ok(Response {
                     submessages: vec![],
-                    messages: vec![BankMsg::Send {
-                        to_address: "sender".into(),
-                        amount: coins(100, DENOM),
-                    }
-                    .into()],
-                    attributes: Vec::new(),
+                    messages: vec![],
+                    attributes: events::new_gateway_undelegation_event(
+                        &Addr::unchecked("sender"),
+                        &identity,
+                        Uint128(100),
+                        0
+                    ),
                     data: None,
                 }),
-                try_remove_delegation_from_mixnode(
+                try_remove_delegation_from_gateway(
                     deps.as_mut(),
+                    mock_env(),
                     mock_info("sender", &[]),
                     identity.clone(),
                 )
             );
 
-            assert!(mix_delegations_read(&deps.storage, &identity)
+            // the funds are locked until the unbonding period is over
+            let withdrawals = pending_withdrawals_read(&deps.storage, &Addr::unchecked("sender"))
+                .range(None, None, Order::Ascending)
+                .map(|res| res.unwrap().1.amount)
+                .collect::<Vec<_>>();
+            assert_eq!(coins(100, DENOM), withdrawals);
+
+            assert!(gateway_delegations_read(&deps.storage, &identity)
                 .may_load(b"sender")
                 .unwrap()
                 .is_none());
@@ -2345,7 +5790,7 @@ pub mod tests {
             // and total delegation is cleared
             assert_eq!(
                 Uint128::zero(),
-                mixnodes_read(&deps.storage)
+                gateways_read(&deps.storage)
                     .load(identity.as_bytes())
                     .unwrap()
                     .total_delegation
@@ -2357,37 +5802,46 @@ pub mod tests {
         fn succeeds_if_delegation_existed_even_if_node_unbonded() {
             let mut deps = helpers::init_contract();
 
-            let mixnode_owner = "bob";
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            let gateway_owner = "bob";
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
 
-            try_delegate_to_mixnode(
+            try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender", &coins(100, DENOM)),
                 identity.clone(),
             )
             .unwrap();
 
-            try_remove_mixnode(deps.as_mut(), mock_info(mixnode_owner, &[])).unwrap();
+            try_remove_gateway(deps.as_mut(), mock_env(), mock_info(gateway_owner, &[])).unwrap();
 
             assert_eq!(
                 Ok(Response {
                     submessages: vec![],
-                    messages: vec![BankMsg::Send {
-                        to_address: "sender".into(),
-                        amount: coins(100, DENOM),
-                    }
-                    .into()],
-                    attributes: Vec::new(),
+                    messages: vec![],
+                    attributes: events::new_gateway_undelegation_event(
+                        &Addr::unchecked("sender"),
+                        &identity,
+                        Uint128(100),
+                        0
+                    ),
                     data: None,
                 }),
-                try_remove_delegation_from_mixnode(
+                try_remove_delegation_from_gateway(
                     deps.as_mut(),
+                    mock_env(),
                     mock_info("sender", &[]),
                     identity.clone(),
                 )
             );
 
-            assert!(mix_delegations_read(&deps.storage, &identity)
+            // the funds are locked until the unbonding period is over
+            let withdrawals = pending_withdrawals_read(&deps.storage, &Addr::unchecked("sender"))
+                .range(None, None, Order::Ascending)
+                .map(|res| res.unwrap().1.amount)
+                .collect::<Vec<_>>();
+            assert_eq!(coins(100, DENOM), withdrawals);
+
+            assert!(gateway_delegations_read(&deps.storage, &identity)
                 .may_load(b"sender")
                 .unwrap()
                 .is_none());
@@ -2396,20 +5850,20 @@ pub mod tests {
         #[test]
         fn total_delegation_is_preserved_if_only_some_undelegate() {
             let mut deps = helpers::init_contract();
-            let mixnode_owner = "bob";
-            let identity = add_mixnode(mixnode_owner, good_mixnode_bond(), &mut deps);
+            let gateway_owner = "bob";
+            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
 
             let delegation1 = coin(123, DENOM);
             let delegation2 = coin(234, DENOM);
 
-            assert!(try_delegate_to_mixnode(
+            assert!(try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender1", &vec![delegation1.clone()]),
                 identity.clone()
             )
             .is_ok());
 
-            assert!(try_delegate_to_mixnode(
+            assert!(try_delegate_to_gateway(
                 deps.as_mut(),
                 mock_info("sender2", &vec![delegation2.clone()]),
                 identity.clone()
@@ -2417,8 +5871,9 @@ pub mod tests {
             .is_ok());
 
             // sender1 undelegates
-            try_remove_delegation_from_mixnode(
+            try_remove_delegation_from_gateway(
                 deps.as_mut(),
+                mock_env(),
                 mock_info("sender1", &[]),
                 identity.clone(),
             )
@@ -2428,7 +5883,7 @@ pub mod tests {
             // node's "total_delegation" is sum of both
             assert_eq!(
                 delegation2,
-                mixnodes_read(&deps.storage)
+                gateways_read(&deps.storage)
                     .load(identity.as_bytes())
                     .unwrap()
                     .total_delegation
@@ -2437,83 +5892,90 @@ pub mod tests {
     }
 
     #[test]
-    fn delegators_on_mix_node_reward_rate() {
+    fn delegators_on_gateway_reward_rate() {
         let mut deps = helpers::init_contract();
         let current_state = config(deps.as_mut().storage).load().unwrap();
         let network_monitor_address = current_state.network_monitor_address;
 
-        let initial_mix_bond = 100_000000;
+        let initial_gateway_bond = 100_000000;
         let initial_delegation1 = 50000; // will see single digits rewards
         let initial_delegation2 = 100; // won't see any rewards due to such a small delegation
         let initial_delegation3 = 100000_000000; // will see big proper rewards
+        let initial_total_delegation =
+            Uint128(initial_delegation1 + initial_delegation2 + initial_delegation3);
 
         let node_owner = "node-owner";
-        let identity = add_mixnode(node_owner, good_mixnode_bond(), &mut deps);
+        let identity = add_gateway(node_owner, good_gateway_bond(), &mut deps);
 
-        mix_delegations(&mut deps.storage, &identity)
-            .save(b"delegator1", &Uint128(initial_delegation1))
-            .unwrap();
-        mix_delegations(&mut deps.storage, &identity)
-            .save(b"delegator2", &Uint128(initial_delegation2))
-            .unwrap();
-        mix_delegations(&mut deps.storage, &identity)
-            .save(b"delegator3", &Uint128(initial_delegation3))
+        for (delegator, amount) in [
+            ("delegator1", initial_delegation1),
+            ("delegator2", initial_delegation2),
+            ("delegator3", initial_delegation3),
+        ] {
+            try_delegate_to_gateway(
+                deps.as_mut(),
+                mock_info(delegator, &coins(amount, DENOM)),
+                identity.clone(),
+            )
             .unwrap();
+        }
 
-        let bond_reward = read_mixnode_epoch_bond_reward_rate(deps.as_ref().storage);
-        let delegation_reward = read_mixnode_epoch_delegation_reward_rate(deps.as_ref().storage);
+        fn delegation_value(storage: &dyn Storage, identity: &str, delegator: &str) -> Uint128 {
+            read_gateway_delegation_value(storage, identity, delegator.as_bytes())
+                .unwrap()
+                .unwrap()
+        }
+
+        let bond_reward = read_gateway_epoch_bond_reward_rate(deps.as_ref().storage);
+        let delegation_reward = read_gateway_epoch_delegation_reward_rate(deps.as_ref().storage);
 
         // the node's bond and delegations are correctly increased and scaled by uptime
         // if node was 100% up, it will get full epoch reward
-        let expected_mix_reward = Uint128(initial_mix_bond) * bond_reward;
+        let expected_gateway_reward = Uint128(initial_gateway_bond) * bond_reward;
         let expected_delegation1_reward = Uint128(initial_delegation1) * delegation_reward;
         let expected_delegation2_reward = Uint128(initial_delegation2) * delegation_reward;
         let expected_delegation3_reward = Uint128(initial_delegation3) * delegation_reward;
+        let expected_total_delegation_reward = initial_total_delegation * delegation_reward;
 
-        let expected_bond = expected_mix_reward + Uint128(initial_mix_bond);
+        let expected_bond = expected_gateway_reward + Uint128(initial_gateway_bond);
         let expected_delegation1 = expected_delegation1_reward + Uint128(initial_delegation1);
         let expected_delegation2 = expected_delegation2_reward + Uint128(initial_delegation2);
         let expected_delegation3 = expected_delegation3_reward + Uint128(initial_delegation3);
+        let expected_total_delegation = expected_total_delegation_reward + initial_total_delegation;
 
         let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_mixnode(deps.as_mut(), info, identity.clone(), 100).unwrap();
+        let res = try_reward_gateway(deps.as_mut(), info, identity.clone(), 100).unwrap();
 
         assert_eq!(
             expected_bond,
-            read_mixnode_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
+            read_gateway_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
+        );
+        assert_eq!(
+            expected_total_delegation,
+            read_gateway_delegation(deps.as_ref().storage, identity.as_bytes()).unwrap()
         );
 
         assert_eq!(
             expected_delegation1,
-            mix_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator1".as_bytes())
-                .unwrap()
+            delegation_value(&deps.storage, &identity, "delegator1")
         );
-
         assert_eq!(
             expected_delegation2,
-            mix_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator2".as_bytes())
-                .unwrap()
+            delegation_value(&deps.storage, &identity, "delegator2")
         );
-
         assert_eq!(
             expected_delegation3,
-            mix_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator3".as_bytes())
-                .unwrap()
+            delegation_value(&deps.storage, &identity, "delegator3")
         );
 
         assert_eq!(
-            vec![
-                attr("bond increase", expected_mix_reward),
-                attr(
-                    "total delegation increase",
-                    expected_delegation1_reward
-                        + expected_delegation2_reward
-                        + expected_delegation3_reward
-                ),
-            ],
+            events::new_gateway_rewarding_event(
+                &identity,
+                100,
+                expected_gateway_reward,
+                expected_total_delegation_reward,
+                0
+            ),
             res.attributes
         );
 
@@ -2521,775 +5983,1728 @@ pub mod tests {
         let scaled_bond_reward = scale_reward_by_uptime(bond_reward, 20).unwrap();
         let scaled_delegation_reward = scale_reward_by_uptime(delegation_reward, 20).unwrap();
 
-        let expected_mix_reward = expected_bond * scaled_bond_reward;
-        let expected_delegation1_reward = expected_delegation1 * scaled_delegation_reward;
-        let expected_delegation2_reward = expected_delegation2 * scaled_delegation_reward;
-        let expected_delegation3_reward = expected_delegation3 * scaled_delegation_reward;
+        // delegations are compounded through the reward index of the node
+        let expected_index = compound_reward_index(
+            compound_reward_index(Decimal::one(), delegation_reward),
+            scaled_delegation_reward,
+        );
+
+        let expected_gateway_reward = expected_bond * scaled_bond_reward;
+        let expected_total_delegation_reward = expected_total_delegation * scaled_delegation_reward;
+
+        let expected_bond = expected_gateway_reward + expected_bond;
+        let expected_delegation1 =
+            apply_reward_index(Uint128(initial_delegation1), Decimal::one(), expected_index);
+        let expected_delegation2 =
+            apply_reward_index(Uint128(initial_delegation2), Decimal::one(), expected_index);
+        let expected_delegation3 =
+            apply_reward_index(Uint128(initial_delegation3), Decimal::one(), expected_index);
+        let expected_total_delegation =
+            expected_total_delegation_reward + expected_total_delegation;
+
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        let res = try_reward_gateway(deps.as_mut(), info, identity.clone(), 20).unwrap();
+
+        assert_eq!(
+            expected_bond,
+            read_gateway_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
+        );
+        assert_eq!(
+            expected_total_delegation,
+            read_gateway_delegation(deps.as_ref().storage, identity.as_bytes()).unwrap()
+        );
+
+        assert_eq!(
+            expected_delegation1,
+            delegation_value(&deps.storage, &identity, "delegator1")
+        );
+        assert_eq!(
+            expected_delegation2,
+            delegation_value(&deps.storage, &identity, "delegator2")
+        );
+        assert_eq!(
+            expected_delegation3,
+            delegation_value(&deps.storage, &identity, "delegator3")
+        );
+
+        assert_eq!(
+            events::new_gateway_rewarding_event(
+                &identity,
+                20,
+                expected_gateway_reward,
+                expected_total_delegation_reward,
+                0
+            ),
+            res.attributes
+        );
+
+        // if the node was 0% up, nobody will get any rewards
+        let info = mock_info(network_monitor_address.as_ref(), &[]);
+        let res = try_reward_gateway(deps.as_mut(), info, identity.clone(), 0).unwrap();
+
+        assert_eq!(
+            expected_bond,
+            read_gateway_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
+        );
+        assert_eq!(
+            expected_total_delegation,
+            read_gateway_delegation(deps.as_ref().storage, identity.as_bytes()).unwrap()
+        );
+
+        assert_eq!(
+            expected_delegation1,
+            delegation_value(&deps.storage, &identity, "delegator1")
+        );
+        assert_eq!(
+            expected_delegation2,
+            delegation_value(&deps.storage, &identity, "delegator2")
+        );
+        assert_eq!(
+            expected_delegation3,
+            delegation_value(&deps.storage, &identity, "delegator3")
+        );
+
+        assert_eq!(
+            events::new_gateway_not_rewarded_event(&identity, 0, events::ZERO_UPTIME_RESULT, 0),
+            res.attributes
+        );
+
+        // and once the delegation is removed, the delegator gets back its rewards as well
+        try_remove_delegation_from_gateway(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("delegator3", &[]),
+            identity.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            coin(expected_delegation3.u128(), DENOM),
+            pending_withdrawals_read(&deps.storage, &Addr::unchecked("delegator3"))
+                .load(&1u64.to_be_bytes())
+                .unwrap()
+                .amount
+        );
+    }
+
+    #[cfg(test)]
+    mod finding_old_delegations {
+        use super::*;
+
+        #[test]
+        fn when_there_werent_any() {
+            let deps = helpers::init_contract();
+
+            let node_identity: IdentityKey = "nodeidentity".into();
+
+            let read_bucket = mix_delegations_read(&deps.storage, &node_identity);
+            let indices_bucket = mix_delegation_reward_indices_read(&deps.storage, &node_identity);
+            let old_delegations =
+                find_old_delegations(read_bucket, indices_bucket, Decimal::one()).unwrap();
+
+            assert_eq!(Coin::new(0, DENOM), old_delegations);
+        }
+
+        #[test]
+        fn when_some_existed() {
+            let num_delegations = vec![
+                1,
+                5,
+                OLD_DELEGATIONS_CHUNK_SIZE - 1,
+                OLD_DELEGATIONS_CHUNK_SIZE,
+                OLD_DELEGATIONS_CHUNK_SIZE + 1,
+                OLD_DELEGATIONS_CHUNK_SIZE * 3,
+                OLD_DELEGATIONS_CHUNK_SIZE * 3 + 1,
+            ];
+
+            for total_delegations in num_delegations {
+                let mut deps = helpers::init_contract();
+
+                let node_identity: IdentityKey = "nodeidentity".into();
+
+                // delegate some stake
+                let mut write_bucket = mix_delegations(&mut deps.storage, &node_identity);
+                for i in 1..=total_delegations {
+                    let delegator = Addr::unchecked(format!("delegator{}", i));
+                    let delegation = Uint128(i as u128);
+                    write_bucket
+                        .save(delegator.as_bytes(), &delegation)
+                        .unwrap();
+                }
+
+                let read_bucket = mix_delegations_read(&deps.storage, &node_identity);
+                let indices_bucket =
+                    mix_delegation_reward_indices_read(&deps.storage, &node_identity);
+                let old_delegations =
+                    find_old_delegations(read_bucket, indices_bucket, Decimal::one()).unwrap();
+
+                let total_delegation = (1..=total_delegations as u128).into_iter().sum();
+                assert_eq!(Coin::new(total_delegation, DENOM), old_delegations);
+            }
+        }
+
+        #[test]
+        fn includes_rewards_given_before_unbonding() {
+            let mut deps = helpers::init_contract();
+
+            let node_identity: IdentityKey = "nodeidentity".into();
+
+            // 0.001
+            let reward = Decimal::from_ratio(1u128, 1000u128);
+
+            save_mix_delegation(&mut deps.storage, &node_identity, b"alice", Uint128(1000))
+                .unwrap();
+            increase_mix_reward_index(&mut deps.storage, &node_identity, reward).unwrap();
+            save_mix_delegation(&mut deps.storage, &node_identity, b"bob", Uint128(1000)).unwrap();
+
+            let read_bucket = mix_delegations_read(&deps.storage, &node_identity);
+            let indices_bucket = mix_delegation_reward_indices_read(&deps.storage, &node_identity);
+            let current_index = read_mix_reward_index(&deps.storage, &node_identity).unwrap();
+            let old_delegations =
+                find_old_delegations(read_bucket, indices_bucket, current_index).unwrap();
+
+            // only alice's delegation was rewarded
+            assert_eq!(Coin::new(2001, DENOM), old_delegations);
+        }
+    }
+
+    #[test]
+    fn choose_layer_mix_node() {
+        let mut deps = helpers::init_contract();
+        for owner in ["alice", "bob"] {
+            try_add_mixnode(
+                deps.as_mut(),
+                mock_info(owner, &good_mixnode_bond()),
+                MixNode {
+                    identity_key: owner.to_string(),
+                    ..helpers::mix_node_fixture()
+                },
+                10,
+            )
+            .unwrap();
+        }
+        let bonded_mix_nodes = helpers::get_mix_nodes(&mut deps);
+        let alice_node = bonded_mix_nodes.get(0).unwrap().clone();
+        let bob_node = bonded_mix_nodes.get(1).unwrap().clone();
+        assert_eq!(alice_node.mix_node.identity_key, "alice");
+        assert_eq!(alice_node.layer, Layer::One);
+        assert_eq!(bob_node.mix_node.identity_key, "bob");
+        assert_eq!(bob_node.layer, Layer::Two);
+    }
+
+    #[cfg(test)]
+    mod advancing_epoch {
+        use super::*;
+        use crate::queries::{query_active_set_paged, query_epoch};
+        use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+        use cosmwasm_std::OwnedDeps;
+
+        fn network_monitor(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>) -> MessageInfo {
+            let state = config_read(&deps.storage).load().unwrap();
+            mock_info(state.network_monitor_address.as_ref(), &[])
+        }
+
+        fn set_active_set_size(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, size: u32) {
+            let mut state = config_read(&deps.storage).load().unwrap();
+            state.params.mixnode_active_set_size = size;
+            config(&mut deps.storage).save(&state).unwrap();
+        }
+
+        fn epoch_length_seconds() -> u64 {
+            INITIAL_DEFAULT_EPOCH_LENGTH as u64 * 60 * 60
+        }
+
+        #[test]
+        fn can_only_be_done_by_network_monitor() {
+            let mut deps = helpers::init_contract();
+            assert_eq!(
+                Err(ContractError::Unauthorized),
+                try_advance_epoch(deps.as_mut(), mock_env(), mock_info("alice", &[]))
+            );
+
+            let info = network_monitor(&deps);
+            assert!(try_advance_epoch(deps.as_mut(), mock_env(), info).is_ok());
+        }
+
+        #[test]
+        fn cannot_be_done_before_current_epoch_ends() {
+            let mut deps = helpers::init_contract();
+            let info = network_monitor(&deps);
+            let env = mock_env();
+            helpers::advance_epoch(&mut deps, env.clone());
+
+            let mut too_early = env.clone();
+            too_early.block.time = too_early
+                .block
+                .time
+                .plus_seconds(epoch_length_seconds() - 1);
+            assert_eq!(
+                Err(ContractError::EpochInProgress {
+                    epoch_end: env.block.time.seconds() + epoch_length_seconds()
+                }),
+                try_advance_epoch(deps.as_mut(), too_early, info.clone())
+            );
+
+            let mut on_time = env;
+            on_time.block.time = on_time.block.time.plus_seconds(epoch_length_seconds());
+            try_advance_epoch(deps.as_mut(), on_time, info).unwrap();
+            assert_eq!(2, query_epoch(deps.as_ref()).unwrap().epoch);
+        }
+
+        #[test]
+        fn selects_nodes_with_highest_stake() {
+            let mut deps = helpers::init_contract();
+            set_active_set_size(&mut deps, 4);
+
+            let mut identities = Vec::new();
+            for (owner, bond) in &[
+                ("alice", 100_000000),
+                ("bob", 300_000000),
+                ("carol", 200_000000),
+                ("dave", 150_000000),
+                ("eve", 120_000000),
+            ] {
+                identities.push(add_mixnode(owner, coins(*bond, DENOM), &mut deps));
+            }
+
+            // delegations count towards the stake of a node
+            try_delegate_to_mixnode(
+                deps.as_mut(),
+                mock_info("delegator", &coins(250_000000, DENOM)),
+                identities[0].clone(),
+            )
+            .unwrap();
+
+            helpers::advance_epoch(&mut deps, mock_env());
+
+            let epoch = query_epoch(deps.as_ref()).unwrap();
+            assert_eq!(1, epoch.epoch);
+            assert_eq!(epoch.epoch_start + epoch_length_seconds(), epoch.epoch_end);
+
+            let active_set = query_active_set_paged(deps.as_ref(), None, None).unwrap();
+            let selected = active_set
+                .nodes
+                .iter()
+                .map(|bond| bond.identity().as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                vec!["alicemixnode", "bobmixnode", "carolmixnode", "davemixnode"],
+                selected
+            );
+
+            // and layers are assigned in order of stake
+            let layers = active_set
+                .nodes
+                .iter()
+                .map(|bond| bond.layer)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                vec![Layer::One, Layer::Two, Layer::Three, Layer::One],
+                layers
+            );
+        }
+
+        #[test]
+        fn layer_distribution_matches_rebalanced_layers() {
+            let mut deps = helpers::init_contract();
+            set_active_set_size(&mut deps, 3);
+
+            for owner in &["alice", "bob", "carol", "dave"] {
+                add_mixnode(owner, good_mixnode_bond(), &mut deps);
+            }
+
+            helpers::advance_epoch(&mut deps, mock_env());
+
+            let mut expected = LayerDistribution::default();
+            for bond in helpers::get_mix_nodes(&mut deps) {
+                match bond.layer {
+                    Layer::One => expected.layer1 += 1,
+                    Layer::Two => expected.layer2 += 1,
+                    Layer::Three => expected.layer3 += 1,
+                    Layer::Gateway => unreachable!(),
+                }
+            }
+            assert_eq!(expected, read_layer_distribution(&deps.storage));
+        }
+
+        #[test]
+        fn unbonded_nodes_are_not_returned_as_active() {
+            let mut deps = helpers::init_contract();
+            add_mixnode("alice", good_mixnode_bond(), &mut deps);
+            let bob_identity = add_mixnode("bob", good_mixnode_bond(), &mut deps);
+
+            helpers::advance_epoch(&mut deps, mock_env());
+            assert_eq!(
+                2,
+                query_active_set_paged(deps.as_ref(), None, None)
+                    .unwrap()
+                    .nodes
+                    .len()
+            );
 
-        let expected_bond = expected_mix_reward + expected_bond;
-        let expected_delegation1 = expected_delegation1_reward + expected_delegation1;
-        let expected_delegation2 = expected_delegation2_reward + expected_delegation2;
-        let expected_delegation3 = expected_delegation3_reward + expected_delegation3;
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info("alice", &[])).unwrap();
 
-        let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_mixnode(deps.as_mut(), info, identity.clone(), 20).unwrap();
+            let active_set = query_active_set_paged(deps.as_ref(), None, None).unwrap();
+            assert_eq!(1, active_set.nodes.len());
+            assert_eq!(&bob_identity, active_set.nodes[0].identity());
+        }
 
-        assert_eq!(
-            expected_bond,
-            read_mixnode_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
-        );
+        #[test]
+        fn selection_is_spread_over_multiple_transactions() {
+            let mut deps = helpers::init_contract();
+            set_active_set_size(&mut deps, 3);
 
-        assert_eq!(
-            expected_delegation1,
-            mix_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator1".as_bytes())
+            // the node with the highest stake is the last one to be looked at
+            let mut identities = Vec::new();
+            for i in 0..ACTIVE_SET_SELECTION_BATCH_SIZE {
+                let owner = format!("owner{:04}", i);
+                identities.push(add_mixnode(&owner, good_mixnode_bond(), &mut deps));
+            }
+            let richest = add_mixnode("richest", coins(500_000000, DENOM), &mut deps);
+
+            let info = network_monitor(&deps);
+            let res = try_advance_epoch(deps.as_mut(), mock_env(), info).unwrap();
+            assert!(res.attributes.contains(&attr("selection_finished", false)));
+            assert_eq!(0, query_epoch(deps.as_ref()).unwrap().epoch);
+
+            // one more transaction to look at the remaining node and one to assign the layers
+            assert_eq!(2, helpers::advance_epoch(&mut deps, mock_env()));
+            assert_eq!(1, query_epoch(deps.as_ref()).unwrap().epoch);
+            assert!(active_set_selection_read(&deps.storage)
+                .may_load()
                 .unwrap()
-        );
+                .is_none());
 
-        assert_eq!(
-            expected_delegation2,
-            mix_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator2".as_bytes())
+            let selected = query_active_set_paged(deps.as_ref(), None, None)
                 .unwrap()
-        );
+                .nodes
+                .into_iter()
+                .map(|bond| bond.mix_node.identity_key)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                vec![identities[0].clone(), identities[1].clone(), richest],
+                selected
+            );
+        }
 
-        assert_eq!(
-            expected_delegation3,
-            mix_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator3".as_bytes())
-                .unwrap()
-        );
+        #[test]
+        fn nodes_unbonded_during_selection_are_skipped() {
+            let mut deps = helpers::init_contract();
+            add_mixnode("alice", good_mixnode_bond(), &mut deps);
+            let bob_identity = add_mixnode("bob", good_mixnode_bond(), &mut deps);
 
-        assert_eq!(
-            vec![
-                attr("bond increase", expected_mix_reward),
-                attr(
-                    "total delegation increase",
-                    expected_delegation1_reward
-                        + expected_delegation2_reward
-                        + expected_delegation3_reward
-                ),
-            ],
-            res.attributes
-        );
+            // the first transaction looks at the stake of both nodes
+            let info = network_monitor(&deps);
+            try_advance_epoch(deps.as_mut(), mock_env(), info).unwrap();
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info("alice", &[])).unwrap();
 
-        // if the node was 0% up, nobody will get any rewards
-        let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_mixnode(deps.as_mut(), info, identity.clone(), 0).unwrap();
+            helpers::advance_epoch(&mut deps, mock_env());
+            assert_eq!(
+                vec![bob_identity],
+                active_set(&mut deps.storage).load().unwrap().nodes
+            );
+        }
 
-        assert_eq!(
-            expected_bond,
-            read_mixnode_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
-        );
+        #[test]
+        fn active_set_can_be_queried_in_pages() {
+            let mut deps = helpers::init_contract();
+            for owner in &["alice", "bob", "carol", "dave", "eve"] {
+                add_mixnode(owner, good_mixnode_bond(), &mut deps);
+            }
+            helpers::advance_epoch(&mut deps, mock_env());
 
-        assert_eq!(
-            expected_delegation1,
-            mix_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator1".as_bytes())
-                .unwrap()
-        );
+            let first_page = query_active_set_paged(deps.as_ref(), None, Some(2)).unwrap();
+            assert_eq!(2, first_page.nodes.len());
+            assert_eq!(Some("bobmixnode".to_string()), first_page.start_next_after);
 
-        assert_eq!(
-            expected_delegation2,
-            mix_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator2".as_bytes())
-                .unwrap()
-        );
+            let second_page =
+                query_active_set_paged(deps.as_ref(), first_page.start_next_after, Some(2))
+                    .unwrap();
+            assert_eq!("carolmixnode", second_page.nodes[0].identity());
+            assert_eq!("davemixnode", second_page.nodes[1].identity());
 
-        assert_eq!(
-            expected_delegation3,
-            mix_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator3".as_bytes())
-                .unwrap()
-        );
+            let last_page =
+                query_active_set_paged(deps.as_ref(), second_page.start_next_after, Some(2))
+                    .unwrap();
+            assert_eq!(1, last_page.nodes.len());
+            assert_eq!("evemixnode", last_page.nodes[0].identity());
 
-        assert_eq!(
-            vec![
-                attr("bond increase", Uint128(0)),
-                attr("total delegation increase", Uint128(0)),
-            ],
-            res.attributes
-        );
+            let empty_page =
+                query_active_set_paged(deps.as_ref(), last_page.start_next_after, Some(2)).unwrap();
+            assert!(empty_page.nodes.is_empty());
+            assert!(empty_page.start_next_after.is_none());
+        }
     }
 
     #[cfg(test)]
-    mod gateway_stake_delegation {
+    mod mixnode_families {
         use super::*;
-        use crate::storage::gateway_delegations_read;
-        use crate::support::tests::helpers::add_gateway;
+        use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+        use cosmwasm_std::OwnedDeps;
+
+        // identity keys derived from ed25519 secret keys filled with 0x01, 0x02 and 0x03 respectively
+        const HEAD_IDENTITY: &str = "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9";
+        const MEMBER_IDENTITY: &str = "9hSR6S7WPtxmTojgo6GG3k4yDPecgJY292j7xrsUGWBu";
+        const OTHER_IDENTITY: &str = "GyGKxMyg1p9SsHfm15MkNUu1u9TN2JtTspcdmrtGUdse";
+
+        // signature of the message letting `MEMBER_IDENTITY` join the family of `HEAD_IDENTITY`
+        // in the mock contract, made with the key of `HEAD_IDENTITY`
+        const HEAD_SIGNATURE_ON_MEMBER: &str = "5dACYUrMaKM9qUfD4YJ5gpogZJmqZh9FWj16ZSV6ZHoWk2QHTFevYKNyL5XtNBuNkLrrvbC24H1HeXgf47LKt7hp";
+        // the same message signed with the key of `OTHER_IDENTITY`
+        const OTHER_SIGNATURE_ON_MEMBER: &str = "5x5vcNmyXxXZtQ4eHUpPmKyuAqvbH9yb49xyjr9un9qcueYi12AWkSYSAazbFzB6FAh95kb7K4zppD4Fn1vpCfQx";
+        // signature of the same head and member made for the contract at "other-contract"
+        const HEAD_SIGNATURE_FOR_OTHER_CONTRACT: &str = "3n2fpR9xYSD1daJS8KuzCrZponaNwzgnPeJmvoGzHzLsbmF8aTSinvLvnd8v7SuMhcdvhn7s7phV6gJb9NRuTRPM";
+        // signature of just the `MEMBER_IDENTITY`, made with the key of `HEAD_IDENTITY`
+        const HEAD_SIGNATURE_ON_MEMBER_IDENTITY: &str = "3U2sKbkxg8F8A7eYwNam8vfWGEi8KazTBo3KC96oUhsVasnhrSXVuez1T8girYhVogXwdTYs13gmJz26TbL3hqa7";
+
+        fn bond_mixnode(
+            owner: &str,
+            identity: &str,
+            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        ) {
+            try_add_mixnode(
+                deps.as_mut(),
+                mock_info(owner, &good_mixnode_bond()),
+                MixNode {
+                    identity_key: identity.to_string(),
+                    ..helpers::mix_node_fixture()
+                },
+                10,
+            )
+            .unwrap();
+        }
+
+        fn family_of(
+            deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            identity: &str,
+        ) -> Option<IdentityKey> {
+            mixnodes_read(&deps.storage)
+                .load(identity.as_bytes())
+                .unwrap()
+                .family
+        }
 
         #[test]
-        fn fails_if_node_doesnt_exist() {
+        fn creating_family_requires_bonded_node() {
             let mut deps = helpers::init_contract();
             assert_eq!(
-                Err(ContractError::GatewayBondNotFound {
-                    identity: "non-existent-gateway-identity".into()
+                Err(ContractError::NoAssociatedMixNodeBond {
+                    owner: Addr::unchecked("alice")
                 }),
-                try_delegate_to_gateway(
-                    deps.as_mut(),
-                    mock_info("sender", &coins(123, DENOM)),
-                    "non-existent-gateway-identity".into()
-                )
+                try_create_family(deps.as_mut(), mock_info("alice", &[]))
             );
         }
 
         #[test]
-        fn succeeds_for_existing_node() {
+        fn creating_family_makes_node_its_head() {
             let mut deps = helpers::init_contract();
-            let gateway_owner = "bob";
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
+            bond_mixnode("alice", HEAD_IDENTITY, &mut deps);
+            assert_eq!(None, family_of(&deps, HEAD_IDENTITY));
 
-            let delegation = coin(123, DENOM);
-            assert!(try_delegate_to_gateway(
-                deps.as_mut(),
-                mock_info("sender", &vec![delegation.clone()]),
-                identity.clone()
-            )
-            .is_ok());
+            try_create_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
+            assert_eq!(
+                Some(HEAD_IDENTITY.to_string()),
+                family_of(&deps, HEAD_IDENTITY)
+            );
+
+            // and the family is exposed alongside the rest of the bond
+            let nodes = helpers::get_mix_nodes(&mut deps);
+            assert_eq!(Some(&HEAD_IDENTITY.to_string()), nodes[0].family());
 
             assert_eq!(
-                delegation.amount,
-                gateway_delegations_read(&deps.storage, &identity)
-                    .load(b"sender")
-                    .unwrap()
+                Err(ContractError::AlreadyInFamily {
+                    family_head: HEAD_IDENTITY.to_string()
+                }),
+                try_create_family(deps.as_mut(), mock_info("alice", &[]))
             );
+        }
+
+        #[test]
+        fn joining_family_requires_existing_family() {
+            let mut deps = helpers::init_contract();
+            bond_mixnode("alice", HEAD_IDENTITY, &mut deps);
+            bond_mixnode("bob", MEMBER_IDENTITY, &mut deps);
 
-            // node's "total_delegation" is increased
             assert_eq!(
-                delegation,
-                gateways_read(&deps.storage)
-                    .load(identity.as_bytes())
-                    .unwrap()
-                    .total_delegation
-            )
+                Err(ContractError::FamilyNotFound {
+                    family_head: HEAD_IDENTITY.to_string()
+                }),
+                try_join_family(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info("bob", &[]),
+                    HEAD_IDENTITY.to_string(),
+                    HEAD_SIGNATURE_ON_MEMBER.to_string()
+                )
+            );
         }
 
         #[test]
-        fn fails_if_node_unbonded() {
+        fn joining_family_requires_signature_of_family_head() {
             let mut deps = helpers::init_contract();
+            bond_mixnode("alice", HEAD_IDENTITY, &mut deps);
+            bond_mixnode("bob", MEMBER_IDENTITY, &mut deps);
+            try_create_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
+
+            // the head has to sign the full message bound to this contract
+            let bad_signatures = [
+                OTHER_SIGNATURE_ON_MEMBER,
+                HEAD_SIGNATURE_FOR_OTHER_CONTRACT,
+                HEAD_SIGNATURE_ON_MEMBER_IDENTITY,
+                "not-even-base58!",
+                "",
+            ];
+            for bad_signature in &bad_signatures {
+                assert_eq!(
+                    Err(ContractError::InvalidFamilySignature),
+                    try_join_family(
+                        deps.as_mut(),
+                        mock_env(),
+                        mock_info("bob", &[]),
+                        HEAD_IDENTITY.to_string(),
+                        bad_signature.to_string()
+                    )
+                );
+            }
+            assert_eq!(None, family_of(&deps, MEMBER_IDENTITY));
 
-            let gateway_owner = "bob";
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
-            try_remove_gateway(deps.as_mut(), mock_info(gateway_owner, &[])).unwrap();
+            try_join_family(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("bob", &[]),
+                HEAD_IDENTITY.to_string(),
+                HEAD_SIGNATURE_ON_MEMBER.to_string(),
+            )
+            .unwrap();
+            assert_eq!(
+                Some(HEAD_IDENTITY.to_string()),
+                family_of(&deps, MEMBER_IDENTITY)
+            );
+        }
+
+        #[test]
+        fn signature_is_only_valid_for_signed_member() {
+            let mut deps = helpers::init_contract();
+            bond_mixnode("alice", HEAD_IDENTITY, &mut deps);
+            bond_mixnode("carol", OTHER_IDENTITY, &mut deps);
+            try_create_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
 
             assert_eq!(
-                Err(ContractError::GatewayBondNotFound {
-                    identity: identity.clone()
-                }),
-                try_delegate_to_gateway(
+                Err(ContractError::InvalidFamilySignature),
+                try_join_family(
                     deps.as_mut(),
-                    mock_info("sender", &coins(123, DENOM)),
-                    identity
+                    mock_env(),
+                    mock_info("carol", &[]),
+                    HEAD_IDENTITY.to_string(),
+                    HEAD_SIGNATURE_ON_MEMBER.to_string()
                 )
             );
         }
 
         #[test]
-        fn succeeds_if_node_rebonded() {
+        fn leaving_family() {
             let mut deps = helpers::init_contract();
+            bond_mixnode("alice", HEAD_IDENTITY, &mut deps);
+            bond_mixnode("bob", MEMBER_IDENTITY, &mut deps);
 
-            let gateway_owner = "bob";
-            add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
-            try_remove_gateway(deps.as_mut(), mock_info(gateway_owner, &[])).unwrap();
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
-            let delegation = coin(123, DENOM);
+            assert_eq!(
+                Err(ContractError::NotInFamily),
+                try_leave_family(deps.as_mut(), mock_info("bob", &[]))
+            );
 
-            assert!(try_delegate_to_gateway(
+            try_create_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
+            try_join_family(
                 deps.as_mut(),
-                mock_info("sender", &vec![delegation.clone()]),
-                identity.clone()
+                mock_env(),
+                mock_info("bob", &[]),
+                HEAD_IDENTITY.to_string(),
+                HEAD_SIGNATURE_ON_MEMBER.to_string(),
             )
-            .is_ok());
+            .unwrap();
 
+            try_leave_family(deps.as_mut(), mock_info("bob", &[])).unwrap();
+            assert_eq!(None, family_of(&deps, MEMBER_IDENTITY));
             assert_eq!(
-                delegation.amount,
-                gateway_delegations_read(&deps.storage, &identity)
-                    .load(b"sender")
-                    .unwrap()
+                Some(HEAD_IDENTITY.to_string()),
+                family_of(&deps, HEAD_IDENTITY)
             );
 
-            // node's "total_delegation" is increased
+            // once the head leaves, nobody else can join the family anymore
+            try_leave_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
             assert_eq!(
-                delegation,
-                gateways_read(&deps.storage)
-                    .load(identity.as_bytes())
-                    .unwrap()
-                    .total_delegation
-            )
+                Err(ContractError::FamilyNotFound {
+                    family_head: HEAD_IDENTITY.to_string()
+                }),
+                try_join_family(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info("bob", &[]),
+                    HEAD_IDENTITY.to_string(),
+                    HEAD_SIGNATURE_ON_MEMBER.to_string()
+                )
+            );
         }
 
         #[test]
-        fn is_possible_for_an_already_delegated_node() {
+        fn family_is_dissolved_when_its_head_unbonds() {
             let mut deps = helpers::init_contract();
-            let gateway_owner = "bob";
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
+            bond_mixnode("alice", HEAD_IDENTITY, &mut deps);
+            bond_mixnode("bob", MEMBER_IDENTITY, &mut deps);
+            try_create_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
+            try_join_family(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("bob", &[]),
+                HEAD_IDENTITY.to_string(),
+                HEAD_SIGNATURE_ON_MEMBER.to_string(),
+            )
+            .unwrap();
 
-            let delegation1 = coin(100, DENOM);
-            let delegation2 = coin(50, DENOM);
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info("alice", &[])).unwrap();
+            assert_eq!(None, family_of(&deps, MEMBER_IDENTITY));
+            assert!(mix_family_members_read(&deps.storage, HEAD_IDENTITY)
+                .range(None, None, Order::Ascending)
+                .next()
+                .is_none());
 
-            try_delegate_to_gateway(
+            // so whoever bonds the identity of the head again starts with an empty family
+            bond_mixnode("mallory", HEAD_IDENTITY, &mut deps);
+            try_create_family(deps.as_mut(), mock_info("mallory", &[])).unwrap();
+            assert_eq!(None, family_of(&deps, MEMBER_IDENTITY));
+        }
+
+        #[test]
+        fn family_is_dissolved_when_its_head_leaves_it() {
+            let mut deps = helpers::init_contract();
+            bond_mixnode("alice", HEAD_IDENTITY, &mut deps);
+            bond_mixnode("bob", MEMBER_IDENTITY, &mut deps);
+            try_create_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
+            try_join_family(
                 deps.as_mut(),
-                mock_info("sender", &vec![delegation1.clone()]),
-                identity.clone(),
+                mock_env(),
+                mock_info("bob", &[]),
+                HEAD_IDENTITY.to_string(),
+                HEAD_SIGNATURE_ON_MEMBER.to_string(),
             )
             .unwrap();
 
-            try_delegate_to_gateway(
+            try_leave_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
+            assert_eq!(None, family_of(&deps, HEAD_IDENTITY));
+            assert_eq!(None, family_of(&deps, MEMBER_IDENTITY));
+
+            // recreating the family doesn't bring the old members back
+            try_create_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
+            assert_eq!(None, family_of(&deps, MEMBER_IDENTITY));
+        }
+
+        #[test]
+        fn unbonded_member_leaves_the_family() {
+            let mut deps = helpers::init_contract();
+            bond_mixnode("alice", HEAD_IDENTITY, &mut deps);
+            bond_mixnode("bob", MEMBER_IDENTITY, &mut deps);
+            try_create_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
+            try_join_family(
                 deps.as_mut(),
-                mock_info("sender", &vec![delegation2.clone()]),
-                identity.clone(),
+                mock_env(),
+                mock_info("bob", &[]),
+                HEAD_IDENTITY.to_string(),
+                HEAD_SIGNATURE_ON_MEMBER.to_string(),
             )
             .unwrap();
 
+            try_remove_mixnode(deps.as_mut(), mock_env(), mock_info("bob", &[])).unwrap();
+            assert!(mix_family_members_read(&deps.storage, HEAD_IDENTITY)
+                .may_load(MEMBER_IDENTITY.as_bytes())
+                .unwrap()
+                .is_none());
+
+            // and rebonding it doesn't restore the membership
+            bond_mixnode("bob", MEMBER_IDENTITY, &mut deps);
+            assert_eq!(None, family_of(&deps, MEMBER_IDENTITY));
+        }
+
+        #[test]
+        fn family_size_is_limited() {
+            let mut deps = helpers::init_contract();
+            bond_mixnode("alice", HEAD_IDENTITY, &mut deps);
+            bond_mixnode("bob", MEMBER_IDENTITY, &mut deps);
+            try_create_family(deps.as_mut(), mock_info("alice", &[])).unwrap();
+
+            for i in 0..MAX_FAMILY_MEMBERS {
+                mix_family_members(&mut deps.storage, HEAD_IDENTITY)
+                    .save(format!("member{}", i).as_bytes(), &())
+                    .unwrap();
+            }
+
             assert_eq!(
-                delegation1.amount + delegation2.amount,
-                gateway_delegations_read(&deps.storage, &identity)
-                    .load(b"sender")
-                    .unwrap()
+                Err(ContractError::FamilyFull {
+                    family_head: HEAD_IDENTITY.to_string(),
+                    max: MAX_FAMILY_MEMBERS,
+                }),
+                try_join_family(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info("bob", &[]),
+                    HEAD_IDENTITY.to_string(),
+                    HEAD_SIGNATURE_ON_MEMBER.to_string()
+                )
+            );
+        }
+    }
+
+    mod misbehaviour_reports {
+        use super::*;
+        use crate::queries::{
+            query_active_set_paged, query_jailed_mixnodes_paged, query_mixnode_misbehaviour_paged,
+            query_slashed_funds,
+        };
+        use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+        use cosmwasm_std::OwnedDeps;
+
+        fn network_monitor(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>) -> MessageInfo {
+            let state = config_read(&deps.storage).load().unwrap();
+            mock_info(state.network_monitor_address.as_ref(), &[])
+        }
+
+        fn set_slashing_percent(
+            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            percent: u64,
+        ) {
+            let mut state = config_read(&deps.storage).load().unwrap();
+            state.params.mixnode_slashing_fraction = Decimal::percent(percent);
+            config(&mut deps.storage).save(&state).unwrap();
+        }
+
+        fn set_epoch(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, epoch: u32) {
+            let mut set = read_active_set(&deps.storage).unwrap();
+            set.epoch = epoch;
+            active_set(&mut deps.storage).save(&set).unwrap();
+        }
+
+        fn report(
+            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            identity: &str,
+        ) -> Result<Response, ContractError> {
+            let info = network_monitor(deps);
+            try_report_misbehaviour(deps.as_mut(), mock_env(), info, identity.to_string(), 10)
+        }
+
+        fn is_jailed(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, identity: &str) -> bool {
+            mixnodes_read(&deps.storage)
+                .load(identity.as_bytes())
+                .unwrap()
+                .jailed
+        }
+
+        // reports the node in enough consecutive epochs to get it jailed
+        fn jail(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, identity: &str) {
+            for epoch in 1..=INITIAL_MIXNODE_JAIL_EPOCHS {
+                set_epoch(deps, epoch);
+                report(deps, identity).unwrap();
+            }
+            assert!(is_jailed(deps, identity));
+        }
+
+        const JAIL_PERIOD: u64 =
+            INITIAL_MIXNODE_JAIL_EPOCHS as u64 * INITIAL_DEFAULT_EPOCH_LENGTH as u64 * 60 * 60;
+
+        // environment from the moment a node jailed at `mock_env` can be unjailed
+        fn after_jail_period() -> Env {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(JAIL_PERIOD);
+            env
+        }
+
+        #[test]
+        fn can_only_be_done_by_network_monitor() {
+            let mut deps = helpers::init_contract();
+            let identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
+
+            assert_eq!(
+                Err(ContractError::Unauthorized),
+                try_report_misbehaviour(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info("alice", &[]),
+                    identity.clone(),
+                    10
+                )
+            );
+            assert!(report(&mut deps, &identity).is_ok());
+        }
+
+        #[test]
+        fn requires_uptime_below_threshold() {
+            let mut deps = helpers::init_contract();
+            let identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
+            let info = network_monitor(&deps);
+
+            assert_eq!(
+                Err(ContractError::UptimeAboveThreshold {
+                    uptime: INITIAL_MINIMUM_MIXNODE_UPTIME,
+                    minimum: INITIAL_MINIMUM_MIXNODE_UPTIME,
+                }),
+                try_report_misbehaviour(
+                    deps.as_mut(),
+                    mock_env(),
+                    info.clone(),
+                    identity.clone(),
+                    INITIAL_MINIMUM_MIXNODE_UPTIME
+                )
+            );
+            assert!(try_report_misbehaviour(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                identity,
+                INITIAL_MINIMUM_MIXNODE_UPTIME - 1
+            )
+            .is_ok());
+        }
+
+        #[test]
+        fn fails_for_nonexistent_node() {
+            let mut deps = helpers::init_contract();
+            assert_eq!(
+                Err(ContractError::MixNodeBondNotFound {
+                    identity: "foomp".to_string()
+                }),
+                report(&mut deps, "foomp")
             );
+        }
 
-            // node's "total_delegation" is sum of both
+        #[test]
+        fn can_only_be_done_once_per_epoch() {
+            let mut deps = helpers::init_contract();
+            let identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
+            set_epoch(&mut deps, 1);
+
+            report(&mut deps, &identity).unwrap();
             assert_eq!(
-                delegation1.amount + delegation2.amount,
-                gateways_read(&deps.storage)
-                    .load(identity.as_bytes())
-                    .unwrap()
-                    .total_delegation
-                    .amount
-            )
+                Err(ContractError::MisbehaviourAlreadyReported {
+                    identity: identity.clone(),
+                    epoch: 1
+                }),
+                report(&mut deps, &identity)
+            );
         }
 
         #[test]
-        fn is_disallowed_for_already_delegated_node_if_it_unbonded() {
+        fn jails_node_after_enough_consecutive_epochs() {
             let mut deps = helpers::init_contract();
+            let identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
 
-            let gateway_owner = "bob";
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
-
-            try_delegate_to_gateway(
-                deps.as_mut(),
-                mock_info("sender", &coins(100, DENOM)),
-                identity.clone(),
-            )
-            .unwrap();
+            for epoch in 1..INITIAL_MIXNODE_JAIL_EPOCHS {
+                set_epoch(&mut deps, epoch);
+                report(&mut deps, &identity).unwrap();
+                assert!(!is_jailed(&deps, &identity));
+            }
 
-            try_remove_gateway(deps.as_mut(), mock_info(gateway_owner, &[])).unwrap();
+            set_epoch(&mut deps, INITIAL_MIXNODE_JAIL_EPOCHS);
+            report(&mut deps, &identity).unwrap();
+            assert!(is_jailed(&deps, &identity));
 
+            // and jailed nodes can't be reported anymore
+            set_epoch(&mut deps, INITIAL_MIXNODE_JAIL_EPOCHS + 1);
             assert_eq!(
-                Err(ContractError::GatewayBondNotFound {
+                Err(ContractError::MixnodeJailed {
                     identity: identity.clone()
                 }),
-                try_delegate_to_gateway(
-                    deps.as_mut(),
-                    mock_info("sender", &coins(50, DENOM)),
-                    identity.clone()
-                )
+                report(&mut deps, &identity)
             );
         }
 
         #[test]
-        fn is_allowed_for_multiple_nodes() {
+        fn streak_is_reset_after_epoch_without_misbehaviour() {
             let mut deps = helpers::init_contract();
-            let gateway_owner1 = "bob";
-            let gateway_owner2 = "fred";
-            let identity1 = add_gateway(gateway_owner1, good_gateway_bond(), &mut deps);
-            let identity2 = add_gateway(gateway_owner2, good_gateway_bond(), &mut deps);
+            let identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
 
-            assert!(try_delegate_to_gateway(
-                deps.as_mut(),
-                mock_info("sender", &coins(123, DENOM)),
-                identity1.clone()
-            )
-            .is_ok());
-
-            assert!(try_delegate_to_gateway(
-                deps.as_mut(),
-                mock_info("sender", &coins(42, DENOM)),
-                identity2.clone()
-            )
-            .is_ok());
+            for epoch in 1..INITIAL_MIXNODE_JAIL_EPOCHS {
+                set_epoch(&mut deps, epoch);
+                report(&mut deps, &identity).unwrap();
+            }
 
-            assert_eq!(
-                123,
-                gateway_delegations_read(&deps.storage, &identity1)
-                    .load(b"sender")
-                    .unwrap()
-                    .u128()
-            );
+            // skip an epoch
+            set_epoch(&mut deps, INITIAL_MIXNODE_JAIL_EPOCHS + 1);
+            report(&mut deps, &identity).unwrap();
+            assert!(!is_jailed(&deps, &identity));
 
-            assert_eq!(
-                42,
-                gateway_delegations_read(&deps.storage, &identity2)
-                    .load(b"sender")
-                    .unwrap()
-                    .u128()
-            );
+            let history =
+                query_mixnode_misbehaviour_paged(deps.as_ref(), identity, None, None).unwrap();
+            assert_eq!(1, history.reports.last().unwrap().consecutive_epochs);
         }
 
         #[test]
-        fn is_allowed_by_multiple_users() {
+        fn jailing_slashes_configured_fraction_of_the_bond() {
             let mut deps = helpers::init_contract();
-            let gateway_owner = "bob";
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
+            let identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
+            let initial_bond = good_mixnode_bond()[0].amount;
 
-            let delegation1 = coin(123, DENOM);
-            let delegation2 = coin(234, DENOM);
+            // by default nothing is slashed
+            jail(&mut deps, &identity);
+            assert_eq!(
+                initial_bond,
+                read_mixnode_bond(&deps.storage, identity.as_bytes()).unwrap()
+            );
 
-            assert!(try_delegate_to_gateway(
-                deps.as_mut(),
-                mock_info("sender1", &vec![delegation1.clone()]),
-                identity.clone()
-            )
-            .is_ok());
+            let identity = add_mixnode("bob", good_mixnode_bond(), &mut deps);
+            set_slashing_percent(&mut deps, 10);
+            jail(&mut deps, &identity);
 
-            assert!(try_delegate_to_gateway(
-                deps.as_mut(),
-                mock_info("sender2", &vec![delegation2.clone()]),
-                identity.clone()
-            )
-            .is_ok());
+            let expected_slash = initial_bond * Decimal::percent(10);
+            assert_eq!(
+                initial_bond.checked_sub(expected_slash).unwrap(),
+                read_mixnode_bond(&deps.storage, identity.as_bytes()).unwrap()
+            );
 
-            // node's "total_delegation" is sum of both
+            let history =
+                query_mixnode_misbehaviour_paged(deps.as_ref(), identity, None, None).unwrap();
+            let last_report = history.reports.last().unwrap();
+            assert!(last_report.jailed);
+            assert_eq!(coin(expected_slash.u128(), DENOM), last_report.slashed);
             assert_eq!(
-                delegation1.amount + delegation2.amount,
-                gateways_read(&deps.storage)
-                    .load(identity.as_bytes())
-                    .unwrap()
-                    .total_delegation
-                    .amount
-            )
+                coin(expected_slash.u128(), DENOM),
+                query_slashed_funds(deps.as_ref()).unwrap()
+            );
         }
 
         #[test]
-        fn delegation_is_not_removed_if_node_unbonded() {
+        fn slashed_funds_can_be_withdrawn_by_the_owner() {
             let mut deps = helpers::init_contract();
+            set_slashing_percent(&mut deps, 10);
 
-            let gateway_owner = "bob";
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
+            assert_eq!(
+                Err(ContractError::NoSlashedFunds),
+                try_withdraw_slashed_funds(
+                    deps.as_mut(),
+                    mock_info("creator", &[]),
+                    "community_pool".to_string()
+                )
+            );
 
-            try_delegate_to_gateway(
+            let alice_identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
+            let bob_identity = add_mixnode("bob", good_mixnode_bond(), &mut deps);
+            jail(&mut deps, &alice_identity);
+            jail(&mut deps, &bob_identity);
+            let expected_total = good_mixnode_bond()[0].amount * Decimal::percent(20);
+            assert_eq!(
+                coin(expected_total.u128(), DENOM),
+                query_slashed_funds(deps.as_ref()).unwrap()
+            );
+
+            assert_eq!(
+                Err(ContractError::Unauthorized),
+                try_withdraw_slashed_funds(
+                    deps.as_mut(),
+                    mock_info("alice", &[]),
+                    "alice".to_string()
+                )
+            );
+
+            let res = try_withdraw_slashed_funds(
                 deps.as_mut(),
-                mock_info("sender", &coins(100, DENOM)),
-                identity.clone(),
+                mock_info("creator", &[]),
+                "community_pool".to_string(),
             )
             .unwrap();
-
-            try_remove_gateway(deps.as_mut(), mock_info(gateway_owner, &[])).unwrap();
-
             assert_eq!(
-                100,
-                gateway_delegations_read(&deps.storage, &identity)
-                    .load(b"sender")
-                    .unwrap()
-                    .u128()
+                vec![BankMsg::Send {
+                    to_address: "community_pool".to_string(),
+                    amount: coins(expected_total.u128(), DENOM),
+                }
+                .into()],
+                res.messages
             );
+            assert_eq!(coin(0, DENOM), query_slashed_funds(deps.as_ref()).unwrap());
         }
-    }
-
-    #[cfg(test)]
-    mod removing_gateway_stake_delegation {
-        use super::*;
-        use crate::storage::gateway_delegations_read;
-        use crate::support::tests::helpers::add_gateway;
 
         #[test]
-        fn fails_if_delegation_never_existed() {
+        fn jailed_nodes_are_excluded_from_the_network() {
             let mut deps = helpers::init_contract();
+            let alice_identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
+            let bob_identity = add_mixnode("bob", good_mixnode_bond(), &mut deps);
+            let info = network_monitor(&deps);
+            helpers::advance_epoch(&mut deps, mock_env());
+            assert_eq!(
+                2,
+                query_active_set_paged(deps.as_ref(), None, None)
+                    .unwrap()
+                    .nodes
+                    .len()
+            );
 
-            let gateway_owner = "bob";
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
+            jail(&mut deps, &alice_identity);
+
+            // immediately disappears from the list of nodes and the current active set
+            let nodes = helpers::get_mix_nodes(&mut deps);
+            assert_eq!(1, nodes.len());
+            assert_eq!(&bob_identity, nodes[0].identity());
+            let active_set = query_active_set_paged(deps.as_ref(), None, None).unwrap();
+            assert_eq!(1, active_set.nodes.len());
+
+            let jailed = query_jailed_mixnodes_paged(deps.as_ref(), None, None).unwrap();
+            assert_eq!(1, jailed.nodes.len());
+            assert_eq!(&alice_identity, jailed.nodes[0].identity());
+
+            // and is not selected for the next one
+            let mut env = mock_env();
+            env.block.time = env
+                .block
+                .time
+                .plus_seconds(INITIAL_DEFAULT_EPOCH_LENGTH as u64 * 60 * 60);
+            helpers::advance_epoch(&mut deps, env);
             assert_eq!(
-                Err(ContractError::NoGatewayDelegationFound {
-                    identity: identity.clone(),
-                    address: Addr::unchecked("sender"),
-                }),
-                try_remove_delegation_from_gateway(
-                    deps.as_mut(),
-                    mock_info("sender", &[]),
-                    identity,
-                )
+                vec![bob_identity],
+                active_set(&mut deps.storage).load().unwrap().nodes
+            );
+
+            // nor rewarded
+            let res = try_reward_mixnode(deps.as_mut(), info, alice_identity.clone(), 100).unwrap();
+            assert_eq!(
+                events::new_mixnode_not_rewarded_event(
+                    &alice_identity,
+                    100,
+                    events::NODE_JAILED_RESULT,
+                    read_current_epoch(&deps.storage).unwrap()
+                ),
+                res.attributes
             );
         }
 
         #[test]
-        fn succeeds_if_delegation_existed() {
+        fn unjailing_can_only_be_done_by_owner_of_jailed_node() {
             let mut deps = helpers::init_contract();
+            let identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
 
-            let gateway_owner = "bob";
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
-
-            try_delegate_to_gateway(
-                deps.as_mut(),
-                mock_info("sender", &coins(100, DENOM)),
-                identity.clone(),
-            )
-            .unwrap();
+            assert_eq!(
+                Err(ContractError::MixnodeNotJailed),
+                try_unjail_mixnode(deps.as_mut(), after_jail_period(), mock_info("alice", &[]))
+            );
 
+            jail(&mut deps, &identity);
             assert_eq!(
-                Ok(Response {
-                    submessages: vec![],
-                    messages: vec![BankMsg::Send {
-                        to_address: "sender".into(),
-                        amount: coins(100, DENOM),
-                    }
-                    .into()],
-                    attributes: Vec::new(),
-                    data: None,
+                Err(ContractError::NoAssociatedMixNodeBond {
+                    owner: Addr::unchecked("bob")
                 }),
-                try_remove_delegation_from_gateway(
-                    deps.as_mut(),
-                    mock_info("sender", &[]),
-                    identity.clone(),
-                )
+                try_unjail_mixnode(deps.as_mut(), after_jail_period(), mock_info("bob", &[]))
             );
 
-            assert!(gateway_delegations_read(&deps.storage, &identity)
-                .may_load(b"sender")
+            try_unjail_mixnode(deps.as_mut(), after_jail_period(), mock_info("alice", &[]))
+                .unwrap();
+            assert!(!is_jailed(&deps, &identity));
+            assert_eq!(1, helpers::get_mix_nodes(&mut deps).len());
+            assert!(query_jailed_mixnodes_paged(deps.as_ref(), None, None)
                 .unwrap()
-                .is_none());
+                .nodes
+                .is_empty());
 
-            // and total delegation is cleared
-            assert_eq!(
-                Uint128::zero(),
-                gateways_read(&deps.storage)
-                    .load(identity.as_bytes())
-                    .unwrap()
-                    .total_delegation
-                    .amount
-            )
+            // and the misbehaviour streak starts anew
+            set_epoch(&mut deps, INITIAL_MIXNODE_JAIL_EPOCHS + 1);
+            report(&mut deps, &identity).unwrap();
+            assert!(!is_jailed(&deps, &identity));
         }
 
         #[test]
-        fn succeeds_if_delegation_existed_even_if_node_unbonded() {
+        fn jailed_node_can_only_be_unjailed_after_the_jail_period() {
             let mut deps = helpers::init_contract();
+            let identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
+            jail(&mut deps, &identity);
 
-            let gateway_owner = "bob";
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
+            let jailed_until = after_jail_period().block.time.seconds();
+            assert_eq!(
+                jailed_until,
+                mixnodes_read(&deps.storage)
+                    .load(identity.as_bytes())
+                    .unwrap()
+                    .jailed_until
+            );
 
-            try_delegate_to_gateway(
-                deps.as_mut(),
-                mock_info("sender", &coins(100, DENOM)),
-                identity.clone(),
-            )
-            .unwrap();
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(JAIL_PERIOD - 1);
+            assert_eq!(
+                Err(ContractError::MixnodeStillJailed { jailed_until }),
+                try_unjail_mixnode(deps.as_mut(), env, mock_info("alice", &[]))
+            );
+            assert!(is_jailed(&deps, &identity));
+
+            try_unjail_mixnode(deps.as_mut(), after_jail_period(), mock_info("alice", &[]))
+                .unwrap();
+            assert!(!is_jailed(&deps, &identity));
+        }
 
-            try_remove_gateway(deps.as_mut(), mock_info(gateway_owner, &[])).unwrap();
+        #[test]
+        fn jailed_node_cannot_be_unbonded() {
+            let mut deps = helpers::init_contract();
+            let identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
+            jail(&mut deps, &identity);
 
+            // otherwise the same identity could be bonded again without being jailed
             assert_eq!(
-                Ok(Response {
-                    submessages: vec![],
-                    messages: vec![BankMsg::Send {
-                        to_address: "sender".into(),
-                        amount: coins(100, DENOM),
-                    }
-                    .into()],
-                    attributes: Vec::new(),
-                    data: None,
+                Err(ContractError::MixnodeJailed {
+                    identity: identity.clone()
                 }),
-                try_remove_delegation_from_gateway(
-                    deps.as_mut(),
-                    mock_info("sender", &[]),
-                    identity.clone(),
-                )
+                try_remove_mixnode(deps.as_mut(), mock_env(), mock_info("alice", &[]))
             );
+            assert!(is_jailed(&deps, &identity));
 
-            assert!(gateway_delegations_read(&deps.storage, &identity)
-                .may_load(b"sender")
-                .unwrap()
-                .is_none());
+            try_unjail_mixnode(deps.as_mut(), after_jail_period(), mock_info("alice", &[]))
+                .unwrap();
+            try_remove_mixnode(deps.as_mut(), after_jail_period(), mock_info("alice", &[]))
+                .unwrap();
         }
 
         #[test]
-        fn total_delegation_is_preserved_if_only_some_undelegate() {
+        fn jailed_nodes_are_queryable_in_pages() {
             let mut deps = helpers::init_contract();
-            let gateway_owner = "bob";
-            let identity = add_gateway(gateway_owner, good_gateway_bond(), &mut deps);
+            let mut jailed_identities = Vec::new();
+            for (i, owner) in ["alice", "bob", "carol", "dave", "eve"].iter().enumerate() {
+                let identity = add_mixnode(owner, good_mixnode_bond(), &mut deps);
+                // every other node gets jailed
+                if i % 2 == 0 {
+                    jail(&mut deps, &identity);
+                    jailed_identities.push(identity);
+                }
+            }
 
-            let delegation1 = coin(123, DENOM);
-            let delegation2 = coin(234, DENOM);
+            let page1 = query_jailed_mixnodes_paged(deps.as_ref(), None, Some(2)).unwrap();
+            assert_eq!(2, page1.nodes.len());
+            let page2 = query_jailed_mixnodes_paged(deps.as_ref(), page1.start_next_after, Some(2))
+                .unwrap();
+            assert_eq!(1, page2.nodes.len());
+
+            let queried: Vec<_> = page1
+                .nodes
+                .iter()
+                .chain(page2.nodes.iter())
+                .map(|bond| bond.identity().clone())
+                .collect();
+            assert_eq!(jailed_identities, queried);
+        }
 
-            assert!(try_delegate_to_gateway(
-                deps.as_mut(),
-                mock_info("sender1", &vec![delegation1.clone()]),
-                identity.clone()
+        #[test]
+        fn history_is_queryable_in_pages() {
+            let mut deps = helpers::init_contract();
+            let identity = add_mixnode("alice", good_mixnode_bond(), &mut deps);
+
+            // report every other epoch so that the node never gets jailed
+            for epoch in &[1, 3, 5, 7] {
+                set_epoch(&mut deps, *epoch);
+                report(&mut deps, &identity).unwrap();
+            }
+
+            let page1 =
+                query_mixnode_misbehaviour_paged(deps.as_ref(), identity.clone(), None, Some(3))
+                    .unwrap();
+            assert_eq!(
+                vec![1, 3, 5],
+                page1.reports.iter().map(|r| r.epoch).collect::<Vec<_>>()
+            );
+            assert_eq!(Some(5), page1.start_next_after);
+
+            let page2 = query_mixnode_misbehaviour_paged(
+                deps.as_ref(),
+                identity,
+                page1.start_next_after,
+                None,
             )
-            .is_ok());
+            .unwrap();
+            assert_eq!(
+                vec![7],
+                page2.reports.iter().map(|r| r.epoch).collect::<Vec<_>>()
+            );
+            assert_eq!(10, page2.reports[0].uptime);
+        }
+    }
 
-            assert!(try_delegate_to_gateway(
+    mod state_params_governance {
+        use super::*;
+        use crate::contract::{INITIAL_PROPOSAL_EXECUTION_PERIOD, INITIAL_PROPOSAL_TIMELOCK};
+        use crate::queries::{query_proposal, query_proposal_votes_paged, query_proposals_paged};
+        use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+        use cosmwasm_std::OwnedDeps;
+        use mixnet_contract::ProposalVote;
+
+        fn after_timelock() -> Env {
+            let mut env = mock_env();
+            env.block.time = env
+                .block
+                .time
+                .plus_seconds(INITIAL_PROPOSAL_TIMELOCK as u64 * 60 * 60);
+            env
+        }
+
+        fn propose_governance(
+            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            proposer: &str,
+            governance_config: GovernanceConfig,
+        ) -> Result<Response, ContractError> {
+            try_propose_governance_config(
                 deps.as_mut(),
-                mock_info("sender2", &vec![delegation2.clone()]),
-                identity.clone()
+                mock_env(),
+                mock_info(proposer, &[]),
+                governance_config,
+                "new governance".to_string(),
             )
-            .is_ok());
+        }
 
-            // sender1 undelegates
-            try_remove_delegation_from_gateway(
+        // governance where 2 out of the 3 members have to approve a proposal. It's proposed by
+        // the owner, the only initial member, so it passes straight away
+        fn governed_contract() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+            let mut deps = helpers::init_contract();
+            let governance_config = GovernanceConfig {
+                members: vec![
+                    Addr::unchecked("alice"),
+                    Addr::unchecked("bob"),
+                    Addr::unchecked("carol"),
+                ],
+                threshold: 2,
+                voting_period: 48,
+                timelock: INITIAL_PROPOSAL_TIMELOCK,
+                execution_period: INITIAL_PROPOSAL_EXECUTION_PERIOD,
+            };
+            let res = propose_governance(&mut deps, "creator", governance_config).unwrap();
+            let proposal_id = res.attributes[1].value.parse().unwrap();
+            try_execute_proposal(deps.as_mut(), after_timelock(), proposal_id).unwrap();
+            deps
+        }
+
+        fn new_params(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>) -> StateParams {
+            let mut params = read_state_params(&deps.storage);
+            params.mixnode_active_set_size = 42;
+            params
+        }
+
+        fn propose(
+            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            proposer: &str,
+        ) -> ProposalId {
+            let params = new_params(deps);
+            let res = try_propose_state_params(
                 deps.as_mut(),
-                mock_info("sender1", &[]),
-                identity.clone(),
+                mock_env(),
+                mock_info(proposer, &[]),
+                params,
+                "bigger active set".to_string(),
             )
             .unwrap();
+            res.attributes[1].value.parse().unwrap()
+        }
 
-            // but total delegation should still equal to what sender2 sent
-            // node's "total_delegation" is sum of both
-            assert_eq!(
-                delegation2,
-                gateways_read(&deps.storage)
-                    .load(identity.as_bytes())
-                    .unwrap()
-                    .total_delegation
+        fn vote(
+            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            voter: &str,
+            proposal_id: ProposalId,
+            vote: VoteOption,
+        ) -> Result<Response, ContractError> {
+            try_vote_on_proposal(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(voter, &[]),
+                proposal_id,
+                vote,
             )
         }
-    }
 
-    #[test]
-    fn delegators_on_gateway_reward_rate() {
-        let mut deps = helpers::init_contract();
-        let current_state = config(deps.as_mut().storage).load().unwrap();
-        let network_monitor_address = current_state.network_monitor_address;
+        fn status(
+            deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            proposal_id: ProposalId,
+        ) -> ProposalStatus {
+            query_proposal(deps.as_ref(), proposal_id).unwrap().status
+        }
 
-        let initial_gateway_bond = 100_000000;
-        let initial_delegation1 = 50000; // will see single digits rewards
-        let initial_delegation2 = 100; // won't see any rewards due to such a small delegation
-        let initial_delegation3 = 100000_000000; // will see big proper rewards
+        #[test]
+        fn governance_config_can_only_be_changed_through_proposals_with_valid_values() {
+            let mut deps = helpers::init_contract();
+            let valid_config = GovernanceConfig {
+                members: vec![
+                    Addr::unchecked("alice"),
+                    Addr::unchecked("bob"),
+                    Addr::unchecked("carol"),
+                ],
+                threshold: 2,
+                voting_period: 48,
+                timelock: 24,
+                execution_period: 24,
+            };
 
-        let node_owner = "node-owner";
-        let identity = add_gateway(node_owner, good_gateway_bond(), &mut deps);
+            assert_eq!(
+                Err(ContractError::NotGovernanceMember),
+                propose_governance(&mut deps, "alice", valid_config.clone())
+            );
 
-        gateway_delegations(&mut deps.storage, &identity)
-            .save(b"delegator1", &Uint128(initial_delegation1))
-            .unwrap();
-        gateway_delegations(&mut deps.storage, &identity)
-            .save(b"delegator2", &Uint128(initial_delegation2))
-            .unwrap();
-        gateway_delegations(&mut deps.storage, &identity)
-            .save(b"delegator3", &Uint128(initial_delegation3))
-            .unwrap();
+            let mut no_members = valid_config.clone();
+            no_members.members = Vec::new();
+            assert_eq!(
+                Err(ContractError::NoGovernanceMembers),
+                propose_governance(&mut deps, "creator", no_members)
+            );
 
-        let bond_reward = read_gateway_epoch_bond_reward_rate(deps.as_ref().storage);
-        let delegation_reward = read_gateway_epoch_delegation_reward_rate(deps.as_ref().storage);
+            let mut duplicate_member = valid_config.clone();
+            duplicate_member.members.push(Addr::unchecked("alice"));
+            assert_eq!(
+                Err(ContractError::DuplicateGovernanceMember {
+                    member: Addr::unchecked("alice")
+                }),
+                propose_governance(&mut deps, "creator", duplicate_member)
+            );
 
-        // the node's bond and delegations are correctly increased and scaled by uptime
-        // if node was 100% up, it will get full epoch reward
-        let expected_gateway_reward = Uint128(initial_gateway_bond) * bond_reward;
-        let expected_delegation1_reward = Uint128(initial_delegation1) * delegation_reward;
-        let expected_delegation2_reward = Uint128(initial_delegation2) * delegation_reward;
-        let expected_delegation3_reward = Uint128(initial_delegation3) * delegation_reward;
+            // the threshold has to be a majority of the members
+            for threshold in &[0, 1, 4] {
+                let mut invalid_threshold = valid_config.clone();
+                invalid_threshold.threshold = *threshold;
+                assert_eq!(
+                    Err(ContractError::InvalidGovernanceThreshold {
+                        threshold: *threshold,
+                        members: 3
+                    }),
+                    propose_governance(&mut deps, "creator", invalid_threshold)
+                );
+            }
 
-        let expected_bond = expected_gateway_reward + Uint128(initial_gateway_bond);
-        let expected_delegation1 = expected_delegation1_reward + Uint128(initial_delegation1);
-        let expected_delegation2 = expected_delegation2_reward + Uint128(initial_delegation2);
-        let expected_delegation3 = expected_delegation3_reward + Uint128(initial_delegation3);
+            let mut no_timelock = valid_config.clone();
+            no_timelock.timelock = 0;
+            assert_eq!(
+                Err(ContractError::ZeroTimelock),
+                propose_governance(&mut deps, "creator", no_timelock)
+            );
 
-        let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_gateway(deps.as_mut(), info, identity.clone(), 100).unwrap();
+            let mut no_execution_period = valid_config.clone();
+            no_execution_period.execution_period = 0;
+            assert_eq!(
+                Err(ContractError::ZeroExecutionPeriod),
+                propose_governance(&mut deps, "creator", no_execution_period)
+            );
 
-        assert_eq!(
-            expected_bond,
-            read_gateway_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
-        );
+            // even though the owner is the only member, the change has to wait for the timelock
+            let res = propose_governance(&mut deps, "creator", valid_config.clone()).unwrap();
+            let proposal_id = res.attributes[1].value.parse().unwrap();
+            assert_eq!(ProposalStatus::Passed, status(&deps, proposal_id));
+            let executable_at =
+                mock_env().block.time.seconds() + INITIAL_PROPOSAL_TIMELOCK as u64 * 60 * 60;
+            assert_eq!(
+                Err(ContractError::ProposalTimelocked {
+                    proposal_id,
+                    executable_at
+                }),
+                try_execute_proposal(deps.as_mut(), mock_env(), proposal_id)
+            );
+            assert_ne!(valid_config, governance_read(&deps.storage).load().unwrap());
 
-        assert_eq!(
-            expected_delegation1,
-            gateway_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator1".as_bytes())
-                .unwrap()
-        );
+            let res = try_execute_proposal(deps.as_mut(), after_timelock(), proposal_id).unwrap();
+            let mut expected_attributes =
+                events::new_governance_config_update_event(&valid_config).unwrap();
+            expected_attributes.extend(events::new_proposal_execution_event(proposal_id));
+            assert_eq!(expected_attributes, res.attributes);
+            assert_eq!(valid_config, governance_read(&deps.storage).load().unwrap());
 
-        assert_eq!(
-            expected_delegation2,
-            gateway_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator2".as_bytes())
-                .unwrap()
-        );
+            // and the owner is no longer part of the governance
+            assert_eq!(
+                Err(ContractError::NotGovernanceMember),
+                propose_governance(&mut deps, "creator", valid_config)
+            );
+        }
 
-        assert_eq!(
-            expected_delegation3,
-            gateway_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator3".as_bytes())
-                .unwrap()
-        );
+        #[test]
+        fn open_proposals_keep_the_governance_they_were_submitted_with() {
+            let mut deps = governed_contract();
+            let params_proposal = propose(&mut deps, "alice");
+
+            // a new member joins and all of them have to approve proposals from now on
+            let mut new_config = governance_read(&deps.storage).load().unwrap();
+            new_config.members.push(Addr::unchecked("dave"));
+            new_config.threshold = 4;
+            let res = propose_governance(&mut deps, "alice", new_config.clone()).unwrap();
+            let governance_proposal = res.attributes[1].value.parse().unwrap();
+            vote(&mut deps, "bob", governance_proposal, VoteOption::Yes).unwrap();
+            try_execute_proposal(deps.as_mut(), after_timelock(), governance_proposal).unwrap();
+            assert_eq!(new_config, governance_read(&deps.storage).load().unwrap());
+
+            // the new member can't vote on the proposal submitted before it joined
+            assert_eq!(
+                Err(ContractError::NotGovernanceMember),
+                try_vote_on_proposal(
+                    deps.as_mut(),
+                    after_timelock(),
+                    mock_info("dave", &[]),
+                    params_proposal,
+                    VoteOption::Yes
+                )
+            );
 
-        assert_eq!(
-            vec![
-                attr("bond increase", expected_gateway_reward),
-                attr(
-                    "total delegation increase",
-                    expected_delegation1_reward
-                        + expected_delegation2_reward
-                        + expected_delegation3_reward
-                ),
-            ],
-            res.attributes
-        );
+            // and the proposal still passes with the approval of 2 out of the 3 original members
+            try_vote_on_proposal(
+                deps.as_mut(),
+                after_timelock(),
+                mock_info("bob", &[]),
+                params_proposal,
+                VoteOption::Yes,
+            )
+            .unwrap();
+            assert_eq!(ProposalStatus::Passed, status(&deps, params_proposal));
+            let proposal = query_proposal(deps.as_ref(), params_proposal).unwrap();
+            assert_eq!(3, proposal.governance.members.len());
+            assert_eq!(2, proposal.governance.threshold);
+
+            // while the new proposals require everyone's approval
+            let new_proposal = propose(&mut deps, "dave");
+            vote(&mut deps, "alice", new_proposal, VoteOption::Yes).unwrap();
+            vote(&mut deps, "bob", new_proposal, VoteOption::Yes).unwrap();
+            assert_eq!(ProposalStatus::Open, status(&deps, new_proposal));
+        }
 
-        // if node was 20% up, it will get 1/5th of epoch reward
-        let scaled_bond_reward = scale_reward_by_uptime(bond_reward, 20).unwrap();
-        let scaled_delegation_reward = scale_reward_by_uptime(delegation_reward, 20).unwrap();
+        #[test]
+        fn only_members_can_propose_valid_params() {
+            let mut deps = governed_contract();
+            let params = new_params(&deps);
 
-        let expected_gateway_reward = expected_bond * scaled_bond_reward;
-        let expected_delegation1_reward = expected_delegation1 * scaled_delegation_reward;
-        let expected_delegation2_reward = expected_delegation2 * scaled_delegation_reward;
-        let expected_delegation3_reward = expected_delegation3 * scaled_delegation_reward;
+            // the owner is no longer a member of the governance
+            assert_eq!(
+                Err(ContractError::NotGovernanceMember),
+                try_propose_state_params(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info("creator", &[]),
+                    params.clone(),
+                    "bigger active set".to_string(),
+                )
+            );
 
-        let expected_bond = expected_gateway_reward + expected_bond;
-        let expected_delegation1 = expected_delegation1_reward + expected_delegation1;
-        let expected_delegation2 = expected_delegation2_reward + expected_delegation2;
-        let expected_delegation3 = expected_delegation3_reward + expected_delegation3;
+            let mut invalid_params = params;
+            invalid_params.mixnode_jail_epochs = 0;
+            assert_eq!(
+                Err(ContractError::ZeroJailEpochs),
+                try_propose_state_params(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info("alice", &[]),
+                    invalid_params,
+                    "never jail anyone".to_string(),
+                )
+            );
+        }
 
-        let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_gateway(deps.as_mut(), info, identity.clone(), 20).unwrap();
+        #[test]
+        fn proposal_is_applied_after_reaching_threshold_and_timelock() {
+            let mut deps = governed_contract();
+            let params = new_params(&deps);
 
-        assert_eq!(
-            expected_bond,
-            read_gateway_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
-        );
+            // proposing counts as approval
+            let proposal_id = propose(&mut deps, "alice");
+            let proposal = query_proposal(deps.as_ref(), proposal_id).unwrap();
+            assert_eq!(ProposalStatus::Open, proposal.status);
+            assert_eq!(1, proposal.yes_votes);
 
-        assert_eq!(
-            expected_delegation1,
-            gateway_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator1".as_bytes())
-                .unwrap()
-        );
+            assert_eq!(
+                Err(ContractError::AlreadyVoted {
+                    proposal_id,
+                    voter: Addr::unchecked("alice")
+                }),
+                vote(&mut deps, "alice", proposal_id, VoteOption::Yes)
+            );
+            assert_eq!(
+                Err(ContractError::NotGovernanceMember),
+                vote(&mut deps, "mallory", proposal_id, VoteOption::Yes)
+            );
 
-        assert_eq!(
-            expected_delegation2,
-            gateway_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator2".as_bytes())
-                .unwrap()
-        );
+            // can't be executed before it passes
+            assert_eq!(
+                Err(ContractError::ProposalNotPassed { proposal_id }),
+                try_execute_proposal(deps.as_mut(), mock_env(), proposal_id)
+            );
 
-        assert_eq!(
-            expected_delegation3,
-            gateway_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator3".as_bytes())
-                .unwrap()
-        );
+            vote(&mut deps, "bob", proposal_id, VoteOption::Yes).unwrap();
+            let proposal = query_proposal(deps.as_ref(), proposal_id).unwrap();
+            assert_eq!(ProposalStatus::Passed, proposal.status);
+            let executable_at =
+                mock_env().block.time.seconds() + INITIAL_PROPOSAL_TIMELOCK as u64 * 60 * 60;
+            assert_eq!(Some(executable_at), proposal.executable_at);
+            assert_eq!(
+                Some(executable_at + INITIAL_PROPOSAL_EXECUTION_PERIOD as u64 * 60 * 60),
+                proposal.expires_at
+            );
 
-        assert_eq!(
-            vec![
-                attr("bond increase", expected_gateway_reward),
-                attr(
-                    "total delegation increase",
-                    expected_delegation1_reward
-                        + expected_delegation2_reward
-                        + expected_delegation3_reward
-                ),
-            ],
-            res.attributes
-        );
+            // no more voting once it passed
+            assert_eq!(
+                Err(ContractError::ProposalNotOpen { proposal_id }),
+                vote(&mut deps, "carol", proposal_id, VoteOption::No)
+            );
 
-        // if the node was 0% up, nobody will get any rewards
-        let info = mock_info(network_monitor_address.as_ref(), &[]);
-        let res = try_reward_gateway(deps.as_mut(), info, identity.clone(), 0).unwrap();
+            // but it has to wait for the timelock
+            assert_eq!(
+                Err(ContractError::ProposalTimelocked {
+                    proposal_id,
+                    executable_at
+                }),
+                try_execute_proposal(deps.as_mut(), mock_env(), proposal_id)
+            );
+            assert_ne!(params, read_state_params(&deps.storage));
 
-        assert_eq!(
-            expected_bond,
-            read_gateway_bond(deps.as_ref().storage, identity.as_bytes()).unwrap()
-        );
+            let mut env = mock_env();
+            env.block.time = env
+                .block
+                .time
+                .plus_seconds(INITIAL_PROPOSAL_TIMELOCK as u64 * 60 * 60);
+            let res = try_execute_proposal(deps.as_mut(), env.clone(), proposal_id).unwrap();
 
-        assert_eq!(
-            expected_delegation1,
-            gateway_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator1".as_bytes())
-                .unwrap()
-        );
+            let mut expected_attributes = events::new_params_update_event(&params, 0).unwrap();
+            expected_attributes.extend(events::new_proposal_execution_event(proposal_id));
+            assert_eq!(expected_attributes, res.attributes);
+            assert_eq!(params, read_state_params(&deps.storage));
+            assert_eq!(ProposalStatus::Executed, status(&deps, proposal_id));
 
-        assert_eq!(
-            expected_delegation2,
-            gateway_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator2".as_bytes())
-                .unwrap()
-        );
+            assert_eq!(
+                Err(ContractError::ProposalFinalised { proposal_id }),
+                try_execute_proposal(deps.as_mut(), env, proposal_id)
+            );
+        }
 
-        assert_eq!(
-            expected_delegation3,
-            gateway_delegations_read(deps.as_ref().storage, &identity)
-                .load("delegator3".as_bytes())
+        #[test]
+        fn passed_proposal_cannot_be_executed_after_its_deadline() {
+            let mut deps = governed_contract();
+            let proposal_id = propose(&mut deps, "alice");
+            vote(&mut deps, "bob", proposal_id, VoteOption::Yes).unwrap();
+            let expired_at = query_proposal(deps.as_ref(), proposal_id)
                 .unwrap()
-        );
+                .expires_at
+                .unwrap();
 
-        assert_eq!(
-            vec![
-                attr("bond increase", Uint128(0)),
-                attr("total delegation increase", Uint128(0)),
-            ],
-            res.attributes
-        );
-    }
+            let mut env = mock_env();
+            let until_deadline = expired_at - env.block.time.seconds();
+            env.block.time = env.block.time.plus_seconds(until_deadline);
+            assert_eq!(
+                Err(ContractError::ProposalExpired {
+                    proposal_id,
+                    expired_at
+                }),
+                try_execute_proposal(deps.as_mut(), env, proposal_id)
+            );
+            assert_ne!(new_params(&deps), read_state_params(&deps.storage));
 
-    #[cfg(test)]
-    mod finding_old_delegations {
-        use super::*;
+            // one second earlier it still could have been executed
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(until_deadline - 1);
+            try_execute_proposal(deps.as_mut(), env, proposal_id).unwrap();
+            assert_eq!(ProposalStatus::Executed, status(&deps, proposal_id));
+        }
 
         #[test]
-        fn when_there_werent_any() {
-            let deps = helpers::init_contract();
+        fn proposal_is_rejected_once_threshold_is_unreachable() {
+            let mut deps = governed_contract();
+            let proposal_id = propose(&mut deps, "alice");
 
-            let node_identity: IdentityKey = "nodeidentity".into();
+            vote(&mut deps, "bob", proposal_id, VoteOption::No).unwrap();
+            assert_eq!(ProposalStatus::Open, status(&deps, proposal_id));
 
-            let read_bucket = mix_delegations_read(&deps.storage, &node_identity);
-            let old_delegations = find_old_delegations(read_bucket).unwrap();
+            vote(&mut deps, "carol", proposal_id, VoteOption::No).unwrap();
+            assert_eq!(ProposalStatus::Rejected, status(&deps, proposal_id));
 
-            assert_eq!(Coin::new(0, DENOM), old_delegations);
+            assert_eq!(
+                Err(ContractError::ProposalNotPassed { proposal_id }),
+                try_execute_proposal(deps.as_mut(), mock_env(), proposal_id)
+            );
         }
 
         #[test]
-        fn when_some_existed() {
-            let num_delegations = vec![
-                1,
-                5,
-                OLD_DELEGATIONS_CHUNK_SIZE - 1,
-                OLD_DELEGATIONS_CHUNK_SIZE,
-                OLD_DELEGATIONS_CHUNK_SIZE + 1,
-                OLD_DELEGATIONS_CHUNK_SIZE * 3,
-                OLD_DELEGATIONS_CHUNK_SIZE * 3 + 1,
-            ];
+        fn votes_are_not_accepted_after_voting_period() {
+            let mut deps = governed_contract();
+            let proposal_id = propose(&mut deps, "alice");
 
-            for total_delegations in num_delegations {
-                let mut deps = helpers::init_contract();
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(48 * 60 * 60);
+            assert_eq!(
+                Err(ContractError::ProposalVotingEnded { proposal_id }),
+                try_vote_on_proposal(
+                    deps.as_mut(),
+                    env,
+                    mock_info("bob", &[]),
+                    proposal_id,
+                    VoteOption::Yes
+                )
+            );
+        }
 
-                let node_identity: IdentityKey = "nodeidentity".into();
+        #[test]
+        fn proposal_can_be_cancelled_by_proposer_or_vetoed_by_owner() {
+            let mut deps = governed_contract();
+            let first = propose(&mut deps, "alice");
+            let second = propose(&mut deps, "alice");
+            vote(&mut deps, "bob", second, VoteOption::Yes).unwrap();
 
-                // delegate some stake
-                let mut write_bucket = mix_delegations(&mut deps.storage, &node_identity);
-                for i in 1..=total_delegations {
-                    let delegator = Addr::unchecked(format!("delegator{}", i));
-                    let delegation = Uint128(i as u128);
-                    write_bucket
-                        .save(delegator.as_bytes(), &delegation)
-                        .unwrap();
-                }
+            assert_eq!(
+                Err(ContractError::Unauthorized),
+                try_cancel_proposal(deps.as_mut(), mock_info("bob", &[]), first)
+            );
 
-                let read_bucket = mix_delegations_read(&deps.storage, &node_identity);
-                let old_delegations = find_old_delegations(read_bucket).unwrap();
+            try_cancel_proposal(deps.as_mut(), mock_info("alice", &[]), first).unwrap();
+            assert_eq!(ProposalStatus::Cancelled, status(&deps, first));
+            assert_eq!(
+                Err(ContractError::ProposalFinalised { proposal_id: first }),
+                try_cancel_proposal(deps.as_mut(), mock_info("alice", &[]), first)
+            );
 
-                let total_delegation = (1..=total_delegations as u128).into_iter().sum();
-                assert_eq!(Coin::new(total_delegation, DENOM), old_delegations);
-            }
+            // even though the owner is not a member, it can still veto the passed proposal
+            try_cancel_proposal(deps.as_mut(), mock_info("creator", &[]), second).unwrap();
+            assert_eq!(ProposalStatus::Cancelled, status(&deps, second));
+
+            let mut env = mock_env();
+            env.block.time = env
+                .block
+                .time
+                .plus_seconds(INITIAL_PROPOSAL_TIMELOCK as u64 * 60 * 60);
+            assert_eq!(
+                Err(ContractError::ProposalFinalised {
+                    proposal_id: second
+                }),
+                try_execute_proposal(deps.as_mut(), env, second)
+            );
         }
-    }
 
-    #[test]
-    fn choose_layer_mix_node() {
-        let mut deps = helpers::init_contract();
-        for owner in ["alice", "bob"] {
-            try_add_mixnode(
-                deps.as_mut(),
-                mock_info(owner, &good_mixnode_bond()),
-                MixNode {
-                    identity_key: owner.to_string(),
-                    ..helpers::mix_node_fixture()
-                },
+        #[test]
+        fn proposals_and_votes_are_queryable() {
+            let mut deps = governed_contract();
+            let first = propose(&mut deps, "alice");
+            let second = propose(&mut deps, "bob");
+            vote(&mut deps, "carol", first, VoteOption::No).unwrap();
+
+            let page = query_proposals_paged(deps.as_ref(), None, Some(1)).unwrap();
+            assert_eq!(
+                vec![first],
+                page.proposals.iter().map(|p| p.id).collect::<Vec<_>>()
+            );
+            let page = query_proposals_paged(deps.as_ref(), page.start_next_after, None).unwrap();
+            assert_eq!(
+                vec![second],
+                page.proposals.iter().map(|p| p.id).collect::<Vec<_>>()
+            );
+
+            let votes = query_proposal_votes_paged(deps.as_ref(), first, None, None).unwrap();
+            assert_eq!(
+                vec![
+                    ProposalVote {
+                        voter: Addr::unchecked("alice"),
+                        vote: VoteOption::Yes
+                    },
+                    ProposalVote {
+                        voter: Addr::unchecked("carol"),
+                        vote: VoteOption::No
+                    },
+                ],
+                votes.votes
+            );
+
+            let votes = query_proposal_votes_paged(
+                deps.as_ref(),
+                first,
+                Some(Addr::unchecked("alice")),
+                None,
             )
             .unwrap();
+            assert_eq!(1, votes.votes.len());
+            assert_eq!(Addr::unchecked("carol"), votes.votes[0].voter);
+
+            assert_eq!(
+                Err(ContractError::ProposalNotFound { proposal_id: 42 }),
+                query_proposal(deps.as_ref(), 42)
+            );
         }
-        let bonded_mix_nodes = helpers::get_mix_nodes(&mut deps);
-        let alice_node = bonded_mix_nodes.get(0).unwrap().clone();
-        let bob_node = bonded_mix_nodes.get(1).unwrap().clone();
-        assert_eq!(alice_node.mix_node.identity_key, "alice");
-        assert_eq!(alice_node.layer, Layer::One);
-        assert_eq!(bob_node.mix_node.identity_key, "bob");
-        assert_eq!(bob_node.layer, Layer::Two);
     }
 }
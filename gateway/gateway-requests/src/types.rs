@@ -74,16 +74,16 @@ impl fmt::Display for GatewayRequestsError {
             TooShortRequest => write!(f, "the request is too short"),
             InvalidMac => write!(f, "provided MAC is invalid"),
             IncorrectlyEncodedAddress => write!(f, "address field was incorrectly encoded"),
-            RequestOfInvalidSize(actual) =>
-                write!(
+            RequestOfInvalidSize(actual) => write!(
                 f,
-                "received request had invalid size. (actual: {}, but expected one of: {} (ACK), {} (REGULAR), {} (EXTENDED))",
-                actual, PacketSize::AckPacket.size(), PacketSize::RegularPacket.size(), PacketSize::ExtendedPacket.size()
+                "received request had invalid size. (actual: {}, but expected one of: {:?})",
+                actual,
+                PacketSize::all().map(PacketSize::size).collect::<Vec<_>>()
             ),
             MalformedSphinxPacket => write!(f, "received sphinx packet was malformed"),
             MalformedEncryption => write!(f, "the received encrypted data was malformed"),
             InvalidPacketMode => write!(f, "provided packet mode is invalid"),
-            InvalidMixPacket(err) => write!(f, "provided mix packet was malformed - {}", err)
+            InvalidMixPacket(err) => write!(f, "provided mix packet was malformed - {}", err),
         }
     }
 }
@@ -0,0 +1,49 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::commands::*;
+use crate::config::Config;
+use clap::{App, Arg, ArgMatches};
+use config::NymConfig;
+use log::*;
+use mixnode_common::node_description::NodeDescription;
+
+pub fn command_args<'a, 'b>() -> App<'a, 'b> {
+    App::new("describe")
+        .about("Describe your gateway and tell people why they should use it")
+        .arg(
+            Arg::with_name(ID_ARG_NAME)
+                .long(ID_ARG_NAME)
+                .help("The id of the gateway you want to describe")
+                .takes_value(true)
+                .required(true),
+        )
+}
+
+pub fn execute(matches: &ArgMatches) {
+    // figure out which node the user is describing
+    let id = matches
+        .value_of(ID_ARG_NAME)
+        .expect("Please provide the id of your gateway");
+
+    // ensure that the gateway has in fact been initialized
+    match Config::load_from_file(Some(id)) {
+        Ok(cfg) => cfg,
+        Err(err) => {
+            error!("Failed to load config for {}. Are you sure you have run `init` before? (Error was: {})", id, err);
+            return;
+        }
+    };
+
+    // get input from the user
+    let example_url = "https://gateway.yourdomain.com";
+    let node_description =
+        NodeDescription::read_from_stdin(example_url).expect("failed to read the description");
+
+    // save the struct
+    NodeDescription::save_to_file(
+        &node_description,
+        Config::default_config_directory(Some(id)),
+    )
+    .unwrap()
+}
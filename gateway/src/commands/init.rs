@@ -37,6 +37,12 @@ pub fn command_args<'a, 'b>() -> clap::App<'a, 'b> {
                 .help("The port on which the gateway will be listening for clients gateway-requests")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name(HTTP_API_PORT_ARG_NAME)
+                .long(HTTP_API_PORT_ARG_NAME)
+                .help("The port on which the gateway will be listening for http requests")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name(ANNOUNCE_HOST_ARG_NAME)
                 .long(ANNOUNCE_HOST_ARG_NAME)
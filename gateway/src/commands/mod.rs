@@ -5,6 +5,7 @@ use crate::config::Config;
 use clap::ArgMatches;
 use url::Url;
 
+pub(crate) mod describe;
 pub(crate) mod init;
 pub(crate) mod run;
 pub(crate) mod upgrade;
@@ -13,6 +14,7 @@ pub(crate) const ID_ARG_NAME: &str = "id";
 pub(crate) const HOST_ARG_NAME: &str = "host";
 pub(crate) const MIX_PORT_ARG_NAME: &str = "mix-port";
 pub(crate) const CLIENTS_PORT_ARG_NAME: &str = "clients-port";
+pub(crate) const HTTP_API_PORT_ARG_NAME: &str = "http-api-port";
 pub(crate) const VALIDATORS_ARG_NAME: &str = "validators";
 pub(crate) const ANNOUNCE_HOST_ARG_NAME: &str = "announce-host";
 pub(crate) const INBOXES_ARG_NAME: &str = "inboxes";
@@ -58,6 +60,17 @@ pub(crate) fn override_config(mut config: Config, matches: &ArgMatches) -> Confi
         config = config.with_clients_port(clients_port.unwrap());
     }
 
+    if let Some(http_api_port) = matches
+        .value_of(HTTP_API_PORT_ARG_NAME)
+        .map(|port| port.parse::<u16>())
+    {
+        if let Err(err) = http_api_port {
+            // if port was overridden, it must be parsable
+            panic!("Invalid http api port value provided - {:?}", err);
+        }
+        config = config.with_http_api_port(http_api_port.unwrap());
+    }
+
     if let Some(announce_host) = matches.value_of(ANNOUNCE_HOST_ARG_NAME) {
         config = config.with_announce_address(announce_host);
     } else if was_host_overridden {
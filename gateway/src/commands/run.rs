@@ -9,6 +9,7 @@ use clap::{App, Arg, ArgMatches};
 use config::NymConfig;
 use crypto::asymmetric::{encryption, identity};
 use log::*;
+use mixnode_common::node_description::NodeDescription;
 use version_checker::is_minor_version_compatible;
 
 pub fn command_args<'a, 'b>() -> clap::App<'a, 'b> {
@@ -40,6 +41,12 @@ pub fn command_args<'a, 'b>() -> clap::App<'a, 'b> {
                 .help("The port on which the gateway will be listening for clients gateway-requests")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name(HTTP_API_PORT_ARG_NAME)
+                .long(HTTP_API_PORT_ARG_NAME)
+                .help("The port on which the gateway will be listening for http requests")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name(ANNOUNCE_HOST_ARG_NAME)
                 .long(ANNOUNCE_HOST_ARG_NAME)
@@ -154,6 +161,9 @@ pub fn execute(matches: &ArgMatches) {
         show_binding_warning(config.get_listening_address().to_string());
     }
 
+    let description = NodeDescription::load_from_file(Config::default_config_directory(Some(id)))
+        .unwrap_or_default();
+
     println!(
         "Validator servers: {:?}",
         config.get_validator_api_endpoints()
@@ -178,5 +188,5 @@ pub fn execute(matches: &ArgMatches) {
         config.get_clients_ledger_path()
     );
 
-    Gateway::new(config, sphinx_keypair, identity).run();
+    Gateway::new(config, description, sphinx_keypair, identity).run();
 }
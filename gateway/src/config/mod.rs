@@ -5,7 +5,9 @@ use crate::config::template::config_template;
 use config::defaults::*;
 use config::NymConfig;
 use log::error;
-use serde::{Deserialize, Serialize};
+use nymnoise::NoiseMode;
+use nymsphinx::params::PacketSize;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::net::IpAddr;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -23,6 +25,15 @@ const DEFAULT_PACKET_FORWARDING_INITIAL_BACKOFF: Duration = Duration::from_milli
 const DEFAULT_PACKET_FORWARDING_MAXIMUM_BACKOFF: Duration = Duration::from_millis(300_000);
 const DEFAULT_INITIAL_CONNECTION_TIMEOUT: Duration = Duration::from_millis(1_500);
 const DEFAULT_MAXIMUM_CONNECTION_BUFFER_SIZE: usize = 128;
+const DEFAULT_IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const DEFAULT_MAXIMUM_OUTBOUND_CONNECTIONS: usize = 1024;
+const DEFAULT_MAXIMUM_INCOMING_CONNECTIONS: usize = 2048;
+const DEFAULT_MAXIMUM_INCOMING_CONNECTIONS_PER_IP: usize = 16;
+const DEFAULT_MAXIMUM_PACKETS_PER_SECOND: u32 = 10_000;
+const DEFAULT_UNKNOWN_PEER_MAXIMUM_INCOMING_CONNECTIONS_PER_IP: usize = 2;
+const DEFAULT_UNKNOWN_PEER_MAXIMUM_PACKETS_PER_SECOND: u32 = 100;
+const DEFAULT_VPN_KEY_CACHE_CAPACITY: usize = 50_000;
+const DEFAULT_VPN_KEY_CACHE_ENTRY_TTL: Duration = Duration::from_secs(10 * 60);
 
 const DEFAULT_STORED_MESSAGE_FILENAME_LENGTH: u16 = 16;
 const DEFAULT_MESSAGE_RETRIEVAL_LIMIT: u16 = 5;
@@ -43,6 +54,28 @@ fn default_clients_port() -> u16 {
     DEFAULT_CLIENT_LISTENING_PORT
 }
 
+fn default_http_api_port() -> u16 {
+    DEFAULT_GATEWAY_HTTP_API_LISTENING_PORT
+}
+
+fn de_node_packet_sizes<'de, D>(deserializer: D) -> Result<Vec<PacketSize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let packet_sizes = Vec::<PacketSize>::deserialize(deserializer)?;
+    if !packet_sizes.contains(&PacketSize::AckPacket) {
+        return Err(serde::de::Error::custom(
+            "the ack packet size must be among the accepted packet sizes",
+        ));
+    }
+    if !packet_sizes.iter().any(|size| size.is_message_packet()) {
+        return Err(serde::de::Error::custom(
+            "at least a single message packet size must be accepted",
+        ));
+    }
+    Ok(packet_sizes)
+}
+
 #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct Config {
     gateway: Gateway,
@@ -165,6 +198,16 @@ impl Config {
         self
     }
 
+    pub fn with_http_api_port(mut self, port: u16) -> Self {
+        self.gateway.http_api_port = port;
+        self
+    }
+
+    pub fn with_noise_mode(mut self, noise_mode: NoiseMode) -> Self {
+        self.gateway.noise_mode = noise_mode;
+        self
+    }
+
     pub fn announce_host_from_listening_host(mut self) -> Self {
         self.gateway.announce_address = self.gateway.listening_address.to_string();
         self
@@ -226,6 +269,14 @@ impl Config {
         self.gateway.clients_port
     }
 
+    pub fn get_http_api_port(&self) -> u16 {
+        self.gateway.http_api_port
+    }
+
+    pub fn get_noise_mode(&self) -> NoiseMode {
+        self.gateway.noise_mode
+    }
+
     pub fn get_clients_inboxes_dir(&self) -> PathBuf {
         self.clients_endpoint.inboxes_directory.clone()
     }
@@ -250,6 +301,14 @@ impl Config {
         self.debug.maximum_connection_buffer_size
     }
 
+    pub fn get_idle_connection_timeout(&self) -> Duration {
+        self.debug.idle_connection_timeout
+    }
+
+    pub fn get_maximum_outbound_connections(&self) -> usize {
+        self.debug.maximum_outbound_connections
+    }
+
     pub fn get_message_retrieval_limit(&self) -> u16 {
         self.debug.message_retrieval_limit
     }
@@ -258,6 +317,38 @@ impl Config {
         self.debug.stored_messages_filename_length
     }
 
+    pub fn get_maximum_incoming_connections(&self) -> usize {
+        self.debug.maximum_incoming_connections
+    }
+
+    pub fn get_maximum_incoming_connections_per_ip(&self) -> usize {
+        self.debug.maximum_incoming_connections_per_ip
+    }
+
+    pub fn get_maximum_packets_per_second(&self) -> u32 {
+        self.debug.maximum_packets_per_second
+    }
+
+    pub fn get_unknown_peer_maximum_incoming_connections_per_ip(&self) -> usize {
+        self.debug.unknown_peer_maximum_incoming_connections_per_ip
+    }
+
+    pub fn get_unknown_peer_maximum_packets_per_second(&self) -> u32 {
+        self.debug.unknown_peer_maximum_packets_per_second
+    }
+
+    pub fn get_vpn_key_cache_capacity(&self) -> usize {
+        self.debug.vpn_key_cache_capacity
+    }
+
+    pub fn get_vpn_key_cache_entry_ttl(&self) -> Duration {
+        self.debug.vpn_key_cache_entry_ttl
+    }
+
+    pub fn get_packet_sizes(&self) -> Vec<PacketSize> {
+        self.debug.packet_sizes.clone()
+    }
+
     pub fn get_version(&self) -> &str {
         &self.gateway.version
     }
@@ -292,6 +383,18 @@ pub struct Gateway {
     #[serde(default = "default_clients_port")]
     clients_port: u16,
 
+    /// Port used for listening for http requests.
+    /// (default: 8001)
+    #[serde(default = "default_http_api_port")]
+    http_api_port: u16,
+
+    /// Determines whether connections with mixnodes are encrypted with the noise protocol.
+    /// 'optional' attempts the handshake, but falls back to plaintext for nodes not supporting it,
+    /// while 'required' refuses any plaintext connections.
+    /// (default: optional)
+    #[serde(default)]
+    noise_mode: NoiseMode,
+
     /// Path to file containing private identity key.
     private_identity_key_file: PathBuf,
 
@@ -339,6 +442,8 @@ impl Default for Gateway {
             announce_address: "127.0.0.1".to_string(),
             mix_port: DEFAULT_MIX_LISTENING_PORT,
             clients_port: DEFAULT_CLIENT_LISTENING_PORT,
+            http_api_port: DEFAULT_GATEWAY_HTTP_API_LISTENING_PORT,
+            noise_mode: Default::default(),
             private_identity_key_file: Default::default(),
             public_identity_key_file: Default::default(),
             private_sphinx_key_file: Default::default(),
@@ -408,6 +513,15 @@ pub struct Debug {
     /// Maximum number of packets that can be stored waiting to get sent to a particular connection.
     maximum_connection_buffer_size: usize,
 
+    /// Outbound connections that were not used for this long get closed, unless they are
+    /// to nodes in the first layer of the topology.
+    #[serde(with = "humantime_serde")]
+    idle_connection_timeout: Duration,
+
+    /// Maximum number of simultaneously open outbound connections. Once reached, the least recently
+    /// used connection gets closed. If set to 0, the number of connections is not limited.
+    maximum_outbound_connections: usize,
+
     /// Delay between each subsequent presence data being sent.
     #[serde(with = "humantime_serde")]
     presence_sending_delay: Duration,
@@ -419,6 +533,41 @@ pub struct Debug {
     /// if there are no real messages, dummy ones are created to always return  
     /// `message_retrieval_limit` total messages
     message_retrieval_limit: u16,
+
+    /// Maximum number of simultaneous incoming mixnet connections.
+    maximum_incoming_connections: usize,
+
+    /// Maximum number of simultaneous incoming mixnet connections from a single ip address
+    /// belonging to a node present in the topology.
+    maximum_incoming_connections_per_ip: usize,
+
+    /// Maximum number of packets per second read from a single mixnet connection of a node
+    /// present in the topology. If set to 0, the rate is not limited.
+    maximum_packets_per_second: u32,
+
+    /// Maximum number of simultaneous incoming mixnet connections from a single ip address
+    /// not belonging to any node in the topology. If set to 0, such connections are refused.
+    unknown_peer_maximum_incoming_connections_per_ip: usize,
+
+    /// Maximum number of packets per second read from a single mixnet connection of an ip address
+    /// not belonging to any node in the topology. If set to 0, the rate is not limited.
+    unknown_peer_maximum_packets_per_second: u32,
+
+    /// Maximum number of sessions of the `Vpn` mode packets for which the derived keys are cached.
+    /// If set to 0, every such packet goes through the full sphinx key derivation.
+    vpn_key_cache_capacity: usize,
+
+    /// Amount of time for which keys derived for the `Vpn` mode packets are cached.
+    #[serde(with = "humantime_serde")]
+    vpn_key_cache_entry_ttl: Duration,
+
+    /// Sizes of the sphinx packets this gateway accepts from and forwards to mixnodes.
+    /// They are announced to other nodes when establishing connections and exposed
+    /// on the `/packet-sizes` endpoint of the http api, so that the clients could choose
+    /// the sizes supported by the nodes on their routes. Must include the ack packet size.
+    /// Apart from the built-in sizes, custom ones can be defined by their `id` and `plaintext_size`.
+    #[serde(deserialize_with = "de_node_packet_sizes")]
+    packet_sizes: Vec<PacketSize>,
 }
 
 impl Default for Debug {
@@ -429,8 +578,20 @@ impl Default for Debug {
             initial_connection_timeout: DEFAULT_INITIAL_CONNECTION_TIMEOUT,
             presence_sending_delay: DEFAULT_PRESENCE_SENDING_DELAY,
             maximum_connection_buffer_size: DEFAULT_MAXIMUM_CONNECTION_BUFFER_SIZE,
+            idle_connection_timeout: DEFAULT_IDLE_CONNECTION_TIMEOUT,
+            maximum_outbound_connections: DEFAULT_MAXIMUM_OUTBOUND_CONNECTIONS,
             stored_messages_filename_length: DEFAULT_STORED_MESSAGE_FILENAME_LENGTH,
             message_retrieval_limit: DEFAULT_MESSAGE_RETRIEVAL_LIMIT,
+            maximum_incoming_connections: DEFAULT_MAXIMUM_INCOMING_CONNECTIONS,
+            maximum_incoming_connections_per_ip: DEFAULT_MAXIMUM_INCOMING_CONNECTIONS_PER_IP,
+            maximum_packets_per_second: DEFAULT_MAXIMUM_PACKETS_PER_SECOND,
+            unknown_peer_maximum_incoming_connections_per_ip:
+                DEFAULT_UNKNOWN_PEER_MAXIMUM_INCOMING_CONNECTIONS_PER_IP,
+            unknown_peer_maximum_packets_per_second:
+                DEFAULT_UNKNOWN_PEER_MAXIMUM_PACKETS_PER_SECOND,
+            vpn_key_cache_capacity: DEFAULT_VPN_KEY_CACHE_CAPACITY,
+            vpn_key_cache_entry_ttl: DEFAULT_VPN_KEY_CACHE_ENTRY_TTL,
+            packet_sizes: PacketSize::all().collect(),
         }
     }
 }
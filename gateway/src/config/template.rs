@@ -49,6 +49,16 @@ mix_port = {{ gateway.mix_port }}
 # (default: 9000)
 clients_port = {{ gateway.clients_port }}
 
+# Port used for listening for http requests.
+# (default: 8001)
+http_api_port = {{ gateway.http_api_port }}
+
+# Determines whether connections with mixnodes are encrypted with the noise protocol.
+# 'optional' attempts the handshake, but falls back to plaintext for nodes not supporting it,
+# while 'required' refuses any plaintext connections. Can also be set to 'disabled'.
+# (default: optional)
+noise_mode = '{{ gateway.noise_mode }}'
+
 # Addresses to APIs running on validator from which the node gets the view of the network.
 validator_api_urls = [
     {{#each gateway.validator_api_urls }}
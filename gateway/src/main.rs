@@ -1,6 +1,9 @@
 // Copyright 2020 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+#[macro_use]
+extern crate rocket;
+
 use clap::{App, ArgMatches};
 
 mod commands;
@@ -16,6 +19,7 @@ fn main() {
         .version(env!("CARGO_PKG_VERSION"))
         .author("Nymtech")
         .about("Implementation of the Nym Mixnet Gateway")
+        .subcommand(commands::describe::command_args())
         .subcommand(commands::init::command_args())
         .subcommand(commands::run::command_args())
         .subcommand(commands::upgrade::command_args())
@@ -26,6 +30,7 @@ fn main() {
 
 fn execute(matches: ArgMatches) {
     match matches.subcommand() {
+        ("describe", Some(m)) => commands::describe::execute(m),
         ("init", Some(m)) => commands::init::execute(m),
         ("run", Some(m)) => commands::run::execute(m),
         ("upgrade", Some(m)) => commands::upgrade::execute(m),
@@ -3,6 +3,7 @@
 
 use crate::node::{
     client_handling::websocket::message_receiver::MixMessageSender,
+    statistics::GatewayStats,
     storage::{inboxes::ClientStorage, ClientLedger},
 };
 use futures::{
@@ -42,6 +43,9 @@ pub(crate) enum ClientsHandlerRequest {
 
     // mix
     IsOnline(DestinationAddressBytes, ClientsHandlerResponseSender),
+
+    // http api
+    ConnectedClients(ClientsHandlerResponseSender),
 }
 
 #[derive(Debug)]
@@ -49,6 +53,7 @@ pub(crate) enum ClientsHandlerResponse {
     Register(bool),
     Authenticate(Option<SharedKeys>),
     IsOnline(Option<MixMessageSender>),
+    ConnectedClients(usize),
     Error(Box<dyn std::error::Error + Send + Sync>),
 }
 
@@ -56,14 +61,20 @@ pub(crate) struct ClientsHandler {
     open_connections: HashMap<DestinationAddressBytes, MixMessageSender>,
     clients_ledger: ClientLedger,
     clients_inbox_storage: ClientStorage,
+    stats: GatewayStats,
 }
 
 impl ClientsHandler {
-    pub(crate) fn new(clients_ledger: ClientLedger, clients_inbox_storage: ClientStorage) -> Self {
+    pub(crate) fn new(
+        clients_ledger: ClientLedger,
+        clients_inbox_storage: ClientStorage,
+        stats: GatewayStats,
+    ) -> Self {
         ClientsHandler {
             open_connections: HashMap::new(),
             clients_ledger,
             clients_inbox_storage,
+            stats,
         }
     }
 
@@ -122,9 +133,11 @@ impl ClientsHandler {
             .map(|c| c.into_tuple())
             .unzip();
 
+        let retrieved = messages.len();
         if comm_channel.unbounded_send(messages).is_err() {
             error!("Somehow we failed to stored messages to a fresh client channel - there seem to be a weird bug present!");
         } else {
+            self.stats.report_retrieved(retrieved);
             // but if all went well, we can now delete it
             if let Err(e) = self.clients_inbox_storage.delete_files(paths).await {
                 error!(
@@ -261,6 +274,12 @@ impl ClientsHandler {
             .unwrap();
     }
 
+    fn handle_connected_clients_request(&self, res_channel: ClientsHandlerResponseSender) {
+        let response_value = self.open_connections.len();
+        // the http api might have given up on waiting for the response, so it's fine if this fails
+        let _ = res_channel.send(ClientsHandlerResponse::ConnectedClients(response_value));
+    }
+
     pub(crate) async fn run(
         &mut self,
         mut request_receiver_channel: ClientsHandlerRequestReceiver,
@@ -301,6 +320,9 @@ impl ClientsHandler {
                 ClientsHandlerRequest::IsOnline(address, res_channel) => {
                     self.handle_is_online_request(address, res_channel)
                 }
+                ClientsHandlerRequest::ConnectedClients(res_channel) => {
+                    self.handle_connected_clients_request(res_channel)
+                }
             };
         }
         error!("Something bad has happened and we stopped listening for requests!");
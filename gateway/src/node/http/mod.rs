@@ -0,0 +1,13 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+pub(crate) mod description;
+pub(crate) mod packet_sizes;
+pub(crate) mod stats;
+
+use rocket::Request;
+
+#[catch(404)]
+pub(crate) fn not_found(req: &Request) -> String {
+    format!("I couldn't find '{}'. Try something else?", req.uri())
+}
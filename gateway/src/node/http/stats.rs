@@ -0,0 +1,70 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::node::client_handling::clients_handler::{
+    ClientsHandlerRequest, ClientsHandlerRequestSender, ClientsHandlerResponse,
+};
+use crate::node::statistics::{GatewayStats, GatewayStatsSnapshot};
+use crate::node::storage::ClientLedger;
+use futures::channel::oneshot;
+use mixnet_client::{ConnectionPoolSnapshot, ConnectionPoolStats};
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Serialize;
+
+pub(crate) struct StatsState {
+    stats: GatewayStats,
+    clients_ledger: ClientLedger,
+    clients_handler_sender: ClientsHandlerRequestSender,
+    connection_pool_stats: ConnectionPoolStats,
+}
+
+impl StatsState {
+    pub(crate) fn new(
+        stats: GatewayStats,
+        clients_ledger: ClientLedger,
+        clients_handler_sender: ClientsHandlerRequestSender,
+        connection_pool_stats: ConnectionPoolStats,
+    ) -> Self {
+        StatsState {
+            stats,
+            clients_ledger,
+            clients_handler_sender,
+            connection_pool_stats,
+        }
+    }
+
+    async fn connected_clients(&self) -> usize {
+        let (res_sender, res_receiver) = oneshot::channel();
+        let clients_handler_request = ClientsHandlerRequest::ConnectedClients(res_sender);
+        self.clients_handler_sender
+            .unbounded_send(clients_handler_request)
+            .unwrap(); // the receiver MUST BE alive
+
+        match res_receiver.await.unwrap() {
+            ClientsHandlerResponse::ConnectedClients(count) => count,
+            _ => panic!("received response to wrong query!"), // again, this should NEVER happen
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct GatewayStatsResponse {
+    #[serde(flatten)]
+    traffic: GatewayStatsSnapshot,
+    registered_clients: usize,
+    connected_clients: usize,
+    outbound_connections: ConnectionPoolSnapshot,
+}
+
+/// Returns running traffic stats of the gateway alongside the number of its clients
+/// and the state of its outbound connections.
+#[get("/stats")]
+pub(crate) async fn stats(state: &State<StatsState>) -> Json<GatewayStatsResponse> {
+    Json(GatewayStatsResponse {
+        traffic: state.stats.snapshot(),
+        registered_clients: state.clients_ledger.registered_clients(),
+        connected_clients: state.connected_clients().await,
+        outbound_connections: state.connection_pool_stats.snapshot(),
+    })
+}
@@ -6,15 +6,17 @@ use crate::node::client_handling::clients_handler::{
 };
 use crate::node::client_handling::websocket::message_receiver::MixMessageSender;
 use crate::node::mixnet_handling::receiver::packet_processing::PacketProcessor;
+use crate::node::statistics::GatewayStats;
 use crate::node::storage::inboxes::{ClientStorage, StoreData};
 use dashmap::DashMap;
 use futures::channel::oneshot;
 use futures::StreamExt;
 use log::*;
 use mixnet_client::forwarder::MixForwardingSender;
+use mixnode_common::admission::AdmissionPermit;
 use mixnode_common::packet_processor::processor::ProcessedFinalHop;
+use nymnoise::NoiseConfig;
 use nymsphinx::forwarding::packet::MixPacket;
-use nymsphinx::framing::codec::SphinxCodec;
 use nymsphinx::framing::packet::FramedSphinxPacket;
 use nymsphinx::DestinationAddressBytes;
 use std::io;
@@ -33,6 +35,8 @@ pub(crate) struct ConnectionHandler {
     client_store: ClientStorage,
     clients_handler_sender: ClientsHandlerRequestSender,
     ack_sender: MixForwardingSender,
+    stats: GatewayStats,
+    noise_config: NoiseConfig,
 }
 
 impl ConnectionHandler {
@@ -42,6 +46,8 @@ impl ConnectionHandler {
         client_store: ClientStorage,
 
         ack_sender: MixForwardingSender,
+        stats: GatewayStats,
+        noise_config: NoiseConfig,
     ) -> Self {
         ConnectionHandler {
             packet_processor,
@@ -49,6 +55,8 @@ impl ConnectionHandler {
             client_store,
             clients_handler_sender,
             ack_sender,
+            stats,
+            noise_config,
         }
     }
 
@@ -69,6 +77,8 @@ impl ConnectionHandler {
             client_store: self.client_store.clone(),
             clients_handler_sender: self.clients_handler_sender.clone(),
             ack_sender: self.ack_sender.clone(),
+            stats: self.stats.clone(),
+            noise_config: self.noise_config.clone(),
         }
     }
 
@@ -190,9 +200,15 @@ impl ConnectionHandler {
                 .await
             {
                 Err(err) => error!("Failed to store client data - {}", err),
-                Ok(_) => trace!("Stored packet for {}", client_address),
+                Ok(_) => {
+                    self.stats.report_stored();
+                    trace!("Stored packet for {}", client_address)
+                }
             },
-            Ok(_) => trace!("Pushed received packet to {}", client_address),
+            Ok(_) => {
+                self.stats.report_sent_to_client();
+                trace!("Pushed received packet to {}", client_address)
+            }
         }
 
         // if we managed to either push message directly to the [online] client or store it at
@@ -208,6 +224,7 @@ impl ConnectionHandler {
         // question: can it also be per connection vs global?
         //
 
+        self.stats.report_received_from_mixnet();
         let processed_final_hop = match self.packet_processor.process_received(framed_sphinx_packet)
         {
             Err(e) => {
@@ -220,10 +237,27 @@ impl ConnectionHandler {
         self.handle_processed_packet(processed_final_hop).await
     }
 
-    pub(crate) async fn handle_connection(self, conn: TcpStream, remote: SocketAddr) {
+    pub(crate) async fn handle_connection(
+        self,
+        mut conn: TcpStream,
+        remote: SocketAddr,
+        mut permit: AdmissionPermit,
+    ) {
         debug!("Starting connection handler for {:?}", remote);
+        let negotiated =
+            match nymnoise::upgrade_inbound(&mut conn, &self.noise_config, remote).await {
+                Ok(negotiated) => negotiated,
+                Err(err) => {
+                    warn!(
+                        "Failed to negotiate the transport with {:?} - {}. Closing the socket",
+                        remote, err
+                    );
+                    return;
+                }
+            };
+
         let this = Arc::new(self);
-        let mut framed_conn = Framed::new(conn, SphinxCodec);
+        let mut framed_conn = Framed::new(conn, negotiated.into_codec());
         while let Some(framed_sphinx_packet) = framed_conn.next().await {
             match framed_sphinx_packet {
                 Ok(framed_sphinx_packet) => {
@@ -235,6 +269,7 @@ impl ConnectionHandler {
                     //
                     // My gut feeling is saying that we might get some nice performance boost
                     // if we introduced the change
+                    permit.throttle().await;
                     let this = Arc::clone(&this);
                     tokio::spawn(this.handle_received_packet(framed_sphinx_packet));
                 }
@@ -3,18 +3,20 @@
 
 use crate::node::mixnet_handling::receiver::connection_handler::ConnectionHandler;
 use log::*;
+use mixnode_common::admission::ConnectionAdmission;
 use std::net::SocketAddr;
 use std::process;
 use tokio::task::JoinHandle;
 
 pub(crate) struct Listener {
     address: SocketAddr,
+    admission: ConnectionAdmission,
 }
 
 // TODO: this file is nearly identical to the one in mixnode
 impl Listener {
-    pub(crate) fn new(address: SocketAddr) -> Self {
-        Listener { address }
+    pub(crate) fn new(address: SocketAddr, admission: ConnectionAdmission) -> Self {
+        Listener { address, admission }
     }
 
     pub(crate) async fn run(&mut self, connection_handler: ConnectionHandler) {
@@ -29,10 +31,14 @@ impl Listener {
 
         loop {
             match tcp_listener.accept().await {
-                Ok((socket, remote_addr)) => {
-                    let handler = connection_handler.clone_without_cache();
-                    tokio::spawn(handler.handle_connection(socket, remote_addr));
-                }
+                Ok((socket, remote_addr)) => match self.admission.try_admit(remote_addr) {
+                    Ok(permit) => {
+                        let handler = connection_handler.clone_without_cache();
+                        tokio::spawn(handler.handle_connection(socket, remote_addr, permit));
+                    }
+                    // the socket is dropped, closing the connection
+                    Err(err) => debug!("Refusing connection from {} - {}", remote_addr, err),
+                },
                 Err(e) => warn!("failed to get client: {:?}", e),
             }
         }
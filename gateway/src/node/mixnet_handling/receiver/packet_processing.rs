@@ -3,6 +3,7 @@
 
 use crypto::asymmetric::encryption;
 use mixnode_common::packet_processor::error::MixProcessingError;
+use mixnode_common::packet_processor::key_cache::KeyCache;
 pub use mixnode_common::packet_processor::processor::MixProcessingResult;
 use mixnode_common::packet_processor::processor::{ProcessedFinalHop, SphinxPacketProcessor};
 use nymsphinx::framing::packet::FramedSphinxPacket;
@@ -28,9 +29,9 @@ pub struct PacketProcessor {
 }
 
 impl PacketProcessor {
-    pub(crate) fn new(encryption_key: &encryption::PrivateKey) -> Self {
+    pub(crate) fn new(encryption_key: &encryption::PrivateKey, vpn_key_cache: KeyCache) -> Self {
         PacketProcessor {
-            inner_processor: SphinxPacketProcessor::new(encryption_key.into()),
+            inner_processor: SphinxPacketProcessor::new(encryption_key.into(), vpn_key_cache),
         }
     }
 
@@ -4,13 +4,28 @@
 use crate::config::Config;
 use crate::node::client_handling::clients_handler::{ClientsHandler, ClientsHandlerRequestSender};
 use crate::node::client_handling::websocket;
+use crate::node::http::{
+    description::description,
+    not_found,
+    packet_sizes::{packet_sizes, PacketSizesState},
+    stats::{stats, StatsState},
+};
 use crate::node::mixnet_handling::receiver::connection_handler::ConnectionHandler;
+use crate::node::statistics::GatewayStats;
 use crate::node::storage::{inboxes, ClientLedger};
 use coconut_interface::VerificationKey;
 use credentials::obtain_aggregate_verification_key;
 use crypto::asymmetric::{encryption, identity};
 use log::*;
 use mixnet_client::forwarder::{MixForwardingSender, PacketForwarder};
+use mixnet_client::{ConnectionPoolStats, TopologyPeers};
+use mixnode_common::admission::{AdmissionConfig, ConnectionAdmission};
+use mixnode_common::known_nodes::{KnownNodesRefresher, NodeRole};
+use mixnode_common::node_description::NodeDescription;
+use mixnode_common::packet_processor::key_cache::KeyCache;
+use nymnoise::{KnownNodes, NoiseConfig};
+use nymsphinx::framing::hello::Capabilities;
+use nymsphinx::params::PacketSize;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 use std::net::SocketAddr;
@@ -19,22 +34,27 @@ use std::sync::Arc;
 use tokio::runtime::Runtime;
 
 pub(crate) mod client_handling;
+pub(crate) mod http;
 pub(crate) mod mixnet_handling;
+pub(crate) mod statistics;
 pub(crate) mod storage;
 
 pub struct Gateway {
     config: Config,
+    descriptor: NodeDescription,
     /// ed25519 keypair used to assert one's identity.
     identity: Arc<identity::KeyPair>,
     /// x25519 keypair used for Diffie-Hellman. Currently only used for sphinx key derivation.
     encryption_keys: Arc<encryption::KeyPair>,
     registered_clients_ledger: ClientLedger,
     client_inbox_storage: inboxes::ClientStorage,
+    stats: GatewayStats,
 }
 
 impl Gateway {
     pub fn new(
         config: Config,
+        descriptor: NodeDescription,
         encryption_keys: encryption::KeyPair,
         identity: identity::KeyPair,
     ) -> Self {
@@ -49,36 +69,95 @@ impl Gateway {
         );
         Gateway {
             config,
+            descriptor,
             identity: Arc::new(identity),
             encryption_keys: Arc::new(encryption_keys),
             client_inbox_storage,
             registered_clients_ledger,
+            stats: GatewayStats::new(),
         }
     }
 
+    fn start_http_api(
+        &self,
+        clients_handler_sender: ClientsHandlerRequestSender,
+        connection_pool_stats: ConnectionPoolStats,
+    ) {
+        info!("Starting HTTP API...");
+
+        let mut config = rocket::config::Config::release_default();
+
+        // bind to the same address as we are using for the mixnet and client traffic
+        config.address = self.config.get_listening_address();
+        config.port = self.config.get_http_api_port();
+
+        let stats_state = StatsState::new(
+            self.stats.clone(),
+            self.registered_clients_ledger.clone(),
+            clients_handler_sender,
+            connection_pool_stats,
+        );
+        let packet_sizes_state = PacketSizesState::new(self.config.get_packet_sizes());
+        let descriptor = self.descriptor.clone();
+
+        tokio::spawn(async move {
+            rocket::build()
+                .configure(config)
+                .mount("/", routes![description, stats, packet_sizes])
+                .register("/", catchers![not_found])
+                .manage(stats_state)
+                .manage(packet_sizes_state)
+                .manage(descriptor)
+                .launch()
+                .await
+        });
+    }
+
     fn start_mix_socket_listener(
         &self,
         clients_handler_sender: ClientsHandlerRequestSender,
         ack_sender: MixForwardingSender,
+        known_nodes: KnownNodes,
     ) {
         info!("Starting mix socket listener...");
 
-        let packet_processor =
-            mixnet_handling::PacketProcessor::new(self.encryption_keys.private_key());
+        let vpn_key_cache = KeyCache::new(
+            self.config.get_vpn_key_cache_capacity(),
+            self.config.get_vpn_key_cache_entry_ttl(),
+        );
+        let packet_processor = mixnet_handling::PacketProcessor::new(
+            self.encryption_keys.private_key(),
+            vpn_key_cache,
+        );
 
         let connection_handler = ConnectionHandler::new(
             packet_processor,
             clients_handler_sender,
             self.client_inbox_storage.clone(),
             ack_sender,
+            self.stats.clone(),
+            self.noise_config(known_nodes.clone()),
         );
 
+        let admission_config = AdmissionConfig::new()
+            .maximum_connections(self.config.get_maximum_incoming_connections())
+            .maximum_connections_per_ip(self.config.get_maximum_incoming_connections_per_ip())
+            .maximum_packets_per_second(self.config.get_maximum_packets_per_second())
+            .unknown_peer_maximum_connections_per_ip(
+                self.config
+                    .get_unknown_peer_maximum_incoming_connections_per_ip(),
+            )
+            .unknown_peer_maximum_packets_per_second(
+                self.config.get_unknown_peer_maximum_packets_per_second(),
+            );
+        let admission = ConnectionAdmission::new(admission_config, known_nodes);
+
         let listening_address = SocketAddr::new(
             self.config.get_listening_address(),
             self.config.get_mix_port(),
         );
 
-        mixnet_handling::Listener::new(listening_address).start(connection_handler);
+        mixnet_handling::Listener::new(listening_address, admission).start(connection_handler);
     }
 
     fn start_client_websocket_listener(
@@ -102,18 +181,52 @@ impl Gateway {
         .start(clients_handler_sender, forwarding_channel);
     }
 
-    fn start_packet_forwarder(&self) -> MixForwardingSender {
+    fn start_known_nodes_refresher(&self, topology_peers: TopologyPeers) -> KnownNodes {
+        info!("Starting known nodes refresher...");
+        let known_nodes = KnownNodes::new();
+        let mut refresher = KnownNodesRefresher::new(
+            self.config.get_validator_api_endpoints(),
+            known_nodes.clone(),
+        )
+        .with_topology_peers(topology_peers, NodeRole::Gateway);
+        tokio::spawn(async move { refresher.run().await });
+        known_nodes
+    }
+
+    fn noise_config(&self, known_nodes: KnownNodes) -> NoiseConfig {
+        NoiseConfig::new(
+            Arc::clone(&self.encryption_keys),
+            self.config.get_noise_mode(),
+            known_nodes,
+        )
+        .with_capabilities(
+            Capabilities::current().with_packet_sizes(&self.config.get_packet_sizes()),
+        )
+    }
+
+    fn start_packet_forwarder(
+        &self,
+        noise_config: NoiseConfig,
+        topology_peers: TopologyPeers,
+    ) -> (MixForwardingSender, ConnectionPoolStats) {
         info!("Starting mix packet forwarder...");
 
-        let (mut packet_forwarder, packet_sender) = PacketForwarder::new(
+        let client_config = mixnet_client::Config::new(
             self.config.get_packet_forwarding_initial_backoff(),
             self.config.get_packet_forwarding_maximum_backoff(),
             self.config.get_initial_connection_timeout(),
             self.config.get_maximum_connection_buffer_size(),
-        );
+            noise_config,
+        )
+        .with_idle_connection_timeout(self.config.get_idle_connection_timeout())
+        .with_maximum_connections(self.config.get_maximum_outbound_connections())
+        .with_topology_peers(topology_peers);
+
+        let (mut packet_forwarder, packet_sender) = PacketForwarder::new(client_config);
+        let pool_stats = packet_forwarder.pool_stats();
 
         tokio::spawn(async move { packet_forwarder.run().await });
-        packet_sender
+        (packet_sender, pool_stats)
     }
 
     fn start_clients_handler(&self) -> ClientsHandlerRequestSender {
@@ -121,6 +234,7 @@ impl Gateway {
         let (_, clients_handler_sender) = ClientsHandler::new(
             self.registered_clients_ledger.clone(),
             self.client_inbox_storage.clone(),
+            self.stats.clone(),
         )
         .start();
         clients_handler_sender
@@ -168,6 +282,11 @@ impl Gateway {
     pub fn run(&mut self) {
         info!("Starting nym gateway!");
 
+        if let Err(err) = PacketSize::register_all(self.config.get_packet_sizes()) {
+            error!("Failed to register the configured packet sizes - {}", err);
+            process::exit(1);
+        }
+
         let runtime = Runtime::new().unwrap();
 
         runtime.block_on(async {
@@ -185,11 +304,14 @@ impl Gateway {
 
             let validators_verification_key = obtain_aggregate_verification_key(&self.config.get_validator_api_endpoints()).await.expect("failed to contact validators to obtain their verification keys");
 
-            let mix_forwarding_channel = self.start_packet_forwarder();
+            let topology_peers = TopologyPeers::new();
+            let known_nodes = self.start_known_nodes_refresher(topology_peers.clone());
+            let (mix_forwarding_channel, connection_pool_stats) = self.start_packet_forwarder(self.noise_config(known_nodes.clone()), topology_peers);
             let clients_handler_sender = self.start_clients_handler();
 
-            self.start_mix_socket_listener(clients_handler_sender.clone(), mix_forwarding_channel.clone());
-            self.start_client_websocket_listener(mix_forwarding_channel, clients_handler_sender, validators_verification_key);
+            self.start_mix_socket_listener(clients_handler_sender.clone(), mix_forwarding_channel.clone(), known_nodes);
+            self.start_client_websocket_listener(mix_forwarding_channel, clients_handler_sender.clone(), validators_verification_key);
+            self.start_http_api(clients_handler_sender, connection_pool_stats);
 
             info!("Finished nym gateway startup procedure - it should now be able to receive mix and client traffic!");
 
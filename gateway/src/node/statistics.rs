@@ -0,0 +1,82 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Counters of the traffic handled by the gateway since its startup.
+// Note: you should NEVER create more than a single instance of this using 'new()'.
+// You should always use .clone() to create additional instances
+#[derive(Clone, Default)]
+pub(crate) struct GatewayStats {
+    inner: Arc<GatewayStatsInner>,
+}
+
+#[derive(Default)]
+struct GatewayStatsInner {
+    packets_received_from_mixnet: AtomicU64,
+    packets_sent_to_clients: AtomicU64,
+    messages_stored_in_inboxes: AtomicU64,
+    messages_retrieved_from_inboxes: AtomicU64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct GatewayStatsSnapshot {
+    packets_received_from_mixnet: u64,
+
+    // note: sent does not imply delivered. We don't know if the client actually received it
+    packets_sent_to_clients: u64,
+
+    messages_stored_in_inboxes: u64,
+
+    messages_retrieved_from_inboxes: u64,
+}
+
+impl GatewayStats {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn report_received_from_mixnet(&self) {
+        self.inner
+            .packets_received_from_mixnet
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn report_sent_to_client(&self) {
+        self.inner
+            .packets_sent_to_clients
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn report_stored(&self) {
+        self.inner
+            .messages_stored_in_inboxes
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn report_retrieved(&self, count: usize) {
+        self.inner
+            .messages_retrieved_from_inboxes
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> GatewayStatsSnapshot {
+        GatewayStatsSnapshot {
+            packets_received_from_mixnet: self
+                .inner
+                .packets_received_from_mixnet
+                .load(Ordering::Relaxed),
+            packets_sent_to_clients: self.inner.packets_sent_to_clients.load(Ordering::Relaxed),
+            messages_stored_in_inboxes: self
+                .inner
+                .messages_stored_in_inboxes
+                .load(Ordering::Relaxed),
+            messages_retrieved_from_inboxes: self
+                .inner
+                .messages_retrieved_from_inboxes
+                .load(Ordering::Relaxed),
+        }
+    }
+}
@@ -103,6 +103,11 @@ impl ClientLedger {
         }
     }
 
+    /// Returns the number of clients that have registered with this gateway.
+    pub(crate) fn registered_clients(&self) -> usize {
+        self.db.len()
+    }
+
     pub(crate) fn insert_shared_key(
         &mut self,
         shared_key: SharedKeys,
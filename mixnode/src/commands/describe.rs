@@ -1,11 +1,9 @@
 use crate::commands::*;
 use crate::config::Config;
-use crate::node::node_description::NodeDescription;
 use clap::{App, Arg, ArgMatches};
 use colored::Colorize;
 use config::NymConfig;
-use std::io;
-use std::io::Write;
+use mixnode_common::node_description::NodeDescription;
 
 pub fn command_args<'a, 'b>() -> App<'a, 'b> {
     App::new("describe")
@@ -35,39 +33,9 @@ pub fn execute(matches: &ArgMatches) {
     };
 
     // get input from the user
-    print!("name: ");
-    io::stdout().flush().unwrap();
-    let mut name_buf = String::new();
-    io::stdin().read_line(&mut name_buf).unwrap();
-    let name = name_buf.trim().to_string();
-
-    print!("description: ");
-    io::stdout().flush().unwrap();
-    let mut desc_buf = String::new();
-    io::stdin().read_line(&mut desc_buf).unwrap();
-    let description = desc_buf.trim().to_string();
-
     let example_url = "https://mixnode.yourdomain.com".bright_cyan();
-    let example_location = "City: London, Country: UK";
-
-    print!("link, e.g. {}: ", example_url);
-    io::stdout().flush().unwrap();
-    let mut link_buf = String::new();
-    io::stdin().read_line(&mut link_buf).unwrap();
-    let link = link_buf.trim().to_string();
-
-    print!("location, e.g. {}: ", example_location);
-    io::stdout().flush().unwrap();
-    let mut location_buf = String::new();
-    io::stdin().read_line(&mut location_buf).unwrap();
-    let location = location_buf.trim().to_string();
-
-    let node_description = NodeDescription {
-        name,
-        description,
-        link,
-        location,
-    };
+    let node_description =
+        NodeDescription::read_from_stdin(example_url).expect("failed to read the description");
 
     // save the struct
     NodeDescription::save_to_file(
@@ -2,13 +2,15 @@ use crate::commands::*;
 use crate::config::{persistence::pathfinder::MixNodePathfinder, Config};
 use clap::{App, Arg, ArgMatches};
 use colored::*;
-use config::defaults::BECH32_PREFIX;
+use config::defaults::{BECH32_PREFIX, DEFAULT_MIXNET_CONTRACT_ADDRESS};
 use config::NymConfig;
 use crypto::asymmetric::identity;
 use log::error;
 use std::process;
 
 const SIGN_TEXT_ARG_NAME: &str = "text";
+const FAMILY_MEMBER_ARG_NAME: &str = "family-member";
+const MIXNET_CONTRACT_ARG_NAME: &str = "mixnet-contract";
 
 pub fn command_args<'a, 'b>() -> App<'a, 'b> {
     App::new("sign")
@@ -25,7 +27,21 @@ pub fn command_args<'a, 'b>() -> App<'a, 'b> {
                 .long(SIGN_TEXT_ARG_NAME)
                 .help("The text to sign")
                 .takes_value(true)
-                .required(true),
+                .required_unless(FAMILY_MEMBER_ARG_NAME),
+        )
+        .arg(
+            Arg::with_name(FAMILY_MEMBER_ARG_NAME)
+                .long(FAMILY_MEMBER_ARG_NAME)
+                .help("Identity key of the mixnode you want to allow to join the family headed by this mixnode")
+                .takes_value(true)
+                .conflicts_with(SIGN_TEXT_ARG_NAME),
+        )
+        .arg(
+            Arg::with_name(MIXNET_CONTRACT_ARG_NAME)
+                .long(MIXNET_CONTRACT_ARG_NAME)
+                .help("Address of the mixnet contract the family member is going to join the family in")
+                .takes_value(true)
+                .requires(FAMILY_MEMBER_ARG_NAME),
         )
 }
 
@@ -38,9 +54,39 @@ fn load_identity_keys(pathfinder: &MixNodePathfinder) -> identity::KeyPair {
     identity_keypair
 }
 
+// has to match the message the contract expects, i.e. `mixnet_contract::family_join_message`
+fn family_join_message(contract_address: &str, family_head: &str, member: &str) -> String {
+    format!("{}:{}:{}", contract_address, family_head, member)
+}
+
+fn print_family_signature(
+    identity_keypair: &identity::KeyPair,
+    contract_address: &str,
+    family_member: &str,
+) {
+    let family_head = identity_keypair.public_key().to_base58_string();
+    let message = family_join_message(contract_address, &family_head, family_member);
+    let signature_bytes = identity_keypair
+        .private_key()
+        .sign(message.as_ref())
+        .to_bytes();
+    let signature = bs58::encode(signature_bytes).into_string();
+
+    println!(
+        "Signing the identity {:?} for the contract {} using your mixnode's Ed25519 identity key...",
+        family_member, contract_address
+    );
+    println!();
+    println!("Signature is: {}", signature);
+    println!();
+    println!(
+        "The operator of {} can now join your family by providing the identity of your mixnode ({}) and the above signature",
+        family_member, family_head
+    );
+}
+
 pub fn execute(matches: &ArgMatches) {
     let id = matches.value_of(ID_ARG_NAME).unwrap();
-    let text = matches.value_of(SIGN_TEXT_ARG_NAME).unwrap();
 
     let config = match Config::load_from_file(Some(id)) {
         Ok(cfg) => cfg,
@@ -51,6 +97,16 @@ pub fn execute(matches: &ArgMatches) {
     };
     let pathfinder = MixNodePathfinder::new_from_config(&config);
     let identity_keypair = load_identity_keys(&pathfinder);
+
+    if let Some(family_member) = matches.value_of(FAMILY_MEMBER_ARG_NAME) {
+        let contract_address = matches
+            .value_of(MIXNET_CONTRACT_ARG_NAME)
+            .unwrap_or(DEFAULT_MIXNET_CONTRACT_ADDRESS);
+        print_family_signature(&identity_keypair, contract_address, family_member);
+        return;
+    }
+
+    let text = matches.value_of(SIGN_TEXT_ARG_NAME).unwrap();
     let signature_bytes = identity_keypair
         .private_key()
         .sign(text.as_ref())
@@ -4,6 +4,8 @@
 use crate::config::template::config_template;
 use config::defaults::*;
 use config::NymConfig;
+use nymnoise::NoiseMode;
+use nymsphinx::params::PacketSize;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
@@ -32,6 +34,18 @@ const DEFAULT_PACKET_FORWARDING_INITIAL_BACKOFF: Duration = Duration::from_milli
 const DEFAULT_PACKET_FORWARDING_MAXIMUM_BACKOFF: Duration = Duration::from_millis(300_000);
 const DEFAULT_INITIAL_CONNECTION_TIMEOUT: Duration = Duration::from_millis(1_500);
 const DEFAULT_MAXIMUM_CONNECTION_BUFFER_SIZE: usize = 128;
+const DEFAULT_IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const DEFAULT_MAXIMUM_OUTBOUND_CONNECTIONS: usize = 1024;
+const DEFAULT_PROCESSING_QUEUE_SIZE: usize = 1024;
+const DEFAULT_SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_MAXIMUM_DELAYED_PACKETS: usize = 1_000_000;
+const DEFAULT_MAXIMUM_INCOMING_CONNECTIONS: usize = 2048;
+const DEFAULT_MAXIMUM_INCOMING_CONNECTIONS_PER_IP: usize = 16;
+const DEFAULT_MAXIMUM_PACKETS_PER_SECOND: u32 = 10_000;
+const DEFAULT_UNKNOWN_PEER_MAXIMUM_INCOMING_CONNECTIONS_PER_IP: usize = 2;
+const DEFAULT_UNKNOWN_PEER_MAXIMUM_PACKETS_PER_SECOND: u32 = 100;
+const DEFAULT_VPN_KEY_CACHE_CAPACITY: usize = 50_000;
+const DEFAULT_VPN_KEY_CACHE_ENTRY_TTL: Duration = Duration::from_secs(10 * 60);
 
 pub fn missing_string_value<T: From<String>>() -> T {
     MISSING_VALUE.to_string().into()
@@ -69,6 +83,24 @@ where
     }
 }
 
+pub(super) fn de_node_packet_sizes<'de, D>(deserializer: D) -> Result<Vec<PacketSize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let packet_sizes = Vec::<PacketSize>::deserialize(deserializer)?;
+    if !packet_sizes.contains(&PacketSize::AckPacket) {
+        return Err(serde::de::Error::custom(
+            "the ack packet size must be among the accepted packet sizes",
+        ));
+    }
+    if !packet_sizes.iter().any(|size| size.is_message_packet()) {
+        return Err(serde::de::Error::custom(
+            "at least a single message packet size must be accepted",
+        ));
+    }
+    Ok(packet_sizes)
+}
+
 #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
@@ -186,6 +218,11 @@ impl Config {
         self
     }
 
+    pub fn with_noise_mode(mut self, noise_mode: NoiseMode) -> Self {
+        self.mixnode.noise_mode = noise_mode;
+        self
+    }
+
     pub fn announce_address_from_listening_address(mut self) -> Self {
         self.mixnode.announce_address = self.mixnode.listening_address.to_string();
         self
@@ -249,6 +286,10 @@ impl Config {
         self.mixnode.http_api_port
     }
 
+    pub fn get_noise_mode(&self) -> NoiseMode {
+        self.mixnode.noise_mode
+    }
+
     pub fn get_packet_forwarding_initial_backoff(&self) -> Duration {
         self.debug.packet_forwarding_initial_backoff
     }
@@ -265,6 +306,66 @@ impl Config {
         self.debug.maximum_connection_buffer_size
     }
 
+    pub fn get_idle_connection_timeout(&self) -> Duration {
+        self.debug.idle_connection_timeout
+    }
+
+    pub fn get_maximum_outbound_connections(&self) -> usize {
+        self.debug.maximum_outbound_connections
+    }
+
+    pub fn get_processing_workers(&self) -> usize {
+        if self.debug.processing_workers == 0 {
+            num_cpus::get()
+        } else {
+            self.debug.processing_workers
+        }
+    }
+
+    pub fn get_processing_queue_size(&self) -> usize {
+        self.debug.processing_queue_size
+    }
+
+    pub fn get_shutdown_drain_timeout(&self) -> Duration {
+        self.debug.shutdown_drain_timeout
+    }
+
+    pub fn get_maximum_delayed_packets(&self) -> usize {
+        self.debug.maximum_delayed_packets
+    }
+
+    pub fn get_maximum_incoming_connections(&self) -> usize {
+        self.debug.maximum_incoming_connections
+    }
+
+    pub fn get_maximum_incoming_connections_per_ip(&self) -> usize {
+        self.debug.maximum_incoming_connections_per_ip
+    }
+
+    pub fn get_maximum_packets_per_second(&self) -> u32 {
+        self.debug.maximum_packets_per_second
+    }
+
+    pub fn get_unknown_peer_maximum_incoming_connections_per_ip(&self) -> usize {
+        self.debug.unknown_peer_maximum_incoming_connections_per_ip
+    }
+
+    pub fn get_unknown_peer_maximum_packets_per_second(&self) -> u32 {
+        self.debug.unknown_peer_maximum_packets_per_second
+    }
+
+    pub fn get_vpn_key_cache_capacity(&self) -> usize {
+        self.debug.vpn_key_cache_capacity
+    }
+
+    pub fn get_vpn_key_cache_entry_ttl(&self) -> Duration {
+        self.debug.vpn_key_cache_entry_ttl
+    }
+
+    pub fn get_packet_sizes(&self) -> Vec<PacketSize> {
+        self.debug.packet_sizes.clone()
+    }
+
     pub fn get_version(&self) -> &str {
         &self.mixnode.version
     }
@@ -331,6 +432,13 @@ pub struct MixNode {
     #[serde(default = "default_http_api_port")]
     http_api_port: u16,
 
+    /// Determines whether connections with other nodes are encrypted with the noise protocol.
+    /// 'optional' attempts the handshake, but falls back to plaintext for nodes not supporting it,
+    /// while 'required' refuses any plaintext connections.
+    /// (default: optional)
+    #[serde(default)]
+    noise_mode: NoiseMode,
+
     /// Path to file containing private identity key.
     #[serde(default = "missing_string_value")]
     private_identity_key_file: PathBuf,
@@ -381,6 +489,7 @@ impl Default for MixNode {
             mix_port: DEFAULT_MIX_LISTENING_PORT,
             verloc_port: DEFAULT_VERLOC_LISTENING_PORT,
             http_api_port: DEFAULT_HTTP_API_LISTENING_PORT,
+            noise_mode: Default::default(),
             private_identity_key_file: Default::default(),
             public_identity_key_file: Default::default(),
             private_sphinx_key_file: Default::default(),
@@ -468,6 +577,67 @@ pub struct Debug {
 
     /// Maximum number of packets that can be stored waiting to get sent to a particular connection.
     maximum_connection_buffer_size: usize,
+
+    /// Outbound connections that were not used for this long get closed, unless they are
+    /// to nodes in the next layer of the topology.
+    #[serde(with = "humantime_serde")]
+    idle_connection_timeout: Duration,
+
+    /// Maximum number of simultaneously open outbound connections. Once reached, the least recently
+    /// used connection gets closed. If set to 0, the number of connections is not limited.
+    maximum_outbound_connections: usize,
+
+    /// Number of threads dedicated to sphinx processing of received packets.
+    /// If set to 0, a thread per available cpu core is used.
+    processing_workers: usize,
+
+    /// Maximum number of received packets that can be waiting for sphinx processing.
+    /// Once it is reached, no more packets are read from the connections until the workers catch up.
+    processing_queue_size: usize,
+
+    /// Maximum amount of time the node keeps forwarding the already delayed packets after
+    /// receiving a shutdown signal.
+    #[serde(with = "humantime_serde")]
+    shutdown_drain_timeout: Duration,
+
+    /// Maximum number of packets that can be getting delayed at once.
+    /// Once it is reached, any new packets get dropped.
+    maximum_delayed_packets: usize,
+
+    /// Maximum number of simultaneous incoming connections.
+    maximum_incoming_connections: usize,
+
+    /// Maximum number of simultaneous incoming connections from a single ip address
+    /// belonging to a node present in the topology.
+    maximum_incoming_connections_per_ip: usize,
+
+    /// Maximum number of packets per second read from a single connection of a node present
+    /// in the topology. If set to 0, the rate is not limited.
+    maximum_packets_per_second: u32,
+
+    /// Maximum number of simultaneous incoming connections from a single ip address
+    /// not belonging to any node in the topology. If set to 0, such connections are refused.
+    unknown_peer_maximum_incoming_connections_per_ip: usize,
+
+    /// Maximum number of packets per second read from a single connection of an ip address
+    /// not belonging to any node in the topology. If set to 0, the rate is not limited.
+    unknown_peer_maximum_packets_per_second: u32,
+
+    /// Maximum number of sessions of the `Vpn` mode packets for which the derived keys are cached.
+    /// If set to 0, every such packet goes through the full sphinx key derivation.
+    vpn_key_cache_capacity: usize,
+
+    /// Amount of time for which keys derived for the `Vpn` mode packets are cached.
+    #[serde(with = "humantime_serde")]
+    vpn_key_cache_entry_ttl: Duration,
+
+    /// Sizes of the sphinx packets this node accepts from and forwards to other nodes.
+    /// They are announced to other nodes when establishing connections and exposed
+    /// on the `/packet-sizes` endpoint of the http api, so that the clients could choose
+    /// the sizes supported by the nodes on their routes. Must include the ack packet size.
+    /// Apart from the built-in sizes, custom ones can be defined by their `id` and `plaintext_size`.
+    #[serde(deserialize_with = "de_node_packet_sizes")]
+    packet_sizes: Vec<PacketSize>,
 }
 
 impl Default for Debug {
@@ -479,6 +649,22 @@ impl Default for Debug {
             packet_forwarding_maximum_backoff: DEFAULT_PACKET_FORWARDING_MAXIMUM_BACKOFF,
             initial_connection_timeout: DEFAULT_INITIAL_CONNECTION_TIMEOUT,
             maximum_connection_buffer_size: DEFAULT_MAXIMUM_CONNECTION_BUFFER_SIZE,
+            idle_connection_timeout: DEFAULT_IDLE_CONNECTION_TIMEOUT,
+            maximum_outbound_connections: DEFAULT_MAXIMUM_OUTBOUND_CONNECTIONS,
+            processing_workers: 0,
+            processing_queue_size: DEFAULT_PROCESSING_QUEUE_SIZE,
+            shutdown_drain_timeout: DEFAULT_SHUTDOWN_DRAIN_TIMEOUT,
+            maximum_delayed_packets: DEFAULT_MAXIMUM_DELAYED_PACKETS,
+            maximum_incoming_connections: DEFAULT_MAXIMUM_INCOMING_CONNECTIONS,
+            maximum_incoming_connections_per_ip: DEFAULT_MAXIMUM_INCOMING_CONNECTIONS_PER_IP,
+            maximum_packets_per_second: DEFAULT_MAXIMUM_PACKETS_PER_SECOND,
+            unknown_peer_maximum_incoming_connections_per_ip:
+                DEFAULT_UNKNOWN_PEER_MAXIMUM_INCOMING_CONNECTIONS_PER_IP,
+            unknown_peer_maximum_packets_per_second:
+                DEFAULT_UNKNOWN_PEER_MAXIMUM_PACKETS_PER_SECOND,
+            vpn_key_cache_capacity: DEFAULT_VPN_KEY_CACHE_CAPACITY,
+            vpn_key_cache_entry_ttl: DEFAULT_VPN_KEY_CACHE_ENTRY_TTL,
+            packet_sizes: PacketSize::all().collect(),
         }
     }
 }
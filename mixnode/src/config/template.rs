@@ -53,6 +53,12 @@ verloc_port = {{ mixnode.verloc_port }}
 # (default: 8000)
 http_api_port = {{ mixnode.http_api_port }}
 
+# Determines whether connections with other nodes are encrypted with the noise protocol.
+# 'optional' attempts the handshake, but falls back to plaintext for nodes not supporting it,
+# while 'required' refuses any plaintext connections. Can also be set to 'disabled'.
+# (default: optional)
+noise_mode = '{{ mixnode.noise_mode }}'
+
 # Addresses to APIs running on validator from which the node gets the view of the network.
 validator_api_urls = [
     {{#each mixnode.validator_api_urls }}
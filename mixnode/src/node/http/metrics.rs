@@ -0,0 +1,24 @@
+use crate::node::http::verloc::VerlocState;
+use crate::node::metrics::NodeMetrics;
+use crate::node::node_statistics::NodeStatsWrapper;
+use mixnet_client::ConnectionPoolStats;
+use rocket::http::ContentType;
+use rocket::State;
+
+/// Returns the node metrics in the Prometheus text exposition format.
+#[get("/metrics")]
+pub(crate) async fn metrics(
+    metrics: &State<NodeMetrics>,
+    stats: &State<NodeStatsWrapper>,
+    verloc: &State<VerlocState>,
+    connections: &State<ConnectionPoolStats>,
+) -> (ContentType, String) {
+    let stats = stats.clone_data().await;
+    let verloc = verloc.clone_data().await;
+    let connections = connections.snapshot();
+
+    (
+        ContentType::Plain,
+        metrics.encode(&stats, &verloc, &connections),
+    )
+}
@@ -1,4 +1,6 @@
 pub(crate) mod description;
+pub(crate) mod metrics;
+pub(crate) mod packet_sizes;
 pub(crate) mod stats;
 pub(crate) mod verloc;
 
@@ -0,0 +1,20 @@
+use nymsphinx::params::PacketSize;
+use rocket::serde::json::Json;
+use rocket::State;
+
+pub(crate) struct PacketSizesState {
+    packet_sizes: Vec<PacketSize>,
+}
+
+impl PacketSizesState {
+    pub fn new(packet_sizes: Vec<PacketSize>) -> Self {
+        PacketSizesState { packet_sizes }
+    }
+}
+
+/// Returns sizes of the sphinx packets this node accepts, so that the clients could avoid
+/// sending it packets it's going to drop.
+#[get("/packet-sizes")]
+pub(crate) fn packet_sizes(state: &State<PacketSizesState>) -> Json<Vec<PacketSize>> {
+    Json(state.packet_sizes.clone())
+}
@@ -1,4 +1,5 @@
 use crate::node::node_statistics::{NodeStats, NodeStatsSimple, NodeStatsWrapper};
+use mixnet_client::{ConnectionPoolSnapshot, ConnectionPoolStats};
 use rocket::serde::json::Json;
 use rocket::State;
 use serde::Serialize;
@@ -10,20 +11,36 @@ pub(crate) enum NodeStatsResponse {
     Simple(NodeStatsSimple),
 }
 
-/// Returns a running stats of the node.
+#[derive(Serialize)]
+pub(crate) struct StatsResponse {
+    #[serde(flatten)]
+    node: NodeStatsResponse,
+    outbound_connections: ConnectionPoolSnapshot,
+}
+
+/// Returns a running stats of the node alongside the state of its outbound connections.
 #[get("/stats?<debug>")]
 pub(crate) async fn stats(
     stats: &State<NodeStatsWrapper>,
+    connections: &State<ConnectionPoolStats>,
     debug: Option<bool>,
-) -> Json<NodeStatsResponse> {
+) -> Json<StatsResponse> {
     let snapshot_data = stats.clone_data().await;
+    let mut outbound_connections = connections.snapshot();
 
     // there's no point in returning the entire hashmap of sending destinations in regular mode
     if let Some(debug) = debug {
         if debug {
-            return Json(NodeStatsResponse::Full(snapshot_data));
+            return Json(StatsResponse {
+                node: NodeStatsResponse::Full(snapshot_data),
+                outbound_connections,
+            });
         }
     }
 
-    Json(NodeStatsResponse::Simple(snapshot_data.simplify()))
+    outbound_connections.connections.clear();
+    Json(StatsResponse {
+        node: NodeStatsResponse::Simple(snapshot_data.simplify()),
+        outbound_connections,
+    })
 }
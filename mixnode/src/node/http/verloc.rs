@@ -12,6 +12,10 @@ impl VerlocState {
             shared: atomic_verloc_result,
         }
     }
+
+    pub(crate) async fn clone_data(&self) -> VerlocResult {
+        self.shared.clone_data().await
+    }
 }
 
 /// Provides verifiable location (verloc) measurements for this mixnode - a list of the
@@ -1,9 +1,11 @@
 // Copyright 2020 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::node::metrics::NodeMetrics;
 use crate::node::node_statistics;
 use crypto::asymmetric::encryption;
 use mixnode_common::packet_processor::error::MixProcessingError;
+use mixnode_common::packet_processor::key_cache::KeyCache;
 pub use mixnode_common::packet_processor::processor::MixProcessingResult;
 use mixnode_common::packet_processor::processor::SphinxPacketProcessor;
 use nymsphinx::framing::packet::FramedSphinxPacket;
@@ -16,16 +18,22 @@ pub struct PacketProcessor {
 
     /// Responsible for updating metrics data
     node_stats_update_sender: node_statistics::UpdateSender,
+
+    /// Responsible for keeping track of processing failures
+    node_metrics: NodeMetrics,
 }
 
 impl PacketProcessor {
     pub(crate) fn new(
         encryption_key: &encryption::PrivateKey,
+        vpn_key_cache: KeyCache,
         node_stats_update_sender: node_statistics::UpdateSender,
+        node_metrics: NodeMetrics,
     ) -> Self {
         PacketProcessor {
-            inner_processor: SphinxPacketProcessor::new(encryption_key.into()),
+            inner_processor: SphinxPacketProcessor::new(encryption_key.into(), vpn_key_cache),
             node_stats_update_sender,
+            node_metrics,
         }
     }
 
@@ -34,6 +42,11 @@ impl PacketProcessor {
         received: FramedSphinxPacket,
     ) -> Result<MixProcessingResult, MixProcessingError> {
         self.node_stats_update_sender.report_received();
-        self.inner_processor.process_received(received)
+        self.inner_processor
+            .process_received(received)
+            .map_err(|err| {
+                self.node_metrics.report_processing_error(&err);
+                err
+            })
     }
 }
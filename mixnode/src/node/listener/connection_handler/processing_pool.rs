@@ -0,0 +1,148 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::node::listener::connection_handler::packet_processing::{
+    MixProcessingResult, PacketProcessor,
+};
+use crate::node::node_statistics;
+use crate::node::packet_delayforwarder::PacketDelayForwardSender;
+use log::*;
+use nymsphinx::framing::packet::FramedSphinxPacket;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+type ProcessingQueueReceiver = Arc<Mutex<mpsc::Receiver<FramedSphinxPacket>>>;
+
+/// Handle used by the connection handlers to submit received packets for processing.
+#[derive(Clone)]
+pub(crate) struct ProcessingPoolSender {
+    sender: mpsc::Sender<FramedSphinxPacket>,
+    queue_depth: Arc<AtomicUsize>,
+}
+
+impl ProcessingPoolSender {
+    /// Submits the packet for processing. If all workers are busy and the queue is full,
+    /// waits until there is space available, thus applying backpressure to the connection.
+    pub(crate) async fn submit(&self, packet: FramedSphinxPacket) {
+        self.queue_depth.fetch_add(1, Ordering::Relaxed);
+        // if send() failed it means all the workers have died
+        // and hence something weird must have happened without a way of recovering
+        if self.sender.send(packet).await.is_err() {
+            panic!("all sphinx processing workers have died!")
+        }
+    }
+}
+
+/// Dedicated pool of threads performing the expensive sphinx unwrapping of packets
+/// received on all connections.
+pub(crate) struct ProcessingPool {
+    workers: usize,
+    queue_size: usize,
+    packet_processor: PacketProcessor,
+    delay_forwarding_channel: PacketDelayForwardSender,
+    node_stats_update_sender: node_statistics::UpdateSender,
+}
+
+impl ProcessingPool {
+    pub(crate) fn new(
+        workers: usize,
+        queue_size: usize,
+        packet_processor: PacketProcessor,
+        delay_forwarding_channel: PacketDelayForwardSender,
+        node_stats_update_sender: node_statistics::UpdateSender,
+    ) -> Self {
+        assert!(
+            workers > 0,
+            "at least a single processing worker is required"
+        );
+        assert!(queue_size > 0, "the processing queue can't be empty");
+
+        ProcessingPool {
+            workers,
+            queue_size,
+            packet_processor,
+            delay_forwarding_channel,
+            node_stats_update_sender,
+        }
+    }
+
+    pub(crate) fn start(self) -> ProcessingPoolSender {
+        info!("Starting {} sphinx processing workers", self.workers);
+
+        let (sender, receiver) = mpsc::channel(self.queue_size);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let queue_depth = Arc::new(AtomicUsize::new(0));
+
+        for id in 0..self.workers {
+            let worker = Worker {
+                receiver: Arc::clone(&receiver),
+                queue_depth: Arc::clone(&queue_depth),
+                packet_processor: self.packet_processor.clone(),
+                delay_forwarding_channel: self.delay_forwarding_channel.clone(),
+                node_stats_update_sender: self.node_stats_update_sender.clone(),
+            };
+            thread::Builder::new()
+                .name(format!("sphinx-worker-{}", id))
+                .spawn(move || worker.run())
+                .expect("failed to spawn sphinx processing worker");
+        }
+
+        ProcessingPoolSender {
+            sender,
+            queue_depth,
+        }
+    }
+}
+
+struct Worker {
+    receiver: ProcessingQueueReceiver,
+    queue_depth: Arc<AtomicUsize>,
+    packet_processor: PacketProcessor,
+    delay_forwarding_channel: PacketDelayForwardSender,
+    node_stats_update_sender: node_statistics::UpdateSender,
+}
+
+impl Worker {
+    fn next_packet(&self) -> Option<FramedSphinxPacket> {
+        // the lock is only held by the worker waiting for the next packet,
+        // the remaining ones are either busy processing or waiting for the lock
+        let packet = self.receiver.lock().unwrap().blocking_recv()?;
+        let queue_depth = self.queue_depth.fetch_sub(1, Ordering::Relaxed) - 1;
+        self.node_stats_update_sender
+            .report_processing_queue_depth(queue_depth);
+        Some(packet)
+    }
+
+    fn process_packet(&self, packet: FramedSphinxPacket) {
+        match self.packet_processor.process_received(packet) {
+            Err(e) => debug!("We failed to process received sphinx packet - {:?}", e),
+            Ok(res) => match res {
+                MixProcessingResult::ForwardHop(forward_packet, delay) => {
+                    // determine instant at which packet should get forwarded. this way we minimise effect of
+                    // being stuck in the queue [of the channel] to get inserted into the delay queue
+                    let forward_instant = delay.map(|delay| Instant::now() + delay.to_duration());
+
+                    // if unbounded_send() failed it means that the receiver channel was disconnected
+                    // and hence something weird must have happened without a way of recovering
+                    self.delay_forwarding_channel
+                        .unbounded_send((forward_packet, forward_instant))
+                        .expect("the delay-forwarder has died!");
+                }
+                MixProcessingResult::FinalHop(..) => {
+                    warn!("Somehow processed a loop cover message that we haven't implemented yet!")
+                }
+            },
+        }
+        self.node_stats_update_sender.report_processed();
+    }
+
+    fn run(self) {
+        while let Some(packet) = self.next_packet() {
+            self.process_packet(packet)
+        }
+        debug!("processing queue got closed - the worker is finished");
+    }
+}
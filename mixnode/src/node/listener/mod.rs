@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::node::listener::connection_handler::ConnectionHandler;
+use crate::node::shutdown::ShutdownListener;
 use log::error;
+use mixnode_common::admission::ConnectionAdmission;
 use std::net::SocketAddr;
 use std::process;
 use tokio::net::TcpListener;
@@ -12,14 +14,15 @@ pub(crate) mod connection_handler;
 
 pub(crate) struct Listener {
     address: SocketAddr,
+    admission: ConnectionAdmission,
 }
 
 impl Listener {
-    pub(crate) fn new(address: SocketAddr) -> Self {
-        Listener { address }
+    pub(crate) fn new(address: SocketAddr, admission: ConnectionAdmission) -> Self {
+        Listener { address, admission }
     }
 
-    async fn run(&mut self, connection_handler: ConnectionHandler) {
+    async fn run(&mut self, connection_handler: ConnectionHandler, mut shutdown: ShutdownListener) {
         let listener = match TcpListener::bind(self.address).await {
             Ok(listener) => listener,
             Err(err) => {
@@ -29,19 +32,38 @@ impl Listener {
         };
 
         loop {
-            match listener.accept().await {
-                Ok((socket, remote_addr)) => {
-                    let handler = connection_handler.clone();
-                    tokio::spawn(handler.handle_connection(socket, remote_addr));
+            tokio::select! {
+                connection = listener.accept() => match connection {
+                    Ok((socket, remote_addr)) => match self.admission.try_admit(remote_addr) {
+                        Ok(permit) => {
+                            let handler = connection_handler.clone();
+                            tokio::spawn(handler.handle_connection(
+                                socket,
+                                remote_addr,
+                                permit,
+                                shutdown.clone(),
+                            ));
+                        }
+                        // the socket is dropped, closing the connection
+                        Err(err) => debug!("Refusing connection from {} - {}", remote_addr, err),
+                    },
+                    Err(err) => warn!("Failed to accept incoming connection - {:?}", err),
+                },
+                _ = shutdown.recv() => {
+                    info!("Stopped accepting new connections");
+                    return;
                 }
-                Err(err) => warn!("Failed to accept incoming connection - {:?}", err),
             }
         }
     }
 
-    pub(crate) fn start(mut self, connection_handler: ConnectionHandler) -> JoinHandle<()> {
+    pub(crate) fn start(
+        mut self,
+        connection_handler: ConnectionHandler,
+        shutdown: ShutdownListener,
+    ) -> JoinHandle<()> {
         info!("Running mix listener on {:?}", self.address.to_string());
 
-        tokio::spawn(async move { self.run(connection_handler).await })
+        tokio::spawn(async move { self.run(connection_handler, shutdown).await })
     }
 }
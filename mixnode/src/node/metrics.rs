@@ -0,0 +1,314 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::node::node_statistics::NodeStats;
+use mixnet_client::{ConnectionPoolSnapshot, ConnectionStats};
+use mixnode_common::packet_processor::error::MixProcessingError;
+use mixnode_common::verloc::VerlocResult;
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+const METRICS_PREFIX: &str = "nym_mixnode";
+
+/// Runtime metrics of the node that are not part of the periodically updated `NodeStats`.
+#[derive(Clone, Default)]
+pub(crate) struct NodeMetrics {
+    inner: Arc<NodeMetricsInner>,
+}
+
+#[derive(Default)]
+struct NodeMetricsInner {
+    delay_queue_length: AtomicUsize,
+    processing_errors: Mutex<HashMap<&'static str, u64>>,
+}
+
+impl NodeMetrics {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn set_delay_queue_length(&self, length: usize) {
+        self.inner
+            .delay_queue_length
+            .store(length, Ordering::Relaxed)
+    }
+
+    pub(crate) fn report_processing_error(&self, err: &MixProcessingError) {
+        *self
+            .inner
+            .processing_errors
+            .lock()
+            .unwrap()
+            .entry(err.kind())
+            .or_insert(0) += 1;
+    }
+
+    /// Encodes all the metrics, alongside the provided node stats, verloc results and
+    /// outbound connections statistics, in the Prometheus text exposition format.
+    pub(crate) fn encode(
+        &self,
+        stats: &NodeStats,
+        verloc: &VerlocResult,
+        connections: &ConnectionPoolSnapshot,
+    ) -> String {
+        let mut encoder = Encoder::default();
+
+        encoder.header(
+            "packets_received_total",
+            "counter",
+            "Number of sphinx packets received since startup",
+        );
+        encoder.sample(
+            "packets_received_total",
+            &[],
+            stats.packets_received_since_startup(),
+        );
+
+        encoder.header(
+            "packets_sent_total",
+            "counter",
+            "Number of sphinx packets sent since startup, per destination",
+        );
+        for (destination, count) in stats.packets_sent_since_startup() {
+            encoder.sample(
+                "packets_sent_total",
+                &[("destination", destination.as_str())],
+                count,
+            );
+        }
+
+        encoder.header(
+            "packets_dropped_total",
+            "counter",
+            "Number of sphinx packets explicitly dropped since startup, per destination",
+        );
+        for (destination, count) in stats.packets_explicitly_dropped_since_startup() {
+            encoder.sample(
+                "packets_dropped_total",
+                &[("destination", destination.as_str())],
+                count,
+            );
+        }
+
+        encoder.header(
+            "packets_processed_total",
+            "counter",
+            "Number of sphinx packets that went through processing since startup",
+        );
+        encoder.sample(
+            "packets_processed_total",
+            &[],
+            stats.packets_processed_since_startup(),
+        );
+
+        encoder.header(
+            "processing_queue_depth",
+            "gauge",
+            "Number of received packets waiting for sphinx processing",
+        );
+        encoder.sample(
+            "processing_queue_depth",
+            &[],
+            stats.processing_queue_depth(),
+        );
+
+        encoder.header(
+            "delay_queue_length",
+            "gauge",
+            "Number of packets currently being delayed",
+        );
+        encoder.sample(
+            "delay_queue_length",
+            &[],
+            self.inner.delay_queue_length.load(Ordering::Relaxed),
+        );
+
+        encoder.header(
+            "packet_processing_errors_total",
+            "counter",
+            "Number of received packets that failed to get processed, per error kind",
+        );
+        for (kind, count) in self.inner.processing_errors.lock().unwrap().iter() {
+            encoder.sample("packet_processing_errors_total", &[("kind", *kind)], count);
+        }
+
+        self.encode_connections(&mut encoder, connections);
+        self.encode_verloc(&mut encoder, verloc);
+
+        encoder.finish()
+    }
+
+    fn encode_connections(&self, encoder: &mut Encoder, connections: &ConnectionPoolSnapshot) {
+        encoder.header(
+            "active_outbound_connections",
+            "gauge",
+            "Number of currently established connections to other nodes",
+        );
+        encoder.sample(
+            "active_outbound_connections",
+            &[],
+            connections.active_connections,
+        );
+
+        encoder.header(
+            "pooled_outbound_connections",
+            "gauge",
+            "Number of connections to other nodes kept in the pool, regardless of their state",
+        );
+        encoder.sample(
+            "pooled_outbound_connections",
+            &[],
+            connections.pooled_connections,
+        );
+
+        let per_connection: [(&str, &str, &str, fn(&ConnectionStats) -> u64); 4] = [
+            (
+                "outbound_connection_packets_queued",
+                "gauge",
+                "Number of packets waiting to be sent, per destination",
+                |stats| stats.queued as u64,
+            ),
+            (
+                "outbound_connection_packets_sent_total",
+                "counter",
+                "Number of packets written to the connection, per destination",
+                |stats| stats.sent,
+            ),
+            (
+                "outbound_connection_packets_dropped_total",
+                "counter",
+                "Number of packets dropped by the connection, per destination",
+                |stats| stats.dropped,
+            ),
+            (
+                "outbound_connection_reconnects_total",
+                "counter",
+                "Number of times the connection had to be re-established, per destination",
+                |stats| stats.reconnects,
+            ),
+        ];
+        for (name, metric_type, help, value) in per_connection.iter() {
+            encoder.header(name, metric_type, help);
+            for (destination, stats) in &connections.connections {
+                encoder.sample(name, &[("destination", destination.as_str())], value(stats));
+            }
+        }
+    }
+
+    fn encode_verloc(&self, encoder: &mut Encoder, verloc: &VerlocResult) {
+        encoder.header(
+            "verloc_tested_nodes",
+            "gauge",
+            "Number of nodes tested in the latest verloc measurement run",
+        );
+        encoder.sample("verloc_tested_nodes", &[], verloc.total_tested());
+
+        if let Some(finished) = verloc.run_finished() {
+            let timestamp = finished
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            encoder.header(
+                "verloc_last_run_finished_timestamp_seconds",
+                "gauge",
+                "Unix time at which the latest verloc measurement run finished",
+            );
+            encoder.sample("verloc_last_run_finished_timestamp_seconds", &[], timestamp);
+        }
+
+        encoder.header(
+            "verloc_rtt_seconds",
+            "gauge",
+            "Round trip times to other nodes measured in the latest verloc measurement run",
+        );
+        for result in verloc.results() {
+            let measurement = match result.latest_measurement {
+                Some(measurement) => measurement,
+                None => continue,
+            };
+            let identity = result.identity.to_base58_string();
+            for (statistic, value) in &[
+                ("minimum", measurement.minimum),
+                ("mean", measurement.mean),
+                ("maximum", measurement.maximum),
+                ("standard_deviation", measurement.standard_deviation),
+            ] {
+                encoder.sample(
+                    "verloc_rtt_seconds",
+                    &[("identity", identity.as_str()), ("statistic", *statistic)],
+                    value.as_secs_f64(),
+                );
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Encoder {
+    output: String,
+}
+
+impl Encoder {
+    fn header(&mut self, name: &str, metric_type: &str, help: &str) {
+        // writing to a String can't fail
+        writeln!(self.output, "# HELP {}_{} {}", METRICS_PREFIX, name, help).unwrap();
+        writeln!(
+            self.output,
+            "# TYPE {}_{} {}",
+            METRICS_PREFIX, name, metric_type
+        )
+        .unwrap();
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl Display) {
+        write!(self.output, "{}_{}", METRICS_PREFIX, name).unwrap();
+        if !labels.is_empty() {
+            let labels = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape_label_value(value)))
+                .collect::<Vec<_>>()
+                .join(",");
+            write!(self.output, "{{{}}}", labels).unwrap();
+        }
+        writeln!(self.output, " {}", value).unwrap();
+    }
+
+    fn finish(self) -> String {
+        self.output
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_samples_in_text_format() {
+        let mut encoder = Encoder::default();
+        encoder.header("foo_total", "counter", "Some foos");
+        encoder.sample("foo_total", &[], 42);
+        encoder.sample("foo_total", &[("kind", "a\"b"), ("other", "c")], 1.5);
+
+        let expected = "# HELP nym_mixnode_foo_total Some foos\n\
+            # TYPE nym_mixnode_foo_total counter\n\
+            nym_mixnode_foo_total 42\n\
+            nym_mixnode_foo_total{kind=\"a\\\"b\",other=\"c\"} 1.5\n";
+        assert_eq!(expected, encoder.finish());
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!("foo", escape_label_value("foo"));
+        assert_eq!("a\\\\b\\nc", escape_label_value("a\\b\nc"));
+    }
+}
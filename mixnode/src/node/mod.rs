@@ -4,33 +4,46 @@
 use crate::config::Config;
 use crate::node::http::{
     description::description,
+    metrics::metrics,
     not_found,
+    packet_sizes::{packet_sizes, PacketSizesState},
     stats::stats,
     verloc::{verloc as verlocRoute, VerlocState},
 };
 use crate::node::listener::connection_handler::packet_processing::PacketProcessor;
+use crate::node::listener::connection_handler::processing_pool::ProcessingPool;
 use crate::node::listener::connection_handler::ConnectionHandler;
 use crate::node::listener::Listener;
-use crate::node::node_description::NodeDescription;
+use crate::node::metrics::NodeMetrics;
 use crate::node::node_statistics::NodeStatsWrapper;
 use crate::node::packet_delayforwarder::{DelayForwarder, PacketDelayForwardSender};
+use crate::node::shutdown::{shutdown_channel, ShutdownListener};
 use crypto::asymmetric::{encryption, identity};
 use log::{error, info, warn};
+use mixnet_client::{ConnectionPoolStats, TopologyPeers};
+use mixnode_common::admission::{AdmissionConfig, ConnectionAdmission};
+use mixnode_common::known_nodes::{KnownNodesRefresher, NodeRole};
+use mixnode_common::node_description::NodeDescription;
+use mixnode_common::packet_processor::key_cache::KeyCache;
 use mixnode_common::verloc::{self, AtomicVerlocResult, VerlocMeasurer};
+use nymnoise::{KnownNodes, NoiseConfig};
+use nymsphinx::framing::hello::Capabilities;
+use nymsphinx::params::PacketSize;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 use std::net::SocketAddr;
 use std::process;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
 use version_checker::parse_version;
 
 pub(crate) mod http;
 mod listener;
-// mod metrics;
-pub(crate) mod node_description;
+pub(crate) mod metrics;
 pub(crate) mod node_statistics;
 pub(crate) mod packet_delayforwarder;
+mod shutdown;
 
 // the MixNode will live for whole duration of this program
 pub struct MixNode {
@@ -59,6 +72,8 @@ impl MixNode {
         &self,
         atomic_verloc_result: AtomicVerlocResult,
         node_stats_pointer: NodeStatsWrapper,
+        node_metrics: NodeMetrics,
+        connection_pool_stats: ConnectionPoolStats,
     ) {
         info!("Starting HTTP API on http://localhost:8000");
 
@@ -69,16 +84,23 @@ impl MixNode {
         config.port = self.config.get_http_api_port();
 
         let verloc_state = VerlocState::new(atomic_verloc_result);
+        let packet_sizes_state = PacketSizesState::new(self.config.get_packet_sizes());
         let descriptor = self.descriptor.clone();
 
         tokio::spawn(async move {
             rocket::build()
                 .configure(config)
-                .mount("/", routes![verlocRoute, description, stats])
+                .mount(
+                    "/",
+                    routes![verlocRoute, description, stats, metrics, packet_sizes],
+                )
                 .register("/", catchers![not_found])
                 .manage(verloc_state)
+                .manage(packet_sizes_state)
                 .manage(descriptor)
                 .manage(node_stats_pointer)
+                .manage(node_metrics)
+                .manage(connection_pool_stats)
                 .launch()
                 .await
         });
@@ -99,41 +121,122 @@ impl MixNode {
     fn start_socket_listener(
         &self,
         node_stats_update_sender: node_statistics::UpdateSender,
+        node_metrics: NodeMetrics,
         delay_forwarding_channel: PacketDelayForwardSender,
+        known_nodes: KnownNodes,
+        shutdown: ShutdownListener,
     ) {
         info!("Starting socket listener...");
 
-        let packet_processor =
-            PacketProcessor::new(self.sphinx_keypair.private_key(), node_stats_update_sender);
+        let vpn_key_cache = KeyCache::new(
+            self.config.get_vpn_key_cache_capacity(),
+            self.config.get_vpn_key_cache_entry_ttl(),
+        );
+        let packet_processor = PacketProcessor::new(
+            self.sphinx_keypair.private_key(),
+            vpn_key_cache,
+            node_stats_update_sender.clone(),
+            node_metrics,
+        );
 
-        let connection_handler = ConnectionHandler::new(packet_processor, delay_forwarding_channel);
+        let processing_pool = ProcessingPool::new(
+            self.config.get_processing_workers(),
+            self.config.get_processing_queue_size(),
+            packet_processor,
+            delay_forwarding_channel,
+            node_stats_update_sender,
+        )
+        .start();
+
+        let connection_handler =
+            ConnectionHandler::new(processing_pool, self.noise_config(known_nodes.clone()));
+
+        let admission_config = AdmissionConfig::new()
+            .maximum_connections(self.config.get_maximum_incoming_connections())
+            .maximum_connections_per_ip(self.config.get_maximum_incoming_connections_per_ip())
+            .maximum_packets_per_second(self.config.get_maximum_packets_per_second())
+            .unknown_peer_maximum_connections_per_ip(
+                self.config
+                    .get_unknown_peer_maximum_incoming_connections_per_ip(),
+            )
+            .unknown_peer_maximum_packets_per_second(
+                self.config.get_unknown_peer_maximum_packets_per_second(),
+            );
+        let admission = ConnectionAdmission::new(admission_config, known_nodes);
 
         let listening_address = SocketAddr::new(
             self.config.get_listening_address(),
             self.config.get_mix_port(),
         );
 
-        Listener::new(listening_address).start(connection_handler);
+        Listener::new(listening_address, admission).start(connection_handler, shutdown);
     }
 
     fn start_packet_delay_forwarder(
         &mut self,
         node_stats_update_sender: node_statistics::UpdateSender,
-    ) -> PacketDelayForwardSender {
+        node_metrics: NodeMetrics,
+        noise_config: NoiseConfig,
+        topology_peers: TopologyPeers,
+        shutdown: ShutdownListener,
+    ) -> (
+        PacketDelayForwardSender,
+        ConnectionPoolStats,
+        JoinHandle<usize>,
+    ) {
         info!("Starting packet delay-forwarder...");
 
-        let mut packet_forwarder = DelayForwarder::new(
+        let client_config = mixnet_client::Config::new(
             self.config.get_packet_forwarding_initial_backoff(),
             self.config.get_packet_forwarding_maximum_backoff(),
             self.config.get_initial_connection_timeout(),
             self.config.get_maximum_connection_buffer_size(),
+            noise_config,
+        )
+        .with_idle_connection_timeout(self.config.get_idle_connection_timeout())
+        .with_maximum_connections(self.config.get_maximum_outbound_connections())
+        .with_topology_peers(topology_peers);
+
+        let mut packet_forwarder = DelayForwarder::new(
+            client_config,
+            self.config.get_maximum_delayed_packets(),
             node_stats_update_sender,
+            node_metrics,
         );
 
         let packet_sender = packet_forwarder.sender();
+        let pool_stats = packet_forwarder.pool_stats();
+        let drain_timeout = self.config.get_shutdown_drain_timeout();
 
-        tokio::spawn(async move { packet_forwarder.run().await });
-        packet_sender
+        let handle =
+            tokio::spawn(async move { packet_forwarder.run(shutdown, drain_timeout).await });
+        (packet_sender, pool_stats, handle)
+    }
+
+    fn start_known_nodes_refresher(&self, topology_peers: TopologyPeers) -> KnownNodes {
+        info!("Starting known nodes refresher...");
+        let known_nodes = KnownNodes::new();
+        let role = NodeRole::Mixnode {
+            identity_key: self.identity_keypair.public_key().to_base58_string(),
+        };
+        let mut refresher = KnownNodesRefresher::new(
+            self.config.get_validator_api_endpoints(),
+            known_nodes.clone(),
+        )
+        .with_topology_peers(topology_peers, role);
+        tokio::spawn(async move { refresher.run().await });
+        known_nodes
+    }
+
+    fn noise_config(&self, known_nodes: KnownNodes) -> NoiseConfig {
+        NoiseConfig::new(
+            Arc::clone(&self.sphinx_keypair),
+            self.config.get_noise_mode(),
+            known_nodes,
+        )
+        .with_capabilities(
+            Capabilities::current().with_packet_sizes(&self.config.get_packet_sizes()),
+        )
     }
 
     fn start_verloc_measurements(&self) -> AtomicVerlocResult {
@@ -199,21 +302,51 @@ impl MixNode {
             .map(|node| node.mix_node.identity_key.clone())
     }
 
+    #[cfg(unix)]
+    async fn wait_for_terminate() {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                error!("There was an error while capturing SIGTERM - {:?}", err);
+                futures::future::pending::<()>().await
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn wait_for_terminate() {
+        futures::future::pending::<()>().await
+    }
+
     async fn wait_for_interrupt(&self) {
-        if let Err(e) = tokio::signal::ctrl_c().await {
-            error!(
-                "There was an error while capturing SIGINT - {:?}. We will terminate regardless",
-                e
-            );
+        tokio::select! {
+            res = tokio::signal::ctrl_c() => {
+                if let Err(e) = res {
+                    error!(
+                        "There was an error while capturing SIGINT - {:?}. We will terminate regardless",
+                        e
+                    );
+                }
+                println!("Received SIGINT - the mixnode will now shut down.");
+            }
+            _ = Self::wait_for_terminate() => {
+                println!("Received SIGTERM - the mixnode will now shut down.");
+            }
         }
-        println!(
-            "Received SIGINT - the mixnode will terminate now (threads are not yet nicely stopped, if you see stack traces that's alright)."
-        );
     }
 
     pub fn run(&mut self) {
         info!("Starting nym mixnode");
 
+        if let Err(err) = PacketSize::register_all(self.config.get_packet_sizes()) {
+            error!("Failed to register the configured packet sizes - {}", err);
+            process::exit(1);
+        }
+
         let runtime = Runtime::new().unwrap();
 
         runtime.block_on(async {
@@ -230,14 +363,26 @@ impl MixNode {
             }
 
             let (node_stats_pointer, node_stats_update_sender) = self.start_node_stats_controller();
-            let delay_forwarding_channel = self.start_packet_delay_forwarder(node_stats_update_sender.clone());
-            self.start_socket_listener(node_stats_update_sender, delay_forwarding_channel);
+            let node_metrics = NodeMetrics::new();
+            let topology_peers = TopologyPeers::new();
+            let known_nodes = self.start_known_nodes_refresher(topology_peers.clone());
+            let (shutdown_notifier, shutdown_listener) = shutdown_channel();
+            let (delay_forwarding_channel, connection_pool_stats, delay_forwarder_handle) = self.start_packet_delay_forwarder(node_stats_update_sender.clone(), node_metrics.clone(), self.noise_config(known_nodes.clone()), topology_peers, shutdown_listener.clone());
+            self.start_socket_listener(node_stats_update_sender, node_metrics.clone(), delay_forwarding_channel, known_nodes, shutdown_listener);
 
             let atomic_verloc_results= self.start_verloc_measurements();
-            self.start_http_api(atomic_verloc_results, node_stats_pointer);
+            self.start_http_api(atomic_verloc_results, node_stats_pointer, node_metrics, connection_pool_stats);
 
             info!("Finished nym mixnode startup procedure - it should now be able to receive mix traffic!");
-            self.wait_for_interrupt().await
+            self.wait_for_interrupt().await;
+
+            // stop accepting new packets and forward whatever we still have
+            shutdown_notifier.notify();
+            match delay_forwarder_handle.await {
+                Ok(0) => info!("All delayed packets got forwarded"),
+                Ok(dropped) => warn!("{} packets got dropped during the shutdown", dropped),
+                Err(err) => error!("The delay-forwarder has failed during the shutdown - {}", err),
+            }
         });
     }
 }
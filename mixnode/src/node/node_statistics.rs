@@ -4,7 +4,7 @@ use futures::StreamExt;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::ops::DerefMut;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::sync::{RwLock, RwLockReadGuard};
@@ -29,19 +29,25 @@ impl NodeStatsWrapper {
                 packets_received_since_startup: 0,
                 packets_sent_since_startup: HashMap::new(),
                 packets_explicitly_dropped_since_startup: HashMap::new(),
+                packets_processed_since_startup: 0,
                 packets_received_since_last_update: 0,
                 packets_sent_since_last_update: HashMap::new(),
                 packets_explicitly_dropped_since_last_update: HashMap::new(),
+                packets_processed_since_last_update: 0,
+                processing_queue_depth: 0,
             })),
         }
     }
 
-    pub(crate) async fn update(
-        &self,
-        new_received: u64,
-        new_sent: PacketsMap,
-        new_dropped: PacketsMap,
-    ) {
+    pub(crate) async fn update(&self, new_data: PacketData) {
+        let PacketData {
+            received: new_received,
+            sent: new_sent,
+            dropped: new_dropped,
+            processed: new_processed,
+            processing_queue_depth,
+        } = new_data;
+
         let mut guard = self.inner.write().await;
         let snapshot_time = SystemTime::now();
 
@@ -49,6 +55,7 @@ impl NodeStatsWrapper {
         guard.update_time = snapshot_time;
 
         guard.packets_received_since_startup += new_received;
+        guard.packets_processed_since_startup += new_processed;
         for (mix, count) in new_sent.iter() {
             *guard
                 .packets_sent_since_startup
@@ -58,7 +65,7 @@ impl NodeStatsWrapper {
 
         for (mix, count) in new_dropped.iter() {
             *guard
-                .packets_explicitly_dropped_since_last_update
+                .packets_explicitly_dropped_since_startup
                 .entry(mix.clone())
                 .or_insert(0) += *count;
         }
@@ -66,6 +73,8 @@ impl NodeStatsWrapper {
         guard.packets_received_since_last_update = new_received;
         guard.packets_sent_since_last_update = new_sent;
         guard.packets_explicitly_dropped_since_last_update = new_dropped;
+        guard.packets_processed_since_last_update = new_processed;
+        guard.processing_queue_depth = processing_queue_depth;
     }
 
     pub(crate) async fn clone_data(&self) -> NodeStats {
@@ -93,6 +102,9 @@ pub(crate) struct NodeStats {
     // we know for sure we dropped packets to those destinations
     packets_explicitly_dropped_since_startup: PacketsMap,
 
+    // packets that went through sphinx processing, regardless of whether it was successful
+    packets_processed_since_startup: u64,
+
     packets_received_since_last_update: u64,
 
     // note: sent does not imply forwarded. We don't know if it was delivered successfully
@@ -100,9 +112,34 @@ pub(crate) struct NodeStats {
 
     // we know for sure we dropped packets to those destinations
     packets_explicitly_dropped_since_last_update: PacketsMap,
+
+    packets_processed_since_last_update: u64,
+
+    // number of packets waiting for sphinx processing at the time of the update
+    processing_queue_depth: usize,
 }
 
 impl NodeStats {
+    pub(crate) fn packets_received_since_startup(&self) -> u64 {
+        self.packets_received_since_startup
+    }
+
+    pub(crate) fn packets_sent_since_startup(&self) -> &PacketsMap {
+        &self.packets_sent_since_startup
+    }
+
+    pub(crate) fn packets_explicitly_dropped_since_startup(&self) -> &PacketsMap {
+        &self.packets_explicitly_dropped_since_startup
+    }
+
+    pub(crate) fn packets_processed_since_startup(&self) -> u64 {
+        self.packets_processed_since_startup
+    }
+
+    pub(crate) fn processing_queue_depth(&self) -> usize {
+        self.processing_queue_depth
+    }
+
     pub(crate) fn simplify(&self) -> NodeStatsSimple {
         NodeStatsSimple {
             update_time: self.update_time,
@@ -113,12 +150,15 @@ impl NodeStats {
                 .packets_explicitly_dropped_since_startup
                 .values()
                 .sum(),
+            packets_processed_since_startup: self.packets_processed_since_startup,
             packets_received_since_last_update: self.packets_received_since_last_update,
             packets_sent_since_last_update: self.packets_sent_since_last_update.values().sum(),
             packets_explicitly_dropped_since_last_update: self
                 .packets_explicitly_dropped_since_last_update
                 .values()
                 .sum(),
+            packets_processed_since_last_update: self.packets_processed_since_last_update,
+            processing_queue_depth: self.processing_queue_depth,
         }
     }
 }
@@ -139,6 +179,8 @@ pub(crate) struct NodeStatsSimple {
     // we know for sure we dropped those packets
     packets_explicitly_dropped_since_startup: u64,
 
+    packets_processed_since_startup: u64,
+
     packets_received_since_last_update: u64,
 
     // note: sent does not imply forwarded. We don't know if it was delivered successfully
@@ -146,12 +188,27 @@ pub(crate) struct NodeStatsSimple {
 
     // we know for sure we dropped those packets
     packets_explicitly_dropped_since_last_update: u64,
+
+    packets_processed_since_last_update: u64,
+
+    processing_queue_depth: usize,
 }
 
 pub(crate) enum PacketEvent {
     Sent(String),
     Received,
     Dropped(String),
+    Processed,
+    ProcessingQueueDepth(usize),
+}
+
+/// Packet data gathered since the last stats update.
+pub(crate) struct PacketData {
+    received: u64,
+    sent: PacketsMap,
+    dropped: PacketsMap,
+    processed: u64,
+    processing_queue_depth: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -164,6 +221,8 @@ struct PacketDataInner {
     received: AtomicU64,
     sent: Mutex<PacketsMap>,
     dropped: Mutex<PacketsMap>,
+    processed: AtomicU64,
+    processing_queue_depth: AtomicUsize,
 }
 
 impl CurrentPacketData {
@@ -173,6 +232,8 @@ impl CurrentPacketData {
                 received: AtomicU64::new(0),
                 sent: Mutex::new(HashMap::new()),
                 dropped: Mutex::new(HashMap::new()),
+                processed: AtomicU64::new(0),
+                processing_queue_depth: AtomicUsize::new(0),
             }),
         }
     }
@@ -181,6 +242,16 @@ impl CurrentPacketData {
         self.inner.received.fetch_add(1, Ordering::SeqCst);
     }
 
+    fn increment_processed(&self) {
+        self.inner.processed.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn set_processing_queue_depth(&self, depth: usize) {
+        self.inner
+            .processing_queue_depth
+            .store(depth, Ordering::SeqCst);
+    }
+
     async fn increment_sent(&self, destination: String) {
         let mut unlocked = self.inner.sent.lock().await;
         let receiver_count = unlocked.entry(destination).or_insert(0);
@@ -193,15 +264,22 @@ impl CurrentPacketData {
         *dropped_count += 1;
     }
 
-    async fn acquire_and_reset(&self) -> (u64, PacketsMap, PacketsMap) {
+    async fn acquire_and_reset(&self) -> PacketData {
         let mut unlocked_sent = self.inner.sent.lock().await;
         let mut unlocked_dropped = self.inner.dropped.lock().await;
         let received = self.inner.received.swap(0, Ordering::SeqCst);
+        let processed = self.inner.processed.swap(0, Ordering::SeqCst);
 
         let sent = std::mem::take(unlocked_sent.deref_mut());
         let dropped = std::mem::take(unlocked_dropped.deref_mut());
 
-        (received, sent, dropped)
+        PacketData {
+            received,
+            sent,
+            dropped,
+            processed,
+            processing_queue_depth: self.inner.processing_queue_depth.load(Ordering::SeqCst),
+        }
     }
 }
 
@@ -228,6 +306,10 @@ impl UpdateHandler {
                 PacketEvent::Dropped(destination) => {
                     self.current_data.increment_dropped(destination).await
                 }
+                PacketEvent::Processed => self.current_data.increment_processed(),
+                PacketEvent::ProcessingQueueDepth(depth) => {
+                    self.current_data.set_processing_queue_depth(depth)
+                }
             }
         }
     }
@@ -264,6 +346,20 @@ impl UpdateSender {
             .unbounded_send(PacketEvent::Dropped(destination))
             .unwrap()
     }
+
+    pub(crate) fn report_processed(&self) {
+        // in unbounded_send() failed it means that the receiver channel was disconnected
+        // and hence something weird must have happened without a way of recovering
+        self.0.unbounded_send(PacketEvent::Processed).unwrap()
+    }
+
+    pub(crate) fn report_processing_queue_depth(&self, depth: usize) {
+        // in unbounded_send() failed it means that the receiver channel was disconnected
+        // and hence something weird must have happened without a way of recovering
+        self.0
+            .unbounded_send(PacketEvent::ProcessingQueueDepth(depth))
+            .unwrap()
+    }
 }
 
 struct StatsUpdater {
@@ -287,8 +383,8 @@ impl StatsUpdater {
 
     async fn update_stats(&self) {
         // grab new data since last update
-        let (received, sent, dropped) = self.current_packet_data.acquire_and_reset().await;
-        self.current_stats.update(received, sent, dropped).await;
+        let new_data = self.current_packet_data.acquire_and_reset().await;
+        self.current_stats.update(new_data).await;
     }
 
     async fn run(&self) {
@@ -350,6 +446,13 @@ impl PacketStatsConsoleLogger {
                 stats.packets_received_since_last_update,
                 difference_secs,
             );
+            debug!(
+                "Since startup processed {} packets ({} in last {} seconds). {} packets are waiting to get processed",
+                stats.packets_processed_since_startup,
+                stats.packets_processed_since_last_update,
+                difference_secs,
+                stats.processing_queue_depth,
+            );
             trace!(
                 "Since startup sent packets to the following: \n{:#?} \n And in last {} seconds: {:#?})",
                 stats.packets_sent_since_startup,
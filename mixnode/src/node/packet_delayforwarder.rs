@@ -1,13 +1,21 @@
 // Copyright 2020 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::node::metrics::NodeMetrics;
 use crate::node::node_statistics::UpdateSender;
+use crate::node::shutdown::ShutdownListener;
 use futures::channel::mpsc;
 use futures::StreamExt;
-use nonexhaustive_delayqueue::{Expired, NonExhaustiveDelayQueue, TimerError};
+use log::*;
+use mixnet_client::client::CONNECTION_MAINTENANCE_INTERVAL;
+use mixnet_client::ConnectionPoolStats;
+use nonexhaustive_delayqueue::TimingWheelDelayQueue;
 use nymsphinx::forwarding::packet::MixPacket;
 use std::io;
-use tokio::time::{Duration, Instant};
+use tokio::time::{interval, sleep_until, timeout, Duration, Instant};
+
+// when draining, how long to wait for any packets still being processed after the delay queue got emptied
+const DRAINING_GRACE_PERIOD: Duration = Duration::from_millis(500);
 
 // Delay + MixPacket vs Instant + MixPacket
 
@@ -18,36 +26,30 @@ type PacketDelayForwardReceiver = mpsc::UnboundedReceiver<(MixPacket, Option<Ins
 
 /// Entity responsible for delaying received sphinx packet and forwarding it to next node.
 pub(crate) struct DelayForwarder {
-    delay_queue: NonExhaustiveDelayQueue<MixPacket>,
+    delay_queue: TimingWheelDelayQueue<MixPacket>,
     mixnet_client: mixnet_client::Client,
     packet_sender: PacketDelayForwardSender,
     packet_receiver: PacketDelayForwardReceiver,
     node_stats_update_sender: UpdateSender,
+    node_metrics: NodeMetrics,
 }
 
 impl DelayForwarder {
     pub(crate) fn new(
-        initial_reconnection_backoff: Duration,
-        maximum_reconnection_backoff: Duration,
-        initial_connection_timeout: Duration,
-        maximum_connection_buffer_size: usize,
+        client_config: mixnet_client::Config,
+        maximum_delayed_packets: usize,
         node_stats_update_sender: UpdateSender,
+        node_metrics: NodeMetrics,
     ) -> Self {
-        let client_config = mixnet_client::Config::new(
-            initial_reconnection_backoff,
-            maximum_reconnection_backoff,
-            initial_connection_timeout,
-            maximum_connection_buffer_size,
-        );
-
         let (packet_sender, packet_receiver) = mpsc::unbounded();
 
         DelayForwarder {
-            delay_queue: NonExhaustiveDelayQueue::new(),
+            delay_queue: TimingWheelDelayQueue::new().with_maximum_len(maximum_delayed_packets),
             mixnet_client: mixnet_client::Client::new(client_config),
             packet_sender,
             packet_receiver,
             node_stats_update_sender,
+            node_metrics,
         }
     }
 
@@ -55,6 +57,10 @@ impl DelayForwarder {
         self.packet_sender.clone()
     }
 
+    pub(crate) fn pool_stats(&self) -> ConnectionPoolStats {
+        self.mixnet_client.pool_stats()
+    }
+
     fn forward_packet(&mut self, packet: MixPacket) {
         let next_hop = packet.next_hop();
         let packet_mode = packet.packet_mode();
@@ -82,12 +88,9 @@ impl DelayForwarder {
     }
 
     /// Upon packet being finished getting delayed, forward it to the mixnet.
-    fn handle_done_delaying(&mut self, packet: Option<Result<Expired<MixPacket>, TimerError>>) {
-        // those are critical errors that I don't think can be recovered from.
-        let delayed = packet.expect("the queue has unexpectedly terminated!");
-        let delayed_packet = delayed
-            .expect("Encountered timer issue within the runtime!")
-            .into_inner();
+    fn handle_done_delaying(&mut self, packet: Option<MixPacket>) {
+        // this is impossible as the queue never terminates
+        let delayed_packet = packet.expect("the queue has unexpectedly terminated!");
 
         self.forward_packet(delayed_packet)
     }
@@ -100,15 +103,74 @@ impl DelayForwarder {
             // the delay queue only to retrieve it immediately. Just forward it.
             if instant.checked_duration_since(Instant::now()).is_none() {
                 self.forward_packet(new_packet.0)
-            } else {
-                self.delay_queue.insert_at(new_packet.0, instant);
+            } else if let Err(packet) = self.delay_queue.insert_at(new_packet.0, instant) {
+                debug!("The delay queue is full - dropping the packet");
+                self.node_stats_update_sender
+                    .report_dropped(packet.next_hop().to_string());
             }
         } else {
             self.forward_packet(new_packet.0)
         }
     }
 
-    pub(crate) async fn run(&mut self) {
+    fn update_metrics(&self) {
+        self.node_metrics
+            .set_delay_queue_length(self.delay_queue.len());
+    }
+
+    /// Keeps forwarding the delayed packets until either all of them got sent or the deadline
+    /// is reached. Afterwards closes all the outbound connections and returns the number
+    /// of packets that got dropped.
+    async fn drain(&mut self, deadline: Instant) -> usize {
+        info!(
+            "Draining {} delayed packets before shutting down...",
+            self.delay_queue.len()
+        );
+
+        let deadline_sleep = sleep_until(deadline);
+        tokio::pin!(deadline_sleep);
+
+        loop {
+            if self.delay_queue.is_empty() {
+                // give the processing workers a moment to hand over any packets they might still have
+                let grace_period = std::cmp::min(
+                    DRAINING_GRACE_PERIOD,
+                    deadline.saturating_duration_since(Instant::now()),
+                );
+                match timeout(grace_period, self.packet_receiver.next()).await {
+                    Ok(Some(new_packet)) => self.handle_new_packet(new_packet),
+                    _ => break,
+                }
+                continue;
+            }
+
+            tokio::select! {
+                delayed = self.delay_queue.next() => {
+                    self.handle_done_delaying(delayed);
+                }
+                new_packet = self.packet_receiver.next() => {
+                    // see the comment in `run`
+                    self.handle_new_packet(new_packet.unwrap())
+                }
+                _ = &mut deadline_sleep => break,
+            }
+            self.update_metrics();
+        }
+
+        let dropped_delayed = self.delay_queue.len();
+        let dropped_unsent = self.mixnet_client.close_connections(deadline).await;
+        dropped_delayed + dropped_unsent
+    }
+
+    /// Runs until the shutdown is signalled and afterwards attempts to forward all the packets
+    /// that are still being delayed for up to `drain_timeout`. Returns the number of packets that
+    /// got dropped during the shutdown.
+    pub(crate) async fn run(
+        &mut self,
+        mut shutdown: ShutdownListener,
+        drain_timeout: Duration,
+    ) -> usize {
+        let mut maintenance_interval = interval(CONNECTION_MAINTENANCE_INTERVAL);
         loop {
             tokio::select! {
                 delayed = self.delay_queue.next() => {
@@ -119,7 +181,14 @@ impl DelayForwarder {
                     // and hence it can't happen that ALL senders are dropped
                     self.handle_new_packet(new_packet.unwrap())
                 }
+                _ = maintenance_interval.tick() => {
+                    self.mixnet_client.maintain_connections();
+                }
+                _ = shutdown.recv() => break,
             }
+            self.update_metrics();
         }
+
+        self.drain(Instant::now() + drain_timeout).await
     }
 }
@@ -0,0 +1,34 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use tokio::sync::watch;
+
+pub(crate) fn shutdown_channel() -> (ShutdownNotifier, ShutdownListener) {
+    let (sender, receiver) = watch::channel(false);
+    (ShutdownNotifier(sender), ShutdownListener(receiver))
+}
+
+/// Used to signal all the tasks of the node that it is shutting down.
+pub(crate) struct ShutdownNotifier(watch::Sender<bool>);
+
+impl ShutdownNotifier {
+    pub(crate) fn notify(&self) {
+        // it can only fail if all listeners were dropped, in which case there's nobody to notify
+        let _ = self.0.send(true);
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ShutdownListener(watch::Receiver<bool>);
+
+impl ShutdownListener {
+    /// Resolves once the shutdown has been signalled.
+    pub(crate) async fn recv(&mut self) {
+        while !*self.0.borrow() {
+            if self.0.changed().await.is_err() {
+                // the notifier got dropped without signalling anything - treat it as a shutdown
+                return;
+            }
+        }
+    }
+}
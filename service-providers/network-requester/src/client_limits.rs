@@ -0,0 +1,672 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nymsphinx::addressing::clients::Recipient;
+use socks5_requests::{ConnectionId, ProtocolVersion};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub(crate) const DEFAULT_MAX_CONCURRENT_CONNECTIONS: usize = 128;
+pub(crate) const DEFAULT_MAX_NEW_CONNECTIONS_PER_MINUTE: usize = 256;
+pub(crate) const DEFAULT_MAX_BANDWIDTH_PER_WINDOW: u64 = 512 * 1024 * 1024;
+pub(crate) const DEFAULT_BANDWIDTH_WINDOW: Duration = Duration::from_secs(60);
+
+const CONNECTION_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Maximum number of bytes, across all connections, that can be received for connections
+/// that have not been opened (yet).
+const MAX_UNCLAIMED_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Amount of time after which data received for a connection that never got opened is forgotten.
+const UNCLAIMED_DATA_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often clients without any recent activity are removed from the limiter.
+pub(crate) const STALE_CLIENTS_PRUNING_INTERVAL: Duration = Duration::from_secs(60);
+
+type ClientKey = [u8; Recipient::LEN];
+
+/// Limits applied independently to each return address making use of this network requester.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ClientLimits {
+    /// Maximum number of outbound connections a single client can have open at any given time.
+    pub(crate) max_concurrent_connections: usize,
+
+    /// Maximum number of new outbound connections a single client can open within a minute.
+    pub(crate) max_new_connections_per_minute: usize,
+
+    /// Maximum number of bytes, in both directions, a single client can proxy
+    /// within `bandwidth_window`.
+    pub(crate) max_bandwidth_per_window: u64,
+
+    /// Length of the window over which the bandwidth usage is accounted for.
+    pub(crate) bandwidth_window: Duration,
+}
+
+impl Default for ClientLimits {
+    fn default() -> Self {
+        ClientLimits {
+            max_concurrent_connections: DEFAULT_MAX_CONCURRENT_CONNECTIONS,
+            max_new_connections_per_minute: DEFAULT_MAX_NEW_CONNECTIONS_PER_MINUTE,
+            max_bandwidth_per_window: DEFAULT_MAX_BANDWIDTH_PER_WINDOW,
+            bandwidth_window: DEFAULT_BANDWIDTH_WINDOW,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum LimitExceeded {
+    ConcurrentConnections(usize),
+    ConnectionRate(usize),
+    Bandwidth(u64, Duration),
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitExceeded::ConcurrentConnections(limit) => {
+                write!(f, "exceeded the limit of {} concurrent connections", limit)
+            }
+            LimitExceeded::ConnectionRate(limit) => {
+                write!(
+                    f,
+                    "exceeded the limit of {} new connections per minute",
+                    limit
+                )
+            }
+            LimitExceeded::Bandwidth(limit, window) => write!(
+                f,
+                "exceeded the bandwidth limit of {} bytes per {:?}",
+                limit, window
+            ),
+        }
+    }
+}
+
+/// Reason for refusing to open a new connection.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ConnectionRefusal {
+    /// A connection with the same id is already open.
+    DuplicateConnection(ConnectionId),
+
+    /// Opening the connection would have put the client above its limits.
+    LimitExceeded(LimitExceeded),
+}
+
+impl Display for ConnectionRefusal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionRefusal::DuplicateConnection(conn_id) => {
+                write!(
+                    f,
+                    "tried to open connection {} that is already open",
+                    conn_id
+                )
+            }
+            ConnectionRefusal::LimitExceeded(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<LimitExceeded> for ConnectionRefusal {
+    fn from(err: LimitExceeded) -> Self {
+        ConnectionRefusal::LimitExceeded(err)
+    }
+}
+
+/// Result of accounting for data proxied on a connection.
+#[derive(Debug, PartialEq)]
+pub(crate) enum BandwidthCheck {
+    /// The data is within the client's limits.
+    Allowed,
+
+    /// The connection has not been opened yet, which might happen as the mix network can reorder
+    /// messages. The data is going to be charged to the client once it opens the connection.
+    Unclaimed,
+
+    /// The connection is not known and there is no room left for any more unclaimed data -
+    /// the data should be dropped.
+    UnknownConnection,
+
+    /// The data pushed the client over its limit - the connection should get terminated.
+    Exceeded(LimitExceeded),
+
+    /// The connection has already been terminated for exceeding the limit - the data should be dropped.
+    Terminated,
+}
+
+struct ClientUsage {
+    connections: HashMap<ConnectionId, ProtocolVersion>,
+    recent_connections: VecDeque<Instant>,
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl ClientUsage {
+    fn new(now: Instant) -> Self {
+        ClientUsage {
+            connections: HashMap::new(),
+            recent_connections: VecDeque::new(),
+            window_start: now,
+            window_bytes: 0,
+        }
+    }
+
+    fn prune_recent_connections(&mut self, now: Instant) {
+        while let Some(opened) = self.recent_connections.front() {
+            if now.duration_since(*opened) >= CONNECTION_RATE_WINDOW {
+                self.recent_connections.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Accounts for `bytes` being proxied by this client. Returns false if this puts the client
+    /// over its bandwidth limit.
+    fn try_consume_bandwidth(&mut self, bytes: u64, limits: &ClientLimits, now: Instant) -> bool {
+        if now.duration_since(self.window_start) >= limits.bandwidth_window {
+            self.window_start = now;
+            self.window_bytes = 0;
+        }
+
+        self.window_bytes += bytes;
+        self.window_bytes <= limits.max_bandwidth_per_window
+    }
+
+    fn is_stale(&mut self, now: Instant, bandwidth_window: Duration) -> bool {
+        self.prune_recent_connections(now);
+        self.connections.is_empty()
+            && self.recent_connections.is_empty()
+            && now.duration_since(self.window_start) >= bandwidth_window
+    }
+}
+
+/// Data received for a connection that has not been opened yet.
+struct UnclaimedData {
+    bytes: u64,
+    first_received: Instant,
+}
+
+struct ClientLimiterInner {
+    limits: ClientLimits,
+    clients: HashMap<ClientKey, ClientUsage>,
+
+    // `Send` requests only carry the connection id, so we need a way of finding the owner of
+    // the connection. Duplicate ids are refused, so that a client can't take over (or get
+    // charged for) a connection of somebody else.
+    connection_owners: HashMap<ConnectionId, Recipient>,
+    terminated_connections: HashSet<ConnectionId>,
+    unclaimed_data: HashMap<ConnectionId, UnclaimedData>,
+    unclaimed_bytes: u64,
+}
+
+impl ClientLimiterInner {
+    fn new(limits: ClientLimits) -> Self {
+        ClientLimiterInner {
+            limits,
+            clients: HashMap::new(),
+            connection_owners: HashMap::new(),
+            terminated_connections: HashSet::new(),
+            unclaimed_data: HashMap::new(),
+            unclaimed_bytes: 0,
+        }
+    }
+
+    fn prune_stale_clients(&mut self, now: Instant) {
+        let bandwidth_window = self.limits.bandwidth_window;
+        self.clients
+            .retain(|_, usage| !usage.is_stale(now, bandwidth_window));
+
+        let mut expired_bytes = 0;
+        self.unclaimed_data.retain(|_, unclaimed| {
+            let expired = now.duration_since(unclaimed.first_received) >= UNCLAIMED_DATA_TIMEOUT;
+            if expired {
+                expired_bytes += unclaimed.bytes;
+            }
+            !expired
+        });
+        self.unclaimed_bytes -= expired_bytes;
+    }
+
+    fn try_open_connection(
+        &mut self,
+        conn_id: ConnectionId,
+        client: &Recipient,
+        protocol_version: ProtocolVersion,
+        now: Instant,
+    ) -> Result<(), ConnectionRefusal> {
+        if self.connection_owners.contains_key(&conn_id) {
+            return Err(ConnectionRefusal::DuplicateConnection(conn_id));
+        }
+
+        let limits = self.limits;
+        let usage = self
+            .clients
+            .entry(client.to_bytes())
+            .or_insert_with(|| ClientUsage::new(now));
+        usage.prune_recent_connections(now);
+
+        if usage.connections.len() >= limits.max_concurrent_connections {
+            return Err(
+                LimitExceeded::ConcurrentConnections(limits.max_concurrent_connections).into(),
+            );
+        }
+        if usage.recent_connections.len() >= limits.max_new_connections_per_minute {
+            return Err(
+                LimitExceeded::ConnectionRate(limits.max_new_connections_per_minute).into(),
+            );
+        }
+
+        // any data that was received before the connection got opened belongs to this client
+        if let Some(unclaimed) = self.unclaimed_data.remove(&conn_id) {
+            self.unclaimed_bytes -= unclaimed.bytes;
+            if !usage.try_consume_bandwidth(unclaimed.bytes, &limits, now) {
+                return Err(LimitExceeded::Bandwidth(
+                    limits.max_bandwidth_per_window,
+                    limits.bandwidth_window,
+                )
+                .into());
+            }
+        }
+
+        usage.connections.insert(conn_id, protocol_version);
+        usage.recent_connections.push_back(now);
+        self.connection_owners.insert(conn_id, *client);
+        Ok(())
+    }
+
+    fn close_connection(&mut self, client: &Recipient, conn_id: ConnectionId) {
+        let key = client.to_bytes();
+        match self.connection_owners.get(&conn_id) {
+            Some(owner) if owner.to_bytes() == key => (),
+            _ => return,
+        }
+        self.connection_owners.remove(&conn_id);
+        self.terminated_connections.remove(&conn_id);
+        if let Some(usage) = self.clients.get_mut(&key) {
+            usage.connections.remove(&conn_id);
+        }
+    }
+
+    fn connection_owner(&self, conn_id: ConnectionId) -> Option<(Recipient, ProtocolVersion)> {
+        let owner = self.connection_owners.get(&conn_id)?;
+        let protocol_version = self
+            .clients
+            .get(&owner.to_bytes())?
+            .connections
+            .get(&conn_id)?;
+        Some((*owner, *protocol_version))
+    }
+
+    fn consume_unclaimed_bandwidth(
+        &mut self,
+        conn_id: ConnectionId,
+        bytes: u64,
+        now: Instant,
+    ) -> BandwidthCheck {
+        if self.unclaimed_bytes + bytes > MAX_UNCLAIMED_BYTES {
+            return BandwidthCheck::UnknownConnection;
+        }
+        self.unclaimed_bytes += bytes;
+        self.unclaimed_data
+            .entry(conn_id)
+            .or_insert(UnclaimedData {
+                bytes: 0,
+                first_received: now,
+            })
+            .bytes += bytes;
+        BandwidthCheck::Unclaimed
+    }
+
+    fn try_consume_bandwidth(
+        &mut self,
+        conn_id: ConnectionId,
+        bytes: usize,
+        now: Instant,
+    ) -> BandwidthCheck {
+        if self.terminated_connections.contains(&conn_id) {
+            return BandwidthCheck::Terminated;
+        }
+
+        let limits = self.limits;
+        let clients = &mut self.clients;
+        let usage = match self
+            .connection_owners
+            .get(&conn_id)
+            .and_then(|owner| clients.get_mut(&owner.to_bytes()))
+        {
+            Some(usage) => usage,
+            None => return self.consume_unclaimed_bandwidth(conn_id, bytes as u64, now),
+        };
+
+        if !usage.try_consume_bandwidth(bytes as u64, &limits, now) {
+            self.terminated_connections.insert(conn_id);
+            return BandwidthCheck::Exceeded(LimitExceeded::Bandwidth(
+                limits.max_bandwidth_per_window,
+                limits.bandwidth_window,
+            ));
+        }
+        BandwidthCheck::Allowed
+    }
+
+    fn try_consume_response_bandwidth(
+        &mut self,
+        client: &Recipient,
+        conn_id: ConnectionId,
+        bytes: usize,
+        now: Instant,
+    ) -> BandwidthCheck {
+        if self.terminated_connections.contains(&conn_id) {
+            return BandwidthCheck::Terminated;
+        }
+
+        // the connection might have already been closed by the time its final responses
+        // are sent back, but they still count towards the usage of the client
+        let limits = self.limits;
+        let usage = self
+            .clients
+            .entry(client.to_bytes())
+            .or_insert_with(|| ClientUsage::new(now));
+        if !usage.try_consume_bandwidth(bytes as u64, &limits, now) {
+            if usage.connections.contains_key(&conn_id) {
+                self.terminated_connections.insert(conn_id);
+            }
+            return BandwidthCheck::Exceeded(LimitExceeded::Bandwidth(
+                limits.max_bandwidth_per_window,
+                limits.bandwidth_window,
+            ));
+        }
+        BandwidthCheck::Allowed
+    }
+}
+
+/// Keeps track of resources used by each client (identified by its return address) and
+/// rejects connections and data exceeding the configured `ClientLimits`.
+// Note: you should NEVER create more than a single instance of this using 'new()'.
+// You should always use .clone() to create additional instances
+#[derive(Clone)]
+pub(crate) struct ClientLimiter {
+    inner: Arc<Mutex<ClientLimiterInner>>,
+}
+
+impl ClientLimiter {
+    pub(crate) fn new(limits: ClientLimits) -> Self {
+        ClientLimiter {
+            inner: Arc::new(Mutex::new(ClientLimiterInner::new(limits))),
+        }
+    }
+
+    /// Attempts to register new connection of the specified client using the specified
+    /// protocol version.
+    pub(crate) fn try_open_connection(
+        &self,
+        conn_id: ConnectionId,
+        client: &Recipient,
+        protocol_version: ProtocolVersion,
+    ) -> Result<(), ConnectionRefusal> {
+        self.inner.lock().unwrap().try_open_connection(
+            conn_id,
+            client,
+            protocol_version,
+            Instant::now(),
+        )
+    }
+
+    /// Removes the connection from the set of connections of the specified client.
+    pub(crate) fn close_connection(&self, client: &Recipient, conn_id: ConnectionId) {
+        self.inner.lock().unwrap().close_connection(client, conn_id)
+    }
+
+    /// Returns the client that has opened the specified connection alongside the protocol
+    /// version agreed on with it.
+    pub(crate) fn connection_owner(
+        &self,
+        conn_id: ConnectionId,
+    ) -> Option<(Recipient, ProtocolVersion)> {
+        self.inner.lock().unwrap().connection_owner(conn_id)
+    }
+
+    /// Accounts for `bytes` received from the mix network for the specified connection
+    /// on behalf of its owner.
+    pub(crate) fn try_consume_bandwidth(
+        &self,
+        conn_id: ConnectionId,
+        bytes: usize,
+    ) -> BandwidthCheck {
+        self.inner
+            .lock()
+            .unwrap()
+            .try_consume_bandwidth(conn_id, bytes, Instant::now())
+    }
+
+    /// Accounts for `bytes` being sent back to the specified client on the specified connection.
+    pub(crate) fn try_consume_response_bandwidth(
+        &self,
+        client: &Recipient,
+        conn_id: ConnectionId,
+        bytes: usize,
+    ) -> BandwidthCheck {
+        self.inner.lock().unwrap().try_consume_response_bandwidth(
+            client,
+            conn_id,
+            bytes,
+            Instant::now(),
+        )
+    }
+
+    /// Forgets about clients without any recent activity and about data received for
+    /// connections that never got opened.
+    pub(crate) fn prune_stale_clients(&self) {
+        self.inner
+            .lock()
+            .unwrap()
+            .prune_stale_clients(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_client() -> Recipient {
+        Recipient::try_from_base58_string("CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f").unwrap()
+    }
+
+    fn other_test_client() -> Recipient {
+        Recipient::try_from_base58_string("4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV").unwrap()
+    }
+
+    fn test_limits() -> ClientLimits {
+        ClientLimits {
+            max_concurrent_connections: 2,
+            max_new_connections_per_minute: 3,
+            max_bandwidth_per_window: 100,
+            bandwidth_window: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn rejects_connections_above_concurrent_limit() {
+        let mut limiter = ClientLimiterInner::new(test_limits());
+        let client = test_client();
+        let now = Instant::now();
+
+        assert!(limiter
+            .try_open_connection(1, &client, ProtocolVersion::V1, now)
+            .is_ok());
+        assert!(limiter
+            .try_open_connection(2, &client, ProtocolVersion::V1, now)
+            .is_ok());
+        assert_eq!(
+            Err(ConnectionRefusal::LimitExceeded(
+                LimitExceeded::ConcurrentConnections(2)
+            )),
+            limiter.try_open_connection(3, &client, ProtocolVersion::V1, now)
+        );
+
+        limiter.close_connection(&client, 1);
+        assert!(limiter
+            .try_open_connection(3, &client, ProtocolVersion::V1, now)
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_connections_above_rate_limit() {
+        let mut limiter = ClientLimiterInner::new(test_limits());
+        let client = test_client();
+        let now = Instant::now();
+
+        for conn_id in 0..3 {
+            assert!(limiter
+                .try_open_connection(conn_id, &client, ProtocolVersion::V1, now)
+                .is_ok());
+            limiter.close_connection(&client, conn_id);
+        }
+        assert_eq!(
+            Err(ConnectionRefusal::LimitExceeded(
+                LimitExceeded::ConnectionRate(3)
+            )),
+            limiter.try_open_connection(4, &client, ProtocolVersion::V1, now)
+        );
+
+        let later = now + CONNECTION_RATE_WINDOW;
+        assert!(limiter
+            .try_open_connection(4, &client, ProtocolVersion::V1, later)
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_data_above_bandwidth_limit() {
+        let mut limiter = ClientLimiterInner::new(test_limits());
+        let client = test_client();
+        let now = Instant::now();
+
+        limiter
+            .try_open_connection(1, &client, ProtocolVersion::V1, now)
+            .unwrap();
+        limiter
+            .try_open_connection(2, &client, ProtocolVersion::V1, now)
+            .unwrap();
+        assert_eq!(
+            BandwidthCheck::Allowed,
+            limiter.try_consume_bandwidth(1, 60, now)
+        );
+        assert_eq!(
+            BandwidthCheck::Exceeded(LimitExceeded::Bandwidth(100, Duration::from_secs(10))),
+            limiter.try_consume_bandwidth(2, 60, now)
+        );
+
+        // the connection remains terminated even after the window resets
+        let later = now + Duration::from_secs(10);
+        assert_eq!(
+            BandwidthCheck::Terminated,
+            limiter.try_consume_bandwidth(2, 60, later)
+        );
+        assert_eq!(
+            BandwidthCheck::Allowed,
+            limiter.try_consume_bandwidth(1, 60, later)
+        );
+    }
+
+    #[test]
+    fn refuses_duplicate_connections() {
+        let mut limiter = ClientLimiterInner::new(test_limits());
+        let client = test_client();
+        let other_client = other_test_client();
+        let now = Instant::now();
+
+        limiter
+            .try_open_connection(1, &client, ProtocolVersion::V1, now)
+            .unwrap();
+        assert_eq!(
+            Err(ConnectionRefusal::DuplicateConnection(1)),
+            limiter.try_open_connection(1, &other_client, ProtocolVersion::V1, now)
+        );
+        assert_eq!(
+            Err(ConnectionRefusal::DuplicateConnection(1)),
+            limiter.try_open_connection(1, &client, ProtocolVersion::V1, now)
+        );
+
+        // somebody else can't close the connection on behalf of its owner
+        limiter.close_connection(&other_client, 1);
+        let (owner, _) = limiter.connection_owner(1).unwrap();
+        assert_eq!(client.to_bytes(), owner.to_bytes());
+
+        limiter.close_connection(&client, 1);
+        assert!(limiter.connection_owner(1).is_none());
+        assert!(limiter
+            .try_open_connection(1, &other_client, ProtocolVersion::V1, now)
+            .is_ok());
+    }
+
+    #[test]
+    fn charges_unclaimed_data_once_connection_is_opened() {
+        let mut limiter = ClientLimiterInner::new(test_limits());
+        let client = test_client();
+        let now = Instant::now();
+
+        assert_eq!(
+            BandwidthCheck::Unclaimed,
+            limiter.try_consume_bandwidth(1, 60, now)
+        );
+        assert_eq!(
+            BandwidthCheck::Unclaimed,
+            limiter.try_consume_bandwidth(2, 60, now)
+        );
+        limiter
+            .try_open_connection(1, &client, ProtocolVersion::V1, now)
+            .unwrap();
+        assert_eq!(
+            Err(ConnectionRefusal::LimitExceeded(LimitExceeded::Bandwidth(
+                100,
+                Duration::from_secs(10)
+            ))),
+            limiter.try_open_connection(2, &client, ProtocolVersion::V1, now)
+        );
+        assert_eq!(0, limiter.unclaimed_bytes);
+    }
+
+    #[test]
+    fn rejects_unknown_connections_above_unclaimed_limit() {
+        let mut limiter = ClientLimiterInner::new(test_limits());
+        let now = Instant::now();
+
+        assert_eq!(
+            BandwidthCheck::Unclaimed,
+            limiter.try_consume_bandwidth(1, MAX_UNCLAIMED_BYTES as usize, now)
+        );
+        assert_eq!(
+            BandwidthCheck::UnknownConnection,
+            limiter.try_consume_bandwidth(2, 1, now)
+        );
+
+        // unclaimed data is eventually forgotten
+        limiter.prune_stale_clients(now + UNCLAIMED_DATA_TIMEOUT);
+        assert_eq!(
+            BandwidthCheck::Unclaimed,
+            limiter.try_consume_bandwidth(2, 1, now + UNCLAIMED_DATA_TIMEOUT)
+        );
+    }
+
+    #[test]
+    fn prunes_only_stale_clients() {
+        let mut limiter = ClientLimiterInner::new(test_limits());
+        let client = test_client();
+        let other_client = other_test_client();
+        let now = Instant::now();
+
+        limiter
+            .try_open_connection(1, &client, ProtocolVersion::V1, now)
+            .unwrap();
+        limiter
+            .try_open_connection(2, &other_client, ProtocolVersion::V1, now)
+            .unwrap();
+        limiter.close_connection(&other_client, 2);
+
+        limiter.prune_stale_clients(now + CONNECTION_RATE_WINDOW);
+        assert!(limiter.clients.contains_key(&client.to_bytes()));
+        assert!(!limiter.clients.contains_key(&other_client.to_bytes()));
+    }
+}
@@ -5,7 +5,7 @@ use futures::channel::mpsc;
 use nymsphinx::addressing::clients::Recipient;
 use proxy_helpers::connection_controller::ConnectionReceiver;
 use proxy_helpers::proxy_runner::ProxyRunner;
-use socks5_requests::{ConnectionId, RemoteAddress, Response};
+use socks5_requests::{ConnectionId, ProtocolVersion, RemoteAddress, Response};
 use std::io;
 use tokio::net::TcpStream;
 
@@ -18,6 +18,7 @@ pub(crate) struct Connection {
     address: RemoteAddress,
     conn: Option<TcpStream>,
     return_address: Recipient,
+    protocol_version: ProtocolVersion,
 }
 
 impl Connection {
@@ -25,6 +26,7 @@ impl Connection {
         id: ConnectionId,
         address: RemoteAddress,
         return_address: Recipient,
+        protocol_version: ProtocolVersion,
     ) -> io::Result<Self> {
         let conn = TcpStream::connect(&address).await?;
 
@@ -33,6 +35,7 @@ impl Connection {
             address,
             conn: Some(conn),
             return_address,
+            protocol_version,
         })
     }
 
@@ -52,9 +55,14 @@ impl Connection {
             mix_receiver,
             mix_sender,
             connection_id,
+            self.protocol_version,
         )
-        .run(move |conn_id, read_data, socket_closed| {
-            (Response::new(conn_id, read_data, socket_closed), recipient)
+        .run(move |conn_id, read_data, socket_closed, receive_window| {
+            let mut response = Response::new(conn_id, read_data, socket_closed);
+            if let Some(receive_window) = receive_window {
+                response = response.with_receive_window(receive_window);
+            }
+            (response, recipient)
         })
         .await
         .into_inner();
@@ -2,6 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::allowed_hosts::{HostsStore, OutboundRequestFilter};
+use crate::client_limits::{
+    BandwidthCheck, ClientLimiter, ClientLimits, STALE_CLIENTS_PRUNING_INTERVAL,
+};
 use crate::connection::Connection;
 use crate::websocket;
 use crate::websocket::TSWebsocketStream;
@@ -12,7 +15,7 @@ use log::*;
 use nymsphinx::addressing::clients::Recipient;
 use nymsphinx::receiver::ReconstructedMessage;
 use proxy_helpers::connection_controller::{Controller, ControllerCommand, ControllerSender};
-use socks5_requests::{ConnectionId, Request, Response};
+use socks5_requests::{ConnectionId, ProtocolVersion, Request, Response, CURRENT_PROTOCOL_VERSION};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio_tungstenite::tungstenite::protocol::Message;
@@ -26,10 +29,17 @@ pub struct ServiceProvider {
     listening_address: String,
     outbound_request_filter: OutboundRequestFilter,
     open_proxy: bool,
+    client_limiter: ClientLimiter,
+    max_buffered_bytes: usize,
 }
 
 impl ServiceProvider {
-    pub fn new(listening_address: String, open_proxy: bool) -> ServiceProvider {
+    pub fn new(
+        listening_address: String,
+        open_proxy: bool,
+        client_limits: ClientLimits,
+        max_buffered_bytes: usize,
+    ) -> ServiceProvider {
         let allowed_hosts = HostsStore::new(
             HostsStore::default_base_dir(),
             PathBuf::from("allowed.list"),
@@ -44,17 +54,56 @@ impl ServiceProvider {
             listening_address,
             outbound_request_filter,
             open_proxy,
+            client_limiter: ClientLimiter::new(client_limits),
+            max_buffered_bytes,
         }
     }
 
     /// Listens for any messages from `mix_reader` that should be written back to the mix network
     /// via the `websocket_writer`.
+    /// Responses produced by connections whose owners went over their bandwidth limit are replaced
+    /// with a single error response, with the connection itself being closed.
     async fn mixnet_response_listener(
         mut websocket_writer: SplitSink<TSWebsocketStream, Message>,
         mut mix_reader: mpsc::UnboundedReceiver<(Response, Recipient)>,
+        controller_sender: ControllerSender,
+        client_limiter: ClientLimiter,
     ) {
         // TODO: wire SURBs in here once they're available
-        while let Some((response, return_address)) = mix_reader.next().await {
+        while let Some((mut response, return_address)) = mix_reader.next().await {
+            // pongs are not associated with any connection
+            if !response.is_error() && !response.is_pong {
+                match client_limiter.try_consume_response_bandwidth(
+                    &return_address,
+                    response.connection_id,
+                    response.data.len(),
+                ) {
+                    // responses are always sent to a known client
+                    BandwidthCheck::Allowed
+                    | BandwidthCheck::Unclaimed
+                    | BandwidthCheck::UnknownConnection => (),
+                    BandwidthCheck::Exceeded(err) => {
+                        warn!(
+                            "Terminating connection {} as its owner {}",
+                            response.connection_id, err
+                        );
+                        controller_sender
+                            .unbounded_send(ControllerCommand::Close(response.connection_id))
+                            .unwrap();
+                        let protocol_version = client_limiter
+                            .connection_owner(response.connection_id)
+                            .map(|(_, protocol_version)| protocol_version)
+                            .unwrap_or_default();
+                        response = Response::new_refusal(
+                            response.connection_id,
+                            err.to_string(),
+                            protocol_version,
+                        );
+                    }
+                    BandwidthCheck::Terminated => continue,
+                }
+            }
+
             // make 'request' to native-websocket client
             let response_message = ClientRequest::Send {
                 recipient: return_address,
@@ -103,10 +152,19 @@ impl ServiceProvider {
         conn_id: ConnectionId,
         remote_addr: String,
         return_address: Recipient,
+        protocol_version: ProtocolVersion,
         controller_sender: ControllerSender,
         mix_input_sender: mpsc::UnboundedSender<(Response, Recipient)>,
+        client_limiter: ClientLimiter,
     ) {
-        let mut conn = match Connection::new(conn_id, remote_addr.clone(), return_address).await {
+        let mut conn = match Connection::new(
+            conn_id,
+            remote_addr.clone(),
+            return_address,
+            protocol_version,
+        )
+        .await
+        {
             Ok(conn) => conn,
             Err(err) => {
                 error!(
@@ -114,6 +172,7 @@ impl ServiceProvider {
                     remote_addr.clone(),
                     err
                 );
+                client_limiter.close_connection(&return_address, conn_id);
 
                 // inform the remote that the connection is closed before it even was established
                 mix_input_sender
@@ -144,6 +203,7 @@ impl ServiceProvider {
         controller_sender
             .unbounded_send(ControllerCommand::Remove(conn_id))
             .unwrap();
+        client_limiter.close_connection(&return_address, conn_id);
 
         let old_count = ACTIVE_PROXIES.fetch_sub(1, Ordering::SeqCst);
         info!(
@@ -160,14 +220,33 @@ impl ServiceProvider {
         conn_id: ConnectionId,
         remote_addr: String,
         return_address: Recipient,
+        protocol_version: ProtocolVersion,
     ) {
         if !self.open_proxy && !self.outbound_request_filter.check(&remote_addr) {
             log::info!("Domain {:?} failed filter check", remote_addr);
             return;
         }
 
+        if let Err(err) =
+            self.client_limiter
+                .try_open_connection(conn_id, &return_address, protocol_version)
+        {
+            info!(
+                "Refusing connection {} to {:?} as the client {}",
+                conn_id, remote_addr, err
+            );
+            mix_input_sender
+                .unbounded_send((
+                    Response::new_refusal(conn_id, err.to_string(), protocol_version),
+                    return_address,
+                ))
+                .unwrap();
+            return;
+        }
+
         let controller_sender_clone = controller_sender.clone();
         let mix_input_sender_clone = mix_input_sender.clone();
+        let client_limiter_clone = self.client_limiter.clone();
 
         // and start the proxy for this connection
         tokio::spawn(async move {
@@ -175,8 +254,10 @@ impl ServiceProvider {
                 conn_id,
                 remote_addr,
                 return_address,
+                protocol_version,
                 controller_sender_clone,
                 mix_input_sender_clone,
+                client_limiter_clone,
             )
             .await
         });
@@ -185,12 +266,55 @@ impl ServiceProvider {
     fn handle_proxy_send(
         &self,
         controller_sender: &mut ControllerSender,
+        mix_input_sender: &mpsc::UnboundedSender<(Response, Recipient)>,
         conn_id: ConnectionId,
         data: Vec<u8>,
         closed: bool,
+        remote_window: Option<u64>,
     ) {
+        match self
+            .client_limiter
+            .try_consume_bandwidth(conn_id, data.len())
+        {
+            BandwidthCheck::Allowed => (),
+            BandwidthCheck::Exceeded(err) => {
+                warn!("Terminating connection {} as its owner {}", conn_id, err);
+                if let Some((owner, protocol_version)) =
+                    self.client_limiter.connection_owner(conn_id)
+                {
+                    let response =
+                        Response::new_refusal(conn_id, err.to_string(), protocol_version);
+                    mix_input_sender.unbounded_send((response, owner)).unwrap();
+                }
+                controller_sender
+                    .unbounded_send(ControllerCommand::Close(conn_id))
+                    .unwrap();
+                return;
+            }
+            BandwidthCheck::Unclaimed => {
+                debug!(
+                    "Received data for connection {} before it got opened",
+                    conn_id
+                );
+            }
+            BandwidthCheck::UnknownConnection => {
+                warn!(
+                    "Dropping {} bytes received for unknown connection {}",
+                    data.len(),
+                    conn_id
+                );
+                return;
+            }
+            BandwidthCheck::Terminated => return,
+        }
+
         controller_sender
-            .unbounded_send(ControllerCommand::Send(conn_id, data, closed))
+            .unbounded_send(ControllerCommand::Send(
+                conn_id,
+                data,
+                closed,
+                remote_window,
+            ))
             .unwrap()
     }
 
@@ -214,15 +338,34 @@ impl ServiceProvider {
                 conn_id,
                 remote_addr,
                 return_address,
+                protocol_version,
             } => self.handle_proxy_connect(
                 controller_sender,
                 mix_input_sender,
                 conn_id,
                 remote_addr,
                 return_address,
+                ProtocolVersion::negotiate(protocol_version),
+            ),
+            Request::Send(conn_id, data, closed, remote_window) => self.handle_proxy_send(
+                controller_sender,
+                mix_input_sender,
+                conn_id,
+                data,
+                closed,
+                remote_window,
             ),
-            Request::Send(conn_id, data, closed) => {
-                self.handle_proxy_send(controller_sender, conn_id, data, closed)
+            Request::Ping {
+                ping_id,
+                return_address,
+            } => {
+                trace!("Received ping {}", ping_id);
+                mix_input_sender
+                    .unbounded_send((
+                        Response::new_pong(ping_id, CURRENT_PROTOCOL_VERSION),
+                        return_address,
+                    ))
+                    .unwrap();
             }
         }
     }
@@ -239,14 +382,33 @@ impl ServiceProvider {
         let (mix_input_sender, mix_input_receiver) = mpsc::unbounded::<(Response, Recipient)>();
 
         // controller for managing all active connections
-        let (mut active_connections_controller, mut controller_sender) = Controller::new();
+        let (mut active_connections_controller, mut controller_sender) =
+            Controller::new(self.max_buffered_bytes);
         tokio::spawn(async move {
             active_connections_controller.run().await;
         });
 
         // start the listener for mix messages
+        let response_controller_sender = controller_sender.clone();
+        let response_client_limiter = self.client_limiter.clone();
+        tokio::spawn(async move {
+            Self::mixnet_response_listener(
+                websocket_writer,
+                mix_input_receiver,
+                response_controller_sender,
+                response_client_limiter,
+            )
+            .await;
+        });
+
+        // periodically forget about clients that haven't been active recently
+        let pruning_client_limiter = self.client_limiter.clone();
         tokio::spawn(async move {
-            Self::mixnet_response_listener(websocket_writer, mix_input_receiver).await;
+            let mut interval = tokio::time::interval(STALE_CLIENTS_PRUNING_INTERVAL);
+            loop {
+                interval.tick().await;
+                pruning_client_limiter.prune_stale_clients();
+            }
         });
 
         println!("\nAll systems go. Press CTRL-C to stop the server.");
@@ -1,14 +1,25 @@
 // Copyright 2020 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::client_limits::ClientLimits;
 use clap::{App, Arg, ArgMatches};
+use ordered_buffer::DEFAULT_MAX_BUFFERED_BYTES;
+use std::process;
+use std::str::FromStr;
+use std::time::Duration;
 
 mod allowed_hosts;
+mod client_limits;
 mod connection;
 mod core;
 mod websocket;
 
 const OPEN_PROXY_ARG: &str = "open-proxy";
+const MAX_CONNECTIONS_ARG: &str = "max-connections-per-client";
+const MAX_NEW_CONNECTIONS_ARG: &str = "max-new-connections-per-minute";
+const MAX_BANDWIDTH_ARG: &str = "max-bandwidth-per-client";
+const BANDWIDTH_WINDOW_ARG: &str = "bandwidth-window";
+const MAX_BUFFERED_BYTES_ARG: &str = "max-buffered-bytes";
 
 fn parse_args<'a>() -> ArgMatches<'a> {
     App::new("Nym Network Requester")
@@ -20,9 +31,70 @@ fn parse_args<'a>() -> ArgMatches<'a> {
                 .long(OPEN_PROXY_ARG)
                 .short("o"),
         )
+        .arg(
+            Arg::with_name(MAX_CONNECTIONS_ARG)
+                .help("maximum number of concurrent connections a single client can have open")
+                .long(MAX_CONNECTIONS_ARG)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(MAX_NEW_CONNECTIONS_ARG)
+                .help("maximum number of new connections a single client can open within a minute")
+                .long(MAX_NEW_CONNECTIONS_ARG)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(MAX_BANDWIDTH_ARG)
+                .help(
+                    "maximum number of bytes a single client can proxy within the bandwidth window",
+                )
+                .long(MAX_BANDWIDTH_ARG)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(BANDWIDTH_WINDOW_ARG)
+                .help("length of the window (in seconds) over which client bandwidth is limited")
+                .long(BANDWIDTH_WINDOW_ARG)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(MAX_BUFFERED_BYTES_ARG)
+                .help("maximum number of out of order bytes that can be buffered for a single connection")
+                .long(MAX_BUFFERED_BYTES_ARG)
+                .takes_value(true),
+        )
         .get_matches()
 }
 
+/// Parses the value of the specified argument, if it was provided.
+fn parse_numeric_arg<T: FromStr>(matches: &ArgMatches, arg: &str) -> Result<Option<T>, String> {
+    matches
+        .value_of(arg)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("Provided '--{}' ({}) is not a valid number", arg, value))
+        })
+        .transpose()
+}
+
+fn parse_client_limits(matches: &ArgMatches) -> Result<ClientLimits, String> {
+    let mut limits = ClientLimits::default();
+    if let Some(max_connections) = parse_numeric_arg(matches, MAX_CONNECTIONS_ARG)? {
+        limits.max_concurrent_connections = max_connections;
+    }
+    if let Some(max_new_connections) = parse_numeric_arg(matches, MAX_NEW_CONNECTIONS_ARG)? {
+        limits.max_new_connections_per_minute = max_new_connections;
+    }
+    if let Some(max_bandwidth) = parse_numeric_arg(matches, MAX_BANDWIDTH_ARG)? {
+        limits.max_bandwidth_per_window = max_bandwidth;
+    }
+    if let Some(window_secs) = parse_numeric_arg(matches, BANDWIDTH_WINDOW_ARG)? {
+        limits.bandwidth_window = Duration::from_secs(window_secs);
+    }
+    Ok(limits)
+}
+
 #[tokio::main]
 async fn main() {
     setup_logging();
@@ -32,9 +104,21 @@ async fn main() {
         println!("\n\nYOU HAVE STARTED IN 'OPEN PROXY' MODE. ANYONE WITH YOUR CLIENT ADDRESS CAN MAKE REQUESTS FROM YOUR MACHINE. PLEASE QUIT IF YOU DON'T UNDERSTAND WHAT YOU'RE DOING.\n\n");
     }
 
+    let client_limits = parse_client_limits(&matches).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1)
+    });
+    let max_buffered_bytes = parse_numeric_arg(&matches, MAX_BUFFERED_BYTES_ARG)
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1)
+        })
+        .unwrap_or(DEFAULT_MAX_BUFFERED_BYTES);
+
     let uri = "ws://localhost:1977";
     println!("Starting socks5 service provider:");
-    let mut server = core::ServiceProvider::new(uri.into(), open_proxy);
+    let mut server =
+        core::ServiceProvider::new(uri.into(), open_proxy, client_limits, max_buffered_bytes);
     server.run().await;
 }
 
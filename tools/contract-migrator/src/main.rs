@@ -0,0 +1,171 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{App, Arg, ArgMatches};
+use log::{info, warn};
+use network_defaults::{default_nymd_endpoints, DEFAULT_MIXNET_CONTRACT_ADDRESS};
+use std::io::{self, BufRead};
+use std::{env, fs};
+use validator_client::nymd::NymdClient;
+
+const NYMD_VALIDATOR_ARG: &str = "nymd-validator";
+const MIXNET_CONTRACT_ARG: &str = "mixnet-contract";
+const MNEMONIC_FILE_ARG: &str = "mnemonic-file";
+const CODE_ID_ARG: &str = "code-id";
+const DRY_RUN_ARG: &str = "dry-run";
+
+/// Environmental variable holding the mnemonic of the contract admin.
+/// Used if no mnemonic file was provided. If the variable is not set either, the mnemonic
+/// is read from the standard input instead.
+const MNEMONIC_ENV_VAR: &str = "NYM_MIXNET_ADMIN_MNEMONIC";
+
+fn parse_args<'a>() -> ArgMatches<'a> {
+    App::new("Nym Mixnet Contract Migrator")
+        .author("Nymtech")
+        .about("Migrates the mixnet contract to new code and upgrades its stored state")
+        .arg(
+            Arg::with_name(NYMD_VALIDATOR_ARG)
+                .help("Endpoint to nymd part of the validator to which the migration is going to be sent")
+                .long(NYMD_VALIDATOR_ARG)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(MIXNET_CONTRACT_ARG)
+                .help("Address of the mixnet contract to migrate")
+                .long(MIXNET_CONTRACT_ARG)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(MNEMONIC_FILE_ARG)
+                .help("Path to the file containing mnemonic of the admin of the mixnet contract. If not provided, the mnemonic is read from NYM_MIXNET_ADMIN_MNEMONIC environmental variable or from the standard input")
+                .long(MNEMONIC_FILE_ARG)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(CODE_ID_ARG)
+                .help("Id of the already uploaded code the contract is going to be migrated to")
+                .long(CODE_ID_ARG)
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name(DRY_RUN_ARG)
+                .help("Only check whether the migration could be performed without sending it")
+                .long(DRY_RUN_ARG),
+        )
+        .get_matches()
+}
+
+fn setup_logging() {
+    let mut log_builder = pretty_env_logger::formatted_timed_builder();
+    if let Ok(s) = ::std::env::var("RUST_LOG") {
+        log_builder.parse_filters(&s);
+    } else {
+        // default to 'Info'
+        log_builder.filter(None, log::LevelFilter::Info);
+    }
+
+    log_builder
+        .filter_module("hyper", log::LevelFilter::Warn)
+        .filter_module("tokio_reactor", log::LevelFilter::Warn)
+        .filter_module("reqwest", log::LevelFilter::Warn)
+        .filter_module("mio", log::LevelFilter::Warn)
+        .filter_module("want", log::LevelFilter::Warn)
+        .init();
+}
+
+// the mnemonic is deliberately never accepted as an argument so that it wouldn't end up
+// in the process list or the shell history
+fn read_mnemonic(matches: &ArgMatches) -> Result<String> {
+    if let Some(path) = matches.value_of(MNEMONIC_FILE_ARG) {
+        return fs::read_to_string(path)
+            .with_context(|| format!("failed to read the mnemonic from {}", path));
+    }
+    if let Ok(mnemonic) = env::var(MNEMONIC_ENV_VAR) {
+        return Ok(mnemonic);
+    }
+
+    eprintln!("Enter the mnemonic of the mixnet contract admin:");
+    let mut mnemonic = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut mnemonic)
+        .context("failed to read the mnemonic from the standard input")?;
+    Ok(mnemonic)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    setup_logging();
+    let matches = parse_args();
+
+    let nymd_url = match matches.value_of(NYMD_VALIDATOR_ARG) {
+        Some(url) => url
+            .parse()
+            .context("the provided nymd validator url is invalid")?,
+        None => default_nymd_endpoints()[0].clone(),
+    };
+    let mixnet_contract = matches
+        .value_of(MIXNET_CONTRACT_ARG)
+        .unwrap_or(DEFAULT_MIXNET_CONTRACT_ADDRESS)
+        .parse()
+        .map_err(|err| anyhow!("the mixnet contract address is invalid - {}", err))?;
+    let mnemonic = read_mnemonic(&matches)?
+        .trim()
+        .parse()
+        .context("the mnemonic is invalid")?;
+    let code_id: u64 = matches
+        .value_of(CODE_ID_ARG)
+        .unwrap()
+        .parse()
+        .context("the provided code id is invalid")?;
+
+    let client =
+        NymdClient::connect_with_mnemonic(nymd_url.as_str(), Some(mixnet_contract), mnemonic)?;
+
+    // the migration can only be performed by the contract admin to code that differs from the current one
+    let contract = client.get_mixnet_contract_info().await?;
+    info!(
+        "The mixnet contract {} is currently running code {}",
+        contract.address, contract.code_id
+    );
+    if contract.admin.as_ref() != Some(client.address()) {
+        bail!(
+            "{} is not the admin of the mixnet contract (admin: {:?})",
+            client.address(),
+            contract.admin
+        )
+    }
+    if contract.code_id == code_id {
+        bail!("The mixnet contract is already running code {}", code_id)
+    }
+
+    // contracts deployed before the version started being tracked do not understand the query
+    match client.get_contract_version().await {
+        Ok(version) => info!("The current version of the contract is {}", version),
+        Err(err) => warn!(
+            "Could not obtain the current contract version ({}). The contract is presumably unversioned",
+            err
+        ),
+    }
+
+    if matches.is_present(DRY_RUN_ARG) {
+        info!("Dry run - the contract is not going to be migrated");
+        return Ok(());
+    }
+
+    info!("Migrating the mixnet contract to code {}...", code_id);
+    let res = client
+        .migrate_mixnet_contract(code_id, "Migrating mixnet contract from rust!")
+        .await?;
+    info!(
+        "Migration succeeded in transaction {}",
+        res.transaction_hash
+    );
+
+    let version = client.get_contract_version().await?;
+    info!("The contract is now at version {}", version);
+
+    Ok(())
+}
@@ -30,6 +30,7 @@ pub struct ValidatorCache {
 struct ValidatorCacheInner {
     initialised: AtomicBool,
     mixnodes: RwLock<Cache<Vec<MixNodeBond>>>,
+    active_mixnodes: RwLock<Cache<Vec<MixNodeBond>>>,
     gateways: RwLock<Cache<Vec<GatewayBond>>>,
 }
 
@@ -70,18 +71,24 @@ impl<C> ValidatorCacheRefresher<C> {
     where
         C: CosmWasmClient + Sync,
     {
-        let (mixnodes, gateways) = tokio::try_join!(
+        let (mixnodes, active_mixnodes, epoch, gateways) = tokio::try_join!(
             self.nymd_client.get_mixnodes(),
+            self.nymd_client.get_active_mixnodes(),
+            self.nymd_client.get_epoch(),
             self.nymd_client.get_gateways()
         )?;
 
         info!(
-            "Updating validator cache. There are {} mixnodes and {} gateways",
+            "Updating validator cache. There are {} mixnodes ({} active in epoch {}) and {} gateways",
             mixnodes.len(),
+            active_mixnodes.len(),
+            epoch.epoch,
             gateways.len()
         );
 
-        self.cache.update_cache(mixnodes, gateways).await;
+        self.cache
+            .update_cache(mixnodes, active_mixnodes, gateways)
+            .await;
 
         Ok(())
     }
@@ -117,13 +124,27 @@ impl ValidatorCache {
             rocket.manage(Self::new()).mount(
                 // this format! is so ugly...
                 format!("/{}", VALIDATOR_API_VERSION),
-                routes![routes::get_mixnodes, routes::get_gateways],
+                routes![
+                    routes::get_mixnodes,
+                    routes::get_active_mixnodes,
+                    routes::get_gateways
+                ],
             )
         })
     }
 
-    async fn update_cache(&self, mixnodes: Vec<MixNodeBond>, gateways: Vec<GatewayBond>) {
+    async fn update_cache(
+        &self,
+        mixnodes: Vec<MixNodeBond>,
+        active_mixnodes: Vec<MixNodeBond>,
+        gateways: Vec<GatewayBond>,
+    ) {
         self.inner.mixnodes.write().await.set(mixnodes);
+        self.inner
+            .active_mixnodes
+            .write()
+            .await
+            .set(active_mixnodes);
         self.inner.gateways.write().await.set(gateways);
     }
 
@@ -131,6 +152,10 @@ impl ValidatorCache {
         self.inner.mixnodes.read().await.clone()
     }
 
+    pub async fn active_mixnodes(&self) -> Cache<Vec<MixNodeBond>> {
+        self.inner.active_mixnodes.read().await.clone()
+    }
+
     pub async fn gateways(&self) -> Cache<Vec<GatewayBond>> {
         self.inner.gateways.read().await.clone()
     }
@@ -145,6 +170,7 @@ impl ValidatorCacheInner {
         ValidatorCacheInner {
             initialised: AtomicBool::new(false),
             mixnodes: RwLock::new(Cache::default()),
+            active_mixnodes: RwLock::new(Cache::default()),
             gateways: RwLock::new(Cache::default()),
         }
     }
@@ -11,6 +11,11 @@ pub(crate) async fn get_mixnodes(cache: &State<ValidatorCache>) -> Json<Vec<MixN
     Json(cache.mixnodes().await.value)
 }
 
+#[get("/mixnodes/active")]
+pub(crate) async fn get_active_mixnodes(cache: &State<ValidatorCache>) -> Json<Vec<MixNodeBond>> {
+    Json(cache.active_mixnodes().await.value)
+}
+
 #[get("/gateways")]
 pub(crate) async fn get_gateways(cache: &State<ValidatorCache>) -> Json<Vec<GatewayBond>> {
     Json(cache.gateways().await.value)
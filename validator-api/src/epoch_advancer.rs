@@ -0,0 +1,69 @@
+// Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::nymd_client::Client;
+use anyhow::Result;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time;
+use validator_client::nymd::SigningNymdClient;
+
+/// Periodically checks whether the current epoch has finished and if so,
+/// triggers selection of the active set for the next one.
+pub(crate) struct EpochAdvancer {
+    nymd_client: Client<SigningNymdClient>,
+    check_interval: Duration,
+}
+
+impl EpochAdvancer {
+    pub(crate) fn new(nymd_client: Client<SigningNymdClient>, check_interval: Duration) -> Self {
+        EpochAdvancer {
+            nymd_client,
+            check_interval,
+        }
+    }
+
+    async fn advance_if_finished(&self) -> Result<()> {
+        let epoch = self.nymd_client.get_epoch().await?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // epoch 0 means no active set has ever been selected
+        if epoch.epoch != 0 && now < epoch.epoch_end {
+            return Ok(());
+        }
+
+        info!(
+            "Epoch {} has finished. Selecting the active set for the next one",
+            epoch.epoch
+        );
+
+        // each transaction only goes through a batch of the mixnodes,
+        // so keep sending them until the new active set is in place
+        let mut transactions = 0;
+        loop {
+            self.nymd_client.advance_epoch().await?;
+            transactions += 1;
+            if self.nymd_client.get_epoch().await?.epoch != epoch.epoch {
+                break;
+            }
+        }
+        debug!(
+            "Selecting the active set took {} transactions",
+            transactions
+        );
+
+        Ok(())
+    }
+
+    pub(crate) async fn run(&self) {
+        let mut interval = time::interval(self.check_interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.advance_if_finished().await {
+                error!("Failed to advance the epoch - {}", err);
+            }
+        }
+    }
+}
@@ -6,6 +6,7 @@ extern crate rocket;
 
 use crate::cache::ValidatorCacheRefresher;
 use crate::config::Config;
+use crate::epoch_advancer::EpochAdvancer;
 use crate::network_monitor::tested_network::good_topology::parse_topology_file;
 use crate::network_monitor::{new_monitor_runnables, NetworkMonitorRunnables};
 use crate::nymd_client::Client;
@@ -25,6 +26,7 @@ use url::Url;
 pub(crate) mod cache;
 mod coconut;
 pub(crate) mod config;
+mod epoch_advancer;
 mod network_monitor;
 mod node_status_api;
 pub(crate) mod nymd_client;
@@ -302,13 +304,17 @@ async fn main() -> Result<()> {
     if config.get_network_monitor_enabled() {
         let nymd_client = Client::new_signing(&config);
         let validator_cache_refresher = ValidatorCacheRefresher::new(
-            nymd_client,
+            nymd_client.clone(),
             config.get_caching_interval(),
             validator_cache,
         );
 
         // spawn our cacher
         tokio::spawn(async move { validator_cache_refresher.run().await });
+
+        // the network monitor is also responsible for starting new epochs
+        let epoch_advancer = EpochAdvancer::new(nymd_client, config.get_caching_interval());
+        tokio::spawn(async move { epoch_advancer.run().await });
     } else {
         let nymd_client = Client::new_query(&config);
         let validator_cache_refresher = ValidatorCacheRefresher::new(
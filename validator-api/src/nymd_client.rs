@@ -3,10 +3,12 @@
 
 use crate::config::Config;
 use config::defaults::DEFAULT_VALIDATOR_API_PORT;
-use mixnet_contract::{GatewayBond, MixNodeBond};
+use mixnet_contract::{EpochResponse, GatewayBond, MixNodeBond};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use validator_client::nymd::{CosmWasmClient, QueryNymdClient, SigningNymdClient};
+use validator_client::nymd::{
+    CosmWasmClient, QueryNymdClient, SigningCosmWasmClient, SigningNymdClient,
+};
 use validator_client::ValidatorClientError;
 
 #[derive(Clone)]
@@ -73,6 +75,28 @@ impl<C> Client<C> {
         self.0.read().await.get_all_nymd_gateways().await
     }
 
+    pub(crate) async fn get_epoch(&self) -> Result<EpochResponse, ValidatorClientError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        self.0.read().await.get_nymd_epoch().await
+    }
+
+    pub(crate) async fn get_active_mixnodes(&self) -> Result<Vec<MixNodeBond>, ValidatorClientError>
+    where
+        C: CosmWasmClient + Sync,
+    {
+        self.0.read().await.get_all_nymd_active_mixnodes().await
+    }
+
+    pub(crate) async fn advance_epoch(&self) -> Result<(), ValidatorClientError>
+    where
+        C: SigningCosmWasmClient + Sync,
+    {
+        self.0.read().await.nymd.advance_epoch().await?;
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub(crate) async fn some_rewarding_stuff_here(&self) {
         todo!()
@@ -109,6 +109,54 @@ export default function AdminForm(props: AdminFormProps) {
                         fullWidth
                     />
                 </Grid>
+                <Grid item xs={12}>
+                    <TextField
+                        required
+                        id="unbonding_period"
+                        name="unbonding_period"
+                        label="Unbonding period (in hours)"
+                        defaultValue={props.currentParams.unbonding_period}
+                        fullWidth
+                        InputProps={{
+                            endAdornment:
+                                <InputAdornment position="end">hours</InputAdornment>
+                        }}
+                    />
+                </Grid>
+                <Grid item xs={12}>
+                    <TextField
+                        required
+                        id="minimum_mixnode_uptime"
+                        name="minimum_mixnode_uptime"
+                        label="Minimum mixnode uptime"
+                        defaultValue={props.currentParams.minimum_mixnode_uptime}
+                        fullWidth
+                        InputProps={{
+                            endAdornment:
+                                <InputAdornment position="end">%</InputAdornment>
+                        }}
+                    />
+                </Grid>
+                <Grid item xs={12}>
+                    <TextField
+                        required
+                        id="mixnode_jail_epochs"
+                        name="mixnode_jail_epochs"
+                        label="Consecutive epochs of low uptime before jailing"
+                        defaultValue={props.currentParams.mixnode_jail_epochs}
+                        fullWidth
+                    />
+                </Grid>
+                <Grid item xs={12}>
+                    <TextField
+                        required
+                        id="mixnode_slashing_fraction"
+                        name="mixnode_slashing_fraction"
+                        label="Fraction of the bond slashed upon jailing"
+                        defaultValue={props.currentParams.mixnode_slashing_fraction}
+                        fullWidth
+                    />
+                </Grid>
             </Grid>
             <div className={classes.buttons}>
                 <Button
@@ -117,7 +165,7 @@ export default function AdminForm(props: AdminFormProps) {
                     type="submit"
                     className={classes.button}
                 >
-                    Update Contract
+                    Propose Update
                 </Button>
             </div>
         </form>
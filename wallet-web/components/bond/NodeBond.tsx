@@ -17,6 +17,9 @@ import NodeTypeChooser from '../NodeTypeChooser'
 import ExecFeeNotice from '../ExecFeeNotice'
 import { UDENOM } from '../../pages/_app'
 
+// the form does not expose it yet, the operator can change it after bonding
+const DEFAULT_PROFIT_MARGIN_PERCENT = 10
+
 export type BondingInformation = {
   amount: string
   nodeDetails: MixNode | Gateway
@@ -69,7 +72,7 @@ const BondNode = () => {
     if (nodeType == NodeType.Mixnode) {
       let mixnode = bondingInformation.nodeDetails as MixNode
       client
-        .bondMixnode(mixnode, amount)
+        .bondMixnode(mixnode, DEFAULT_PROFIT_MARGIN_PERCENT, amount)
         .then((value) => {
           console.log('bonded mixnode!', value)
         })
@@ -74,7 +74,7 @@ export default function Admin() {
         })()
     }, [client])
 
-    const updateStateParams = async (event) => {
+    const proposeStateParams = async (event) => {
         event.preventDefault()
         let newState: StateParams = {
             minimum_mixnode_bond: printableBalanceToNative(event.target.mix_bond.value),
@@ -85,10 +85,14 @@ export default function Admin() {
             gateway_delegation_reward_rate: event.target.gateway_delegation_reward.value,
             epoch_length: parseInt(event.target.epoch_length.value),
             mixnode_active_set_size: parseInt(event.target.active_set.value),
+            unbonding_period: parseInt(event.target.unbonding_period.value),
+            minimum_mixnode_uptime: parseInt(event.target.minimum_mixnode_uptime.value),
+            mixnode_jail_epochs: parseInt(event.target.mixnode_jail_epochs.value),
+            mixnode_slashing_fraction: event.target.mixnode_slashing_fraction.value,
         };
         setUpdatingState(true)
-        await client.updateStateParams(newState)
-            .then((_) => setCurrentStateParams(newState))
+        // the new parameters only get applied once the proposal passes and its timelock expires
+        await client.proposeStateParams(newState, "proposed from the wallet admin page")
             .catch((err) => setUpdateError(err))
             .finally(() => setUpdatingState(false))
     }
@@ -105,7 +109,7 @@ export default function Admin() {
             return (
                 <Alert severity="error">
                     <AlertTitle>{updateError.name}</AlertTitle>
-                    <strong>Failed to update contract state</strong> - {updateError.message}
+                    <strong>Failed to propose new contract state</strong> - {updateError.message}
                 </Alert>
             )
         }
@@ -120,14 +124,14 @@ export default function Admin() {
                 <React.Fragment>
                     <br />
                     <Typography component="h1" variant="h5" align="center">
-                        Updating contract state...
+                        Proposing new contract state...
                     </Typography>
                     <LinearProgress/>
                 </React.Fragment>
             )
         }
 
-        return (<AdminForm onSubmit={updateStateParams} currentParams={currentStateParams}/>)
+        return (<AdminForm onSubmit={proposeStateParams} currentParams={currentStateParams}/>)
     }
 
     return (